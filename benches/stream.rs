@@ -0,0 +1,43 @@
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use nf_e::models::NFeProc;
+use nf_e::stream::nfe_procs;
+use serde::Deserialize;
+use std::hint::black_box;
+
+#[derive(Deserialize)]
+struct Batch {
+    #[serde(rename = "nfeProc")]
+    documents: Vec<NFeProc>,
+}
+
+fn batch(count: usize) -> String {
+    let nfe_proc = include_str!("../tests/fixtures/nfe_proc.xml");
+    format!("<lote>{}</lote>", nfe_proc.repeat(count))
+}
+
+fn bench_nfe_procs(c: &mut Criterion) {
+    let mut group = c.benchmark_group("nfe_procs");
+    for count in [10, 100, 1000] {
+        let xml = batch(count);
+        group.bench_with_input(BenchmarkId::new("stream", count), &xml, |b, xml| {
+            b.iter(|| {
+                nfe_procs(black_box(xml.as_bytes()))
+                    .collect::<Result<Vec<_>, _>>()
+                    .unwrap()
+                    .len()
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("from_str", count), &xml, |b, xml| {
+            b.iter(|| {
+                quick_xml::de::from_str::<Batch>(black_box(xml))
+                    .unwrap()
+                    .documents
+                    .len()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_nfe_procs);
+criterion_main!(benches);
@@ -1,47 +1,319 @@
-//! A procedural macro crate providing the `#[serialization_test]` attribute.
+//! A procedural macro crate providing the `#[serialization_test]` and
+//! `#[deserialization_error_test]` attributes and the derives of the library.
 //!
 //! This crate is designed to reduce boilerplate when writing tests for types
-//! that should serialize to and deserialize from a specific string structure.
+//! that should serialize to and deserialize from a specific string structure,
+//! or refuse a malformed one, and when writing the coded enums of the layout.
 
 use proc_macro::TokenStream;
 use quote::{format_ident, quote};
-use syn::{parse::{Parse, ParseStream}, parse_macro_input, DeriveInput, ItemFn, LitStr, Token};
+use syn::{
+    parenthesized,
+    parse::{Parse, ParseStream},
+    parse_macro_input,
+    punctuated::Punctuated,
+    DeriveInput, Ident, ItemFn, LitStr, Token,
+};
 
-/// Represents the possible arguments for the `#[serialization_test]` macro.
+/// Source of the expected serialization of an instance.
 ///
-/// The macro can accept either a file path to a fixture or a raw
-/// string literal representing the expected output.
-enum MacroArgs {
+/// The expected output can be either a file path to a fixture or a raw
+/// string literal.
+enum Expected {
     /// A relative path to a fixture file, e.g., `fixture = "path/to/file.xml"`.
     FixturePath(LitStr),
     /// The expected string result, e.g., `expected = "<tag>value</tag>"`.
     ExpectedResult(LitStr),
 }
 
-/// Parser implementation for `MacroArgs`.
-///
-/// This allows `syn` to parse the attribute's arguments from a token stream
-/// into the `MacroArgs` enum. It expects a key-value pair format like
-/// `key = "value"`.
-impl Parse for MacroArgs {
+impl Expected {
+    /// Tokens evaluating to the expected content, as a `&'static str`.
+    fn content(&self) -> proc_macro2::TokenStream {
+        match self {
+            Expected::FixturePath(path) => quote! { include_str!(#path) },
+            Expected::ExpectedResult(result_literal) => quote! { #result_literal },
+        }
+    }
+
+    /// Tokens rewriting the fixture file with `canonicalized_output`, passed
+    /// through `indent` when given, and returning from the test when
+    /// `UPDATE_FIXTURES=1`, nothing for inline results.
+    ///
+    /// The fixture is resolved like `include_str!`, relative to the source file
+    /// of the test, which is looked up from the manifest directory of the crate
+    /// and its ancestors, as `file!()` is relative to the workspace root.
+    fn update(&self, indent: Option<&syn::Path>) -> proc_macro2::TokenStream {
+        let Expected::FixturePath(path) = self else {
+            return quote! {};
+        };
+        let content = match indent {
+            Some(indent) => quote! {
+                #indent(&canonicalized_output).expect("Failed to indent the serialized output")
+            },
+            None => quote! { canonicalized_output.clone() },
+        };
+        quote! {
+            if std::env::var_os(#UPDATE_FIXTURES).is_some_and(|value| value == "1") {
+                let source = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                    .ancestors()
+                    .map(|dir| dir.join(file!()))
+                    .find(|source| source.exists())
+                    .expect("Failed to locate the source file of the test");
+                let fixture = source
+                    .parent()
+                    .expect("Failed to locate the directory of the test")
+                    .join(#path);
+                std::fs::write(&fixture, #content).expect("Failed to update fixture");
+                return;
+            }
+        }
+    }
+}
+
+/// Environment variable rewriting the fixtures with the serialized output
+/// when set to `1`, instead of failing the serialization tests.
+const UPDATE_FIXTURES: &str = "UPDATE_FIXTURES";
+
+/// A `key = "value"` pair of the macro arguments.
+struct KeyValue {
+    key: Ident,
+    value: LitStr,
+}
+
+impl Parse for KeyValue {
     fn parse(input: ParseStream) -> syn::Result<Self> {
-        let key: syn::Ident = input.parse()?;
+        let key: Ident = input.parse()?;
         let _eq_token: Token![=] = input.parse()?;
         let value: LitStr = input.parse()?;
+        Ok(KeyValue { key, value })
+    }
+}
 
-        if key == "fixture" {
-            Ok(MacroArgs::FixturePath(value))
-        } else if key == "expected" {
-            Ok(MacroArgs::ExpectedResult(value))
+impl KeyValue {
+    /// Reads the pair as the expected output, `fixture` or `expected`.
+    fn into_expected(self) -> syn::Result<Expected> {
+        if self.key == "fixture" {
+            Ok(Expected::FixturePath(self.value))
+        } else if self.key == "expected" {
+            Ok(Expected::ExpectedResult(self.value))
         } else {
             Err(syn::Error::new(
-                key.span(),
+                self.key.span(),
                 "expected attribute `fixture` or `expected`",
             ))
         }
     }
 }
 
+/// Paths of the helper functions called by the generated tests.
+///
+/// They default to `serialize`, `deserialize` and `canonicalize`, resolved in
+/// the scope of the tests, and to no indentation of the updated fixtures.
+struct Helpers {
+    ser: syn::Path,
+    de: syn::Path,
+    canon: syn::Path,
+    indent: Option<syn::Path>,
+}
+
+impl Default for Helpers {
+    fn default() -> Self {
+        Helpers {
+            ser: syn::parse_quote!(serialize),
+            de: syn::parse_quote!(deserialize),
+            canon: syn::parse_quote!(canonicalize),
+            indent: None,
+        }
+    }
+}
+
+/// A named case of a setup function, e.g.,
+/// `case(name = "icms00", fixture = "path/to/icms00.xml")`.
+///
+/// The name is passed to the setup function and suffixes the generated tests,
+/// so it must be a valid identifier.
+struct Case {
+    name: LitStr,
+    expected: Expected,
+}
+
+impl Parse for Case {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let span = input.span();
+        let mut name = None;
+        let mut expected = None;
+        for pair in Punctuated::<KeyValue, Token![,]>::parse_terminated(input)? {
+            if pair.key == "name" {
+                if syn::parse_str::<Ident>(&pair.value.value()).is_err() {
+                    return Err(syn::Error::new(
+                        pair.value.span(),
+                        "case name must be a valid identifier",
+                    ));
+                }
+                name = Some(pair.value);
+            } else if expected.is_none() {
+                expected = Some(pair.into_expected()?);
+            } else {
+                return Err(syn::Error::new(
+                    pair.key.span(),
+                    "case accepts a single `fixture` or `expected`",
+                ));
+            }
+        }
+        match (name, expected) {
+            (Some(name), Some(expected)) => Ok(Case { name, expected }),
+            _ => Err(syn::Error::new(
+                span,
+                "case requires a `name` and a `fixture` or `expected`",
+            )),
+        }
+    }
+}
+
+/// Represents the possible arguments for the `#[serialization_test]` macro.
+///
+/// The macro accepts either a single expected output for the setup function,
+/// or several named cases of it.
+enum MacroArg {
+    Expected(Expected),
+    Case(Case),
+    /// The `roundtrip` flag, generating the round-trip tests.
+    Roundtrip,
+    /// A helper function path, e.g., `ser = "quick_xml::se::to_string"`.
+    Helper(Ident, syn::Path),
+}
+
+/// Parser implementation for `MacroArg`.
+///
+/// This allows `syn` to parse the attribute's arguments from a token stream
+/// into the `MacroArg` enum. It expects a key-value pair format like
+/// `key = "value"`, a `case(...)` list of them or a flag like `roundtrip`.
+impl Parse for MacroArg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let fork = input.fork();
+        if let Ok(flag) = fork.parse::<Ident>()
+            && (fork.is_empty() || fork.peek(Token![,]))
+        {
+            input.parse::<Ident>()?;
+            if flag != "roundtrip" {
+                return Err(syn::Error::new(flag.span(), "expected flag `roundtrip`"));
+            }
+            return Ok(MacroArg::Roundtrip);
+        }
+        if input.peek(Ident) && input.peek2(syn::token::Paren) {
+            let key: Ident = input.parse()?;
+            if key != "case" {
+                return Err(syn::Error::new(key.span(), "expected `case(...)`"));
+            }
+            let content;
+            parenthesized!(content in input);
+            return Ok(MacroArg::Case(content.parse()?));
+        }
+        let pair = input.parse::<KeyValue>()?;
+        if ["ser", "de", "canon", "indent"].iter().any(|key| pair.key == key) {
+            return Ok(MacroArg::Helper(pair.key, pair.value.parse()?));
+        }
+        if pair.key != "fixture" && pair.key != "expected" {
+            return Err(syn::Error::new(
+                pair.key.span(),
+                "expected attribute `fixture`, `expected`, `case`, `ser`, `de`, `canon` or `indent`",
+            ));
+        }
+        pair.into_expected().map(MacroArg::Expected)
+    }
+}
+
+/// Generates a serialization and a deserialization test of the instance
+/// returned by `setup_call`.
+fn test_pair(
+    serialize_test_name: Ident,
+    deserialize_test_name: Ident,
+    setup_call: proc_macro2::TokenStream,
+    return_type: &syn::Type,
+    expected: &Expected,
+    helpers: &Helpers,
+) -> proc_macro2::TokenStream {
+    let expected_content_provider = expected.content();
+    let update_fixture = expected.update(helpers.indent.as_ref());
+    let Helpers {
+        ser: serialize,
+        de: deserialize,
+        canon: canonicalize,
+        ..
+    } = helpers;
+    quote! {
+        #[test]
+        fn #serialize_test_name() {
+            let instance = #setup_call;
+            let serialized = #serialize(&instance)
+                .expect("Failed to serialize instance");
+
+            let canonicalized_output = #canonicalize(&serialized)
+                .expect("Failed to canonicalize serialized output");
+
+            let fixture_content = #expected_content_provider;
+            let expected_canonical = #canonicalize(fixture_content)
+                .expect("Failed to canonicalize fixture content");
+
+            if canonicalized_output != expected_canonical {
+                #update_fixture
+            }
+
+            assert_eq!(canonicalized_output, expected_canonical, "Serialized output does not match fixture");
+        }
+
+        #[test]
+        fn #deserialize_test_name() {
+            let expected_instance = #setup_call;
+
+            let fixture_content = #expected_content_provider;
+            let canonicalized_fixture = #canonicalize(fixture_content)
+                .expect("Failed to canonicalize fixture content");
+
+            let deserialized: #return_type = #deserialize(&canonicalized_fixture)
+                .expect("Failed to deserialize fixture content");
+
+            assert_eq!(deserialized, expected_instance, "Deserialized instance does not match setup instance");
+        }
+    }
+}
+
+/// Generates a test asserting that serializing the deserialized output of the
+/// instance returned by `setup_call` gives the same output again, catching
+/// custom implementations that do not read back what they write.
+fn roundtrip_test(
+    roundtrip_test_name: Ident,
+    setup_call: proc_macro2::TokenStream,
+    return_type: &syn::Type,
+    helpers: &Helpers,
+) -> proc_macro2::TokenStream {
+    let Helpers {
+        ser: serialize,
+        de: deserialize,
+        canon: canonicalize,
+        ..
+    } = helpers;
+    quote! {
+        #[test]
+        fn #roundtrip_test_name() {
+            let instance = #setup_call;
+            let serialized = #serialize(&instance)
+                .expect("Failed to serialize instance");
+
+            let deserialized: #return_type = #deserialize(&serialized)
+                .expect("Failed to deserialize serialized output");
+            let reserialized = #serialize(&deserialized)
+                .expect("Failed to serialize deserialized instance");
+
+            assert_eq!(
+                #canonicalize(&reserialized).expect("Failed to canonicalize serialized output"),
+                #canonicalize(&serialized).expect("Failed to canonicalize serialized output"),
+                "Serialization is not stable after a round trip"
+            );
+        }
+    }
+}
+
 /// Generates a pair of serialization and deserialization tests.
 ///
 /// This attribute macro is attached to a "setup" function that returns an
@@ -57,6 +329,30 @@ impl Parse for MacroArgs {
 ///
 /// * `fixture = "path/to/your/fixture.file"`: Use an external file.
 /// * `expected = "<your><content/></your>"`: Use an inline string.
+/// * `case(name = "...", fixture = "..." | expected = "...")`: A named case,
+///   repeatable. The setup function receives the name of the case as its only
+///   argument, and a pair `serialize_<name>_<case>` and
+///   `deserialize_<name>_<case>` is generated for each case.
+/// * `ser = "path"`, `de = "path"`, `canon = "path"`: Paths of the helper
+///   functions, for tests that do not import them with the assumed names.
+/// * `indent = "path"`: Path of a `fn(&str) -> Result<String, _>` formatting
+///   the fixtures rewritten by `UPDATE_FIXTURES`, written as canonicalized
+///   otherwise.
+/// * `roundtrip`: Also generates `roundtrip_<name>` (or `roundtrip_<name>_<case>`),
+///   asserting that serialize -> deserialize -> serialize is a fixed point on
+///   the setup instance.
+///
+/// # Updating fixtures
+///
+/// When the environment variable `UPDATE_FIXTURES` is set to `1`, the
+/// serialization tests of a `fixture` rewrite the file with the canonicalized
+/// output, passed through the `indent` helper when given, instead of failing
+/// when it does not match canonically. The fixtures are read at compile time, so the deserialization
+/// tests see the new content on the next run:
+///
+/// ```sh
+/// UPDATE_FIXTURES=1 cargo test serialize_ && cargo test
+/// ```
 ///
 /// # Panics
 ///
@@ -65,8 +361,8 @@ impl Parse for MacroArgs {
 ///
 /// # Assumptions
 ///
-/// This macro assumes the following helper functions are available in the scope
-/// where the tests are generated:
+/// Unless their paths are given, this macro assumes the following helper
+/// functions are available in the scope where the tests are generated:
 ///
 /// * `serialize<T: Serialize>(value: &T) -> Result<String, _>`
 /// * `deserialize<'a, T: Deserialize<'a>>(s: &'a str) -> Result<T, _>`
@@ -80,10 +376,23 @@ impl Parse for MacroArgs {
 ///     // ... return a Detail instance
 /// }
 /// // This will generate `serialize_detail()` and `deserialize_detail()` tests.
+///
+/// #[serialization_test(
+///     case(name = "icms00", fixture = "../tests/fixtures/enums/icms00.xml"),
+///     case(name = "icms40", expected = "<ICMS><ICMS40>...</ICMS40></ICMS>"),
+/// )]
+/// fn setup_icms(case: &str) -> ICMS {
+///     match case {
+///         "icms00" => // ... return the ICMS00 variant
+///         _ => // ... return the ICMS40 variant
+///     }
+/// }
+/// // This will generate `serialize_icms_icms00()`, `deserialize_icms_icms00()`,
+/// // `serialize_icms_icms40()` and `deserialize_icms_icms40()` tests.
 /// ```
 #[proc_macro_attribute]
 pub fn serialization_test(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let args = parse_macro_input!(attr as MacroArgs);
+    let args = parse_macro_input!(attr with Punctuated::<MacroArg, Token![,]>::parse_terminated);
 
     let setup_fn = parse_macro_input!(item as ItemFn);
 
@@ -94,9 +403,6 @@ pub fn serialization_test(attr: TokenStream, item: TokenStream) -> TokenStream {
         .strip_prefix("setup_")
         .unwrap_or(&setup_fn_name_str);
 
-    let serialize_test_name = format_ident!("serialize_{}", base_name);
-    let deserialize_test_name = format_ident!("deserialize_{}", base_name);
-
     let return_type = match &setup_fn.sig.output {
         syn::ReturnType::Type(_, ty) => ty,
         syn::ReturnType::Default => {
@@ -107,42 +413,197 @@ pub fn serialization_test(attr: TokenStream, item: TokenStream) -> TokenStream {
         }
     };
 
-    let expected_content_provider = match args {
-        MacroArgs::FixturePath(path) => quote! { include_str!(#path) },
-        MacroArgs::ExpectedResult(result_literal) => quote! { #result_literal },
+    let mut expected = Vec::new();
+    let mut cases = Vec::new();
+    let mut roundtrip = false;
+    let mut helpers = Helpers::default();
+    for arg in args {
+        match arg {
+            MacroArg::Expected(value) => expected.push(value),
+            MacroArg::Case(case) => cases.push(case),
+            MacroArg::Roundtrip => roundtrip = true,
+            MacroArg::Helper(key, path) if key == "ser" => helpers.ser = path,
+            MacroArg::Helper(key, path) if key == "de" => helpers.de = path,
+            MacroArg::Helper(key, path) if key == "canon" => helpers.canon = path,
+            MacroArg::Helper(_, path) => helpers.indent = Some(path),
+        }
+    }
+    let arguments = setup_fn.sig.inputs.len();
+
+    let tests = match (expected.as_slice(), cases.is_empty()) {
+        ([expected], true) if arguments == 0 => {
+            let setup_call = quote! { #setup_fn_name() };
+            let mut tests = vec![test_pair(
+                format_ident!("serialize_{}", base_name),
+                format_ident!("deserialize_{}", base_name),
+                setup_call.clone(),
+                return_type,
+                expected,
+                &helpers,
+            )];
+            if roundtrip {
+                tests.push(roundtrip_test(
+                    format_ident!("roundtrip_{}", base_name),
+                    setup_call,
+                    return_type,
+                    &helpers,
+                ));
+            }
+            tests
+        }
+        ([], false) if arguments == 1 => cases
+            .iter()
+            .flat_map(|case| {
+                let name = &case.name;
+                let suffix = name.value();
+                let setup_call = quote! { #setup_fn_name(#name) };
+                let mut tests = vec![test_pair(
+                    format_ident!("serialize_{}_{}", base_name, suffix),
+                    format_ident!("deserialize_{}_{}", base_name, suffix),
+                    setup_call.clone(),
+                    return_type,
+                    &case.expected,
+                    &helpers,
+                )];
+                if roundtrip {
+                    tests.push(roundtrip_test(
+                        format_ident!("roundtrip_{}_{}", base_name, suffix),
+                        setup_call,
+                        return_type,
+                        &helpers,
+                    ));
+                }
+                tests
+            })
+            .collect(),
+        _ => {
+            let msg = "expected a single `fixture` or `expected` for a setup function without \
+                       arguments, or `case(...)` arguments for a setup function taking the case name";
+            return syn::Error::new(setup_fn.sig.ident.span(), msg)
+                .to_compile_error()
+                .into();
+        }
     };
 
     let expanded = quote! {
         #setup_fn
 
-        #[test]
-        fn #serialize_test_name() {
-            let instance = #setup_fn_name();
-            let serialized = serialize(&instance)
-                .expect("Failed to serialize instance");
+        #(#tests)*
+    };
 
-            let canonicalized_output = canonicalize(&serialized)
-                .expect("Failed to canonicalize serialized output");
+    TokenStream::from(expanded)
+}
 
-            let fixture_content = #expected_content_provider;
-            let expected_canonical = canonicalize(fixture_content)
-                .expect("Failed to canonicalize fixture content");
+/// Represents the possible arguments for the `#[deserialization_error_test]` macro.
+enum ErrorTestArg {
+    Expected(Expected),
+    /// The substring of the expected error message, e.g., `expect = "Unsupported version"`.
+    Message(LitStr),
+    /// A helper function path, e.g., `de = "quick_xml::de::from_str"`.
+    Helper(syn::Path),
+}
 
-            assert_eq!(canonicalized_output, expected_canonical, "Serialized output does not match fixture");
+impl Parse for ErrorTestArg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let pair = input.parse::<KeyValue>()?;
+        if pair.key == "expect" {
+            return Ok(ErrorTestArg::Message(pair.value));
         }
+        if pair.key == "de" {
+            return Ok(ErrorTestArg::Helper(pair.value.parse()?));
+        }
+        if pair.key != "fixture" && pair.key != "expected" {
+            return Err(syn::Error::new(
+                pair.key.span(),
+                "expected attribute `fixture`, `expected`, `expect` or `de`",
+            ));
+        }
+        pair.into_expected().map(ErrorTestArg::Expected)
+    }
+}
 
-        #[test]
-        fn #deserialize_test_name() {
-            let expected_instance = #setup_fn_name();
+/// Generates a test asserting that a malformed content fails to deserialize.
+///
+/// This attribute macro is attached to a function signature without body,
+/// whose name becomes the name of the test and whose return type is the type
+/// being deserialized. The generated test deserializes the content and
+/// asserts that it fails with an error whose message contains the expected
+/// substring, locking in the checks of the custom implementations.
+///
+/// # Arguments
+///
+/// * `fixture = "path/to/malformed.file"`: Use an external file.
+/// * `expected = "<your><content/></your>"`: Use an inline string.
+/// * `expect = "substring"`: Part of the message of the expected error.
+/// * `de = "path"`: Path of the deserialization function, for tests that do
+///   not import it as `deserialize`.
+///
+/// # Panics
+///
+/// The generated test panics if the content deserializes, or if the message
+/// of the error does not contain the expected substring.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// #[deserialization_error_test(
+///     fixture = "../tests/fixtures/malformed/info_version.xml",
+///     expect = "Unsupported version: 3.10"
+/// )]
+/// fn deserialize_info_unsupported_version() -> Info;
+/// // This will generate the `deserialize_info_unsupported_version()` test.
+/// ```
+#[proc_macro_attribute]
+pub fn deserialization_error_test(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args =
+        parse_macro_input!(attr with Punctuated::<ErrorTestArg, Token![,]>::parse_terminated);
+    let test_fn = parse_macro_input!(item as syn::TraitItemFn);
 
-            let fixture_content = #expected_content_provider;
-            let canonicalized_fixture = canonicalize(fixture_content)
-                .expect("Failed to canonicalize fixture content");
+    let test_name = &test_fn.sig.ident;
+    let return_type = match &test_fn.sig.output {
+        syn::ReturnType::Type(_, ty) => ty,
+        syn::ReturnType::Default => {
+            let msg = "function must return the type being deserialized to be used with \
+                       #[deserialization_error_test]";
+            return syn::Error::new(test_name.span(), msg)
+                .to_compile_error()
+                .into();
+        }
+    };
 
-            let deserialized: #return_type = deserialize(&canonicalized_fixture)
-                .expect("Failed to deserialize fixture content");
+    let mut expected = Vec::new();
+    let mut messages = Vec::new();
+    let mut deserialize: syn::Path = syn::parse_quote!(deserialize);
+    for arg in args {
+        match arg {
+            ErrorTestArg::Expected(value) => expected.push(value),
+            ErrorTestArg::Message(message) => messages.push(message),
+            ErrorTestArg::Helper(path) => deserialize = path,
+        }
+    }
+    let ([expected], [message]) = (expected.as_slice(), messages.as_slice()) else {
+        let msg = "expected a single `fixture` or `expected` and a single `expect`";
+        return syn::Error::new(test_name.span(), msg)
+            .to_compile_error()
+            .into();
+    };
+    let content = expected.content();
 
-            assert_eq!(deserialized, expected_instance, "Deserialized instance does not match setup instance");
+    let expanded = quote! {
+        #[test]
+        fn #test_name() {
+            let content = #content;
+            let result: Result<#return_type, _> = #deserialize(content);
+            let message = match result {
+                Ok(_) => panic!("Malformed content deserialized"),
+                Err(error) => error.to_string(),
+            };
+            assert!(
+                message.contains(#message),
+                "Error {:?} does not contain {:?}",
+                message,
+                #message
+            );
         }
     };
 
@@ -206,4 +667,274 @@ pub fn method_algorithm_derive(input: TokenStream) -> TokenStream {
     };
 
     generated.into()
-}
\ No newline at end of file
+}
+
+/// Splits a type name into lowercase words, e.g., `TransportType` into
+/// `transport type`.
+fn words(name: &str) -> String {
+    let mut words = String::new();
+    let mut previous_lowercase = false;
+    for c in name.chars() {
+        if c.is_uppercase() && previous_lowercase {
+            words.push(' ');
+        }
+        previous_lowercase = c.is_lowercase();
+        words.extend(c.to_lowercase());
+    }
+    words
+}
+
+/// Derives the numeric code of an enum whose variants have explicit
+/// discriminants, as the codes of the layout.
+///
+/// Generates:
+///
+/// * `code(&self)`: The discriminant of the variant.
+/// * `TryFrom<int>` and `From<Self> for int`, with `String` errors like
+///   `Invalid transport type value: 5`.
+/// * `Serialize` and `Deserialize` as the number, or as a zero-padded string
+///   when a `width` is given.
+///
+/// The integer type is the one of `#[repr(...)]`, `u8` by default.
+///
+/// # Arguments
+///
+/// * `#[coded(name = "...")]`: Name of the value in the error messages,
+///   defaults to the words of the type name.
+/// * `#[coded(width = 2)]`: Zero-pads the serialized code, e.g., `01`.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// #[derive(CodedEnum, PartialEq, Debug, Clone)]
+/// #[coded(name = "PIS/COFINS CST", width = 2)]
+/// pub enum ContributionCST {
+///     TaxableBasicRate = 1,
+///     // ...
+/// }
+/// ```
+#[proc_macro_derive(CodedEnum, attributes(coded))]
+pub fn coded_enum_derive(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match coded_enum(&input) {
+        Ok(generated) => generated.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+fn coded_enum(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &input.ident;
+    let syn::Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new(name.span(), "CodedEnum can only be derived for enums"));
+    };
+
+    let mut value_name = words(&name.to_string());
+    let mut width: Option<usize> = None;
+    let mut int: Ident = format_ident!("u8");
+    for attr in &input.attrs {
+        if attr.path().is_ident("coded") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    value_name = meta.value()?.parse::<LitStr>()?.value();
+                    Ok(())
+                } else if meta.path.is_ident("width") {
+                    width = Some(meta.value()?.parse::<syn::LitInt>()?.base10_parse()?);
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `name` or `width`"))
+                }
+            })?;
+        } else if attr.path().is_ident("repr") {
+            int = attr.parse_args()?;
+        }
+    }
+
+    let mut variants = Vec::new();
+    let mut codes = Vec::new();
+    for variant in &data.variants {
+        match (&variant.fields, &variant.discriminant) {
+            (syn::Fields::Unit, Some((_, code))) => {
+                variants.push(&variant.ident);
+                codes.push(code);
+            }
+            _ => {
+                return Err(syn::Error::new(
+                    variant.ident.span(),
+                    "CodedEnum variants must be units with an explicit discriminant",
+                ));
+            }
+        }
+    }
+
+    let serialize = match width {
+        Some(width) => quote! {
+            serializer.serialize_str(&format!("{:0width$}", self.code(), width = #width))
+        },
+        None => quote! { ::serde::Serialize::serialize(&self.code(), serializer) },
+    };
+    let deserialize = match width {
+        Some(_) => quote! { deserializer.deserialize_str(CodeVisitor) },
+        None => {
+            let method = format_ident!("deserialize_{}", int);
+            quote! { deserializer.#method(CodeVisitor) }
+        }
+    };
+    let expecting = format!("a {} code", value_name);
+
+    Ok(quote! {
+        impl #name {
+            pub fn code(&self) -> #int {
+                match self {
+                    #(#name::#variants => #codes,)*
+                }
+            }
+        }
+
+        impl TryFrom<#int> for #name {
+            type Error = String;
+
+            fn try_from(value: #int) -> Result<Self, Self::Error> {
+                match value {
+                    #(#codes => Ok(#name::#variants),)*
+                    _ => Err(format!("Invalid {} value: {}", #value_name, value)),
+                }
+            }
+        }
+
+        impl From<#name> for #int {
+            fn from(value: #name) -> Self {
+                value.code()
+            }
+        }
+
+        impl ::serde::Serialize for #name {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: ::serde::Serializer,
+            {
+                #serialize
+            }
+        }
+
+        impl<'de> ::serde::Deserialize<'de> for #name {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: ::serde::Deserializer<'de>,
+            {
+                struct CodeVisitor;
+
+                impl ::serde::de::Visitor<'_> for CodeVisitor {
+                    type Value = #name;
+
+                    fn expecting(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                        f.write_str(#expecting)
+                    }
+
+                    fn visit_u64<E: ::serde::de::Error>(self, value: u64) -> Result<Self::Value, E> {
+                        let value = #int::try_from(value).map_err(E::custom)?;
+                        #name::try_from(value).map_err(E::custom)
+                    }
+
+                    fn visit_i64<E: ::serde::de::Error>(self, value: i64) -> Result<Self::Value, E> {
+                        let value = #int::try_from(value).map_err(E::custom)?;
+                        #name::try_from(value).map_err(E::custom)
+                    }
+
+                    fn visit_str<E: ::serde::de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                        let value = value.parse::<#int>().map_err(E::custom)?;
+                        #name::try_from(value).map_err(E::custom)
+                    }
+                }
+
+                #deserialize
+            }
+        }
+    })
+}
+
+/// Whether the type is an `Option<...>`.
+fn is_option(ty: &syn::Type) -> bool {
+    matches!(ty, syn::Type::Path(path) if path.path.segments.last().is_some_and(|segment| segment.ident == "Option"))
+}
+
+/// Formats the `Decimal` fields of a struct with the decimal places of the
+/// layout.
+///
+/// Attached to a struct deriving `Serialize` and `Deserialize` (before the
+/// derives), it reads the `#[nfe_decimal(places = N)]` attribute of its
+/// `Decimal` and `Option<Decimal>` fields, replacing it with the serde helpers
+/// of `nf_e::decimal`. The fields are serialized like `Fixed<N>`, rounded half
+/// away from zero with `N` decimal places, and parsed like it too.
+///
+/// Optional fields are serialized only when present and default to `None`.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// #[nfe_decimal]
+/// #[derive(Serialize, Deserialize)]
+/// struct Product {
+///     #[nfe_decimal(places = 10)]
+///     #[serde(rename = "vUnCom")]
+///     unit_value: Decimal,
+///     #[nfe_decimal(places = 2)]
+///     #[serde(rename = "vDesc")]
+///     discount: Option<Decimal>,
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn nfe_decimal(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        let msg = "expected `#[nfe_decimal]` on the struct and `#[nfe_decimal(places = N)]` on its fields";
+        return syn::Error::new(proc_macro2::Span::call_site(), msg)
+            .to_compile_error()
+            .into();
+    }
+    let mut item = parse_macro_input!(item as syn::ItemStruct);
+    for field in item.fields.iter_mut() {
+        let mut places = None;
+        let mut error = None;
+        field.attrs.retain(|attr| {
+            if !attr.path().is_ident("nfe_decimal") {
+                return true;
+            }
+            let parsed = attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("places") {
+                    places = Some(meta.value()?.parse::<syn::LitInt>()?);
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `places`"))
+                }
+            });
+            error = parsed.err();
+            false
+        });
+        if let Some(error) = error {
+            return error.to_compile_error().into();
+        }
+        let Some(places) = places else {
+            continue;
+        };
+        let attrs: syn::Attribute = match is_option(&field.ty) {
+            true => {
+                let serialize = format!("::nf_e::decimal::serialize_option::<{}, _>", places);
+                syn::parse_quote! {
+                    #[serde(
+                        default,
+                        skip_serializing_if = "Option::is_none",
+                        serialize_with = #serialize,
+                        deserialize_with = "::nf_e::decimal::deserialize_option"
+                    )]
+                }
+            }
+            false => {
+                let serialize = format!("::nf_e::decimal::serialize::<{}, _>", places);
+                syn::parse_quote! {
+                    #[serde(serialize_with = #serialize, deserialize_with = "::nf_e::decimal::deserialize")]
+                }
+            }
+        };
+        field.attrs.push(attrs);
+    }
+    quote! { #item }.into()
+}
@@ -0,0 +1,345 @@
+//! Access key (chave de acesso) of the NF-e and NFC-e
+//!
+//! The 44 digits of the key identify the document across every webservice:
+//!
+//! | Digits | Content                                  |
+//! |--------|------------------------------------------|
+//! | 2      | State code (cUF)                         |
+//! | 4      | Year and month of emission (AAMM)        |
+//! | 14     | CNPJ or CPF of the issuer (zero padded)  |
+//! | 2      | Model (mod)                              |
+//! | 3      | Series (serie)                           |
+//! | 9      | Number (nNF)                             |
+//! | 1      | Type of emission (tpEmis)                |
+//! | 8      | Numeric code (cNF)                       |
+//! | 1      | Verifier digit (cDV), modulo 11          |
+
+use crate::enums::{EmissionType, Environment, Model, PersonDocument};
+use crate::states::State;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// Number of digits of an access key
+pub const LENGTH: usize = 44;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AccessKeyError {
+    Length(usize),
+    Character(char),
+    State(u8),
+    Model(u8),
+    EmissionType(u8),
+    VerifierDigit,
+}
+
+impl Display for AccessKeyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccessKeyError::Length(length) => write!(f, "Invalid access key length: {}", length),
+            AccessKeyError::Character(c) => write!(f, "Invalid access key character: {}", c),
+            AccessKeyError::State(code) => write!(f, "Invalid access key state: {}", code),
+            AccessKeyError::Model(code) => write!(f, "Invalid access key model: {}", code),
+            AccessKeyError::EmissionType(code) => {
+                write!(f, "Invalid access key emission type: {}", code)
+            }
+            AccessKeyError::VerifierDigit => write!(f, "Invalid access key verifier digit"),
+        }
+    }
+}
+
+/// Fields composing an access key
+///
+/// state: State of the issuer (cUF)
+/// year: Year of emission, two digits (AA)
+/// month: Month of emission (MM)
+/// issuer: Document of the issuer (CNPJ or CPF)
+/// model: Model of the document (mod)
+/// series: Series of the document (serie)
+/// number: Number of the document (nNF)
+/// emission_type: Type of emission (tpEmis)
+/// numeric_code: Random code of the document (cNF)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Components<'a> {
+    pub state: State,
+    pub year: u8,
+    pub month: u8,
+    pub issuer: &'a PersonDocument,
+    pub model: Model,
+    pub series: u16,
+    pub number: u32,
+    pub emission_type: EmissionType,
+    pub numeric_code: u32,
+}
+
+/// Validated access key, always 44 digits with a matching verifier digit
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(try_from = "String")]
+pub struct AccessKey(String);
+
+/// Modulo 11 digit of the 43 first digits, weighted 2 to 9 from the right
+fn verifier_digit(digits: &str) -> u8 {
+    let sum: u32 = digits
+        .bytes()
+        .rev()
+        .zip((2..=9).cycle())
+        .map(|(digit, weight)| (digit - b'0') as u32 * weight)
+        .sum();
+    match sum % 11 {
+        0 | 1 => 0,
+        rest => 11 - rest as u8,
+    }
+}
+
+/// Public consultation page of the NF-e in production, at the national portal
+pub const NFE_CONSULTATION_URL: &str = "https://www.nfe.fazenda.gov.br/portal/consultaRecaptcha.aspx?tipoConsulta=resumo&tipoConteudo=7PhJ+gAVw2g=";
+
+/// Public consultation page of the NF-e in homologation, at the national portal
+pub const NFE_HOMOLOGATION_CONSULTATION_URL: &str = "https://hom.nfe.fazenda.gov.br/portal/consultaRecaptcha.aspx?tipoConsulta=resumo&tipoConteudo=7PhJ+gAVw2g=";
+
+impl AccessKey {
+    /// Composes the key, computing its verifier digit
+    pub fn new(components: &Components) -> Self {
+        let bare = format!(
+            "{:02}{:02}{:02}{:0>14}{:02}{:03}{:09}{}{:08}",
+            components.state.code(),
+            components.year % 100,
+            components.month,
+            components.issuer.as_str(),
+            components.model.code(),
+            components.series,
+            components.number,
+            components.emission_type.code(),
+            components.numeric_code,
+        );
+        let digit = verifier_digit(&bare);
+        AccessKey(format!("{}{}", bare, digit))
+    }
+
+    /// Parses the 44 digits of a key, ignoring the spaces it is printed with
+    pub fn parse(value: &str) -> Result<Self, AccessKeyError> {
+        let digits: String = value.chars().filter(|c| !c.is_whitespace()).collect();
+        if let Some(c) = digits.chars().find(|c| !c.is_ascii_digit()) {
+            return Err(AccessKeyError::Character(c));
+        }
+        if digits.len() != LENGTH {
+            return Err(AccessKeyError::Length(digits.len()));
+        }
+        let key = AccessKey(digits);
+        let state = key.code(0..2);
+        State::try_from(state).map_err(|_| AccessKeyError::State(state))?;
+        let model = key.code(20..22);
+        Model::try_from(model).map_err(|_| AccessKeyError::Model(model))?;
+        let emission_type = key.code(34..35);
+        EmissionType::try_from(emission_type)
+            .map_err(|_| AccessKeyError::EmissionType(emission_type))?;
+        if verifier_digit(&key.0[..43]) != key.verifier_digit() {
+            return Err(AccessKeyError::VerifierDigit);
+        }
+        Ok(key)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    fn code(&self, range: std::ops::Range<usize>) -> u8 {
+        self.0[range].parse().unwrap()
+    }
+
+    fn field(&self, range: std::ops::Range<usize>) -> u32 {
+        self.0[range].parse().unwrap()
+    }
+
+    pub fn state(&self) -> State {
+        State::try_from(self.code(0..2)).unwrap()
+    }
+
+    /// Year of emission, two digits (AA)
+    pub fn year(&self) -> u8 {
+        self.code(2..4)
+    }
+
+    /// Month of emission (MM)
+    pub fn month(&self) -> u8 {
+        self.code(4..6)
+    }
+
+    /// Document of the issuer, 14 digits (CPF are padded with zeros)
+    pub fn issuer(&self) -> &str {
+        &self.0[6..20]
+    }
+
+    pub fn model(&self) -> Model {
+        Model::try_from(self.code(20..22)).unwrap()
+    }
+
+    pub fn series(&self) -> u16 {
+        self.field(22..25) as u16
+    }
+
+    pub fn number(&self) -> u32 {
+        self.field(25..34)
+    }
+
+    pub fn emission_type(&self) -> EmissionType {
+        EmissionType::try_from(self.code(34..35)).unwrap()
+    }
+
+    /// Random code of the document (cNF)
+    pub fn numeric_code(&self) -> u32 {
+        self.field(35..43)
+    }
+
+    /// Verifier digit (cDV)
+    pub fn verifier_digit(&self) -> u8 {
+        self.code(43..44)
+    }
+
+    /// Digits in groups of 4, as printed on the DANFE
+    pub fn grouped(&self) -> String {
+        let groups: Vec<&str> = (0..self.0.len())
+            .step_by(4)
+            .map(|start| &self.0[start..start + 4])
+            .collect();
+        groups.join(" ")
+    }
+
+    /// Public page where the document is consulted with the key
+    ///
+    /// The NFC-e are consulted at the page of their state (urlChave), while the
+    /// NF-e are consulted at the national portal.
+    pub fn consultation_url(&self, environment: &Environment) -> &'static str {
+        match (self.model(), environment) {
+            (Model::NFCe, _) => crate::qr_code::consultation_url(&self.state(), environment),
+            (Model::NFe, Environment::Production) => NFE_CONSULTATION_URL,
+            (Model::NFe, Environment::Homologation) => NFE_HOMOLOGATION_CONSULTATION_URL,
+        }
+    }
+}
+
+impl Display for AccessKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl TryFrom<String> for AccessKey {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        AccessKey::parse(&value).map_err(|e| e.to_string())
+    }
+}
+
+impl From<AccessKey> for String {
+    fn from(value: AccessKey) -> Self {
+        value.0
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::enums::CNPJ;
+    use quick_xml::{de::from_str as deserialize, se::to_string_with_root};
+
+    pub const ACCESS_KEY: &str = "35250812345678000195650010000000011000000019";
+
+    pub fn setup_access_key() -> AccessKey {
+        AccessKey::parse(ACCESS_KEY).expect("Failed to parse access key")
+    }
+
+    #[test]
+    fn test_consultation_url() {
+        let key = setup_access_key();
+        assert_eq!(
+            key.grouped(),
+            "3525 0812 3456 7800 0195 6500 1000 0000 0110 0000 0019"
+        );
+        assert_eq!(
+            key.consultation_url(&Environment::Production),
+            "https://www.nfce.fazenda.sp.gov.br/NFCeConsultaPublica"
+        );
+
+        let issuer = PersonDocument::CNPJ(CNPJ("12345678000195".to_string()));
+        let key = AccessKey::new(&Components {
+            state: State::SaoPaulo,
+            year: 25,
+            month: 8,
+            issuer: &issuer,
+            model: Model::NFe,
+            series: 1,
+            number: 1,
+            emission_type: EmissionType::Normal,
+            numeric_code: 1,
+        });
+        assert_eq!(
+            key.consultation_url(&Environment::Production),
+            NFE_CONSULTATION_URL
+        );
+        assert_eq!(
+            key.consultation_url(&Environment::Homologation),
+            NFE_HOMOLOGATION_CONSULTATION_URL
+        );
+    }
+
+    #[test]
+    fn test_serialization() {
+        let xml = format!("<chNFe>{}</chNFe>", ACCESS_KEY);
+        assert_eq!(
+            to_string_with_root("chNFe", &setup_access_key()).unwrap(),
+            xml
+        );
+        assert_eq!(deserialize::<AccessKey>(&xml).unwrap(), setup_access_key());
+    }
+
+    #[test]
+    fn test_compose() {
+        let issuer = PersonDocument::CNPJ(CNPJ("12345678000195".to_string()));
+        let key = AccessKey::new(&Components {
+            state: State::SaoPaulo,
+            year: 25,
+            month: 8,
+            issuer: &issuer,
+            model: Model::NFCe,
+            series: 1,
+            number: 1,
+            emission_type: EmissionType::Normal,
+            numeric_code: 1,
+        });
+        assert_eq!(key, setup_access_key());
+    }
+
+    #[test]
+    fn test_decompose() {
+        let key = AccessKey::parse("3525 0812 3456 7800 0195 6500 1000 0000 0110 0000 0019")
+            .expect("Failed to parse access key");
+        assert_eq!(key.state(), State::SaoPaulo);
+        assert_eq!((key.year(), key.month()), (25, 8));
+        assert_eq!(key.issuer(), "12345678000195");
+        assert_eq!(key.model(), Model::NFCe);
+        assert_eq!(key.series(), 1);
+        assert_eq!(key.number(), 1);
+        assert_eq!(key.emission_type(), EmissionType::Normal);
+        assert_eq!(key.numeric_code(), 1);
+        assert_eq!(key.verifier_digit(), 9);
+    }
+
+    #[test]
+    fn test_invalid_access_key() {
+        assert_eq!(AccessKey::parse("3525"), Err(AccessKeyError::Length(4)));
+        assert_eq!(
+            AccessKey::parse("3525081234567800019565001000000001100000001A"),
+            Err(AccessKeyError::Character('A'))
+        );
+        assert_eq!(
+            AccessKey::parse("99250812345678000195650010000000011000000019"),
+            Err(AccessKeyError::State(99))
+        );
+        assert_eq!(
+            AccessKey::parse("35250812345678000195650010000000011000000018"),
+            Err(AccessKeyError::VerifierDigit)
+        );
+        assert!(deserialize::<AccessKey>("<chNFe>3525</chNFe>").is_err());
+    }
+}
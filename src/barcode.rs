@@ -0,0 +1,28 @@
+//! Barcodes printed on the DANFE
+//!
+//! The access key is printed as a Code 128 (subset C) barcode on the DANFE of
+//! the NF-e, and the NFC-e carries its QR Code. Both are rendered here as raw
+//! modules, for the renderers of the crate and for integrators with their own
+//! layouts, or directly as SVG (and PNG for the QR Code).
+
+use crate::access_key::AccessKey;
+
+mod code128;
+mod png;
+mod qr;
+
+pub use code128::Code128;
+pub use qr::QrMatrix;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BarcodeError {
+    /// Code 128C only encodes an even number of digits
+    InvalidDigits(String),
+    /// Length in bytes of a text that does not fit in a QR Code
+    TooLong(usize),
+}
+
+/// Code 128C barcode of the access key, as printed on the DANFE
+pub fn access_key_barcode(access_key: &AccessKey) -> Code128 {
+    Code128::digits(access_key.as_str()).expect("Access keys have 44 digits")
+}
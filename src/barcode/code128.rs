@@ -0,0 +1,152 @@
+use super::BarcodeError;
+
+/// Widths of the bars and spaces of each symbol, starting with a bar
+const PATTERNS: [&str; 107] = [
+    "212222", "222122", "222221", "121223", "121322", "131222", "122213", "122312", "132212",
+    "221213", "221312", "231212", "112232", "122132", "122231", "113222", "123122", "123221",
+    "223211", "221132", "221231", "213212", "223112", "312131", "311222", "321122", "321221",
+    "312212", "322112", "322211", "212123", "212321", "232121", "111323", "131123", "131321",
+    "112313", "132113", "132311", "211313", "231113", "231311", "112133", "112331", "132131",
+    "113123", "113321", "133121", "313121", "211331", "231131", "213113", "213311", "213131",
+    "311123", "311321", "331121", "312113", "312311", "332111", "314111", "221411", "431111",
+    "111224", "111422", "121124", "121421", "141122", "141221", "112214", "112412", "122114",
+    "122411", "142112", "142211", "241211", "221114", "413111", "241112", "134111", "111242",
+    "121142", "121241", "114212", "124112", "124211", "411212", "421112", "421211", "212141",
+    "214121", "412121", "111143", "111341", "131141", "114113", "114311", "411113", "411311",
+    "113141", "114131", "311141", "411131", "211412", "211214", "211232", "2331112",
+];
+
+const START_C: u8 = 105;
+const STOP: u8 = 106;
+
+/// Modules of the quiet zone on each side of the barcode
+pub const QUIET_ZONE: usize = 10;
+
+/// Code 128 barcode of the subset C, encoding pairs of digits
+///
+/// symbols: Values of the symbols, from the start to the stop one
+#[derive(Debug, Clone, PartialEq)]
+pub struct Code128 {
+    symbols: Vec<u8>,
+}
+
+impl Code128 {
+    /// Encodes an even number of digits, as the 44 of the access key
+    pub fn digits(digits: &str) -> Result<Self, BarcodeError> {
+        if digits.is_empty()
+            || !digits.len().is_multiple_of(2)
+            || !digits.bytes().all(|digit| digit.is_ascii_digit())
+        {
+            return Err(BarcodeError::InvalidDigits(digits.to_string()));
+        }
+
+        let mut symbols = vec![START_C];
+        symbols.extend(
+            digits
+                .as_bytes()
+                .chunks(2)
+                .map(|pair| (pair[0] - b'0') * 10 + (pair[1] - b'0')),
+        );
+        let checksum = symbols
+            .iter()
+            .enumerate()
+            .map(|(position, &value)| position.max(1) * value as usize)
+            .sum::<usize>()
+            % 103;
+        symbols.push(checksum as u8);
+        symbols.push(STOP);
+        Ok(Code128 { symbols })
+    }
+
+    /// Values of the symbols, with the start, the checksum and the stop
+    pub fn symbols(&self) -> &[u8] {
+        &self.symbols
+    }
+
+    /// Modules of the barcode, `true` for the bars, without the quiet zone
+    pub fn bars(&self) -> Vec<bool> {
+        let mut bars = Vec::with_capacity(self.symbols.len() * 11 + 2);
+        for &symbol in &self.symbols {
+            for (index, width) in PATTERNS[symbol as usize].bytes().enumerate() {
+                let bar = index % 2 == 0;
+                bars.extend(std::iter::repeat_n(bar, (width - b'0') as usize));
+            }
+        }
+        bars
+    }
+
+    /// Width of the barcode in modules, with the quiet zones
+    pub fn width(&self) -> usize {
+        self.bars().len() + 2 * QUIET_ZONE
+    }
+
+    /// SVG of the barcode, one unit wide per module and `height` units high
+    pub fn to_svg(&self, height: u32) -> String {
+        let bars = self.bars();
+        let width = bars.len() + 2 * QUIET_ZONE;
+        let mut path = String::new();
+        let mut start = None;
+        for (x, &bar) in bars.iter().chain(std::iter::once(&false)).enumerate() {
+            match (bar, start) {
+                (true, None) => start = Some(x),
+                (false, Some(begin)) => {
+                    path.push_str(&format!(
+                        "M{},0h{}v{}h-{}z",
+                        begin + QUIET_ZONE,
+                        x - begin,
+                        height,
+                        x - begin
+                    ));
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        format!(
+            concat!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {width} {height}" "#,
+                r#"shape-rendering="crispEdges"><rect width="{width}" height="{height}" "#,
+                r##"fill="#fff"/><path d="{path}" fill="#000"/></svg>"##
+            ),
+            width = width,
+            height = height,
+            path = path
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_patterns() {
+        for (value, pattern) in PATTERNS.iter().enumerate() {
+            let modules: u32 = pattern.bytes().map(|width| (width - b'0') as u32).sum();
+            let expected = if value == STOP as usize { 13 } else { 11 };
+            assert_eq!(modules, expected, "Pattern of the symbol {}", value);
+        }
+    }
+
+    #[test]
+    fn test_digits() {
+        let barcode = Code128::digits("123456").unwrap();
+        // (105 + 1 * 12 + 2 * 34 + 3 * 56) % 103 = 44
+        assert_eq!(barcode.symbols(), &[105, 12, 34, 56, 44, 106]);
+        assert_eq!(barcode.bars().len(), 5 * 11 + 13);
+        assert_eq!(barcode.width(), 5 * 11 + 13 + 2 * QUIET_ZONE);
+        assert_eq!(
+            Code128::digits("12345"),
+            Err(BarcodeError::InvalidDigits("12345".to_string()))
+        );
+        assert!(Code128::digits("12a4").is_err());
+    }
+
+    #[test]
+    fn test_svg() {
+        let svg = Code128::digits("00").unwrap().to_svg(50);
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 66 50""#));
+        // Start C (211232) begins with a bar of 2 modules after the quiet zone
+        assert!(svg.contains(r#"d="M10,0h2v50h-2z"#));
+    }
+}
@@ -0,0 +1,617 @@
+//! QR Code encoder (ISO/IEC 18004) of the byte mode, with the error correction
+//! level M required for the QR Code of the NFC-e
+
+use super::{BarcodeError, png};
+
+/// Error correction codewords per block of the level M, by version
+const ECC_CODEWORDS: [usize; 41] = [
+    0, 10, 16, 26, 18, 24, 16, 18, 22, 22, 26, 30, 22, 22, 24, 24, 28, 28, 26, 26, 26, 26, 28, 28,
+    28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28,
+];
+
+/// Error correction blocks of the level M, by version
+const ECC_BLOCKS: [usize; 41] = [
+    0, 1, 1, 1, 2, 2, 4, 4, 4, 5, 5, 5, 8, 9, 9, 10, 10, 11, 13, 14, 16, 17, 17, 18, 20, 21, 23,
+    25, 26, 28, 29, 31, 33, 35, 37, 38, 40, 43, 45, 47, 49,
+];
+
+/// Bits of the level M in the format information
+const LEVEL_M: u32 = 0b00;
+
+/// Modules of the quiet zone around the symbol
+pub const QUIET_ZONE: usize = 4;
+
+/// Modules of a QR Code
+///
+/// version: Version of the symbol, from 1 to 40
+/// size: Modules of each side, without the quiet zone
+/// modules: Modules row by row, `true` for the dark ones
+#[derive(Debug, Clone, PartialEq)]
+pub struct QrMatrix {
+    version: usize,
+    size: usize,
+    modules: Vec<bool>,
+}
+
+impl QrMatrix {
+    /// Encodes the text in the smallest version that fits it
+    pub fn encode(text: &str) -> Result<Self, BarcodeError> {
+        let data = text.as_bytes();
+        let version = (1..=40)
+            .find(|&version| data_bits(data.len(), version) <= data_codewords(version) * 8)
+            .ok_or(BarcodeError::TooLong(data.len()))?;
+
+        let codewords = add_ecc_and_interleave(&data_codewords_of(data, version), version);
+        let mut builder = Builder::new(version);
+        builder.draw_function_patterns();
+        builder.draw_codewords(&codewords);
+
+        let mask = (0..8)
+            .min_by_key(|&mask| {
+                builder.apply_mask(mask);
+                builder.draw_format_bits(mask);
+                let penalty = builder.penalty();
+                builder.apply_mask(mask);
+                penalty
+            })
+            .expect("There are 8 masks");
+        builder.apply_mask(mask);
+        builder.draw_format_bits(mask);
+
+        Ok(QrMatrix {
+            version,
+            size: builder.size,
+            modules: builder.modules,
+        })
+    }
+
+    pub fn version(&self) -> usize {
+        self.version
+    }
+
+    /// Modules of each side, without the quiet zone
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Whether the module at the column `x` and the row `y` is dark
+    pub fn is_dark(&self, x: usize, y: usize) -> bool {
+        x < self.size && y < self.size && self.modules[y * self.size + x]
+    }
+
+    /// SVG of the symbol, one unit per module, with the quiet zone
+    pub fn to_svg(&self) -> String {
+        let side = self.size + 2 * QUIET_ZONE;
+        let mut path = String::new();
+        for y in 0..self.size {
+            for x in 0..self.size {
+                if self.is_dark(x, y) {
+                    path.push_str(&format!("M{},{}h1v1h-1z", x + QUIET_ZONE, y + QUIET_ZONE));
+                }
+            }
+        }
+        format!(
+            concat!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {side} {side}" "#,
+                r#"shape-rendering="crispEdges"><rect width="{side}" height="{side}" "#,
+                r##"fill="#fff"/><path d="{path}" fill="#000"/></svg>"##
+            ),
+            side = side,
+            path = path
+        )
+    }
+
+    /// PNG of the symbol, `scale` pixels per module, with the quiet zone
+    pub fn to_png(&self, scale: u32) -> Vec<u8> {
+        let scale = scale.max(1) as usize;
+        let side = (self.size + 2 * QUIET_ZONE) * scale;
+        let mut pixels = vec![255u8; side * side];
+        for y in 0..side {
+            for x in 0..side {
+                let (module_x, module_y) = (x / scale, y / scale);
+                if module_x >= QUIET_ZONE
+                    && module_y >= QUIET_ZONE
+                    && self.is_dark(module_x - QUIET_ZONE, module_y - QUIET_ZONE)
+                {
+                    pixels[y * side + x] = 0;
+                }
+            }
+        }
+        png::grayscale(side as u32, side as u32, &pixels)
+    }
+}
+
+/// Modules of the symbol available for the data and error correction codewords
+fn raw_data_modules(version: usize) -> usize {
+    let mut modules = (16 * version + 128) * version + 64;
+    if version >= 2 {
+        let alignments = version / 7 + 2;
+        modules -= (25 * alignments - 10) * alignments - 55;
+        if version >= 7 {
+            modules -= 36;
+        }
+    }
+    modules
+}
+
+fn data_codewords(version: usize) -> usize {
+    raw_data_modules(version) / 8 - ECC_CODEWORDS[version] * ECC_BLOCKS[version]
+}
+
+/// Bits of the character count of the byte mode
+fn count_bits(version: usize) -> usize {
+    if version <= 9 { 8 } else { 16 }
+}
+
+fn data_bits(length: usize, version: usize) -> usize {
+    4 + count_bits(version) + length * 8
+}
+
+/// Data codewords of the byte mode segment, with the terminator and the padding
+fn data_codewords_of(data: &[u8], version: usize) -> Vec<u8> {
+    let mut bits = Vec::with_capacity(data_codewords(version) * 8);
+    let mut push = |value: u32, length: usize| {
+        bits.extend((0..length).rev().map(|bit| (value >> bit) & 1 == 1));
+    };
+    push(0b0100, 4);
+    push(data.len() as u32, count_bits(version));
+    for &byte in data {
+        push(byte as u32, 8);
+    }
+
+    let capacity = data_codewords(version) * 8;
+    let terminator = (capacity - bits.len()).min(4);
+    bits.extend(std::iter::repeat_n(false, terminator));
+    bits.extend(std::iter::repeat_n(false, (8 - bits.len() % 8) % 8));
+
+    let mut codewords: Vec<u8> = bits
+        .chunks(8)
+        .map(|byte| byte.iter().fold(0, |value, &bit| (value << 1) | bit as u8))
+        .collect();
+    for pad in [0xEC, 0x11].into_iter().cycle() {
+        if codewords.len() >= data_codewords(version) {
+            break;
+        }
+        codewords.push(pad);
+    }
+    codewords
+}
+
+/// Splits the data in blocks, appends their error correction codewords and
+/// interleaves the blocks
+fn add_ecc_and_interleave(data: &[u8], version: usize) -> Vec<u8> {
+    let blocks = ECC_BLOCKS[version];
+    let ecc_length = ECC_CODEWORDS[version];
+    let raw_codewords = raw_data_modules(version) / 8;
+    let short_blocks = blocks - raw_codewords % blocks;
+    let short_length = raw_codewords / blocks;
+    let divisor = reed_solomon_divisor(ecc_length);
+
+    let mut offset = 0;
+    let blocks: Vec<Vec<u8>> = (0..blocks)
+        .map(|index| {
+            let length = short_length - ecc_length + usize::from(index >= short_blocks);
+            let mut block = data[offset..offset + length].to_vec();
+            offset += length;
+            let ecc = reed_solomon_remainder(&block, &divisor);
+            // Placeholder aligning the short blocks, skipped when interleaving
+            if index < short_blocks {
+                block.push(0);
+            }
+            block.extend(ecc);
+            block
+        })
+        .collect();
+
+    let mut result = Vec::with_capacity(raw_codewords);
+    for position in 0..blocks[0].len() {
+        for (index, block) in blocks.iter().enumerate() {
+            if position != short_length - ecc_length || index >= short_blocks {
+                result.push(block[position]);
+            }
+        }
+    }
+    result
+}
+
+/// Product in GF(2^8) modulo x^8 + x^4 + x^3 + x^2 + 1
+fn gf_multiply(x: u8, y: u8) -> u8 {
+    let mut z: u8 = 0;
+    for bit in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x1D);
+        z ^= ((y >> bit) & 1) * x;
+    }
+    z
+}
+
+/// Generator polynomial of the given degree, without the leading term
+fn reed_solomon_divisor(degree: usize) -> Vec<u8> {
+    let mut result = vec![0u8; degree];
+    result[degree - 1] = 1;
+    let mut root: u8 = 1;
+    for _ in 0..degree {
+        for index in 0..degree {
+            result[index] = gf_multiply(result[index], root);
+            if index + 1 < degree {
+                result[index] ^= result[index + 1];
+            }
+        }
+        root = gf_multiply(root, 0x02);
+    }
+    result
+}
+
+fn reed_solomon_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut result = vec![0u8; divisor.len()];
+    for &byte in data {
+        let factor = byte ^ result.remove(0);
+        result.push(0);
+        for (value, &coefficient) in result.iter_mut().zip(divisor) {
+            *value ^= gf_multiply(coefficient, factor);
+        }
+    }
+    result
+}
+
+/// Positions of the centers of the alignment patterns, on both axes
+fn alignment_positions(version: usize) -> Vec<usize> {
+    if version == 1 {
+        return Vec::new();
+    }
+    let size = version * 4 + 17;
+    let count = version / 7 + 2;
+    let step = if version == 32 {
+        26
+    } else {
+        (version * 4 + count * 2 + 1) / (count * 2 - 2) * 2
+    };
+    let mut positions: Vec<usize> = (0..count - 1)
+        .map(|index| size - 7 - index * step)
+        .collect();
+    positions.push(6);
+    positions.reverse();
+    positions
+}
+
+struct Builder {
+    version: usize,
+    size: usize,
+    modules: Vec<bool>,
+    function: Vec<bool>,
+}
+
+impl Builder {
+    fn new(version: usize) -> Self {
+        let size = version * 4 + 17;
+        Builder {
+            version,
+            size,
+            modules: vec![false; size * size],
+            function: vec![false; size * size],
+        }
+    }
+
+    fn get(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.size + x]
+    }
+
+    fn set_function(&mut self, x: usize, y: usize, dark: bool) {
+        self.modules[y * self.size + x] = dark;
+        self.function[y * self.size + x] = true;
+    }
+
+    fn draw_function_patterns(&mut self) {
+        for index in 0..self.size {
+            self.set_function(6, index, index % 2 == 0);
+            self.set_function(index, 6, index % 2 == 0);
+        }
+
+        let far = self.size - 4;
+        self.draw_finder(3, 3);
+        self.draw_finder(far, 3);
+        self.draw_finder(3, far);
+
+        let positions = alignment_positions(self.version);
+        let last = positions.len().saturating_sub(1);
+        for (i, &x) in positions.iter().enumerate() {
+            for (j, &y) in positions.iter().enumerate() {
+                // Corners overlapping the finder patterns
+                if [(0, 0), (0, last), (last, 0)].contains(&(i, j)) {
+                    continue;
+                }
+                self.draw_alignment(x, y);
+            }
+        }
+
+        // Reserves the format information, drawn after choosing the mask
+        self.draw_format_bits(0);
+        self.draw_version();
+    }
+
+    /// Finder pattern centered on the module, with its separator
+    fn draw_finder(&mut self, x: usize, y: usize) {
+        for dy in -4i32..=4 {
+            for dx in -4i32..=4 {
+                let (xx, yy) = (x as i32 + dx, y as i32 + dy);
+                if (0..self.size as i32).contains(&xx) && (0..self.size as i32).contains(&yy) {
+                    let distance = dx.abs().max(dy.abs());
+                    self.set_function(xx as usize, yy as usize, distance != 2 && distance != 4);
+                }
+            }
+        }
+    }
+
+    fn draw_alignment(&mut self, x: usize, y: usize) {
+        for dy in -2i32..=2 {
+            for dx in -2i32..=2 {
+                let distance = dx.abs().max(dy.abs());
+                self.set_function(
+                    (x as i32 + dx) as usize,
+                    (y as i32 + dy) as usize,
+                    distance != 1,
+                );
+            }
+        }
+    }
+
+    fn draw_format_bits(&mut self, mask: u32) {
+        let data = (LEVEL_M << 3) | mask;
+        let mut remainder = data;
+        for _ in 0..10 {
+            remainder = (remainder << 1) ^ ((remainder >> 9) * 0x537);
+        }
+        let bits = ((data << 10) | remainder) ^ 0x5412;
+        let bit = |index: usize| (bits >> index) & 1 == 1;
+
+        for index in 0..6 {
+            self.set_function(8, index, bit(index));
+        }
+        self.set_function(8, 7, bit(6));
+        self.set_function(8, 8, bit(7));
+        self.set_function(7, 8, bit(8));
+        for index in 9..15 {
+            self.set_function(14 - index, 8, bit(index));
+        }
+
+        let size = self.size;
+        for index in 0..8 {
+            self.set_function(size - 1 - index, 8, bit(index));
+        }
+        for index in 8..15 {
+            self.set_function(8, size - 15 + index, bit(index));
+        }
+        self.set_function(8, size - 8, true);
+    }
+
+    fn draw_version(&mut self) {
+        if self.version < 7 {
+            return;
+        }
+        let mut remainder = self.version as u32;
+        for _ in 0..12 {
+            remainder = (remainder << 1) ^ ((remainder >> 11) * 0x1F25);
+        }
+        let bits = ((self.version as u32) << 12) | remainder;
+        for index in 0..18 {
+            let dark = (bits >> index) & 1 == 1;
+            let (a, b) = (self.size - 11 + index % 3, index / 3);
+            self.set_function(a, b, dark);
+            self.set_function(b, a, dark);
+        }
+    }
+
+    /// Places the codewords in the zigzag of the columns pairs, from the bottom right
+    fn draw_codewords(&mut self, codewords: &[u8]) {
+        let total = codewords.len() * 8;
+        let mut index = 0;
+        let mut right = self.size - 1;
+        loop {
+            if right == 6 {
+                right = 5;
+            }
+            for vertical in 0..self.size {
+                for column in 0..2 {
+                    let x = right - column;
+                    let upward = (right + 1) & 2 == 0;
+                    let y = if upward {
+                        self.size - 1 - vertical
+                    } else {
+                        vertical
+                    };
+                    if !self.function[y * self.size + x] && index < total {
+                        self.modules[y * self.size + x] =
+                            (codewords[index >> 3] >> (7 - (index & 7))) & 1 == 1;
+                        index += 1;
+                    }
+                }
+            }
+            if right < 2 {
+                break;
+            }
+            right -= 2;
+        }
+    }
+
+    /// Inverts the data modules selected by the mask, so applying it twice undoes it
+    fn apply_mask(&mut self, mask: u32) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let invert = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                if invert && !self.function[y * self.size + x] {
+                    self.modules[y * self.size + x] ^= true;
+                }
+            }
+        }
+    }
+
+    /// Penalty of the symbol, used to choose the mask
+    fn penalty(&self) -> usize {
+        const FINDER: [bool; 11] = [
+            true, false, true, true, true, false, true, false, false, false, false,
+        ];
+        let size = self.size;
+        let mut penalty = 0;
+
+        let lines = (0..size).flat_map(|line| {
+            [
+                (0..size).map(|x| self.get(x, line)).collect::<Vec<_>>(),
+                (0..size).map(|y| self.get(line, y)).collect::<Vec<_>>(),
+            ]
+        });
+        for line in lines {
+            for run in line.chunk_by(|a, b| a == b) {
+                if run.len() >= 5 {
+                    penalty += run.len() - 2;
+                }
+            }
+            for window in line.windows(FINDER.len()) {
+                if window == FINDER || window.iter().rev().eq(FINDER.iter()) {
+                    penalty += 40;
+                }
+            }
+        }
+
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                let color = self.get(x, y);
+                if color == self.get(x + 1, y)
+                    && color == self.get(x, y + 1)
+                    && color == self.get(x + 1, y + 1)
+                {
+                    penalty += 3;
+                }
+            }
+        }
+
+        let dark = self.modules.iter().filter(|&&dark| dark).count();
+        let total = size * size;
+        let deviation = (dark * 20).abs_diff(total * 10);
+        penalty + deviation.div_ceil(total).saturating_sub(1) * 10
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reed_solomon() {
+        // Data of "HELLO WORLD" in the version 1-M, from the alphanumeric mode
+        let data = [
+            32, 91, 11, 120, 209, 114, 220, 77, 67, 64, 236, 17, 236, 17, 236, 17,
+        ];
+        assert_eq!(
+            reed_solomon_remainder(&data, &reed_solomon_divisor(10)),
+            vec![196, 35, 39, 119, 235, 215, 231, 226, 93, 23]
+        );
+    }
+
+    #[test]
+    fn test_capacity() {
+        assert_eq!(data_codewords(1), 16);
+        assert_eq!(data_codewords(10), 216);
+        assert_eq!(data_codewords(40), 2334);
+        assert_eq!(alignment_positions(7), vec![6, 22, 38]);
+        assert_eq!(alignment_positions(32), vec![6, 34, 60, 86, 112, 138]);
+    }
+
+    #[test]
+    fn test_encode() {
+        let qr = QrMatrix::encode("https://www.nfce.fazenda.sp.gov.br/qrcode?p=123").unwrap();
+        assert_eq!(qr.version(), 4);
+        assert_eq!(qr.size(), 33);
+        // Finder pattern of the top left corner and its separator
+        assert!((0..7).all(|index| qr.is_dark(index, 0) && qr.is_dark(0, index)));
+        assert!((0..8).all(|index| !qr.is_dark(index, 7) && !qr.is_dark(7, index)));
+        // Timing pattern and the dark module
+        assert!((8..qr.size() - 8).all(|index| qr.is_dark(index, 6) == (index % 2 == 0)));
+        assert!(qr.is_dark(8, qr.size() - 8));
+
+        assert_eq!(
+            QrMatrix::encode(&"a".repeat(3000)),
+            Err(BarcodeError::TooLong(3000))
+        );
+    }
+
+    #[test]
+    fn test_codewords_roundtrip() {
+        let text = "Nota Fiscal de Consumidor Eletronica";
+        let qr = QrMatrix::encode(text).unwrap();
+        let mut builder = Builder::new(qr.version());
+        builder.draw_function_patterns();
+
+        // Reads the mask back from the format information, checking its BCH code
+        let format = (0..15).fold(0u32, |bits, index| {
+            let (x, y) = match index {
+                0..=5 => (8, index),
+                6 => (8, 7),
+                7 => (8, 8),
+                8 => (7, 8),
+                _ => (14 - index, 8),
+            };
+            bits | ((qr.is_dark(x, y) as u32) << index)
+        }) ^ 0x5412;
+        let mask = (format >> 10) & 0b111;
+        assert_eq!(format >> 13, LEVEL_M);
+        let mut remainder = format >> 10;
+        for _ in 0..10 {
+            remainder = (remainder << 1) ^ ((remainder >> 9) * 0x537);
+        }
+        assert_eq!(remainder & 0x3FF, format & 0x3FF);
+
+        builder.modules = qr.modules.clone();
+        builder.apply_mask(mask);
+        let mut bits = Vec::new();
+        let mut right = builder.size - 1;
+        loop {
+            if right == 6 {
+                right = 5;
+            }
+            for vertical in 0..builder.size {
+                for column in 0..2 {
+                    let x = right - column;
+                    let y = if (right + 1) & 2 == 0 {
+                        builder.size - 1 - vertical
+                    } else {
+                        vertical
+                    };
+                    if !builder.function[y * builder.size + x] {
+                        bits.push(builder.get(x, y));
+                    }
+                }
+            }
+            if right < 2 {
+                break;
+            }
+            right -= 2;
+        }
+        let codewords: Vec<u8> = bits
+            .chunks_exact(8)
+            .map(|byte| byte.iter().fold(0, |value, &bit| (value << 1) | bit as u8))
+            .collect();
+        let data = data_codewords_of(text.as_bytes(), qr.version());
+        assert_eq!(codewords, add_ecc_and_interleave(&data, qr.version()));
+    }
+
+    #[test]
+    fn test_images() {
+        let qr = QrMatrix::encode("123").unwrap();
+        let svg = qr.to_svg();
+        assert!(svg.contains(r#"viewBox="0 0 29 29""#));
+        assert!(svg.contains("M4,4h1v1h-1z"));
+
+        let png = qr.to_png(2);
+        assert_eq!(&png[1..4], b"PNG");
+        // Width and height of the header, 29 modules of 2 pixels
+        assert_eq!(&png[16..24], &[0, 0, 0, 58, 0, 0, 0, 58]);
+    }
+}
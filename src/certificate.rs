@@ -0,0 +1,166 @@
+//! Checks of the A1 certificate against the issuer of the documents
+//!
+//! The authorizers reject documents signed with an expired certificate or
+//! with a certificate whose CNPJ root (the first 8 digits) differs from the
+//! one of the issuer, so both are checked before building and sending.
+
+use crate::enums::PersonDocument;
+use crate::sign::{Certificate, SignError};
+use chrono::{DateTime, TimeDelta, Utc};
+use std::fmt::Display;
+
+/// Default window before the expiration in which the certificate is reported
+pub const EXPIRATION_WINDOW_DAYS: i64 = 30;
+
+/// Number of digits of the CNPJ root, shared by the head office and its branches
+const CNPJ_ROOT_LENGTH: usize = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CertificateError {
+    Load(SignError),
+    /// Expiration of the certificate
+    Expired(DateTime<Utc>),
+    /// CNPJ of the certificate holder and document of the issuer
+    IssuerMismatch(String, String),
+}
+
+impl Display for CertificateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CertificateError::Load(e) => write!(f, "Failed to load the certificate: {:?}", e),
+            CertificateError::Expired(date) => write!(f, "Certificate expired at {}", date),
+            CertificateError::IssuerMismatch(holder, issuer) => write!(
+                f,
+                "Certificate of CNPJ {} does not belong to the issuer {}",
+                holder, issuer
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CertificateWarning {
+    /// Expiration of the certificate, within the configured window
+    ExpiresSoon(DateTime<Utc>),
+}
+
+/// Checks that `certificate` may sign the documents of `issuer` at `now`
+///
+/// Certificates without a CNPJ (e.g. e-CPF) are not matched against the issuer.
+pub fn validate(
+    certificate: &Certificate,
+    issuer: &PersonDocument,
+    now: DateTime<Utc>,
+    window: TimeDelta,
+) -> Result<Option<CertificateWarning>, CertificateError> {
+    let expiration = certificate.expiration();
+    if expiration <= now {
+        return Err(CertificateError::Expired(expiration));
+    }
+
+    if let Some(cnpj) = certificate.cnpj() {
+        let matches = match issuer {
+            PersonDocument::CNPJ(issuer) => {
+                issuer.0.get(..CNPJ_ROOT_LENGTH) == cnpj.0.get(..CNPJ_ROOT_LENGTH)
+            }
+            PersonDocument::CPF(_) => false,
+        };
+        if !matches {
+            return Err(CertificateError::IssuerMismatch(
+                cnpj.0.clone(),
+                issuer.as_str().to_string(),
+            ));
+        }
+    }
+
+    if expiration - now <= window {
+        return Ok(Some(CertificateWarning::ExpiresSoon(expiration)));
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PKCS12Config;
+    use crate::enums::{CNPJ, CPF};
+
+    fn setup_e_cnpj() -> Certificate {
+        Certificate::from_pkcs12(&PKCS12Config::new(
+            "tests/credentials/ecnpj.p12".to_string(),
+            "12345678".to_string(),
+        ))
+        .expect("Failed to load certificate")
+    }
+
+    fn issuer(cnpj: &str) -> PersonDocument {
+        PersonDocument::CNPJ(CNPJ(cnpj.to_string()))
+    }
+
+    fn window() -> TimeDelta {
+        TimeDelta::days(EXPIRATION_WINDOW_DAYS)
+    }
+
+    #[test]
+    fn test_valid_certificate() {
+        let certificate = setup_e_cnpj();
+        let now = certificate.expiration() - TimeDelta::days(365);
+        assert_eq!(
+            validate(&certificate, &issuer("12345678000195"), now, window()),
+            Ok(None)
+        );
+        // Branches share the certificate of the head office
+        assert_eq!(
+            validate(&certificate, &issuer("12345678000276"), now, window()),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn test_expiration() {
+        let certificate = setup_e_cnpj();
+        let expiration = certificate.expiration();
+        let issuer = issuer("12345678000195");
+        assert_eq!(
+            validate(
+                &certificate,
+                &issuer,
+                expiration - TimeDelta::days(10),
+                window()
+            ),
+            Ok(Some(CertificateWarning::ExpiresSoon(expiration)))
+        );
+        assert_eq!(
+            validate(&certificate, &issuer, expiration, window()),
+            Err(CertificateError::Expired(expiration))
+        );
+    }
+
+    #[test]
+    fn test_issuer_mismatch() {
+        let certificate = setup_e_cnpj();
+        let now = certificate.expiration() - TimeDelta::days(365);
+        assert_eq!(
+            validate(&certificate, &issuer("11222333000181"), now, window()),
+            Err(CertificateError::IssuerMismatch(
+                "12345678000195".to_string(),
+                "11222333000181".to_string()
+            ))
+        );
+        let cpf = PersonDocument::CPF(CPF("12345678909".to_string()));
+        assert!(matches!(
+            validate(&certificate, &cpf, now, window()),
+            Err(CertificateError::IssuerMismatch(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_certificate_without_cnpj() {
+        let certificate = crate::sign::tests::setup_certificate();
+        let now = certificate.expiration() - TimeDelta::days(365);
+        assert_eq!(
+            validate(&certificate, &issuer("11222333000181"), now, window()),
+            Ok(None)
+        );
+    }
+}
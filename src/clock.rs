@@ -0,0 +1,59 @@
+//! Source of the current moment of the library
+//!
+//! The emission date of the documents, and so their access keys, the dates of
+//! the events and the checks of the certificate read the clock of the
+//! `Config`. Tests and replays of past emissions fix it with `FixedClock`.
+
+use chrono::{DateTime, TimeZone, Utc};
+
+/// Source of the current moment
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+
+    /// Current moment in the zone of the machine
+    fn local(&self) -> DateTime<chrono::Local> {
+        self.now().with_timezone(&chrono::Local)
+    }
+}
+
+/// Clock of the system, the default one
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Clock always at the same moment
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FixedClock(DateTime<Utc>);
+
+impl FixedClock {
+    pub fn new<Tz: TimeZone>(moment: DateTime<Tz>) -> Self {
+        FixedClock(moment.to_utc())
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_clock() {
+        let moment = DateTime::parse_from_rfc3339("2023-10-05T14:30:00-03:00").unwrap();
+        let clock = FixedClock::new(moment);
+        assert_eq!(clock.now(), moment);
+        assert_eq!(clock.local(), moment);
+
+        let now = Utc::now();
+        assert!(SystemClock.now() >= now);
+    }
+}
@@ -1,7 +1,20 @@
-use crate::models::Issuer;
+use crate::LIBRARY_VERSION;
+#[cfg(feature = "sign")]
+use crate::certificate::{CertificateError, CertificateWarning, EXPIRATION_WINDOW_DAYS, validate};
+use crate::clock::{Clock, SystemClock};
+use crate::enums::{Environment, Model};
+use crate::models::{Contingency, Issuer};
+use crate::qr_code::{CSC, QRCodeError};
+#[cfg(feature = "sign")]
+use crate::sign::{Certificate, SignError};
+#[cfg(feature = "sign")]
+use chrono::{DateTime, TimeDelta, Utc};
 use lazy_static::lazy_static;
-use std::sync::RwLock;
+#[cfg(feature = "sign")]
+use std::sync::OnceLock;
+use std::sync::{Arc, RwLock};
 
+#[derive(Clone)]
 pub struct PKCS12Config {
     pub path: String,
     pub password: String,
@@ -13,9 +26,73 @@ impl PKCS12Config {
     }
 }
 
+/// Proxy of the requests to the SEFAZ webservices, for HTTP and HTTPS
+///
+/// url: Address of the proxy, as http://proxy.example:3128
+/// credentials: User and password of the basic authentication - Optional
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProxyConfig {
+    pub url: String,
+    pub credentials: Option<(String, String)>,
+}
+
+impl ProxyConfig {
+    pub fn new(url: String) -> Self {
+        ProxyConfig {
+            url,
+            credentials: None,
+        }
+    }
+
+    pub fn with_credentials(mut self, username: String, password: String) -> Self {
+        self.credentials = Some((username, password));
+        self
+    }
+}
+
+/// Number of CSC active at the same time for an environment, as SEFAZ keeps
+/// the previous one valid while the new one is rolled out
+pub const MAX_ACTIVE_CSC: usize = 2;
+
+/// Configuration of an issuer: its data, certificate and contingency
+///
+/// `Config` is a plain value, so each issuer of a process keeps its own and
+/// passes it to `InfoBuilder::new`, `Client::with_config` and the signers. The
+/// global configuration (`set_config`) is only a convenience for processes
+/// with a single issuer.
+///
+/// The certificate is parsed once and shared by the clones of the value.
+///
+/// The environment, model and series are the defaults of the documents started
+/// with `IdentificationBuilder`: homologation, NF-e and series 1.
+///
+/// The clock dates the documents and events and checks the certificate, the
+/// one of the system unless replaced. The emission version (verProc) defaults
+/// to the version of the library; fixing both keeps the documents built in
+/// tests and replays identical.
+///
+/// The proxy and the root certificates, PEM or DER files trusted besides the
+/// default ones, are used by the clients created with `Client::with_config`.
+///
+/// The CSC of the QR Code of the NFC-e are kept per environment, the newest
+/// first, up to `MAX_ACTIVE_CSC` of each.
+#[derive(Clone)]
 pub struct Config {
     issuer: Issuer,
     pkcs12_config: PKCS12Config,
+    contingency: Option<Contingency>,
+    #[cfg(feature = "sign")]
+    certificate: Arc<OnceLock<Arc<Certificate>>>,
+    #[cfg(feature = "sign")]
+    expiration_window: TimeDelta,
+    environment: Environment,
+    model: Model,
+    series: u16,
+    proxy: Option<ProxyConfig>,
+    root_certificates: Vec<String>,
+    cscs: Vec<(Environment, CSC)>,
+    clock: Arc<dyn Clock>,
+    emission_version: String,
 }
 
 impl Config {
@@ -23,7 +100,198 @@ impl Config {
         Config {
             issuer,
             pkcs12_config,
+            contingency: None,
+            #[cfg(feature = "sign")]
+            certificate: Arc::default(),
+            #[cfg(feature = "sign")]
+            expiration_window: TimeDelta::days(EXPIRATION_WINDOW_DAYS),
+            environment: Environment::Homologation,
+            model: Model::NFe,
+            series: 1,
+            proxy: None,
+            root_certificates: Vec::new(),
+            cscs: Vec::new(),
+            clock: Arc::new(SystemClock),
+            emission_version: LIBRARY_VERSION.to_string(),
+        }
+    }
+
+    /// Environment of the documents of the issuer (tpAmb)
+    pub fn with_environment(mut self, environment: Environment) -> Self {
+        self.environment = environment;
+        self
+    }
+
+    /// Model of the documents of the issuer (mod)
+    pub fn with_model(mut self, model: Model) -> Self {
+        self.model = model;
+        self
+    }
+
+    /// Series of the documents of the issuer (serie)
+    pub fn with_series(mut self, series: u16) -> Self {
+        self.series = series;
+        self
+    }
+
+    /// Window before the expiration in which the certificate is reported
+    #[cfg(feature = "sign")]
+    pub fn with_expiration_window(mut self, window: TimeDelta) -> Self {
+        self.expiration_window = window;
+        self
+    }
+
+    /// Certificate of the issuer already loaded, instead of reading the
+    /// PKCS#12 file on the first use
+    #[cfg(feature = "sign")]
+    pub fn with_certificate(mut self, certificate: Certificate) -> Self {
+        self.certificate = Arc::new(OnceLock::from(Arc::new(certificate)));
+        self
+    }
+
+    /// Source of the current moment, instead of the clock of the system
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Version of the application issuing the documents (verProc), 1 to 20
+    /// characters
+    pub fn with_emission_version(mut self, version: &str) -> Self {
+        self.emission_version = version.to_string();
+        self
+    }
+
+    /// Proxy of the requests to the webservices
+    pub fn with_proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Trusts the root certificates of the PEM or DER file at `path`
+    pub fn with_root_certificate(mut self, path: String) -> Self {
+        self.root_certificates.push(path);
+        self
+    }
+
+    /// Starts in contingency, so NFC-e are issued offline
+    pub fn with_contingency(mut self, contingency: Contingency) -> Self {
+        self.contingency = Some(contingency);
+        self
+    }
+
+    /// CSC of the QR Code of the NFC-e of `environment`, see `rotate_csc`
+    pub fn with_csc(mut self, environment: Environment, csc: CSC) -> Result<Self, ConfigError> {
+        self.rotate_csc(environment, csc)?;
+        Ok(self)
+    }
+
+    pub fn issuer(&self) -> &Issuer {
+        &self.issuer
+    }
+
+    pub fn pkcs12_config(&self) -> &PKCS12Config {
+        &self.pkcs12_config
+    }
+
+    pub fn environment(&self) -> &Environment {
+        &self.environment
+    }
+
+    pub fn model(&self) -> &Model {
+        &self.model
+    }
+
+    pub fn series(&self) -> u16 {
+        self.series
+    }
+
+    pub fn clock(&self) -> &dyn Clock {
+        self.clock.as_ref()
+    }
+
+    pub fn emission_version(&self) -> &str {
+        &self.emission_version
+    }
+
+    pub fn proxy(&self) -> Option<&ProxyConfig> {
+        self.proxy.as_ref()
+    }
+
+    /// Paths of the root certificates trusted besides the default ones
+    pub fn root_certificates(&self) -> &[String] {
+        &self.root_certificates
+    }
+
+    /// Contingency of the issuer, when enabled
+    pub fn contingency(&self) -> Option<&Contingency> {
+        self.contingency.as_ref()
+    }
+
+    /// Enters (`Some`) or leaves (`None`) the contingency of the issuer
+    pub fn set_contingency(&mut self, contingency: Option<Contingency>) {
+        self.contingency = contingency;
+    }
+
+    /// Newest CSC of `environment`, used by the QR Codes
+    pub fn csc(&self, environment: &Environment) -> Option<&CSC> {
+        self.active_cscs(environment).next()
+    }
+
+    /// Active CSC of `environment`, the newest first
+    pub fn active_cscs(&self, environment: &Environment) -> impl Iterator<Item = &CSC> {
+        self.cscs
+            .iter()
+            .filter(move |(csc_environment, _)| csc_environment == environment)
+            .map(|(_, csc)| csc)
+    }
+
+    /// Makes `csc` the newest CSC of `environment`
+    ///
+    /// The previous one stays active, so the QR Codes already printed with it
+    /// remain valid, while the older ones beyond `MAX_ACTIVE_CSC` are dropped.
+    /// Adding again an active identifier replaces its token.
+    pub fn rotate_csc(&mut self, environment: Environment, csc: CSC) -> Result<(), ConfigError> {
+        csc.validate().map_err(ConfigError::InvalidCSC)?;
+        self.cscs.retain(|(csc_environment, active)| {
+            *csc_environment != environment || active.id != csc.id
+        });
+        self.cscs.insert(0, (environment.clone(), csc));
+
+        let mut active = 0;
+        self.cscs.retain(|(csc_environment, _)| {
+            if *csc_environment != environment {
+                return true;
+            }
+            active += 1;
+            active <= MAX_ACTIVE_CSC
+        });
+        Ok(())
+    }
+
+    /// Certificate of the issuer, loaded from its PKCS#12 file on the first use
+    #[cfg(feature = "sign")]
+    pub fn certificate(&self) -> Result<Arc<Certificate>, SignError> {
+        if let Some(certificate) = self.certificate.get() {
+            return Ok(certificate.clone());
         }
+        let certificate = Arc::new(Certificate::from_pkcs12(&self.pkcs12_config)?);
+        Ok(self.certificate.get_or_init(|| certificate).clone())
+    }
+
+    /// Checks the expiration of the certificate and that it belongs to the issuer
+    #[cfg(feature = "sign")]
+    pub fn validate_certificate(
+        &self,
+        now: DateTime<Utc>,
+    ) -> Result<Option<CertificateWarning>, CertificateError> {
+        let certificate = self.certificate().map_err(CertificateError::Load)?;
+        validate(
+            &certificate,
+            &self.issuer.document,
+            now,
+            self.expiration_window,
+        )
     }
 }
 
@@ -33,6 +301,7 @@ pub enum ConfigError {
     MissingPKCS12Config,
     Locked,
     NotInitialized,
+    InvalidCSC(QRCodeError),
 }
 
 lazy_static! {
@@ -45,30 +314,67 @@ pub fn set_config(config: Config) -> Result<(), ConfigError> {
     Ok(())
 }
 
-pub fn get_issuer() -> Result<Issuer, ConfigError> {
+/// Copy of the global configuration
+pub fn global() -> Result<Config, ConfigError> {
     let config_lock = CONFIG.read().map_err(|_| ConfigError::Locked)?;
-    if let Some(ref config) = *config_lock {
-        Ok(config.issuer.clone())
-    } else {
-        Err(ConfigError::NotInitialized)
+    config_lock.clone().ok_or(ConfigError::NotInitialized)
+}
+
+pub fn get_issuer() -> Result<Issuer, ConfigError> {
+    global().map(|config| config.issuer)
+}
+
+pub fn get_pkcs12_config() -> Result<PKCS12Config, ConfigError> {
+    global().map(|config| config.pkcs12_config)
+}
+
+/// Contingency of the global issuer, when enabled
+pub fn get_contingency() -> Result<Option<Contingency>, ConfigError> {
+    global().map(|config| config.contingency)
+}
+
+/// Enters (`Some`) or leaves (`None`) the contingency of the global issuer
+pub fn set_contingency(contingency: Option<Contingency>) -> Result<(), ConfigError> {
+    let mut config_lock = CONFIG.write().map_err(|_| ConfigError::Locked)?;
+    match *config_lock {
+        Some(ref mut config) => {
+            config.set_contingency(contingency);
+            Ok(())
+        }
+        None => Err(ConfigError::NotInitialized),
+    }
+}
+
+/// Newest CSC of `environment` of the global issuer
+pub fn get_csc(environment: &Environment) -> Result<Option<CSC>, ConfigError> {
+    global().map(|config| config.csc(environment).cloned())
+}
+
+/// Makes `csc` the newest CSC of `environment` of the global issuer, see
+/// `Config::rotate_csc`
+pub fn rotate_csc(environment: Environment, csc: CSC) -> Result<(), ConfigError> {
+    let mut config_lock = CONFIG.write().map_err(|_| ConfigError::Locked)?;
+    match *config_lock {
+        Some(ref mut config) => config.rotate_csc(environment, csc),
+        None => Err(ConfigError::NotInitialized),
     }
 }
 
 pub fn is_set() -> bool {
-    let config_lock = CONFIG
-        .read()
-        .expect("CONFIG lock is poisoned");
+    let config_lock = CONFIG.read().expect("CONFIG lock is poisoned");
     config_lock.is_some()
 }
 
-pub fn get_pkcs12_certificate() -> Result<(), ConfigError> {
-    todo!("Implement PKCS#12 certificate loading logic here");
+/// Certificate of the global issuer, cached after the first load
+#[cfg(feature = "sign")]
+pub fn get_pkcs12_certificate() -> Result<Arc<Certificate>, SignError> {
+    global().map_err(SignError::ConfigError)?.certificate()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::tests::setup_issuer;
+    use crate::models::tests::{setup_config, setup_issuer};
 
     #[test]
     fn test_set_and_get_config() {
@@ -84,4 +390,68 @@ mod tests {
         let retrieved_issuer = get_issuer().unwrap();
         assert_eq!(retrieved_issuer, issuer);
     }
+
+    #[cfg(feature = "sign")]
+    #[test]
+    fn test_certificate_cache() {
+        let config = Config::new(
+            setup_issuer(),
+            PKCS12Config::new(
+                "tests/credentials/cert.p12".to_string(),
+                "12345678".to_string(),
+            ),
+        );
+        let certificate = config.certificate().unwrap();
+        assert!(Arc::ptr_eq(
+            &certificate,
+            &config.clone().certificate().unwrap()
+        ));
+
+        let missing = Config::new(
+            setup_issuer(),
+            PKCS12Config::new("path/to/cert.p12".to_string(), "password".to_string()),
+        );
+        assert!(matches!(missing.certificate(), Err(SignError::Io(_))));
+    }
+
+    #[test]
+    fn test_csc_rotation() {
+        let first = CSC::new(1, "0123456789ABCDEF".to_string());
+        let second = CSC::new(2, "FEDCBA9876543210".to_string());
+        let third = CSC::new(3, "00112233445566778899".to_string());
+        let mut config = setup_config()
+            .with_csc(Environment::Homologation, first.clone())
+            .unwrap()
+            .with_csc(Environment::Production, third.clone())
+            .unwrap();
+        assert_eq!(config.csc(&Environment::Homologation), Some(&first));
+
+        config
+            .rotate_csc(Environment::Homologation, second.clone())
+            .unwrap();
+        assert_eq!(config.csc(&Environment::Homologation), Some(&second));
+        assert_eq!(
+            config
+                .active_cscs(&Environment::Homologation)
+                .collect::<Vec<_>>(),
+            vec![&second, &first]
+        );
+
+        config
+            .rotate_csc(Environment::Homologation, third.clone())
+            .unwrap();
+        assert_eq!(
+            config
+                .active_cscs(&Environment::Homologation)
+                .collect::<Vec<_>>(),
+            vec![&third, &second]
+        );
+        assert_eq!(config.csc(&Environment::Production), Some(&third));
+
+        assert_eq!(
+            config.rotate_csc(Environment::Homologation, CSC::new(4, String::new())),
+            Err(ConfigError::InvalidCSC(QRCodeError::EmptyToken))
+        );
+        assert_eq!(config.csc(&Environment::Homologation), Some(&third));
+    }
 }
@@ -0,0 +1,132 @@
+//! Contingency of the documents
+//!
+//! While SEFAZ is unreachable the NFC-e are issued offline (tpEmis = 9) and
+//! handed to the consumer right away, but they must still be transmitted once
+//! the connection is back through the `Queue`, see the `queue` module.
+//!
+//! The NF-e are instead sent to the virtual contingency authorizer (SVC-AN or
+//! SVC-RS) of the state of the issuer, see `svc`.
+
+use crate::enums::EmissionType;
+use crate::models::{Contingency, Info};
+use crate::soap::endpoints::Authorizer;
+use crate::states::State;
+
+pub use crate::queue::Queue;
+
+/// Emission type of the virtual contingency authorizer of the state
+pub fn svc_emission_type(state: &State) -> EmissionType {
+    match Authorizer::contingency(state) {
+        Authorizer::SVCRS => EmissionType::SVCRS,
+        _ => EmissionType::SVCAN,
+    }
+}
+
+/// Moves the NF-e to the virtual contingency authorizer of its state
+///
+/// Rewrites the emission type (tpEmis), the entry in contingency (dhCont and
+/// xJust) and the verifier digit of the new access key, so the document must
+/// be signed again.
+pub fn svc(mut info: Info, contingency: Contingency) -> Info {
+    let identification = &mut info.identification;
+    identification.emission_type = svc_emission_type(&identification.location.state);
+    identification.contingency = Some(contingency);
+    info.identification.verifier_digit = info.access_key().verifier_digit();
+    info
+}
+
+/// Moves the NFC-e to the offline contingency (tpEmis = 9)
+///
+/// As `svc`, rewrites the entry in contingency and the verifier digit, so the
+/// document must be signed again and get a new QR Code.
+pub fn offline(mut info: Info, contingency: Contingency) -> Info {
+    let identification = &mut info.identification;
+    identification.emission_type = EmissionType::Offline;
+    identification.contingency = Some(contingency);
+    info.identification.verifier_digit = info.access_key().verifier_digit();
+    info
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::Model;
+    use crate::models::NFe;
+    use crate::models::tests::{setup_info_builder, setup_local_date};
+    use crate::qr_code::CSC;
+    use crate::queue::tests::setup_directory;
+    use crate::queue::{FileStorage, QueueStatus, QueueStorage};
+    use crate::sign::tests::setup_certificate;
+
+    fn setup_offline_nfe() -> NFe {
+        let contingency =
+            Contingency::new(setup_local_date(14, 0), "Falha de comunicacao com a SEFAZ");
+        let info = setup_info_builder()
+            .set_contingency(contingency)
+            .build()
+            .expect("Failed to build Info");
+        NFe::with_certificate(info, &setup_certificate())
+            .expect("Failed to sign NFe")
+            .with_qr_code(&CSC::new(1, "0123456789ABCDEF".to_string()))
+            .expect("Failed to generate QR Code")
+    }
+
+    #[test]
+    fn test_offline_nfe() {
+        let nfe = setup_offline_nfe();
+        assert_eq!(nfe.info.identification.emission_type, EmissionType::Offline);
+        assert_eq!(&nfe.info.id()[37..38], "9");
+        // Offline QR Codes carry the day, the total and the digest
+        let qr_code = &nfe.supplement.as_ref().unwrap().qr_code;
+        assert_eq!(qr_code.split('|').count(), 8);
+    }
+
+    #[test]
+    fn test_svc() {
+        let mut info = setup_info_builder().build().expect("Failed to build Info");
+        info.identification.model = Model::NFe;
+        let contingency = Contingency::new(setup_local_date(14, 0), "SEFAZ de origem indisponivel");
+
+        let info = svc(info, contingency.clone());
+        let identification = &info.identification;
+        // Minas Gerais falls back to SVC-AN
+        assert_eq!(identification.emission_type, EmissionType::SVCAN);
+        assert_eq!(identification.contingency, Some(contingency));
+        let access_key = info.access_key();
+        assert_eq!(access_key.emission_type(), EmissionType::SVCAN);
+        assert_eq!(identification.verifier_digit, access_key.verifier_digit());
+        assert!(crate::access_key::AccessKey::parse(access_key.as_str()).is_ok());
+
+        assert_eq!(svc_emission_type(&State::Parana), EmissionType::SVCRS);
+    }
+
+    #[test]
+    fn test_offline() {
+        let info = setup_info_builder().build().expect("Failed to build Info");
+        let contingency =
+            Contingency::new(setup_local_date(14, 0), "Falha de comunicacao com a SEFAZ");
+
+        let info = offline(info, contingency.clone());
+        let identification = &info.identification;
+        assert_eq!(identification.emission_type, EmissionType::Offline);
+        assert_eq!(identification.contingency, Some(contingency));
+        let access_key = info.access_key();
+        assert_eq!(access_key.emission_type(), EmissionType::Offline);
+        assert_eq!(identification.verifier_digit, access_key.verifier_digit());
+    }
+
+    #[test]
+    fn test_queue() {
+        let directory = setup_directory("contingency");
+        let mut storage = FileStorage::new(&directory).expect("Failed to open queue");
+        let nfe = setup_offline_nfe();
+
+        storage.push(&nfe).expect("Failed to queue document");
+        assert_eq!(storage.pending().unwrap(), vec![setup_offline_nfe()]);
+        assert_eq!(
+            storage.status(&nfe.info.access_key()).unwrap(),
+            Some(QueueStatus::Pending)
+        );
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,543 @@
+//! DANFE, the auxiliary document of the NF-e printed for the transit of the goods
+//!
+//! Only the simplified layout (tpImp 3) is rendered for now, for operations
+//! that print a simple document for the transport: a single block on an A4
+//! page with the issuer, the operation, the access key and its barcode, the
+//! authorization protocol, the recipient and the total. The items are listed
+//! up to a configurable number, with their descriptions truncated, and the
+//! remaining ones are only counted.
+//!
+//! The page is an SVG in millimeters, printed or converted by the integrators.
+
+use crate::barcode::access_key_barcode;
+use crate::decimal::Money;
+use crate::enums::{DanfeGeneration, Environment, Operation, PersonDocument, RecipientDocument};
+use crate::models::{Address, Detail, NFeProc};
+use crate::xml::escape_text;
+
+/// Width of the A4 page, in millimeters
+pub const PAGE_WIDTH: f64 = 210.0;
+
+/// Height of the A4 page, in millimeters
+pub const PAGE_HEIGHT: f64 = 297.0;
+
+/// Margin of the block on the page, in millimeters
+const MARGIN: f64 = 10.0;
+
+/// Padding of the texts inside the block, in millimeters
+const PADDING: f64 = 2.0;
+
+/// Size of the barcode of the access key, in millimeters
+const BARCODE_WIDTH: f64 = 120.0;
+const BARCODE_HEIGHT: f64 = 15.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DanfeError {
+    /// Printing type (tpImp) of the document other than the rendered one
+    UnsupportedGeneration(Option<DanfeGeneration>),
+    /// Status code (cStat) of the protocol of a document that is not authorized
+    NotAuthorized(u16),
+}
+
+/// Renderer of the simplified DANFE (tpImp 3)
+///
+/// max_items: Items listed, the remaining ones only counted - 10 by default
+/// description_length: Characters of the descriptions of the items - 60 by default
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimplifiedDanfe {
+    max_items: usize,
+    description_length: usize,
+}
+
+impl Default for SimplifiedDanfe {
+    fn default() -> Self {
+        SimplifiedDanfe {
+            max_items: 10,
+            description_length: 60,
+        }
+    }
+}
+
+impl SimplifiedDanfe {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Items listed on the DANFE, 0 to print none of them
+    pub fn with_max_items(mut self, max_items: usize) -> Self {
+        self.max_items = max_items;
+        self
+    }
+
+    /// Characters of the descriptions (xProd) printed, the rest replaced by "..."
+    pub fn with_description_length(mut self, description_length: usize) -> Self {
+        self.description_length = description_length;
+        self
+    }
+
+    /// SVG of the DANFE of the authorized document
+    ///
+    /// The document must be issued with the simplified printing type
+    /// (`DanfeGeneration::Simplified`) and its protocol must be an authorization.
+    pub fn render(&self, nfe_proc: &NFeProc) -> Result<String, DanfeError> {
+        let info = &nfe_proc.nfe.info;
+        let identification = &info.identification;
+        if identification.printing_type != Some(DanfeGeneration::Simplified) {
+            return Err(DanfeError::UnsupportedGeneration(
+                identification.printing_type.clone(),
+            ));
+        }
+        let protocol = &nfe_proc.protocol.info;
+        let number = match &protocol.number {
+            Some(number) if protocol.is_authorized() => number,
+            _ => return Err(DanfeError::NotAuthorized(protocol.status)),
+        };
+
+        let mut page = Page::new();
+        page.text(Font::TITLE, "DANFE SIMPLIFICADO");
+        page.text(Font::CENTER, "Documento Auxiliar da Nota Fiscal Eletrônica");
+        if identification.environment == Environment::Homologation {
+            page.text(
+                Font::TITLE_SMALL,
+                "SEM VALOR FISCAL - EMITIDA EM AMBIENTE DE HOMOLOGAÇÃO",
+            );
+        }
+        page.separator();
+
+        let issuer = &info.issuer;
+        page.text(Font::LABEL, "EMITENTE");
+        page.text(Font::STRONG, &issuer.name);
+        page.text(
+            Font::TEXT,
+            &format!(
+                "{}    IE: {}",
+                person_document(&issuer.document),
+                issuer.address.ie.0
+            ),
+        );
+        page.text(Font::TEXT, &address(&issuer.address.address));
+        page.separator();
+
+        let operation = match identification.r#type {
+            Operation::Incoming => "0 - ENTRADA",
+            Operation::Outgoing => "1 - SAÍDA",
+        };
+        page.text(
+            Font::TEXT,
+            &format!(
+                "Nº {}    Série: {}    Tipo: {}    Emissão: {}",
+                document_number(identification.number),
+                identification.series,
+                operation,
+                identification.emission_date.format("%d/%m/%Y %H:%M:%S")
+            ),
+        );
+        page.text(
+            Font::TEXT,
+            &format!("Natureza da operação: {}", identification.operation_nature),
+        );
+        page.separator();
+
+        let access_key = info.access_key();
+        let barcode = access_key_barcode(&access_key);
+        page.barcode(&barcode.to_svg(barcode_height(barcode.width())));
+        page.text(Font::LABEL_CENTER, "CHAVE DE ACESSO");
+        page.text(Font::STRONG_CENTER, &access_key.grouped());
+        page.text(
+            Font::CENTER,
+            &format!(
+                "Protocolo de autorização de uso: {} - {}",
+                number,
+                protocol.received_at.format("%d/%m/%Y %H:%M:%S")
+            ),
+        );
+        page.text(
+            Font::CENTER,
+            &format!(
+                "Consulte a autenticidade em {}",
+                access_key.consultation_url(&identification.environment)
+            ),
+        );
+        page.separator();
+
+        if let Some(recipient) = &info.recipient {
+            page.text(Font::LABEL, "DESTINATÁRIO");
+            if let Some(name) = &recipient.name {
+                page.text(Font::STRONG, name);
+            }
+            page.text(Font::TEXT, &recipient_document(&recipient.document));
+            if let Some(recipient_address) = &recipient.address {
+                page.text(Font::TEXT, &address(recipient_address));
+            }
+            page.separator();
+        }
+
+        if self.max_items > 0 && !info.details.is_empty() {
+            page.columns(
+                Font::LABEL,
+                ["CÓDIGO", "DESCRIÇÃO", "QUANTIDADE", "VALOR (R$)"],
+            );
+            for detail in info.details.iter().take(self.max_items) {
+                page.columns(Font::TEXT, self.item_columns(detail));
+            }
+            let remaining = info.details.len().saturating_sub(self.max_items);
+            if remaining > 0 {
+                page.text(
+                    Font::TEXT,
+                    &format!("... e mais {} item(ns) não listado(s)", remaining),
+                );
+            }
+            page.separator();
+        }
+
+        page.text(
+            Font::STRONG_RIGHT,
+            &format!("VALOR TOTAL DA NF-e: R$ {}", money(&info.total.icms.total)),
+        );
+        Ok(page.finish())
+    }
+
+    fn item_columns(&self, detail: &Detail) -> [String; 4] {
+        let item = &detail.item;
+        [
+            item.code.clone(),
+            truncate(&item.description, self.description_length),
+            format!(
+                "{} {}",
+                item.quantity.to_string().replace('.', ","),
+                item.unit
+            ),
+            money(&item.total_value),
+        ]
+    }
+}
+
+/// Height of the barcode in modules, keeping the proportion of its place on the page
+fn barcode_height(width: usize) -> u32 {
+    (width as f64 * BARCODE_HEIGHT / BARCODE_WIDTH).round() as u32
+}
+
+/// Size in millimeters, weight and alignment of a line of text
+#[derive(Debug, Clone, Copy)]
+struct Font {
+    size: f64,
+    bold: bool,
+    anchor: Anchor,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Anchor {
+    Start,
+    Middle,
+    End,
+}
+
+impl Font {
+    const TITLE: Font = Font::new(5.0, true, Anchor::Middle);
+    const TITLE_SMALL: Font = Font::new(3.5, true, Anchor::Middle);
+    const LABEL: Font = Font::new(2.5, true, Anchor::Start);
+    const LABEL_CENTER: Font = Font::new(2.5, true, Anchor::Middle);
+    const TEXT: Font = Font::new(3.0, false, Anchor::Start);
+    const CENTER: Font = Font::new(3.0, false, Anchor::Middle);
+    const STRONG: Font = Font::new(3.5, true, Anchor::Start);
+    const STRONG_CENTER: Font = Font::new(3.5, true, Anchor::Middle);
+    const STRONG_RIGHT: Font = Font::new(4.0, true, Anchor::End);
+
+    const fn new(size: f64, bold: bool, anchor: Anchor) -> Self {
+        Font { size, bold, anchor }
+    }
+}
+
+/// Elements of the page, laid out from the top of the block
+struct Page {
+    elements: String,
+    y: f64,
+}
+
+impl Page {
+    fn new() -> Self {
+        Page {
+            elements: String::new(),
+            y: MARGIN + PADDING,
+        }
+    }
+
+    /// Line of text below the previous element
+    fn text(&mut self, font: Font, content: &str) {
+        let x = match font.anchor {
+            Anchor::Start => MARGIN + PADDING,
+            Anchor::Middle => PAGE_WIDTH / 2.0,
+            Anchor::End => PAGE_WIDTH - MARGIN - PADDING,
+        };
+        self.y += font.size * 1.4;
+        self.push_text(x, font, content);
+    }
+
+    /// Line of the items, with the columns of the code, the description, the
+    /// quantity and the value
+    fn columns(&mut self, font: Font, columns: [impl AsRef<str>; 4]) {
+        self.y += font.size * 1.4;
+        let [code, description, quantity, value] = columns;
+        let start = MARGIN + PADDING;
+        let end = PAGE_WIDTH - MARGIN - PADDING;
+        self.push_text(start, font, code.as_ref());
+        self.push_text(start + 28.0, font, description.as_ref());
+        let right = Font {
+            anchor: Anchor::End,
+            ..font
+        };
+        self.push_text(end - 32.0, right, quantity.as_ref());
+        self.push_text(end, right, value.as_ref());
+    }
+
+    fn push_text(&mut self, x: f64, font: Font, content: &str) {
+        let anchor = match font.anchor {
+            Anchor::Start => "start",
+            Anchor::Middle => "middle",
+            Anchor::End => "end",
+        };
+        let weight = if font.bold { "bold" } else { "normal" };
+        self.elements.push_str(&format!(
+            concat!(
+                r#"<text x="{x:.1}" y="{y:.1}" font-size="{size:.1}" "#,
+                r#"font-weight="{weight}" text-anchor="{anchor}">"#
+            ),
+            x = x,
+            y = self.y,
+            size = font.size,
+            weight = weight,
+            anchor = anchor
+        ));
+        escape_text(content, &mut self.elements);
+        self.elements.push_str("</text>");
+    }
+
+    /// Barcode centered below the previous element
+    fn barcode(&mut self, svg: &str) {
+        self.y += PADDING;
+        self.elements.push_str(&format!(
+            r#"<svg x="{x:.1}" y="{y:.1}" width="{width:.1}" height="{height:.1}">{svg}</svg>"#,
+            x = (PAGE_WIDTH - BARCODE_WIDTH) / 2.0,
+            y = self.y,
+            width = BARCODE_WIDTH,
+            height = BARCODE_HEIGHT,
+            svg = svg
+        ));
+        self.y += BARCODE_HEIGHT;
+    }
+
+    /// Horizontal line across the block, below the previous element
+    fn separator(&mut self) {
+        self.y += PADDING;
+        self.elements.push_str(&format!(
+            concat!(
+                r#"<line x1="{x1:.1}" y1="{y:.1}" x2="{x2:.1}" y2="{y:.1}" "#,
+                r##"stroke="#000" stroke-width="0.2"/>"##
+            ),
+            x1 = MARGIN,
+            x2 = PAGE_WIDTH - MARGIN,
+            y = self.y
+        ));
+    }
+
+    /// SVG of the page, with the border of the block around its elements
+    fn finish(self) -> String {
+        let height = self.y + PADDING - MARGIN;
+        format!(
+            concat!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}mm" "#,
+                r#"height="{page_height}mm" viewBox="0 0 {width} {page_height}" "#,
+                r#"font-family="Helvetica, Arial, sans-serif">"#,
+                r##"<rect width="{width}" height="{page_height}" fill="#fff"/>"##,
+                r##"<rect x="{margin}" y="{margin}" width="{block_width}" height="{height:.1}" "##,
+                r##"fill="none" stroke="#000" stroke-width="0.3"/>{elements}</svg>"##
+            ),
+            width = PAGE_WIDTH,
+            page_height = PAGE_HEIGHT,
+            margin = MARGIN,
+            block_width = PAGE_WIDTH - 2.0 * MARGIN,
+            height = height,
+            elements = self.elements
+        )
+    }
+}
+
+/// Value in the Brazilian format, as "1.234,56"
+fn money(value: &Money) -> String {
+    let formatted = value.to_string();
+    let (integer, decimals) = formatted.split_once('.').unwrap_or((&formatted, "00"));
+    let (sign, digits) = match integer.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", integer),
+    };
+    let mut grouped = String::new();
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index) % 3 == 0 {
+            grouped.push('.');
+        }
+        grouped.push(digit);
+    }
+    format!("{}{},{}", sign, grouped, decimals)
+}
+
+/// Number of the document in groups of 3 digits, as "000.012.345"
+fn document_number(number: u32) -> String {
+    let digits = format!("{:09}", number);
+    format!("{}.{}.{}", &digits[..3], &digits[3..6], &digits[6..])
+}
+
+fn person_document(document: &PersonDocument) -> String {
+    match document {
+        PersonDocument::CNPJ(cnpj) => format!("CNPJ: {}", masked(&cnpj.0)),
+        PersonDocument::CPF(cpf) => format!("CPF: {}", masked(&cpf.0)),
+    }
+}
+
+fn recipient_document(document: &RecipientDocument) -> String {
+    match document {
+        RecipientDocument::CNPJ(cnpj) => format!("CNPJ: {}", masked(&cnpj.0)),
+        RecipientDocument::CPF(cpf) => format!("CPF: {}", masked(&cpf.0)),
+        RecipientDocument::Foreign(id) => format!("Identificação estrangeira: {}", id.0),
+    }
+}
+
+/// CNPJ as "12.345.678/0001-95" and CPF as "123.456.789-09"
+fn masked(digits: &str) -> String {
+    if !digits.bytes().all(|digit| digit.is_ascii_digit()) {
+        return digits.to_string();
+    }
+    match digits.len() {
+        14 => format!(
+            "{}.{}.{}/{}-{}",
+            &digits[..2],
+            &digits[2..5],
+            &digits[5..8],
+            &digits[8..12],
+            &digits[12..]
+        ),
+        11 => format!(
+            "{}.{}.{}-{}",
+            &digits[..3],
+            &digits[3..6],
+            &digits[6..9],
+            &digits[9..]
+        ),
+        _ => digits.to_string(),
+    }
+}
+
+fn address(address: &Address) -> String {
+    let mut line = format!("{}, {}", address.line_1, address.number);
+    if let Some(line_2) = &address.line_2 {
+        line.push_str(&format!(" - {}", line_2));
+    }
+    format!(
+        "{} - {} - {}/{} - CEP {}",
+        line,
+        address.neighborhood,
+        address.city.name,
+        address.state.acronym(),
+        address.zip_code
+    )
+}
+
+/// Text cut to `length` characters, ending with "..." when cut
+fn truncate(text: &str, length: usize) -> String {
+    if text.chars().count() <= length {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(length.saturating_sub(3)).collect();
+    format!("{}...", kept.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::tests::setup_nfe_proc;
+    use crate::xml::canonicalize_xml;
+    use rust_decimal_macros::dec;
+
+    fn setup_simplified() -> NFeProc {
+        let mut nfe_proc = setup_nfe_proc();
+        nfe_proc.nfe.info.identification.printing_type = Some(DanfeGeneration::Simplified);
+        nfe_proc
+    }
+
+    #[test]
+    fn test_render() {
+        let nfe_proc = setup_simplified();
+        let info = &nfe_proc.nfe.info;
+        let svg = SimplifiedDanfe::new()
+            .render(&nfe_proc)
+            .expect("Failed to render the DANFE");
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="210mm""#));
+        assert!(svg.ends_with("</svg>"));
+        assert!(canonicalize_xml(&svg).is_ok());
+        assert!(svg.contains("DANFE SIMPLIFICADO"));
+        assert!(svg.contains(&info.access_key().grouped()));
+        let barcode = access_key_barcode(&info.access_key());
+        assert!(svg.contains(&barcode.to_svg(barcode_height(barcode.width()))));
+        assert!(svg.contains("131230000000001"));
+        assert!(svg.contains(&format!(
+            "VALOR TOTAL DA NF-e: R$ {}",
+            money(&info.total.icms.total)
+        )));
+        assert_eq!(
+            svg.matches(&info.details[0].item.description).count(),
+            info.details.len()
+        );
+    }
+
+    #[test]
+    fn test_truncation() {
+        let nfe_proc = setup_simplified();
+        let details = nfe_proc.nfe.info.details.len();
+        let svg = SimplifiedDanfe::new()
+            .with_max_items(1)
+            .with_description_length(8)
+            .render(&nfe_proc)
+            .expect("Failed to render the DANFE");
+        assert!(svg.contains(&format!(
+            "... e mais {} item(ns) não listado(s)",
+            details - 1
+        )));
+        let description = &nfe_proc.nfe.info.details[0].item.description;
+        assert!(svg.contains(&truncate(description, 8)));
+        assert!(!svg.contains(description.as_str()));
+
+        let svg = SimplifiedDanfe::new()
+            .with_max_items(0)
+            .render(&nfe_proc)
+            .unwrap();
+        assert!(!svg.contains("DESCRIÇÃO"));
+    }
+
+    #[test]
+    fn test_rules() {
+        let mut nfe_proc = setup_nfe_proc();
+        assert_eq!(
+            SimplifiedDanfe::new().render(&nfe_proc),
+            Err(DanfeError::UnsupportedGeneration(Some(
+                DanfeGeneration::NFCe
+            )))
+        );
+        nfe_proc.nfe.info.identification.printing_type = Some(DanfeGeneration::Simplified);
+        nfe_proc.protocol.info.status = 302;
+        assert_eq!(
+            SimplifiedDanfe::new().render(&nfe_proc),
+            Err(DanfeError::NotAuthorized(302))
+        );
+    }
+
+    #[test]
+    fn test_formatting() {
+        assert_eq!(money(&Money::from(dec!(1234567.891))), "1.234.567,89");
+        assert_eq!(money(&Money::from(dec!(-12.5))), "-12,50");
+        assert_eq!(money(&Money::ZERO), "0,00");
+        assert_eq!(document_number(12345), "000.012.345");
+        assert_eq!(masked("12345678000195"), "12.345.678/0001-95");
+        assert_eq!(masked("12345678909"), "123.456.789-09");
+        assert_eq!(truncate("Produto de teste", 10), "Produto...");
+        assert_eq!(truncate("Produto", 10), "Produto");
+    }
+}
@@ -0,0 +1,236 @@
+//! Decimal values with the fixed number of decimal places required by the layout
+//!
+//! The values are kept as `rust_decimal::Decimal`, so the sums of the totals
+//! are exact, and are only rounded (half away from zero) when serialized.
+
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Mul, Sub};
+use std::str::FromStr;
+
+/// Decimal serialized with `PLACES` decimal places
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Fixed<const PLACES: u32>(pub Decimal);
+
+/// Monetary values and percentages, with 2 decimal places
+pub type Money = Fixed<2>;
+
+/// Quantities and unit values, with 4 decimal places
+pub type Quantity = Fixed<4>;
+
+/// Weights in kilograms, with 3 decimal places
+pub type Weight = Fixed<3>;
+
+impl<const PLACES: u32> Fixed<PLACES> {
+    pub const ZERO: Self = Fixed(Decimal::ZERO);
+
+    pub fn value(&self) -> Decimal {
+        self.0
+    }
+
+    /// Value rounded to the decimal places of the layout
+    pub fn rounded(&self) -> Decimal {
+        self.0
+            .round_dp_with_strategy(PLACES, RoundingStrategy::MidpointAwayFromZero)
+    }
+}
+
+impl<const PLACES: u32> From<Decimal> for Fixed<PLACES> {
+    fn from(value: Decimal) -> Self {
+        Fixed(value)
+    }
+}
+
+impl<const PLACES: u32> Display for Fixed<PLACES> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.*}", PLACES as usize, self.rounded())
+    }
+}
+
+impl<const PLACES: u32> FromStr for Fixed<PLACES> {
+    type Err = rust_decimal::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Decimal::from_str(s.trim()).map(Fixed)
+    }
+}
+
+impl<const PLACES: u32> Serialize for Fixed<PLACES> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de, const PLACES: u32> Deserialize<'de> for Fixed<PLACES> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s: String = Deserialize::deserialize(deserializer)?;
+        if crate::utils::is_lenient() && s.contains(',') {
+            // Brazilian format, with the comma as the decimal separator
+            return s
+                .replace('.', "")
+                .replace(',', ".")
+                .parse()
+                .map_err(serde::de::Error::custom);
+        }
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl<const PLACES: u32> Add for Fixed<PLACES> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Fixed(self.0 + rhs.0)
+    }
+}
+
+impl<const PLACES: u32> AddAssign for Fixed<PLACES> {
+    fn add_assign(&mut self, rhs: Self) {
+        self.0 += rhs.0;
+    }
+}
+
+impl<const PLACES: u32> Sub for Fixed<PLACES> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Fixed(self.0 - rhs.0)
+    }
+}
+
+impl<const PLACES: u32> Mul<Decimal> for Fixed<PLACES> {
+    type Output = Self;
+
+    fn mul(self, rhs: Decimal) -> Self::Output {
+        Fixed(self.0 * rhs)
+    }
+}
+
+impl<const PLACES: u32> Sum for Fixed<PLACES> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::ZERO, Add::add)
+    }
+}
+
+/// Serializes a plain `Decimal` like `Fixed<PLACES>`, see `nf_e_macros::nfe_decimal`
+pub fn serialize<const PLACES: u32, S>(value: &Decimal, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    Fixed::<PLACES>(*value).serialize(serializer)
+}
+
+/// Serializes an optional plain `Decimal` like `Option<Fixed<PLACES>>`
+pub fn serialize_option<const PLACES: u32, S>(
+    value: &Option<Decimal>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    value.map(Fixed::<PLACES>).serialize(serializer)
+}
+
+/// Parses a plain `Decimal` like `Fixed`
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Decimal, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Fixed::<0>::deserialize(deserializer).map(|value| value.0)
+}
+
+/// Parses an optional plain `Decimal` like `Option<Fixed>`
+pub fn deserialize_option<'de, D>(deserializer: D) -> Result<Option<Decimal>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Option::<Fixed<0>>::deserialize(deserializer).map(|value| value.map(|value| value.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_format_places() {
+        assert_eq!(Money::from(dec!(18)).to_string(), "18.00");
+        assert_eq!(Money::from(dec!(0.125)).to_string(), "0.13");
+        assert_eq!(Money::from(dec!(-0.125)).to_string(), "-0.13");
+        assert_eq!(Quantity::from(dec!(3)).to_string(), "3.0000");
+        assert_eq!(Quantity::from(dec!(1.23456)).to_string(), "1.2346");
+    }
+
+    #[test]
+    fn test_exact_sum() {
+        let total: Money = std::iter::repeat_n(Money::from(dec!(0.1)), 10).sum();
+        assert_eq!(total, Money::from(dec!(1)));
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            "113.94".parse::<Money>().unwrap(),
+            Money::from(dec!(113.94))
+        );
+        assert!("abc".parse::<Money>().is_err());
+    }
+
+    #[test]
+    fn test_lenient_comma() {
+        let xml = "<vNF>1.234,56</vNF>";
+        assert!(quick_xml::de::from_str::<Money>(xml).is_err());
+        assert_eq!(
+            crate::utils::lenient(|| quick_xml::de::from_str::<Money>(xml)).unwrap(),
+            Money::from(dec!(1234.56))
+        );
+    }
+
+    #[nf_e_macros::nfe_decimal]
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(rename = "prod")]
+    struct Product {
+        #[nfe_decimal(places = 10)]
+        #[serde(rename = "vUnCom")]
+        unit_value: Decimal,
+        #[nfe_decimal(places = 2)]
+        #[serde(rename = "vDesc")]
+        discount: Option<Decimal>,
+    }
+
+    #[test]
+    fn test_nfe_decimal() {
+        let product = Product {
+            unit_value: dec!(1.23456789),
+            discount: Some(dec!(0.125)),
+        };
+        let xml = quick_xml::se::to_string(&product).unwrap();
+        assert_eq!(
+            xml,
+            "<prod><vUnCom>1.2345678900</vUnCom><vDesc>0.13</vDesc></prod>"
+        );
+        assert_eq!(
+            quick_xml::de::from_str::<Product>(&xml).unwrap(),
+            Product {
+                unit_value: dec!(1.23456789),
+                discount: Some(dec!(0.13)),
+            }
+        );
+
+        let product = Product {
+            unit_value: dec!(5),
+            discount: None,
+        };
+        let xml = quick_xml::se::to_string(&product).unwrap();
+        assert_eq!(xml, "<prod><vUnCom>5.0000000000</vUnCom></prod>");
+        assert_eq!(quick_xml::de::from_str::<Product>(&xml).unwrap(), product);
+    }
+}
@@ -0,0 +1,392 @@
+//! Disablement of unused NF-e numbers (inutNFe)
+//!
+//! A range of numbers skipped by the issuer must be voided before the
+//! deadline, so the gap in the sequence is not treated as missing documents.
+
+use crate::enums::{CNPJ, Environment, Model};
+use crate::models::{NFE_NAMESPACE, Signature};
+#[cfg(feature = "sign")]
+use crate::sign::{Certificate, SignError, Signer, sign};
+use crate::states::State;
+use crate::xml::left_pad;
+#[cfg(feature = "sign")]
+use crate::xml::to_namespaced_xml;
+use serde::{Deserialize, Serialize, ser::SerializeStruct};
+
+pub const DISABLEMENT_VERSION: &str = "4.00";
+
+/// Highest number (nNF) of a document
+pub const MAX_NUMBER: u32 = 999_999_999;
+
+/// Highest series (serie) of a document
+pub const MAX_SERIES: u16 = 999;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DisablementError {
+    /// Length of the justification outside of 15..=255 characters
+    InvalidJustification(usize),
+    /// Range with a start after its end or outside of 1..=999999999
+    InvalidRange(u32, u32),
+    /// Series above 999
+    InvalidSeries(u16),
+    #[cfg(feature = "sign")]
+    Sign(SignError),
+}
+
+/// Information of the disablement (infInut)
+///
+/// environment: Environment type (tpAmb)
+/// state: State of the issuer (cUF)
+/// year: Last two digits of the year of the disablement (ano)
+/// cnpj: CNPJ of the issuer (CNPJ)
+/// model: Model of the documents (mod)
+/// series: Series of the documents (serie)
+/// start: First number of the range (nNFIni)
+/// end: Last number of the range (nNFFin)
+/// justification: Reason of the disablement, 15 to 255 characters (xJust)
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisablementInfo {
+    pub environment: Environment,
+    pub state: State,
+    pub year: u8,
+    pub cnpj: CNPJ,
+    pub model: Model,
+    pub series: u16,
+    pub start: u32,
+    pub end: u32,
+    pub justification: String,
+}
+
+impl DisablementInfo {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        environment: Environment,
+        state: State,
+        year: u8,
+        cnpj: CNPJ,
+        model: Model,
+        series: u16,
+        start: u32,
+        end: u32,
+        justification: &str,
+    ) -> Result<Self, DisablementError> {
+        if start == 0 || start > end || end > MAX_NUMBER {
+            return Err(DisablementError::InvalidRange(start, end));
+        }
+        if series > MAX_SERIES {
+            return Err(DisablementError::InvalidSeries(series));
+        }
+        let length = justification.trim().chars().count();
+        if !(15..=255).contains(&length) {
+            return Err(DisablementError::InvalidJustification(length));
+        }
+
+        Ok(Self {
+            environment,
+            state,
+            year: year % 100,
+            cnpj,
+            model,
+            series,
+            start,
+            end,
+            justification: justification.trim().to_string(),
+        })
+    }
+
+    /// Identifier of the disablement: "ID" + cUF + ano + CNPJ + mod + serie + nNFIni + nNFFin
+    pub fn id(&self) -> String {
+        let mut id = String::from("ID");
+        id.push_str(&self.state.code().to_string());
+        id.push_str(&format!("{:02}", self.year));
+        id.push_str(&left_pad(&self.cnpj.0, 14, '0'));
+        id.push_str(&self.model.code().to_string());
+        id.push_str(&left_pad(&self.series.to_string(), 3, '0'));
+        id.push_str(&left_pad(&self.start.to_string(), 9, '0'));
+        id.push_str(&left_pad(&self.end.to_string(), 9, '0'));
+        id
+    }
+}
+
+impl Serialize for DisablementInfo {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("infInut", 11)?;
+        state.serialize_field("@Id", &self.id())?;
+        state.serialize_field("tpAmb", &self.environment)?;
+        state.serialize_field("xServ", "INUTILIZAR")?;
+        state.serialize_field("cUF", &self.state.code())?;
+        state.serialize_field("ano", &format!("{:02}", self.year))?;
+        state.serialize_field("CNPJ", &self.cnpj)?;
+        state.serialize_field("mod", &self.model.code())?;
+        state.serialize_field("serie", &self.series)?;
+        state.serialize_field("nNFIni", &self.start)?;
+        state.serialize_field("nNFFin", &self.end)?;
+        state.serialize_field("xJust", &self.justification)?;
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for DisablementInfo {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct DisablementInfoHelper {
+            #[serde(rename = "tpAmb")]
+            environment: Environment,
+            #[serde(rename = "cUF")]
+            state: u8,
+            #[serde(rename = "ano")]
+            year: u8,
+            #[serde(rename = "CNPJ")]
+            cnpj: CNPJ,
+            #[serde(rename = "mod")]
+            model: u8,
+            #[serde(rename = "serie")]
+            series: u16,
+            #[serde(rename = "nNFIni")]
+            start: u32,
+            #[serde(rename = "nNFFin")]
+            end: u32,
+            #[serde(rename = "xJust")]
+            justification: String,
+        }
+
+        let helper = DisablementInfoHelper::deserialize(deserializer)?;
+        Ok(DisablementInfo {
+            environment: helper.environment,
+            state: State::try_from(helper.state).map_err(serde::de::Error::custom)?,
+            year: helper.year,
+            cnpj: helper.cnpj,
+            model: Model::try_from(helper.model).map_err(serde::de::Error::custom)?,
+            series: helper.series,
+            start: helper.start,
+            end: helper.end,
+            justification: helper.justification,
+        })
+    }
+}
+
+/// Signed disablement request (inutNFe)
+///
+/// info: Information of the disablement (infInut)
+/// signature: Enveloped signature of `info` (Signature)
+#[derive(Debug, PartialEq)]
+pub struct Disablement {
+    pub info: DisablementInfo,
+    pub signature: Signature,
+}
+
+#[cfg(feature = "sign")]
+impl Disablement {
+    /// Signs the request with the certificate of the global configuration
+    pub fn new(info: DisablementInfo) -> Result<Self, DisablementError> {
+        let certificate = Certificate::from_config().map_err(DisablementError::Sign)?;
+        Self::with_certificate(info, &certificate).map_err(DisablementError::Sign)
+    }
+
+    pub fn with_certificate(
+        info: DisablementInfo,
+        certificate: &Certificate,
+    ) -> Result<Self, SignError> {
+        Self::with_signer(info, certificate)
+    }
+
+    /// Signs with the key of `signer`, as the one of an A3 token
+    pub fn with_signer(info: DisablementInfo, signer: &dyn Signer) -> Result<Self, SignError> {
+        let element = to_namespaced_xml("infInut", NFE_NAMESPACE, &info)
+            .map_err(|e| SignError::Serialization(e.to_string()))?;
+        let signature = sign(&element, &info.id(), signer)?;
+        Ok(Self { info, signature })
+    }
+}
+
+impl Serialize for Disablement {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("inutNFe", 4)?;
+        state.serialize_field("@xmlns", NFE_NAMESPACE)?;
+        state.serialize_field("@versao", DISABLEMENT_VERSION)?;
+        state.serialize_field("infInut", &self.info)?;
+        state.serialize_field("Signature", &self.signature)?;
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Disablement {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct DisablementHelper {
+            #[serde(rename = "infInut")]
+            info: DisablementInfo,
+            #[serde(rename = "Signature")]
+            signature: Signature,
+        }
+
+        let helper = DisablementHelper::deserialize(deserializer)?;
+        Ok(Disablement {
+            info: helper.info,
+            signature: helper.signature,
+        })
+    }
+}
+
+/// Result of the disablement (infInut of retInutNFe)
+///
+/// id: Identifier of the result (@Id) - Optional
+/// environment: Environment type (tpAmb)
+/// application_version: Version of the SEFAZ application (verAplic)
+/// status: Status code (cStat)
+/// reason: Status description (xMotivo)
+/// state_code: IBGE code of the state that answered (cUF)
+/// year: Year of the disablement (ano) - Optional
+/// cnpj: CNPJ of the issuer (CNPJ) - Optional
+/// model: Model of the documents (mod) - Optional
+/// series: Series of the documents (serie) - Optional
+/// start: First number of the range (nNFIni) - Optional
+/// end: Last number of the range (nNFFin) - Optional
+/// received_at: Date and time of the processing (dhRecbto)
+/// protocol: Protocol of the disablement (nProt) - Only when homologated
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct DisablementResultInfo {
+    #[serde(rename = "@Id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(rename = "tpAmb")]
+    pub environment: Environment,
+    #[serde(rename = "verAplic")]
+    pub application_version: String,
+    #[serde(rename = "cStat")]
+    pub status: u16,
+    #[serde(rename = "xMotivo")]
+    pub reason: String,
+    #[serde(rename = "cUF")]
+    pub state_code: u8,
+    #[serde(rename = "ano", skip_serializing_if = "Option::is_none")]
+    pub year: Option<u8>,
+    #[serde(rename = "CNPJ", skip_serializing_if = "Option::is_none")]
+    pub cnpj: Option<CNPJ>,
+    #[serde(rename = "mod", skip_serializing_if = "Option::is_none")]
+    pub model: Option<u8>,
+    #[serde(rename = "serie", skip_serializing_if = "Option::is_none")]
+    pub series: Option<u16>,
+    #[serde(rename = "nNFIni", skip_serializing_if = "Option::is_none")]
+    pub start: Option<u32>,
+    #[serde(rename = "nNFFin", skip_serializing_if = "Option::is_none")]
+    pub end: Option<u32>,
+    #[serde(rename = "dhRecbto")]
+    pub received_at: chrono::DateTime<chrono::FixedOffset>,
+    #[serde(rename = "nProt", skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<String>,
+}
+
+impl DisablementResultInfo {
+    /// Disablement of the numbers homologated (102)
+    pub fn is_homologated(&self) -> bool {
+        self.status == 102
+    }
+}
+
+/// Response of the disablement webservice (retInutNFe)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct DisablementResponse {
+    #[serde(rename = "@versao")]
+    pub version: String,
+    #[serde(rename = "infInut")]
+    pub info: DisablementResultInfo,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "sign")]
+    use crate::sign::tests::setup_certificate;
+    use crate::xml::{indent_xml, normalize_xml as canonicalize};
+    use nf_e_macros::serialization_test;
+    use quick_xml::{de::from_str as deserialize, se::to_string as serialize};
+
+    #[serialization_test(
+        fixture = "../tests/fixtures/disablement/info.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_disablement_info() -> DisablementInfo {
+        DisablementInfo::new(
+            Environment::Homologation,
+            State::SaoPaulo,
+            25,
+            CNPJ("12345678000195".to_string()),
+            Model::NFe,
+            1,
+            10,
+            15,
+            "Falha no sistema de emissao",
+        )
+        .expect("Failed to create disablement")
+    }
+
+    #[test]
+    fn test_disablement_id() {
+        assert_eq!(
+            setup_disablement_info().id(),
+            "ID35251234567800019555001000000010000000015"
+        );
+    }
+
+    #[test]
+    fn test_invalid_disablement() {
+        let new = |series, start, end, justification| {
+            DisablementInfo::new(
+                Environment::Homologation,
+                State::SaoPaulo,
+                25,
+                CNPJ("12345678000195".to_string()),
+                Model::NFe,
+                series,
+                start,
+                end,
+                justification,
+            )
+        };
+
+        assert_eq!(
+            new(1, 15, 10, "Falha no sistema de emissao"),
+            Err(DisablementError::InvalidRange(15, 10))
+        );
+        assert_eq!(
+            new(1, 0, 10, "Falha no sistema de emissao"),
+            Err(DisablementError::InvalidRange(0, 10))
+        );
+        assert_eq!(
+            new(1000, 1, 10, "Falha no sistema de emissao"),
+            Err(DisablementError::InvalidSeries(1000))
+        );
+        assert_eq!(
+            new(1, 1, 10, "Falha"),
+            Err(DisablementError::InvalidJustification(5))
+        );
+    }
+
+    #[cfg(feature = "sign")]
+    #[test]
+    fn test_sign_disablement() {
+        let disablement =
+            Disablement::with_certificate(setup_disablement_info(), &setup_certificate())
+                .expect("Failed to sign disablement");
+        assert_eq!(
+            disablement.signature.info.reference.uri,
+            "#ID35251234567800019555001000000010000000015"
+        );
+
+        let serialized = serialize(&disablement).expect("Failed to serialize");
+        let deserialized: Disablement = deserialize(&serialized).expect("Failed to deserialize");
+        assert_eq!(deserialized, disablement);
+    }
+}
@@ -0,0 +1,425 @@
+//! Distribution of the documents of interest of an issuer (distDFeInt)
+//!
+//! The Ambiente Nacional returns up to 50 documents per request, each one
+//! identified by its NSU and compressed with gzip in a base64 `docZip`.
+
+use crate::enums::{Environment, PersonDocument};
+use crate::events::{EventProc, EventType};
+use crate::models::{NFE_NAMESPACE, NFeProc};
+use crate::states::State;
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use chrono::{DateTime, FixedOffset};
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize, ser::SerializeStruct};
+use std::io::Read;
+
+pub const DISTRIBUTION_VERSION: &str = "1.01";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DistributionError {
+    Base64(String),
+    Gzip(String),
+    Deserialization(String),
+}
+
+/// Serde helpers for the 15 digits NSU
+mod nsu {
+    use crate::xml::left_pad;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(nsu: &u64, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&left_pad(&nsu.to_string(), 15, '0'))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<u64, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.trim().parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Documents requested from the distribution
+#[derive(Debug, Clone, PartialEq)]
+pub enum DistributionKind {
+    /// Documents after the last NSU already received (distNSU/ultNSU)
+    LastNSU(u64),
+    /// Single document by its NSU (consNSU/NSU)
+    NSU(u64),
+    /// Documents of an access key (consChNFe/chNFe)
+    AccessKey(String),
+}
+
+/// Query of the documents of interest (distDFeInt)
+///
+/// environment: Environment type (tpAmb)
+/// state: State of the interested party (cUFAutor)
+/// interested: Document of the interested party (CNPJ or CPF)
+/// kind: Documents requested (distNSU, consNSU or consChNFe)
+#[derive(Debug, Clone, PartialEq)]
+pub struct DistributionQuery {
+    pub environment: Environment,
+    pub state: State,
+    pub interested: PersonDocument,
+    pub kind: DistributionKind,
+}
+
+impl Serialize for DistributionQuery {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        #[derive(Serialize)]
+        struct DistNSU {
+            #[serde(rename = "ultNSU", with = "nsu")]
+            nsu: u64,
+        }
+
+        #[derive(Serialize)]
+        struct ConsNSU {
+            #[serde(rename = "NSU", with = "nsu")]
+            nsu: u64,
+        }
+
+        #[derive(Serialize)]
+        struct AccessKey<'a> {
+            #[serde(rename = "chNFe")]
+            access_key: &'a str,
+        }
+
+        let mut state = serializer.serialize_struct("distDFeInt", 6)?;
+        state.serialize_field("@xmlns", NFE_NAMESPACE)?;
+        state.serialize_field("@versao", DISTRIBUTION_VERSION)?;
+        state.serialize_field("tpAmb", &self.environment)?;
+        state.serialize_field("cUFAutor", &self.state.code())?;
+        state.serialize_field("$value", &self.interested)?;
+        match &self.kind {
+            DistributionKind::LastNSU(nsu) => {
+                state.serialize_field("distNSU", &DistNSU { nsu: *nsu })?
+            }
+            DistributionKind::NSU(nsu) => {
+                state.serialize_field("consNSU", &ConsNSU { nsu: *nsu })?
+            }
+            DistributionKind::AccessKey(access_key) => {
+                state.serialize_field("consChNFe", &AccessKey { access_key })?
+            }
+        }
+        state.end()
+    }
+}
+
+/// Summary of a document of interest (resNFe)
+///
+/// access_key: Access key of the NF-e (chNFe)
+/// issuer: Document of the issuer (CNPJ or CPF)
+/// name: Name of the issuer (xNome)
+/// ie: State registration of the issuer (IE)
+/// emission_date: Date and time of emission (dhEmi)
+/// operation: Type of operation (tpNF)
+/// total: Total value of the NF-e (vNF)
+/// digest: Digest of the NF-e (digVal) - Optional
+/// received_at: Date and time of the authorization (dhRecbto)
+/// protocol: Authorization protocol (nProt)
+/// situation: Situation of the NF-e, 1 authorized, 2 denied or 3 cancelled (cSitNFe)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct NFeSummary {
+    #[serde(rename = "chNFe")]
+    pub access_key: String,
+    #[serde(rename = "$value")]
+    pub issuer: PersonDocument,
+    #[serde(rename = "xNome")]
+    pub name: String,
+    #[serde(rename = "IE")]
+    pub ie: String,
+    #[serde(rename = "dhEmi")]
+    pub emission_date: DateTime<FixedOffset>,
+    #[serde(rename = "tpNF")]
+    pub operation: u8,
+    #[serde(rename = "vNF")]
+    pub total: crate::decimal::Money,
+    #[serde(rename = "digVal", skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+    #[serde(rename = "dhRecbto")]
+    pub received_at: DateTime<FixedOffset>,
+    #[serde(rename = "nProt")]
+    pub protocol: String,
+    #[serde(rename = "cSitNFe")]
+    pub situation: u8,
+}
+
+/// Summary of an event of interest (resEvento)
+///
+/// organ: IBGE code of the organ of the event (cOrgao)
+/// author: Document of the author of the event (CNPJ or CPF)
+/// access_key: Access key of the NF-e (chNFe)
+/// date: Date and time of the event (dhEvento)
+/// event_type: Code of the type of the event (tpEvento)
+/// sequence: Sequence of the event (nSeqEvento)
+/// description: Description of the event (xEvento)
+/// received_at: Date and time of the registration (dhRecbto)
+/// protocol: Registration protocol (nProt)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct EventSummary {
+    #[serde(rename = "cOrgao")]
+    pub organ: u8,
+    #[serde(rename = "$value")]
+    pub author: PersonDocument,
+    #[serde(rename = "chNFe")]
+    pub access_key: String,
+    #[serde(rename = "dhEvento")]
+    pub date: DateTime<FixedOffset>,
+    #[serde(rename = "tpEvento")]
+    pub event_type: u32,
+    #[serde(rename = "nSeqEvento")]
+    pub sequence: u8,
+    #[serde(rename = "xEvento")]
+    pub description: String,
+    #[serde(rename = "dhRecbto")]
+    pub received_at: DateTime<FixedOffset>,
+    #[serde(rename = "nProt")]
+    pub protocol: String,
+}
+
+impl EventSummary {
+    /// Type of the event, when supported by the events module
+    pub fn known_type(&self) -> Option<EventType> {
+        Some(EventType::from(self.event_type)).filter(EventType::is_known)
+    }
+}
+
+/// Decompressed document of the distribution
+#[derive(Debug, PartialEq)]
+pub enum DistributedDocument {
+    /// Authorized NF-e with its protocol (procNFe)
+    Proc(Box<NFeProc>),
+    /// Summary of an NF-e (resNFe)
+    Summary(NFeSummary),
+    /// Event with its registration (procEventoNFe), with the payload of the
+    /// types not known by the crate read as `EventDetail::Other`
+    Event(Box<EventProc>),
+    /// Summary of an event (resEvento)
+    EventSummary(EventSummary),
+    /// Document of an unsupported schema, as XML
+    Other(String),
+}
+
+/// Compressed document (docZip)
+///
+/// nsu: Unique sequential number of the document (@NSU)
+/// schema: XSD of the document, e.g. resNFe_v1.01.xsd (@schema)
+/// content: Base64 encoded gzip of the document ($text)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct DocZip {
+    #[serde(rename = "@NSU", with = "nsu")]
+    pub nsu: u64,
+    #[serde(rename = "@schema")]
+    pub schema: String,
+    #[serde(rename = "$text")]
+    pub content: String,
+}
+
+impl DocZip {
+    /// XML of the document
+    pub fn decompress(&self) -> Result<String, DistributionError> {
+        let data = BASE64
+            .decode(self.content.trim())
+            .map_err(|e| DistributionError::Base64(e.to_string()))?;
+        let mut xml = String::new();
+        GzDecoder::new(data.as_slice())
+            .read_to_string(&mut xml)
+            .map_err(|e| DistributionError::Gzip(e.to_string()))?;
+        Ok(xml)
+    }
+
+    /// Decompresses and deserializes the document according to its schema
+    pub fn document(&self) -> Result<DistributedDocument, DistributionError> {
+        fn parse<'a, T: Deserialize<'a>>(xml: &'a str) -> Result<T, DistributionError> {
+            quick_xml::de::from_str(xml)
+                .map_err(|e| DistributionError::Deserialization(e.to_string()))
+        }
+
+        let xml = self.decompress()?;
+        let name = self.schema.split('_').next().unwrap_or_default();
+        Ok(match name {
+            "procNFe" => DistributedDocument::Proc(Box::new(parse(&xml)?)),
+            "resNFe" => DistributedDocument::Summary(parse(&xml)?),
+            "procEventoNFe" => DistributedDocument::Event(Box::new(parse(&xml)?)),
+            "resEvento" => DistributedDocument::EventSummary(parse(&xml)?),
+            _ => DistributedDocument::Other(xml),
+        })
+    }
+}
+
+/// Documents returned by the distribution (loteDistDFeInt)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct DocumentLot {
+    #[serde(rename = "docZip", default)]
+    pub documents: Vec<DocZip>,
+}
+
+/// Response of the distribution webservice (retDistDFeInt)
+///
+/// version: Layout version (@versao)
+/// environment: Environment type (tpAmb)
+/// application_version: Version of the SEFAZ application (verAplic)
+/// status: Status code (cStat)
+/// reason: Status description (xMotivo)
+/// responded_at: Date and time of the response (dhResp)
+/// last_nsu: Last NSU returned, to be used in the next query (ultNSU)
+/// max_nsu: Highest NSU available for the interested party (maxNSU)
+/// lot: Documents found (loteDistDFeInt) - Only when documents were found
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct DistributionResponse {
+    #[serde(rename = "@versao")]
+    pub version: String,
+    #[serde(rename = "tpAmb")]
+    pub environment: Environment,
+    #[serde(rename = "verAplic")]
+    pub application_version: String,
+    #[serde(rename = "cStat")]
+    pub status: u16,
+    #[serde(rename = "xMotivo")]
+    pub reason: String,
+    #[serde(rename = "dhResp")]
+    pub responded_at: DateTime<FixedOffset>,
+    #[serde(rename = "ultNSU", with = "nsu")]
+    pub last_nsu: u64,
+    #[serde(rename = "maxNSU", with = "nsu")]
+    pub max_nsu: u64,
+    #[serde(rename = "loteDistDFeInt", skip_serializing_if = "Option::is_none")]
+    pub lot: Option<DocumentLot>,
+}
+
+impl DistributionResponse {
+    pub fn documents(&self) -> &[DocZip] {
+        self.lot.as_ref().map_or(&[], |lot| &lot.documents)
+    }
+
+    /// Whether there are documents after `last_nsu`
+    pub fn has_more(&self) -> bool {
+        self.last_nsu < self.max_nsu
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::CNPJ;
+    use crate::events::EventDetail;
+    use flate2::{Compression, write::GzEncoder};
+    use std::io::Write;
+
+    fn compress(xml: &str) -> String {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(xml.as_bytes()).unwrap();
+        BASE64.encode(encoder.finish().unwrap())
+    }
+
+    #[test]
+    fn test_query() {
+        let query = DistributionQuery {
+            environment: Environment::Production,
+            state: State::SaoPaulo,
+            interested: PersonDocument::CNPJ(CNPJ("12345678000195".to_string())),
+            kind: DistributionKind::LastNSU(42),
+        };
+        let expected = concat!(
+            r#"<distDFeInt xmlns="http://www.portalfiscal.inf.br/nfe" versao="1.01">"#,
+            "<tpAmb>1</tpAmb><cUFAutor>35</cUFAutor><CNPJ>12345678000195</CNPJ>",
+            "<distNSU><ultNSU>000000000000042</ultNSU></distNSU></distDFeInt>"
+        );
+        assert_eq!(
+            quick_xml::se::to_string_with_root("distDFeInt", &query).unwrap(),
+            expected
+        );
+
+        let query = DistributionQuery {
+            kind: DistributionKind::AccessKey(
+                "35250812345678000195550010000000011000000019".to_string(),
+            ),
+            ..query
+        };
+        assert!(
+            quick_xml::se::to_string_with_root("distDFeInt", &query)
+                .unwrap()
+                .contains(
+                    "<consChNFe><chNFe>35250812345678000195550010000000011000000019</chNFe></consChNFe>"
+                )
+        );
+    }
+
+    #[test]
+    fn test_summary_document() {
+        let doc = DocZip {
+            nsu: 43,
+            schema: "resNFe_v1.01.xsd".to_string(),
+            content: compress(include_str!("../tests/fixtures/distribution/res_nfe.xml")),
+        };
+
+        match doc.document().expect("Failed to read document") {
+            DistributedDocument::Summary(summary) => {
+                assert_eq!(
+                    summary.access_key,
+                    "35250898765432000198550010000000071000000075"
+                );
+                assert_eq!(summary.total.to_string(), "1500.00");
+                assert_eq!(summary.situation, 1);
+            }
+            document => panic!("Unexpected document {:?}", document),
+        }
+    }
+
+    #[test]
+    fn test_event_document() {
+        let xml = include_str!("../tests/fixtures/events/cancel_proc.xml");
+        let doc = |xml: &str| DocZip {
+            nsu: 46,
+            schema: "procEventoNFe_v1.00.xsd".to_string(),
+            content: compress(xml),
+        };
+
+        match doc(xml).document().expect("Failed to read document") {
+            DistributedDocument::Event(proc) => {
+                assert_eq!(proc.event.info.event_type(), EventType::Cancellation);
+            }
+            document => panic!("Unexpected document {:?}", document),
+        }
+
+        // Events registered by SEFAZ of types not known by the crate
+        let xml = xml.replace("110111", "610600");
+        match doc(&xml).document().expect("Failed to read document") {
+            DistributedDocument::Event(proc) => {
+                assert_eq!(proc.event.info.event_type(), EventType::Other(610600));
+                assert!(matches!(proc.event.info.detail, EventDetail::Other(_)));
+            }
+            document => panic!("Unexpected document {:?}", document),
+        }
+    }
+
+    #[test]
+    fn test_unknown_schema() {
+        let doc = DocZip {
+            nsu: 44,
+            schema: "procCTe_v3.00.xsd".to_string(),
+            content: compress("<cteProc/>"),
+        };
+        assert_eq!(
+            doc.document(),
+            Ok(DistributedDocument::Other("<cteProc/>".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_invalid_content() {
+        let doc = DocZip {
+            nsu: 45,
+            schema: "resNFe_v1.01.xsd".to_string(),
+            content: BASE64.encode("not gzip"),
+        };
+        assert!(matches!(doc.decompress(), Err(DistributionError::Gzip(_))));
+    }
+}
@@ -0,0 +1,408 @@
+//! Issue of documents in a single call
+//!
+//! The `Emitter` ties the configuration of the issuer, the builder, the
+//! signature, the QR Code of the NFC-e and the client: the document is built,
+//! signed and sent, falling back to the contingency when SEFAZ is unreachable.
+//! With a `DocumentRegistry`, the keys already issued are never sent again.
+
+use crate::access_key::AccessKey;
+use crate::config::Config;
+use crate::contingency;
+use crate::enums::Model;
+use crate::models::{
+    Contingency, Detail, Identification, Info, InfoBuilder, InfoBuilderError, NFe, NFeProc,
+    Payments,
+};
+use crate::qr_code::{CSC, QRCodeError};
+use crate::queue::{FlushReport, Queue, QueueError, QueueStorage};
+use crate::registry::{DocumentRegistry, DocumentStatus, RegistryError};
+use crate::sign::SignError;
+use crate::soap::rejection::{RejectionCategory, RejectionCode};
+use crate::soap::{AuthorizationBatch, Client, ClientError, retry};
+use tokio::sync::Mutex;
+
+/// Justification of the contingencies entered by the emitter (xJust)
+pub const DEFAULT_JUSTIFICATION: &str = "Falha de comunicacao com a SEFAZ";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EmitterError {
+    Build(InfoBuilderError),
+    Sign(SignError),
+    QRCode(QRCodeError),
+    /// NFC-e issued without the CSC of the issuer
+    MissingCSC,
+    Client(ClientError),
+    Queue(QueueError),
+    Registry(RegistryError),
+    /// Key of the document already issued, with its situation in the registry,
+    /// sending it again would be rejected as a duplicity (204)
+    AlreadyIssued(AccessKey, DocumentStatus),
+    /// Status code (cStat) and reason (xMotivo) of the rejection of the document
+    Rejected(RejectionCode, String),
+}
+
+impl From<ClientError> for EmitterError {
+    fn from(error: ClientError) -> Self {
+        match error {
+            ClientError::Rejected(status, reason) => EmitterError::Rejected(status, reason),
+            error => EmitterError::Client(error),
+        }
+    }
+}
+
+/// Document issued by the emitter
+#[derive(Debug, PartialEq)]
+pub enum Emission {
+    /// Document authorized by SEFAZ, with its protocol
+    Authorized(NFeProc),
+    /// NFC-e issued offline (tpEmis = 9), waiting in the queue for transmission
+    Offline(NFe),
+}
+
+/// Issuer of documents for a configuration
+///
+/// NF-e that can not be authorized by the state fall back to its virtual
+/// contingency authorizer (SVC). NFC-e fall back to the offline contingency:
+/// they are issued again with tpEmis 9, pushed to the queue of the emitter and
+/// returned for the consumer, to be transmitted by `flush`.
+pub struct Emitter {
+    config: Config,
+    client: Client,
+    csc: Option<CSC>,
+    queue: Option<Mutex<Queue<Box<dyn QueueStorage + Send>>>>,
+    registry: Option<Box<dyn DocumentRegistry + Send + Sync>>,
+    justification: String,
+}
+
+impl Emitter {
+    /// Emitter of the issuer of `config`, with a client authenticating with its certificate
+    pub fn new(config: Config) -> Result<Self, EmitterError> {
+        let client = Client::with_config(&config)?;
+        Ok(Self::with_client(config, client))
+    }
+
+    pub fn with_client(config: Config, client: Client) -> Self {
+        Emitter {
+            config,
+            client,
+            csc: None,
+            queue: None,
+            registry: None,
+            justification: DEFAULT_JUSTIFICATION.to_string(),
+        }
+    }
+
+    /// CSC of the QR Code of the NFC-e, instead of the ones of the configuration
+    pub fn with_csc(mut self, csc: CSC) -> Self {
+        self.csc = Some(csc);
+        self
+    }
+
+    /// Storage of the NFC-e issued offline
+    pub fn with_queue(mut self, storage: impl QueueStorage + Send + 'static) -> Self {
+        let storage: Box<dyn QueueStorage + Send> = Box::new(storage);
+        self.queue = Some(Mutex::new(Queue::new(storage)));
+        self
+    }
+
+    /// Registry of the keys issued, checked before each transmission and
+    /// updated with its outcome
+    pub fn with_registry(
+        mut self,
+        registry: impl DocumentRegistry + Send + Sync + 'static,
+    ) -> Self {
+        self.registry = Some(Box::new(registry));
+        self
+    }
+
+    /// Justification of the contingencies entered when SEFAZ is unreachable
+    pub fn with_justification(mut self, justification: &str) -> Self {
+        self.justification = justification.to_string();
+        self
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Builds, signs and sends the document
+    pub async fn issue(
+        &self,
+        identification: Identification,
+        details: Vec<Detail>,
+        payments: Payments,
+    ) -> Result<Emission, EmitterError> {
+        let builder = details.into_iter().fold(
+            InfoBuilder::new(&self.config, identification, payments),
+            InfoBuilder::add_detail,
+        );
+        self.issue_with(builder).await
+    }
+
+    /// Signs and sends the document of the builder, for the documents with
+    /// recipient, transport and the other optional groups
+    ///
+    /// Fails with `EmitterError::AlreadyIssued`, before sending anything, when
+    /// the registry has the key of the document as issued.
+    pub async fn issue_with(&self, builder: InfoBuilder) -> Result<Emission, EmitterError> {
+        let info = builder.build().map_err(EmitterError::Build)?;
+        let nfe = self.sign(info)?;
+        let access_key = nfe.info.access_key();
+        if let Some(status) = self.status(&access_key)?
+            && status.is_issued()
+        {
+            return Err(EmitterError::AlreadyIssued(access_key, status));
+        }
+
+        let identification = &nfe.info.identification;
+        if identification.contingency.is_some() && identification.model == Model::NFCe {
+            self.record(&access_key, DocumentStatus::Draft)?;
+            return self.enqueue(nfe).await;
+        }
+
+        self.record(&access_key, DocumentStatus::Transmitted)?;
+        let emission = self.transmit(nfe).await;
+        match &emission {
+            // The key changes when the document goes to a contingency
+            Ok(Emission::Authorized(nfe_proc)) => {
+                self.record(&nfe_proc.nfe.info.access_key(), DocumentStatus::Authorized)?
+            }
+            Ok(Emission::Offline(nfe)) => {
+                self.record(&nfe.info.access_key(), DocumentStatus::Draft)?
+            }
+            Err(EmitterError::Rejected(code, _)) => {
+                self.record(&access_key, rejection_status(code))?
+            }
+            // Without an answer, the key stays as transmitted
+            Err(_) => {}
+        }
+        emission
+    }
+
+    /// Sends the signed document, falling back to the contingencies
+    async fn transmit(&self, nfe: NFe) -> Result<Emission, EmitterError> {
+        let identification = &nfe.info.identification;
+        let id = identification.number.into();
+        if identification.model == Model::NFe {
+            let certificate = self.config.certificate().map_err(EmitterError::Sign)?;
+            let authorization = self
+                .client
+                .authorize_with_failover(id, nfe, &certificate, &self.justification)
+                .await?;
+            let protocol = authorization.response.into_protocol()?;
+            return Ok(Emission::Authorized(NFeProc::new(
+                authorization.nfe,
+                protocol,
+            )));
+        }
+
+        let mut batch = AuthorizationBatch::single(id, nfe);
+        let result = self.client.authorize(&batch).await;
+        let nfe = batch.documents.remove(0);
+        match result {
+            Ok(response) => Ok(Emission::Authorized(NFeProc::new(
+                nfe,
+                response.into_protocol()?,
+            ))),
+            Err(error) if retry::is_transient(&error) => {
+                let contingency =
+                    Contingency::new(self.config.clock().local(), &self.justification);
+                let nfe = self.sign(contingency::offline(nfe.info, contingency))?;
+                self.enqueue(nfe).await
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Transmits the NFC-e issued offline, see `Queue::flush`
+    pub async fn flush(&self, first_batch_id: u64) -> Result<Option<FlushReport>, EmitterError> {
+        let Some(queue) = &self.queue else {
+            return Ok(None);
+        };
+        let mut queue = queue.lock().await;
+        let report = queue
+            .flush(&self.client, first_batch_id)
+            .await
+            .map_err(EmitterError::Queue)?;
+        for access_key in &report.authorized {
+            self.record(access_key, DocumentStatus::Authorized)?;
+        }
+        for access_key in &report.denied {
+            self.record(access_key, DocumentStatus::Denied)?;
+        }
+        Ok(Some(report))
+    }
+
+    fn status(&self, access_key: &AccessKey) -> Result<Option<DocumentStatus>, EmitterError> {
+        let Some(registry) = &self.registry else {
+            return Ok(None);
+        };
+        registry.status(access_key).map_err(EmitterError::Registry)
+    }
+
+    fn record(&self, access_key: &AccessKey, status: DocumentStatus) -> Result<(), EmitterError> {
+        let Some(registry) = &self.registry else {
+            return Ok(());
+        };
+        registry
+            .record(access_key, status)
+            .map_err(EmitterError::Registry)
+    }
+
+    /// Signs the document, adding the QR Code of the NFC-e
+    fn sign(&self, info: Info) -> Result<NFe, EmitterError> {
+        let nfce = info.identification.model == Model::NFCe;
+        let nfe = NFe::with_config(info, &self.config).map_err(EmitterError::Sign)?;
+        if !nfce {
+            return Ok(nfe);
+        }
+        let environment = &nfe.info.identification.environment;
+        let csc = self
+            .csc
+            .as_ref()
+            .or_else(|| self.config.csc(environment))
+            .ok_or(EmitterError::MissingCSC)?
+            .clone();
+        nfe.with_qr_code(&csc).map_err(EmitterError::QRCode)
+    }
+
+    async fn enqueue(&self, nfe: NFe) -> Result<Emission, EmitterError> {
+        if let Some(queue) = &self.queue {
+            queue.lock().await.push(&nfe).map_err(EmitterError::Queue)?;
+        }
+        Ok(Emission::Offline(nfe))
+    }
+}
+
+/// Situation of the key of a document rejected with `code`
+///
+/// A duplicity (204) means the key was already authorized, and a denial uses
+/// the key. Any other rejection leaves the key free for the corrected document.
+pub(crate) fn rejection_status(code: &RejectionCode) -> DocumentStatus {
+    match code {
+        RejectionCode::DuplicateNFe => DocumentStatus::Authorized,
+        code if code.category() == RejectionCategory::UseDenied => DocumentStatus::Denied,
+        _ => DocumentStatus::Draft,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::Environment;
+    use crate::models::tests::{setup_config, setup_detail, setup_identification, setup_payments};
+    use crate::queue::{FileStorage, QueueStatus};
+    use crate::registry::MemoryRegistry;
+    use crate::sign::tests::setup_certificate;
+
+    fn block_on<T>(future: impl Future<Output = T>) -> T {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    /// Configuration of the environment of `setup_identification`
+    fn setup_production_config() -> Config {
+        setup_config().with_environment(Environment::Production)
+    }
+
+    fn setup_emitter(config: Config) -> Emitter {
+        let client = Client::new(&setup_certificate()).unwrap();
+        Emitter::with_client(config, client)
+    }
+
+    #[test]
+    fn test_missing_csc() {
+        let emitter = setup_emitter(setup_production_config());
+        let result = block_on(emitter.issue(
+            setup_identification(),
+            vec![setup_detail(), setup_detail()],
+            setup_payments(),
+        ));
+        assert_eq!(result, Err(EmitterError::MissingCSC));
+    }
+
+    #[test]
+    fn test_offline_emission() {
+        let directory =
+            std::env::temp_dir().join(format!("nf-e-emitter-{}", rand::random::<u32>()));
+        let contingency = Contingency::new(chrono::Local::now(), DEFAULT_JUSTIFICATION);
+        let emitter = setup_emitter(setup_production_config().with_contingency(contingency))
+            .with_csc(CSC::new(1, "0123456789ABCDEF".to_string()))
+            .with_queue(FileStorage::new(&directory).unwrap());
+
+        let emission = block_on(emitter.issue(
+            setup_identification(),
+            vec![setup_detail(), setup_detail()],
+            setup_payments(),
+        ))
+        .expect("Failed to issue the NFC-e");
+        let Emission::Offline(nfe) = emission else {
+            panic!("NFC-e not issued offline");
+        };
+        assert_eq!(
+            nfe.info.identification.emission_type,
+            crate::enums::EmissionType::Offline
+        );
+        assert!(nfe.supplement.is_some());
+
+        let storage = FileStorage::new(&directory).unwrap();
+        assert_eq!(
+            storage.status(&nfe.info.access_key()).unwrap(),
+            Some(QueueStatus::Pending)
+        );
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_already_issued() {
+        let config = setup_production_config();
+        let access_key = InfoBuilder::new(&config, setup_identification(), setup_payments())
+            .add_detail(setup_detail())
+            .add_detail(setup_detail())
+            .build()
+            .unwrap()
+            .access_key();
+        let registry = MemoryRegistry::new();
+        registry
+            .record(&access_key, DocumentStatus::Authorized)
+            .unwrap();
+        let emitter = setup_emitter(config)
+            .with_csc(CSC::new(1, "0123456789ABCDEF".to_string()))
+            .with_registry(registry);
+
+        let result = block_on(emitter.issue(
+            setup_identification(),
+            vec![setup_detail(), setup_detail()],
+            setup_payments(),
+        ));
+        assert_eq!(
+            result,
+            Err(EmitterError::AlreadyIssued(
+                access_key,
+                DocumentStatus::Authorized
+            ))
+        );
+    }
+
+    #[test]
+    fn test_rejection_status() {
+        assert_eq!(
+            rejection_status(&RejectionCode::DuplicateNFe),
+            DocumentStatus::Authorized
+        );
+        assert_eq!(
+            rejection_status(&RejectionCode::IssuerIrregular),
+            DocumentStatus::Denied
+        );
+        assert_eq!(
+            rejection_status(&RejectionCode::SchemaFailure),
+            DocumentStatus::Draft
+        );
+    }
+}
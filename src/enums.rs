@@ -1,104 +1,53 @@
-use std::fmt::Display;
-use crate::models::ICMSSN102;
-use crate::utils::left_pad;
+use crate::decimal::Money;
+use crate::models::{
+    COFINSAliq, COFINSNT, COFINSOutr, COFINSQtde, ICMS00, ICMS10, ICMS20, ICMS30, ICMS40, ICMS51,
+    ICMS60, ICMS70, ICMS90, ICMSPart, ICMSSN101, ICMSSN102, ICMSSN201, ICMSSN202, ICMSSN500,
+    ICMSSN900, ICMSST, PISAliq, PISNT, PISOutr, PISQtde,
+};
+use nf_e_macros::CodedEnum;
 use serde::ser::SerializeStruct;
 use serde::{Deserialize, Serialize, Serializer};
 
-#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+mod cfop;
+mod gtin;
+mod ie;
+pub use cfop::*;
+pub use gtin::*;
+pub use ie::*;
+use std::fmt::Display;
+
+#[derive(CodedEnum, PartialEq, Clone, Debug, Default)]
 pub enum TransportType {
     CIF = 0,
     FOB = 1,
     ThirdParty = 2,
     Issuer = 3,
     Recipient = 4,
+    #[default]
     None = 9,
 }
 
-impl Default for TransportType {
-    fn default() -> Self {
-        TransportType::None
-    }
-}
-
-impl TryFrom<u8> for TransportType {
-    type Error = String;
-
-    fn try_from(value: u8) -> Result<Self, Self::Error> {
-        match value {
-            0 => Ok(TransportType::CIF),
-            1 => Ok(TransportType::FOB),
-            2 => Ok(TransportType::ThirdParty),
-            3 => Ok(TransportType::Issuer),
-            4 => Ok(TransportType::Recipient),
-            9 => Ok(TransportType::None),
-            _ => Err(format!("Invalid transport type value: {}", value)),
-        }
-    }
-}
-
-#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[derive(CodedEnum, PartialEq, Debug, Clone)]
 pub enum Model {
     NFe = 55,
     NFCe = 65,
 }
 
-impl Model {
-    pub fn code(&self) -> u8 {
-        self.clone() as u8
-    }
-}
-
-impl TryFrom<u8> for Model {
-    type Error = String;
-
-    fn try_from(value: u8) -> Result<Self, Self::Error> {
-        match value {
-            55 => Ok(Model::NFe),
-            65 => Ok(Model::NFCe),
-            _ => Err(format!("Invalid model value: {}", value)),
-        }
-    }
-}
-
-#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[derive(CodedEnum, PartialEq, Debug, Clone)]
 pub enum Operation {
     Incoming = 0,
     Outgoing = 1,
 }
 
-impl TryFrom<u8> for Operation {
-    type Error = String;
-
-    fn try_from(value: u8) -> Result<Self, Self::Error> {
-        match value {
-            0 => Ok(Operation::Incoming),
-            1 => Ok(Operation::Outgoing),
-            _ => Err(format!("Invalid operation value: {}", value)),
-        }
-    }
-}
-
-#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[derive(CodedEnum, PartialEq, Debug, Clone)]
 pub enum DestinationTarget {
     Internal = 1,
     Interstate = 2,
     External = 3,
 }
 
-impl TryFrom<u8> for DestinationTarget {
-    type Error = String;
-
-    fn try_from(value: u8) -> Result<Self, Self::Error> {
-        match value {
-            1 => Ok(DestinationTarget::Internal),
-            2 => Ok(DestinationTarget::Interstate),
-            3 => Ok(DestinationTarget::External),
-            _ => Err(format!("Invalid destination target value: {}", value)),
-        }
-    }
-}
-
-#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[derive(CodedEnum, PartialEq, Debug, Clone)]
+#[coded(name = "DANFE generation")]
 pub enum DanfeGeneration {
     NormalPortrait = 1,
     NormalLandscape = 2,
@@ -107,22 +56,7 @@ pub enum DanfeGeneration {
     NFCeVirtual = 5,
 }
 
-impl TryFrom<u8> for DanfeGeneration {
-    type Error = String;
-
-    fn try_from(value: u8) -> Result<Self, Self::Error> {
-        match value {
-            1 => Ok(DanfeGeneration::NormalPortrait),
-            2 => Ok(DanfeGeneration::NormalLandscape),
-            3 => Ok(DanfeGeneration::Simplified),
-            4 => Ok(DanfeGeneration::NFCe),
-            5 => Ok(DanfeGeneration::NFCeVirtual),
-            _ => Err(format!("Invalid DANFE generation value: {}", value)),
-        }
-    }
-}
-
-#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[derive(CodedEnum, PartialEq, Debug, Clone)]
 pub enum EmissionType {
     Normal = 1,
     FSIA = 2,
@@ -133,48 +67,13 @@ pub enum EmissionType {
     Offline = 9,
 }
 
-impl EmissionType {
-    pub fn code(&self) -> u8 {
-        self.clone() as u8
-    }
-}
-
-impl TryFrom<u8> for EmissionType {
-    type Error = String;
-
-    fn try_from(value: u8) -> Result<Self, Self::Error> {
-        match value {
-            1 => Ok(EmissionType::Normal),
-            2 => Ok(EmissionType::FSIA),
-            4 => Ok(EmissionType::EPEC),
-            5 => Ok(EmissionType::FSDA),
-            6 => Ok(EmissionType::SVCAN),
-            7 => Ok(EmissionType::SVCRS),
-            9 => Ok(EmissionType::Offline),
-            _ => Err(format!("Invalid emission type value: {}", value)),
-        }
-    }
-}
-
-#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[derive(CodedEnum, PartialEq, Debug, Clone)]
 pub enum Environment {
     Production = 1,
     Homologation = 2,
 }
 
-impl TryFrom<u8> for Environment {
-    type Error = String;
-
-    fn try_from(value: u8) -> Result<Self, Self::Error> {
-        match value {
-            1 => Ok(Environment::Production),
-            2 => Ok(Environment::Homologation),
-            _ => Err(format!("Invalid environment value: {}", value)),
-        }
-    }
-}
-
-#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[derive(CodedEnum, PartialEq, Debug, Clone)]
 pub enum Finality {
     Normal = 1,
     Complementary = 2,
@@ -182,21 +81,7 @@ pub enum Finality {
     Cancellation = 4,
 }
 
-impl TryFrom<u8> for Finality {
-    type Error = String;
-
-    fn try_from(value: u8) -> Result<Self, Self::Error> {
-        match value {
-            1 => Ok(Finality::Normal),
-            2 => Ok(Finality::Complementary),
-            3 => Ok(Finality::Adjustment),
-            4 => Ok(Finality::Cancellation),
-            _ => Err(format!("Invalid finality value: {}", value)),
-        }
-    }
-}
-
-#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[derive(CodedEnum, PartialEq, Debug, Clone)]
 pub enum Presence {
     InplaceIndoor = 1,
     InplaceOutdoor = 5,
@@ -206,36 +91,22 @@ pub enum Presence {
     Other = 9,
 }
 
-impl TryFrom<u8> for Presence {
-    type Error = String;
-
-    fn try_from(value: u8) -> Result<Self, Self::Error> {
-        match value {
-            1 => Ok(Presence::InplaceIndoor),
-            2 => Ok(Presence::Internet),
-            3 => Ok(Presence::Teleservice),
-            4 => Ok(Presence::Delivery),
-            5 => Ok(Presence::InplaceOutdoor),
-            9 => Ok(Presence::Other),
-            _ => Err(format!("Invalid presence value: {}", value)),
-        }
+impl Presence {
+    /// Whether the buyer is not at the establishment, the operations which
+    /// must inform their intermediation (indIntermed)
+    pub fn is_remote(&self) -> bool {
+        !matches!(self, Presence::InplaceIndoor | Presence::InplaceOutdoor)
     }
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
-pub enum Intermediator {
-    External = 1,
-}
-
-impl TryFrom<u8> for Intermediator {
-    type Error = String;
-
-    fn try_from(value: u8) -> Result<Self, Self::Error> {
-        match value {
-            1 => Ok(Intermediator::External),
-            _ => Err(format!("Invalid intermediator value: {}", value)),
-        }
-    }
+/// Intermediation of the operation (indIntermed)
+#[derive(CodedEnum, PartialEq, Debug, Clone)]
+pub enum IntermediatorIndicator {
+    /// Operation without intermediator, in the own site or platform of the issuer
+    Own = 0,
+    /// Operation in the site or platform of a third party (marketplace), which
+    /// is identified in the group of the intermediator (infIntermed)
+    ThirdParty = 1,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
@@ -271,17 +142,384 @@ impl PersonDocument {
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub enum RecipientDocument {
+    CNPJ(CNPJ),
+    CPF(CPF),
+    #[serde(rename = "idEstrangeiro")]
+    Foreign(ForeignId),
+}
+
+impl RecipientDocument {
+    pub fn as_str(&self) -> &str {
+        match self {
+            RecipientDocument::CNPJ(cnpj) => &cnpj.0,
+            RecipientDocument::CPF(cpf) => &cpf.0,
+            RecipientDocument::Foreign(id) => &id.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DocumentError {
+    Length(usize),
+    Character(char),
+    Repeated,
+    VerifierDigits,
+}
+
+impl Display for DocumentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DocumentError::Length(length) => write!(f, "Invalid document length: {}", length),
+            DocumentError::Character(c) => write!(f, "Invalid document character: {}", c),
+            DocumentError::Repeated => write!(f, "Document with all digits repeated"),
+            DocumentError::VerifierDigits => write!(f, "Invalid document verifier digits"),
+        }
+    }
+}
+
+/// Digits of a document, without the formatting characters (`.`, `/`, `-`)
+fn document_digits(value: &str, length: usize) -> Result<Vec<u32>, DocumentError> {
+    let digits = value
+        .chars()
+        .filter(|c| !matches!(c, '.' | '/' | '-') && !c.is_whitespace())
+        .map(|c| c.to_digit(10).ok_or(DocumentError::Character(c)))
+        .collect::<Result<Vec<_>, _>>()?;
+    if digits.len() != length {
+        return Err(DocumentError::Length(digits.len()));
+    }
+    // Sequences like 00000000000 pass the verifier but are never issued
+    if digits.iter().all(|&digit| digit == digits[0]) {
+        return Err(DocumentError::Repeated);
+    }
+    Ok(digits)
+}
+
+/// Modulo 11 verifier digit, weighting the digits from right to left
+fn verifier_digit(digits: &[u32], max_weight: u32) -> u32 {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .zip((2..=max_weight).cycle())
+        .map(|(digit, weight)| digit * weight)
+        .sum();
+    match sum % 11 {
+        0 | 1 => 0,
+        rest => 11 - rest,
+    }
+}
+
+fn check_verifier_digits(digits: &[u32], max_weight: u32) -> Result<(), DocumentError> {
+    let base = digits.len() - 2;
+    let first = verifier_digit(&digits[..base], max_weight);
+    let second = verifier_digit(&digits[..=base], max_weight);
+    if digits[base] != first || digits[base + 1] != second {
+        return Err(DocumentError::VerifierDigits);
+    }
+    Ok(())
+}
+
+/// Digits followed by their two verifier digits, as generated by `testkit`
+#[cfg(any(test, feature = "testkit"))]
+pub(crate) fn with_verifier_digits(mut digits: Vec<u32>, max_weight: u32) -> String {
+    for _ in 0..2 {
+        digits.push(verifier_digit(&digits, max_weight));
+    }
+    format_digits(&digits)
+}
+
+fn format_digits(digits: &[u32]) -> String {
+    digits
+        .iter()
+        .map(|&digit| char::from_digit(digit, 10).unwrap())
+        .collect()
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(try_from = "String")]
 pub struct CNPJ(pub String);
 
+impl CNPJ {
+    /// Parses a CNPJ, formatted (12.345.678/0001-95) or not, checking its verifier digits
+    pub fn parse(value: &str) -> Result<Self, DocumentError> {
+        let digits = document_digits(value, 14)?;
+        check_verifier_digits(&digits, 9)?;
+        Ok(CNPJ(format_digits(&digits)))
+    }
+}
+
+impl TryFrom<String> for CNPJ {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        CNPJ::parse(&value).map_err(|e| format!("Invalid CNPJ {}: {}", value, e))
+    }
+}
+
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(try_from = "String")]
 pub struct CPF(pub String);
 
+impl CPF {
+    /// Parses a CPF, formatted (123.456.789-09) or not, checking its verifier digits
+    pub fn parse(value: &str) -> Result<Self, DocumentError> {
+        let digits = document_digits(value, 11)?;
+        check_verifier_digits(&digits, 11)?;
+        Ok(CPF(format_digits(&digits)))
+    }
+}
+
+impl TryFrom<String> for CPF {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        CPF::parse(&value).map_err(|e| format!("Invalid CPF {}: {}", value, e))
+    }
+}
+
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct IE(pub String);
 
+/// Identifier of a foreign recipient (idEstrangeiro), e.g. a passport number
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct ForeignId(pub String);
+
+/// State registration indicator of the recipient (indIEDest)
+#[derive(CodedEnum, PartialEq, Debug, Clone)]
+#[coded(name = "IE indicator")]
+pub enum IEIndicator {
+    Contributor = 1,
+    Exempt = 2,
+    NonContributor = 9,
+}
+
+/// Enforceability of the ISS of a service item (indISS)
+#[derive(CodedEnum, PartialEq, Debug, Clone, Copy)]
+#[coded(name = "ISS indicator")]
+pub enum ISSIndicator {
+    Enforceable = 1,
+    NotLevied = 2,
+    Exempt = 3,
+    Export = 4,
+    Immune = 5,
+    SuspendedByCourt = 6,
+    SuspendedByProcess = 7,
+}
+
+/// Tax incentive of a service item (indIncentivo)
+#[derive(CodedEnum, PartialEq, Debug, Clone, Copy)]
+pub enum TaxIncentive {
+    Yes = 1,
+    No = 2,
+}
+
+/// Tax regime of the issuer (CRT)
+#[derive(CodedEnum, PartialEq, Debug, Clone, Copy)]
+pub enum TaxRegime {
+    Simples = 1,
+    SimplesExcesso = 2,
+    Normal = 3,
+    MEI = 4,
+}
+
+impl TaxRegime {
+    /// Whether the ICMS of the items is informed by CSOSN, otherwise by CST
+    ///
+    /// Issuers above the gross revenue sublimit (CRT 2) use the CST.
+    pub fn uses_csosn(&self) -> bool {
+        matches!(self, TaxRegime::Simples | TaxRegime::MEI)
+    }
+}
+
+/// Special municipal tax regime of the services (cRegTrib)
+#[derive(CodedEnum, PartialEq, Debug, Clone, Copy)]
+pub enum SpecialTaxRegime {
+    MunicipalMicroenterprise = 1,
+    Estimate = 2,
+    ProfessionalSociety = 3,
+    Cooperative = 4,
+    IndividualMicroentrepreneur = 5,
+    MicroenterpriseOrSmallBusiness = 6,
+}
+
 #[derive(Debug, PartialEq)]
 pub enum ICMS {
+    ICMS00(ICMS00),
+    ICMS10(ICMS10),
+    ICMS20(ICMS20),
+    ICMS30(ICMS30),
+    ICMS40(ICMS40),
+    ICMS51(ICMS51),
+    ICMS60(ICMS60),
+    ICMS70(ICMS70),
+    ICMS90(ICMS90),
+    ICMSPart(ICMSPart),
+    ICMSST(ICMSST),
+    ICMSSN101(ICMSSN101),
     ICMSSN102(ICMSSN102),
+    ICMSSN201(ICMSSN201),
+    ICMSSN202(ICMSSN202),
+    ICMSSN500(ICMSSN500),
+    ICMSSN900(ICMSSN900),
+}
+
+impl ICMS {
+    /// Origin of the product (orig)
+    pub fn origin(&self) -> &Origin {
+        match self {
+            ICMS::ICMS00(data) => &data.origin,
+            ICMS::ICMS10(data) => &data.origin,
+            ICMS::ICMS20(data) => &data.origin,
+            ICMS::ICMS30(data) => &data.origin,
+            ICMS::ICMS40(data) => &data.origin,
+            ICMS::ICMS51(data) => &data.origin,
+            ICMS::ICMS60(data) => &data.origin,
+            ICMS::ICMS70(data) => &data.origin,
+            ICMS::ICMS90(data) => &data.origin,
+            ICMS::ICMSPart(data) => &data.origin,
+            ICMS::ICMSST(data) => &data.origin,
+            ICMS::ICMSSN101(data) => &data.origin,
+            ICMS::ICMSSN102(data) => &data.origin,
+            ICMS::ICMSSN201(data) => &data.origin,
+            ICMS::ICMSSN202(data) => &data.origin,
+            ICMS::ICMSSN500(data) => &data.origin,
+            ICMS::ICMSSN900(data) => &data.origin,
+        }
+    }
+
+    /// ICMS calculation base of the item (vBC), zero when not taxed
+    pub fn base(&self) -> Money {
+        match self {
+            ICMS::ICMS00(data) => data.base,
+            ICMS::ICMS10(data) => data.base,
+            ICMS::ICMS20(data) => data.base,
+            ICMS::ICMS51(data) => data.base.unwrap_or(Money::ZERO),
+            ICMS::ICMS70(data) => data.base,
+            ICMS::ICMS90(data) => data.base.unwrap_or(Money::ZERO),
+            ICMS::ICMSPart(data) => data.base,
+            ICMS::ICMSSN900(data) => data.base.unwrap_or(Money::ZERO),
+            _ => Money::ZERO,
+        }
+    }
+
+    /// ICMS value of the item (vICMS), zero when not taxed
+    pub fn value(&self) -> Money {
+        match self {
+            ICMS::ICMS00(data) => data.value,
+            ICMS::ICMS10(data) => data.value,
+            ICMS::ICMS20(data) => data.value,
+            ICMS::ICMS51(data) => data.value.unwrap_or(Money::ZERO),
+            ICMS::ICMS70(data) => data.value,
+            ICMS::ICMS90(data) => data.value.unwrap_or(Money::ZERO),
+            ICMS::ICMSPart(data) => data.value,
+            ICMS::ICMSSN900(data) => data.value.unwrap_or(Money::ZERO),
+            _ => Money::ZERO,
+        }
+    }
+
+    /// ST calculation base of the item (vBCST), zero without ST
+    pub fn st_base(&self) -> Money {
+        match self {
+            ICMS::ICMS10(data) => data.st_base,
+            ICMS::ICMS30(data) => data.st_base,
+            ICMS::ICMS70(data) => data.st_base,
+            ICMS::ICMS90(data) => data.st_base.unwrap_or(Money::ZERO),
+            ICMS::ICMSPart(data) => data.st_base,
+            ICMS::ICMSSN201(data) => data.st_base,
+            ICMS::ICMSSN202(data) => data.st_base,
+            ICMS::ICMSSN900(data) => data.st_base.unwrap_or(Money::ZERO),
+            _ => Money::ZERO,
+        }
+    }
+
+    /// ICMS ST value of the item (vICMSST), zero without ST
+    ///
+    /// The ST retained previously (ICMS60, ICMSST and CSOSN 500) is not due
+    /// in the operation, so it does not count.
+    pub fn st_value(&self) -> Money {
+        match self {
+            ICMS::ICMS10(data) => data.st_value,
+            ICMS::ICMS30(data) => data.st_value,
+            ICMS::ICMS70(data) => data.st_value,
+            ICMS::ICMS90(data) => data.st_value.unwrap_or(Money::ZERO),
+            ICMS::ICMSPart(data) => data.st_value,
+            ICMS::ICMSSN201(data) => data.st_value,
+            ICMS::ICMSSN202(data) => data.st_value,
+            ICMS::ICMSSN900(data) => data.st_value.unwrap_or(Money::ZERO),
+            _ => Money::ZERO,
+        }
+    }
+
+    /// FCP value of the item (vFCP), zero when not informed
+    pub fn fcp_value(&self) -> Money {
+        let value = match self {
+            ICMS::ICMS00(data) => data.fcp_value,
+            ICMS::ICMS10(data) => data.fcp_value,
+            ICMS::ICMS20(data) => data.fcp_value,
+            ICMS::ICMS51(data) => data.fcp_value,
+            ICMS::ICMS70(data) => data.fcp_value,
+            ICMS::ICMS90(data) => data.fcp_value,
+            _ => None,
+        };
+        value.unwrap_or(Money::ZERO)
+    }
+
+    /// FCP value retained by ST of the item (vFCPST), zero when not informed
+    pub fn st_fcp_value(&self) -> Money {
+        let value = match self {
+            ICMS::ICMS10(data) => data.st_fcp_value,
+            ICMS::ICMS30(data) => data.st_fcp_value,
+            ICMS::ICMS70(data) => data.st_fcp_value,
+            ICMS::ICMS90(data) => data.st_fcp_value,
+            ICMS::ICMSPart(data) => data.st_fcp_value,
+            ICMS::ICMSSN201(data) => data.st_fcp_value,
+            ICMS::ICMSSN202(data) => data.st_fcp_value,
+            ICMS::ICMSSN900(data) => data.st_fcp_value,
+            _ => None,
+        };
+        value.unwrap_or(Money::ZERO)
+    }
+
+    /// FCP value retained previously by ST of the item (vFCPSTRet), zero when not informed
+    pub fn retained_st_fcp_value(&self) -> Money {
+        match self {
+            ICMS::ICMS60(data) => data.retained_st_fcp_value,
+            ICMS::ICMSST(data) => data.retained_st_fcp_value,
+            ICMS::ICMSSN500(data) => data.retained_st_fcp_value,
+            _ => None,
+        }
+        .unwrap_or(Money::ZERO)
+    }
+
+    /// Exempted ICMS value (vICMSDeson) and reason (motDesICMS) of the item, as informed
+    pub(crate) fn unburden(&self) -> (Option<Money>, Option<&UnburdenReason>) {
+        match self {
+            ICMS::ICMS20(data) => (data.unburdened, data.unburden_reason.as_ref()),
+            ICMS::ICMS30(data) => (data.unburdened, data.unburden_reason.as_ref()),
+            ICMS::ICMS40(data) => (data.unburdened, data.unburden_reason.as_ref()),
+            ICMS::ICMS70(data) => (data.unburdened, data.unburden_reason.as_ref()),
+            ICMS::ICMS90(data) => (data.unburdened, data.unburden_reason.as_ref()),
+            _ => (None, None),
+        }
+    }
+
+    /// Exempted ICMS value of the item (vICMSDeson), zero when not informed
+    pub fn unburdened(&self) -> Money {
+        self.unburden().0.unwrap_or(Money::ZERO)
+    }
+
+    /// Whether the group is of the Simples Nacional, taxed by CSOSN instead of CST
+    pub fn is_simples_nacional(&self) -> bool {
+        matches!(
+            self,
+            ICMS::ICMSSN101(_)
+                | ICMS::ICMSSN102(_)
+                | ICMS::ICMSSN201(_)
+                | ICMS::ICMSSN202(_)
+                | ICMS::ICMSSN500(_)
+                | ICMS::ICMSSN900(_)
+        )
+    }
 }
 
 impl Serialize for ICMS {
@@ -289,13 +527,27 @@ impl Serialize for ICMS {
     where
         S: Serializer,
     {
+        let mut state = serializer.serialize_struct("ICMS", 1)?;
         match self {
-            ICMS::ICMSSN102(data) => {
-                let mut state = serializer.serialize_struct("ICMS", 1)?;
-                state.serialize_field("ICMSSN102", data)?;
-                state.end()
-            }
+            ICMS::ICMS00(data) => state.serialize_field("ICMS00", data)?,
+            ICMS::ICMS10(data) => state.serialize_field("ICMS10", data)?,
+            ICMS::ICMS20(data) => state.serialize_field("ICMS20", data)?,
+            ICMS::ICMS30(data) => state.serialize_field("ICMS30", data)?,
+            ICMS::ICMS40(data) => state.serialize_field("ICMS40", data)?,
+            ICMS::ICMS51(data) => state.serialize_field("ICMS51", data)?,
+            ICMS::ICMS60(data) => state.serialize_field("ICMS60", data)?,
+            ICMS::ICMS70(data) => state.serialize_field("ICMS70", data)?,
+            ICMS::ICMS90(data) => state.serialize_field("ICMS90", data)?,
+            ICMS::ICMSPart(data) => state.serialize_field("ICMSPart", data)?,
+            ICMS::ICMSST(data) => state.serialize_field("ICMSST", data)?,
+            ICMS::ICMSSN101(data) => state.serialize_field("ICMSSN101", data)?,
+            ICMS::ICMSSN102(data) => state.serialize_field("ICMSSN102", data)?,
+            ICMS::ICMSSN201(data) => state.serialize_field("ICMSSN201", data)?,
+            ICMS::ICMSSN202(data) => state.serialize_field("ICMSSN202", data)?,
+            ICMS::ICMSSN500(data) => state.serialize_field("ICMSSN500", data)?,
+            ICMS::ICMSSN900(data) => state.serialize_field("ICMSSN900", data)?,
         }
+        state.end()
     }
 }
 
@@ -305,56 +557,265 @@ impl<'de> Deserialize<'de> for ICMS {
         D: serde::Deserializer<'de>,
     {
         #[derive(Deserialize)]
-        struct ICMSHelper {
-            #[serde(rename = "ICMSSN102")]
-            icmssn102: Option<ICMSSN102>,
+        #[allow(clippy::upper_case_acronyms)]
+        enum ICMSHelper {
+            ICMS00(ICMS00),
+            ICMS10(ICMS10),
+            ICMS20(ICMS20),
+            ICMS30(ICMS30),
+            ICMS40(ICMS40),
+            ICMS51(ICMS51),
+            ICMS60(ICMS60),
+            ICMS70(ICMS70),
+            ICMS90(ICMS90),
+            ICMSPart(ICMSPart),
+            ICMSST(ICMSST),
+            ICMSSN101(ICMSSN101),
+            ICMSSN102(ICMSSN102),
+            ICMSSN201(ICMSSN201),
+            ICMSSN202(ICMSSN202),
+            ICMSSN500(ICMSSN500),
+            ICMSSN900(ICMSSN900),
         }
 
-        let helper = ICMSHelper::deserialize(deserializer)?;
-        if let Some(data) = helper.icmssn102 {
-            Ok(ICMS::ICMSSN102(data))
-        } else {
-            Err(serde::de::Error::custom("Unknown ICMS variant"))
+        #[derive(Deserialize)]
+        struct ICMSWrapper {
+            #[serde(rename = "$value")]
+            group: ICMSHelper,
         }
+
+        let wrapper = ICMSWrapper::deserialize(deserializer)?;
+        Ok(match wrapper.group {
+            ICMSHelper::ICMS00(data) => ICMS::ICMS00(data),
+            ICMSHelper::ICMS10(data) => ICMS::ICMS10(data),
+            ICMSHelper::ICMS20(data) => ICMS::ICMS20(data),
+            ICMSHelper::ICMS30(data) => ICMS::ICMS30(data),
+            ICMSHelper::ICMS40(data) => ICMS::ICMS40(data),
+            ICMSHelper::ICMS51(data) => ICMS::ICMS51(data),
+            ICMSHelper::ICMS60(data) => ICMS::ICMS60(data),
+            ICMSHelper::ICMS70(data) => ICMS::ICMS70(data),
+            ICMSHelper::ICMS90(data) => ICMS::ICMS90(data),
+            ICMSHelper::ICMSPart(data) => ICMS::ICMSPart(data),
+            ICMSHelper::ICMSST(data) => ICMS::ICMSST(data),
+            ICMSHelper::ICMSSN101(data) => ICMS::ICMSSN101(data),
+            ICMSHelper::ICMSSN102(data) => ICMS::ICMSSN102(data),
+            ICMSHelper::ICMSSN201(data) => ICMS::ICMSSN201(data),
+            ICMSHelper::ICMSSN202(data) => ICMS::ICMSSN202(data),
+            ICMSHelper::ICMSSN500(data) => ICMS::ICMSSN500(data),
+            ICMSHelper::ICMSSN900(data) => ICMS::ICMSSN900(data),
+        })
     }
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
-#[repr(u8)]
-#[serde(try_from = "u8", into = "u8")]
-pub enum CSOSN {
-    FinalConsumer = 102,
+#[derive(Debug, PartialEq)]
+pub enum PIS {
+    PISAliq(PISAliq),
+    PISQtde(PISQtde),
+    PISNT(PISNT),
+    PISOutr(PISOutr),
 }
 
-#[derive(PartialEq, Debug, Clone)]
-pub struct InvalidCSOSN(u8);
+impl PIS {
+    /// PIS value of the item (vPIS), zero when not taxed
+    pub fn value(&self) -> Money {
+        match self {
+            PIS::PISAliq(data) => data.value,
+            PIS::PISQtde(data) => data.value,
+            PIS::PISNT(_) => Money::ZERO,
+            PIS::PISOutr(data) => data.value,
+        }
+    }
+}
 
-impl Display for InvalidCSOSN {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Invalid CSOSN value: {}", self.0)
+impl Serialize for PIS {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("PIS", 1)?;
+        match self {
+            PIS::PISAliq(data) => state.serialize_field("PISAliq", data)?,
+            PIS::PISQtde(data) => state.serialize_field("PISQtde", data)?,
+            PIS::PISNT(data) => state.serialize_field("PISNT", data)?,
+            PIS::PISOutr(data) => state.serialize_field("PISOutr", data)?,
+        }
+        state.end()
     }
 }
 
-impl TryFrom<u8> for CSOSN {
-    type Error = InvalidCSOSN;
+impl<'de> Deserialize<'de> for PIS {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[allow(clippy::upper_case_acronyms)]
+        enum PISHelper {
+            PISAliq(PISAliq),
+            PISQtde(PISQtde),
+            PISNT(PISNT),
+            PISOutr(PISOutr),
+        }
 
-    fn try_from(value: u8) -> Result<Self, Self::Error> {
-        match value {
-            102 => Ok(CSOSN::FinalConsumer),
-            _ => Err(InvalidCSOSN(value)),
+        #[derive(Deserialize)]
+        struct PISWrapper {
+            #[serde(rename = "$value")]
+            group: PISHelper,
         }
+
+        let wrapper = PISWrapper::deserialize(deserializer)?;
+        Ok(match wrapper.group {
+            PISHelper::PISAliq(data) => PIS::PISAliq(data),
+            PISHelper::PISQtde(data) => PIS::PISQtde(data),
+            PISHelper::PISNT(data) => PIS::PISNT(data),
+            PISHelper::PISOutr(data) => PIS::PISOutr(data),
+        })
     }
 }
 
-impl From<CSOSN> for u8 {
-    fn from(value: CSOSN) -> Self {
-        value as u8
+#[derive(Debug, PartialEq)]
+pub enum COFINS {
+    COFINSAliq(COFINSAliq),
+    COFINSQtde(COFINSQtde),
+    COFINSNT(COFINSNT),
+    COFINSOutr(COFINSOutr),
+}
+
+impl COFINS {
+    /// COFINS value of the item (vCOFINS), zero when not taxed
+    pub fn value(&self) -> Money {
+        match self {
+            COFINS::COFINSAliq(data) => data.value,
+            COFINS::COFINSQtde(data) => data.value,
+            COFINS::COFINSNT(_) => Money::ZERO,
+            COFINS::COFINSOutr(data) => data.value,
+        }
     }
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
-#[repr(u8)]
-#[serde(from = "u8", into = "u8")]
+impl Serialize for COFINS {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("COFINS", 1)?;
+        match self {
+            COFINS::COFINSAliq(data) => state.serialize_field("COFINSAliq", data)?,
+            COFINS::COFINSQtde(data) => state.serialize_field("COFINSQtde", data)?,
+            COFINS::COFINSNT(data) => state.serialize_field("COFINSNT", data)?,
+            COFINS::COFINSOutr(data) => state.serialize_field("COFINSOutr", data)?,
+        }
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for COFINS {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[allow(clippy::upper_case_acronyms)]
+        enum COFINSHelper {
+            COFINSAliq(COFINSAliq),
+            COFINSQtde(COFINSQtde),
+            COFINSNT(COFINSNT),
+            COFINSOutr(COFINSOutr),
+        }
+
+        #[derive(Deserialize)]
+        struct COFINSWrapper {
+            #[serde(rename = "$value")]
+            group: COFINSHelper,
+        }
+
+        let wrapper = COFINSWrapper::deserialize(deserializer)?;
+        Ok(match wrapper.group {
+            COFINSHelper::COFINSAliq(data) => COFINS::COFINSAliq(data),
+            COFINSHelper::COFINSQtde(data) => COFINS::COFINSQtde(data),
+            COFINSHelper::COFINSNT(data) => COFINS::COFINSNT(data),
+            COFINSHelper::COFINSOutr(data) => COFINS::COFINSOutr(data),
+        })
+    }
+}
+
+/// PIS and COFINS tax situation code (CST)
+#[derive(CodedEnum, PartialEq, Clone, Debug)]
+#[coded(name = "PIS/COFINS CST", width = 2)]
+pub enum ContributionCST {
+    TaxableBasicRate = 1,
+    TaxableDifferentiatedRate = 2,
+    TaxableUnitRate = 3,
+    MonophasicZeroRate = 4,
+    Substitution = 5,
+    ZeroRate = 6,
+    Exempt = 7,
+    NoIncidence = 8,
+    Suspended = 9,
+    OtherOutputs = 49,
+    CreditTaxedRevenue = 50,
+    CreditUntaxedRevenue = 51,
+    CreditExportRevenue = 52,
+    CreditTaxedAndUntaxedRevenue = 53,
+    CreditTaxedAndExportRevenue = 54,
+    CreditUntaxedAndExportRevenue = 55,
+    CreditAllRevenues = 56,
+    PresumedCreditTaxedRevenue = 60,
+    PresumedCreditUntaxedRevenue = 61,
+    PresumedCreditExportRevenue = 62,
+    PresumedCreditTaxedAndUntaxedRevenue = 63,
+    PresumedCreditTaxedAndExportRevenue = 64,
+    PresumedCreditUntaxedAndExportRevenue = 65,
+    PresumedCreditAllRevenues = 66,
+    PresumedCreditOthers = 67,
+    AcquisitionWithoutCredit = 70,
+    ExemptAcquisition = 71,
+    SuspendedAcquisition = 72,
+    ZeroRateAcquisition = 73,
+    NoIncidenceAcquisition = 74,
+    SubstitutionAcquisition = 75,
+    OtherInputs = 98,
+    Others = 99,
+}
+
+/// IPI tax situation code (CST)
+#[derive(CodedEnum, PartialEq, Clone, Debug)]
+#[coded(name = "IPI CST", width = 2)]
+pub enum IPICST {
+    TaxedEntry = 0,
+    ZeroRateEntry = 1,
+    ExemptEntry = 2,
+    NotTaxedEntry = 3,
+    ImmuneEntry = 4,
+    SuspendedEntry = 5,
+    OtherEntries = 49,
+    TaxedExit = 50,
+    ZeroRateExit = 51,
+    ExemptExit = 52,
+    NotTaxedExit = 53,
+    ImmuneExit = 54,
+    SuspendedExit = 55,
+    OtherExits = 99,
+}
+
+/// ICMS tax situation code (CSOSN) for companies in the Simples Nacional
+#[derive(CodedEnum, PartialEq, Debug, Clone)]
+#[repr(u16)]
+#[coded(name = "CSOSN")]
+pub enum CSOSN {
+    WithCredit = 101,
+    FinalConsumer = 102,
+    ExemptRange = 103,
+    WithCreditAndST = 201,
+    WithoutCreditAndST = 202,
+    ExemptRangeWithST = 203,
+    Immune = 300,
+    NotTaxed = 400,
+    PreviouslyChargedST = 500,
+    Others = 900,
+}
+
+#[derive(CodedEnum, PartialEq, Debug, Clone)]
 pub enum Origin {
     National = 0,
     NationalInConformity = 4,
@@ -367,30 +828,66 @@ pub enum Origin {
     ForeignInternalMarketNoSimilar = 7,
 }
 
-impl From<u8> for Origin {
-    fn from(value: u8) -> Self {
-        match value {
-            0 => Origin::National,
-            1 => Origin::Foreign,
-            2 => Origin::ForeignInternalMarket,
-            3 => Origin::NationalContentBetween40And70,
-            4 => Origin::NationalInConformity,
-            5 => Origin::NationalContentBelow40,
-            6 => Origin::ForeignNoSimilar,
-            7 => Origin::ForeignInternalMarketNoSimilar,
-            8 => Origin::NationalContentAbove70,
-            _ => panic!("Invalid origin value: {}", value),
-        }
-    }
+/// ICMS tax situation code (CST) for companies outside the Simples Nacional
+#[derive(CodedEnum, PartialEq, Clone, Debug)]
+#[coded(name = "CST", width = 2)]
+pub enum CST {
+    Taxed = 0,
+    TaxedWithST = 10,
+    ReducedBase = 20,
+    ExemptWithST = 30,
+    Exempt = 40,
+    NotTaxed = 41,
+    Suspended = 50,
+    Deferred = 51,
+    PreviouslyChargedST = 60,
+    ReducedBaseWithST = 70,
+    Others = 90,
 }
 
-impl From<Origin> for u8 {
-    fn from(value: Origin) -> Self {
-        value as u8
-    }
+/// Modality of the ICMS calculation base (modBC)
+#[derive(CodedEnum, PartialEq, Debug, Clone)]
+pub enum BaseModality {
+    ValueAddedMargin = 0,
+    ReferencePrice = 1,
+    MaximumPrice = 2,
+    OperationValue = 3,
+}
+
+/// Modality of the ICMS ST calculation base (modBCST)
+#[derive(CodedEnum, PartialEq, Debug, Clone)]
+#[coded(name = "ST base modality")]
+pub enum STBaseModality {
+    MaximumPrice = 0,
+    NegativeList = 1,
+    PositiveList = 2,
+    NeutralList = 3,
+    ValueAddedMargin = 4,
+    ReferencePrice = 5,
+    OperationValue = 6,
+}
+
+/// Reason of the exemption of the ICMS (motDesICMS)
+#[derive(CodedEnum, PartialEq, Debug, Clone)]
+#[coded(name = "Unburden reason")]
+pub enum UnburdenReason {
+    Taxi = 1,
+    AgriculturalUse = 3,
+    FleetOrRental = 4,
+    DiplomaticOrConsular = 5,
+    AmazonUtilityVehicles = 6,
+    Suframa = 7,
+    SaleToPublicBody = 8,
+    Other = 9,
+    DisabledDriver = 10,
+    DisabledNonDriver = 11,
+    AgriculturalDevelopmentBody = 12,
+    OlympicsOrganization = 16,
+    RequestedByTaxAuthority = 90,
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(CodedEnum, PartialEq, Clone, Debug)]
+#[coded(width = 2)]
 pub enum PaymentType {
     Cash = 1,
     Check = 2,
@@ -406,63 +903,226 @@ pub enum PaymentType {
     PIX = 17,
     Transfer = 18,
     Program = 19,
+    /// Without payment, for the complementary and adjustment notes
+    NoPayment = 90,
+    /// Other means, described in the payment (xPag)
+    Other = 99,
 }
 
-impl Serialize for PaymentType {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        left_pad(&self.code().to_string(), 2, '0').serialize(serializer)
-    }
+/// Form of the payment (indPag)
+#[derive(CodedEnum, PartialEq, Debug, Clone)]
+pub enum PaymentIndicator {
+    /// Payment in cash (à vista)
+    Cash = 0,
+    /// Payment in installments (a prazo)
+    Installments = 1,
 }
 
-impl<'de> Deserialize<'de> for PaymentType {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        let s: String = Deserialize::deserialize(deserializer)?;
-        let value = s.parse::<u8>().map_err(serde::de::Error::custom)?;
-        PaymentType::try_from(value).map_err(serde::de::Error::custom)
-    }
+/// Integration of the card payment with the automation system (tpIntegra)
+#[derive(CodedEnum, PartialEq, Debug, Clone)]
+pub enum CardIntegration {
+    /// Payment integrated with the automation system, e.g. TEF or POS with link
+    Integrated = 1,
+    /// Payment not integrated, e.g. standalone POS
+    NotIntegrated = 2,
 }
 
-impl TryFrom<u8> for PaymentType {
-    type Error = String;
+/// Brand of the card (tBand)
+#[derive(CodedEnum, PartialEq, Debug, Clone)]
+#[coded(width = 2)]
+pub enum CardBrand {
+    Visa = 1,
+    Mastercard = 2,
+    AmericanExpress = 3,
+    Sorocred = 4,
+    DinersClub = 5,
+    Elo = 6,
+    Hipercard = 7,
+    Aura = 8,
+    Cabal = 9,
+    Alelo = 10,
+    BanesCard = 11,
+    CalCard = 12,
+    Credz = 13,
+    Discover = 14,
+    GoodCard = 15,
+    GreenCard = 16,
+    Hiper = 17,
+    JCB = 18,
+    Mais = 19,
+    MaxVan = 20,
+    Policard = 21,
+    RedeCompras = 22,
+    Sodexo = 23,
+    ValeCard = 24,
+    Verocheque = 25,
+    VR = 26,
+    Ticket = 27,
+    Other = 99,
+}
 
-    fn try_from(value: u8) -> Result<Self, Self::Error> {
-        match value {
-            1 => Ok(PaymentType::Cash),
-            2 => Ok(PaymentType::Check),
-            3 => Ok(PaymentType::CreditCard),
-            4 => Ok(PaymentType::DebitCard),
-            5 => Ok(PaymentType::ShopCredit),
-            6 => Ok(PaymentType::FoodVoucher),
-            7 => Ok(PaymentType::MealVoucher),
-            8 => Ok(PaymentType::GiftCard),
-            9 => Ok(PaymentType::GasVoucher),
-            15 => Ok(PaymentType::Boleto),
-            16 => Ok(PaymentType::BankDeposit),
-            17 => Ok(PaymentType::PIX),
-            18 => Ok(PaymentType::Transfer),
-            19 => Ok(PaymentType::Program),
-            _ => Err(format!("Invalid payment type value: {}", value)),
-        }
-    }
+/// Kind of the vehicle sale (tpOp)
+#[derive(CodedEnum, PartialEq, Debug, Clone, Copy)]
+pub enum VehicleOperation {
+    Other = 0,
+    DealershipSale = 1,
+    DirectBilling = 2,
+    DirectSale = 3,
 }
 
-impl PaymentType {
-    pub fn code(&self) -> u8 {
-        self.clone() as u8
-    }
+/// Fuel of the vehicle, as in the RENAVAM table (tpComb)
+#[derive(CodedEnum, PartialEq, Debug, Clone)]
+#[coded(width = 2)]
+pub enum VehicleFuel {
+    Alcohol = 1,
+    Gasoline = 2,
+    Diesel = 3,
+    Gasogen = 4,
+    Methane = 5,
+    ElectricInternalSource = 6,
+    ElectricExternalSource = 7,
+    GasolineNaturalGas = 8,
+    AlcoholNaturalGas = 9,
+    DieselNaturalGas = 10,
+    SeeObservation = 11,
+    AlcoholVehicularNaturalGas = 12,
+    GasolineVehicularNaturalGas = 13,
+    DieselVehicularNaturalGas = 14,
+    VehicularNaturalGas = 15,
+    AlcoholGasoline = 16,
+    GasolineAlcoholNaturalGas = 17,
+    GasolineElectric = 18,
+}
+
+/// Type of the vehicle, as in the RENAVAM table (tpVeic)
+#[derive(CodedEnum, PartialEq, Debug, Clone)]
+#[coded(width = 2)]
+pub enum VehicleType {
+    Moped = 2,
+    Scooter = 3,
+    Motorcycle = 4,
+    Tricycle = 5,
+    Car = 6,
+    Minibus = 7,
+    Bus = 8,
+    Trailer = 10,
+    SemiTrailer = 11,
+    Van = 13,
+    Truck = 14,
+    TruckTractor = 17,
+    WheelTractor = 18,
+    CrawlerTractor = 19,
+    MixedTractor = 20,
+    Quadricycle = 21,
+    Special = 22,
+    Pickup = 23,
+    Cargo = 24,
+    Utility = 25,
+    MotorHome = 26,
+}
+
+/// Species of the vehicle, as in the RENAVAM table (espVeic)
+#[derive(CodedEnum, PartialEq, Debug, Clone, Copy)]
+pub enum VehicleSpecies {
+    Passenger = 1,
+    Cargo = 2,
+    Mixed = 3,
+    Racing = 4,
+    Traction = 5,
+    Special = 6,
+}
+
+/// Condition of the vehicle (condVeic)
+#[derive(CodedEnum, PartialEq, Debug, Clone, Copy)]
+pub enum VehicleCondition {
+    Finished = 1,
+    Unfinished = 2,
+    SemiFinished = 3,
+}
+
+/// Color of the vehicle, as in the DENATRAN table (cCorDENATRAN)
+#[derive(CodedEnum, PartialEq, Debug, Clone)]
+#[coded(name = "DENATRAN color", width = 2)]
+pub enum DenatranColor {
+    Yellow = 1,
+    Blue = 2,
+    Beige = 3,
+    White = 4,
+    Gray = 5,
+    Golden = 6,
+    Garnet = 7,
+    Orange = 8,
+    Brown = 9,
+    Silver = 10,
+    Black = 11,
+    Pink = 12,
+    Purple = 13,
+    Green = 14,
+    Red = 15,
+    Fantasy = 16,
+}
+
+/// Restriction on the vehicle (tpRest)
+#[derive(CodedEnum, PartialEq, Debug, Clone, Copy)]
+pub enum VehicleRestriction {
+    None = 0,
+    FiduciaryAlienation = 1,
+    Leasing = 2,
+    RetentionOfTitle = 3,
+    Pledge = 4,
+    Other = 9,
+}
+
+/// Condition of the VIN (Vehicle Identification Number) of the chassis (VIN)
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy)]
+pub enum VINCondition {
+    #[serde(rename = "N")]
+    Normal,
+    #[serde(rename = "R")]
+    Remarked,
+}
+
+/// International transport route of the imported goods (tpViaTransp)
+#[derive(CodedEnum, PartialEq, Debug, Clone, Copy)]
+pub enum InternationalTransport {
+    Maritime = 1,
+    River = 2,
+    Lake = 3,
+    Air = 4,
+    Postal = 5,
+    Rail = 6,
+    Road = 7,
+    Pipeline = 8,
+    OwnMeans = 9,
+    Fictitious = 10,
+    Courier = 11,
+    ByHand = 12,
+    Towing = 13,
+}
+
+/// Form of the import regarding the intermediation (tpIntermedio)
+#[derive(CodedEnum, PartialEq, Debug, Clone, Copy)]
+pub enum ImportIntermediation {
+    OwnAccount = 1,
+    OnBehalf = 2,
+    ToOrder = 3,
+}
+
+/// Whether the product is made in a relevant scale, for the ICMS ST (indEscala)
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy)]
+pub enum ProductionScale {
+    #[serde(rename = "S")]
+    Relevant,
+    #[serde(rename = "N")]
+    NotRelevant,
 }
 
 #[cfg(test)]
 mod test {
-    use crate::utils::canonicalize_xml as canonicalize;
+    use crate::xml::{indent_xml, normalize_xml as canonicalize};
     use nf_e_macros::serialization_test;
     use quick_xml::{de::from_str as deserialize, se::to_string as serialize};
+    use rust_decimal_macros::dec;
 
     use super::*;
 
@@ -471,9 +1131,78 @@ mod test {
         CNPJ("12345678000195".to_string())
     }
 
-    #[serialization_test(expected = "<CPF>12345678901</CPF>")]
+    #[serialization_test(expected = "<CPF>12345678909</CPF>")]
     fn setup_cpf() -> CPF {
-        CPF("12345678901".to_string())
+        CPF("12345678909".to_string())
+    }
+
+    #[test]
+    fn test_parse_cnpj() {
+        assert_eq!(CNPJ::parse("12.345.678/0001-95"), Ok(setup_cnpj()));
+        assert_eq!(
+            CNPJ::parse("12345678000196"),
+            Err(DocumentError::VerifierDigits)
+        );
+        assert_eq!(CNPJ::parse("1234567800019"), Err(DocumentError::Length(13)));
+        assert_eq!(
+            CNPJ::parse("12345678A00195"),
+            Err(DocumentError::Character('A'))
+        );
+        assert_eq!(CNPJ::parse("11111111111111"), Err(DocumentError::Repeated));
+    }
+
+    #[test]
+    fn test_parse_cpf() {
+        assert_eq!(CPF::parse("123.456.789-09"), Ok(setup_cpf()));
+        assert_eq!(
+            CPF::parse("12345678901"),
+            Err(DocumentError::VerifierDigits)
+        );
+        assert_eq!(CPF::parse("123456789"), Err(DocumentError::Length(9)));
+        assert_eq!(CPF::parse("00000000000"), Err(DocumentError::Repeated));
+    }
+
+    #[test]
+    fn test_coded_enum() {
+        assert_eq!(Model::NFCe.code(), 65);
+        assert_eq!(CSOSN::Others.code(), 900);
+        assert_eq!(u8::from(Presence::Delivery), 4);
+        assert_eq!(Model::try_from(55), Ok(Model::NFe));
+        assert_eq!(
+            DanfeGeneration::try_from(6),
+            Err("Invalid DANFE generation value: 6".to_string())
+        );
+        assert_eq!(
+            TransportType::try_from(5),
+            Err("Invalid transport type value: 5".to_string())
+        );
+
+        use quick_xml::se::to_string_with_root;
+        assert_eq!(
+            to_string_with_root("CST", &CST::Taxed).unwrap(),
+            "<CST>00</CST>"
+        );
+        assert_eq!(
+            to_string_with_root("tPag", &PaymentType::PIX).unwrap(),
+            "<tPag>17</tPag>"
+        );
+        assert_eq!(
+            to_string_with_root("CRT", &TaxRegime::Normal).unwrap(),
+            "<CRT>3</CRT>"
+        );
+        assert_eq!(deserialize::<CST>("<CST>00</CST>").unwrap(), CST::Taxed);
+        assert_eq!(
+            deserialize::<CSOSN>("<CSOSN>102</CSOSN>").unwrap(),
+            CSOSN::FinalConsumer
+        );
+        assert!(deserialize::<CST>("<CST>05</CST>").is_err());
+        assert!(deserialize::<Origin>("<orig>A</orig>").is_err());
+    }
+
+    #[test]
+    fn test_invalid_person_document() {
+        let result = deserialize::<PersonDocument>("<CPF>12345678901</CPF>");
+        assert!(result.is_err());
     }
 
     #[serialization_test(expected = "<IE>123456789</IE>")]
@@ -481,11 +1210,43 @@ mod test {
         IE("123456789".to_string())
     }
 
-    #[serialization_test(fixture = "../tests/fixtures/enums/icms.xml")]
-    fn setup_icms() -> ICMS {
-        ICMS::ICMSSN102(ICMSSN102 {
-            csosn: CSOSN::FinalConsumer,
-            origin: Origin::National,
-        })
+    #[serialization_test(expected = "<idEstrangeiro>AB1234567</idEstrangeiro>")]
+    fn setup_foreign_recipient_document() -> RecipientDocument {
+        RecipientDocument::Foreign(ForeignId("AB1234567".to_string()))
+    }
+
+    #[serialization_test(
+        case(name = "icms00", fixture = "../tests/fixtures/enums/icms00.xml"),
+        case(name = "icmssn102", fixture = "../tests/fixtures/enums/icms.xml"),
+        case(
+            name = "icms40",
+            expected = "<ICMS><ICMS40><orig>1</orig><CST>41</CST></ICMS40></ICMS>"
+        ),
+        roundtrip,
+        indent = "indent_xml"
+    )]
+    fn setup_icms(case: &str) -> ICMS {
+        match case {
+            "icms00" => ICMS::ICMS00(ICMS00 {
+                origin: Origin::National,
+                cst: CST::Taxed,
+                base_modality: BaseModality::OperationValue,
+                base: Money::from(dec!(100.0)),
+                rate: Money::from(dec!(18.0)),
+                value: Money::from(dec!(18.0)),
+                fcp_rate: None,
+                fcp_value: None,
+            }),
+            "icmssn102" => ICMS::ICMSSN102(ICMSSN102 {
+                csosn: CSOSN::FinalConsumer,
+                origin: Origin::National,
+            }),
+            _ => ICMS::ICMS40(ICMS40 {
+                origin: Origin::Foreign,
+                cst: CST::NotTaxed,
+                unburdened: None,
+                unburden_reason: None,
+            }),
+        }
     }
 }
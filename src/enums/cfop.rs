@@ -0,0 +1,170 @@
+//! Fiscal code of the operations (Código Fiscal de Operações e Prestações)
+//!
+//! The first digit of the CFOP tells the direction of the operation: 1, 2 and
+//! 3 for the entries and 5, 6 and 7 for the exits, within the state, with
+//! other states and with other countries. The catalog lists the codes of the
+//! CFOP table (Convênio s/nº de 1970) that may be used in the NF-e.
+
+use super::{DestinationTarget, Operation};
+use std::fmt::Display;
+
+/// Codes of the CFOP table accepted by the NF-e
+#[cfg(feature = "tables")]
+#[rustfmt::skip]
+const CATALOG: &[u32] = &[
+    // Entries within the state
+    1101, 1102, 1111, 1113, 1116, 1117, 1118, 1120, 1121, 1122, 1124, 1125, 1126, 1128, 1151,
+    1152, 1153, 1154, 1201, 1202, 1203, 1204, 1205, 1206, 1207, 1208, 1209, 1212, 1251, 1252,
+    1253, 1254, 1255, 1256, 1257, 1301, 1302, 1303, 1304, 1305, 1306, 1351, 1352, 1353, 1354,
+    1355, 1356, 1360, 1401, 1403, 1406, 1407, 1408, 1409, 1410, 1411, 1414, 1415, 1451, 1452,
+    1501, 1503, 1504, 1505, 1506, 1551, 1552, 1553, 1554, 1555, 1556, 1557, 1601, 1602, 1603,
+    1604, 1605, 1651, 1652, 1653, 1658, 1659, 1660, 1661, 1662, 1663, 1664, 1901, 1902, 1903,
+    1904, 1905, 1906, 1907, 1908, 1909, 1910, 1911, 1912, 1913, 1914, 1915, 1916, 1917, 1918,
+    1919, 1920, 1921, 1922, 1923, 1924, 1925, 1926, 1931, 1932, 1933, 1934, 1949,
+    // Entries from other states
+    2101, 2102, 2111, 2113, 2116, 2117, 2118, 2120, 2121, 2122, 2124, 2125, 2126, 2128, 2151,
+    2152, 2153, 2154, 2201, 2202, 2203, 2204, 2205, 2206, 2207, 2208, 2209, 2212, 2251, 2252,
+    2253, 2254, 2255, 2256, 2257, 2301, 2302, 2303, 2304, 2305, 2306, 2351, 2352, 2353, 2354,
+    2355, 2356, 2401, 2403, 2406, 2407, 2408, 2409, 2410, 2411, 2414, 2415, 2501, 2503, 2504,
+    2505, 2506, 2551, 2552, 2553, 2554, 2555, 2556, 2557, 2603, 2651, 2652, 2653, 2658, 2659,
+    2660, 2661, 2662, 2663, 2664, 2901, 2902, 2903, 2904, 2905, 2906, 2907, 2908, 2909, 2910,
+    2911, 2912, 2913, 2914, 2915, 2916, 2917, 2918, 2919, 2920, 2921, 2922, 2923, 2924, 2925,
+    2931, 2932, 2933, 2934, 2949,
+    // Entries from other countries
+    3101, 3102, 3126, 3127, 3128, 3201, 3202, 3205, 3206, 3207, 3211, 3212, 3251, 3301, 3351,
+    3352, 3353, 3354, 3355, 3356, 3503, 3551, 3553, 3556, 3651, 3652, 3653, 3930, 3949,
+    // Exits within the state
+    5101, 5102, 5103, 5104, 5105, 5106, 5109, 5110, 5111, 5112, 5113, 5114, 5115, 5116, 5117,
+    5118, 5119, 5120, 5122, 5123, 5124, 5125, 5151, 5152, 5153, 5155, 5156, 5201, 5202, 5205,
+    5206, 5207, 5208, 5209, 5210, 5251, 5252, 5253, 5254, 5255, 5256, 5257, 5258, 5301, 5302,
+    5303, 5304, 5305, 5306, 5307, 5351, 5352, 5353, 5354, 5355, 5356, 5357, 5359, 5360, 5401,
+    5402, 5403, 5405, 5408, 5409, 5410, 5411, 5412, 5413, 5414, 5415, 5451, 5501, 5502, 5503,
+    5504, 5505, 5601, 5602, 5603, 5605, 5606, 5651, 5652, 5653, 5654, 5655, 5656, 5657, 5658,
+    5659, 5660, 5661, 5662, 5663, 5664, 5665, 5666, 5667, 5901, 5902, 5903, 5904, 5905, 5906,
+    5907, 5908, 5909, 5910, 5911, 5912, 5913, 5914, 5915, 5916, 5917, 5918, 5919, 5920, 5921,
+    5922, 5923, 5924, 5925, 5926, 5927, 5928, 5929, 5931, 5932, 5933, 5934, 5949,
+    // Exits to other states
+    6101, 6102, 6103, 6104, 6105, 6106, 6107, 6108, 6109, 6110, 6111, 6112, 6113, 6114, 6115,
+    6116, 6117, 6118, 6119, 6120, 6122, 6123, 6124, 6125, 6151, 6152, 6153, 6155, 6156, 6201,
+    6202, 6205, 6206, 6207, 6208, 6209, 6210, 6251, 6252, 6253, 6254, 6255, 6256, 6257, 6258,
+    6301, 6302, 6303, 6304, 6305, 6306, 6307, 6351, 6352, 6353, 6354, 6355, 6356, 6357, 6359,
+    6360, 6401, 6402, 6403, 6404, 6408, 6409, 6410, 6411, 6412, 6413, 6414, 6415, 6501, 6502,
+    6503, 6504, 6505, 6603, 6651, 6652, 6653, 6654, 6655, 6656, 6657, 6658, 6659, 6660, 6661,
+    6662, 6663, 6664, 6665, 6666, 6667, 6901, 6902, 6903, 6904, 6905, 6906, 6907, 6908, 6909,
+    6910, 6911, 6912, 6913, 6914, 6915, 6916, 6917, 6918, 6919, 6920, 6921, 6922, 6923, 6924,
+    6925, 6929, 6931, 6932, 6933, 6934, 6949,
+    // Exits to other countries
+    7101, 7102, 7105, 7106, 7127, 7129, 7201, 7202, 7205, 7206, 7207, 7210, 7211, 7212, 7251,
+    7301, 7358, 7501, 7504, 7551, 7553, 7556, 7651, 7654, 7667, 7930, 7949,
+];
+
+/// Reason the CFOP of an item was rejected
+#[derive(Debug, Clone, PartialEq)]
+pub enum CFOPError {
+    /// Code missing from the CFOP table
+    Unknown(u32),
+    /// Entry code in an exit (tpNF) or the opposite
+    Operation(u32, Operation),
+    /// Code of another destination (idDest): 5xxx internal, 6xxx interstate and 7xxx export
+    Destination(u32, DestinationTarget),
+}
+
+impl Display for CFOPError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CFOPError::Unknown(cfop) => write!(f, "Unknown CFOP: {}", cfop),
+            CFOPError::Operation(cfop, operation) => {
+                write!(
+                    f,
+                    "CFOP {} does not match the operation {:?}",
+                    cfop, operation
+                )
+            }
+            CFOPError::Destination(cfop, destination) => write!(
+                f,
+                "CFOP {} does not match the destination {:?}",
+                cfop, destination
+            ),
+        }
+    }
+}
+
+/// Whether the code is part of the CFOP table
+#[cfg(feature = "tables")]
+pub fn is_known_cfop(cfop: u32) -> bool {
+    CATALOG.binary_search(&cfop).is_ok()
+}
+
+/// Checks that the CFOP exists and matches the direction of the document
+///
+/// Without the `tables` feature the catalog is not available, so only the
+/// direction and the destination of the code are checked.
+pub fn validate_cfop(
+    cfop: u32,
+    operation: &Operation,
+    destination: &DestinationTarget,
+) -> Result<(), CFOPError> {
+    #[cfg(feature = "tables")]
+    if !is_known_cfop(cfop) {
+        return Err(CFOPError::Unknown(cfop));
+    }
+    let first = cfop / 1000;
+    let exit = first >= 5;
+    if exit != (*operation == Operation::Outgoing) {
+        return Err(CFOPError::Operation(cfop, operation.clone()));
+    }
+    if first % 4 != destination.clone() as u32 {
+        return Err(CFOPError::Destination(cfop, destination.clone()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "tables")]
+    #[test]
+    fn test_catalog_sorted() {
+        assert!(CATALOG.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_validate_cfop() {
+        let outgoing = Operation::Outgoing;
+        assert_eq!(
+            validate_cfop(5102, &outgoing, &DestinationTarget::Internal),
+            Ok(())
+        );
+        assert_eq!(
+            validate_cfop(6108, &outgoing, &DestinationTarget::Interstate),
+            Ok(())
+        );
+        assert_eq!(
+            validate_cfop(7101, &outgoing, &DestinationTarget::External),
+            Ok(())
+        );
+        assert_eq!(
+            validate_cfop(1202, &Operation::Incoming, &DestinationTarget::Internal),
+            Ok(())
+        );
+
+        #[cfg(feature = "tables")]
+        assert_eq!(
+            validate_cfop(5199, &outgoing, &DestinationTarget::Internal),
+            Err(CFOPError::Unknown(5199))
+        );
+        assert_eq!(
+            validate_cfop(1102, &outgoing, &DestinationTarget::Internal),
+            Err(CFOPError::Operation(1102, Operation::Outgoing))
+        );
+        assert_eq!(
+            validate_cfop(5102, &outgoing, &DestinationTarget::Interstate),
+            Err(CFOPError::Destination(5102, DestinationTarget::Interstate))
+        );
+        assert_eq!(
+            validate_cfop(3102, &Operation::Incoming, &DestinationTarget::Internal),
+            Err(CFOPError::Destination(3102, DestinationTarget::Internal))
+        );
+    }
+}
@@ -0,0 +1,85 @@
+//! Global Trade Item Number (cEAN, cEANTrib) check digit
+//!
+//! The GTIN-8, GTIN-12 (UPC), GTIN-13 (EAN) and GTIN-14 codes share the GS1
+//! modulo 10 check digit. Products without a code inform "SEM GTIN".
+
+use std::fmt::Display;
+
+/// Code informed by products without a GTIN
+pub const NO_GTIN: &str = "SEM GTIN";
+
+/// Reason the GTIN was rejected
+#[derive(Debug, Clone, PartialEq)]
+pub enum GTINError {
+    /// Number of digits other than 8, 12, 13 or 14
+    Length(usize),
+    Character(char),
+    CheckDigit,
+}
+
+impl Display for GTINError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GTINError::Length(length) => write!(f, "Invalid GTIN length: {}", length),
+            GTINError::Character(c) => write!(f, "Invalid GTIN character: {}", c),
+            GTINError::CheckDigit => write!(f, "Invalid GTIN check digit"),
+        }
+    }
+}
+
+/// Checks the format and the check digit of the GTIN, accepting "SEM GTIN"
+pub fn validate_gtin(gtin: &str) -> Result<(), GTINError> {
+    if gtin == NO_GTIN {
+        return Ok(());
+    }
+    let digits = gtin
+        .chars()
+        .map(|c| c.to_digit(10).ok_or(GTINError::Character(c)))
+        .collect::<Result<Vec<_>, _>>()?;
+    if ![8, 12, 13, 14].contains(&digits.len()) {
+        return Err(GTINError::Length(digits.len()));
+    }
+
+    let (check, body) = digits.split_last().ok_or(GTINError::Length(0))?;
+    let sum: u32 = body
+        .iter()
+        .rev()
+        .zip([3, 1].into_iter().cycle())
+        .map(|(digit, weight)| digit * weight)
+        .sum();
+    match (10 - sum % 10) % 10 == *check {
+        true => Ok(()),
+        false => Err(GTINError::CheckDigit),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_gtin() {
+        for gtin in [
+            "7896235354499",
+            "96385074",
+            "036000291452",
+            "17896235354496",
+            NO_GTIN,
+        ] {
+            assert_eq!(validate_gtin(gtin), Ok(()), "{}", gtin);
+        }
+    }
+
+    #[test]
+    fn test_invalid_gtin() {
+        assert_eq!(validate_gtin("7896235354490"), Err(GTINError::CheckDigit));
+        assert_eq!(validate_gtin("789623535449"), Err(GTINError::CheckDigit));
+        assert_eq!(validate_gtin("78962353544"), Err(GTINError::Length(11)));
+        assert_eq!(validate_gtin(""), Err(GTINError::Length(0)));
+        assert_eq!(
+            validate_gtin("789623535449X"),
+            Err(GTINError::Character('X'))
+        );
+        assert_eq!(validate_gtin("sem gtin"), Err(GTINError::Character('s')));
+    }
+}
@@ -0,0 +1,442 @@
+//! State registration (Inscrição Estadual) check digits
+//!
+//! Every state defines its own length, prefixes and verifier digit algorithm,
+//! as published by SINTEGRA.
+
+use super::IE;
+use crate::states::State;
+use std::fmt::Display;
+
+/// Registration informed by exempt taxpayers
+pub const EXEMPT: &str = "ISENTO";
+
+/// Reason the state registration was rejected, along with the state
+#[derive(Debug, Clone, PartialEq)]
+pub enum IEError {
+    /// Number of digits not accepted by the state
+    Length(State, usize),
+    Character(State, char),
+    /// Digits the registrations of the state must start with
+    Prefix(State),
+    VerifierDigits(State),
+}
+
+impl IEError {
+    pub fn state(&self) -> &State {
+        match self {
+            IEError::Length(state, _)
+            | IEError::Character(state, _)
+            | IEError::Prefix(state)
+            | IEError::VerifierDigits(state) => state,
+        }
+    }
+}
+
+impl Display for IEError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IEError::Length(state, length) => {
+                write!(f, "Invalid IE length for {}: {}", state.acronym(), length)
+            }
+            IEError::Character(state, c) => {
+                write!(f, "Invalid IE character for {}: {}", state.acronym(), c)
+            }
+            IEError::Prefix(state) => write!(f, "Invalid IE prefix for {}", state.acronym()),
+            IEError::VerifierDigits(state) => {
+                write!(f, "Invalid IE verifier digits for {}", state.acronym())
+            }
+        }
+    }
+}
+
+enum Failure {
+    Length,
+    Prefix,
+    VerifierDigits,
+}
+
+const DESCENDING: [u32; 13] = [14, 13, 12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2];
+
+/// Weights from `first` down to 2
+fn descending(first: u32) -> &'static [u32] {
+    &DESCENDING[(14 - first) as usize..]
+}
+
+fn weighted(digits: &[u32], weights: &[u32]) -> u32 {
+    digits
+        .iter()
+        .zip(weights)
+        .map(|(digit, weight)| digit * weight)
+        .sum()
+}
+
+/// Usual modulo 11 digit, where the remainders 0 and 1 give 0
+fn modulo_11(sum: u32) -> u32 {
+    match sum % 11 {
+        0 | 1 => 0,
+        rest => 11 - rest,
+    }
+}
+
+fn number(digits: &[u32]) -> u32 {
+    digits.iter().fold(0, |number, digit| number * 10 + digit)
+}
+
+fn length(digits: &[u32], lengths: &[usize]) -> Result<(), Failure> {
+    match lengths.contains(&digits.len()) {
+        true => Ok(()),
+        false => Err(Failure::Length),
+    }
+}
+
+fn prefix(digits: &[u32], prefixes: &[u32]) -> Result<(), Failure> {
+    match prefixes.contains(&number(&digits[..2])) {
+        true => Ok(()),
+        false => Err(Failure::Prefix),
+    }
+}
+
+fn verifier(digit: u32, expected: u32) -> Result<(), Failure> {
+    match digit == expected {
+        true => Ok(()),
+        false => Err(Failure::VerifierDigits),
+    }
+}
+
+/// Single modulo 11 digit over the 8 first digits (CE, ES, PB, PI, SC, SE...)
+fn single_digit(digits: &[u32]) -> Result<(), Failure> {
+    length(digits, &[9])?;
+    verifier(digits[8], modulo_11(weighted(&digits[..8], descending(9))))
+}
+
+/// Two modulo 11 digits over 13 digits (AC, DF)
+fn thirteen_digits(digits: &[u32], prefixes: &[u32]) -> Result<(), Failure> {
+    length(digits, &[13])?;
+    prefix(digits, prefixes)?;
+    let weights = [5, 4, 3, 2, 9, 8, 7, 6, 5, 4, 3, 2];
+    verifier(
+        digits[11],
+        modulo_11(weighted(&digits[..11], &weights[1..])),
+    )?;
+    verifier(digits[12], modulo_11(weighted(&digits[..12], &weights)))
+}
+
+fn alagoas(digits: &[u32]) -> Result<(), Failure> {
+    length(digits, &[9])?;
+    prefix(digits, &[24])?;
+    let digit = weighted(&digits[..8], descending(9)) * 10 % 11;
+    verifier(digits[8], if digit == 10 { 0 } else { digit })
+}
+
+fn amapa(digits: &[u32]) -> Result<(), Failure> {
+    length(digits, &[9])?;
+    prefix(digits, &[3])?;
+    let (extra, eleven) = match number(&digits[..8]) {
+        ..=3017000 => (5, 0),
+        3017001..=3019022 => (9, 1),
+        _ => (0, 0),
+    };
+    let digit = match 11 - (extra + weighted(&digits[..8], descending(9))) % 11 {
+        10 => 0,
+        11 => eleven,
+        digit => digit,
+    };
+    verifier(digits[8], digit)
+}
+
+/// Second digit computed first, with modulo 10 or 11 depending on the first
+/// digit (the second one, for 9 digits)
+fn bahia(digits: &[u32]) -> Result<(), Failure> {
+    length(digits, &[8, 9])?;
+    let base = digits.len() - 2;
+    let modulo = match digits[digits.len() - 8] {
+        6 | 7 | 9 => 11,
+        _ => 10,
+    };
+    let digit = |sum: u32| match (modulo, sum % modulo) {
+        (10, 0) | (11, 0 | 1) => 0,
+        (_, rest) => modulo - rest,
+    };
+    let second = digit(weighted(&digits[..base], descending(base as u32 + 1)));
+    verifier(digits[base + 1], second)?;
+    let mut first = digits[..base].to_vec();
+    first.push(second);
+    verifier(
+        digits[base],
+        digit(weighted(&first, descending(base as u32 + 2))),
+    )
+}
+
+fn goias(digits: &[u32]) -> Result<(), Failure> {
+    length(digits, &[9])?;
+    prefix(digits, &[10, 11, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29])?;
+    let digit = match weighted(&digits[..8], descending(9)) % 11 {
+        0 => 0,
+        1 if (10103105..=10119997).contains(&number(&digits[..8])) => 1,
+        1 => 0,
+        rest => 11 - rest,
+    };
+    verifier(digits[8], digit)
+}
+
+fn maranhao_para(digits: &[u32], state_prefix: u32) -> Result<(), Failure> {
+    length(digits, &[9])?;
+    prefix(digits, &[state_prefix])?;
+    single_digit(digits)
+}
+
+/// Municipality (3) + registration (6) + order (2) + 2 verifier digits
+fn minas_gerais(digits: &[u32]) -> Result<(), Failure> {
+    length(digits, &[13])?;
+    // A zero is inserted after the municipality and the digits of the
+    // products, weighted 1 and 2 alternately, are added up
+    let sum: u32 = digits[..3]
+        .iter()
+        .chain(&[0])
+        .chain(&digits[3..11])
+        .zip([1, 2].iter().cycle())
+        .map(|(digit, weight)| digit * weight)
+        .map(|product| product / 10 + product % 10)
+        .sum();
+    verifier(digits[11], (10 - sum % 10) % 10)?;
+    let weights = [3, 2, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2];
+    verifier(digits[12], modulo_11(weighted(&digits[..12], &weights)))
+}
+
+fn mato_grosso(digits: &[u32]) -> Result<(), Failure> {
+    // Shorter registrations are completed with zeros on the left
+    let mut padded = vec![0; 11usize.saturating_sub(digits.len())];
+    padded.extend_from_slice(digits);
+    length(&padded, &[11])?;
+    verifier(
+        padded[10],
+        modulo_11(weighted(&padded[..10], &[3, 2, 9, 8, 7, 6, 5, 4, 3, 2])),
+    )
+}
+
+fn parana(digits: &[u32]) -> Result<(), Failure> {
+    length(digits, &[10])?;
+    let weights = [4, 3, 2, 7, 6, 5, 4, 3, 2];
+    verifier(digits[8], modulo_11(weighted(&digits[..8], &weights[1..])))?;
+    verifier(digits[9], modulo_11(weighted(&digits[..9], &weights)))
+}
+
+fn pernambuco(digits: &[u32]) -> Result<(), Failure> {
+    length(digits, &[9])?;
+    verifier(digits[7], modulo_11(weighted(&digits[..7], descending(8))))?;
+    verifier(digits[8], modulo_11(weighted(&digits[..8], descending(9))))
+}
+
+fn rio_de_janeiro(digits: &[u32]) -> Result<(), Failure> {
+    length(digits, &[8])?;
+    verifier(
+        digits[7],
+        modulo_11(weighted(&digits[..7], &[2, 7, 6, 5, 4, 3, 2])),
+    )
+}
+
+fn rio_grande_do_norte(digits: &[u32]) -> Result<(), Failure> {
+    length(digits, &[9, 10])?;
+    prefix(digits, &[20])?;
+    let base = digits.len() - 1;
+    let digit = weighted(&digits[..base], descending(base as u32 + 1)) * 10 % 11;
+    verifier(digits[base], if digit == 10 { 0 } else { digit })
+}
+
+fn rio_grande_do_sul(digits: &[u32]) -> Result<(), Failure> {
+    length(digits, &[10])?;
+    verifier(
+        digits[9],
+        modulo_11(weighted(&digits[..9], &[2, 9, 8, 7, 6, 5, 4, 3, 2])),
+    )
+}
+
+fn rondonia(digits: &[u32]) -> Result<(), Failure> {
+    length(digits, &[14])?;
+    let weights = [6, 5, 4, 3, 2, 9, 8, 7, 6, 5, 4, 3, 2];
+    let digit = 11 - weighted(&digits[..13], &weights) % 11;
+    verifier(digits[13], if digit >= 10 { digit - 10 } else { digit })
+}
+
+fn roraima(digits: &[u32]) -> Result<(), Failure> {
+    length(digits, &[9])?;
+    prefix(digits, &[24])?;
+    verifier(
+        digits[8],
+        weighted(&digits[..8], &[1, 2, 3, 4, 5, 6, 7, 8]) % 9,
+    )
+}
+
+/// The verifier digits of São Paulo are the rightmost digit of the remainder
+fn sao_paulo(digits: &[u32]) -> Result<(), Failure> {
+    length(digits, &[12])?;
+    let weights = [1, 3, 4, 5, 6, 7, 8, 10];
+    verifier(digits[8], weighted(&digits[..8], &weights) % 11 % 10)?;
+    let weights = [3, 2, 10, 9, 8, 7, 6, 5, 4, 3, 2];
+    verifier(digits[11], weighted(&digits[..11], &weights) % 11 % 10)
+}
+
+/// Rural producers of São Paulo (P-00000000.0/000)
+fn sao_paulo_rural(digits: &[u32]) -> Result<(), Failure> {
+    length(digits, &[12])?;
+    let weights = [1, 3, 4, 5, 6, 7, 8, 10];
+    verifier(digits[8], weighted(&digits[..8], &weights) % 11 % 10)
+}
+
+fn tocantins(digits: &[u32]) -> Result<(), Failure> {
+    length(digits, &[9, 11])?;
+    if digits.len() == 9 {
+        return single_digit(digits);
+    }
+    // The former format carries the kind of company, left out of the digit
+    if ![1, 2, 3, 99].contains(&number(&digits[2..4])) {
+        return Err(Failure::Prefix);
+    }
+    let digits: Vec<u32> = digits[..2].iter().chain(&digits[4..]).copied().collect();
+    single_digit(&digits)
+}
+
+impl IE {
+    /// Whether the taxpayer is exempt of registration ("ISENTO")
+    pub fn is_exempt(&self) -> bool {
+        self.0 == EXEMPT
+    }
+
+    /// Checks the registration against the rules of the state
+    ///
+    /// Formatting characters (`.`, `/`, `-`) are ignored and "ISENTO" is
+    /// accepted for every state.
+    pub fn validate(&self, state: &State) -> Result<(), IEError> {
+        if self.is_exempt() {
+            return Ok(());
+        }
+        let (rural, value) = match self.0.strip_prefix('P') {
+            Some(value) if *state == State::SaoPaulo => (true, value),
+            _ => (false, self.0.as_str()),
+        };
+        let digits = value
+            .chars()
+            .filter(|c| !matches!(c, '.' | '/' | '-') && !c.is_whitespace())
+            .map(|c| c.to_digit(10).ok_or(IEError::Character(state.clone(), c)))
+            .collect::<Result<Vec<_>, _>>()?;
+        if digits.len() < 2 {
+            return Err(IEError::Length(state.clone(), digits.len()));
+        }
+
+        let result = match state {
+            State::Acre => thirteen_digits(&digits, &[1]),
+            State::Alagoas => alagoas(&digits),
+            State::Amapa => amapa(&digits),
+            State::Bahia => bahia(&digits),
+            State::DistritoFederal => thirteen_digits(&digits, &[7]),
+            State::Goias => goias(&digits),
+            State::Maranhao => maranhao_para(&digits, 12),
+            State::MatoGrosso => mato_grosso(&digits),
+            State::MatoGrossoDoSul => length(&digits, &[9])
+                .and_then(|_| prefix(&digits, &[28, 50]))
+                .and_then(|_| single_digit(&digits)),
+            State::MinasGerais => minas_gerais(&digits),
+            State::Para => maranhao_para(&digits, 15),
+            State::Parana => parana(&digits),
+            State::Pernambuco => pernambuco(&digits),
+            State::RioDeJaneiro => rio_de_janeiro(&digits),
+            State::RioGrandeDoNorte => rio_grande_do_norte(&digits),
+            State::RioGrandeDoSul => rio_grande_do_sul(&digits),
+            State::Rondonia => rondonia(&digits),
+            State::Roraima => roraima(&digits),
+            State::SaoPaulo if rural => sao_paulo_rural(&digits),
+            State::SaoPaulo => sao_paulo(&digits),
+            State::Tocantins => tocantins(&digits),
+            State::Amazonas
+            | State::Ceara
+            | State::EspiritoSanto
+            | State::Paraiba
+            | State::Piaui
+            | State::SantaCatarina
+            | State::Sergipe => single_digit(&digits),
+        };
+        result.map_err(|failure| match failure {
+            Failure::Length => IEError::Length(state.clone(), digits.len()),
+            Failure::Prefix => IEError::Prefix(state.clone()),
+            Failure::VerifierDigits => IEError::VerifierDigits(state.clone()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_ie() {
+        let registrations = [
+            (State::Acre, "01.004.823/001-12"),
+            (State::Alagoas, "240000048"),
+            (State::Amapa, "030123459"),
+            (State::Amazonas, "999999990"),
+            (State::Bahia, "123456-63"),
+            (State::Bahia, "612345-57"),
+            (State::Bahia, "1000003-06"),
+            (State::Ceara, "06000001-5"),
+            (State::DistritoFederal, "0730000100109"),
+            (State::EspiritoSanto, "999999990"),
+            (State::Goias, "10.987.654-7"),
+            (State::Maranhao, "120000385"),
+            (State::MatoGrosso, "0013000001-9"),
+            (State::MatoGrossoDoSul, "280000006"),
+            (State::MinasGerais, "062.307.904/0081"),
+            (State::Para, "15-999999-5"),
+            (State::Paraiba, "06000001-5"),
+            (State::Parana, "123.45678-50"),
+            (State::Pernambuco, "0321418-40"),
+            (State::Piaui, "012345679"),
+            (State::RioDeJaneiro, "99.999.99-3"),
+            (State::RioGrandeDoNorte, "20.040.040-1"),
+            (State::RioGrandeDoNorte, "20.0.040.040-0"),
+            (State::RioGrandeDoSul, "224/3658792"),
+            (State::Rondonia, "0000000062521-3"),
+            (State::Roraima, "24006628-1"),
+            (State::SantaCatarina, "251.040.852"),
+            (State::SaoPaulo, "110.042.490.114"),
+            (State::SaoPaulo, "P-01100424.3/002"),
+            (State::Sergipe, "27123456-3"),
+            (State::Tocantins, "29.01.022783-6"),
+            (State::Tocantins, "29022783-6"),
+            (State::SaoPaulo, EXEMPT),
+        ];
+        for (state, registration) in registrations {
+            let ie = IE(registration.to_string());
+            assert_eq!(ie.validate(&state), Ok(()), "{}", registration);
+        }
+    }
+
+    #[test]
+    fn test_invalid_ie() {
+        let validate =
+            |state: State, registration: &str| IE(registration.to_string()).validate(&state);
+        assert_eq!(
+            validate(State::MinasGerais, "0623079040082"),
+            Err(IEError::VerifierDigits(State::MinasGerais))
+        );
+        assert_eq!(
+            validate(State::MinasGerais, "123456789"),
+            Err(IEError::Length(State::MinasGerais, 9))
+        );
+        assert_eq!(
+            validate(State::SaoPaulo, "110.042.490.115"),
+            Err(IEError::VerifierDigits(State::SaoPaulo))
+        );
+        assert_eq!(
+            validate(State::Goias, "309876547"),
+            Err(IEError::Prefix(State::Goias))
+        );
+        assert_eq!(
+            validate(State::Parana, "12345678A0"),
+            Err(IEError::Character(State::Parana, 'A'))
+        );
+        assert_eq!(
+            validate(State::RioDeJaneiro, "P9999993"),
+            Err(IEError::Character(State::RioDeJaneiro, 'P'))
+        );
+    }
+}
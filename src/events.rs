@@ -0,0 +1,1761 @@
+//! Events registered against an authorized NF-e (envEvento)
+//!
+//! Each event is identified by its type (tpEvento), the access key of the
+//! document and a sequence number (nSeqEvento), and is signed the same way as
+//! the document itself, referencing the `Id` of `infEvento`.
+//!
+//! Only the payload (detEvento) differs between the types of events, so the
+//! information and the signed event are generic over it, see `EventPayload`.
+
+use crate::LIBRARY_VERSION;
+use crate::access_key::AccessKey;
+#[cfg(feature = "sign")]
+use crate::config::Config;
+use crate::config::ConfigError;
+use crate::enums::{Environment, Model, PersonDocument};
+use crate::models::{Authorized, NFE_NAMESPACE, NFeProc, Signature};
+#[cfg(feature = "sign")]
+use crate::sign::{Certificate, SignError, Signer, sign};
+use crate::states::State;
+#[cfg(feature = "sign")]
+use crate::xml::to_namespaced_xml;
+use chrono::{DateTime, FixedOffset, SecondsFormat, TimeDelta};
+use nf_e_macros::CodedEnum;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize, ser::SerializeStruct};
+
+pub const EVENT_VERSION: &str = "1.00";
+
+/// Maximum number of events of the same type for the same access key
+pub const MAX_SEQUENCE: u8 = 20;
+
+/// Code of the Ambiente Nacional as the organ receiving the event (cOrgao)
+pub const NATIONAL_ORGAN: u8 = 91;
+
+/// Conditions of use of the correction letter (xCondUso), fixed by the layout
+pub const CORRECTION_CONDITIONS: &str = concat!(
+    "A Carta de Correcao e disciplinada pelo paragrafo 1o-A do art. 7o do Convenio S/N, ",
+    "de 15 de dezembro de 1970 e pode ser utilizada para regularizacao de erro ocorrido ",
+    "na emissao de documento fiscal, desde que o erro nao esteja relacionado com: ",
+    "I - as variaveis que determinam o valor do imposto tais como: base de calculo, ",
+    "aliquota, diferenca de preco, quantidade, valor da operacao ou da prestacao; ",
+    "II - a correcao de dados cadastrais que implique mudanca do remetente ou do ",
+    "destinatario; III - a data de emissao ou de saida."
+);
+
+/// Conditions of use of the interested actor (xCondUso), fixed by the layout and
+/// only informed when the transporter may authorize other transporters
+pub const INTERESTED_ACTOR_CONDITIONS: &str = concat!(
+    "O emitente ou destinatario da NF-e, declara que permite o transportador declarado ",
+    "no campo CNPJ/CPF deste evento a autorizar os transportadores subcontratados ou ",
+    "redespachados a terem acesso ao download da NF-e"
+);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventError {
+    /// Length of a justification outside of 15..=255 characters
+    InvalidJustification(usize),
+    /// Length of a correction outside of 15..=1000 characters
+    InvalidCorrection(usize),
+    /// Sequence outside of 1..=20
+    InvalidSequence(u8),
+    ConfigError(ConfigError),
+    #[cfg(feature = "sign")]
+    Sign(SignError),
+}
+
+/// Rule of SEFAZ that prevents the cancellation of a document, see
+/// `CancelEvent::for_document`
+#[derive(Debug, Clone, PartialEq)]
+pub enum CancellationError {
+    /// Status code (cStat) of the protocol of a document that is not authorized
+    NotAuthorized(u16),
+    /// Deadline of the cancellation, already past
+    Expired(DateTime<FixedOffset>),
+    /// Protocol (nProt) of the cancellation already registered
+    AlreadyCancelled(Option<String>),
+    /// Event registered for the document that prevents its cancellation, as
+    /// the confirmation of the operation by the recipient
+    Blocked(EventType),
+    Event(EventError),
+}
+
+/// Deadlines of the cancellation of the documents, after their authorization
+///
+/// The NF-e may be cancelled up to 24 hours after the authorization and the
+/// NFC-e up to 30 minutes, but some states set deadlines of their own.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CancellationWindow {
+    nfe: TimeDelta,
+    nfce: TimeDelta,
+    states: Vec<(State, Model, TimeDelta)>,
+}
+
+impl Default for CancellationWindow {
+    fn default() -> Self {
+        CancellationWindow {
+            nfe: TimeDelta::hours(24),
+            nfce: TimeDelta::minutes(30),
+            states: Vec::new(),
+        }
+    }
+}
+
+impl CancellationWindow {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Deadline of the documents of `model` issued in `state`
+    pub fn with_state(mut self, state: State, model: Model, window: TimeDelta) -> Self {
+        self.states
+            .retain(|(s, m, _)| !(*s == state && *m == model));
+        self.states.push((state, model, window));
+        self
+    }
+
+    /// Time to cancel a document of `model` issued in `state`
+    pub fn window(&self, state: &State, model: &Model) -> TimeDelta {
+        self.states
+            .iter()
+            .find(|(s, m, _)| s == state && m == model)
+            .map(|(_, _, window)| *window)
+            .unwrap_or(match model {
+                Model::NFe => self.nfe,
+                Model::NFCe => self.nfce,
+            })
+    }
+}
+
+/// Type of the event (tpEvento)
+///
+/// The codes not known by the crate, as the ones of custom payloads, are
+/// read as `Other`, use `EventType::from` to build a type from its code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "u32", into = "u32")]
+pub enum EventType {
+    Correction,
+    Cancellation,
+    InterestedActor,
+    Confirmation,
+    Awareness,
+    Unknown,
+    NotPerformed,
+    Other(u32),
+}
+
+impl EventType {
+    /// Description of the event (descEvento), empty for the types not known
+    /// by the crate
+    pub fn description(&self) -> &'static str {
+        match self {
+            EventType::Correction => "Carta de Correcao",
+            EventType::Cancellation => "Cancelamento",
+            EventType::InterestedActor => "Ator interessado na NF-e",
+            EventType::Confirmation => "Confirmacao da Operacao",
+            EventType::Awareness => "Ciencia da Operacao",
+            EventType::Unknown => "Desconhecimento da Operacao",
+            EventType::NotPerformed => "Operacao nao Realizada",
+            EventType::Other(_) => "",
+        }
+    }
+
+    /// Whether the type is one of the crate, not `Other`
+    pub fn is_known(&self) -> bool {
+        !matches!(self, EventType::Other(_))
+    }
+
+    /// Manifestation of the recipient, registered in the Ambiente Nacional
+    pub fn is_manifestation(&self) -> bool {
+        matches!(
+            self,
+            EventType::Confirmation
+                | EventType::Awareness
+                | EventType::Unknown
+                | EventType::NotPerformed
+        )
+    }
+}
+
+impl From<u32> for EventType {
+    fn from(value: u32) -> Self {
+        match value {
+            110110 => EventType::Correction,
+            110111 => EventType::Cancellation,
+            110150 => EventType::InterestedActor,
+            210200 => EventType::Confirmation,
+            210210 => EventType::Awareness,
+            210220 => EventType::Unknown,
+            210240 => EventType::NotPerformed,
+            other => EventType::Other(other),
+        }
+    }
+}
+
+impl From<EventType> for u32 {
+    fn from(value: EventType) -> Self {
+        match value {
+            EventType::Correction => 110110,
+            EventType::Cancellation => 110111,
+            EventType::InterestedActor => 110150,
+            EventType::Confirmation => 210200,
+            EventType::Awareness => 210210,
+            EventType::Unknown => 210220,
+            EventType::NotPerformed => 210240,
+            EventType::Other(code) => code,
+        }
+    }
+}
+
+/// Payload of a cancellation event
+///
+/// protocol: Authorization protocol of the document (nProt)
+/// justification: Reason of the cancellation (xJust)
+#[derive(Debug, Clone, PartialEq)]
+pub struct CancellationDetail {
+    pub protocol: String,
+    pub justification: String,
+}
+
+/// Payload of a correction letter (CC-e)
+///
+/// correction: Text of the correction (xCorrecao)
+/// The conditions of use (xCondUso) are always `CORRECTION_CONDITIONS`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorrectionDetail {
+    pub correction: String,
+}
+
+/// Author of the interested actor event (tpAutor)
+#[derive(CodedEnum, PartialEq, Debug, Clone)]
+pub enum ActorAuthor {
+    Issuer = 1,
+    Recipient = 2,
+    /// Transporter authorized by the issuer or the recipient to authorize others
+    Transporter = 3,
+}
+
+/// Permission of the interested transporter to authorize others (tpAutorizacao)
+#[derive(CodedEnum, PartialEq, Debug, Clone)]
+pub enum ActorAuthorization {
+    NotAllowed = 0,
+    /// Subcontracted and redispatching transporters may be authorized too
+    Allowed = 1,
+}
+
+/// Payload of the interested actor (110150), a transporter allowed to download
+/// the XML of the document
+///
+/// author_organ: IBGE code of the state of the author (cOrgaoAutor)
+/// author: Type of the author (tpAutor)
+/// application_version: Version of the application of the author (verAplic)
+/// actor: Document of the transporter (autXML)
+/// authorization: Permission to authorize other transporters (tpAutorizacao)
+/// The conditions of use (xCondUso) are `INTERESTED_ACTOR_CONDITIONS` when allowed
+#[derive(Debug, Clone, PartialEq)]
+pub struct InterestedActorDetail {
+    pub author_organ: u8,
+    pub author: ActorAuthor,
+    pub application_version: String,
+    pub actor: PersonDocument,
+    pub authorization: ActorAuthorization,
+}
+
+/// Payload of a manifestation of the recipient
+///
+/// Only the operation not performed carries a justification (xJust)
+#[derive(Debug, Clone, PartialEq)]
+pub enum ManifestationDetail {
+    Confirmation,
+    Awareness,
+    Unknown,
+    NotPerformed(String),
+}
+
+impl ManifestationDetail {
+    pub fn event_type(&self) -> EventType {
+        match self {
+            ManifestationDetail::Confirmation => EventType::Confirmation,
+            ManifestationDetail::Awareness => EventType::Awareness,
+            ManifestationDetail::Unknown => EventType::Unknown,
+            ManifestationDetail::NotPerformed(_) => EventType::NotPerformed,
+        }
+    }
+}
+
+/// Payload of an event of a type not known by the crate, as the ones
+/// registered by SEFAZ itself, of which only the description is read
+///
+/// event_type: Type of the event (tpEvento)
+/// description: Description of the event (descEvento)
+#[derive(Debug, Clone, PartialEq)]
+pub struct OtherDetail {
+    pub event_type: u32,
+    pub description: String,
+}
+
+/// Payload of the event (detEvento)
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventDetail {
+    Correction(CorrectionDetail),
+    Cancellation(CancellationDetail),
+    InterestedActor(InterestedActorDetail),
+    Manifestation(ManifestationDetail),
+    Other(OtherDetail),
+}
+
+impl EventDetail {
+    pub fn event_type(&self) -> EventType {
+        match self {
+            EventDetail::Correction(_) => EventType::Correction,
+            EventDetail::Cancellation(_) => EventType::Cancellation,
+            EventDetail::InterestedActor(_) => EventType::InterestedActor,
+            EventDetail::Manifestation(detail) => detail.event_type(),
+            EventDetail::Other(detail) => EventType::from(detail.event_type),
+        }
+    }
+}
+
+/// Payload of an event (detEvento), the only part that differs between its types
+///
+/// The information (infEvento), its identifier and the signature are shared by
+/// every payload, so a new type of event only defines its payload and
+/// implements this trait, to be carried by `EventInfo` and `Event`.
+pub trait EventPayload: Sized {
+    /// Fields of detEvento, read before the payload is checked against tpEvento
+    type Fields: DeserializeOwned;
+
+    /// Type of the event (tpEvento), `EventType::Other` for the types not
+    /// known by the crate
+    fn event_type(&self) -> EventType;
+
+    /// Description of the event (descEvento), to be given by the payloads of
+    /// the types not known by the crate
+    fn description(&self) -> &str {
+        self.event_type().description()
+    }
+
+    /// Serializes the fields of detEvento that follow descEvento
+    fn serialize_fields<S: SerializeStruct>(&self, state: &mut S) -> Result<(), S::Error>;
+
+    /// Payload of an event of the type read from tpEvento
+    fn from_fields<E: serde::de::Error>(
+        event_type: EventType,
+        fields: Self::Fields,
+    ) -> Result<Self, E>;
+}
+
+impl EventPayload for EventDetail {
+    type Fields = DetailFields;
+
+    fn event_type(&self) -> EventType {
+        EventDetail::event_type(self)
+    }
+
+    fn description(&self) -> &str {
+        match self {
+            EventDetail::Other(detail) => &detail.description,
+            _ => self.event_type().description(),
+        }
+    }
+
+    fn serialize_fields<S: SerializeStruct>(&self, state: &mut S) -> Result<(), S::Error> {
+        match self {
+            EventDetail::Correction(detail) => detail.serialize_fields(state),
+            EventDetail::Cancellation(detail) => detail.serialize_fields(state),
+            EventDetail::InterestedActor(detail) => detail.serialize_fields(state),
+            EventDetail::Manifestation(detail) => detail.serialize_fields(state),
+            EventDetail::Other(_) => Ok(()),
+        }
+    }
+
+    fn from_fields<E: serde::de::Error>(
+        event_type: EventType,
+        fields: DetailFields,
+    ) -> Result<Self, E> {
+        match event_type {
+            EventType::Correction => Ok(EventDetail::Correction(CorrectionDetail {
+                correction: fields
+                    .correction
+                    .ok_or_else(|| E::missing_field("xCorrecao"))?,
+            })),
+            EventType::Cancellation => Ok(EventDetail::Cancellation(CancellationDetail {
+                protocol: fields.protocol.ok_or_else(|| E::missing_field("nProt"))?,
+                justification: fields
+                    .justification
+                    .ok_or_else(|| E::missing_field("xJust"))?,
+            })),
+            EventType::InterestedActor => Ok(EventDetail::InterestedActor(InterestedActorDetail {
+                author_organ: fields
+                    .author_organ
+                    .ok_or_else(|| E::missing_field("cOrgaoAutor"))?,
+                author: fields.author.ok_or_else(|| E::missing_field("tpAutor"))?,
+                application_version: fields
+                    .application_version
+                    .ok_or_else(|| E::missing_field("verAplic"))?,
+                actor: fields
+                    .actor
+                    .and_then(|actor| actor.documents.into_iter().next())
+                    .ok_or_else(|| E::missing_field("autXML"))?,
+                authorization: fields
+                    .authorization
+                    .ok_or_else(|| E::missing_field("tpAutorizacao"))?,
+            })),
+            EventType::Confirmation => Ok(EventDetail::Manifestation(
+                ManifestationDetail::Confirmation,
+            )),
+            EventType::Awareness => Ok(EventDetail::Manifestation(ManifestationDetail::Awareness)),
+            EventType::Unknown => Ok(EventDetail::Manifestation(ManifestationDetail::Unknown)),
+            EventType::NotPerformed => Ok(EventDetail::Manifestation(
+                ManifestationDetail::NotPerformed(
+                    fields
+                        .justification
+                        .ok_or_else(|| E::missing_field("xJust"))?,
+                ),
+            )),
+            EventType::Other(code) => Ok(EventDetail::Other(OtherDetail {
+                event_type: code,
+                description: fields.description.unwrap_or_default(),
+            })),
+        }
+    }
+}
+
+impl EventPayload for CorrectionDetail {
+    type Fields = DetailFields;
+
+    fn event_type(&self) -> EventType {
+        EventType::Correction
+    }
+
+    fn serialize_fields<S: SerializeStruct>(&self, state: &mut S) -> Result<(), S::Error> {
+        state.serialize_field("xCorrecao", &self.correction)?;
+        state.serialize_field("xCondUso", CORRECTION_CONDITIONS)
+    }
+
+    fn from_fields<E: serde::de::Error>(
+        event_type: EventType,
+        fields: DetailFields,
+    ) -> Result<Self, E> {
+        match EventDetail::from_fields(event_type, fields)? {
+            EventDetail::Correction(detail) => Ok(detail),
+            _ => Err(unexpected_type(event_type)),
+        }
+    }
+}
+
+impl EventPayload for CancellationDetail {
+    type Fields = DetailFields;
+
+    fn event_type(&self) -> EventType {
+        EventType::Cancellation
+    }
+
+    fn serialize_fields<S: SerializeStruct>(&self, state: &mut S) -> Result<(), S::Error> {
+        state.serialize_field("nProt", &self.protocol)?;
+        state.serialize_field("xJust", &self.justification)
+    }
+
+    fn from_fields<E: serde::de::Error>(
+        event_type: EventType,
+        fields: DetailFields,
+    ) -> Result<Self, E> {
+        match EventDetail::from_fields(event_type, fields)? {
+            EventDetail::Cancellation(detail) => Ok(detail),
+            _ => Err(unexpected_type(event_type)),
+        }
+    }
+}
+
+impl EventPayload for InterestedActorDetail {
+    type Fields = DetailFields;
+
+    fn event_type(&self) -> EventType {
+        EventType::InterestedActor
+    }
+
+    fn serialize_fields<S: SerializeStruct>(&self, state: &mut S) -> Result<(), S::Error> {
+        state.serialize_field("cOrgaoAutor", &self.author_organ)?;
+        state.serialize_field("tpAutor", &self.author)?;
+        state.serialize_field("verAplic", &self.application_version)?;
+        state.serialize_field(
+            "autXML",
+            &Authorized {
+                documents: vec![self.actor.clone()],
+            },
+        )?;
+        state.serialize_field("tpAutorizacao", &self.authorization)?;
+        if self.authorization == ActorAuthorization::Allowed {
+            state.serialize_field("xCondUso", INTERESTED_ACTOR_CONDITIONS)?;
+        }
+        Ok(())
+    }
+
+    fn from_fields<E: serde::de::Error>(
+        event_type: EventType,
+        fields: DetailFields,
+    ) -> Result<Self, E> {
+        match EventDetail::from_fields(event_type, fields)? {
+            EventDetail::InterestedActor(detail) => Ok(detail),
+            _ => Err(unexpected_type(event_type)),
+        }
+    }
+}
+
+impl EventPayload for ManifestationDetail {
+    type Fields = DetailFields;
+
+    fn event_type(&self) -> EventType {
+        ManifestationDetail::event_type(self)
+    }
+
+    fn serialize_fields<S: SerializeStruct>(&self, state: &mut S) -> Result<(), S::Error> {
+        match self {
+            ManifestationDetail::NotPerformed(justification) => {
+                state.serialize_field("xJust", justification)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn from_fields<E: serde::de::Error>(
+        event_type: EventType,
+        fields: DetailFields,
+    ) -> Result<Self, E> {
+        match EventDetail::from_fields(event_type, fields)? {
+            EventDetail::Manifestation(detail) => Ok(detail),
+            _ => Err(unexpected_type(event_type)),
+        }
+    }
+}
+
+fn unexpected_type<E: serde::de::Error>(event_type: EventType) -> E {
+    E::custom(format!("Unexpected event type: {}", u32::from(event_type)))
+}
+
+/// Fields of every `detEvento` of the types of `EventDetail`, checked against
+/// the type of the event
+#[derive(Deserialize)]
+pub struct DetailFields {
+    #[serde(rename = "descEvento")]
+    description: Option<String>,
+    #[serde(rename = "nProt")]
+    protocol: Option<String>,
+    #[serde(rename = "xJust")]
+    justification: Option<String>,
+    #[serde(rename = "xCorrecao")]
+    correction: Option<String>,
+    #[serde(rename = "cOrgaoAutor")]
+    author_organ: Option<u8>,
+    #[serde(rename = "tpAutor")]
+    author: Option<ActorAuthor>,
+    #[serde(rename = "verAplic")]
+    application_version: Option<String>,
+    #[serde(rename = "autXML")]
+    actor: Option<Authorized>,
+    #[serde(rename = "tpAutorizacao")]
+    authorization: Option<ActorAuthorization>,
+}
+
+/// detEvento of the payload, with the version and the description of its type
+struct DetailElement<'a, TDet>(&'a TDet);
+
+impl<TDet: EventPayload> Serialize for DetailElement<'_, TDet> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("detEvento", 4)?;
+        state.serialize_field("@versao", EVENT_VERSION)?;
+        state.serialize_field("descEvento", self.0.description())?;
+        self.0.serialize_fields(&mut state)?;
+        state.end()
+    }
+}
+
+/// Information of the event (infEvento)
+///
+/// The payload defaults to `EventDetail`, which reads any type of event known
+/// by the crate, as the ones returned by the queries.
+///
+/// organ: IBGE code of the organ receiving the event (cOrgao)
+/// environment: Environment type (tpAmb)
+/// author: Document of the author of the event (CNPJ or CPF)
+/// access_key: Access key of the NF-e (chNFe)
+/// date: Date and time of the event (dhEvento)
+/// sequence: Sequence of the event for the same type and key (nSeqEvento)
+/// detail: Payload of the event (detEvento)
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventInfo<TDet = EventDetail> {
+    pub organ: u8,
+    pub environment: Environment,
+    pub author: PersonDocument,
+    pub access_key: AccessKey,
+    pub date: DateTime<FixedOffset>,
+    pub sequence: u8,
+    pub detail: TDet,
+}
+
+impl<TDet: EventPayload> EventInfo<TDet> {
+    pub fn event_type(&self) -> EventType {
+        self.detail.event_type()
+    }
+
+    /// Identifier of the event: "ID" + tpEvento + chNFe + nSeqEvento
+    pub fn id(&self) -> String {
+        format!(
+            "ID{}{}{:02}",
+            u32::from(self.event_type()),
+            self.access_key,
+            self.sequence
+        )
+    }
+}
+
+impl<TDet: EventPayload> Serialize for EventInfo<TDet> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("infEvento", 10)?;
+        state.serialize_field("@Id", &self.id())?;
+        state.serialize_field("cOrgao", &self.organ)?;
+        state.serialize_field("tpAmb", &self.environment)?;
+        state.serialize_field("$value", &self.author)?;
+        state.serialize_field("chNFe", &self.access_key)?;
+        state.serialize_field(
+            "dhEvento",
+            &self.date.to_rfc3339_opts(SecondsFormat::Secs, false),
+        )?;
+        state.serialize_field("tpEvento", &self.event_type())?;
+        state.serialize_field("nSeqEvento", &self.sequence)?;
+        state.serialize_field("verEvento", EVENT_VERSION)?;
+        state.serialize_field("detEvento", &DetailElement(&self.detail))?;
+        state.end()
+    }
+}
+
+impl<'de, TDet: EventPayload> Deserialize<'de> for EventInfo<TDet> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(bound = "")]
+        struct EventInfoHelper<TDet: EventPayload> {
+            #[serde(rename = "cOrgao")]
+            organ: u8,
+            #[serde(rename = "tpAmb")]
+            environment: Environment,
+            #[serde(rename = "$value")]
+            author: PersonDocument,
+            #[serde(rename = "chNFe")]
+            access_key: AccessKey,
+            #[serde(rename = "dhEvento")]
+            date: DateTime<FixedOffset>,
+            #[serde(rename = "tpEvento")]
+            event_type: EventType,
+            #[serde(rename = "nSeqEvento")]
+            sequence: u8,
+            #[serde(rename = "verEvento")]
+            _version: String,
+            #[serde(rename = "detEvento")]
+            detail: TDet::Fields,
+        }
+
+        let helper = EventInfoHelper::<TDet>::deserialize(deserializer)?;
+        Ok(EventInfo {
+            organ: helper.organ,
+            environment: helper.environment,
+            author: helper.author,
+            access_key: helper.access_key,
+            date: helper.date,
+            sequence: helper.sequence,
+            detail: TDet::from_fields(helper.event_type, helper.detail)?,
+        })
+    }
+}
+
+/// Signed event (evento)
+///
+/// info: Information of the event (infEvento)
+/// signature: Enveloped signature of `info` (Signature)
+#[derive(Debug, PartialEq)]
+pub struct Event<TDet = EventDetail> {
+    pub info: EventInfo<TDet>,
+    pub signature: Signature,
+}
+
+#[cfg(feature = "sign")]
+impl<TDet: EventPayload> Event<TDet> {
+    /// Signs the event with the certificate of the global configuration
+    pub fn new(info: EventInfo<TDet>) -> Result<Self, EventError> {
+        let certificate = Certificate::from_config().map_err(EventError::Sign)?;
+        Self::with_certificate(info, &certificate).map_err(EventError::Sign)
+    }
+
+    pub fn with_certificate(
+        info: EventInfo<TDet>,
+        certificate: &Certificate,
+    ) -> Result<Self, SignError> {
+        Self::with_signer(info, certificate)
+    }
+
+    /// Signs with the key of `signer`, as the one of an A3 token
+    pub fn with_signer(info: EventInfo<TDet>, signer: &dyn Signer) -> Result<Self, SignError> {
+        let element = to_namespaced_xml("infEvento", NFE_NAMESPACE, &info)
+            .map_err(|e| SignError::Serialization(e.to_string()))?;
+        let signature = sign(&element, &info.id(), signer)?;
+        Ok(Self { info, signature })
+    }
+}
+
+impl<TDet: EventPayload> Serialize for Event<TDet> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("evento", 4)?;
+        state.serialize_field("@xmlns", NFE_NAMESPACE)?;
+        state.serialize_field("@versao", EVENT_VERSION)?;
+        state.serialize_field("infEvento", &self.info)?;
+        state.serialize_field("Signature", &self.signature)?;
+        state.end()
+    }
+}
+
+impl<'de, TDet: EventPayload> Deserialize<'de> for Event<TDet> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(bound = "")]
+        struct EventHelper<TDet: EventPayload> {
+            #[serde(rename = "infEvento")]
+            info: EventInfo<TDet>,
+            #[serde(rename = "Signature")]
+            signature: Signature,
+        }
+
+        let helper = EventHelper::<TDet>::deserialize(deserializer)?;
+        Ok(Event {
+            info: helper.info,
+            signature: helper.signature,
+        })
+    }
+}
+
+/// Batch of events sent to the NFeRecepcaoEvento4 webservice (envEvento)
+///
+/// id: Batch identifier (idLote)
+/// events: Signed events, up to 20 per batch (evento)
+#[derive(Debug, PartialEq)]
+pub struct EventBatch<TDet = EventDetail> {
+    pub id: u64,
+    pub events: Vec<Event<TDet>>,
+}
+
+impl<TDet: EventPayload> Serialize for EventBatch<TDet> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("envEvento", 4)?;
+        state.serialize_field("@xmlns", NFE_NAMESPACE)?;
+        state.serialize_field("@versao", EVENT_VERSION)?;
+        state.serialize_field("idLote", &self.id)?;
+        state.serialize_field("evento", &self.events)?;
+        state.end()
+    }
+}
+
+impl<'de, TDet: EventPayload> Deserialize<'de> for EventBatch<TDet> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(bound = "")]
+        struct EventBatchHelper<TDet: EventPayload> {
+            #[serde(rename = "idLote")]
+            id: u64,
+            #[serde(rename = "evento")]
+            events: Vec<Event<TDet>>,
+        }
+
+        let helper = EventBatchHelper::<TDet>::deserialize(deserializer)?;
+        Ok(EventBatch {
+            id: helper.id,
+            events: helper.events,
+        })
+    }
+}
+
+/// Result of a single event (infEvento of retEvento)
+///
+/// id: Identifier of the result (@Id) - Optional
+/// environment: Environment type (tpAmb)
+/// application_version: Version of the SEFAZ application (verAplic)
+/// organ: IBGE code of the organ that registered the event (cOrgao)
+/// status: Status code (cStat)
+/// reason: Status description (xMotivo)
+/// access_key: Access key of the NF-e (chNFe) - Optional
+/// event_type: Type of the event (tpEvento) - Optional
+/// description: Description of the event (xEvento) - Optional
+/// sequence: Sequence of the event (nSeqEvento) - Optional
+/// registered_at: Date and time of the registration (dhRegEvento) - Optional
+/// protocol: Registration protocol (nProt) - Only when registered
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct EventResultInfo {
+    #[serde(rename = "@Id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(rename = "tpAmb")]
+    pub environment: Environment,
+    #[serde(rename = "verAplic")]
+    pub application_version: String,
+    #[serde(rename = "cOrgao")]
+    pub organ: u8,
+    #[serde(rename = "cStat")]
+    pub status: u16,
+    #[serde(rename = "xMotivo")]
+    pub reason: String,
+    #[serde(rename = "chNFe", skip_serializing_if = "Option::is_none")]
+    pub access_key: Option<String>,
+    #[serde(rename = "tpEvento", skip_serializing_if = "Option::is_none")]
+    pub event_type: Option<EventType>,
+    #[serde(rename = "xEvento", skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(rename = "nSeqEvento", skip_serializing_if = "Option::is_none")]
+    pub sequence: Option<u8>,
+    #[serde(rename = "dhRegEvento", skip_serializing_if = "Option::is_none")]
+    pub registered_at: Option<DateTime<FixedOffset>>,
+    #[serde(rename = "nProt", skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<String>,
+}
+
+impl EventResultInfo {
+    /// Registered (135), registered without linking to the document (136) or
+    /// registered out of time (155)
+    pub fn is_registered(&self) -> bool {
+        matches!(self.status, 135 | 136 | 155)
+    }
+}
+
+/// Result of a single event (retEvento)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct EventResult {
+    #[serde(rename = "@versao")]
+    pub version: String,
+    #[serde(rename = "infEvento")]
+    pub info: EventResultInfo,
+}
+
+/// Response of the events webservice (retEnvEvento)
+///
+/// id: Batch identifier (idLote)
+/// status: Status code of the batch (cStat)
+/// reason: Status description (xMotivo)
+/// results: Result of each event of the batch (retEvento)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct EventResponse {
+    #[serde(rename = "@versao")]
+    pub version: String,
+    #[serde(rename = "idLote")]
+    pub id: u64,
+    #[serde(rename = "tpAmb")]
+    pub environment: Environment,
+    #[serde(rename = "verAplic")]
+    pub application_version: String,
+    #[serde(rename = "cOrgao")]
+    pub organ: u8,
+    #[serde(rename = "cStat")]
+    pub status: u16,
+    #[serde(rename = "xMotivo")]
+    pub reason: String,
+    #[serde(rename = "retEvento", default)]
+    pub results: Vec<EventResult>,
+}
+
+impl EventResponse {
+    /// Result of the given event, matched by access key, type and sequence
+    pub fn result_of<TDet: EventPayload>(
+        &self,
+        event: &EventInfo<TDet>,
+    ) -> Option<&EventResultInfo> {
+        self.results.iter().map(|r| &r.info).find(|info| {
+            info.access_key.as_deref() == Some(event.access_key.as_str())
+                && info.event_type == Some(event.event_type())
+                && info.sequence == Some(event.sequence)
+        })
+    }
+}
+
+/// Event with the result of its registration (procEventoNFe)
+///
+/// version: Layout version (@versao)
+/// event: Signed event (evento)
+/// result: Result of the registration (retEvento)
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(bound = "TDet: EventPayload")]
+pub struct EventProc<TDet = EventDetail> {
+    #[serde(rename = "@versao")]
+    pub version: String,
+    #[serde(rename = "evento")]
+    pub event: Event<TDet>,
+    #[serde(rename = "retEvento")]
+    pub result: EventResult,
+}
+
+fn check_justification(justification: &str) -> Result<(), EventError> {
+    let length = justification.trim().chars().count();
+    if (15..=255).contains(&length) {
+        Ok(())
+    } else {
+        Err(EventError::InvalidJustification(length))
+    }
+}
+
+fn check_sequence(sequence: u8) -> Result<(), EventError> {
+    if (1..=MAX_SEQUENCE).contains(&sequence) {
+        Ok(())
+    } else {
+        Err(EventError::InvalidSequence(sequence))
+    }
+}
+
+fn check_correction(correction: &str) -> Result<(), EventError> {
+    let length = correction.trim().chars().count();
+    if (15..=1000).contains(&length) {
+        Ok(())
+    } else {
+        Err(EventError::InvalidCorrection(length))
+    }
+}
+
+/// Signs the event built with the issuer of `config` as author, dated now
+#[cfg(feature = "sign")]
+fn sign_now<TDet: EventPayload>(
+    config: &Config,
+    info: impl FnOnce(PersonDocument, DateTime<FixedOffset>) -> EventInfo<TDet>,
+) -> Result<Event<TDet>, EventError> {
+    let certificate = config.certificate().map_err(EventError::Sign)?;
+    let date = config.clock().local().fixed_offset();
+    let info = info(config.issuer().document.clone(), date);
+    Event::with_certificate(info, &certificate).map_err(EventError::Sign)
+}
+
+/// Cancellation of an authorized NF-e (110111)
+///
+/// access_key: Access key of the NF-e (chNFe)
+/// protocol: Authorization protocol of the NF-e (nProt)
+/// justification: Reason of the cancellation, 15 to 255 characters (xJust)
+#[derive(Debug, Clone, PartialEq)]
+pub struct CancelEvent {
+    access_key: AccessKey,
+    protocol: String,
+    justification: String,
+}
+
+impl CancelEvent {
+    pub fn new(
+        access_key: &AccessKey,
+        protocol: &str,
+        justification: &str,
+    ) -> Result<Self, EventError> {
+        check_justification(justification)?;
+        Ok(Self {
+            access_key: access_key.clone(),
+            protocol: protocol.to_string(),
+            justification: justification.trim().to_string(),
+        })
+    }
+
+    /// Cancellation of the authorized document, checking the rules of SEFAZ at `now`
+    ///
+    /// The document must be authorized and within the `window` of its
+    /// authorization. Its registered `events`, as the ones of
+    /// `ProtocolQueryResponse`, must have neither a cancellation nor a
+    /// confirmation of the operation by the recipient. The events of other
+    /// documents are ignored.
+    pub fn for_document(
+        nfe_proc: &NFeProc,
+        events: &[EventProc],
+        justification: &str,
+        window: &CancellationWindow,
+        now: DateTime<FixedOffset>,
+    ) -> Result<Self, CancellationError> {
+        let protocol = &nfe_proc.protocol.info;
+        let number = match &protocol.number {
+            Some(number) if protocol.is_authorized() => number,
+            _ => return Err(CancellationError::NotAuthorized(protocol.status)),
+        };
+
+        let access_key = nfe_proc.nfe.info.access_key();
+        let registered = events.iter().filter(|proc| {
+            proc.event.info.access_key == access_key && proc.result.info.is_registered()
+        });
+        for proc in registered {
+            match proc.event.info.event_type() {
+                EventType::Cancellation => {
+                    let protocol = proc.result.info.protocol.clone();
+                    return Err(CancellationError::AlreadyCancelled(protocol));
+                }
+                EventType::Confirmation => {
+                    return Err(CancellationError::Blocked(EventType::Confirmation));
+                }
+                _ => {}
+            }
+        }
+
+        let deadline =
+            protocol.received_at + window.window(&access_key.state(), &access_key.model());
+        if now > deadline {
+            return Err(CancellationError::Expired(deadline));
+        }
+        Self::new(&access_key, number, justification).map_err(CancellationError::Event)
+    }
+
+    /// Information of the event, authored by `author` at `date`
+    pub fn info(
+        &self,
+        environment: Environment,
+        author: PersonDocument,
+        date: DateTime<FixedOffset>,
+    ) -> EventInfo {
+        EventInfo {
+            organ: self.access_key.state().code(),
+            environment,
+            author,
+            access_key: self.access_key.clone(),
+            date,
+            sequence: 1,
+            detail: EventDetail::Cancellation(CancellationDetail {
+                protocol: self.protocol.clone(),
+                justification: self.justification.clone(),
+            }),
+        }
+    }
+
+    /// Builds the event authored now by the issuer of the global configuration and signs it
+    #[cfg(feature = "sign")]
+    pub fn sign(&self, environment: Environment) -> Result<Event, EventError> {
+        let config = crate::config::global().map_err(EventError::ConfigError)?;
+        self.sign_with_config(&config, environment)
+    }
+
+    /// Builds the event authored now by the issuer of `config` and signs it
+    #[cfg(feature = "sign")]
+    pub fn sign_with_config(
+        &self,
+        config: &Config,
+        environment: Environment,
+    ) -> Result<Event, EventError> {
+        sign_now(config, |author, date| self.info(environment, author, date))
+    }
+}
+
+/// Correction letter of an authorized NF-e (110110)
+///
+/// Every correction replaces the previous ones, so it must carry all the
+/// corrected fields and the next sequence (nSeqEvento) for the access key.
+///
+/// access_key: Access key of the NF-e (chNFe)
+/// sequence: Sequence of the correction, from 1 to 20 (nSeqEvento)
+/// correction: Text of the correction, 15 to 1000 characters (xCorrecao)
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorrectionEvent {
+    access_key: AccessKey,
+    sequence: u8,
+    correction: String,
+}
+
+impl CorrectionEvent {
+    pub fn new(access_key: &AccessKey, sequence: u8, correction: &str) -> Result<Self, EventError> {
+        check_sequence(sequence)?;
+        check_correction(correction)?;
+        Ok(Self {
+            access_key: access_key.clone(),
+            sequence,
+            correction: correction.trim().to_string(),
+        })
+    }
+
+    /// Correction following this one for the same access key
+    pub fn next(&self, correction: &str) -> Result<Self, EventError> {
+        Self::new(
+            &self.access_key,
+            self.sequence.saturating_add(1),
+            correction,
+        )
+    }
+
+    pub fn sequence(&self) -> u8 {
+        self.sequence
+    }
+
+    /// Information of the event, authored by `author` at `date`
+    pub fn info(
+        &self,
+        environment: Environment,
+        author: PersonDocument,
+        date: DateTime<FixedOffset>,
+    ) -> EventInfo {
+        EventInfo {
+            organ: self.access_key.state().code(),
+            environment,
+            author,
+            access_key: self.access_key.clone(),
+            date,
+            sequence: self.sequence,
+            detail: EventDetail::Correction(CorrectionDetail {
+                correction: self.correction.clone(),
+            }),
+        }
+    }
+
+    /// Builds the event authored now by the issuer of the global configuration and signs it
+    #[cfg(feature = "sign")]
+    pub fn sign(&self, environment: Environment) -> Result<Event, EventError> {
+        let config = crate::config::global().map_err(EventError::ConfigError)?;
+        self.sign_with_config(&config, environment)
+    }
+
+    /// Builds the event authored now by the issuer of `config` and signs it
+    #[cfg(feature = "sign")]
+    pub fn sign_with_config(
+        &self,
+        config: &Config,
+        environment: Environment,
+    ) -> Result<Event, EventError> {
+        sign_now(config, |author, date| self.info(environment, author, date))
+    }
+}
+
+/// Interested actor of an authorized NF-e (110150), allowing a transporter to
+/// download its XML
+///
+/// Authored by the issuer by default, in the state of the document. Every
+/// transporter is informed in its own event, with the next sequence
+/// (nSeqEvento) for the access key.
+///
+/// access_key: Access key of the NF-e (chNFe)
+/// sequence: Sequence of the event, from 1 to 20 (nSeqEvento)
+/// detail: Transporter, its permission and the author of the event (detEvento)
+/// application_version: Version of the application of the author (verAplic) - Optional
+#[derive(Debug, Clone, PartialEq)]
+pub struct InterestedActorEvent {
+    access_key: AccessKey,
+    sequence: u8,
+    detail: InterestedActorDetail,
+    application_version: Option<String>,
+}
+
+impl InterestedActorEvent {
+    pub fn new(
+        access_key: &AccessKey,
+        sequence: u8,
+        actor: PersonDocument,
+        authorization: ActorAuthorization,
+    ) -> Result<Self, EventError> {
+        check_sequence(sequence)?;
+        Ok(Self {
+            access_key: access_key.clone(),
+            sequence,
+            detail: InterestedActorDetail {
+                author_organ: access_key.state().code(),
+                author: ActorAuthor::Issuer,
+                application_version: LIBRARY_VERSION.to_string(),
+                actor,
+                authorization,
+            },
+            application_version: None,
+        })
+    }
+
+    /// Author of the event and the IBGE code of its state (cOrgaoAutor)
+    pub fn with_author(mut self, author: ActorAuthor, organ: u8) -> Self {
+        self.detail.author = author;
+        self.detail.author_organ = organ;
+        self
+    }
+
+    /// Version of the application of the author (verAplic)
+    pub fn with_application_version(mut self, version: &str) -> Self {
+        self.application_version = Some(version.to_string());
+        self
+    }
+
+    pub fn sequence(&self) -> u8 {
+        self.sequence
+    }
+
+    /// Information of the event, authored by `author` at `date`
+    pub fn info(
+        &self,
+        environment: Environment,
+        author: PersonDocument,
+        date: DateTime<FixedOffset>,
+    ) -> EventInfo {
+        EventInfo {
+            organ: self.access_key.state().code(),
+            environment,
+            author,
+            access_key: self.access_key.clone(),
+            date,
+            sequence: self.sequence,
+            detail: EventDetail::InterestedActor(InterestedActorDetail {
+                application_version: self
+                    .application_version
+                    .clone()
+                    .unwrap_or_else(|| LIBRARY_VERSION.to_string()),
+                ..self.detail.clone()
+            }),
+        }
+    }
+
+    /// Builds the event authored now by the issuer of the global configuration and signs it
+    #[cfg(feature = "sign")]
+    pub fn sign(&self, environment: Environment) -> Result<Event, EventError> {
+        let config = crate::config::global().map_err(EventError::ConfigError)?;
+        self.sign_with_config(&config, environment)
+    }
+
+    /// Builds the event authored now by the issuer of `config` and signs it, with
+    /// its emission version as the version of the application unless one was set
+    #[cfg(feature = "sign")]
+    pub fn sign_with_config(
+        &self,
+        config: &Config,
+        environment: Environment,
+    ) -> Result<Event, EventError> {
+        let version = self
+            .application_version
+            .as_deref()
+            .unwrap_or(config.emission_version());
+        let event = self.clone().with_application_version(version);
+        sign_now(config, |author, date| event.info(environment, author, date))
+    }
+}
+
+/// Manifestation of the recipient about an NF-e issued against it (2102xx)
+///
+/// Registered in the Ambiente Nacional (cOrgao 91) and authored by the
+/// recipient of the document, always with the sequence 1.
+///
+/// access_key: Access key of the NF-e (chNFe)
+/// detail: Manifestation, with the justification of 210240 (xJust)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManifestationEvent {
+    access_key: AccessKey,
+    detail: ManifestationDetail,
+}
+
+impl ManifestationEvent {
+    pub fn new(access_key: &AccessKey, detail: ManifestationDetail) -> Result<Self, EventError> {
+        let detail = match detail {
+            ManifestationDetail::NotPerformed(justification) => {
+                check_justification(&justification)?;
+                ManifestationDetail::NotPerformed(justification.trim().to_string())
+            }
+            detail => detail,
+        };
+        Ok(Self {
+            access_key: access_key.clone(),
+            detail,
+        })
+    }
+
+    /// Confirmation of the operation (210200)
+    pub fn confirmation(access_key: &AccessKey) -> Result<Self, EventError> {
+        Self::new(access_key, ManifestationDetail::Confirmation)
+    }
+
+    /// Awareness of the operation (210210)
+    pub fn awareness(access_key: &AccessKey) -> Result<Self, EventError> {
+        Self::new(access_key, ManifestationDetail::Awareness)
+    }
+
+    /// Unknown operation (210220)
+    pub fn unknown(access_key: &AccessKey) -> Result<Self, EventError> {
+        Self::new(access_key, ManifestationDetail::Unknown)
+    }
+
+    /// Operation not performed (210240), justified in 15 to 255 characters
+    pub fn not_performed(access_key: &AccessKey, justification: &str) -> Result<Self, EventError> {
+        Self::new(
+            access_key,
+            ManifestationDetail::NotPerformed(justification.to_string()),
+        )
+    }
+
+    /// Information of the event, authored by `author` at `date`
+    pub fn info(
+        &self,
+        environment: Environment,
+        author: PersonDocument,
+        date: DateTime<FixedOffset>,
+    ) -> EventInfo {
+        EventInfo {
+            organ: NATIONAL_ORGAN,
+            environment,
+            author,
+            access_key: self.access_key.clone(),
+            date,
+            sequence: 1,
+            detail: EventDetail::Manifestation(self.detail.clone()),
+        }
+    }
+
+    /// Builds the event authored now by the issuer of the global configuration and signs it
+    #[cfg(feature = "sign")]
+    pub fn sign(&self, environment: Environment) -> Result<Event, EventError> {
+        let config = crate::config::global().map_err(EventError::ConfigError)?;
+        self.sign_with_config(&config, environment)
+    }
+
+    /// Builds the event authored now by the issuer of `config` and signs it
+    #[cfg(feature = "sign")]
+    pub fn sign_with_config(
+        &self,
+        config: &Config,
+        environment: Environment,
+    ) -> Result<Event, EventError> {
+        sign_now(config, |author, date| self.info(environment, author, date))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::access_key::tests::{ACCESS_KEY, setup_access_key};
+    use crate::enums::{CNPJ, CPF};
+    use crate::models::tests::setup_nfe_proc;
+    #[cfg(feature = "sign")]
+    use crate::sign::tests::setup_certificate;
+    use crate::xml::{indent_xml, normalize_xml as canonicalize};
+    use nf_e_macros::serialization_test;
+    use quick_xml::{de::from_str as deserialize, se::to_string as serialize};
+
+    pub fn setup_date() -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339("2025-08-02T09:00:00-03:00").unwrap()
+    }
+
+    fn setup_cancel_event() -> CancelEvent {
+        CancelEvent::new(
+            &setup_access_key(),
+            "135250000000001",
+            "Cancelamento por erro na digitacao",
+        )
+        .expect("Failed to create cancel event")
+    }
+
+    #[serialization_test(
+        fixture = "../tests/fixtures/events/cancel_info.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_cancel_info() -> EventInfo {
+        setup_cancel_event().info(
+            Environment::Homologation,
+            PersonDocument::CNPJ(CNPJ("12345678000195".to_string())),
+            setup_date(),
+        )
+    }
+
+    fn setup_correction_event() -> CorrectionEvent {
+        CorrectionEvent::new(&setup_access_key(), 2, "Onde se le Rua A, leia-se Rua B")
+            .expect("Failed to create correction event")
+    }
+
+    #[serialization_test(
+        fixture = "../tests/fixtures/events/correction_info.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_correction_info() -> EventInfo {
+        setup_correction_event().info(
+            Environment::Homologation,
+            PersonDocument::CNPJ(CNPJ("12345678000195".to_string())),
+            setup_date(),
+        )
+    }
+
+    #[serialization_test(
+        fixture = "../tests/fixtures/events/not_performed_info.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_not_performed_info() -> EventInfo {
+        ManifestationEvent::not_performed(&setup_access_key(), "Mercadoria devolvida ao emitente")
+            .expect("Failed to create manifestation event")
+            .info(
+                Environment::Homologation,
+                PersonDocument::CNPJ(CNPJ("98765432000198".to_string())),
+                setup_date(),
+            )
+    }
+
+    #[serialization_test(
+        fixture = "../tests/fixtures/events/interested_actor_info.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_interested_actor_info() -> EventInfo {
+        InterestedActorEvent::new(
+            &setup_access_key(),
+            1,
+            PersonDocument::CNPJ(CNPJ("11222333000181".to_string())),
+            ActorAuthorization::Allowed,
+        )
+        .expect("Failed to create interested actor event")
+        .with_application_version("1.0.0")
+        .info(
+            Environment::Homologation,
+            PersonDocument::CNPJ(CNPJ("12345678000195".to_string())),
+            setup_date(),
+        )
+    }
+
+    #[test]
+    fn test_interested_actor() {
+        let info = InterestedActorEvent::new(
+            &setup_access_key(),
+            2,
+            PersonDocument::CPF(CPF("12345678909".to_string())),
+            ActorAuthorization::NotAllowed,
+        )
+        .expect("Failed to create interested actor event")
+        .with_author(ActorAuthor::Recipient, 31)
+        .info(
+            Environment::Homologation,
+            PersonDocument::CNPJ(CNPJ("98765432000198".to_string())),
+            setup_date(),
+        );
+        assert_eq!(info.organ, 35);
+        assert_eq!(info.id(), format!("ID110150{}02", ACCESS_KEY));
+
+        let serialized = serialize(&info).expect("Failed to serialize");
+        assert!(serialized.contains("<cOrgaoAutor>31</cOrgaoAutor><tpAutor>2</tpAutor>"));
+        assert!(!serialized.contains("xCondUso"));
+        let deserialized: EventInfo<InterestedActorDetail> =
+            deserialize(&serialized).expect("Failed to deserialize");
+        assert_eq!(
+            EventDetail::InterestedActor(deserialized.detail),
+            info.detail
+        );
+
+        assert_eq!(
+            InterestedActorEvent::new(
+                &setup_access_key(),
+                21,
+                PersonDocument::CPF(CPF("12345678909".to_string())),
+                ActorAuthorization::NotAllowed,
+            ),
+            Err(EventError::InvalidSequence(21))
+        );
+    }
+
+    #[test]
+    fn test_manifestation() {
+        let info = ManifestationEvent::awareness(&setup_access_key())
+            .expect("Failed to create manifestation event")
+            .info(
+                Environment::Homologation,
+                PersonDocument::CNPJ(CNPJ("98765432000198".to_string())),
+                setup_date(),
+            );
+        assert_eq!(info.organ, NATIONAL_ORGAN);
+        assert_eq!(info.id(), format!("ID210210{}01", ACCESS_KEY));
+        assert!(info.event_type().is_manifestation());
+
+        let serialized = serialize(&info).expect("Failed to serialize");
+        assert!(!serialized.contains("xJust"));
+        let deserialized: EventInfo = deserialize(&serialized).expect("Failed to deserialize");
+        assert_eq!(deserialized, info);
+
+        assert_eq!(
+            ManifestationEvent::not_performed(&setup_access_key(), "Nao houve"),
+            Err(EventError::InvalidJustification(9))
+        );
+    }
+
+    #[test]
+    fn test_correction_sequence() {
+        let first = CorrectionEvent::new(&setup_access_key(), 1, "Onde se le Rua A, leia-se Rua B")
+            .expect("Failed to create correction event");
+        let second = first
+            .next("Onde se le Rua A, leia-se Rua C")
+            .expect("Failed to create next correction");
+        assert_eq!(second.sequence(), 2);
+        assert_eq!(
+            CorrectionEvent::new(&setup_access_key(), 0, "Onde se le Rua A, leia-se Rua B"),
+            Err(EventError::InvalidSequence(0))
+        );
+        let last = CorrectionEvent::new(
+            &setup_access_key(),
+            MAX_SEQUENCE,
+            "Onde se le Rua A, leia-se Rua B",
+        )
+        .unwrap();
+        assert_eq!(
+            last.next("Onde se le Rua A, leia-se Rua D"),
+            Err(EventError::InvalidSequence(21))
+        );
+    }
+
+    #[test]
+    fn test_correction_length() {
+        assert_eq!(
+            CorrectionEvent::new(&setup_access_key(), 1, "Rua B"),
+            Err(EventError::InvalidCorrection(5))
+        );
+    }
+
+    #[test]
+    fn test_typed_payload() {
+        let info = setup_cancel_info();
+        let EventDetail::Cancellation(detail) = info.detail.clone() else {
+            panic!("Expected a cancellation");
+        };
+        let typed = EventInfo {
+            organ: info.organ,
+            environment: info.environment.clone(),
+            author: info.author.clone(),
+            access_key: info.access_key.clone(),
+            date: info.date,
+            sequence: info.sequence,
+            detail,
+        };
+        assert_eq!(typed.id(), info.id());
+        let serialized = serialize(&typed).expect("Failed to serialize");
+        assert_eq!(serialized, serialize(&info).expect("Failed to serialize"));
+        let deserialized: EventInfo<CancellationDetail> =
+            deserialize(&serialized).expect("Failed to deserialize");
+        assert_eq!(deserialized, typed);
+
+        let correction = serialize(&setup_correction_info()).expect("Failed to serialize");
+        assert!(deserialize::<EventInfo<CancellationDetail>>(&correction).is_err());
+        assert!(deserialize::<EventInfo<CorrectionDetail>>(&correction).is_ok());
+    }
+
+    /// Payload of a type of event not known by the crate (110130)
+    #[derive(Debug, Clone, PartialEq)]
+    struct DeliveryDetail {
+        receiver: String,
+    }
+
+    #[derive(Deserialize)]
+    struct DeliveryFields {
+        #[serde(rename = "xNome")]
+        receiver: String,
+    }
+
+    impl EventPayload for DeliveryDetail {
+        type Fields = DeliveryFields;
+
+        fn event_type(&self) -> EventType {
+            EventType::Other(110130)
+        }
+
+        fn description(&self) -> &str {
+            "Comprovante de Entrega da NF-e"
+        }
+
+        fn serialize_fields<S: SerializeStruct>(&self, state: &mut S) -> Result<(), S::Error> {
+            state.serialize_field("xNome", &self.receiver)
+        }
+
+        fn from_fields<E: serde::de::Error>(
+            event_type: EventType,
+            fields: DeliveryFields,
+        ) -> Result<Self, E> {
+            match event_type {
+                EventType::Other(110130) => Ok(DeliveryDetail {
+                    receiver: fields.receiver,
+                }),
+                _ => Err(unexpected_type(event_type)),
+            }
+        }
+    }
+
+    #[test]
+    fn test_custom_payload() {
+        assert_eq!(EventType::from(110111), EventType::Cancellation);
+        assert_eq!(EventType::from(110130), EventType::Other(110130));
+        assert_eq!(u32::from(EventType::Other(110130)), 110130);
+        assert!(!EventType::Other(110130).is_known());
+
+        let info = setup_cancel_info();
+        let custom = EventInfo {
+            organ: info.organ,
+            environment: info.environment.clone(),
+            author: info.author.clone(),
+            access_key: info.access_key.clone(),
+            date: info.date,
+            sequence: info.sequence,
+            detail: DeliveryDetail {
+                receiver: "Joao da Silva".to_string(),
+            },
+        };
+        assert_eq!(custom.id(), format!("ID110130{}01", ACCESS_KEY));
+        let serialized = serialize(&custom).expect("Failed to serialize");
+        assert!(serialized.contains("<tpEvento>110130</tpEvento>"));
+        assert!(serialized.contains(
+            "<descEvento>Comprovante de Entrega da NF-e</descEvento><xNome>Joao da Silva</xNome>"
+        ));
+        let deserialized: EventInfo<DeliveryDetail> =
+            deserialize(&serialized).expect("Failed to deserialize");
+        assert_eq!(deserialized, custom);
+
+        let cancellation = serialize(&info).expect("Failed to serialize");
+        assert!(deserialize::<EventInfo<DeliveryDetail>>(&cancellation).is_err());
+    }
+
+    #[test]
+    fn test_event_id() {
+        assert_eq!(
+            setup_cancel_info().id(),
+            format!("ID110111{}01", ACCESS_KEY)
+        );
+        assert_eq!(setup_cancel_info().organ, 35);
+    }
+
+    fn setup_events(event_type: EventType) -> Vec<EventProc> {
+        let mut proc: EventProc =
+            deserialize(include_str!("../tests/fixtures/events/cancel_proc.xml"))
+                .expect("Failed to parse the event");
+        proc.event.info.access_key = setup_nfe_proc().nfe.info.access_key();
+        if event_type == EventType::Confirmation {
+            proc.event.info.detail = EventDetail::Manifestation(ManifestationDetail::Confirmation);
+        }
+        vec![proc]
+    }
+
+    #[test]
+    fn test_cancellation_rules() {
+        let justification = "Cancelamento por erro na digitacao";
+        let window = CancellationWindow::new();
+        let nfe_proc = setup_nfe_proc();
+        let authorized_at = nfe_proc.protocol.info.received_at;
+
+        let event = CancelEvent::for_document(
+            &nfe_proc,
+            &[],
+            justification,
+            &window,
+            authorized_at + TimeDelta::minutes(29),
+        )
+        .expect("Failed to check the cancellation");
+        assert_eq!(
+            event,
+            CancelEvent::new(
+                &nfe_proc.nfe.info.access_key(),
+                "131230000000001",
+                justification
+            )
+            .unwrap()
+        );
+
+        // NFC-e of Minas Gerais, cancelled up to 30 minutes after the authorization
+        let late = authorized_at + TimeDelta::minutes(31);
+        assert_eq!(
+            CancelEvent::for_document(&nfe_proc, &[], justification, &window, late),
+            Err(CancellationError::Expired(
+                authorized_at + TimeDelta::minutes(30)
+            ))
+        );
+        let window = window.with_state(State::MinasGerais, Model::NFCe, TimeDelta::hours(24));
+        assert!(CancelEvent::for_document(&nfe_proc, &[], justification, &window, late).is_ok());
+
+        assert_eq!(
+            CancelEvent::for_document(
+                &nfe_proc,
+                &setup_events(EventType::Cancellation),
+                justification,
+                &window,
+                late
+            ),
+            Err(CancellationError::AlreadyCancelled(Some(
+                "135250000000002".to_string()
+            )))
+        );
+        assert_eq!(
+            CancelEvent::for_document(
+                &nfe_proc,
+                &setup_events(EventType::Confirmation),
+                justification,
+                &window,
+                late
+            ),
+            Err(CancellationError::Blocked(EventType::Confirmation))
+        );
+        assert_eq!(
+            CancelEvent::for_document(&nfe_proc, &[], "Erro", &window, late),
+            Err(CancellationError::Event(EventError::InvalidJustification(
+                4
+            )))
+        );
+
+        let mut denied = setup_nfe_proc();
+        denied.protocol.info.status = 302;
+        assert_eq!(
+            CancelEvent::for_document(&denied, &[], justification, &window, late),
+            Err(CancellationError::NotAuthorized(302))
+        );
+    }
+
+    #[test]
+    fn test_justification_length() {
+        assert_eq!(
+            CancelEvent::new(&setup_access_key(), "135250000000001", "Muito curta"),
+            Err(EventError::InvalidJustification(11))
+        );
+        assert_eq!(
+            CancelEvent::new(&setup_access_key(), "135250000000001", &"a".repeat(256)),
+            Err(EventError::InvalidJustification(256))
+        );
+    }
+
+    #[cfg(feature = "sign")]
+    #[test]
+    fn test_sign_event() {
+        let event = Event::with_certificate(setup_cancel_info(), &setup_certificate())
+            .expect("Failed to sign event");
+        assert_eq!(
+            event.signature.info.reference.uri,
+            format!("#ID110111{}01", ACCESS_KEY)
+        );
+
+        let batch = EventBatch {
+            id: 1,
+            events: vec![event],
+        };
+        let serialized = quick_xml::se::to_string(&batch).expect("Failed to serialize batch");
+        let deserialized: EventBatch = deserialize(&serialized).expect("Failed to deserialize");
+        assert_eq!(deserialized, batch);
+    }
+
+    #[cfg(feature = "sign")]
+    #[test]
+    fn test_sign_interested_actor_version() {
+        let config = crate::testkit::sample_config().with_emission_version("2.0.0");
+        let event = InterestedActorEvent::new(
+            &setup_access_key(),
+            1,
+            PersonDocument::CNPJ(CNPJ("11222333000181".to_string())),
+            ActorAuthorization::Allowed,
+        )
+        .expect("Failed to create interested actor event");
+        let version = |event: &InterestedActorEvent| match event
+            .sign_with_config(&config, Environment::Homologation)
+            .expect("Failed to sign event")
+            .info
+            .detail
+        {
+            EventDetail::InterestedActor(detail) => detail.application_version,
+            detail => panic!("Unexpected detail {detail:?}"),
+        };
+        assert_eq!(version(&event), "2.0.0");
+        assert_eq!(
+            version(&event.clone().with_application_version("1.0.0")),
+            "1.0.0"
+        );
+    }
+}
@@ -0,0 +1,520 @@
+//! JSON representation of the documents
+//!
+//! The models are serialized to XML with the names of the layout, so the JSON
+//! is translated from that XML instead of a second set of serde rules: each
+//! element becomes a member named after the field of the model in camelCase,
+//! from `NAMES`, the attributes become members as well and the values are kept
+//! as the strings of the layout, so the decimals keep their places. The
+//! elements that may repeat are always arrays and the content of `autXML`, an
+//! ordered list of CNPJ and CPF, is an array of single member objects.
+//!
+//! The names are part of the format: renaming a field of the models does not
+//! change them, and elements missing from `NAMES` keep the name of the layout.
+
+use crate::xml::{escape_attribute, escape_text, resolve_reference};
+use quick_xml::Reader;
+use quick_xml::events::{BytesStart, Event};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonError {
+    /// Model that could not be serialized to XML
+    Serialization(String),
+    /// Model that could not be read from the XML of the JSON
+    Deserialization(String),
+    /// JSON that does not represent a document, as a value out of an object
+    Syntax(String),
+}
+
+/// Element wrapping the content of the JSON while it goes through XML
+const ROOT: &str = "json";
+
+/// Names of the elements (and attributes, with `@`) of the layout in the JSON
+#[rustfmt::skip]
+pub const NAMES: &[(&str, &str)] = &[
+    // Attributes
+    ("@Id", "id"), ("@versao", "version"), ("@nItem", "itemNumber"), ("@URI", "uri"),
+    ("@Algorithm", "algorithm"),
+    // Document (NFe, nfeProc, infNFe, infNFeSupl, protNFe)
+    ("NFe", "nfe"), ("nfeProc", "nfeProc"), ("infNFe", "info"), ("infNFeSupl", "supplement"),
+    ("qrCode", "qrCode"), ("urlChave", "url"), ("protNFe", "protocol"), ("infProt", "protocolInfo"),
+    ("verAplic", "applicationVersion"), ("chNFe", "accessKey"), ("dhRecbto", "receivedAt"),
+    ("nProt", "protocolNumber"), ("digVal", "digest"), ("cStat", "status"), ("xMotivo", "reason"),
+    // Identification (ide)
+    ("ide", "identification"), ("cUF", "stateCode"), ("cNF", "numericCode"),
+    ("natOp", "operationNature"), ("mod", "model"), ("serie", "series"), ("nNF", "number"),
+    ("dhEmi", "emissionDate"), ("dhSaiEnt", "exitDate"), ("tpNF", "type"),
+    ("idDest", "destination"), ("cMunFG", "generatorCity"), ("tpImp", "printingType"),
+    ("tpEmis", "emissionType"), ("cDV", "verifierDigit"), ("tpAmb", "environment"),
+    ("finNFe", "finality"), ("indFinal", "consumer"), ("indPres", "presence"),
+    ("indIntermed", "intermediatorIndicator"), ("procEmi", "emissionProcess"),
+    ("verProc", "emissionVersion"), ("dhCont", "contingencyDate"), ("xJust", "justification"),
+    // Parties (emit, dest, retirada, entrega, autXML)
+    ("emit", "issuer"), ("dest", "recipient"), ("retirada", "withdrawal"), ("entrega", "delivery"),
+    ("autXML", "authorized"), ("CNPJ", "cnpj"), ("CPF", "cpf"), ("idEstrangeiro", "foreignId"),
+    ("xNome", "name"), ("xFant", "tradeName"), ("IE", "ie"), ("IEST", "ieSt"), ("IM", "im"),
+    ("CNAE", "cnae"), ("CRT", "taxRegime"), ("indIEDest", "ieIndicator"), ("ISUF", "suframa"),
+    ("email", "email"),
+    // Addresses (enderEmit, enderDest)
+    ("enderEmit", "issuerAddress"), ("enderDest", "recipientAddress"), ("xLgr", "line1"),
+    ("nro", "streetNumber"), ("xCpl", "line2"), ("xBairro", "neighborhood"), ("cMun", "cityCode"),
+    ("xMun", "cityName"), ("UF", "state"), ("CEP", "zipCode"), ("cPais", "countryCode"),
+    ("xPais", "countryName"), ("fone", "telephone"),
+    // Items (det, prod)
+    ("det", "details"), ("prod", "item"), ("infAdProd", "additionalInfo"), ("cProd", "code"),
+    ("cEAN", "gtin"), ("xProd", "description"), ("NCM", "ncm"), ("NVE", "nveCodes"),
+    ("CEST", "cest"), ("indEscala", "scale"), ("CNPJFab", "manufacturerCnpj"),
+    ("cBenef", "benefitCode"), ("EXTIPI", "exTipi"), ("CFOP", "cfop"), ("uCom", "unit"),
+    ("qCom", "quantity"), ("vUnCom", "unitValue"), ("vProd", "productsValue"),
+    ("cEANTrib", "tributeGtin"), ("uTrib", "tributeUnit"), ("qTrib", "tributeQuantity"),
+    ("vUnTrib", "tributeUnitValue"), ("vFrete", "freight"), ("vSeg", "insurance"),
+    ("vDesc", "discount"), ("vOutro", "other"), ("indTot", "included"),
+    // Foreign trade (DI, adi, detExport)
+    ("DI", "importDeclarations"), ("nDI", "declarationNumber"), ("dDI", "declarationDate"),
+    ("xLocDesemb", "clearanceLocation"), ("UFDesemb", "clearanceState"),
+    ("dDesemb", "clearanceDate"), ("tpViaTransp", "transportRoute"), ("vAFRMM", "afrmm"),
+    ("tpIntermedio", "intermediation"), ("UFTerceiro", "acquirerState"),
+    ("cExportador", "exporter"), ("adi", "additions"), ("nAdicao", "additionNumber"),
+    ("nSeqAdic", "sequence"), ("cFabricante", "manufacturer"), ("vDescDI", "additionDiscount"),
+    ("nDraw", "drawback"), ("detExport", "exportDetails"), ("exportInd", "indirect"),
+    ("nRE", "registration"), ("qExport", "exportedQuantity"),
+    // Products (rastro, veicProd, med, comb)
+    ("rastro", "traceability"), ("nLote", "batch"), ("qLote", "batchQuantity"),
+    ("dFab", "manufactureDate"), ("dVal", "expirationDate"), ("cAgreg", "aggregationCode"),
+    ("veicProd", "vehicle"), ("tpOp", "operation"), ("chassi", "chassis"), ("cCor", "colorCode"),
+    ("xCor", "color"), ("pot", "power"), ("cilin", "displacement"), ("pesoL", "netWeight"),
+    ("pesoB", "grossWeight"), ("nSerie", "serial"), ("tpComb", "fuelType"), ("nMotor", "engine"),
+    ("CMT", "maxTraction"), ("dist", "wheelbase"), ("anoMod", "modelYear"),
+    ("anoFab", "manufactureYear"), ("tpPint", "paint"), ("tpVeic", "vehicleType"),
+    ("espVeic", "vehicleSpecies"), ("VIN", "vin"), ("condVeic", "condition"),
+    ("cMod", "modelCode"), ("cCorDENATRAN", "denatranColor"), ("lota", "capacity"),
+    ("tpRest", "restriction"), ("med", "medicine"), ("cProdANVISA", "anvisaCode"),
+    ("xMotivoIsencao", "exemptionReason"), ("vPMC", "maxConsumerPrice"), ("comb", "fuel"),
+    ("cProdANP", "anpCode"), ("descANP", "anpDescription"), ("pGLP", "lpgRate"),
+    ("pGNn", "nationalGasRate"), ("pGNi", "importedGasRate"), ("vPart", "startingValue"),
+    ("CODIF", "codif"), ("qTemp", "temperatureQuantity"), ("UFCons", "consumptionState"),
+    ("CIDE", "cide"), ("vCIDE", "cideValue"), ("encerrante", "totalizer"), ("nBico", "nozzle"),
+    ("nBomba", "pump"), ("nTanque", "tank"), ("vEncIni", "initialReading"),
+    ("vEncFin", "finalReading"),
+    // Taxes (imposto)
+    ("imposto", "tax"), ("vTotTrib", "approximateBurden"), ("ICMS", "icms"),
+    ("ICMS00", "icms00"), ("ICMS10", "icms10"), ("ICMS20", "icms20"), ("ICMS30", "icms30"),
+    ("ICMS40", "icms40"), ("ICMS51", "icms51"), ("ICMS60", "icms60"), ("ICMS70", "icms70"),
+    ("ICMS90", "icms90"), ("ICMSPart", "icmsPart"), ("ICMSST", "icmsSt"),
+    ("ICMSSN101", "icmssn101"), ("ICMSSN102", "icmssn102"), ("ICMSSN201", "icmssn201"),
+    ("ICMSSN202", "icmssn202"), ("ICMSSN500", "icmssn500"), ("ICMSSN900", "icmssn900"),
+    ("orig", "origin"), ("CST", "cst"), ("CSOSN", "csosn"), ("modBC", "baseModality"),
+    ("vBC", "base"), ("pRedBC", "baseReduction"), ("pICMS", "icmsRate"), ("vICMS", "icmsValue"),
+    ("modBCST", "stBaseModality"), ("pMVAST", "stMargin"), ("pRedBCST", "stBaseReduction"),
+    ("vBCST", "stBase"), ("pICMSST", "stRate"), ("vICMSST", "stValue"),
+    ("vICMSDeson", "unburdenedValue"), ("motDesICMS", "unburdenReason"),
+    ("vICMSOp", "operationValue"), ("pDif", "deferralRate"), ("vICMSDif", "deferredValue"),
+    ("vBCSTRet", "retainedStBase"), ("pST", "finalConsumerRate"),
+    ("vICMSSubstituto", "substituteValue"), ("vICMSSTRet", "retainedStValue"),
+    ("pRedBCEfet", "effectiveBaseReduction"), ("vBCEfet", "effectiveBase"),
+    ("pICMSEfet", "effectiveRate"), ("vICMSEfet", "effectiveValue"),
+    ("vBCSTDest", "destinationStBase"), ("vICMSSTDest", "destinationStValue"),
+    ("pBCOp", "ownOperationRate"), ("UFST", "stState"),
+    ("pCredSN", "creditRate"), ("vCredICMSSN", "creditValue"), ("vFCP", "fcpValue"),
+    ("vBCFCP", "fcpBase"), ("pFCP", "fcpRate"), ("vBCFCPST", "fcpStBase"),
+    ("pFCPST", "fcpStRate"), ("vFCPST", "fcpStValue"), ("vBCFCPSTRet", "retainedFcpStBase"),
+    ("pFCPSTRet", "retainedFcpStRate"), ("vFCPSTRet", "retainedFcpStValue"),
+    ("IPI", "ipi"), ("CNPJProd", "producer"), ("cSelo", "sealCode"), ("qSelo", "sealQuantity"),
+    ("cEnq", "framingCode"), ("IPITrib", "ipiTrib"), ("IPINT", "ipiNt"), ("pIPI", "ipiRate"),
+    ("vIPI", "ipiValue"), ("qUnid", "taxUnitQuantity"), ("vUnid", "taxUnitValue"),
+    ("II", "ii"), ("vDespAdu", "customsExpenses"), ("vII", "importTaxValue"),
+    ("vIOF", "iofValue"), ("ISSQN", "issqn"), ("vAliq", "issRate"), ("vISSQN", "issValue"),
+    ("cListServ", "serviceItem"), ("vDeducao", "deduction"),
+    ("vDescIncond", "unconditionalDiscount"), ("vDescCond", "conditionalDiscount"),
+    ("vISSRet", "retainedIssValue"), ("indISS", "issIndicator"), ("cServico", "serviceCode"),
+    ("nProcesso", "processNumber"), ("indIncentivo", "incentive"),
+    ("impostoDevol", "devolution"), ("pDevol", "devolutionRate"),
+    ("PIS", "pis"), ("PISAliq", "pisAliq"), ("PISNT", "pisNt"), ("PISOutr", "pisOutr"),
+    ("PISQtde", "pisQtde"), ("PISST", "pisSt"), ("pPIS", "pisRate"), ("vPIS", "pisValue"),
+    ("qBCProd", "baseQuantity"), ("vAliqProd", "unitRate"), ("indSomaPISST", "pisStIncluded"),
+    ("COFINS", "cofins"), ("COFINSAliq", "cofinsAliq"), ("COFINSNT", "cofinsNt"),
+    ("COFINSOutr", "cofinsOutr"), ("COFINSQtde", "cofinsQtde"), ("COFINSST", "cofinsSt"),
+    ("pCOFINS", "cofinsRate"), ("vCOFINS", "cofinsValue"),
+    ("indSomaCOFINSST", "cofinsStIncluded"),
+    // Totals (total)
+    ("total", "total"), ("ICMSTot", "icmsTotal"), ("vST", "stTotal"),
+    ("vIPIDevol", "returnedIpiValue"), ("vNF", "totalValue"), ("ISSQNtot", "issqnTotal"),
+    ("vServ", "serviceValue"), ("vISS", "issTotalValue"), ("dCompet", "competence"),
+    ("cRegTrib", "specialRegime"),
+    ("retTrib", "withheldTaxes"), ("vRetPIS", "withheldPis"), ("vRetCOFINS", "withheldCofins"),
+    ("vRetCSLL", "withheldCsll"), ("vBCIRRF", "irrfBase"), ("vIRRF", "irrfValue"),
+    ("vBCRetPrev", "socialSecurityBase"), ("vRetPrev", "socialSecurityValue"),
+    // Transport (transp)
+    ("transp", "transport"), ("modFrete", "freightModality"), ("transporta", "transporter"),
+    ("xEnder", "address"), ("retTransp", "withholding"), ("vBCRet", "retainedBase"),
+    ("pICMSRet", "retainedRate"), ("vICMSRet", "retainedIcmsValue"),
+    ("veicTransp", "transportVehicle"), ("placa", "plate"), ("RNTC", "rntc"),
+    ("reboque", "trailers"), ("vol", "volumes"), ("qVol", "volumeQuantity"), ("esp", "species"),
+    ("marca", "brand"), ("nVol", "numbering"), ("lacres", "seals"), ("nLacre", "sealNumber"),
+    // Billing (cobr)
+    ("cobr", "billing"), ("fat", "invoice"), ("nFat", "invoiceNumber"), ("vOrig", "originalValue"),
+    ("vLiq", "netValue"), ("dup", "installments"), ("nDup", "installmentNumber"),
+    ("dVenc", "dueDate"), ("vDup", "installmentValue"),
+    // Payments (pag)
+    ("pag", "payments"), ("detPag", "paymentDetails"), ("indPag", "paymentIndicator"),
+    ("tPag", "paymentType"), ("xPag", "paymentDescription"), ("vPag", "paymentValue"),
+    ("card", "card"), ("tpIntegra", "integration"), ("tBand", "cardBrand"),
+    ("cAut", "authorization"), ("vTroco", "change"),
+    // Intermediator (infIntermed)
+    ("infIntermed", "intermediator"), ("idCadIntTran", "intermediatorId"),
+    // Signature
+    ("Signature", "signature"), ("SignedInfo", "signedInfo"),
+    ("CanonicalizationMethod", "canonicalizationMethod"), ("SignatureMethod", "signatureMethod"),
+    ("Reference", "reference"), ("Transforms", "transforms"), ("Transform", "transform"),
+    ("DigestMethod", "digestMethod"), ("DigestValue", "digestValue"),
+    ("SignatureValue", "signatureValue"), ("KeyInfo", "keyInfo"), ("X509Data", "x509Data"),
+    ("X509Certificate", "x509Certificate"),
+];
+
+/// Elements that may repeat, always represented by arrays
+const REPEATED: &[&str] = &[
+    "det",
+    "NVE",
+    "DI",
+    "adi",
+    "detExport",
+    "rastro",
+    "reboque",
+    "vol",
+    "lacres",
+    "dup",
+    "detPag",
+    "Transform",
+];
+
+/// Elements whose content is an ordered list of choices, represented by an
+/// array of single member objects
+const SEQUENCES: &[&str] = &["autXML"];
+
+/// Name in the JSON of an element or attribute (`@`) of the layout
+fn json_name(xml: &str) -> &str {
+    NAMES
+        .iter()
+        .find(|(name, _)| *name == xml)
+        .map_or(xml, |(_, json)| json)
+}
+
+/// Name in the layout of a member of the JSON
+fn xml_name(json: &str) -> &str {
+    NAMES
+        .iter()
+        .find(|(_, name)| *name == json)
+        .map_or(json, |(xml, _)| xml)
+}
+
+/// JSON of a model, the content of its root element
+pub fn to_json<T: Serialize>(value: &T) -> Result<Value, JsonError> {
+    let xml = quick_xml::se::to_string_with_root(ROOT, value)
+        .map_err(|e| JsonError::Serialization(e.to_string()))?;
+    xml_to_json(&xml)
+}
+
+/// Model read from its JSON, see `to_json`
+pub fn from_json<T: DeserializeOwned>(value: &Value) -> Result<T, JsonError> {
+    let xml = json_to_xml(value)?;
+    quick_xml::de::from_str(&xml).map_err(|e| JsonError::Deserialization(e.to_string()))
+}
+
+/// Text of the JSON of a model, see `to_json`
+pub fn to_json_string<T: Serialize>(value: &T) -> Result<String, JsonError> {
+    let json = to_json(value)?;
+    serde_json::to_string(&json).map_err(|e| JsonError::Serialization(e.to_string()))
+}
+
+/// Model read from the text of its JSON, see `to_json`
+pub fn from_json_str<T: DeserializeOwned>(json: &str) -> Result<T, JsonError> {
+    let value = serde_json::from_str(json).map_err(|e| JsonError::Syntax(e.to_string()))?;
+    from_json(&value)
+}
+
+/// JSON of the root element of an XML document
+pub fn xml_to_json(xml: &str) -> Result<Value, JsonError> {
+    let syntax = |e: quick_xml::Error| JsonError::Syntax(e.to_string());
+    let mut reader = Reader::from_str(xml);
+    let mut stack: Vec<Element> = Vec::new();
+    loop {
+        match reader.read_event().map_err(syntax)? {
+            Event::Start(start) => stack.push(Element::new(&start)?),
+            Event::Empty(start) => {
+                let element = Element::new(&start)?;
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => return Ok(element.into_json()),
+                }
+            }
+            Event::End(_) => {
+                let element = stack.pop().ok_or_else(|| unexpected("end"))?;
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => return Ok(element.into_json()),
+                }
+            }
+            Event::Text(text) => {
+                if let Some(element) = stack.last_mut() {
+                    element
+                        .text
+                        .push_str(&text.decode().map_err(|e| syntax(e.into()))?);
+                }
+            }
+            Event::CData(data) => {
+                if let Some(element) = stack.last_mut() {
+                    element
+                        .text
+                        .push_str(&data.decode().map_err(|e| syntax(e.into()))?);
+                }
+            }
+            Event::GeneralRef(reference) => {
+                let name = reference.decode().map_err(|e| syntax(e.into()))?;
+                let c = resolve_reference(&name).ok_or_else(|| unexpected(&name))?;
+                if let Some(element) = stack.last_mut() {
+                    element.text.push(c);
+                }
+            }
+            Event::Eof => return Err(unexpected("end of the document")),
+            _ => {}
+        }
+    }
+}
+
+/// XML document of a JSON, under a root element named `json`
+pub fn json_to_xml(value: &Value) -> Result<String, JsonError> {
+    if !value.is_object() {
+        return Err(JsonError::Syntax("Expected an object".to_string()));
+    }
+    let mut output = String::new();
+    write_element(ROOT, value, &mut output)?;
+    Ok(output)
+}
+
+fn unexpected(what: &str) -> JsonError {
+    JsonError::Syntax(format!("Unexpected {}", what))
+}
+
+/// Element of the XML while it is read
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Element>,
+    text: String,
+}
+
+impl Element {
+    fn new(start: &BytesStart) -> Result<Self, JsonError> {
+        let syntax = |e: String| JsonError::Syntax(e);
+        let name = String::from_utf8_lossy(start.local_name().as_ref()).into_owned();
+        let mut attributes = Vec::new();
+        for attribute in start.attributes() {
+            let attribute = attribute.map_err(|e| syntax(e.to_string()))?;
+            let key = attribute.key;
+            if key.as_ref() == b"xmlns" || key.prefix().is_some_and(|p| p.as_ref() == b"xmlns") {
+                continue;
+            }
+            let value = attribute
+                .unescape_value()
+                .map_err(|e| syntax(e.to_string()))?;
+            let key = String::from_utf8_lossy(key.local_name().as_ref()).into_owned();
+            attributes.push((format!("@{}", key), value.into_owned()));
+        }
+        Ok(Element {
+            name,
+            attributes,
+            children: Vec::new(),
+            text: String::new(),
+        })
+    }
+
+    fn into_json(self) -> Value {
+        if SEQUENCES.contains(&self.name.as_str()) {
+            let items = self.children.into_iter().map(|child| {
+                let mut item = Map::new();
+                item.insert(json_name(&child.name).to_string(), child.into_json());
+                Value::Object(item)
+            });
+            return Value::Array(items.collect());
+        }
+        if self.attributes.is_empty() && self.children.is_empty() {
+            return Value::String(self.text);
+        }
+
+        let mut object = Map::new();
+        for (name, value) in self.attributes {
+            object.insert(json_name(&name).to_string(), Value::String(value));
+        }
+        for child in self.children {
+            let name = json_name(&child.name).to_string();
+            let repeated = REPEATED.contains(&child.name.as_str());
+            let value = child.into_json();
+            match object.get_mut(&name) {
+                Some(Value::Array(items)) if repeated => items.push(value),
+                _ if repeated => {
+                    object.insert(name, Value::Array(vec![value]));
+                }
+                _ => {
+                    object.insert(name, value);
+                }
+            }
+        }
+        Value::Object(object)
+    }
+}
+
+fn write_element(name: &str, value: &Value, output: &mut String) -> Result<(), JsonError> {
+    match value {
+        Value::Null => {}
+        Value::Array(items) if SEQUENCES.contains(&name) => {
+            output.push_str(&format!("<{}>", name));
+            for item in items {
+                let Value::Object(item) = item else {
+                    return Err(JsonError::Syntax(format!("Expected objects in `{}`", name)));
+                };
+                for (key, value) in item {
+                    write_element(xml_name(key), value, output)?;
+                }
+            }
+            output.push_str(&format!("</{}>", name));
+        }
+        Value::Array(items) => {
+            for item in items {
+                write_element(name, item, output)?;
+            }
+        }
+        Value::Object(members) => {
+            output.push('<');
+            output.push_str(name);
+            let (attributes, children): (Vec<_>, Vec<_>) = members
+                .iter()
+                .map(|(key, value)| (xml_name(key), value))
+                .partition(|(key, _)| key.starts_with('@'));
+            for (key, value) in attributes {
+                output.push_str(&format!(" {}=\"", &key[1..]));
+                escape_attribute(&scalar(key, value)?, output);
+                output.push('"');
+            }
+            output.push('>');
+            for (key, value) in children {
+                write_element(key, value, output)?;
+            }
+            output.push_str(&format!("</{}>", name));
+        }
+        value => {
+            output.push_str(&format!("<{}>", name));
+            escape_text(&scalar(name, value)?, output);
+            output.push_str(&format!("</{}>", name));
+        }
+    }
+    Ok(())
+}
+
+/// Text of a string, number or boolean of the JSON
+fn scalar(name: &str, value: &Value) -> Result<String, JsonError> {
+    match value {
+        Value::String(text) => Ok(text.clone()),
+        Value::Number(number) => Ok(number.to_string()),
+        Value::Bool(flag) => Ok(flag.to_string()),
+        _ => Err(JsonError::Syntax(format!("Expected a value in `{}`", name))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::tests::{setup_authorized, setup_info_builder, setup_nfe, setup_nfe_proc};
+    use crate::models::{Info, NFe, NFeProc};
+    use std::collections::HashSet;
+
+    /// Names of the members of the JSON, recursively
+    fn members(value: &Value, names: &mut HashSet<String>) {
+        match value {
+            Value::Object(object) => {
+                for (name, value) in object {
+                    names.insert(name.clone());
+                    members(value, names);
+                }
+            }
+            Value::Array(items) => items.iter().for_each(|item| members(item, names)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_names() {
+        let xml: HashSet<_> = NAMES.iter().map(|(xml, _)| *xml).collect();
+        let json: HashSet<_> = NAMES.iter().map(|(_, json)| *json).collect();
+        assert_eq!(xml.len(), NAMES.len());
+        assert_eq!(json.len(), NAMES.len());
+        for (xml, name) in NAMES {
+            assert!(
+                !name.starts_with(|c: char| c.is_ascii_uppercase()) && !name.contains('_'),
+                "{} is not camelCase",
+                name
+            );
+            // A member named as an element of the layout would be read as it
+            assert!(
+                !NAMES.iter().any(|(other, _)| other == name && other != xml),
+                "{} is also an element of the layout",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_nfe_json() {
+        let nfe = setup_nfe_proc();
+        let json = to_json(&nfe).expect("Failed to convert to JSON");
+        assert_eq!(json["version"], "4.00");
+        let info = &json["nfe"]["info"];
+        assert_eq!(
+            info["identification"]["operationNature"],
+            "Venda de mercadoria"
+        );
+        assert_eq!(info["issuer"]["issuerAddress"]["line1"], "Rua Exemplo");
+        assert_eq!(info["details"][1]["item"]["quantity"], "3.0000");
+        assert_eq!(info["total"]["icmsTotal"]["totalValue"], "113.94");
+        assert_eq!(info["payments"]["paymentDetails"][0]["paymentType"], "01");
+
+        let mut names = HashSet::new();
+        members(&json, &mut names);
+        let unknown: Vec<_> = names
+            .iter()
+            .filter(|name| !NAMES.iter().any(|(_, json)| json == name))
+            .collect();
+        assert!(unknown.is_empty(), "Members without a name: {:?}", unknown);
+
+        assert_eq!(from_json::<NFeProc>(&json).unwrap(), nfe);
+        let text = to_json_string(&nfe.nfe).unwrap();
+        assert_eq!(from_json_str::<NFe>(&text).unwrap(), setup_nfe());
+    }
+
+    #[test]
+    fn test_info_json() {
+        let mut builder = setup_info_builder();
+        builder = builder.set_authorized(setup_authorized());
+        let info = builder.build().expect("Failed to build Info");
+        let json = to_json(&info).unwrap();
+        assert_eq!(
+            json["authorized"],
+            serde_json::json!([{ "cnpj": "12345678000195" }, { "cpf": "12345678909" }])
+        );
+        assert_eq!(json["details"].as_array().unwrap().len(), 2);
+        assert_eq!(from_json::<Info>(&json).unwrap(), info);
+    }
+
+    #[test]
+    fn test_invalid_json() {
+        assert!(matches!(
+            from_json_str::<Info>("[]"),
+            Err(JsonError::Syntax(_))
+        ));
+        assert!(matches!(
+            from_json_str::<Info>("{\"identification\": {}}"),
+            Err(JsonError::Deserialization(_))
+        ));
+    }
+}
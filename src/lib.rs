@@ -1,7 +1,65 @@
+// Lets the attributes of `nf_e_macros` name the crate as `nf_e` from within it
+extern crate self as nf_e;
+
+#[cfg(feature = "models")]
 pub mod enums;
+#[cfg(feature = "models")]
 pub mod models;
+#[cfg(feature = "models")]
 pub mod states;
+#[cfg(feature = "models")]
 mod utils;
-mod config;
+#[cfg(feature = "models")]
+pub mod decimal;
+#[cfg(feature = "models")]
+pub mod qr_code;
+#[cfg(feature = "danfe")]
+pub mod barcode;
+#[cfg(feature = "danfe")]
+pub mod danfe;
+#[cfg(feature = "sign")]
+pub mod sign;
+#[cfg(feature = "sign")]
+pub mod certificate;
+#[cfg(feature = "client")]
+pub mod soap;
+#[cfg(feature = "models")]
+pub mod access_key;
+#[cfg(feature = "models")]
+pub mod config;
+#[cfg(feature = "models")]
+pub mod clock;
+#[cfg(feature = "client")]
+pub mod contingency;
+#[cfg(feature = "models")]
+pub mod disablement;
+#[cfg(feature = "client")]
+pub mod distribution;
+#[cfg(feature = "models")]
+pub mod events;
+#[cfg(feature = "xsd-validation")]
+pub mod validation;
+#[cfg(feature = "models")]
+pub mod stream;
+#[cfg(feature = "client")]
+pub mod queue;
+#[cfg(feature = "client")]
+pub mod emitter;
+#[cfg(feature = "client")]
+pub mod monitor;
+#[cfg(feature = "models")]
+pub mod series;
+#[cfg(feature = "models")]
+pub mod registry;
+#[cfg(feature = "models")]
+pub mod tax_burden;
+#[cfg(feature = "models")]
+pub mod taxpayer;
+#[cfg(feature = "models")]
+pub mod xml;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(any(feature = "testkit", all(test, feature = "models")))]
+pub mod testkit;
 
 pub const LIBRARY_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -1,124 +1,337 @@
 use crate::enums::*;
 
-use crate::LIBRARY_VERSION;
-use crate::config::ConfigError;
+use crate::access_key::{self, AccessKey};
+#[cfg(feature = "sign")]
+use crate::certificate::CertificateError;
+use crate::config::{Config, ConfigError};
+use crate::decimal::{Money, Quantity};
+use crate::qr_code::{self, CSC, QRCodeError};
+#[cfg(feature = "sign")]
+use crate::sign::{
+    Certificate, SignError, Signer, VerifyError, sign, verify, verify_chain, verify_document,
+};
 use crate::states::{City, Location, State};
-use crate::utils::left_pad;
+use crate::tax_burden::TaxBurdenProvider;
+use crate::utils::{is_lenient, lenient_field};
+#[cfg(feature = "sign")]
+use crate::xml::to_namespaced_xml;
 use chrono::Datelike;
 use nf_e_macros::MethodAlgorithm;
-use serde::ser::SerializeSeq;
-use serde::{Deserialize, Serialize, Serializer, ser::SerializeStruct};
+use serde::{Deserialize, Serialize, ser::SerializeStruct};
+use std::sync::Arc;
+
+mod billing;
+mod cofins;
+mod devolution;
+mod foreign_trade;
+mod icms;
+mod identification;
+mod intermediator;
+mod ipi;
+mod issqn;
+mod location;
+mod payment;
+mod pis;
+mod product;
+mod profile;
+mod reconciliation;
+mod transport;
+mod validation;
+mod withholding;
+pub use billing::*;
+pub use cofins::*;
+pub use devolution::*;
+pub use foreign_trade::*;
+pub use icms::*;
+pub use identification::*;
+pub use intermediator::*;
+pub use ipi::*;
+pub use issqn::*;
+pub use location::*;
+pub use payment::*;
+pub use pis::*;
+pub use product::*;
+pub use profile::{NFCeProfile, NFeProfile, Profile};
+pub use reconciliation::*;
+pub use transport::*;
+pub use validation::*;
+pub use withholding::*;
 
-#[derive(Deserialize, Debug, Clone, PartialEq, PartialOrd)]
-pub struct F64(pub f64);
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(rename = "autXML")]
+pub struct Authorized {
+    #[serde(rename = "$value")]
+    pub documents: Vec<PersonDocument>,
+}
 
-impl Serialize for F64 {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        serializer.serialize_str(&format!("{:.2}", self.0))
-    }
+pub const NFE_NAMESPACE: &str = "http://www.portalfiscal.inf.br/nfe";
+
+pub const XMLDSIG_NAMESPACE: &str = "http://www.w3.org/2000/09/xmldsig#";
+
+/// Name of the recipient (xNome) required by SEFAZ in homologation
+pub const HOMOLOGATION_RECIPIENT_NAME: &str =
+    "NF-E EMITIDA EM AMBIENTE DE HOMOLOGACAO - SEM VALOR FISCAL";
+
+/// Description of the first item (xProd) of the NFC-e required by SEFAZ in homologation
+pub const HOMOLOGATION_ITEM_DESCRIPTION: &str =
+    "NOTA FISCAL EMITIDA EM AMBIENTE DE HOMOLOGACAO - SEM VALOR FISCAL";
+
+/// Signed NFe document
+///
+/// info: Information structure (infNFe)
+/// supplement: Supplementary information (infNFeSupl) - Only for NFC-e
+/// signature: Enveloped signature of `info` (Signature)
+#[derive(Debug, PartialEq)]
+pub struct NFe {
+    pub info: Info,
+    pub supplement: Option<Supplement>,
+    pub signature: Signature,
 }
 
-impl From<f64> for F64 {
-    fn from(value: f64) -> Self {
-        F64(value)
+#[cfg(feature = "sign")]
+impl NFe {
+    /// Signs the information with the certificate of the global configuration
+    pub fn new(info: Info) -> Result<Self, SignError> {
+        let certificate = Certificate::from_config()?;
+        Self::with_certificate(info, &certificate)
+    }
+
+    /// Signs the information with the certificate of `config`
+    ///
+    /// Documents of an environment (tpAmb) other than the one of `config` are
+    /// refused, so homologation notes are never signed with a production
+    /// configuration and vice versa.
+    pub fn with_config(info: Info, config: &Config) -> Result<Self, SignError> {
+        let environment = &info.identification.environment;
+        if environment != config.environment() {
+            return Err(SignError::Environment(
+                environment.clone(),
+                config.environment().clone(),
+            ));
+        }
+        let certificate = config.certificate()?;
+        Self::with_certificate(info, &certificate)
     }
-}
 
-impl AsRef<f64> for F64 {
-    fn as_ref(&self) -> &f64 {
-        &self.0
+    pub fn with_certificate(info: Info, certificate: &Certificate) -> Result<Self, SignError> {
+        Self::with_signer(info, certificate)
     }
-}
 
-#[derive(Serialize, Deserialize, PartialEq, Debug)]
-#[serde(rename = "autXML")]
-pub struct Authorized {
-    #[serde(rename = "$value")]
-    pub documents: Vec<PersonDocument>,
+    /// Signs with the key of `signer`, as the one of an A3 token
+    pub fn with_signer(info: Info, signer: &dyn Signer) -> Result<Self, SignError> {
+        let element = to_namespaced_xml("infNFe", NFE_NAMESPACE, &info)
+            .map_err(|e| SignError::Serialization(e.to_string()))?;
+        let signature = sign(&element, &info.id(), signer)?;
+        Ok(Self {
+            info,
+            supplement: None,
+            signature,
+        })
+    }
+
+    /// Checks the signature of the information, see `sign::verify`
+    pub fn verify_signature(&self) -> Result<(), VerifyError> {
+        let element = to_namespaced_xml("infNFe", NFE_NAMESPACE, &self.info)
+            .map_err(|e| VerifyError::Serialization(e.to_string()))?;
+        verify(&element, &self.info.id(), &self.signature)
+    }
+
+    /// Checks the signature and that its certificate is issued by the
+    /// `trusted` authorities, see `sign::verify_chain`
+    pub fn verify_chain(&self, trusted: &[Vec<u8>]) -> Result<(), VerifyError> {
+        self.verify_signature()?;
+        verify_chain(&self.signature, trusted)
+    }
+
+    /// Checks the signature of a received document (NFe or nfeProc)
+    ///
+    /// The documents of other systems are not serialized back exactly as they
+    /// were signed, so the infNFe and the SignedInfo are canonicalized from the
+    /// text itself, see `sign::verify_document`.
+    pub fn verify_xml(xml: &str) -> Result<(), VerifyError> {
+        verify_document(xml, NFE_NAMESPACE, "infNFe")
+    }
 }
 
-#[derive(Default, PartialEq, Debug)]
-pub struct Transport {
-    pub r#type: TransportType,
+impl NFe {
+    /// Reads a document issued by any system, see `parse_external`
+    pub fn parse_external(xml: &str) -> Result<Self, ParseError> {
+        parse_external(xml)
+    }
+
+    /// Authorized document (nfeProc) of the document and of its protocol
+    ///
+    /// The protocol must be the one of the document: same access key (chNFe)
+    /// and same digest of the signature (digVal), when it has one. Only the
+    /// protocols of authorized or denied documents are accepted.
+    pub fn into_proc(self, protocol: Protocol) -> Result<NFeProc, ProtocolError> {
+        let info = &protocol.info;
+        let access_key = self.info.access_key();
+        if info.access_key != access_key.as_str() {
+            return Err(ProtocolError::AccessKey(
+                access_key.to_string(),
+                info.access_key.clone(),
+            ));
+        }
+        let digest = &self.signature.info.reference.digest_value;
+        if let Some(received) = &info.digest
+            && received != digest
+        {
+            return Err(ProtocolError::Digest(digest.clone(), received.clone()));
+        }
+        if !info.is_authorized() && !info.is_denied() {
+            return Err(ProtocolError::Status(info.status, info.reason.clone()));
+        }
+        Ok(NFeProc::new(self, protocol))
+    }
+
+    /// Adds the QR Code of the NFC-e, generated with the given CSC
+    pub fn with_qr_code(mut self, csc: &CSC) -> Result<Self, QRCodeError> {
+        let supplement =
+            qr_code::generate(&self.info, &self.signature.info.reference.digest_value, csc)?;
+        self.supplement = Some(supplement);
+        Ok(self)
+    }
+
+    /// Adds the QR Code of the NFC-e, generated with the newest CSC of `config`
+    /// for the environment of the document
+    pub fn with_config_qr_code(self, config: &Config) -> Result<Self, QRCodeError> {
+        let environment = &self.info.identification.environment;
+        let csc = config
+            .csc(environment)
+            .ok_or_else(|| QRCodeError::MissingCSC(environment.clone()))?
+            .clone();
+        self.with_qr_code(&csc)
+    }
+
+    /// Checks the signed document against the profile of its model, as the
+    /// QR Code required by the NFC-e
+    pub fn check_profile(&self) -> Result<(), Vec<ValidationError>> {
+        let errors = profile::check_signed(self);
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors),
+        }
+    }
 }
 
-impl Serialize for Transport {
+impl Serialize for NFe {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
-        S: Serializer,
+        S: serde::Serializer,
     {
-        let mut state = serializer.serialize_struct("transp", 1)?;
-        state.serialize_field("modFrete", &(self.r#type.clone() as u8))?;
+        let mut state =
+            serializer.serialize_struct("NFe", 3 + self.supplement.is_some() as usize)?;
+        state.serialize_field("@xmlns", NFE_NAMESPACE)?;
+        state.serialize_field("infNFe", &self.info)?;
+        if let Some(supplement) = &self.supplement {
+            state.serialize_field("infNFeSupl", supplement)?;
+        }
+        state.serialize_field("Signature", &self.signature)?;
         state.end()
     }
 }
 
-impl<'de> Deserialize<'de> for Transport {
+impl<'de> Deserialize<'de> for NFe {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: serde::Deserializer<'de>,
     {
         #[derive(Deserialize)]
-        struct TransportHelper {
-            #[serde(rename = "modFrete")]
-            mod_frete: u8,
+        struct NFeHelper {
+            #[serde(rename = "infNFe")]
+            info: Info,
+            #[serde(rename = "infNFeSupl")]
+            supplement: Option<Supplement>,
+            #[serde(rename = "Signature")]
+            signature: Signature,
         }
 
-        let helper = TransportHelper::deserialize(deserializer)?;
-        let r#type = TransportType::try_from(helper.mod_frete).map_err(serde::de::Error::custom)?;
-
-        Ok(Transport { r#type })
+        let helper = NFeHelper::deserialize(deserializer)?;
+        Ok(NFe {
+            info: helper.info,
+            supplement: helper.supplement,
+            signature: helper.signature,
+        })
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
-pub struct NFe {
-    pub info: Info,
-    pub signature: Signature,
+/// Protocol that can not be attached to a document, see `NFe::into_proc`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProtocolError {
+    /// Access key of the document and the one of the protocol (chNFe)
+    AccessKey(String, String),
+    /// Digest of the signature of the document and the one received by SEFAZ (digVal)
+    Digest(String, String),
+    /// Status code (cStat) and reason (xMotivo) of a protocol neither
+    /// authorizing nor denying the document
+    Status(u16, String),
 }
 
-impl NFe {
-    // TODO: Implement digital signature generation and verification and complete test
-    pub fn new(info: Info) -> Self {
-        let id = info.id();
-        Self {
-            info,
-            signature: Signature {
-                info: SignatureInfo {
-                    canonicalization_method: CanonicalizationMethod,
-                    signature_method: SignatureMethod,
-                    reference: SignatureReference {
-                        uri: format!("#{}", id),
-                        transforms: SignatureTransforms,
-                        digest_method: DigestMethod,
-                        digest_value: "".to_string(),
-                    },
-                },
-                key_info: KeyInfo {
-                    data: X509Data {
-                        certificate: "".to_string(),
-                    },
-                },
-                value: Vec::new(),
-            },
-        }
-    }
+/// Document that could not be read, with the reason
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError(pub String);
+
+/// Deserializes a document in lenient mode
+///
+/// The deserialization of the documents is strict, so the ones of the library
+/// round trip exactly. The documents of other systems and the ones returned by
+/// SEFAZ follow the layout instead: elements unknown to the library are
+/// skipped, the values of the library missing from the layout (as the phone of
+/// the address) are left empty and decimals may use a comma as separator.
+fn parse_external<T: serde::de::DeserializeOwned>(xml: &str) -> Result<T, ParseError> {
+    let xml = xml.trim_start_matches('\u{feff}');
+    crate::utils::lenient(|| quick_xml::de::from_str(xml)).map_err(|e| ParseError(e.to_string()))
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+/// Supplementary information of the NFC-e (infNFeSupl)
+///
+/// qr_code: Text of the QR Code printed on the DANFE NFC-e (qrCode)
+/// url: URL of the access key consultation page (urlChave)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Supplement {
+    #[serde(rename = "qrCode")]
+    pub qr_code: String,
+    #[serde(rename = "urlChave")]
+    pub url: String,
+}
+
+/// Signature structure based on the XMLDSig layout required by SEFAZ
+///
+/// info: Signed information (SignedInfo)
+/// value: Base64 encoded RSA-SHA1 signature of `info` (SignatureValue)
+/// key_info: Certificate used to sign (KeyInfo)
+#[derive(Deserialize, Debug, PartialEq)]
 pub struct Signature {
+    #[serde(rename = "SignedInfo")]
     pub info: SignatureInfo,
-    pub value: Vec<u8>,
+    #[serde(rename = "SignatureValue")]
+    pub value: String,
+    #[serde(rename = "KeyInfo")]
     pub key_info: KeyInfo,
 }
 
+impl Serialize for Signature {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("Signature", 4)?;
+        state.serialize_field("@xmlns", XMLDSIG_NAMESPACE)?;
+        state.serialize_field("SignedInfo", &self.info)?;
+        state.serialize_field("SignatureValue", &self.value)?;
+        state.serialize_field("KeyInfo", &self.key_info)?;
+        state.end()
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub struct SignatureInfo {
+    #[serde(rename = "CanonicalizationMethod")]
     pub canonicalization_method: CanonicalizationMethod,
+    #[serde(rename = "SignatureMethod")]
     pub signature_method: SignatureMethod,
+    #[serde(rename = "Reference")]
     pub reference: SignatureReference,
 }
 
@@ -151,12 +364,9 @@ impl Serialize for SignatureTransforms {
     where
         S: serde::Serializer,
     {
-        let transforms = Self::transforms();
-        let mut seq = serializer.serialize_seq(Some(transforms.len()))?;
-        for transform in transforms {
-            seq.serialize_element(&transform)?;
-        }
-        seq.end()
+        let mut state = serializer.serialize_struct("Transforms", 1)?;
+        state.serialize_field("Transform", &Self::transforms())?;
+        state.end()
     }
 }
 
@@ -185,6 +395,7 @@ impl<'de> Deserialize<'de> for SignatureTransforms {
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(untagged)]
 pub enum SignatureTransform {
     SignatureEnvelopedTransform(SignatureEnvelopedTransform),
     SignatureCanonicalizedTransform(SignatureCanonicalizedTransform),
@@ -222,6 +433,125 @@ pub struct X509Data {
     pub certificate: String,
 }
 
+/// Authorized NFe as distributed to the recipient (nfeProc)
+///
+/// nfe: Signed document (NFe)
+/// protocol: Authorization protocol of the document (protNFe)
+/// version: Fixed value "4.00" (@versao)
+#[derive(Debug, PartialEq)]
+pub struct NFeProc {
+    pub nfe: NFe,
+    pub protocol: Protocol,
+}
+
+impl NFeProc {
+    pub fn new(nfe: NFe, protocol: Protocol) -> Self {
+        Self { nfe, protocol }
+    }
+
+    /// Reads an authorized document issued by any system, see `parse_external`
+    pub fn parse_external(xml: &str) -> Result<Self, ParseError> {
+        parse_external(xml)
+    }
+
+    /// Document and protocol, the reverse of `NFe::into_proc`
+    pub fn split(self) -> (NFe, Protocol) {
+        (self.nfe, self.protocol)
+    }
+}
+
+impl Serialize for NFeProc {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("nfeProc", 4)?;
+        state.serialize_field("@xmlns", NFE_NAMESPACE)?;
+        state.serialize_field("@versao", "4.00")?;
+        state.serialize_field("NFe", &self.nfe)?;
+        state.serialize_field("protNFe", &self.protocol)?;
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for NFeProc {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct NFeProcHelper {
+            #[serde(rename = "NFe")]
+            nfe: NFe,
+            #[serde(rename = "protNFe")]
+            protocol: Protocol,
+        }
+
+        let helper = NFeProcHelper::deserialize(deserializer)?;
+        Ok(NFeProc {
+            nfe: helper.nfe,
+            protocol: helper.protocol,
+        })
+    }
+}
+
+/// Authorization protocol returned by SEFAZ for a NFe (protNFe)
+///
+/// version: Layout version (@versao)
+/// info: Protocol information (infProt)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Protocol {
+    #[serde(rename = "@versao")]
+    pub version: String,
+    #[serde(rename = "infProt")]
+    pub info: ProtocolInfo,
+}
+
+/// Protocol information structure (infProt)
+///
+/// id: Identifier of the protocol (@Id) - Optional
+/// environment: Environment type (tpAmb)
+/// application_version: Version of the SEFAZ application (verAplic)
+/// access_key: Access key of the NFe (chNFe)
+/// received_at: Date and time of the processing (dhRecbto)
+/// number: Protocol number (nProt) - Only when authorized or denied
+/// digest: Digest of the NFe received by SEFAZ (digVal) - Optional
+/// status: Status code (cStat)
+/// reason: Status description (xMotivo)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct ProtocolInfo {
+    #[serde(rename = "@Id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(rename = "tpAmb")]
+    pub environment: Environment,
+    #[serde(rename = "verAplic")]
+    pub application_version: String,
+    #[serde(rename = "chNFe")]
+    pub access_key: String,
+    #[serde(rename = "dhRecbto")]
+    pub received_at: chrono::DateTime<chrono::FixedOffset>,
+    #[serde(rename = "nProt", skip_serializing_if = "Option::is_none")]
+    pub number: Option<String>,
+    #[serde(rename = "digVal", skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+    #[serde(rename = "cStat")]
+    pub status: u16,
+    #[serde(rename = "xMotivo")]
+    pub reason: String,
+}
+
+impl ProtocolInfo {
+    /// Authorized (100) or authorized out of time (150)
+    pub fn is_authorized(&self) -> bool {
+        matches!(self.status, 100 | 150)
+    }
+
+    /// Use denied (110, 301, 302 and 303)
+    pub fn is_denied(&self) -> bool {
+        matches!(self.status, 110 | 301 | 302 | 303)
+    }
+}
+
 /// Main structure based on the XML structure of the NFe
 ///
 /// The fields are public but use the `InfoBuilder` to create the structure.
@@ -229,17 +559,27 @@ pub struct X509Data {
 /// Id: Identifier of the NFe (Id) - Format "NFe{chave}"
 /// identification: Identification structure (ide)
 /// issuer: Issuer structure (emit)
+/// recipient: Recipient structure (dest) - Optional
+/// withdrawal: Location where the goods are picked up (retirada) - Optional
+/// delivery: Location where the goods are delivered (entrega) - Optional
 /// details: Details structure (det)
+/// billing: Billing structure (cobr) - Optional
+/// intermediator: Intermediator of the operation (infIntermed) - Optional
 /// version: Fixed value "4.00" (@versao)
 #[derive(Debug, PartialEq)]
 pub struct Info {
     pub identification: Identification,
     pub issuer: Issuer,
+    pub recipient: Option<Recipient>,
+    pub withdrawal: Option<ThirdPartyLocation>,
+    pub delivery: Option<ThirdPartyLocation>,
     pub details: Vec<Detail>,
     pub authorized: Option<Authorized>,
     pub total: Total,
     pub transport: Transport,
+    pub billing: Option<Billing>,
     pub payments: Payments,
+    pub intermediator: Option<Intermediator>,
 }
 
 impl Info {
@@ -247,53 +587,26 @@ impl Info {
         "4.00".to_string()
     }
 
-    fn verifier_digit(&self, id: &str) -> u8 {
-        let mut weight = 4;
-        let remainder = id.chars().fold(0, |acc, d| {
-            let d = d
-                .to_digit(10)
-                .unwrap_or_else(|| panic!("verifier_digit: failed to parse digit '{}'", d));
-            let result = d * weight;
-            weight = if weight <= 2 { 9 } else { weight - 1 };
-            acc + result
-        }) % 11;
-        if remainder > 1 {
-            11 - remainder as u8
-        } else {
-            0
-        }
-    }
-
-    pub fn bare_id(&self) -> String {
-        let mut id = String::new();
-        id.push_str(&self.identification.location.state.code().to_string());
-        id.push_str(&self.identification.emission_date.year().to_string()[2..]);
-        id.push_str(&self.identification.emission_date.month().to_string());
-        id.push_str(left_pad(self.issuer.document.as_str(), 14, '0').as_str());
-        id.push_str(&self.identification.model.code().to_string());
-        id.push_str(left_pad(&self.identification.series.to_string(), 3, '0').as_str());
-        id.push_str(left_pad(&self.identification.number.to_string(), 9, '0').as_str());
-        id.push_str(&self.identification.emission_type.code().to_string());
-        id.push_str(left_pad(&self.identification.numeric_code.to_string(), 8, '0').as_str());
-        assert_eq!(id.len(), 43);
-        id
-    }
-
-    /// Generates the NFe key (chave) based on the identification and issuer information
-    /// The key is composed of:
-    /// - State code (cUF) - 2 digits
-    /// - Year and month of emission (AA/MM) - 4 digits
-    /// - CNPJ of the issuer - 14 digits (left-padded with zeros)
-    /// - Model of the NFe (mod) - 2 digits
-    /// - Series of the NFe (serie) - 3 digits (left-padded with zeros)
-    /// - Number of the NFe (nNF) - 9 digits (left-padded with zeros)
-    /// - Type of emission (tpEmis) - 1 digit
-    /// - Numeric code (cNF) - 8 digits (left-padded with zeros)
-    /// - Verifier digit (cDV) - 1 digit (calculated using a modulus 11 algorithm)
-    ///   Returns the complete key in the format "NFe{chave}"
+    /// Access key (chave de acesso) composed from the identification and issuer
+    pub fn access_key(&self) -> AccessKey {
+        let identification = &self.identification;
+        let emission_date = identification.local_emission_date();
+        AccessKey::new(&access_key::Components {
+            state: identification.location.state.clone(),
+            year: (emission_date.year() % 100) as u8,
+            month: emission_date.month() as u8,
+            issuer: &self.issuer.document,
+            model: identification.model.clone(),
+            series: identification.series,
+            number: identification.number,
+            emission_type: identification.emission_type.clone(),
+            numeric_code: identification.numeric_code,
+        })
+    }
+
+    /// Identifier of the document: "NFe" + access key
     pub fn id(&self) -> String {
-        let id = self.bare_id();
-        format!("NFe{}{}", id, self.verifier_digit(&id))
+        format!("NFe{}", self.access_key())
     }
 }
 
@@ -310,19 +623,31 @@ impl Serialize for Info {
             index: usize,
         }
 
-        let len = 6 + self.authorized.is_some() as usize;
+        let len = 6
+            + self.recipient.is_some() as usize
+            + self.withdrawal.is_some() as usize
+            + self.delivery.is_some() as usize
+            + self.authorized.is_some() as usize
+            + self.billing.is_some() as usize
+            + self.intermediator.is_some() as usize;
 
         let mut state = serializer.serialize_struct("infNFe", len)?;
         state.serialize_field("@versao", &self.version())?;
         state.serialize_field("@Id", &self.id())?;
         state.serialize_field("ide", &self.identification)?;
         state.serialize_field("emit", &self.issuer)?;
+        if let Some(recipient) = &self.recipient {
+            state.serialize_field("dest", recipient)?;
+        }
+        if let Some(withdrawal) = &self.withdrawal {
+            state.serialize_field("retirada", withdrawal)?;
+        }
+        if let Some(delivery) = &self.delivery {
+            state.serialize_field("entrega", delivery)?;
+        }
         if self.authorized.is_some() {
             state.serialize_field("autXML", &self.authorized)?;
         }
-        state.serialize_field("total", &self.total)?;
-        state.serialize_field("pag", &self.payments)?;
-        state.serialize_field("transp", &self.transport)?;
         state.serialize_field(
             "det",
             &self
@@ -335,6 +660,15 @@ impl Serialize for Info {
                 })
                 .collect::<Vec<_>>(),
         )?;
+        state.serialize_field("total", &self.total)?;
+        state.serialize_field("transp", &self.transport)?;
+        if let Some(billing) = &self.billing {
+            state.serialize_field("cobr", billing)?;
+        }
+        state.serialize_field("pag", &self.payments)?;
+        if let Some(intermediator) = &self.intermediator {
+            state.serialize_field("infIntermed", intermediator)?;
+        }
         state.end()
     }
 }
@@ -354,6 +688,12 @@ impl<'de> Deserialize<'de> for Info {
             identification: Identification,
             #[serde(rename = "emit")]
             issuer: Issuer,
+            #[serde(rename = "dest")]
+            recipient: Option<Recipient>,
+            #[serde(rename = "retirada")]
+            withdrawal: Option<ThirdPartyLocation>,
+            #[serde(rename = "entrega")]
+            delivery: Option<ThirdPartyLocation>,
             #[serde(rename = "det")]
             details: Vec<Detail>,
             #[serde(rename = "autXML")]
@@ -361,8 +701,12 @@ impl<'de> Deserialize<'de> for Info {
             total: Total,
             #[serde(rename = "transp")]
             transport: Transport,
+            #[serde(rename = "cobr")]
+            billing: Option<Billing>,
             #[serde(rename = "pag")]
             payments: Payments,
+            #[serde(rename = "infIntermed")]
+            intermediator: Option<Intermediator>,
         }
 
         let helper = InfoHelper::deserialize(deserializer)?;
@@ -374,14 +718,27 @@ impl<'de> Deserialize<'de> for Info {
             )));
         }
 
+        let mut identification = helper.identification;
+        let issuer_city = &helper.issuer.address.address.city;
+        if identification.location.city.name.is_empty()
+            && identification.location.city.code == issuer_city.code
+        {
+            identification.location.city.name = issuer_city.name.clone();
+        }
+
         let info = Info {
-            identification: helper.identification,
+            identification,
             issuer: helper.issuer,
+            recipient: helper.recipient,
+            withdrawal: helper.withdrawal,
+            delivery: helper.delivery,
             details: helper.details,
             authorized: helper.authorized,
             total: helper.total,
             transport: helper.transport,
+            billing: helper.billing,
             payments: helper.payments,
+            intermediator: helper.intermediator,
         };
         if info.id() != helper.id {
             return Err(serde::de::Error::custom(format!(
@@ -397,36 +754,89 @@ impl<'de> Deserialize<'de> for Info {
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct DoNotMatchTotal {
-    expected: f64,
-    total: f64,
+    expected: Money,
+    total: Money,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum InfoBuilderError {
-    PaymentsDoNotMatchTotal(DoNotMatchTotal),
+    /// Every rule violated by the document
+    Validation(Vec<ValidationError>),
     ConfigError(ConfigError),
+    #[cfg(feature = "sign")]
+    Certificate(CertificateError),
 }
 
 pub struct InfoBuilder {
+    #[cfg(feature = "sign")]
+    config: Config,
     identification: Identification,
     issuer: Issuer,
+    recipient: Option<Recipient>,
+    withdrawal: Option<ThirdPartyLocation>,
+    delivery: Option<ThirdPartyLocation>,
     payments: Payments,
     details: Vec<Detail>,
     authorized: Option<Authorized>,
     transport: Option<Transport>,
+    billing: Option<Billing>,
+    intermediator: Option<Intermediator>,
+    withholdings: Vec<Withholding>,
+    special_tax_regime: Option<SpecialTaxRegime>,
+    tax_burden: Option<Arc<dyn TaxBurdenProvider + Send + Sync>>,
 }
 
 impl InfoBuilder {
-    fn new(identification: Identification, payments: Payments) -> Result<Self, InfoBuilderError> {
-        let issuer = crate::config::get_issuer().map_err(InfoBuilderError::ConfigError)?;
-        Ok(Self {
+    /// Starts the document of the issuer of `config`
+    ///
+    /// NFC-e are issued in contingency when the configuration is in contingency.
+    pub fn new(config: &Config, identification: Identification, payments: Payments) -> Self {
+        let builder = Self {
+            #[cfg(feature = "sign")]
+            config: config.clone(),
             identification,
-            issuer,
+            issuer: config.issuer().clone(),
+            recipient: None,
+            withdrawal: None,
+            delivery: None,
             payments,
             details: Vec::new(),
             authorized: None,
             transport: None,
-        })
+            billing: None,
+            intermediator: None,
+            withholdings: Vec::new(),
+            special_tax_regime: None,
+            tax_burden: None,
+        };
+        match config.contingency() {
+            Some(contingency) if builder.identification.model == Model::NFCe => {
+                builder.set_contingency(contingency.clone())
+            }
+            _ => builder,
+        }
+    }
+
+    /// Starts the document of the issuer of the global configuration
+    pub fn from_config(
+        identification: Identification,
+        payments: Payments,
+    ) -> Result<Self, InfoBuilderError> {
+        let config = crate::config::global().map_err(InfoBuilderError::ConfigError)?;
+        Ok(Self::new(&config, identification, payments))
+    }
+
+    /// Issues the document in contingency, filling dhCont and xJust
+    ///
+    /// NFC-e are issued offline (tpEmis = 9), so their QR Code carries the
+    /// digest of the document. Other models keep the emission type, which must
+    /// be set to the contingency authorizer.
+    pub fn set_contingency(mut self, contingency: Contingency) -> Self {
+        if self.identification.model == Model::NFCe {
+            self.identification.emission_type = EmissionType::Offline;
+        }
+        self.identification.contingency = Some(contingency);
+        self
     }
 
     pub fn add_detail(mut self, detail: Detail) -> Self {
@@ -434,6 +844,28 @@ impl InfoBuilder {
         self
     }
 
+    pub fn set_recipient(mut self, recipient: Recipient) -> Self {
+        self.recipient = Some(recipient);
+        self
+    }
+
+    /// Identifies the consumer of an NFC-e by the CPF only (CPF na nota),
+    /// formatted (123.456.789-09) or not
+    pub fn set_consumer_cpf(self, cpf: &str) -> Result<Self, DocumentError> {
+        let cpf = CPF::parse(cpf)?;
+        Ok(self.set_recipient(Recipient::consumer(PersonDocument::CPF(cpf), None)))
+    }
+
+    pub fn set_withdrawal(mut self, withdrawal: ThirdPartyLocation) -> Self {
+        self.withdrawal = Some(withdrawal);
+        self
+    }
+
+    pub fn set_delivery(mut self, delivery: ThirdPartyLocation) -> Self {
+        self.delivery = Some(delivery);
+        self
+    }
+
     pub fn set_authorized(mut self, authorized: Authorized) -> Self {
         self.authorized = Some(authorized);
         self
@@ -444,53 +876,130 @@ impl InfoBuilder {
         self
     }
 
-    fn check_paid(&self, total: &Total) -> Result<(), InfoBuilderError> {
-        let paid = self
-            .payments
-            .payments
-            .iter()
-            .fold(0.0f64, |acc, p| acc + p.value.as_ref());
-        let expected = total.icms.total.as_ref();
-        if (paid - expected).abs() < f64::EPSILON {
-            Ok(())
-        } else {
-            Err(InfoBuilderError::PaymentsDoNotMatchTotal(DoNotMatchTotal {
-                expected: *expected,
-                total: paid,
-            }))
-        }
+    pub fn set_billing(mut self, billing: Billing) -> Self {
+        self.billing = Some(billing);
+        self
+    }
+
+    /// Sells through the site or platform of a third party (marketplace),
+    /// setting the intermediation of the identification (indIntermed = 1)
+    pub fn set_intermediator(mut self, intermediator: Intermediator) -> Self {
+        self.identification.intermediator_indicator = Some(IntermediatorIndicator::ThirdParty);
+        self.intermediator = Some(intermediator);
+        self
+    }
+
+    /// Adds the taxes withheld by the recipient, as the ones of a payment,
+    /// summed in the totals of the document (retTrib)
+    pub fn add_withholding(mut self, withholding: Withholding) -> Self {
+        self.withholdings.push(withholding);
+        self
+    }
+
+    pub fn set_invoice(mut self, invoice: Invoice) -> Self {
+        self.billing.get_or_insert_with(Billing::default).invoice = Some(invoice);
+        self
+    }
+
+    pub fn add_installment(mut self, installment: Installment) -> Self {
+        self.billing
+            .get_or_insert_with(Billing::default)
+            .installments
+            .push(installment);
+        self
+    }
+
+    /// Special municipal tax regime of the services (cRegTrib in ISSQNtot)
+    pub fn set_special_tax_regime(mut self, regime: SpecialTaxRegime) -> Self {
+        self.special_tax_regime = Some(regime);
+        self
+    }
+
+    /// Fills the approximate tax burden (vTotTrib) of the items that do not
+    /// inform it with the rates of `provider`
+    pub fn set_tax_burden_provider(
+        mut self,
+        provider: Arc<dyn TaxBurdenProvider + Send + Sync>,
+    ) -> Self {
+        self.tax_burden = Some(provider);
+        self
     }
 
-    pub fn build(self) -> Result<Info, InfoBuilderError> {
+    /// Builds the document, failing when the certificate of the configuration
+    /// is expired or does not belong to the issuer
+    pub fn build(mut self) -> Result<Info, InfoBuilderError> {
+        #[cfg(feature = "sign")]
+        self.config
+            .validate_certificate(self.config.clock().now())
+            .map_err(InfoBuilderError::Certificate)?;
+        if let Some(provider) = &self.tax_burden {
+            let state = &self.issuer.address.address.state;
+            for detail in &mut self.details {
+                if detail.tax.approximate_burden.is_none() {
+                    detail.tax.approximate_burden = provider
+                        .rates(detail, state)
+                        .map(|rates| rates.burden(detail));
+                }
+            }
+        }
+        if self.identification.model == Model::NFCe
+            && let Some(recipient) = &mut self.recipient
+        {
+            // Consumers of an NFC-e are never contributors of the ICMS
+            recipient.ie_indicator = IEIndicator::NonContributor;
+            recipient.ie = None;
+        }
+        if self.identification.environment == Environment::Homologation {
+            self.apply_homologation();
+        }
         let total = Total::calculate(&self);
-        self.check_paid(&total)?;
+        let errors = validation::validate(&self, &total);
+        if !errors.is_empty() {
+            return Err(InfoBuilderError::Validation(errors));
+        }
 
         let mut info = Info {
             identification: self.identification,
             issuer: self.issuer,
+            recipient: self.recipient,
+            withdrawal: self.withdrawal,
+            delivery: self.delivery,
             details: self.details,
             authorized: self.authorized,
             payments: self.payments,
             total,
             transport: self.transport.unwrap_or_default(),
+            billing: self.billing,
+            intermediator: self.intermediator,
         };
-        info.identification.verifier_digit = info.verifier_digit(&info.bare_id());
+        info.identification.verifier_digit = info.access_key().verifier_digit();
         Ok(info)
     }
-}
 
-#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
-pub struct Payments {
-    #[serde(rename = "detPag")]
-    pub payments: Vec<Payment>,
-}
+    /// Replaces the texts SEFAZ requires in homologation: the name of the
+    /// recipient and, for NFC-e, the description of the first item
+    fn apply_homologation(&mut self) {
+        let nfce = self.identification.model == Model::NFCe;
+        if let Some(recipient) = &mut self.recipient
+            && (!nfce || recipient.name.is_some())
+        {
+            recipient.name = Some(HOMOLOGATION_RECIPIENT_NAME.to_string());
+        }
+        if nfce && let Some(detail) = self.details.first_mut() {
+            detail.item.description = HOMOLOGATION_ITEM_DESCRIPTION.to_string();
+        }
+    }
 
-#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
-pub struct Payment {
-    #[serde(rename = "tPag")]
-    pub r#type: PaymentType,
-    #[serde(rename = "vPag")]
-    pub value: F64,
+    /// Builds the document of the model of the profile `P`, failing when the
+    /// identification is of another model
+    pub fn build_profile<P: Profile>(self) -> Result<Info, InfoBuilderError> {
+        if self.identification.model != P::MODEL {
+            return Err(InfoBuilderError::Validation(vec![
+                ValidationError::ProfileModel(P::MODEL),
+            ]));
+        }
+        self.build()
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
@@ -498,134 +1007,224 @@ pub struct Payment {
 pub struct Total {
     #[serde(rename = "ICMSTot")]
     pub icms: TotalICMS,
+    #[serde(rename = "ISSQNtot", skip_serializing_if = "Option::is_none")]
+    pub issqn: Option<ISSQNTotal>,
+    #[serde(rename = "retTrib", skip_serializing_if = "Option::is_none")]
+    pub withholding: Option<Withholding>,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub struct TotalICMS {
     #[serde(rename = "vBC")]
-    pub base: F64,
+    pub base: Money,
     #[serde(rename = "vICMS")]
-    pub value: F64,
+    pub value: Money,
     #[serde(rename = "vICMSDeson")]
-    pub unburdened: F64,
+    pub unburdened: Money,
     #[serde(rename = "vFCP")]
-    pub fcp_value: F64,
+    pub fcp_value: Money,
     #[serde(rename = "vBCST")]
-    pub base_tributary_substitution: F64,
+    pub base_tributary_substitution: Money,
     #[serde(rename = "vST")]
-    pub total_tributary_substitution: F64,
+    pub total_tributary_substitution: Money,
     #[serde(rename = "vFCPST")]
-    pub fcp_value_tributary_substitution: F64,
+    pub fcp_value_tributary_substitution: Money,
     #[serde(rename = "vFCPSTRet")]
-    pub retained_fcp_value_tributary_substitution: F64,
+    pub retained_fcp_value_tributary_substitution: Money,
     #[serde(rename = "vProd")]
-    pub total_products: F64,
+    pub total_products: Money,
     #[serde(rename = "vFrete")]
-    pub freight: F64,
+    pub freight: Money,
     #[serde(rename = "vSeg")]
-    pub insurance: F64,
+    pub insurance: Money,
     #[serde(rename = "vDesc")]
-    pub discount: F64,
+    pub discount: Money,
     #[serde(rename = "vII")]
-    pub import_tax: F64,
+    pub import_tax: Money,
     #[serde(rename = "vIPI")]
-    pub industrial_tax: F64,
+    pub industrial_tax: Money,
     #[serde(rename = "vIPIDevol")]
-    pub refunded_industrial_tax: F64,
+    pub refunded_industrial_tax: Money,
     #[serde(rename = "vPIS")]
-    pub pis_value: F64,
+    pub pis_value: Money,
     #[serde(rename = "vCOFINS")]
-    pub cofins_value: F64,
+    pub cofins_value: Money,
     #[serde(rename = "vOutro")]
-    pub other: F64,
+    pub other: Money,
     #[serde(rename = "vNF")]
-    pub total: F64,
+    pub total: Money,
+    /// Approximate value of the taxes of the items, when informed by any of them
+    #[serde(rename = "vTotTrib", skip_serializing_if = "Option::is_none")]
+    pub approximate_burden: Option<Money>,
 }
 
 impl Total {
     pub(crate) fn calculate(builder: &InfoBuilder) -> Self {
-        let total_products = builder
-            .details
-            .iter()
-            .fold(0.0f64, |acc, d| acc + d.item.total_value);
-        let discount = builder
-            .details
-            .iter()
-            .fold(0.0f64, |acc, d| acc + d.item.discount_value.unwrap_or(0.0));
-        let unburdened = 0.0;
-        let freight = 0.0;
-        let insurance = 0.0;
-        let other = builder
-            .details
+        Self::from_details(
+            &builder.details,
+            builder.identification.emission_date.date_naive(),
+            builder.special_tax_regime,
+            Withholding::sum(&builder.withholdings),
+        )
+    }
+
+    /// Totals of the details, with the data of the ISSQN and the withholdings
+    /// that are not derived from them
+    pub(crate) fn from_details(
+        details: &[Detail],
+        competence: chrono::NaiveDate,
+        special_regime: Option<SpecialTaxRegime>,
+        withholding: Option<Withholding>,
+    ) -> Self {
+        let (services, goods): (Vec<&Detail>, Vec<&Detail>) =
+            details.iter().partition(|d| d.item.service);
+        let sum = |details: &[&Detail], value: &dyn Fn(&Detail) -> Money| -> Money {
+            details.iter().map(|d| value(d)).sum()
+        };
+        let icms =
+            |d: &Detail, value: fn(&ICMS) -> Money| d.tax.icms.as_ref().map_or(Money::ZERO, value);
+        let pis = |d: &Detail| d.tax.pis.as_ref().map_or(Money::ZERO, PIS::value);
+        let cofins = |d: &Detail| d.tax.cofins.as_ref().map_or(Money::ZERO, COFINS::value);
+        let all = details.iter().collect::<Vec<_>>();
+
+        let base = sum(&goods, &|d| icms(d, ICMS::base));
+        let value = sum(&goods, &|d| icms(d, ICMS::value));
+        let base_tributary_substitution = sum(&goods, &|d| icms(d, ICMS::st_base));
+        let total_tributary_substitution = sum(&goods, &|d| icms(d, ICMS::st_value));
+        let unburdened = sum(&goods, &|d| icms(d, ICMS::unburdened));
+        let fcp_value = sum(&goods, &|d| icms(d, ICMS::fcp_value));
+        let fcp_value_tributary_substitution = sum(&goods, &|d| icms(d, ICMS::st_fcp_value));
+        let retained_fcp_value_tributary_substitution =
+            sum(&goods, &|d| icms(d, ICMS::retained_st_fcp_value));
+
+        let total_products = sum(&goods, &|d| d.item.total_value);
+        let freight = sum(&all, &|d| d.item.freight.unwrap_or(Money::ZERO));
+        let insurance = sum(&all, &|d| d.item.insurance.unwrap_or(Money::ZERO));
+        let discount = sum(&all, &|d| d.item.discount_value.unwrap_or(Money::ZERO));
+        let other = sum(&all, &|d| d.item.other_value.unwrap_or(Money::ZERO));
+        let pis_value = sum(&goods, &pis);
+        let cofins_value = sum(&goods, &cofins);
+        let import_tax = sum(&goods, &|d| {
+            d.tax.ii.as_ref().map_or(Money::ZERO, |ii| ii.value)
+        });
+        let industrial_tax = sum(&goods, &|d| {
+            d.tax.ipi.as_ref().map_or(Money::ZERO, IPI::value)
+        });
+        let approximate_burden = all
             .iter()
-            .fold(0.0f64, |acc, d| acc + d.item.other_value.unwrap_or(0.0));
-        let import_tax = 0.0;
-        let industrial_tax = 0.0;
-        let refunded_industrial_tax = 0.0;
-
-        let total_value = total_products - discount - unburdened
-            + freight
-            + insurance
-            + other
-            + import_tax
-            + industrial_tax
-            + refunded_industrial_tax;
+            .filter_map(|d| d.tax.approximate_burden)
+            .reduce(|total, value| total + value);
+        let refunded_industrial_tax = sum(&all, &|d| {
+            d.devolution
+                .as_ref()
+                .map_or(Money::ZERO, |devolution| devolution.ipi.value)
+        });
+
+        let issqn = (!services.is_empty()).then(|| {
+            let issqn = |value: &dyn Fn(&ISSQN) -> Option<Money>| {
+                let total = sum(&services, &|d| {
+                    d.tax.issqn.as_ref().and_then(value).unwrap_or(Money::ZERO)
+                });
+                (total != Money::ZERO).then_some(total)
+            };
+            ISSQNTotal {
+                services: Some(sum(&services, &|d| d.item.total_value)),
+                base: issqn(&|i| Some(i.base)),
+                value: issqn(&|i| Some(i.value)),
+                pis_value: Some(sum(&services, &pis)).filter(|v| *v != Money::ZERO),
+                cofins_value: Some(sum(&services, &cofins)).filter(|v| *v != Money::ZERO),
+                competence,
+                deduction: issqn(&|i| i.deduction),
+                other: issqn(&|i| i.other),
+                unconditional_discount: issqn(&|i| i.unconditional_discount),
+                conditional_discount: issqn(&|i| i.conditional_discount),
+                retained_value: issqn(&|i| i.retained_value),
+                special_regime,
+            }
+        });
+        let total_services = issqn
+            .as_ref()
+            .and_then(|issqn| issqn.services)
+            .unwrap_or(Money::ZERO);
+
+        let mut icms = TotalICMS {
+            base,
+            value,
+            unburdened,
+            fcp_value,
+            base_tributary_substitution,
+            total_tributary_substitution,
+            fcp_value_tributary_substitution,
+            retained_fcp_value_tributary_substitution,
+            total_products,
+            freight,
+            insurance,
+            discount,
+            import_tax,
+            industrial_tax,
+            refunded_industrial_tax,
+            pis_value,
+            cofins_value,
+            other,
+            total: Money::ZERO,
+            approximate_burden,
+        };
+        icms.total = icms.document_total(total_services);
 
         Total {
-            icms: TotalICMS {
-                base: F64(0.0),
-                value: F64(0.0),
-                unburdened: F64(unburdened),
-                fcp_value: F64(0.0),
-                base_tributary_substitution: F64(0.0),
-                total_tributary_substitution: F64(0.0),
-                fcp_value_tributary_substitution: F64(0.0),
-                retained_fcp_value_tributary_substitution: F64(0.0),
-                total_products: F64(total_products),
-                freight: F64(freight),
-                insurance: F64(insurance),
-                discount: F64(discount),
-                import_tax: F64(import_tax),
-                industrial_tax: F64(industrial_tax),
-                refunded_industrial_tax: F64(refunded_industrial_tax),
-                pis_value: F64(0.0),
-                cofins_value: F64(0.0),
-                other: F64(other),
-                total: F64(total_value),
-            },
+            icms,
+            issqn,
+            withholding,
         }
     }
 }
 
+impl TotalICMS {
+    /// Value of the document (vNF) from the other totals and the value of the services
+    pub(crate) fn document_total(&self, services: Money) -> Money {
+        self.total_products - self.discount - self.unburdened
+            + self.total_tributary_substitution
+            + self.fcp_value_tributary_substitution
+            + self.freight
+            + self.insurance
+            + self.other
+            + self.import_tax
+            + self.industrial_tax
+            + self.refunded_industrial_tax
+            + services
+    }
+}
+
 /// Identification structure based on the XML structure of the NFe
 ///
 /// location: Location of the issuer (cUF, cMun)
 /// numeric_code: Numeric code of the NFe (cNF)
 /// operation_nature: Nature of the operation (natOp)
 /// model: Model of the NFe (mod)
-/// series: Series of the NFe (serie)
+/// series: Series of the NFe, up to 999 (serie)
 /// number: Number of the NFe (nNF)
 /// emission_date: Date and time of emission (dhEmi)
 /// date: Date and time of exit or entry (dhSaiEnt) - Optional
 /// type: Type of operation (tpNF)
 /// destination: Destination target (idDest)
-/// printing_type: Type of DANFE printing (tpImp) - Optional
+/// printing_type: Type of DANFE printing (tpImp) - None without DANFE (0)
 /// emission_type: Type of emission (tpEmis)
 /// verifier_digit: Verifier digit (cDV)
 /// environment: Environment type (tpAmb)
 /// finality: Finality of the NFe (finNFe)
 /// consumer: Indicates if the operation is for a final consumer (indFinal)
 /// presence: Presence indicator (indPres) - Optional
-/// intermediator: Intermediator information (intermed) - Optional
+/// intermediator_indicator: Intermediation of the operation (indIntermed) - Optional
 /// emission_process: Emission process (procEmi) - Fixed value "0"
-/// emission_version: Emission version (verProc) - Library version
+/// emission_version: Version of the issuing application (verProc) - Library version by default
+/// contingency: Entry in contingency (dhCont, xJust) - Only for contingency emissions
 #[derive(Debug, PartialEq)]
 pub struct Identification {
     pub location: Location,
     pub numeric_code: u32,
     pub operation_nature: String,
     pub model: Model,
-    pub series: u8,
+    pub series: u16,
     pub number: u32,
     pub emission_date: chrono::DateTime<chrono::Local>,
     pub date: Option<chrono::DateTime<chrono::Local>>,
@@ -638,7 +1237,9 @@ pub struct Identification {
     pub finality: Finality,
     pub consumer: bool,
     pub presence: Option<Presence>,
-    pub intermediator: Option<Intermediator>,
+    pub intermediator_indicator: Option<IntermediatorIndicator>,
+    pub emission_version: String,
+    pub contingency: Option<Contingency>,
 }
 
 impl Identification {
@@ -646,8 +1247,24 @@ impl Identification {
         0
     }
 
-    fn emission_version(&self) -> &str {
-        LIBRARY_VERSION
+    /// Date of emission in the legal offset of the location of the issuer,
+    /// which gives the year and month of the access key
+    pub fn local_emission_date(&self) -> chrono::DateTime<chrono::FixedOffset> {
+        self.in_location(&self.emission_date)
+    }
+
+    /// Date in the legal offset of the location of the issuer, whatever the
+    /// zone of the machine
+    fn in_location(
+        &self,
+        date: &chrono::DateTime<chrono::Local>,
+    ) -> chrono::DateTime<chrono::FixedOffset> {
+        date.with_timezone(&self.location.utc_offset(date.to_utc()))
+    }
+
+    fn local_date(&self, date: &chrono::DateTime<chrono::Local>) -> String {
+        self.in_location(date)
+            .to_rfc3339_opts(chrono::SecondsFormat::Secs, false)
     }
 }
 
@@ -658,41 +1275,42 @@ impl Serialize for Identification {
     {
         let len = 17
             + self.date.is_some() as usize
-            + self.printing_type.is_some() as usize
-            + self.intermediator.is_some() as usize;
+            + self.intermediator_indicator.is_some() as usize
+            + 2 * self.contingency.is_some() as usize;
 
         let mut state = serializer.serialize_struct("ide", len)?;
-        state.serialize_field("cUF", &(self.location.state.clone() as u8))?;
+        state.serialize_field("cUF", &self.location.state.code())?;
         state.serialize_field("cNF", &self.numeric_code)?;
         state.serialize_field("natOp", &self.operation_nature)?;
-        state.serialize_field("mod", &(self.model.clone() as u8))?;
+        state.serialize_field("mod", &self.model.code())?;
         state.serialize_field("serie", &self.series)?;
         state.serialize_field("nNF", &self.number)?;
-        state.serialize_field("dhEmi", &self.emission_date.to_rfc3339())?;
+        state.serialize_field("dhEmi", &self.local_date(&self.emission_date))?;
         if let Some(date) = &self.date {
-            state.serialize_field("dhSaiEnt", &date.to_utc())?;
+            state.serialize_field("dhSaiEnt", &self.local_date(date))?;
         }
-        state.serialize_field("tpNF", &(self.r#type.clone() as u8))?;
-        state.serialize_field("idDest", &(self.destination.clone() as u8))?;
+        state.serialize_field("tpNF", &self.r#type.code())?;
+        state.serialize_field("idDest", &self.destination.code())?;
         state.serialize_field("cMunFG", &self.location.city.code)?;
-        state.serialize_field("xMun", &self.location.city.name)?;
-        if let Some(printing_type) = &self.printing_type {
-            state.serialize_field("tpImp", &(printing_type.clone() as u8))?;
-        }
-        state.serialize_field("tpEmis", &(self.emission_type.clone() as u8))?;
-        state.serialize_field("cDV", &self.verifier_digit)?;
-        state.serialize_field("tpAmb", &(self.environment.clone() as u8))?;
-        state.serialize_field("finNFe", &(self.finality.clone() as u8))?;
-        state.serialize_field("indFinal", if self.consumer { &1 } else { &0 })?;
         state.serialize_field(
-            "indPres",
-            &(self.presence.as_ref().map_or(0, |p| (*p).clone() as u8)),
+            "tpImp",
+            &self.printing_type.as_ref().map_or(0, DanfeGeneration::code),
         )?;
-        if let Some(intermediator) = &self.intermediator {
-            state.serialize_field("intermed", intermediator)?;
+        state.serialize_field("tpEmis", &self.emission_type.code())?;
+        state.serialize_field("cDV", &self.verifier_digit)?;
+        state.serialize_field("tpAmb", &self.environment.code())?;
+        state.serialize_field("finNFe", &self.finality.code())?;
+        state.serialize_field("indFinal", if self.consumer { &1 } else { &0 })?;
+        state.serialize_field("indPres", &self.presence.as_ref().map_or(0, Presence::code))?;
+        if let Some(indicator) = &self.intermediator_indicator {
+            state.serialize_field("indIntermed", &indicator.code())?;
         }
         state.serialize_field("procEmi", &self.emission_process())?;
-        state.serialize_field("verProc", &self.emission_version())?;
+        state.serialize_field("verProc", &self.emission_version)?;
+        if let Some(contingency) = &self.contingency {
+            state.serialize_field("dhCont", &self.local_date(&contingency.date))?;
+            state.serialize_field("xJust", &contingency.justification)?;
+        }
         state.end()
     }
 }
@@ -713,7 +1331,7 @@ impl<'de> Deserialize<'de> for Identification {
             #[serde(rename = "mod")]
             model: u8,
             #[serde(rename = "serie")]
-            serie: u8,
+            serie: u16,
             #[serde(rename = "nNF")]
             n_nf: u32,
             #[serde(rename = "dhEmi")]
@@ -727,7 +1345,7 @@ impl<'de> Deserialize<'de> for Identification {
             #[serde(rename = "cMunFG")]
             c_mun_fg: u32,
             #[serde(rename = "xMun")]
-            x_mun: String,
+            x_mun: Option<String>,
             #[serde(rename = "tpImp")]
             tp_imp: Option<u8>,
             #[serde(rename = "tpEmis")]
@@ -742,19 +1360,34 @@ impl<'de> Deserialize<'de> for Identification {
             ind_final: u8,
             #[serde(rename = "indPres")]
             ind_pres: u8,
-            #[serde(rename = "intermed")]
-            intermed: Option<Intermediator>,
+            #[serde(rename = "indIntermed")]
+            ind_intermed: Option<u8>,
+            #[serde(rename = "verProc")]
+            ver_proc: String,
+            #[serde(rename = "dhCont")]
+            dh_cont: Option<String>,
+            #[serde(rename = "xJust")]
+            x_just: Option<String>,
         }
 
         let helper = IdentificationHelper::deserialize(deserializer)?;
+        // Not part of the layout, only written by older versions of the library,
+        // filled from the address of the issuer by `Info` otherwise
+        let x_mun = helper.x_mun.unwrap_or_default();
+        if helper.serie > crate::disablement::MAX_SERIES {
+            return Err(serde::de::Error::custom(format!(
+                "Invalid series: {}",
+                helper.serie
+            )));
+        }
         let state = State::try_from(helper.c_uf).map_err(serde::de::Error::custom)?;
         let model = Model::try_from(helper.model).map_err(serde::de::Error::custom)?;
         let r#type = Operation::try_from(helper.tp_nf).map_err(serde::de::Error::custom)?;
         let destination =
             DestinationTarget::try_from(helper.id_dest).map_err(serde::de::Error::custom)?;
         let printing_type = match helper.tp_imp {
+            None | Some(0) => None,
             Some(v) => Some(DanfeGeneration::try_from(v).map_err(serde::de::Error::custom)?),
-            None => None,
         };
         let emission_type =
             EmissionType::try_from(helper.tp_emis).map_err(serde::de::Error::custom)?;
@@ -766,6 +1399,11 @@ impl<'de> Deserialize<'de> for Identification {
             1..=6 => Some(Presence::try_from(helper.ind_pres).map_err(serde::de::Error::custom)?),
             _ => return Err(serde::de::Error::custom("Invalid ind_pres value")),
         };
+        let intermediator_indicator = helper
+            .ind_intermed
+            .map(IntermediatorIndicator::try_from)
+            .transpose()
+            .map_err(serde::de::Error::custom)?;
         let emission_date = chrono::DateTime::parse_from_rfc3339(&helper.dh_emi)
             .map_err(serde::de::Error::custom)?
             .with_timezone(&chrono::Local);
@@ -777,12 +1415,23 @@ impl<'de> Deserialize<'de> for Identification {
             ),
             None => None,
         };
+        let contingency = match (helper.dh_cont, helper.x_just) {
+            (Some(date), Some(justification)) => Some(Contingency {
+                date: chrono::DateTime::parse_from_rfc3339(&date)
+                    .map_err(serde::de::Error::custom)?
+                    .with_timezone(&chrono::Local),
+                justification,
+            }),
+            (None, None) => None,
+            (Some(_), None) => return Err(serde::de::Error::missing_field("xJust")),
+            (None, Some(_)) => return Err(serde::de::Error::missing_field("dhCont")),
+        };
         Ok(Identification {
             location: Location {
                 state,
                 city: City {
                     code: helper.c_mun_fg,
-                    name: helper.x_mun,
+                    name: x_mun,
                 },
             },
             numeric_code: helper.c_nf,
@@ -801,11 +1450,32 @@ impl<'de> Deserialize<'de> for Identification {
             finality,
             consumer,
             presence,
-            intermediator: helper.intermed,
+            intermediator_indicator,
+            emission_version: helper.ver_proc,
+            contingency,
         })
     }
 }
 
+/// Entry in contingency of the issuer
+///
+/// date: Date and time of the entry in contingency (dhCont)
+/// justification: Reason of the contingency, 15 to 256 characters (xJust)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contingency {
+    pub date: chrono::DateTime<chrono::Local>,
+    pub justification: String,
+}
+
+impl Contingency {
+    pub fn new(date: chrono::DateTime<chrono::Local>, justification: &str) -> Self {
+        Contingency {
+            date,
+            justification: justification.trim().to_string(),
+        }
+    }
+}
+
 /// Address structure based on the XML structure of the NFe
 ///
 /// line_1: Address line 1 (xLgr)
@@ -838,18 +1508,18 @@ impl Serialize for Address {
         let len = 7 + self.line_2.is_some() as usize;
         let mut state = serializer.serialize_struct("enderEmit", len)?;
         state.serialize_field("xLgr", &self.line_1)?;
+        state.serialize_field("nro", &self.number)?;
         if let Some(line_2) = &self.line_2 {
             state.serialize_field("xCpl", line_2)?;
         }
-        state.serialize_field("nro", &self.number)?;
         state.serialize_field("xBairro", &self.neighborhood)?;
         state.serialize_field("cMun", &self.city.code)?;
         state.serialize_field("xMun", &self.city.name)?;
         state.serialize_field("UF", self.state.acronym())?;
         state.serialize_field("CEP", &self.zip_code)?;
-        state.serialize_field("fone", &self.telephone)?;
-        state.serialize_field("xPais", &"Brasil".to_string())?;
         state.serialize_field("cPais", &1058)?;
+        state.serialize_field("xPais", &"Brasil".to_string())?;
+        state.serialize_field("fone", &self.telephone)?;
         state.end()
     }
 }
@@ -876,9 +1546,9 @@ impl<'de> Deserialize<'de> for Address {
             #[serde(rename = "UF")]
             uf: String,
             #[serde(rename = "CEP")]
-            cep: String,
+            cep: Option<String>,
             #[serde(rename = "fone")]
-            fone: String,
+            fone: Option<String>,
         }
 
         let helper = AddressHelper::deserialize(deserializer)?;
@@ -896,8 +1566,8 @@ impl<'de> Deserialize<'de> for Address {
                 name: helper.x_mun,
             },
             state,
-            zip_code: helper.cep,
-            telephone: helper.fone,
+            zip_code: lenient_field(helper.cep, "CEP")?,
+            telephone: lenient_field(helper.fone, "fone")?,
         })
     }
 }
@@ -905,7 +1575,8 @@ impl<'de> Deserialize<'de> for Address {
 /// Taxable entity identifier
 ///
 /// address: Address of the taxable entity
-/// ie: State registration (IE) - Use "ISENTO" if exempt
+/// ie: State registration (IE) - Use "ISENTO" if exempt, serialized by the `Issuer` out of the
+/// address
 #[derive(Debug, PartialEq, Clone)]
 pub struct TaxableAddress {
     pub address: Address,
@@ -917,22 +1588,7 @@ impl Serialize for TaxableAddress {
     where
         S: serde::Serializer,
     {
-        let mut state = serializer.serialize_struct("enderEmit", 8)?;
-        state.serialize_field("xLgr", &self.address.line_1)?;
-        if let Some(line_2) = &self.address.line_2 {
-            state.serialize_field("xCpl", line_2)?;
-        }
-        state.serialize_field("nro", &self.address.number)?;
-        state.serialize_field("xBairro", &self.address.neighborhood)?;
-        state.serialize_field("cMun", &self.address.city.code)?;
-        state.serialize_field("xMun", &self.address.city.name)?;
-        state.serialize_field("UF", self.address.state.acronym())?;
-        state.serialize_field("CEP", &self.address.zip_code)?;
-        state.serialize_field("fone", &self.address.telephone)?;
-        state.serialize_field("xPais", &"Brasil".to_string())?;
-        state.serialize_field("cPais", &1058)?;
-        state.serialize_field("IE", &self.ie.0)?;
-        state.end()
+        self.address.serialize(serializer)
     }
 }
 
@@ -958,17 +1614,23 @@ impl<'de> Deserialize<'de> for TaxableAddress {
             #[serde(rename = "UF")]
             uf: String,
             #[serde(rename = "CEP")]
-            cep: String,
+            cep: Option<String>,
             #[serde(rename = "fone")]
-            fone: String,
+            fone: Option<String>,
             #[serde(rename = "IE")]
-            ie: String,
+            ie: Option<String>,
         }
 
         let helper = TaxableAddressHelper::deserialize(deserializer)?;
         let state = State::from_acronym(&helper.uf).ok_or_else(|| {
             serde::de::Error::custom(format!("Invalid state acronym: {}", helper.uf))
         })?;
+        // The layout places the IE out of the address, in the emit, where it is
+        // read by `Issuer`. Older versions of the library wrote it here
+        let ie = IE(helper.ie.unwrap_or_default());
+        if !ie.0.is_empty() {
+            ie.validate(&state).map_err(serde::de::Error::custom)?;
+        }
 
         Ok(TaxableAddress {
             address: Address {
@@ -981,10 +1643,10 @@ impl<'de> Deserialize<'de> for TaxableAddress {
                     name: helper.x_mun,
                 },
                 state,
-                zip_code: helper.cep,
-                telephone: helper.fone,
+                zip_code: lenient_field(helper.cep, "CEP")?,
+                telephone: lenient_field(helper.fone, "fone")?,
             },
-            ie: IE(helper.ie),
+            ie,
         })
     }
 }
@@ -994,26 +1656,163 @@ impl<'de> Deserialize<'de> for TaxableAddress {
 /// document: Document (CNPJ, CPF, or IE)
 /// name: Legal name of the issuer (xNome)
 /// trade_name: Trade name of the issuer (xFant) - Optional
-/// address: Taxable address of the issuer (enderEmit)
-#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
-#[serde(rename = "emit")]
+/// address: Taxable address of the issuer (enderEmit, IE)
+/// ie_st: State registration as tax substitute in the destination state (IEST) - Optional
+/// im: Municipal registration, when services are provided (IM) - Optional
+/// cnae: Main economic activity code, informed along with the IM (CNAE) - Optional
+/// tax_regime: Tax regime of the issuer (CRT)
+#[derive(Debug, PartialEq, Clone)]
 pub struct Issuer {
-    #[serde(rename = "$value")]
     pub document: PersonDocument,
-    #[serde(rename = "xNome")]
     pub name: String,
-    #[serde(rename = "xFant")]
     pub trade_name: Option<String>,
-    #[serde(rename = "enderEmit")]
     pub address: TaxableAddress,
+    pub ie_st: Option<IE>,
+    pub im: Option<String>,
+    pub cnae: Option<String>,
+    pub tax_regime: TaxRegime,
+}
+
+impl Serialize for Issuer {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let len = 6
+            + self.ie_st.is_some() as usize
+            + self.im.is_some() as usize
+            + self.cnae.is_some() as usize;
+        let mut state = serializer.serialize_struct("emit", len)?;
+        state.serialize_field("$value", &self.document)?;
+        state.serialize_field("xNome", &self.name)?;
+        state.serialize_field("xFant", &self.trade_name)?;
+        state.serialize_field("enderEmit", &self.address)?;
+        state.serialize_field("IE", &self.address.ie)?;
+        if let Some(ie_st) = &self.ie_st {
+            state.serialize_field("IEST", ie_st)?;
+        }
+        if let Some(im) = &self.im {
+            state.serialize_field("IM", im)?;
+        }
+        if let Some(cnae) = &self.cnae {
+            state.serialize_field("CNAE", cnae)?;
+        }
+        state.serialize_field("CRT", &self.tax_regime)?;
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Issuer {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct IssuerHelper {
+            #[serde(rename = "$value")]
+            document: PersonDocument,
+            #[serde(rename = "xNome")]
+            name: String,
+            #[serde(rename = "xFant")]
+            trade_name: Option<String>,
+            #[serde(rename = "enderEmit")]
+            address: TaxableAddress,
+            #[serde(rename = "IE")]
+            ie: Option<IE>,
+            #[serde(rename = "IEST")]
+            ie_st: Option<IE>,
+            #[serde(rename = "IM")]
+            im: Option<String>,
+            #[serde(rename = "CNAE")]
+            cnae: Option<String>,
+            #[serde(rename = "CRT")]
+            tax_regime: TaxRegime,
+        }
+
+        let mut helper = IssuerHelper::deserialize(deserializer)?;
+        if let Some(ie) = helper.ie {
+            ie.validate(&helper.address.address.state)
+                .map_err(serde::de::Error::custom)?;
+            helper.address.ie = ie;
+        } else if helper.address.ie.0.is_empty() && !is_lenient() {
+            return Err(serde::de::Error::missing_field("IE"));
+        }
+        Ok(Issuer {
+            document: helper.document,
+            name: helper.name,
+            trade_name: helper.trade_name,
+            address: helper.address,
+            ie_st: helper.ie_st,
+            im: helper.im,
+            cnae: helper.cnae,
+            tax_regime: helper.tax_regime,
+        })
+    }
+}
+
+/// Recipient structure based on the XML structure of the NFe
+///
+/// document: Document (CNPJ, CPF, or idEstrangeiro)
+/// name: Legal name of the recipient (xNome) - Optional for NFC-e
+/// address: Address of the recipient (enderDest) - Optional for NFC-e
+/// ie_indicator: State registration indicator (indIEDest)
+/// ie: State registration (IE) - Only for contributors
+/// suframa: SUFRAMA registration (ISUF) - Optional
+/// im: Municipal registration (IM) - Optional
+/// email: E-mail address (email) - Optional
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename = "dest")]
+pub struct Recipient {
+    #[serde(rename = "$value")]
+    pub document: RecipientDocument,
+    #[serde(rename = "xNome", skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(rename = "enderDest", skip_serializing_if = "Option::is_none")]
+    pub address: Option<Address>,
+    #[serde(rename = "indIEDest")]
+    pub ie_indicator: IEIndicator,
+    #[serde(rename = "IE", skip_serializing_if = "Option::is_none")]
+    pub ie: Option<IE>,
+    #[serde(rename = "ISUF", skip_serializing_if = "Option::is_none")]
+    pub suframa: Option<String>,
+    #[serde(rename = "IM", skip_serializing_if = "Option::is_none")]
+    pub im: Option<String>,
+    #[serde(rename = "email", skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+}
+
+impl Recipient {
+    /// Simplified recipient of an NFC-e: the document and, optionally, the
+    /// name of the consumer, who is not a contributor (indIEDest = 9)
+    pub fn consumer(document: PersonDocument, name: Option<String>) -> Self {
+        Recipient {
+            document: match document {
+                PersonDocument::CNPJ(cnpj) => RecipientDocument::CNPJ(cnpj),
+                PersonDocument::CPF(cpf) => RecipientDocument::CPF(cpf),
+            },
+            name,
+            address: None,
+            ie_indicator: IEIndicator::NonContributor,
+            ie: None,
+            suframa: None,
+            im: None,
+            email: None,
+        }
+    }
 }
 
 /// Item structure based on the XML structure of the NFe
 ///
 /// code: Product code (cProd)
-/// gtin: Global Trade Item Number (cEAN) - Optional
+/// gtin: Global Trade Item Number (cEAN), see `validate_gtin` - Optional, "SEM GTIN" when missing
 /// description: Product description (xProd)
-/// ncm: NCM code (Nomenclatura Comum do Mercosul)
+/// ncm: NCM code (Nomenclatura Comum do Mercosul) - 0 for services, serialized as "00000000"
+/// nve_codes: Codes of the Nomenclatura de Valor Aduaneiro e Estatística, up to 8 (NVE)
+/// cest: Tax substitution specifier code (CEST) - Optional
+/// scale: Whether the product is made in a relevant scale (indEscala) - Optional
+/// manufacturer: CNPJ of the manufacturer, when not in a relevant scale (CNPJFab) - Optional
+/// benefit_code: Code of the tax benefit of the state (cBenef) - Optional
+/// ex_tipi: Exception code of the TIPI (EXTIPI) - Optional
 /// cfop: CFOP code (Código Fiscal de Operações e Prestações)
 /// unit: Unit of measurement (uCom)
 /// quantity: Quantity of the product (qCom)
@@ -1021,25 +1820,59 @@ pub struct Issuer {
 /// tribute_unit: Unit of measurement for tax purposes (uTrib)
 /// tribute_quantity: Quantity for tax purposes (qTrib)
 /// tribute_unit_value: Unit value for tax purposes (vUnTrib)
+/// freight: Freight value apportioned to the item (vFrete) - Optional
+/// insurance: Insurance value apportioned to the item (vSeg) - Optional
 /// discount_value: Discount value (vDesc) - Optional
 /// other_value: Other additional costs (vOutro) - Optional
 /// included: Indicates if the item is included in the total invoice value (indTot)
+/// import_declarations: Import declarations of the item (DI)
+/// export_details: Export details of the item (detExport)
+/// traceability: Traceability of the batches of the product (rastro)
+/// vehicle: Details of the new vehicle (veicProd) - Optional
+/// medicine: Details of the medicine (med) - Optional
+/// fuel: Details of the fuel (comb) - Optional
+/// service: Indicates if the item is a service taxed by the ISSQN, not serialized
 #[derive(Debug, PartialEq)]
 pub struct Item {
     pub code: String,
     pub gtin: Option<String>,
     pub description: String,
     pub ncm: u32,
+    pub nve_codes: Vec<String>,
+    pub cest: Option<String>,
+    pub scale: Option<ProductionScale>,
+    pub manufacturer: Option<CNPJ>,
+    pub benefit_code: Option<String>,
+    pub ex_tipi: Option<String>,
     pub cfop: u32,
     pub unit: String,
-    pub quantity: f64,
-    pub total_value: f64,
+    pub quantity: Quantity,
+    pub total_value: Money,
     pub tribute_unit: String,
-    pub tribute_quantity: f64,
-    pub tribute_unit_value: f64,
-    pub discount_value: Option<f64>,
-    pub other_value: Option<f64>,
+    pub tribute_quantity: Quantity,
+    pub tribute_unit_value: Money,
+    pub freight: Option<Money>,
+    pub insurance: Option<Money>,
+    pub discount_value: Option<Money>,
+    pub other_value: Option<Money>,
     pub included: bool,
+    pub import_declarations: Vec<ImportDeclaration>,
+    pub export_details: Vec<ExportDetail>,
+    pub traceability: Vec<Traceability>,
+    pub vehicle: Option<NewVehicle>,
+    pub medicine: Option<Medicine>,
+    pub fuel: Option<Fuel>,
+    pub service: bool,
+}
+
+impl Item {
+    /// Commercial unit value (vUnCom), derived from the total and the quantity
+    pub fn unit_value(&self) -> Money {
+        self.total_value
+            .value()
+            .checked_div(self.quantity.value())
+            .map_or(Money::ZERO, Money::from)
+    }
 }
 
 impl Serialize for Item {
@@ -1049,35 +1882,88 @@ impl Serialize for Item {
     {
         let len = 12
             + self.gtin.is_some() as usize
+            + !self.nve_codes.is_empty() as usize
+            + self.cest.is_some() as usize
+            + self.scale.is_some() as usize
+            + self.manufacturer.is_some() as usize
+            + self.benefit_code.is_some() as usize
+            + self.ex_tipi.is_some() as usize
+            + self.freight.is_some() as usize
+            + self.insurance.is_some() as usize
             + self.discount_value.is_some() as usize
-            + self.other_value.is_some() as usize;
-
-        let no_gtin = &"SEM GTIN".to_string();
+            + self.other_value.is_some() as usize
+            + !self.import_declarations.is_empty() as usize
+            + !self.export_details.is_empty() as usize
+            + !self.traceability.is_empty() as usize
+            + self.vehicle.is_some() as usize
+            + self.medicine.is_some() as usize
+            + self.fuel.is_some() as usize;
+
+        let no_gtin = &NO_GTIN.to_string();
         let gtin = self.gtin.as_ref().unwrap_or(no_gtin);
         let mut state = serializer.serialize_struct("prod", len)?;
         state.serialize_field("cProd", &self.code)?;
         state.serialize_field("cEAN", gtin)?;
         state.serialize_field("xProd", &self.description)?;
-        state.serialize_field("NCM", &self.ncm)?;
+        state.serialize_field("NCM", &format!("{:08}", self.ncm))?;
+        if !self.nve_codes.is_empty() {
+            state.serialize_field("NVE", &self.nve_codes)?;
+        }
+        if let Some(cest) = &self.cest {
+            state.serialize_field("CEST", cest)?;
+        }
+        if let Some(scale) = &self.scale {
+            state.serialize_field("indEscala", scale)?;
+        }
+        if let Some(manufacturer) = &self.manufacturer {
+            state.serialize_field("CNPJFab", manufacturer)?;
+        }
+        if let Some(benefit_code) = &self.benefit_code {
+            state.serialize_field("cBenef", benefit_code)?;
+        }
+        if let Some(ex_tipi) = &self.ex_tipi {
+            state.serialize_field("EXTIPI", ex_tipi)?;
+        }
         state.serialize_field("CFOP", &self.cfop)?;
         state.serialize_field("uCom", &self.unit)?;
-        state.serialize_field("qCom", &format!("{:.4}", self.quantity))?;
-        state.serialize_field(
-            "vUnCom",
-            &format!("{:.2}", self.total_value / self.quantity),
-        )?;
-        state.serialize_field("vProd", &format!("{:.2}", self.total_value))?;
+        state.serialize_field("qCom", &self.quantity)?;
+        state.serialize_field("vUnCom", &self.unit_value())?;
+        state.serialize_field("vProd", &self.total_value)?;
         state.serialize_field("cEANTrib", gtin)?;
         state.serialize_field("uTrib", &self.tribute_unit)?;
-        state.serialize_field("qTrib", &format!("{:.4}", self.tribute_quantity))?;
-        state.serialize_field("vUnTrib", &format!("{:.2}", self.tribute_unit_value))?;
+        state.serialize_field("qTrib", &self.tribute_quantity)?;
+        state.serialize_field("vUnTrib", &self.tribute_unit_value)?;
+        if let Some(freight) = &self.freight {
+            state.serialize_field("vFrete", freight)?;
+        }
+        if let Some(insurance) = &self.insurance {
+            state.serialize_field("vSeg", insurance)?;
+        }
         if let Some(discount_value) = &self.discount_value {
-            state.serialize_field("vDesc", &format!("{:.4}", discount_value))?;
+            state.serialize_field("vDesc", discount_value)?;
         }
         if let Some(other_value) = &self.other_value {
-            state.serialize_field("vOutro", &format!("{:.4}", other_value))?;
+            state.serialize_field("vOutro", other_value)?;
         }
         state.serialize_field("indTot", if self.included { &1 } else { &0 })?;
+        if !self.import_declarations.is_empty() {
+            state.serialize_field("DI", &self.import_declarations)?;
+        }
+        if !self.export_details.is_empty() {
+            state.serialize_field("detExport", &self.export_details)?;
+        }
+        if !self.traceability.is_empty() {
+            state.serialize_field("rastro", &self.traceability)?;
+        }
+        if let Some(vehicle) = &self.vehicle {
+            state.serialize_field("veicProd", vehicle)?;
+        }
+        if let Some(medicine) = &self.medicine {
+            state.serialize_field("med", medicine)?;
+        }
+        if let Some(fuel) = &self.fuel {
+            state.serialize_field("comb", fuel)?;
+        }
         state.end()
     }
 }
@@ -1097,54 +1983,60 @@ impl<'de> Deserialize<'de> for Item {
             x_prod: String,
             #[serde(rename = "NCM")]
             ncm: u32,
+            #[serde(rename = "NVE", default)]
+            nve: Vec<String>,
+            #[serde(rename = "CEST")]
+            cest: Option<String>,
+            #[serde(rename = "indEscala")]
+            ind_escala: Option<ProductionScale>,
+            #[serde(rename = "CNPJFab")]
+            cnpj_fab: Option<CNPJ>,
+            #[serde(rename = "cBenef")]
+            c_benef: Option<String>,
+            #[serde(rename = "EXTIPI")]
+            extipi: Option<String>,
             #[serde(rename = "CFOP")]
             cfop: u32,
             #[serde(rename = "uCom")]
             u_com: String,
             #[serde(rename = "qCom")]
-            q_com: String,
+            q_com: Quantity,
             #[serde(rename = "vProd")]
-            v_prod: String,
+            v_prod: Money,
             #[serde(rename = "uTrib")]
             u_trib: String,
             #[serde(rename = "qTrib")]
-            q_trib: String,
+            q_trib: Quantity,
             #[serde(rename = "vUnTrib")]
-            v_un_trib: String,
+            v_un_trib: Money,
+            #[serde(rename = "vFrete")]
+            v_frete: Option<Money>,
+            #[serde(rename = "vSeg")]
+            v_seg: Option<Money>,
             #[serde(rename = "vDesc")]
-            v_desc: Option<String>,
+            v_desc: Option<Money>,
             #[serde(rename = "vOutro")]
-            v_outro: Option<String>,
+            v_outro: Option<Money>,
             #[serde(rename = "indTot")]
             ind_tot: u8,
+            #[serde(rename = "DI", default)]
+            di: Vec<ImportDeclaration>,
+            #[serde(rename = "detExport", default)]
+            det_export: Vec<ExportDetail>,
+            #[serde(rename = "rastro", default)]
+            rastro: Vec<Traceability>,
+            #[serde(rename = "veicProd")]
+            veic_prod: Option<NewVehicle>,
+            med: Option<Medicine>,
+            comb: Option<Fuel>,
         }
 
         let helper = ItemHelper::deserialize(deserializer)?;
+        let gtin = helper.c_ean.filter(|gtin| gtin != NO_GTIN);
+        if let Some(gtin) = gtin.as_ref().filter(|_| !is_lenient()) {
+            validate_gtin(gtin).map_err(serde::de::Error::custom)?;
+        }
 
-        let quantity = helper
-            .q_com
-            .parse::<f64>()
-            .map_err(serde::de::Error::custom)?;
-        let total_value = helper
-            .v_prod
-            .parse::<f64>()
-            .map_err(serde::de::Error::custom)?;
-        let tribute_quantity = helper
-            .q_trib
-            .parse::<f64>()
-            .map_err(serde::de::Error::custom)?;
-        let tribute_unit_value = helper
-            .v_un_trib
-            .parse::<f64>()
-            .map_err(serde::de::Error::custom)?;
-        let discount_value = match helper.v_desc {
-            Some(v) => Some(v.parse::<f64>().map_err(serde::de::Error::custom)?),
-            None => None,
-        };
-        let other_value = match helper.v_outro {
-            Some(v) => Some(v.parse::<f64>().map_err(serde::de::Error::custom)?),
-            None => None,
-        };
         let included = match helper.ind_tot {
             0 => false,
             1 => true,
@@ -1153,147 +2045,712 @@ impl<'de> Deserialize<'de> for Item {
 
         Ok(Item {
             code: helper.c_prod,
-            gtin: helper.c_ean,
+            gtin,
             description: helper.x_prod,
             ncm: helper.ncm,
+            nve_codes: helper.nve,
+            cest: helper.cest,
+            scale: helper.ind_escala,
+            manufacturer: helper.cnpj_fab,
+            benefit_code: helper.c_benef,
+            ex_tipi: helper.extipi,
             cfop: helper.cfop,
             unit: helper.u_com,
-            quantity,
-            total_value,
+            quantity: helper.q_com,
+            total_value: helper.v_prod,
             tribute_unit: helper.u_trib,
-            tribute_quantity,
-            tribute_unit_value,
-            discount_value,
-            other_value,
+            tribute_quantity: helper.q_trib,
+            tribute_unit_value: helper.v_un_trib,
+            freight: helper.v_frete,
+            insurance: helper.v_seg,
+            discount_value: helper.v_desc,
+            other_value: helper.v_outro,
             included,
+            import_declarations: helper.di,
+            export_details: helper.det_export,
+            traceability: helper.rastro,
+            vehicle: helper.veic_prod,
+            medicine: helper.med,
+            fuel: helper.comb,
+            service: false,
         })
     }
 }
 
-/// ICMS structure for CSOSN 102
+/// Tax structure of an item (imposto)
 ///
-/// origin: Origin of the product (orig)
-/// csosn: CSOSN code (CSOSN)
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
-pub struct ICMSSN102 {
-    #[serde(rename = "orig")]
-    pub origin: Origin,
-    #[serde(rename = "CSOSN")]
-    pub csosn: CSOSN,
-}
-
+/// approximate_burden: Approximate value of the taxes of the item, Lei 12.741/2012 (vTotTrib) - Optional
+/// icms: ICMS group of the goods (ICMS) - Optional
+/// ipi: IPI group (IPI) - Optional
+/// ii: Import tax group (II) - Optional
+/// issqn: ISSQN group of the services (ISSQN) - Optional
+/// pis: PIS group (PIS) - Optional
+/// pis_st: PIS ST group (PISST) - Optional
+/// cofins: COFINS group (COFINS) - Optional
+/// cofins_st: COFINS ST group (COFINSST) - Optional
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[serde(rename = "imposto")]
 pub struct Tax {
-    #[serde(rename = "ICMS")]
-    pub icms: ICMS,
+    #[serde(rename = "vTotTrib", skip_serializing_if = "Option::is_none")]
+    pub approximate_burden: Option<Money>,
+    #[serde(rename = "ICMS", skip_serializing_if = "Option::is_none")]
+    pub icms: Option<ICMS>,
+    #[serde(rename = "IPI", skip_serializing_if = "Option::is_none")]
+    pub ipi: Option<IPI>,
+    #[serde(rename = "II", skip_serializing_if = "Option::is_none")]
+    pub ii: Option<II>,
+    #[serde(rename = "ISSQN", skip_serializing_if = "Option::is_none")]
+    pub issqn: Option<ISSQN>,
+    #[serde(rename = "PIS", skip_serializing_if = "Option::is_none")]
+    pub pis: Option<PIS>,
+    #[serde(rename = "PISST", skip_serializing_if = "Option::is_none")]
+    pub pis_st: Option<PISST>,
+    #[serde(rename = "COFINS", skip_serializing_if = "Option::is_none")]
+    pub cofins: Option<COFINS>,
+    #[serde(rename = "COFINSST", skip_serializing_if = "Option::is_none")]
+    pub cofins_st: Option<COFINSST>,
 }
 
 /// Detail structure based on the XML structure of the NFe
 ///
 /// item: Item structure (prod)
 /// tax: Tax structure (imposto)
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+/// devolution: Taxes of the goods returned (impostoDevol) - Optional
+/// additional_info: Additional information of the item (infAdProd) - Optional
+#[derive(Serialize, Debug, PartialEq)]
 #[serde(rename = "det")]
 pub struct Detail {
     #[serde(rename = "prod")]
     pub item: Item,
     #[serde(rename = "imposto")]
     pub tax: Tax,
+    #[serde(rename = "impostoDevol", skip_serializing_if = "Option::is_none")]
+    pub devolution: Option<Devolution>,
+    #[serde(rename = "infAdProd", skip_serializing_if = "Option::is_none")]
+    pub additional_info: Option<String>,
+}
+
+impl<'de> Deserialize<'de> for Detail {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct DetailHelper {
+            #[serde(rename = "prod")]
+            item: Item,
+            #[serde(rename = "imposto")]
+            tax: Tax,
+            #[serde(rename = "impostoDevol")]
+            devolution: Option<Devolution>,
+            #[serde(rename = "infAdProd")]
+            additional_info: Option<String>,
+        }
+
+        let DetailHelper {
+            mut item,
+            tax,
+            devolution,
+            additional_info,
+        } = DetailHelper::deserialize(deserializer)?;
+        item.service = tax.issqn.is_some();
+        Ok(Detail {
+            item,
+            tax,
+            devolution,
+            additional_info,
+        })
+    }
 }
 
 #[cfg(test)]
 pub mod tests {
     use super::*;
-    use crate::config::{Config, PKCS12Config, set_config};
-    use crate::utils::canonicalize_xml as canonicalize;
-    use chrono::TimeZone;
-    use nf_e_macros::serialization_test;
+    use crate::config::{Config, PKCS12Config};
+    #[cfg(feature = "sign")]
+    use crate::sign::tests::setup_certificate;
+    use crate::tax_burden::TaxBurdenRates;
+    use crate::xml::{indent_xml, normalize_xml as canonicalize};
+    use chrono::{NaiveDate, TimeZone};
+    use nf_e_macros::{deserialization_error_test, serialization_test};
     use quick_xml::{de::from_str as deserialize, se::to_string as serialize};
+    use rust_decimal_macros::dec;
 
-    #[serialization_test(fixture = "../tests/fixtures/tax.xml")]
+    /// Moment of 2023-10-05 at `hour`:`minute` in Belo Horizonte, whatever the
+    /// zone of the machine
+    pub fn setup_local_date(hour: u32, minute: u32) -> chrono::DateTime<chrono::Local> {
+        chrono::FixedOffset::west_opt(3 * 3600)
+            .unwrap()
+            .with_ymd_and_hms(2023, 10, 5, hour, minute, 0)
+            .unwrap()
+            .with_timezone(&chrono::Local)
+    }
+
+    #[serialization_test(fixture = "../tests/fixtures/tax.xml", indent = "indent_xml")]
     fn setup_tax() -> Tax {
         Tax {
-            icms: ICMS::ICMSSN102(ICMSSN102 {
+            approximate_burden: None,
+            icms: Some(ICMS::ICMSSN102(ICMSSN102 {
                 origin: Origin::National,
                 csosn: CSOSN::FinalConsumer,
-            }),
+            })),
+            ipi: None,
+            ii: None,
+            issqn: None,
+            pis: None,
+            pis_st: None,
+            cofins: None,
+            cofins_st: None,
         }
     }
 
-    #[serialization_test(fixture = "../tests/fixtures/item.xml")]
+    #[serialization_test(
+        fixture = "../tests/fixtures/item.xml",
+        roundtrip,
+        indent = "indent_xml"
+    )]
     fn setup_item() -> Item {
         Item {
             cfop: 5403,
             code: "7896235354499".to_string(),
             description: "desodorante aerosol monange 200ML".to_string(),
             ncm: 33072010,
+            nve_codes: Vec::new(),
+            cest: None,
+            scale: None,
+            manufacturer: None,
+            benefit_code: None,
+            ex_tipi: None,
             gtin: Some("7896235354499".to_string()),
             included: true,
-            quantity: 3.0f64,
-            total_value: 18.99f64 * 3.0f64,
+            quantity: Quantity::from(dec!(3)),
+            total_value: Money::from(dec!(18.99) * dec!(3)),
             unit: "UN".to_string(),
             tribute_unit: "UN".to_string(),
-            tribute_quantity: 3.0f64,
-            tribute_unit_value: 18.99f64,
+            tribute_quantity: Quantity::from(dec!(3)),
+            tribute_unit_value: Money::from(dec!(18.99)),
+            freight: None,
+            insurance: None,
             discount_value: None,
             other_value: None,
+            import_declarations: Vec::new(),
+            export_details: Vec::new(),
+            traceability: Vec::new(),
+            vehicle: None,
+            medicine: None,
+            fuel: None,
+            service: false,
+        }
+    }
+
+    #[test]
+    fn test_item_invalid_gtin() {
+        let xml = include_str!("../tests/fixtures/item.xml");
+        let xml = xml.replace("<cEAN>7896235354499", "<cEAN>7896235354490");
+        let error = deserialize::<Item>(&xml).unwrap_err();
+        assert!(error.to_string().contains("Invalid GTIN check digit"));
+
+        let xml = xml.replace("<cEAN>7896235354490", "<cEAN>SEM GTIN");
+        assert_eq!(deserialize::<Item>(&xml).unwrap().gtin, None);
+    }
+
+    #[serialization_test(fixture = "../tests/fixtures/item_fiscal.xml", indent = "indent_xml")]
+    fn setup_item_fiscal() -> Item {
+        Item {
+            nve_codes: vec!["AA0001".to_string(), "AB0002".to_string()],
+            cest: Some("2003500".to_string()),
+            scale: Some(ProductionScale::NotRelevant),
+            manufacturer: Some(CNPJ("11222333000181".to_string())),
+            benefit_code: Some("MG000001".to_string()),
+            ex_tipi: Some("01".to_string()),
+            freight: Some(Money::from(dec!(4.50))),
+            insurance: Some(Money::from(dec!(1.20))),
+            ..setup_item()
+        }
+    }
+
+    #[serialization_test(fixture = "../tests/fixtures/item_medicine.xml", indent = "indent_xml")]
+    fn setup_item_medicine() -> Item {
+        Item {
+            traceability: vec![
+                product::tests::setup_traceability(),
+                Traceability {
+                    batch: "L2310B".to_string(),
+                    quantity: dec!(2),
+                    manufacture_date: NaiveDate::from_ymd_opt(2023, 10, 2).unwrap(),
+                    expiration_date: NaiveDate::from_ymd_opt(2025, 10, 2).unwrap(),
+                    aggregation_code: Some("7896235354499".to_string()),
+                },
+            ],
+            medicine: Some(product::tests::setup_medicine()),
+            ..setup_item()
         }
     }
 
-    #[serialization_test(fixture = "../tests/fixtures/detail.xml")]
-    fn setup_detail() -> Detail {
+    #[serialization_test(
+        fixture = "../tests/fixtures/item_foreign_trade.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_item_foreign_trade() -> Item {
+        Item {
+            import_declarations: vec![foreign_trade::tests::setup_import_declaration()],
+            export_details: vec![foreign_trade::tests::setup_export_detail()],
+            ..setup_item()
+        }
+    }
+
+    #[serialization_test(fixture = "../tests/fixtures/item_fuel.xml", indent = "indent_xml")]
+    fn setup_item_fuel() -> Item {
+        Item {
+            code: "GC".to_string(),
+            gtin: None,
+            description: "GASOLINA COMUM".to_string(),
+            ncm: 27101259,
+            cfop: 5656,
+            unit: "L".to_string(),
+            quantity: Quantity::from(dec!(40)),
+            total_value: Money::from(dec!(239.60)),
+            tribute_unit: "L".to_string(),
+            tribute_quantity: Quantity::from(dec!(40)),
+            tribute_unit_value: Money::from(dec!(5.99)),
+            fuel: Some(product::tests::setup_fuel()),
+            ..setup_item()
+        }
+    }
+
+    #[serialization_test(fixture = "../tests/fixtures/detail.xml", indent = "indent_xml")]
+    pub fn setup_detail() -> Detail {
         Detail {
             tax: Tax {
-                icms: ICMS::ICMSSN102(ICMSSN102 {
+                approximate_burden: None,
+                icms: Some(ICMS::ICMSSN102(ICMSSN102 {
                     csosn: CSOSN::FinalConsumer,
                     origin: Origin::National,
-                }),
+                })),
+                ipi: None,
+                ii: None,
+                issqn: None,
+                pis: None,
+                pis_st: None,
+                cofins: None,
+                cofins_st: None,
             },
             item: setup_item(),
+            devolution: None,
+            additional_info: None,
+        }
+    }
+
+    #[serialization_test(
+        fixture = "../tests/fixtures/detail_additional_info.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_detail_additional_info() -> Detail {
+        Detail {
+            additional_info: Some("Lote sujeito a conferencia no recebimento".to_string()),
+            ..setup_detail()
         }
     }
 
-    fn setup_payments() -> Payments {
+    pub fn setup_payments() -> Payments {
         Payments {
             payments: vec![
                 Payment {
+                    indicator: None,
                     r#type: PaymentType::Cash,
-                    value: F64(40.00),
+                    description: None,
+                    value: Money::from(dec!(40.00)),
+                    card: None,
                 },
                 Payment {
+                    indicator: None,
                     r#type: PaymentType::CreditCard,
-                    value: F64(73.94),
+                    description: None,
+                    value: Money::from(dec!(73.94)),
+                    card: None,
                 },
             ],
+            change: None,
         }
     }
 
-    fn setup_config() {
-        if crate::config::is_set() {
-            return;
-        }
-
-        set_config(Config::new(
+    pub fn setup_config() -> Config {
+        Config::new(
             setup_issuer(),
             PKCS12Config::new(
-                "tests/certificates/cert.pfx".to_string(),
+                "tests/credentials/cert.p12".to_string(),
                 "12345678".to_string(),
             ),
-        ))
-        .expect("Failed to set config");
+        )
+        .with_emission_version("0.1.0")
+    }
+
+    #[serialization_test(
+        fixture = "../tests/fixtures/tax_contributions.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_tax_contributions() -> Tax {
+        Tax {
+            approximate_burden: None,
+            icms: Some(ICMS::ICMSSN102(ICMSSN102 {
+                origin: Origin::National,
+                csosn: CSOSN::FinalConsumer,
+            })),
+            ipi: None,
+            ii: None,
+            issqn: None,
+            pis: Some(PIS::PISAliq(PISAliq {
+                cst: ContributionCST::TaxableBasicRate,
+                base: Money::from(dec!(100.0)),
+                rate: Money::from(dec!(1.65)),
+                value: Money::from(dec!(1.65)),
+            })),
+            pis_st: None,
+            cofins: Some(COFINS::COFINSNT(COFINSNT {
+                cst: ContributionCST::Exempt,
+            })),
+            cofins_st: None,
+        }
     }
 
-    fn setup_info_builder() -> InfoBuilder {
-        setup_config();
+    #[test]
+    fn test_total_contributions() {
+        let mut detail = setup_detail();
+        detail.tax = setup_tax_contributions();
+        detail.tax.cofins = Some(COFINS::COFINSAliq(COFINSAliq {
+            cst: ContributionCST::TaxableBasicRate,
+            base: Money::from(dec!(100.0)),
+            rate: Money::from(dec!(7.6)),
+            value: Money::from(dec!(7.6)),
+        }));
+        let builder = InfoBuilder::new(&setup_config(), setup_identification(), setup_payments())
+            .add_detail(detail)
+            .add_detail(setup_detail());
+
+        let total = Total::calculate(&builder);
+        assert_eq!(total.icms.pis_value, Money::from(dec!(1.65)));
+        assert_eq!(total.icms.cofins_value, Money::from(dec!(7.6)));
+    }
 
-        InfoBuilder::new(setup_identification(), setup_payments())
-            .unwrap()
+    #[test]
+    fn test_total_mixed_cst() {
+        let mut taxed = setup_detail();
+        taxed.item.freight = Some(Money::from(dec!(5.00)));
+        taxed.item.insurance = Some(Money::from(dec!(1.50)));
+        taxed.tax.icms = Some(ICMS::ICMS00(ICMS00 {
+            origin: Origin::National,
+            cst: CST::Taxed,
+            base_modality: BaseModality::OperationValue,
+            base: Money::from(dec!(63.47)),
+            rate: Money::from(dec!(18.00)),
+            value: Money::from(dec!(11.42)),
+            fcp_rate: Some(Money::from(dec!(2.00))),
+            fcp_value: Some(Money::from(dec!(1.27))),
+        }));
+        let mut with_st = setup_detail();
+        with_st.item.discount_value = Some(Money::from(dec!(6.97)));
+        with_st.tax.icms = Some(ICMS::ICMS10(ICMS10 {
+            origin: Origin::National,
+            cst: CST::TaxedWithST,
+            base_modality: BaseModality::OperationValue,
+            base: Money::from(dec!(50.00)),
+            rate: Money::from(dec!(18.00)),
+            value: Money::from(dec!(9.00)),
+            fcp_base: None,
+            fcp_rate: None,
+            fcp_value: None,
+            st_base_modality: STBaseModality::ValueAddedMargin,
+            st_margin: Some(Money::from(dec!(40.00))),
+            st_base_reduction: None,
+            st_base: Money::from(dec!(70.00)),
+            st_rate: Money::from(dec!(18.00)),
+            st_value: Money::from(dec!(3.60)),
+            st_fcp_base: Some(Money::from(dec!(70.00))),
+            st_fcp_rate: Some(Money::from(dec!(2.00))),
+            st_fcp_value: Some(Money::from(dec!(1.40))),
+        }));
+        let mut exempt = setup_detail();
+        exempt.tax.icms = Some(ICMS::ICMS40(ICMS40 {
+            origin: Origin::National,
+            cst: CST::Exempt,
+            unburdened: Some(Money::from(dec!(10.25))),
+            unburden_reason: Some(UnburdenReason::Other),
+        }));
+        let builder = InfoBuilder::new(&setup_config(), setup_identification(), setup_payments())
+            .add_detail(taxed)
+            .add_detail(with_st)
+            .add_detail(exempt)
+            .add_detail(setup_detail());
+
+        let total = Total::calculate(&builder).icms;
+        assert_eq!(total.base, Money::from(dec!(113.47)));
+        assert_eq!(total.value, Money::from(dec!(20.42)));
+        assert_eq!(total.base_tributary_substitution, Money::from(dec!(70.00)));
+        assert_eq!(total.total_tributary_substitution, Money::from(dec!(3.60)));
+        assert_eq!(total.freight, Money::from(dec!(5.00)));
+        assert_eq!(total.insurance, Money::from(dec!(1.50)));
+        assert_eq!(total.discount, Money::from(dec!(6.97)));
+        assert_eq!(total.total_products, Money::from(dec!(227.88)));
+        assert_eq!(total.unburdened, Money::from(dec!(10.25)));
+        assert_eq!(total.fcp_value, Money::from(dec!(1.27)));
+        assert_eq!(
+            total.fcp_value_tributary_substitution,
+            Money::from(dec!(1.40))
+        );
+        // vProd - vDesc - vICMSDeson + vST + vFCPST + vFrete + vSeg
+        assert_eq!(total.total, Money::from(dec!(222.16)));
+    }
+
+    #[test]
+    fn test_total_retained_st() {
+        let mut retained = setup_detail();
+        retained.tax.icms = Some(ICMS::ICMS60(ICMS60 {
+            origin: Origin::National,
+            cst: CST::PreviouslyChargedST,
+            retained_st_base: Some(Money::from(dec!(63.47))),
+            final_consumer_rate: Some(Money::from(dec!(20.00))),
+            substitute_value: None,
+            retained_st_value: Some(Money::from(dec!(11.42))),
+            retained_st_fcp_base: Some(Money::from(dec!(63.47))),
+            retained_st_fcp_rate: Some(Money::from(dec!(2.00))),
+            retained_st_fcp_value: Some(Money::from(dec!(1.27))),
+            effective_base_reduction: None,
+            effective_base: None,
+            effective_rate: None,
+            effective_value: None,
+        }));
+        let builder = InfoBuilder::new(&setup_config(), setup_identification(), setup_payments())
+            .add_detail(retained)
+            .add_detail(setup_detail());
+
+        let total = Total::calculate(&builder).icms;
+        assert_eq!(
+            total.retained_fcp_value_tributary_substitution,
+            Money::from(dec!(1.27))
+        );
+        // The ST retained previously is not due in the operation
+        assert_eq!(total.total_tributary_substitution, Money::ZERO);
+        assert_eq!(total.total, total.total_products);
+    }
+
+    #[test]
+    fn test_total_devolution() {
+        let mut returned = setup_detail();
+        returned.devolution = Some(devolution::tests::setup_devolution());
+        let builder = InfoBuilder::new(&setup_config(), setup_identification(), setup_payments())
+            .add_detail(returned)
+            .add_detail(setup_detail());
+
+        let total = Total::calculate(&builder).icms;
+        assert_eq!(total.refunded_industrial_tax, Money::from(dec!(4.50)));
+        assert_eq!(
+            total.total,
+            total.total_products - total.discount + Money::from(dec!(4.50))
+        );
+    }
+
+    #[test]
+    fn test_total_ipi_and_ii() {
+        let mut industrialized = setup_detail();
+        industrialized.tax.ipi = Some(ipi::tests::setup_ipi_trib());
+        let mut imported = setup_detail();
+        imported.tax.ii = Some(ipi::tests::setup_ii());
+        let builder = InfoBuilder::new(&setup_config(), setup_identification(), setup_payments())
+            .add_detail(industrialized)
+            .add_detail(imported);
+
+        let total = Total::calculate(&builder).icms;
+        assert_eq!(total.industrial_tax, Money::from(dec!(10.00)));
+        assert_eq!(total.import_tax, Money::from(dec!(140.00)));
+        assert_eq!(total.total, Money::from(dec!(263.94)));
+    }
+
+    #[test]
+    fn test_total_services() {
+        let mut service = setup_detail();
+        service.item.service = true;
+        service.item.total_value = Money::from(dec!(100.00));
+        service.tax.icms = None;
+        service.tax.issqn = Some(issqn::tests::setup_issqn());
+        service.tax.pis = Some(PIS::PISAliq(PISAliq {
+            cst: ContributionCST::TaxableBasicRate,
+            base: Money::from(dec!(100.00)),
+            rate: Money::from(dec!(0.65)),
+            value: Money::from(dec!(0.65)),
+        }));
+        let builder = InfoBuilder::new(&setup_config(), setup_identification(), setup_payments())
             .add_detail(setup_detail())
+            .add_detail(service)
+            .set_special_tax_regime(SpecialTaxRegime::Estimate);
+
+        let total = Total::calculate(&builder);
+        assert_eq!(total.icms.total_products, Money::from(dec!(56.97)));
+        assert_eq!(total.icms.pis_value, Money::ZERO);
+        assert_eq!(total.icms.total, Money::from(dec!(156.97)));
+        assert_eq!(
+            total.issqn,
+            Some(ISSQNTotal {
+                services: Some(Money::from(dec!(100.00))),
+                base: Some(Money::from(dec!(100.00))),
+                value: Some(Money::from(dec!(5.00))),
+                pis_value: Some(Money::from(dec!(0.65))),
+                cofins_value: None,
+                competence: setup_identification().emission_date.date_naive(),
+                deduction: None,
+                other: None,
+                unconditional_discount: None,
+                conditional_discount: None,
+                retained_value: Some(Money::from(dec!(2.00))),
+                special_regime: Some(SpecialTaxRegime::Estimate),
+            })
+        );
+    }
+
+    #[test]
+    fn test_tax_burden() {
+        let provider = |_: &Detail, state: &State| {
+            (*state == State::MinasGerais).then_some(TaxBurdenRates {
+                federal: dec!(13.45),
+                state: dec!(18),
+                municipal: dec!(0),
+            })
+        };
+        let mut informed = setup_detail();
+        informed.tax.approximate_burden = Some(Money::from(dec!(1.00)));
+        let info = InfoBuilder::new(&setup_config(), setup_identification(), setup_payments())
             .add_detail(setup_detail())
+            .add_detail(informed)
+            .set_tax_burden_provider(Arc::new(provider))
+            .build()
+            .expect("Failed to build the document");
+
+        assert_eq!(
+            info.details[0]
+                .tax
+                .approximate_burden
+                .map(|v| v.to_string()),
+            Some("17.92".to_string())
+        );
+        assert_eq!(
+            info.details[1].tax.approximate_burden,
+            Some(Money::from(dec!(1.00)))
+        );
+        assert_eq!(
+            info.total.icms.approximate_burden.map(|v| v.to_string()),
+            Some("18.92".to_string())
+        );
+        let xml = serialize(&info.details[0].tax).unwrap();
+        assert!(xml.starts_with("<imposto><vTotTrib>17.92</vTotTrib><ICMS>"));
+
+        let info = setup_info_builder().build().unwrap();
+        assert_eq!(info.total.icms.approximate_burden, None);
     }
 
-    #[serialization_test(fixture = "../tests/fixtures/info_authorized.xml")]
+    #[test]
+    fn test_service_detail_deserialization() {
+        let mut detail = setup_detail();
+        detail.item.service = true;
+        detail.tax.icms = None;
+        detail.tax.issqn = Some(issqn::tests::setup_issqn());
+
+        let xml = serialize(&detail).expect("Failed to serialize detail");
+        let parsed: Detail = deserialize(&xml).expect("Failed to deserialize detail");
+        assert_eq!(parsed, detail);
+
+        let xml = serialize(&setup_detail()).expect("Failed to serialize detail");
+        let parsed: Detail = deserialize(&xml).expect("Failed to deserialize detail");
+        assert!(!parsed.item.service);
+    }
+
+    #[test]
+    fn test_total_simples_nacional_with_st() {
+        let mut detail = setup_detail();
+        detail.tax.icms = Some(ICMS::ICMSSN900(ICMSSN900 {
+            origin: Origin::National,
+            csosn: CSOSN::Others,
+            base_modality: Some(BaseModality::OperationValue),
+            base: Some(Money::from(dec!(56.97))),
+            base_reduction: None,
+            rate: Some(Money::from(dec!(12.00))),
+            value: Some(Money::from(dec!(6.84))),
+            st_base_modality: None,
+            st_margin: None,
+            st_base_reduction: None,
+            st_base: Some(Money::from(dec!(80.00))),
+            st_rate: None,
+            st_value: Some(Money::from(dec!(2.76))),
+            st_fcp_base: None,
+            st_fcp_rate: None,
+            st_fcp_value: None,
+            credit_rate: None,
+            credit_value: None,
+        }));
+        let builder = InfoBuilder::new(&setup_config(), setup_identification(), setup_payments())
+            .add_detail(detail)
+            .add_detail(setup_detail());
+
+        let total = Total::calculate(&builder).icms;
+        assert_eq!(total.base, Money::from(dec!(56.97)));
+        assert_eq!(total.value, Money::from(dec!(6.84)));
+        assert_eq!(total.base_tributary_substitution, Money::from(dec!(80.00)));
+        assert_eq!(total.total_tributary_substitution, Money::from(dec!(2.76)));
+        assert_eq!(total.total, Money::from(dec!(116.70)));
+    }
+
+    #[test]
+    fn test_payments_do_not_match_total() {
+        let mut payments = setup_payments();
+        payments.payments[1].value = Money::from(dec!(73.95));
+        let result = InfoBuilder::new(&setup_config(), setup_identification(), payments)
+            .add_detail(setup_detail())
+            .add_detail(setup_detail())
+            .build();
+
+        assert_eq!(
+            result.err(),
+            Some(InfoBuilderError::Validation(vec![
+                ValidationError::PaymentsDoNotMatchTotal(DoNotMatchTotal {
+                    expected: Money::from(dec!(113.94)),
+                    total: Money::from(dec!(113.95)),
+                })
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_multiple_configs() {
+        let mut other = setup_issuer();
+        other.document = PersonDocument::CNPJ(CNPJ("11222333000181".to_string()));
+        other.name = "Outra Empresa LTDA".to_string();
+        let other_config = Config::new(
+            other.clone(),
+            PKCS12Config::new(
+                "tests/credentials/cert.p12".to_string(),
+                "12345678".to_string(),
+            ),
+        );
+
+        let build = |config: &Config| {
+            InfoBuilder::new(config, setup_identification(), setup_payments())
+                .add_detail(setup_detail())
+                .add_detail(setup_detail())
+                .build()
+                .expect("Failed to build info")
+        };
+        assert_eq!(build(&setup_config()).issuer, setup_issuer());
+        assert_eq!(build(&other_config).issuer, other);
+    }
+
+    pub fn setup_info_builder() -> InfoBuilder {
+        InfoBuilder::new(&setup_config(), setup_identification(), setup_payments())
+            .add_detail(setup_detail())
+            .add_detail(setup_detail())
+    }
+
+    #[serialization_test(
+        fixture = "../tests/fixtures/info_authorized.xml",
+        indent = "indent_xml"
+    )]
     fn setup_info() -> Info {
         setup_info_builder()
             .set_authorized(setup_authorized())
@@ -1301,6 +2758,30 @@ pub mod tests {
             .expect("Failed to build Info")
     }
 
+    #[test]
+    fn test_withdrawal_and_delivery() {
+        let info = setup_info_builder()
+            .set_recipient(setup_recipient())
+            .set_withdrawal(ThirdPartyLocation {
+                name: None,
+                email: None,
+                ie: None,
+                ..location::tests::setup_delivery()
+            })
+            .set_delivery(location::tests::setup_delivery())
+            .build()
+            .expect("Failed to build Info");
+
+        let xml = serialize(&info).expect("Failed to serialize info");
+        let withdrawal = xml.find("<retirada>").expect("Missing retirada");
+        let delivery = xml.find("<entrega>").expect("Missing entrega");
+        assert!(xml.find("</dest>").unwrap() < withdrawal);
+        assert!(withdrawal < delivery);
+
+        let parsed: Info = deserialize(&xml).expect("Failed to deserialize info");
+        assert_eq!(parsed, info);
+    }
+
     #[test]
     fn serialize_info_without_authorized() {
         let info = setup_info_builder().build().expect("Failed to build Info");
@@ -1312,8 +2793,20 @@ pub mod tests {
         );
     }
 
-    #[serialization_test(fixture = "../tests/fixtures/identification.xml")]
-    fn setup_identification() -> Identification {
+    #[deserialization_error_test(
+        fixture = "../tests/fixtures/malformed/info_version.xml",
+        expect = "Unsupported version: 3.10"
+    )]
+    fn deserialize_info_unsupported_version() -> Info;
+
+    #[deserialization_error_test(
+        fixture = "../tests/fixtures/malformed/info_id.xml",
+        expect = "ID mismatch: expected NFe31231012345678000195650010000123451123456783, \
+                  found NFe31231012345678000195650010000123461123456780"
+    )]
+    fn deserialize_info_id_mismatch() -> Info;
+
+    pub fn setup_identification() -> Identification {
         Identification {
             location: Location {
                 state: State::MinasGerais,
@@ -1327,9 +2820,7 @@ pub mod tests {
             model: Model::NFCe,
             series: 1,
             number: 12345,
-            emission_date: chrono::Local
-                .with_ymd_and_hms(2023, 10, 5, 14, 30, 0)
-                .unwrap(),
+            emission_date: setup_local_date(14, 30),
             date: None,
             r#type: Operation::Outgoing,
             destination: DestinationTarget::Internal,
@@ -1340,12 +2831,82 @@ pub mod tests {
             finality: Finality::Normal,
             consumer: true,
             presence: Some(Presence::InplaceIndoor),
-            intermediator: None,
+            intermediator_indicator: None,
+            emission_version: "0.1.0".to_string(),
+            contingency: None,
         }
     }
 
-    #[serialization_test(fixture = "../tests/fixtures/address.xml")]
-    fn setup_address() -> Address {
+    /// The ide does not carry the name of the city, filled by `Info` from the
+    /// address of the issuer
+    #[serialization_test(
+        fixture = "../tests/fixtures/identification.xml",
+        roundtrip,
+        indent = "indent_xml"
+    )]
+    fn setup_standalone_identification() -> Identification {
+        let mut identification = setup_identification();
+        identification.location.city.name = String::new();
+        identification
+    }
+
+    #[serialization_test(
+        fixture = "../tests/fixtures/identification_contingency.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_contingency_identification() -> Identification {
+        let mut identification = setup_standalone_identification();
+        identification.emission_type = EmissionType::Offline;
+        identification.contingency = Some(Contingency::new(
+            setup_local_date(14, 0),
+            "Falha de comunicacao com a SEFAZ",
+        ));
+        identification
+    }
+
+    #[test]
+    fn test_identification_dates_in_state_offset() {
+        let emission_date = chrono::DateTime::parse_from_rfc3339("2023-10-05T17:30:00.123Z")
+            .unwrap()
+            .with_timezone(&chrono::Local);
+        let mut identification = Identification {
+            emission_date,
+            date: Some(emission_date),
+            ..setup_identification()
+        };
+        let xml = serialize(&identification).unwrap();
+        assert!(xml.contains("<dhEmi>2023-10-05T14:30:00-03:00</dhEmi>"));
+        assert!(xml.contains("<dhSaiEnt>2023-10-05T14:30:00-03:00</dhSaiEnt>"));
+
+        identification.location = Location {
+            state: State::Amazonas,
+            city: City {
+                code: 1302603,
+                name: "Manaus".to_string(),
+            },
+        };
+        let xml = serialize(&identification).unwrap();
+        assert!(xml.contains("<dhEmi>2023-10-05T13:30:00-04:00</dhEmi>"));
+    }
+
+    #[test]
+    fn test_identification_series() {
+        let xml = include_str!("../tests/fixtures/identification.xml");
+        let identification: Identification =
+            deserialize(&xml.replace("<serie>1</serie>", "<serie>999</serie>")).unwrap();
+        assert_eq!(identification.series, 999);
+        assert!(
+            deserialize::<Identification>(&xml.replace("<serie>1</serie>", "<serie>1000</serie>"))
+                .is_err()
+        );
+    }
+
+    #[serialization_test(
+        fixture = "../tests/fixtures/address.xml",
+        roundtrip,
+        indent = "indent_xml"
+    )]
+    pub fn setup_address() -> Address {
         Address {
             line_1: "Rua Exemplo".to_string(),
             line_2: Some("Loja 1".to_string()),
@@ -1361,7 +2922,11 @@ pub mod tests {
         }
     }
 
-    #[serialization_test(fixture = "../tests/fixtures/issuer.xml")]
+    #[serialization_test(
+        fixture = "../tests/fixtures/issuer.xml",
+        roundtrip,
+        indent = "indent_xml"
+    )]
     pub fn setup_issuer() -> Issuer {
         Issuer {
             document: PersonDocument::CNPJ(CNPJ("12345678000195".to_string())),
@@ -1369,32 +2934,403 @@ pub mod tests {
             trade_name: Some("Empresa Exemplo".to_string()),
             address: TaxableAddress {
                 address: setup_address(),
-                ie: IE("123456789".to_string()),
+                ie: IE("0623079040081".to_string()),
             },
+            ie_st: None,
+            im: None,
+            cnae: None,
+            tax_regime: TaxRegime::Simples,
+        }
+    }
+
+    /// Substitute taxpayer in São Paulo, with its own IE there
+    #[serialization_test(
+        fixture = "../tests/fixtures/issuer_services.xml",
+        roundtrip,
+        indent = "indent_xml"
+    )]
+    fn setup_issuer_services() -> Issuer {
+        Issuer {
+            ie_st: Some(IE("110042490114".to_string())),
+            im: Some("12345678".to_string()),
+            cnae: Some("4751201".to_string()),
+            tax_regime: TaxRegime::Normal,
+            ..setup_issuer()
+        }
+    }
+
+    #[test]
+    fn test_issuer_invalid_ie() {
+        let xml = include_str!("../tests/fixtures/issuer.xml")
+            .replace("<IE>0623079040081</IE>", "<IE>0623079040082</IE>");
+        let error = deserialize::<Issuer>(&xml).expect_err("Invalid IE accepted");
+        assert!(
+            error
+                .to_string()
+                .contains("Invalid IE verifier digits for MG")
+        );
+    }
+
+    #[test]
+    fn test_issuer_ie_placement() {
+        let xml = include_str!("../tests/fixtures/issuer.xml");
+        // Written in the address by older versions of the library
+        let legacy = xml
+            .replace("    <IE>0623079040081</IE>\n", "")
+            .replace("</fone>", "</fone><IE>0623079040081</IE>");
+        assert_eq!(deserialize::<Issuer>(&legacy).unwrap(), setup_issuer());
+
+        let missing = xml.replace("<IE>0623079040081</IE>", "");
+        let error = deserialize::<Issuer>(&missing).expect_err("Missing IE accepted");
+        assert!(error.to_string().contains("IE"));
+    }
+
+    #[serialization_test(fixture = "../tests/fixtures/recipient.xml", indent = "indent_xml")]
+    pub fn setup_recipient() -> Recipient {
+        Recipient {
+            document: RecipientDocument::CPF(CPF("12345678909".to_string())),
+            name: Some("Cliente Exemplo".to_string()),
+            address: Some(setup_address()),
+            ie_indicator: IEIndicator::NonContributor,
+            ie: None,
+            suframa: None,
+            im: None,
+            email: Some("cliente@exemplo.com.br".to_string()),
         }
     }
 
-    #[serialization_test(fixture = "../tests/fixtures/authorized.xml")]
-    fn setup_authorized() -> Authorized {
+    #[serialization_test(fixture = "../tests/fixtures/authorized.xml", indent = "indent_xml")]
+    pub fn setup_authorized() -> Authorized {
         Authorized {
             documents: vec![
                 PersonDocument::CNPJ(CNPJ("12345678000195".to_string())),
-                PersonDocument::CPF(CPF("12345678901".to_string())),
+                PersonDocument::CPF(CPF("12345678909".to_string())),
             ],
         }
     }
 
-    #[serialization_test(fixture = "../tests/fixtures/nfe.xml")]
-    fn setup_nfe() -> NFe {
-        NFe::new(setup_info())
+    #[cfg(feature = "sign")]
+    #[serialization_test(fixture = "../tests/fixtures/nfe.xml", indent = "indent_xml")]
+    pub fn setup_nfe() -> NFe {
+        let info = setup_info_builder().build().expect("Failed to build Info");
+        NFe::with_certificate(info, &setup_certificate()).expect("Failed to sign NFe")
+    }
+
+    /// Document signed by `setup_nfe` with the `sign` feature
+    #[cfg(not(feature = "sign"))]
+    pub fn setup_nfe() -> NFe {
+        deserialize(include_str!("../tests/fixtures/nfe.xml")).expect("Failed to parse NFe")
+    }
+
+    #[serialization_test(fixture = "../tests/fixtures/nfe_proc.xml", indent = "indent_xml")]
+    pub fn setup_nfe_proc() -> NFeProc {
+        NFeProc::new(
+            setup_nfe(),
+            Protocol {
+                version: "4.00".to_string(),
+                info: ProtocolInfo {
+                    id: Some("ID131230000000001".to_string()),
+                    environment: Environment::Production,
+                    application_version: "MG_NFE_PL009_V4".to_string(),
+                    access_key: "31231012345678000195650010000123451123456783".to_string(),
+                    received_at: chrono::DateTime::parse_from_rfc3339("2023-10-05T14:30:05-03:00")
+                        .unwrap(),
+                    number: Some("131230000000001".to_string()),
+                    digest: Some("oUXFqAZS2BejMb22mU0r/DBzolo=".to_string()),
+                    status: 100,
+                    reason: "Autorizado o uso da NF-e".to_string(),
+                },
+            },
+        )
+    }
+
+    #[serialization_test(
+        fixture = "../tests/fixtures/recipient_consumer.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_consumer_recipient() -> Recipient {
+        Recipient::consumer(PersonDocument::CPF(CPF("12345678909".to_string())), None)
+    }
+
+    #[test]
+    fn test_consumer_recipient() {
+        let info = setup_info_builder()
+            .set_consumer_cpf("123.456.789-09")
+            .unwrap()
+            .build()
+            .expect("Failed to build Info");
+        assert_eq!(info.recipient, Some(setup_consumer_recipient()));
+        assert!(matches!(
+            setup_info_builder().set_consumer_cpf("123.456.789-00"),
+            Err(DocumentError::VerifierDigits)
+        ));
+
+        // The recipient of an NFC-e is forced to a non contributor
+        let info = setup_info_builder()
+            .set_recipient(Recipient {
+                ie_indicator: IEIndicator::Contributor,
+                ie: Some(IE("0623079040081".to_string())),
+                ..setup_recipient()
+            })
+            .build()
+            .expect("Failed to build Info");
+        let recipient = info.recipient.unwrap();
+        assert_eq!(recipient.ie_indicator, IEIndicator::NonContributor);
+        assert_eq!(recipient.ie, None);
+    }
+
+    #[test]
+    fn test_homologation_texts() {
+        let mut identification = setup_identification();
+        identification.environment = Environment::Homologation;
+        let info = InfoBuilder::new(&setup_config(), identification, setup_payments())
+            .set_recipient(setup_recipient())
+            .add_detail(setup_detail())
+            .add_detail(setup_detail())
+            .build()
+            .expect("Failed to build Info");
+        assert_eq!(
+            info.recipient.unwrap().name.as_deref(),
+            Some(HOMOLOGATION_RECIPIENT_NAME)
+        );
+        assert_eq!(
+            info.details[0].item.description,
+            HOMOLOGATION_ITEM_DESCRIPTION
+        );
+        assert_eq!(info.details[1].item.description, setup_item().description);
+
+        // Production documents keep their texts
+        let info = setup_info_builder()
+            .set_recipient(setup_recipient())
+            .build()
+            .expect("Failed to build Info");
+        assert_eq!(info.recipient, Some(setup_recipient()));
+    }
+
+    #[cfg(feature = "sign")]
+    #[test]
+    fn test_sign_environment() {
+        let info = setup_info_builder().build().expect("Failed to build Info");
+        assert_eq!(
+            NFe::with_config(info, &setup_config()),
+            Err(SignError::Environment(
+                Environment::Production,
+                Environment::Homologation
+            ))
+        );
+
+        let info = setup_info_builder().build().expect("Failed to build Info");
+        let config = setup_config().with_environment(Environment::Production);
+        assert_eq!(NFe::with_config(info, &config), Ok(setup_nfe()));
+    }
+
+    #[test]
+    fn test_into_proc() {
+        let (nfe, mut protocol) = setup_nfe_proc().split();
+        assert_eq!(nfe, setup_nfe());
+        let digest = nfe.signature.info.reference.digest_value.clone();
+        protocol.info.digest = Some(digest.clone());
+
+        let nfe_proc = setup_nfe().into_proc(protocol.clone()).unwrap();
+        assert_eq!(nfe_proc.split(), (setup_nfe(), protocol.clone()));
+
+        let mut other = protocol.clone();
+        other.info.access_key = "31231012345678000195650010000123461123456780".to_string();
+        assert_eq!(
+            setup_nfe().into_proc(other),
+            Err(ProtocolError::AccessKey(
+                "31231012345678000195650010000123451123456783".to_string(),
+                "31231012345678000195650010000123461123456780".to_string()
+            ))
+        );
+
+        let mut other = protocol.clone();
+        other.info.digest = Some("oUXFqAZS2BejMb22mU0r/DBzolo=".to_string());
+        assert_eq!(
+            setup_nfe().into_proc(other),
+            Err(ProtocolError::Digest(
+                digest,
+                "oUXFqAZS2BejMb22mU0r/DBzolo=".to_string()
+            ))
+        );
+
+        let mut other = protocol;
+        other.info.digest = None;
+        other.info.status = 204;
+        other.info.reason = "Rejeicao: Duplicidade de NF-e".to_string();
+        assert_eq!(
+            setup_nfe().into_proc(other),
+            Err(ProtocolError::Status(
+                204,
+                "Rejeicao: Duplicidade de NF-e".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_external() {
+        let xml = include_str!("../tests/fixtures/external/nfce_proc.xml");
+        assert!(quick_xml::de::from_str::<NFeProc>(xml).is_err());
+
+        let proc = NFeProc::parse_external(xml).expect("Failed to read the document");
+        let info = &proc.nfe.info;
+        assert_eq!(info.id(), "NFe31231012345678000195650010000123451123456783");
+        assert_eq!(info.identification.location.city.name, "BELO HORIZONTE");
+        assert_eq!(info.issuer.address.ie, IE("0623079040081".to_string()));
+        assert_eq!(info.issuer.address.address.telephone, "3132123456");
+        assert_eq!(info.details.len(), 2);
+        assert_eq!(
+            info.details[0].item.tribute_unit_value,
+            Money::from(dec!(18.99))
+        );
+        assert_eq!(info.total.icms.total, Money::from(dec!(113.94)));
+        assert!(proc.nfe.supplement.is_some());
+        assert!(proc.protocol.info.is_authorized());
+
+        let nfe = xml
+            .split_once("<NFe ")
+            .unwrap()
+            .1
+            .split_once("</NFe>")
+            .unwrap()
+            .0;
+        let nfe = NFe::parse_external(&format!("<NFe {}</NFe>", nfe)).unwrap();
+        assert_eq!(nfe, proc.nfe);
+        assert!(NFe::parse_external("<NFe/>").is_err());
+    }
+
+    #[test]
+    fn test_namespaces() {
+        let xml = quick_xml::se::to_string(&setup_nfe()).unwrap();
+        assert!(xml.starts_with(r#"<NFe xmlns="http://www.portalfiscal.inf.br/nfe">"#));
+        assert!(xml.contains(r#"<Signature xmlns="http://www.w3.org/2000/09/xmldsig#">"#));
+
+        // Elements qualified by prefixes instead of the default namespace
+        let prefixed = include_str!("../tests/fixtures/external/nfe_prefixed.xml");
+        assert!(prefixed.contains("<ds:Signature xmlns:ds="));
+        assert_eq!(
+            quick_xml::de::from_str::<NFe>(prefixed).unwrap(),
+            setup_nfe()
+        );
+        assert_eq!(NFe::parse_external(prefixed).unwrap(), setup_nfe());
+    }
+
+    #[cfg(feature = "sign")]
+    #[test]
+    fn test_verify_signature() {
+        let nfe = setup_nfe();
+        assert_eq!(nfe.verify_signature(), Ok(()));
+        assert_eq!(
+            nfe.verify_chain(&[]),
+            Err(VerifyError::UntrustedCertificate)
+        );
+        assert_eq!(
+            nfe.verify_chain(&[setup_certificate().der().to_vec()]),
+            Ok(())
+        );
+
+        let mut changed = setup_nfe();
+        changed.info.identification.operation_nature = "Devolucao".to_string();
+        assert_eq!(changed.verify_signature(), Err(VerifyError::DigestMismatch));
+    }
+
+    #[test]
+    fn test_config_qr_code() {
+        let config = setup_config();
+        assert_eq!(
+            setup_nfe().with_config_qr_code(&config),
+            Err(QRCodeError::MissingCSC(Environment::Production))
+        );
+
+        let csc = CSC::new(1, "0123456789ABCDEF".to_string());
+        let config = config
+            .with_csc(Environment::Production, csc.clone())
+            .unwrap();
+        assert_eq!(
+            setup_nfe().with_config_qr_code(&config),
+            setup_nfe().with_qr_code(&csc)
+        );
+    }
+
+    #[cfg(feature = "sign")]
+    #[test]
+    fn test_verify_xml() {
+        // The fixtures are indented, the documents were signed without indentation
+        let indented = include_str!("../tests/fixtures/nfe.xml");
+        let xml = canonicalize(indented).unwrap();
+        assert_eq!(NFe::verify_xml(&xml), Ok(()));
+        assert_eq!(
+            NFe::verify_xml(&canonicalize(include_str!("../tests/fixtures/nfe_proc.xml")).unwrap()),
+            Ok(())
+        );
+        assert_eq!(NFe::verify_xml(indented), Err(VerifyError::DigestMismatch));
+        assert_eq!(
+            NFe::verify_xml(&xml.replace("Venda de mercadoria", "Devolucao")),
+            Err(VerifyError::DigestMismatch)
+        );
+        assert_eq!(
+            NFe::verify_xml("<NFe/>"),
+            Err(VerifyError::MissingElement("infNFe".to_string()))
+        );
+    }
+
+    #[cfg(feature = "sign")]
+    #[test]
+    fn test_verify_prefixed_xml() {
+        use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+
+        // Signed by another system with the prefix nfe, the Signature keeping
+        // its default namespace
+        let xml = canonicalize(include_str!("../tests/fixtures/nfe.xml")).unwrap();
+        let (document, _) = xml.split_at(xml.find("<Signature").unwrap());
+        let document = regex_lite::Regex::new(r"<(/?)(\w)")
+            .unwrap()
+            .replace_all(document, "<${1}nfe:$2")
+            .replace(" xmlns=", " xmlns:nfe=");
+        let info = &document[document.find("<nfe:infNFe").unwrap()..];
+        let element = info.replacen(
+            "<nfe:infNFe",
+            &format!(r#"<nfe:infNFe xmlns:nfe="{}""#, NFE_NAMESPACE),
+            1,
+        );
+        let certificate = setup_certificate();
+        let mut signature = sign(&element, &setup_nfe().info.id(), &certificate).unwrap();
+        // Its SignedInfo inherits the declaration of the prefix nfe
+        let signed_info = to_namespaced_xml("SignedInfo", XMLDSIG_NAMESPACE, &signature.info)
+            .unwrap()
+            .replacen(
+                "<SignedInfo",
+                &format!(r#"<SignedInfo xmlns:nfe="{}""#, NFE_NAMESPACE),
+                1,
+            );
+        let value = certificate
+            .sign_sha1(
+                crate::xml::canonicalize_xml(&signed_info)
+                    .unwrap()
+                    .as_bytes(),
+            )
+            .unwrap();
+        signature.value = BASE64.encode(value);
+        let signed = format!("{}{}</nfe:NFe>", document, serialize(&signature).unwrap());
+
+        assert!(signed.starts_with("<nfe:NFe xmlns:nfe="));
+        assert_eq!(NFe::verify_xml(&signed), Ok(()));
+        assert_eq!(
+            NFe::verify_xml(&signed.replace("Venda de mercadoria", "Devolucao")),
+            Err(VerifyError::DigestMismatch)
+        );
+        assert_eq!(
+            NFe::verify_xml(&signed.replace("nfe:infNFe", "infNFe")),
+            Err(VerifyError::MissingElement("infNFe".to_string()))
+        );
     }
 
-    #[serialization_test(fixture = "../tests/fixtures/total.xml")]
+    #[serialization_test(fixture = "../tests/fixtures/total.xml", indent = "indent_xml")]
     fn setup_total() -> Total {
         Total::calculate(&setup_info_builder())
     }
 
-    #[serialization_test(fixture = "../tests/fixtures/transport.xml")]
+    #[serialization_test(fixture = "../tests/fixtures/transport.xml", indent = "indent_xml")]
     fn setup_transport() -> Transport {
         Transport::default()
     }
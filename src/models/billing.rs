@@ -0,0 +1,104 @@
+//! Billing group of the document (cobr)
+
+use crate::decimal::Money;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// Billing information based on the XML structure of the NFe
+///
+/// invoice: Invoice of the operation (fat) - Optional
+/// installments: Installments of the invoice, up to 120 (dup)
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Clone)]
+#[serde(rename = "cobr")]
+pub struct Billing {
+    #[serde(rename = "fat", skip_serializing_if = "Option::is_none")]
+    pub invoice: Option<Invoice>,
+    #[serde(rename = "dup", default, skip_serializing_if = "Vec::is_empty")]
+    pub installments: Vec<Installment>,
+}
+
+impl Billing {
+    /// Sum of the values of the installments
+    pub fn installments_total(&self) -> Money {
+        self.installments
+            .iter()
+            .map(|installment| installment.value)
+            .sum()
+    }
+}
+
+/// Invoice of the operation (fat)
+///
+/// number: Number of the invoice (nFat) - Optional
+/// original_value: Original value of the invoice (vOrig) - Optional
+/// discount: Discount granted (vDesc) - Optional
+/// net_value: Net value of the invoice (vLiq) - Optional
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Clone)]
+#[serde(rename = "fat")]
+pub struct Invoice {
+    #[serde(rename = "nFat", skip_serializing_if = "Option::is_none")]
+    pub number: Option<String>,
+    #[serde(rename = "vOrig", skip_serializing_if = "Option::is_none")]
+    pub original_value: Option<Money>,
+    #[serde(rename = "vDesc", skip_serializing_if = "Option::is_none")]
+    pub discount: Option<Money>,
+    #[serde(rename = "vLiq", skip_serializing_if = "Option::is_none")]
+    pub net_value: Option<Money>,
+}
+
+/// Installment of the invoice (dup)
+///
+/// number: Number of the installment, in sequence from "001" (nDup) - Optional
+/// due_date: Due date of the installment (dVenc) - Optional
+/// value: Value of the installment (vDup)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename = "dup")]
+pub struct Installment {
+    #[serde(rename = "nDup", skip_serializing_if = "Option::is_none")]
+    pub number: Option<String>,
+    #[serde(rename = "dVenc", skip_serializing_if = "Option::is_none")]
+    pub due_date: Option<NaiveDate>,
+    #[serde(rename = "vDup")]
+    pub value: Money,
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::xml::{indent_xml, normalize_xml as canonicalize};
+    use nf_e_macros::serialization_test;
+    use quick_xml::{de::from_str as deserialize, se::to_string as serialize};
+    use rust_decimal_macros::dec;
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/billing/billing.xml",
+        indent = "indent_xml"
+    )]
+    pub fn setup_billing() -> Billing {
+        Billing {
+            invoice: Some(Invoice {
+                number: Some("12345".to_string()),
+                original_value: Some(Money::from(dec!(120.0))),
+                discount: Some(Money::from(dec!(6.06))),
+                net_value: Some(Money::from(dec!(113.94))),
+            }),
+            installments: vec![
+                Installment {
+                    number: Some("001".to_string()),
+                    due_date: NaiveDate::from_ymd_opt(2023, 11, 5),
+                    value: Money::from(dec!(56.97)),
+                },
+                Installment {
+                    number: Some("002".to_string()),
+                    due_date: NaiveDate::from_ymd_opt(2023, 12, 5),
+                    value: Money::from(dec!(56.97)),
+                },
+            ],
+        }
+    }
+
+    #[serialization_test(expected = "<cobr/>")]
+    fn setup_empty_billing() -> Billing {
+        Billing::default()
+    }
+}
@@ -0,0 +1,178 @@
+//! COFINS groups, one per kind of taxation
+
+use crate::decimal::{Money, Quantity};
+use crate::enums::ContributionCST;
+use serde::{Deserialize, Serialize};
+
+/// COFINS structure for CST 01 and 02 (taxed by percentage)
+///
+/// cst: Tax situation code (CST)
+/// base: Calculation base (vBC)
+/// rate: COFINS rate (pCOFINS)
+/// value: COFINS value (vCOFINS)
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct COFINSAliq {
+    #[serde(rename = "CST")]
+    pub cst: ContributionCST,
+    #[serde(rename = "vBC")]
+    pub base: Money,
+    #[serde(rename = "pCOFINS")]
+    pub rate: Money,
+    #[serde(rename = "vCOFINS")]
+    pub value: Money,
+}
+
+/// COFINS structure for CST 03 (taxed by quantity)
+///
+/// cst: Tax situation code (CST)
+/// quantity: Quantity sold, base of the unit rate (qBCProd)
+/// unit_rate: Rate per unit (vAliqProd)
+/// value: COFINS value (vCOFINS)
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct COFINSQtde {
+    #[serde(rename = "CST")]
+    pub cst: ContributionCST,
+    #[serde(rename = "qBCProd")]
+    pub quantity: Quantity,
+    #[serde(rename = "vAliqProd")]
+    pub unit_rate: Quantity,
+    #[serde(rename = "vCOFINS")]
+    pub value: Money,
+}
+
+/// COFINS structure for CST 04 to 09 (not taxed)
+///
+/// cst: Tax situation code (CST)
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct COFINSNT {
+    #[serde(rename = "CST")]
+    pub cst: ContributionCST,
+}
+
+/// COFINS structure for CST 49 to 99 (others), taxed either by percentage or by quantity
+///
+/// cst: Tax situation code (CST)
+/// base: Calculation base (vBC) - Optional
+/// rate: COFINS rate (pCOFINS) - Optional
+/// quantity: Quantity sold, base of the unit rate (qBCProd) - Optional
+/// unit_rate: Rate per unit (vAliqProd) - Optional
+/// value: COFINS value (vCOFINS)
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct COFINSOutr {
+    #[serde(rename = "CST")]
+    pub cst: ContributionCST,
+    #[serde(rename = "vBC", skip_serializing_if = "Option::is_none")]
+    pub base: Option<Money>,
+    #[serde(rename = "pCOFINS", skip_serializing_if = "Option::is_none")]
+    pub rate: Option<Money>,
+    #[serde(rename = "qBCProd", skip_serializing_if = "Option::is_none")]
+    pub quantity: Option<Quantity>,
+    #[serde(rename = "vAliqProd", skip_serializing_if = "Option::is_none")]
+    pub unit_rate: Option<Quantity>,
+    #[serde(rename = "vCOFINS")]
+    pub value: Money,
+}
+
+/// COFINS ST structure, taxed either by percentage or by quantity
+///
+/// base: Calculation base (vBC) - Optional
+/// rate: COFINS rate (pCOFINS) - Optional
+/// quantity: Quantity sold, base of the unit rate (qBCProd) - Optional
+/// unit_rate: Rate per unit (vAliqProd) - Optional
+/// value: COFINS value (vCOFINS)
+/// included: Whether the value is included in the total of the NFe (indSomaCOFINSST) - Optional
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct COFINSST {
+    #[serde(rename = "vBC", skip_serializing_if = "Option::is_none")]
+    pub base: Option<Money>,
+    #[serde(rename = "pCOFINS", skip_serializing_if = "Option::is_none")]
+    pub rate: Option<Money>,
+    #[serde(rename = "qBCProd", skip_serializing_if = "Option::is_none")]
+    pub quantity: Option<Quantity>,
+    #[serde(rename = "vAliqProd", skip_serializing_if = "Option::is_none")]
+    pub unit_rate: Option<Quantity>,
+    #[serde(rename = "vCOFINS")]
+    pub value: Money,
+    #[serde(
+        rename = "indSomaCOFINSST",
+        default,
+        with = "crate::utils::optional_flag",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub included: Option<bool>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xml::{indent_xml, normalize_xml as canonicalize};
+    use nf_e_macros::serialization_test;
+    use quick_xml::{de::from_str as deserialize, se::to_string as serialize};
+    use rust_decimal_macros::dec;
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/cofins/cofins_aliq.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_cofins_aliq() -> COFINSAliq {
+        COFINSAliq {
+            cst: ContributionCST::TaxableBasicRate,
+            base: Money::from(dec!(100.0)),
+            rate: Money::from(dec!(7.60)),
+            value: Money::from(dec!(7.60)),
+        }
+    }
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/cofins/cofins_qtde.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_cofins_qtde() -> COFINSQtde {
+        COFINSQtde {
+            cst: ContributionCST::TaxableUnitRate,
+            quantity: Quantity::from(dec!(10.0)),
+            unit_rate: Quantity::from(dec!(0.5)),
+            value: Money::from(dec!(5.0)),
+        }
+    }
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/cofins/cofins_nt.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_cofins_nt() -> COFINSNT {
+        COFINSNT {
+            cst: ContributionCST::Exempt,
+        }
+    }
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/cofins/cofins_outr.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_cofins_outr() -> COFINSOutr {
+        COFINSOutr {
+            cst: ContributionCST::Others,
+            base: Some(Money::from(dec!(100.0))),
+            rate: Some(Money::from(dec!(3.00))),
+            quantity: None,
+            unit_rate: None,
+            value: Money::from(dec!(3.00)),
+        }
+    }
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/cofins/cofins_st.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_cofins_st() -> COFINSST {
+        COFINSST {
+            base: None,
+            rate: None,
+            quantity: Some(Quantity::from(dec!(10.0))),
+            unit_rate: Some(Quantity::from(dec!(0.5))),
+            value: Money::from(dec!(5.0)),
+            included: Some(true),
+        }
+    }
+}
@@ -0,0 +1,52 @@
+//! Taxes of the goods returned (impostoDevol)
+
+use crate::decimal::Money;
+use serde::{Deserialize, Serialize};
+
+/// Taxes of the goods returned by the item, informed on the devolution notes
+/// (finNFe = 4) that return the goods of an original NF-e
+///
+/// rate: Percentage of the goods returned (pDevol)
+/// ipi: IPI of the goods returned (IPI)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename = "impostoDevol")]
+pub struct Devolution {
+    #[serde(rename = "pDevol")]
+    pub rate: Money,
+    #[serde(rename = "IPI")]
+    pub ipi: ReturnedIPI,
+}
+
+/// IPI of the goods returned, summed in the total of the document (vIPIDevol)
+///
+/// value: Value of the IPI returned (vIPIDevol)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct ReturnedIPI {
+    #[serde(rename = "vIPIDevol")]
+    pub value: Money,
+}
+
+impl Devolution {
+    pub fn new(rate: Money, returned_ipi: Money) -> Self {
+        Devolution {
+            rate,
+            ipi: ReturnedIPI {
+                value: returned_ipi,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::xml::{indent_xml, normalize_xml as canonicalize};
+    use nf_e_macros::serialization_test;
+    use quick_xml::{de::from_str as deserialize, se::to_string as serialize};
+    use rust_decimal_macros::dec;
+
+    #[serialization_test(fixture = "../../tests/fixtures/devolution.xml", indent = "indent_xml")]
+    pub fn setup_devolution() -> Devolution {
+        Devolution::new(Money::from(dec!(100.00)), Money::from(dec!(4.50)))
+    }
+}
@@ -0,0 +1,165 @@
+//! Import declarations (DI) and export details (detExport) of the items
+
+use crate::decimal::{Money, Quantity};
+use crate::enums::{CNPJ, ImportIntermediation, InternationalTransport};
+use crate::states::State;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// Import declaration of the item, up to 100 per item (DI)
+///
+/// number: Number of the DI, DSI, DIRE or DUIMP (nDI)
+/// date: Date of registration of the declaration (dDI)
+/// clearance_location: Location of the customs clearance (xLocDesemb)
+/// clearance_state: State of the customs clearance (UFDesemb)
+/// clearance_date: Date of the customs clearance (dDesemb)
+/// transport: International transport route (tpViaTransp)
+/// afrmm: Additional freight for the renewal of the merchant navy (vAFRMM) - Optional
+/// intermediation: Form of the import (tpIntermedio)
+/// acquirer: CNPJ of the acquirer or orderer, when intermediated (CNPJ) - Optional
+/// acquirer_state: State of the acquirer or orderer, when intermediated (UFTerceiro) - Optional
+/// exporter: Code of the exporter (cExportador)
+/// additions: Additions of the declaration (adi)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename = "DI")]
+pub struct ImportDeclaration {
+    #[serde(rename = "nDI")]
+    pub number: String,
+    #[serde(rename = "dDI")]
+    pub date: NaiveDate,
+    #[serde(rename = "xLocDesemb")]
+    pub clearance_location: String,
+    #[serde(rename = "UFDesemb", with = "crate::states::acronym")]
+    pub clearance_state: State,
+    #[serde(rename = "dDesemb")]
+    pub clearance_date: NaiveDate,
+    #[serde(rename = "tpViaTransp")]
+    pub transport: InternationalTransport,
+    #[serde(rename = "vAFRMM", skip_serializing_if = "Option::is_none")]
+    pub afrmm: Option<Money>,
+    #[serde(rename = "tpIntermedio")]
+    pub intermediation: ImportIntermediation,
+    #[serde(rename = "CNPJ", skip_serializing_if = "Option::is_none")]
+    pub acquirer: Option<CNPJ>,
+    #[serde(
+        rename = "UFTerceiro",
+        default,
+        with = "crate::states::optional_acronym",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub acquirer_state: Option<State>,
+    #[serde(rename = "cExportador")]
+    pub exporter: String,
+    #[serde(rename = "adi")]
+    pub additions: Vec<Addition>,
+}
+
+/// Addition of the import declaration, at least one per declaration (adi)
+///
+/// number: Number of the addition (nAdicao) - Optional
+/// sequence: Sequence of the item in the addition (nSeqAdic)
+/// manufacturer: Code of the foreign manufacturer (cFabricante)
+/// discount: Discount of the item in the declaration (vDescDI) - Optional
+/// drawback: Number of the drawback concession act (nDraw) - Optional
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename = "adi")]
+pub struct Addition {
+    #[serde(rename = "nAdicao", skip_serializing_if = "Option::is_none")]
+    pub number: Option<u16>,
+    #[serde(rename = "nSeqAdic")]
+    pub sequence: u32,
+    #[serde(rename = "cFabricante")]
+    pub manufacturer: String,
+    #[serde(rename = "vDescDI", skip_serializing_if = "Option::is_none")]
+    pub discount: Option<Money>,
+    #[serde(rename = "nDraw", skip_serializing_if = "Option::is_none")]
+    pub drawback: Option<String>,
+}
+
+/// Export details of the item, up to 500 per item (detExport)
+///
+/// drawback: Number of the drawback concession act (nDraw) - Optional
+/// indirect: Indirect export information (exportInd) - Optional
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename = "detExport")]
+pub struct ExportDetail {
+    #[serde(rename = "nDraw", skip_serializing_if = "Option::is_none")]
+    pub drawback: Option<String>,
+    #[serde(rename = "exportInd", skip_serializing_if = "Option::is_none")]
+    pub indirect: Option<IndirectExport>,
+}
+
+/// Indirect export, through a trading company (exportInd)
+///
+/// registration: Number of the export registration (nRE)
+/// access_key: Access key of the NFe received for the export (chNFe)
+/// quantity: Quantity actually exported (qExport)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct IndirectExport {
+    #[serde(rename = "nRE")]
+    pub registration: String,
+    #[serde(rename = "chNFe")]
+    pub access_key: String,
+    #[serde(rename = "qExport")]
+    pub quantity: Quantity,
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::xml::{indent_xml, normalize_xml as canonicalize};
+    use nf_e_macros::serialization_test;
+    use quick_xml::{de::from_str as deserialize, se::to_string as serialize};
+    use rust_decimal_macros::dec;
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/foreign_trade/di.xml",
+        indent = "indent_xml"
+    )]
+    pub fn setup_import_declaration() -> ImportDeclaration {
+        ImportDeclaration {
+            number: "2312345678".to_string(),
+            date: NaiveDate::from_ymd_opt(2023, 9, 20).unwrap(),
+            clearance_location: "PORTO DE SANTOS".to_string(),
+            clearance_state: State::SaoPaulo,
+            clearance_date: NaiveDate::from_ymd_opt(2023, 9, 28).unwrap(),
+            transport: InternationalTransport::Maritime,
+            afrmm: Some(Money::from(dec!(25.00))),
+            intermediation: ImportIntermediation::OnBehalf,
+            acquirer: Some(CNPJ("12345678000195".to_string())),
+            acquirer_state: Some(State::MinasGerais),
+            exporter: "EXP001".to_string(),
+            additions: vec![
+                Addition {
+                    number: Some(1),
+                    sequence: 1,
+                    manufacturer: "FAB001".to_string(),
+                    discount: None,
+                    drawback: None,
+                },
+                Addition {
+                    number: Some(1),
+                    sequence: 2,
+                    manufacturer: "FAB002".to_string(),
+                    discount: Some(Money::from(dec!(10.00))),
+                    drawback: None,
+                },
+            ],
+        }
+    }
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/foreign_trade/det_export.xml",
+        indent = "indent_xml"
+    )]
+    pub fn setup_export_detail() -> ExportDetail {
+        ExportDetail {
+            drawback: Some("20230012345".to_string()),
+            indirect: Some(IndirectExport {
+                registration: "230123456789".to_string(),
+                access_key: "31231012345678000195550010000000011000000010".to_string(),
+                quantity: Quantity::from(dec!(3)),
+            }),
+        }
+    }
+}
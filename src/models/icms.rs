@@ -0,0 +1,1163 @@
+//! ICMS groups, one per CST for the normal regime and one per CSOSN for the
+//! companies in the Simples Nacional
+
+use crate::decimal::Money;
+use crate::enums::{BaseModality, CSOSN, CST, Origin, STBaseModality, UnburdenReason};
+use crate::states::State;
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// ICMS structure for CST 00 (fully taxed)
+///
+/// origin: Origin of the product (orig)
+/// cst: Tax situation code (CST)
+/// base_modality: Modality of the calculation base (modBC)
+/// base: Calculation base (vBC)
+/// rate: ICMS rate (pICMS)
+/// value: ICMS value (vICMS)
+/// fcp_rate: Rate of the FCP (pFCP) - Optional
+/// fcp_value: Value of the FCP (vFCP) - Optional
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ICMS00 {
+    #[serde(rename = "orig")]
+    pub origin: Origin,
+    #[serde(rename = "CST")]
+    pub cst: CST,
+    #[serde(rename = "modBC")]
+    pub base_modality: BaseModality,
+    #[serde(rename = "vBC")]
+    pub base: Money,
+    #[serde(rename = "pICMS")]
+    pub rate: Money,
+    #[serde(rename = "vICMS")]
+    pub value: Money,
+    #[serde(rename = "pFCP", skip_serializing_if = "Option::is_none")]
+    pub fcp_rate: Option<Money>,
+    #[serde(rename = "vFCP", skip_serializing_if = "Option::is_none")]
+    pub fcp_value: Option<Money>,
+}
+
+/// ICMS structure for CST 10 (taxed with ST)
+///
+/// origin: Origin of the product (orig)
+/// cst: Tax situation code (CST)
+/// base_modality: Modality of the calculation base (modBC)
+/// base: Calculation base (vBC)
+/// rate: ICMS rate (pICMS)
+/// value: ICMS value (vICMS)
+/// fcp_base: Calculation base of the FCP (vBCFCP) - Optional
+/// fcp_rate: Rate of the FCP (pFCP) - Optional
+/// fcp_value: Value of the FCP (vFCP) - Optional
+/// st_base_modality: Modality of the ST calculation base (modBCST)
+/// st_margin: Value added margin percentage of the ST (pMVAST) - Optional
+/// st_base_reduction: Reduction percentage of the ST calculation base (pRedBCST) - Optional
+/// st_base: ST calculation base (vBCST)
+/// st_rate: ICMS ST rate (pICMSST)
+/// st_value: ICMS ST value (vICMSST)
+/// st_fcp_base: Calculation base of the FCP retained by ST (vBCFCPST) - Optional
+/// st_fcp_rate: Rate of the FCP retained by ST (pFCPST) - Optional
+/// st_fcp_value: Value of the FCP retained by ST (vFCPST) - Optional
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ICMS10 {
+    #[serde(rename = "orig")]
+    pub origin: Origin,
+    #[serde(rename = "CST")]
+    pub cst: CST,
+    #[serde(rename = "modBC")]
+    pub base_modality: BaseModality,
+    #[serde(rename = "vBC")]
+    pub base: Money,
+    #[serde(rename = "pICMS")]
+    pub rate: Money,
+    #[serde(rename = "vICMS")]
+    pub value: Money,
+    #[serde(rename = "vBCFCP", skip_serializing_if = "Option::is_none")]
+    pub fcp_base: Option<Money>,
+    #[serde(rename = "pFCP", skip_serializing_if = "Option::is_none")]
+    pub fcp_rate: Option<Money>,
+    #[serde(rename = "vFCP", skip_serializing_if = "Option::is_none")]
+    pub fcp_value: Option<Money>,
+    #[serde(rename = "modBCST")]
+    pub st_base_modality: STBaseModality,
+    #[serde(rename = "pMVAST", skip_serializing_if = "Option::is_none")]
+    pub st_margin: Option<Money>,
+    #[serde(rename = "pRedBCST", skip_serializing_if = "Option::is_none")]
+    pub st_base_reduction: Option<Money>,
+    #[serde(rename = "vBCST")]
+    pub st_base: Money,
+    #[serde(rename = "pICMSST")]
+    pub st_rate: Money,
+    #[serde(rename = "vICMSST")]
+    pub st_value: Money,
+    #[serde(rename = "vBCFCPST", skip_serializing_if = "Option::is_none")]
+    pub st_fcp_base: Option<Money>,
+    #[serde(rename = "pFCPST", skip_serializing_if = "Option::is_none")]
+    pub st_fcp_rate: Option<Money>,
+    #[serde(rename = "vFCPST", skip_serializing_if = "Option::is_none")]
+    pub st_fcp_value: Option<Money>,
+}
+
+/// ICMS structure for CST 20 (reduced calculation base)
+///
+/// origin: Origin of the product (orig)
+/// cst: Tax situation code (CST)
+/// base_modality: Modality of the calculation base (modBC)
+/// base_reduction: Reduction percentage of the calculation base (pRedBC)
+/// base: Calculation base (vBC)
+/// rate: ICMS rate (pICMS)
+/// value: ICMS value (vICMS)
+/// fcp_base: Calculation base of the FCP (vBCFCP) - Optional
+/// fcp_rate: Rate of the FCP (pFCP) - Optional
+/// fcp_value: Value of the FCP (vFCP) - Optional
+/// unburdened: Value of the exempted ICMS (vICMSDeson) - Optional
+/// unburden_reason: Reason of the exemption of the ICMS (motDesICMS) - Optional
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ICMS20 {
+    #[serde(rename = "orig")]
+    pub origin: Origin,
+    #[serde(rename = "CST")]
+    pub cst: CST,
+    #[serde(rename = "modBC")]
+    pub base_modality: BaseModality,
+    #[serde(rename = "pRedBC")]
+    pub base_reduction: Money,
+    #[serde(rename = "vBC")]
+    pub base: Money,
+    #[serde(rename = "pICMS")]
+    pub rate: Money,
+    #[serde(rename = "vICMS")]
+    pub value: Money,
+    #[serde(rename = "vBCFCP", skip_serializing_if = "Option::is_none")]
+    pub fcp_base: Option<Money>,
+    #[serde(rename = "pFCP", skip_serializing_if = "Option::is_none")]
+    pub fcp_rate: Option<Money>,
+    #[serde(rename = "vFCP", skip_serializing_if = "Option::is_none")]
+    pub fcp_value: Option<Money>,
+    #[serde(rename = "vICMSDeson", skip_serializing_if = "Option::is_none")]
+    pub unburdened: Option<Money>,
+    #[serde(rename = "motDesICMS", skip_serializing_if = "Option::is_none")]
+    pub unburden_reason: Option<UnburdenReason>,
+}
+
+/// ICMS structure for CST 30 (exempt or not taxed, with ST)
+///
+/// origin: Origin of the product (orig)
+/// cst: Tax situation code (CST)
+/// st_base_modality: Modality of the ST calculation base (modBCST)
+/// st_margin: Value added margin percentage of the ST (pMVAST) - Optional
+/// st_base_reduction: Reduction percentage of the ST calculation base (pRedBCST) - Optional
+/// st_base: ST calculation base (vBCST)
+/// st_rate: ICMS ST rate (pICMSST)
+/// st_value: ICMS ST value (vICMSST)
+/// st_fcp_base: Calculation base of the FCP retained by ST (vBCFCPST) - Optional
+/// st_fcp_rate: Rate of the FCP retained by ST (pFCPST) - Optional
+/// st_fcp_value: Value of the FCP retained by ST (vFCPST) - Optional
+/// unburdened: Value of the exempted ICMS (vICMSDeson) - Optional
+/// unburden_reason: Reason of the exemption of the ICMS (motDesICMS) - Optional
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ICMS30 {
+    #[serde(rename = "orig")]
+    pub origin: Origin,
+    #[serde(rename = "CST")]
+    pub cst: CST,
+    #[serde(rename = "modBCST")]
+    pub st_base_modality: STBaseModality,
+    #[serde(rename = "pMVAST", skip_serializing_if = "Option::is_none")]
+    pub st_margin: Option<Money>,
+    #[serde(rename = "pRedBCST", skip_serializing_if = "Option::is_none")]
+    pub st_base_reduction: Option<Money>,
+    #[serde(rename = "vBCST")]
+    pub st_base: Money,
+    #[serde(rename = "pICMSST")]
+    pub st_rate: Money,
+    #[serde(rename = "vICMSST")]
+    pub st_value: Money,
+    #[serde(rename = "vBCFCPST", skip_serializing_if = "Option::is_none")]
+    pub st_fcp_base: Option<Money>,
+    #[serde(rename = "pFCPST", skip_serializing_if = "Option::is_none")]
+    pub st_fcp_rate: Option<Money>,
+    #[serde(rename = "vFCPST", skip_serializing_if = "Option::is_none")]
+    pub st_fcp_value: Option<Money>,
+    #[serde(rename = "vICMSDeson", skip_serializing_if = "Option::is_none")]
+    pub unburdened: Option<Money>,
+    #[serde(rename = "motDesICMS", skip_serializing_if = "Option::is_none")]
+    pub unburden_reason: Option<UnburdenReason>,
+}
+
+/// ICMS structure for CST 40, 41 and 50 (exempt, not taxed or suspended)
+///
+/// origin: Origin of the product (orig)
+/// cst: Tax situation code (CST)
+/// unburdened: Value of the exempted ICMS (vICMSDeson) - Optional
+/// unburden_reason: Reason of the exemption of the ICMS (motDesICMS) - Optional
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ICMS40 {
+    #[serde(rename = "orig")]
+    pub origin: Origin,
+    #[serde(rename = "CST")]
+    pub cst: CST,
+    #[serde(rename = "vICMSDeson", skip_serializing_if = "Option::is_none")]
+    pub unburdened: Option<Money>,
+    #[serde(rename = "motDesICMS", skip_serializing_if = "Option::is_none")]
+    pub unburden_reason: Option<UnburdenReason>,
+}
+
+/// ICMS structure for CST 51 (deferred)
+///
+/// origin: Origin of the product (orig)
+/// cst: Tax situation code (CST)
+/// base_modality: Modality of the calculation base (modBC) - Optional
+/// base_reduction: Reduction percentage of the calculation base (pRedBC) - Optional
+/// base: Calculation base (vBC) - Optional
+/// rate: ICMS rate (pICMS) - Optional
+/// operation_value: ICMS value of the operation (vICMSOp) - Optional
+/// deferral_rate: Deferral percentage (pDif) - Optional
+/// deferred_value: Deferred ICMS value (vICMSDif) - Optional
+/// value: ICMS value (vICMS) - Optional
+/// fcp_base: Calculation base of the FCP (vBCFCP) - Optional
+/// fcp_rate: Rate of the FCP (pFCP) - Optional
+/// fcp_value: Value of the FCP (vFCP) - Optional
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ICMS51 {
+    #[serde(rename = "orig")]
+    pub origin: Origin,
+    #[serde(rename = "CST")]
+    pub cst: CST,
+    #[serde(rename = "modBC", skip_serializing_if = "Option::is_none")]
+    pub base_modality: Option<BaseModality>,
+    #[serde(rename = "pRedBC", skip_serializing_if = "Option::is_none")]
+    pub base_reduction: Option<Money>,
+    #[serde(rename = "vBC", skip_serializing_if = "Option::is_none")]
+    pub base: Option<Money>,
+    #[serde(rename = "pICMS", skip_serializing_if = "Option::is_none")]
+    pub rate: Option<Money>,
+    #[serde(rename = "vICMSOp", skip_serializing_if = "Option::is_none")]
+    pub operation_value: Option<Money>,
+    #[serde(rename = "pDif", skip_serializing_if = "Option::is_none")]
+    pub deferral_rate: Option<Money>,
+    #[serde(rename = "vICMSDif", skip_serializing_if = "Option::is_none")]
+    pub deferred_value: Option<Money>,
+    #[serde(rename = "vICMS", skip_serializing_if = "Option::is_none")]
+    pub value: Option<Money>,
+    #[serde(rename = "vBCFCP", skip_serializing_if = "Option::is_none")]
+    pub fcp_base: Option<Money>,
+    #[serde(rename = "pFCP", skip_serializing_if = "Option::is_none")]
+    pub fcp_rate: Option<Money>,
+    #[serde(rename = "vFCP", skip_serializing_if = "Option::is_none")]
+    pub fcp_value: Option<Money>,
+}
+
+/// ICMS structure for CST 60 (ST charged previously)
+///
+/// origin: Origin of the product (orig)
+/// cst: Tax situation code (CST)
+/// retained_st_base: ST calculation base retained previously (vBCSTRet) - Optional
+/// final_consumer_rate: Rate supported by the final consumer, with the FCP (pST) - Optional
+/// substitute_value: Own ICMS value of the substitute (vICMSSubstituto) - Optional
+/// retained_st_value: ICMS ST value retained previously (vICMSSTRet) - Optional
+/// retained_st_fcp_base: Calculation base of the FCP retained previously by ST (vBCFCPSTRet) - Optional
+/// retained_st_fcp_rate: Rate of the FCP retained previously by ST (pFCPSTRet) - Optional
+/// retained_st_fcp_value: Value of the FCP retained previously by ST (vFCPSTRet) - Optional
+/// effective_base_reduction: Reduction percentage of the effective calculation base (pRedBCEfet) - Optional
+/// effective_base: Effective calculation base (vBCEfet) - Optional
+/// effective_rate: Effective ICMS rate (pICMSEfet) - Optional
+/// effective_value: Effective ICMS value (vICMSEfet) - Optional
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ICMS60 {
+    #[serde(rename = "orig")]
+    pub origin: Origin,
+    #[serde(rename = "CST")]
+    pub cst: CST,
+    #[serde(rename = "vBCSTRet", skip_serializing_if = "Option::is_none")]
+    pub retained_st_base: Option<Money>,
+    #[serde(rename = "pST", skip_serializing_if = "Option::is_none")]
+    pub final_consumer_rate: Option<Money>,
+    #[serde(rename = "vICMSSubstituto", skip_serializing_if = "Option::is_none")]
+    pub substitute_value: Option<Money>,
+    #[serde(rename = "vICMSSTRet", skip_serializing_if = "Option::is_none")]
+    pub retained_st_value: Option<Money>,
+    #[serde(rename = "vBCFCPSTRet", skip_serializing_if = "Option::is_none")]
+    pub retained_st_fcp_base: Option<Money>,
+    #[serde(rename = "pFCPSTRet", skip_serializing_if = "Option::is_none")]
+    pub retained_st_fcp_rate: Option<Money>,
+    #[serde(rename = "vFCPSTRet", skip_serializing_if = "Option::is_none")]
+    pub retained_st_fcp_value: Option<Money>,
+    #[serde(rename = "pRedBCEfet", skip_serializing_if = "Option::is_none")]
+    pub effective_base_reduction: Option<Money>,
+    #[serde(rename = "vBCEfet", skip_serializing_if = "Option::is_none")]
+    pub effective_base: Option<Money>,
+    #[serde(rename = "pICMSEfet", skip_serializing_if = "Option::is_none")]
+    pub effective_rate: Option<Money>,
+    #[serde(rename = "vICMSEfet", skip_serializing_if = "Option::is_none")]
+    pub effective_value: Option<Money>,
+}
+
+/// ICMS structure for CST 70 (reduced calculation base, with ST)
+///
+/// origin: Origin of the product (orig)
+/// cst: Tax situation code (CST)
+/// base_modality: Modality of the calculation base (modBC)
+/// base_reduction: Reduction percentage of the calculation base (pRedBC)
+/// base: Calculation base (vBC)
+/// rate: ICMS rate (pICMS)
+/// value: ICMS value (vICMS)
+/// fcp_base: Calculation base of the FCP (vBCFCP) - Optional
+/// fcp_rate: Rate of the FCP (pFCP) - Optional
+/// fcp_value: Value of the FCP (vFCP) - Optional
+/// st_base_modality: Modality of the ST calculation base (modBCST)
+/// st_margin: Value added margin percentage of the ST (pMVAST) - Optional
+/// st_base_reduction: Reduction percentage of the ST calculation base (pRedBCST) - Optional
+/// st_base: ST calculation base (vBCST)
+/// st_rate: ICMS ST rate (pICMSST)
+/// st_value: ICMS ST value (vICMSST)
+/// st_fcp_base: Calculation base of the FCP retained by ST (vBCFCPST) - Optional
+/// st_fcp_rate: Rate of the FCP retained by ST (pFCPST) - Optional
+/// st_fcp_value: Value of the FCP retained by ST (vFCPST) - Optional
+/// unburdened: Value of the exempted ICMS (vICMSDeson) - Optional
+/// unburden_reason: Reason of the exemption of the ICMS (motDesICMS) - Optional
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ICMS70 {
+    #[serde(rename = "orig")]
+    pub origin: Origin,
+    #[serde(rename = "CST")]
+    pub cst: CST,
+    #[serde(rename = "modBC")]
+    pub base_modality: BaseModality,
+    #[serde(rename = "pRedBC")]
+    pub base_reduction: Money,
+    #[serde(rename = "vBC")]
+    pub base: Money,
+    #[serde(rename = "pICMS")]
+    pub rate: Money,
+    #[serde(rename = "vICMS")]
+    pub value: Money,
+    #[serde(rename = "vBCFCP", skip_serializing_if = "Option::is_none")]
+    pub fcp_base: Option<Money>,
+    #[serde(rename = "pFCP", skip_serializing_if = "Option::is_none")]
+    pub fcp_rate: Option<Money>,
+    #[serde(rename = "vFCP", skip_serializing_if = "Option::is_none")]
+    pub fcp_value: Option<Money>,
+    #[serde(rename = "modBCST")]
+    pub st_base_modality: STBaseModality,
+    #[serde(rename = "pMVAST", skip_serializing_if = "Option::is_none")]
+    pub st_margin: Option<Money>,
+    #[serde(rename = "pRedBCST", skip_serializing_if = "Option::is_none")]
+    pub st_base_reduction: Option<Money>,
+    #[serde(rename = "vBCST")]
+    pub st_base: Money,
+    #[serde(rename = "pICMSST")]
+    pub st_rate: Money,
+    #[serde(rename = "vICMSST")]
+    pub st_value: Money,
+    #[serde(rename = "vBCFCPST", skip_serializing_if = "Option::is_none")]
+    pub st_fcp_base: Option<Money>,
+    #[serde(rename = "pFCPST", skip_serializing_if = "Option::is_none")]
+    pub st_fcp_rate: Option<Money>,
+    #[serde(rename = "vFCPST", skip_serializing_if = "Option::is_none")]
+    pub st_fcp_value: Option<Money>,
+    #[serde(rename = "vICMSDeson", skip_serializing_if = "Option::is_none")]
+    pub unburdened: Option<Money>,
+    #[serde(rename = "motDesICMS", skip_serializing_if = "Option::is_none")]
+    pub unburden_reason: Option<UnburdenReason>,
+}
+
+/// ICMS structure for CST 90 (others)
+///
+/// origin: Origin of the product (orig)
+/// cst: Tax situation code (CST)
+/// base_modality: Modality of the calculation base (modBC) - Optional
+/// base: Calculation base (vBC) - Optional
+/// base_reduction: Reduction percentage of the calculation base (pRedBC) - Optional
+/// rate: ICMS rate (pICMS) - Optional
+/// value: ICMS value (vICMS) - Optional
+/// fcp_base: Calculation base of the FCP (vBCFCP) - Optional
+/// fcp_rate: Rate of the FCP (pFCP) - Optional
+/// fcp_value: Value of the FCP (vFCP) - Optional
+/// st_base_modality: Modality of the ST calculation base (modBCST) - Optional
+/// st_margin: Value added margin percentage of the ST (pMVAST) - Optional
+/// st_base_reduction: Reduction percentage of the ST calculation base (pRedBCST) - Optional
+/// st_base: ST calculation base (vBCST) - Optional
+/// st_rate: ICMS ST rate (pICMSST) - Optional
+/// st_value: ICMS ST value (vICMSST) - Optional
+/// st_fcp_base: Calculation base of the FCP retained by ST (vBCFCPST) - Optional
+/// st_fcp_rate: Rate of the FCP retained by ST (pFCPST) - Optional
+/// st_fcp_value: Value of the FCP retained by ST (vFCPST) - Optional
+/// unburdened: Value of the exempted ICMS (vICMSDeson) - Optional
+/// unburden_reason: Reason of the exemption of the ICMS (motDesICMS) - Optional
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ICMS90 {
+    #[serde(rename = "orig")]
+    pub origin: Origin,
+    #[serde(rename = "CST")]
+    pub cst: CST,
+    #[serde(rename = "modBC", skip_serializing_if = "Option::is_none")]
+    pub base_modality: Option<BaseModality>,
+    #[serde(rename = "vBC", skip_serializing_if = "Option::is_none")]
+    pub base: Option<Money>,
+    #[serde(rename = "pRedBC", skip_serializing_if = "Option::is_none")]
+    pub base_reduction: Option<Money>,
+    #[serde(rename = "pICMS", skip_serializing_if = "Option::is_none")]
+    pub rate: Option<Money>,
+    #[serde(rename = "vICMS", skip_serializing_if = "Option::is_none")]
+    pub value: Option<Money>,
+    #[serde(rename = "vBCFCP", skip_serializing_if = "Option::is_none")]
+    pub fcp_base: Option<Money>,
+    #[serde(rename = "pFCP", skip_serializing_if = "Option::is_none")]
+    pub fcp_rate: Option<Money>,
+    #[serde(rename = "vFCP", skip_serializing_if = "Option::is_none")]
+    pub fcp_value: Option<Money>,
+    #[serde(rename = "modBCST", skip_serializing_if = "Option::is_none")]
+    pub st_base_modality: Option<STBaseModality>,
+    #[serde(rename = "pMVAST", skip_serializing_if = "Option::is_none")]
+    pub st_margin: Option<Money>,
+    #[serde(rename = "pRedBCST", skip_serializing_if = "Option::is_none")]
+    pub st_base_reduction: Option<Money>,
+    #[serde(rename = "vBCST", skip_serializing_if = "Option::is_none")]
+    pub st_base: Option<Money>,
+    #[serde(rename = "pICMSST", skip_serializing_if = "Option::is_none")]
+    pub st_rate: Option<Money>,
+    #[serde(rename = "vICMSST", skip_serializing_if = "Option::is_none")]
+    pub st_value: Option<Money>,
+    #[serde(rename = "vBCFCPST", skip_serializing_if = "Option::is_none")]
+    pub st_fcp_base: Option<Money>,
+    #[serde(rename = "pFCPST", skip_serializing_if = "Option::is_none")]
+    pub st_fcp_rate: Option<Money>,
+    #[serde(rename = "vFCPST", skip_serializing_if = "Option::is_none")]
+    pub st_fcp_value: Option<Money>,
+    #[serde(rename = "vICMSDeson", skip_serializing_if = "Option::is_none")]
+    pub unburdened: Option<Money>,
+    #[serde(rename = "motDesICMS", skip_serializing_if = "Option::is_none")]
+    pub unburden_reason: Option<UnburdenReason>,
+}
+
+/// ICMS structure for the shared operations between states (CST 10 or 90)
+///
+/// origin: Origin of the product (orig)
+/// cst: Tax situation code (CST)
+/// base_modality: Modality of the calculation base (modBC)
+/// base: Calculation base (vBC)
+/// base_reduction: Reduction percentage of the calculation base (pRedBC) - Optional
+/// rate: ICMS rate (pICMS)
+/// value: ICMS value (vICMS)
+/// st_base_modality: Modality of the ST calculation base (modBCST)
+/// st_margin: Value added margin percentage of the ST (pMVAST) - Optional
+/// st_base_reduction: Reduction percentage of the ST calculation base (pRedBCST) - Optional
+/// st_base: ST calculation base (vBCST)
+/// st_rate: ICMS ST rate (pICMSST)
+/// st_value: ICMS ST value (vICMSST)
+/// st_fcp_base: Calculation base of the FCP retained by ST (vBCFCPST) - Optional
+/// st_fcp_rate: Rate of the FCP retained by ST (pFCPST) - Optional
+/// st_fcp_value: Value of the FCP retained by ST (vFCPST) - Optional
+/// own_operation_rate: Percentage of the calculation base of the own operation (pBCOp)
+/// st_state: State where the ST is due (UFST)
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ICMSPart {
+    #[serde(rename = "orig")]
+    pub origin: Origin,
+    #[serde(rename = "CST", deserialize_with = "part_cst")]
+    pub cst: CST,
+    #[serde(rename = "modBC")]
+    pub base_modality: BaseModality,
+    #[serde(rename = "vBC")]
+    pub base: Money,
+    #[serde(rename = "pRedBC", skip_serializing_if = "Option::is_none")]
+    pub base_reduction: Option<Money>,
+    #[serde(rename = "pICMS")]
+    pub rate: Money,
+    #[serde(rename = "vICMS")]
+    pub value: Money,
+    #[serde(rename = "modBCST")]
+    pub st_base_modality: STBaseModality,
+    #[serde(rename = "pMVAST", skip_serializing_if = "Option::is_none")]
+    pub st_margin: Option<Money>,
+    #[serde(rename = "pRedBCST", skip_serializing_if = "Option::is_none")]
+    pub st_base_reduction: Option<Money>,
+    #[serde(rename = "vBCST")]
+    pub st_base: Money,
+    #[serde(rename = "pICMSST")]
+    pub st_rate: Money,
+    #[serde(rename = "vICMSST")]
+    pub st_value: Money,
+    #[serde(rename = "vBCFCPST", skip_serializing_if = "Option::is_none")]
+    pub st_fcp_base: Option<Money>,
+    #[serde(rename = "pFCPST", skip_serializing_if = "Option::is_none")]
+    pub st_fcp_rate: Option<Money>,
+    #[serde(rename = "vFCPST", skip_serializing_if = "Option::is_none")]
+    pub st_fcp_value: Option<Money>,
+    #[serde(rename = "pBCOp")]
+    pub own_operation_rate: Money,
+    #[serde(rename = "UFST", with = "crate::states::acronym")]
+    pub st_state: State,
+}
+
+/// ICMS structure for the ST transferred between states (CST 41 or 60)
+///
+/// origin: Origin of the product (orig)
+/// cst: Tax situation code (CST)
+/// retained_st_base: ST calculation base retained previously (vBCSTRet)
+/// retained_st_value: ICMS ST value retained previously (vICMSSTRet)
+/// retained_st_fcp_base: Calculation base of the FCP retained previously by ST (vBCFCPSTRet) - Optional
+/// retained_st_fcp_rate: Rate of the FCP retained previously by ST (pFCPSTRet) - Optional
+/// retained_st_fcp_value: Value of the FCP retained previously by ST (vFCPSTRet) - Optional
+/// destination_st_base: ST calculation base of the destination state (vBCSTDest)
+/// destination_st_value: ICMS ST value of the destination state (vICMSSTDest)
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ICMSST {
+    #[serde(rename = "orig")]
+    pub origin: Origin,
+    #[serde(rename = "CST", deserialize_with = "st_cst")]
+    pub cst: CST,
+    #[serde(rename = "vBCSTRet")]
+    pub retained_st_base: Money,
+    #[serde(rename = "vICMSSTRet")]
+    pub retained_st_value: Money,
+    #[serde(rename = "vBCFCPSTRet", skip_serializing_if = "Option::is_none")]
+    pub retained_st_fcp_base: Option<Money>,
+    #[serde(rename = "pFCPSTRet", skip_serializing_if = "Option::is_none")]
+    pub retained_st_fcp_rate: Option<Money>,
+    #[serde(rename = "vFCPSTRet", skip_serializing_if = "Option::is_none")]
+    pub retained_st_fcp_value: Option<Money>,
+    #[serde(rename = "vBCSTDest")]
+    pub destination_st_base: Money,
+    #[serde(rename = "vICMSSTDest")]
+    pub destination_st_value: Money,
+}
+
+/// Deserializes the CST of a group, rejecting the ones its layout does not allow
+fn restricted_cst<'de, D>(deserializer: D, group: &str, allowed: &[CST]) -> Result<CST, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let cst = CST::deserialize(deserializer)?;
+    if !allowed.contains(&cst) {
+        return Err(serde::de::Error::custom(format!(
+            "Invalid CST for {}: {:02}",
+            group,
+            cst.code()
+        )));
+    }
+    Ok(cst)
+}
+
+fn part_cst<'de, D: Deserializer<'de>>(deserializer: D) -> Result<CST, D::Error> {
+    restricted_cst(deserializer, "ICMSPart", &[CST::TaxedWithST, CST::Others])
+}
+
+fn st_cst<'de, D: Deserializer<'de>>(deserializer: D) -> Result<CST, D::Error> {
+    restricted_cst(
+        deserializer,
+        "ICMSST",
+        &[CST::NotTaxed, CST::PreviouslyChargedST],
+    )
+}
+
+/// ICMS structure for CSOSN 101 (taxed with credit)
+///
+/// origin: Origin of the product (orig)
+/// csosn: CSOSN code (CSOSN)
+/// credit_rate: Credit rate that can be used by the recipient (pCredSN)
+/// credit_value: Credit value that can be used by the recipient (vCredICMSSN)
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ICMSSN101 {
+    #[serde(rename = "orig")]
+    pub origin: Origin,
+    #[serde(rename = "CSOSN")]
+    pub csosn: CSOSN,
+    #[serde(rename = "pCredSN")]
+    pub credit_rate: Money,
+    #[serde(rename = "vCredICMSSN")]
+    pub credit_value: Money,
+}
+
+/// ICMS structure for CSOSN 102, 103, 300 and 400 (taxed without credit, exempt range, immune or not taxed)
+///
+/// origin: Origin of the product (orig)
+/// csosn: CSOSN code (CSOSN)
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ICMSSN102 {
+    #[serde(rename = "orig")]
+    pub origin: Origin,
+    #[serde(rename = "CSOSN")]
+    pub csosn: CSOSN,
+}
+
+/// ICMS structure for CSOSN 201 (taxed with credit and ST)
+///
+/// origin: Origin of the product (orig)
+/// csosn: CSOSN code (CSOSN)
+/// st_base_modality: Modality of the ST calculation base (modBCST)
+/// st_margin: Value added margin percentage of the ST (pMVAST) - Optional
+/// st_base_reduction: Reduction percentage of the ST calculation base (pRedBCST) - Optional
+/// st_base: ST calculation base (vBCST)
+/// st_rate: ICMS ST rate (pICMSST)
+/// st_value: ICMS ST value (vICMSST)
+/// st_fcp_base: Calculation base of the FCP retained by ST (vBCFCPST) - Optional
+/// st_fcp_rate: Rate of the FCP retained by ST (pFCPST) - Optional
+/// st_fcp_value: Value of the FCP retained by ST (vFCPST) - Optional
+/// credit_rate: Credit rate that can be used by the recipient (pCredSN)
+/// credit_value: Credit value that can be used by the recipient (vCredICMSSN)
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ICMSSN201 {
+    #[serde(rename = "orig")]
+    pub origin: Origin,
+    #[serde(rename = "CSOSN")]
+    pub csosn: CSOSN,
+    #[serde(rename = "modBCST")]
+    pub st_base_modality: STBaseModality,
+    #[serde(rename = "pMVAST", skip_serializing_if = "Option::is_none")]
+    pub st_margin: Option<Money>,
+    #[serde(rename = "pRedBCST", skip_serializing_if = "Option::is_none")]
+    pub st_base_reduction: Option<Money>,
+    #[serde(rename = "vBCST")]
+    pub st_base: Money,
+    #[serde(rename = "pICMSST")]
+    pub st_rate: Money,
+    #[serde(rename = "vICMSST")]
+    pub st_value: Money,
+    #[serde(rename = "vBCFCPST", skip_serializing_if = "Option::is_none")]
+    pub st_fcp_base: Option<Money>,
+    #[serde(rename = "pFCPST", skip_serializing_if = "Option::is_none")]
+    pub st_fcp_rate: Option<Money>,
+    #[serde(rename = "vFCPST", skip_serializing_if = "Option::is_none")]
+    pub st_fcp_value: Option<Money>,
+    #[serde(rename = "pCredSN")]
+    pub credit_rate: Money,
+    #[serde(rename = "vCredICMSSN")]
+    pub credit_value: Money,
+}
+
+/// ICMS structure for CSOSN 202 and 203 (taxed without credit or exempt range, with ST)
+///
+/// origin: Origin of the product (orig)
+/// csosn: CSOSN code (CSOSN)
+/// st_base_modality: Modality of the ST calculation base (modBCST)
+/// st_margin: Value added margin percentage of the ST (pMVAST) - Optional
+/// st_base_reduction: Reduction percentage of the ST calculation base (pRedBCST) - Optional
+/// st_base: ST calculation base (vBCST)
+/// st_rate: ICMS ST rate (pICMSST)
+/// st_value: ICMS ST value (vICMSST)
+/// st_fcp_base: Calculation base of the FCP retained by ST (vBCFCPST) - Optional
+/// st_fcp_rate: Rate of the FCP retained by ST (pFCPST) - Optional
+/// st_fcp_value: Value of the FCP retained by ST (vFCPST) - Optional
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ICMSSN202 {
+    #[serde(rename = "orig")]
+    pub origin: Origin,
+    #[serde(rename = "CSOSN")]
+    pub csosn: CSOSN,
+    #[serde(rename = "modBCST")]
+    pub st_base_modality: STBaseModality,
+    #[serde(rename = "pMVAST", skip_serializing_if = "Option::is_none")]
+    pub st_margin: Option<Money>,
+    #[serde(rename = "pRedBCST", skip_serializing_if = "Option::is_none")]
+    pub st_base_reduction: Option<Money>,
+    #[serde(rename = "vBCST")]
+    pub st_base: Money,
+    #[serde(rename = "pICMSST")]
+    pub st_rate: Money,
+    #[serde(rename = "vICMSST")]
+    pub st_value: Money,
+    #[serde(rename = "vBCFCPST", skip_serializing_if = "Option::is_none")]
+    pub st_fcp_base: Option<Money>,
+    #[serde(rename = "pFCPST", skip_serializing_if = "Option::is_none")]
+    pub st_fcp_rate: Option<Money>,
+    #[serde(rename = "vFCPST", skip_serializing_if = "Option::is_none")]
+    pub st_fcp_value: Option<Money>,
+}
+
+/// ICMS structure for CSOSN 500 (ST charged previously)
+///
+/// origin: Origin of the product (orig)
+/// csosn: CSOSN code (CSOSN)
+/// retained_st_base: ST calculation base retained previously (vBCSTRet) - Optional
+/// final_consumer_rate: Rate supported by the final consumer, with the FCP (pST) - Optional
+/// substitute_value: Own ICMS value of the substitute (vICMSSubstituto) - Optional
+/// retained_st_value: ICMS ST value retained previously (vICMSSTRet) - Optional
+/// retained_st_fcp_base: Calculation base of the FCP retained previously by ST (vBCFCPSTRet) - Optional
+/// retained_st_fcp_rate: Rate of the FCP retained previously by ST (pFCPSTRet) - Optional
+/// retained_st_fcp_value: Value of the FCP retained previously by ST (vFCPSTRet) - Optional
+/// effective_base_reduction: Reduction percentage of the effective calculation base (pRedBCEfet) - Optional
+/// effective_base: Effective calculation base (vBCEfet) - Optional
+/// effective_rate: Effective ICMS rate (pICMSEfet) - Optional
+/// effective_value: Effective ICMS value (vICMSEfet) - Optional
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ICMSSN500 {
+    #[serde(rename = "orig")]
+    pub origin: Origin,
+    #[serde(rename = "CSOSN")]
+    pub csosn: CSOSN,
+    #[serde(rename = "vBCSTRet", skip_serializing_if = "Option::is_none")]
+    pub retained_st_base: Option<Money>,
+    #[serde(rename = "pST", skip_serializing_if = "Option::is_none")]
+    pub final_consumer_rate: Option<Money>,
+    #[serde(rename = "vICMSSubstituto", skip_serializing_if = "Option::is_none")]
+    pub substitute_value: Option<Money>,
+    #[serde(rename = "vICMSSTRet", skip_serializing_if = "Option::is_none")]
+    pub retained_st_value: Option<Money>,
+    #[serde(rename = "vBCFCPSTRet", skip_serializing_if = "Option::is_none")]
+    pub retained_st_fcp_base: Option<Money>,
+    #[serde(rename = "pFCPSTRet", skip_serializing_if = "Option::is_none")]
+    pub retained_st_fcp_rate: Option<Money>,
+    #[serde(rename = "vFCPSTRet", skip_serializing_if = "Option::is_none")]
+    pub retained_st_fcp_value: Option<Money>,
+    #[serde(rename = "pRedBCEfet", skip_serializing_if = "Option::is_none")]
+    pub effective_base_reduction: Option<Money>,
+    #[serde(rename = "vBCEfet", skip_serializing_if = "Option::is_none")]
+    pub effective_base: Option<Money>,
+    #[serde(rename = "pICMSEfet", skip_serializing_if = "Option::is_none")]
+    pub effective_rate: Option<Money>,
+    #[serde(rename = "vICMSEfet", skip_serializing_if = "Option::is_none")]
+    pub effective_value: Option<Money>,
+}
+
+/// ICMS structure for CSOSN 900 (others)
+///
+/// origin: Origin of the product (orig)
+/// csosn: CSOSN code (CSOSN)
+/// base_modality: Modality of the calculation base (modBC) - Optional
+/// base: Calculation base (vBC) - Optional
+/// base_reduction: Reduction percentage of the calculation base (pRedBC) - Optional
+/// rate: ICMS rate (pICMS) - Optional
+/// value: ICMS value (vICMS) - Optional
+/// st_base_modality: Modality of the ST calculation base (modBCST) - Optional
+/// st_margin: Value added margin percentage of the ST (pMVAST) - Optional
+/// st_base_reduction: Reduction percentage of the ST calculation base (pRedBCST) - Optional
+/// st_base: ST calculation base (vBCST) - Optional
+/// st_rate: ICMS ST rate (pICMSST) - Optional
+/// st_value: ICMS ST value (vICMSST) - Optional
+/// st_fcp_base: Calculation base of the FCP retained by ST (vBCFCPST) - Optional
+/// st_fcp_rate: Rate of the FCP retained by ST (pFCPST) - Optional
+/// st_fcp_value: Value of the FCP retained by ST (vFCPST) - Optional
+/// credit_rate: Credit rate that can be used by the recipient (pCredSN) - Optional
+/// credit_value: Credit value that can be used by the recipient (vCredICMSSN) - Optional
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ICMSSN900 {
+    #[serde(rename = "orig")]
+    pub origin: Origin,
+    #[serde(rename = "CSOSN")]
+    pub csosn: CSOSN,
+    #[serde(rename = "modBC", skip_serializing_if = "Option::is_none")]
+    pub base_modality: Option<BaseModality>,
+    #[serde(rename = "vBC", skip_serializing_if = "Option::is_none")]
+    pub base: Option<Money>,
+    #[serde(rename = "pRedBC", skip_serializing_if = "Option::is_none")]
+    pub base_reduction: Option<Money>,
+    #[serde(rename = "pICMS", skip_serializing_if = "Option::is_none")]
+    pub rate: Option<Money>,
+    #[serde(rename = "vICMS", skip_serializing_if = "Option::is_none")]
+    pub value: Option<Money>,
+    #[serde(rename = "modBCST", skip_serializing_if = "Option::is_none")]
+    pub st_base_modality: Option<STBaseModality>,
+    #[serde(rename = "pMVAST", skip_serializing_if = "Option::is_none")]
+    pub st_margin: Option<Money>,
+    #[serde(rename = "pRedBCST", skip_serializing_if = "Option::is_none")]
+    pub st_base_reduction: Option<Money>,
+    #[serde(rename = "vBCST", skip_serializing_if = "Option::is_none")]
+    pub st_base: Option<Money>,
+    #[serde(rename = "pICMSST", skip_serializing_if = "Option::is_none")]
+    pub st_rate: Option<Money>,
+    #[serde(rename = "vICMSST", skip_serializing_if = "Option::is_none")]
+    pub st_value: Option<Money>,
+    #[serde(rename = "vBCFCPST", skip_serializing_if = "Option::is_none")]
+    pub st_fcp_base: Option<Money>,
+    #[serde(rename = "pFCPST", skip_serializing_if = "Option::is_none")]
+    pub st_fcp_rate: Option<Money>,
+    #[serde(rename = "vFCPST", skip_serializing_if = "Option::is_none")]
+    pub st_fcp_value: Option<Money>,
+    #[serde(rename = "pCredSN", skip_serializing_if = "Option::is_none")]
+    pub credit_rate: Option<Money>,
+    #[serde(rename = "vCredICMSSN", skip_serializing_if = "Option::is_none")]
+    pub credit_value: Option<Money>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xml::{indent_xml, normalize_xml as canonicalize};
+    use nf_e_macros::{deserialization_error_test, serialization_test};
+    use quick_xml::{de::from_str as deserialize, se::to_string as serialize};
+    use rust_decimal_macros::dec;
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/icms/icms00.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_icms00() -> ICMS00 {
+        ICMS00 {
+            origin: Origin::National,
+            cst: CST::Taxed,
+            base_modality: BaseModality::OperationValue,
+            base: Money::from(dec!(100.0)),
+            rate: Money::from(dec!(18.0)),
+            value: Money::from(dec!(18.0)),
+            fcp_rate: Some(Money::from(dec!(2.0))),
+            fcp_value: Some(Money::from(dec!(2.0))),
+        }
+    }
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/icms/icms10.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_icms10() -> ICMS10 {
+        ICMS10 {
+            origin: Origin::National,
+            cst: CST::TaxedWithST,
+            base_modality: BaseModality::OperationValue,
+            base: Money::from(dec!(100.0)),
+            rate: Money::from(dec!(18.0)),
+            value: Money::from(dec!(18.0)),
+            fcp_base: None,
+            fcp_rate: None,
+            fcp_value: None,
+            st_base_modality: STBaseModality::ValueAddedMargin,
+            st_margin: Some(Money::from(dec!(40.0))),
+            st_base_reduction: None,
+            st_base: Money::from(dec!(140.0)),
+            st_rate: Money::from(dec!(18.0)),
+            st_value: Money::from(dec!(7.2)),
+            st_fcp_base: Some(Money::from(dec!(140.0))),
+            st_fcp_rate: Some(Money::from(dec!(2.0))),
+            st_fcp_value: Some(Money::from(dec!(2.8))),
+        }
+    }
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/icms/icms20.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_icms20() -> ICMS20 {
+        ICMS20 {
+            origin: Origin::National,
+            cst: CST::ReducedBase,
+            base_modality: BaseModality::OperationValue,
+            base_reduction: Money::from(dec!(33.33)),
+            base: Money::from(dec!(66.67)),
+            rate: Money::from(dec!(18.0)),
+            value: Money::from(dec!(12.0)),
+            fcp_base: None,
+            fcp_rate: None,
+            fcp_value: None,
+            unburdened: Some(Money::from(dec!(6.0))),
+            unburden_reason: Some(UnburdenReason::Other),
+        }
+    }
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/icms/icms30.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_icms30() -> ICMS30 {
+        ICMS30 {
+            origin: Origin::National,
+            cst: CST::ExemptWithST,
+            st_base_modality: STBaseModality::ValueAddedMargin,
+            st_margin: Some(Money::from(dec!(40.0))),
+            st_base_reduction: Some(Money::from(dec!(10.0))),
+            st_base: Money::from(dec!(126.0)),
+            st_rate: Money::from(dec!(18.0)),
+            st_value: Money::from(dec!(22.68)),
+            st_fcp_base: None,
+            st_fcp_rate: None,
+            st_fcp_value: None,
+            unburdened: None,
+            unburden_reason: None,
+        }
+    }
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/icms/icms40.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_icms40() -> ICMS40 {
+        ICMS40 {
+            origin: Origin::Foreign,
+            cst: CST::NotTaxed,
+            unburdened: None,
+            unburden_reason: None,
+        }
+    }
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/icms/icms51.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_icms51() -> ICMS51 {
+        ICMS51 {
+            origin: Origin::National,
+            cst: CST::Deferred,
+            base_modality: Some(BaseModality::OperationValue),
+            base_reduction: None,
+            base: Some(Money::from(dec!(100.0))),
+            rate: Some(Money::from(dec!(18.0))),
+            operation_value: Some(Money::from(dec!(18.0))),
+            deferral_rate: Some(Money::from(dec!(33.33))),
+            deferred_value: Some(Money::from(dec!(6.0))),
+            value: Some(Money::from(dec!(12.0))),
+            fcp_base: None,
+            fcp_rate: None,
+            fcp_value: None,
+        }
+    }
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/icms/icms60.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_icms60() -> ICMS60 {
+        ICMS60 {
+            origin: Origin::National,
+            cst: CST::PreviouslyChargedST,
+            retained_st_base: Some(Money::from(dec!(100.0))),
+            final_consumer_rate: Some(Money::from(dec!(20.0))),
+            substitute_value: Some(Money::from(dec!(12.0))),
+            retained_st_value: Some(Money::from(dec!(8.0))),
+            retained_st_fcp_base: Some(Money::from(dec!(100.0))),
+            retained_st_fcp_rate: Some(Money::from(dec!(2.0))),
+            retained_st_fcp_value: Some(Money::from(dec!(2.0))),
+            effective_base_reduction: Some(Money::from(dec!(10.0))),
+            effective_base: Some(Money::from(dec!(90.0))),
+            effective_rate: Some(Money::from(dec!(18.0))),
+            effective_value: Some(Money::from(dec!(16.2))),
+        }
+    }
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/icms/icms70.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_icms70() -> ICMS70 {
+        ICMS70 {
+            origin: Origin::National,
+            cst: CST::ReducedBaseWithST,
+            base_modality: BaseModality::OperationValue,
+            base_reduction: Money::from(dec!(10.0)),
+            base: Money::from(dec!(90.0)),
+            rate: Money::from(dec!(18.0)),
+            value: Money::from(dec!(16.2)),
+            fcp_base: None,
+            fcp_rate: None,
+            fcp_value: None,
+            st_base_modality: STBaseModality::ValueAddedMargin,
+            st_margin: Some(Money::from(dec!(40.0))),
+            st_base_reduction: None,
+            st_base: Money::from(dec!(126.0)),
+            st_rate: Money::from(dec!(18.0)),
+            st_value: Money::from(dec!(6.48)),
+            st_fcp_base: None,
+            st_fcp_rate: None,
+            st_fcp_value: None,
+            unburdened: None,
+            unburden_reason: None,
+        }
+    }
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/icms/icms90.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_icms90() -> ICMS90 {
+        ICMS90 {
+            origin: Origin::National,
+            cst: CST::Others,
+            base_modality: Some(BaseModality::OperationValue),
+            base: Some(Money::from(dec!(100.0))),
+            base_reduction: None,
+            rate: Some(Money::from(dec!(12.0))),
+            value: Some(Money::from(dec!(12.0))),
+            fcp_base: None,
+            fcp_rate: None,
+            fcp_value: None,
+            st_base_modality: None,
+            st_margin: None,
+            st_base_reduction: None,
+            st_base: None,
+            st_rate: None,
+            st_value: None,
+            st_fcp_base: None,
+            st_fcp_rate: None,
+            st_fcp_value: None,
+            unburdened: None,
+            unburden_reason: None,
+        }
+    }
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/icms/icms_part.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_icms_part() -> ICMSPart {
+        ICMSPart {
+            origin: Origin::National,
+            cst: CST::TaxedWithST,
+            base_modality: BaseModality::OperationValue,
+            base: Money::from(dec!(100.0)),
+            base_reduction: None,
+            rate: Money::from(dec!(12.0)),
+            value: Money::from(dec!(12.0)),
+            st_base_modality: STBaseModality::ValueAddedMargin,
+            st_margin: Some(Money::from(dec!(40.0))),
+            st_base_reduction: None,
+            st_base: Money::from(dec!(140.0)),
+            st_rate: Money::from(dec!(18.0)),
+            st_value: Money::from(dec!(13.2)),
+            st_fcp_base: None,
+            st_fcp_rate: None,
+            st_fcp_value: None,
+            own_operation_rate: Money::from(dec!(100.0)),
+            st_state: State::SaoPaulo,
+        }
+    }
+
+    #[deserialization_error_test(
+        expected = "<ICMSPart><orig>0</orig><CST>00</CST><modBC>3</modBC><vBC>100.00</vBC>\
+                    <pICMS>12.00</pICMS><vICMS>12.00</vICMS><modBCST>4</modBCST>\
+                    <vBCST>140.00</vBCST><pICMSST>18.00</pICMSST><vICMSST>13.20</vICMSST>\
+                    <pBCOp>100.00</pBCOp><UFST>SP</UFST></ICMSPart>",
+        expect = "Invalid CST for ICMSPart: 00"
+    )]
+    fn deserialize_icms_part_invalid_cst() -> ICMSPart;
+
+    #[deserialization_error_test(
+        expected = "<ICMSST><orig>0</orig><CST>10</CST><vBCSTRet>140.00</vBCSTRet>\
+                    <vICMSSTRet>7.20</vICMSSTRet><vBCSTDest>140.00</vBCSTDest>\
+                    <vICMSSTDest>9.80</vICMSSTDest></ICMSST>",
+        expect = "Invalid CST for ICMSST: 10"
+    )]
+    fn deserialize_icms_st_invalid_cst() -> ICMSST;
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/icms/icms_st.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_icms_st() -> ICMSST {
+        ICMSST {
+            origin: Origin::National,
+            cst: CST::PreviouslyChargedST,
+            retained_st_base: Money::from(dec!(140.0)),
+            retained_st_value: Money::from(dec!(7.2)),
+            retained_st_fcp_base: Some(Money::from(dec!(140.0))),
+            retained_st_fcp_rate: Some(Money::from(dec!(2.0))),
+            retained_st_fcp_value: Some(Money::from(dec!(2.8))),
+            destination_st_base: Money::from(dec!(140.0)),
+            destination_st_value: Money::from(dec!(9.8)),
+        }
+    }
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/icms/icmssn101.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_icmssn101() -> ICMSSN101 {
+        ICMSSN101 {
+            origin: Origin::National,
+            csosn: CSOSN::WithCredit,
+            credit_rate: Money::from(dec!(1.25)),
+            credit_value: Money::from(dec!(1.25)),
+        }
+    }
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/icms/icmssn201.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_icmssn201() -> ICMSSN201 {
+        ICMSSN201 {
+            origin: Origin::National,
+            csosn: CSOSN::WithCreditAndST,
+            st_base_modality: STBaseModality::ValueAddedMargin,
+            st_margin: Some(Money::from(dec!(40.0))),
+            st_base_reduction: None,
+            st_base: Money::from(dec!(140.0)),
+            st_rate: Money::from(dec!(18.0)),
+            st_value: Money::from(dec!(7.2)),
+            st_fcp_base: None,
+            st_fcp_rate: None,
+            st_fcp_value: None,
+            credit_rate: Money::from(dec!(1.25)),
+            credit_value: Money::from(dec!(1.25)),
+        }
+    }
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/icms/icmssn202.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_icmssn202() -> ICMSSN202 {
+        ICMSSN202 {
+            origin: Origin::National,
+            csosn: CSOSN::ExemptRangeWithST,
+            st_base_modality: STBaseModality::ReferencePrice,
+            st_margin: None,
+            st_base_reduction: None,
+            st_base: Money::from(dec!(150.0)),
+            st_rate: Money::from(dec!(18.0)),
+            st_value: Money::from(dec!(27.0)),
+            st_fcp_base: None,
+            st_fcp_rate: None,
+            st_fcp_value: None,
+        }
+    }
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/icms/icmssn500.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_icmssn500() -> ICMSSN500 {
+        ICMSSN500 {
+            origin: Origin::National,
+            csosn: CSOSN::PreviouslyChargedST,
+            retained_st_base: Some(Money::from(dec!(100.0))),
+            final_consumer_rate: Some(Money::from(dec!(20.0))),
+            substitute_value: Some(Money::from(dec!(12.0))),
+            retained_st_value: Some(Money::from(dec!(8.0))),
+            retained_st_fcp_base: Some(Money::from(dec!(100.0))),
+            retained_st_fcp_rate: Some(Money::from(dec!(2.0))),
+            retained_st_fcp_value: Some(Money::from(dec!(2.0))),
+            effective_base_reduction: None,
+            effective_base: None,
+            effective_rate: None,
+            effective_value: None,
+        }
+    }
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/icms/icmssn900.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_icmssn900() -> ICMSSN900 {
+        ICMSSN900 {
+            origin: Origin::National,
+            csosn: CSOSN::Others,
+            base_modality: Some(BaseModality::OperationValue),
+            base: Some(Money::from(dec!(100.0))),
+            base_reduction: None,
+            rate: Some(Money::from(dec!(18.0))),
+            value: Some(Money::from(dec!(18.0))),
+            st_base_modality: None,
+            st_margin: None,
+            st_base_reduction: None,
+            st_base: None,
+            st_rate: None,
+            st_value: None,
+            st_fcp_base: None,
+            st_fcp_rate: None,
+            st_fcp_value: None,
+            credit_rate: Some(Money::from(dec!(1.25))),
+            credit_value: Some(Money::from(dec!(1.25))),
+        }
+    }
+
+    #[test]
+    fn test_invalid_csosn() {
+        let result =
+            deserialize::<ICMSSN102>("<ICMSSN102><orig>0</orig><CSOSN>104</CSOSN></ICMSSN102>");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_invalid_origin() {
+        let result =
+            deserialize::<ICMSSN102>("<ICMSSN102><orig>9</orig><CSOSN>102</CSOSN></ICMSSN102>");
+        assert!(result.is_err());
+    }
+}
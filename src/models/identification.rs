@@ -0,0 +1,418 @@
+//! Builder of the identification (ide) of a document, filling the derived
+//! fields from the configuration of the issuer
+
+use super::Identification;
+use crate::access_key::{AccessKey, Components};
+use crate::config::{Config, ConfigError};
+use crate::disablement::{MAX_NUMBER, MAX_SERIES};
+use crate::enums::{
+    DanfeGeneration, DestinationTarget, EmissionType, Environment, Finality,
+    IntermediatorIndicator, Model, Operation, PersonDocument, Presence,
+};
+use crate::states::Location;
+use chrono::Datelike;
+use rand::Rng;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IdentificationError {
+    /// Number of the document (nNF) out of 1 to 999999999
+    InvalidNumber(u32),
+    /// Numeric code (cNF) refused by `is_valid_numeric_code`
+    InvalidNumericCode(u32),
+    /// Series of the document (serie) above 999
+    InvalidSeries(u16),
+}
+
+/// Builder of `Identification`
+///
+/// The location, environment, model, series and emission version (verProc)
+/// come from the configuration, the emission date is the moment of the
+/// creation of the builder by the clock of the configuration and the numeric
+/// code (cNF) is random. With a `FixedClock` and `set_numeric_code` the access
+/// key is always the same. The verifier digit (cDV) is computed by `build`, so
+/// it always matches the access key, after checking the series (serie), the
+/// number (nNF) and the numeric code given.
+///
+/// The other fields default to an outgoing internal operation with the
+/// normal emission and finality, with the portrait DANFE. NFC-e default to a
+/// final consumer present at the establishment, with the NFC-e DANFE.
+pub struct IdentificationBuilder {
+    issuer: PersonDocument,
+    location: Location,
+    numeric_code: Option<u32>,
+    operation_nature: String,
+    model: Model,
+    series: u16,
+    number: u32,
+    emission_date: chrono::DateTime<chrono::Local>,
+    date: Option<chrono::DateTime<chrono::Local>>,
+    r#type: Operation,
+    destination: DestinationTarget,
+    printing_type: Option<DanfeGeneration>,
+    emission_type: EmissionType,
+    environment: Environment,
+    finality: Finality,
+    consumer: Option<bool>,
+    presence: Option<Presence>,
+    intermediator_indicator: Option<IntermediatorIndicator>,
+    emission_version: String,
+}
+
+impl IdentificationBuilder {
+    /// Starts the identification of the document `number` of the issuer of `config`
+    pub fn new(config: &Config, number: u32, operation_nature: &str) -> Self {
+        let issuer = config.issuer();
+        Self {
+            issuer: issuer.document.clone(),
+            location: Location {
+                state: issuer.address.address.state.clone(),
+                city: issuer.address.address.city.clone(),
+            },
+            numeric_code: None,
+            operation_nature: operation_nature.to_string(),
+            model: config.model().clone(),
+            series: config.series(),
+            number,
+            emission_date: config.clock().local(),
+            date: None,
+            r#type: Operation::Outgoing,
+            destination: DestinationTarget::Internal,
+            printing_type: None,
+            emission_type: EmissionType::Normal,
+            environment: config.environment().clone(),
+            finality: Finality::Normal,
+            consumer: None,
+            presence: None,
+            intermediator_indicator: None,
+            emission_version: config.emission_version().to_string(),
+        }
+    }
+
+    /// Starts the identification of the document `number` of the global issuer
+    pub fn from_config(number: u32, operation_nature: &str) -> Result<Self, ConfigError> {
+        let config = crate::config::global()?;
+        Ok(Self::new(&config, number, operation_nature))
+    }
+
+    pub fn set_model(mut self, model: Model) -> Self {
+        self.model = model;
+        self
+    }
+
+    pub fn set_series(mut self, series: u16) -> Self {
+        self.series = series;
+        self
+    }
+
+    /// Numeric code of the access key (cNF), instead of a random one
+    pub fn set_numeric_code(mut self, numeric_code: u32) -> Self {
+        self.numeric_code = Some(numeric_code);
+        self
+    }
+
+    pub fn set_emission_date(mut self, emission_date: chrono::DateTime<chrono::Local>) -> Self {
+        self.emission_date = emission_date;
+        self
+    }
+
+    /// Date and time of the exit or entry of the goods (dhSaiEnt)
+    pub fn set_date(mut self, date: chrono::DateTime<chrono::Local>) -> Self {
+        self.date = Some(date);
+        self
+    }
+
+    pub fn set_type(mut self, r#type: Operation) -> Self {
+        self.r#type = r#type;
+        self
+    }
+
+    pub fn set_destination(mut self, destination: DestinationTarget) -> Self {
+        self.destination = destination;
+        self
+    }
+
+    pub fn set_printing_type(mut self, printing_type: DanfeGeneration) -> Self {
+        self.printing_type = Some(printing_type);
+        self
+    }
+
+    pub fn set_emission_type(mut self, emission_type: EmissionType) -> Self {
+        self.emission_type = emission_type;
+        self
+    }
+
+    pub fn set_environment(mut self, environment: Environment) -> Self {
+        self.environment = environment;
+        self
+    }
+
+    pub fn set_finality(mut self, finality: Finality) -> Self {
+        self.finality = finality;
+        self
+    }
+
+    pub fn set_consumer(mut self, consumer: bool) -> Self {
+        self.consumer = Some(consumer);
+        self
+    }
+
+    pub fn set_presence(mut self, presence: Presence) -> Self {
+        self.presence = Some(presence);
+        self
+    }
+
+    /// Intermediation of the operation, defaults to the own site or
+    /// platform of the issuer when the buyer is not present
+    pub fn set_intermediator_indicator(mut self, indicator: IntermediatorIndicator) -> Self {
+        self.intermediator_indicator = Some(indicator);
+        self
+    }
+
+    pub fn build(self) -> Result<Identification, IdentificationError> {
+        if self.series > MAX_SERIES {
+            return Err(IdentificationError::InvalidSeries(self.series));
+        }
+        if !(1..=MAX_NUMBER).contains(&self.number) {
+            return Err(IdentificationError::InvalidNumber(self.number));
+        }
+        if let Some(code) = self.numeric_code
+            && !is_valid_numeric_code(code, self.number)
+        {
+            return Err(IdentificationError::InvalidNumericCode(code));
+        }
+        let nfce = self.model == Model::NFCe;
+        let presence = self
+            .presence
+            .or_else(|| nfce.then_some(Presence::InplaceIndoor));
+        let numeric_code = self
+            .numeric_code
+            .unwrap_or_else(|| Identification::generate_numeric_code(self.number));
+        let emission_date = self
+            .emission_date
+            .with_timezone(&self.location.utc_offset(self.emission_date.to_utc()));
+        let access_key = AccessKey::new(&Components {
+            state: self.location.state.clone(),
+            year: (emission_date.year() % 100) as u8,
+            month: emission_date.month() as u8,
+            issuer: &self.issuer,
+            model: self.model.clone(),
+            series: self.series,
+            number: self.number,
+            emission_type: self.emission_type.clone(),
+            numeric_code,
+        });
+
+        Ok(Identification {
+            location: self.location,
+            numeric_code,
+            operation_nature: self.operation_nature,
+            model: self.model,
+            series: self.series,
+            number: self.number,
+            emission_date: self.emission_date,
+            date: self.date,
+            r#type: self.r#type,
+            destination: self.destination,
+            printing_type: self.printing_type.or(Some(if nfce {
+                DanfeGeneration::NFCe
+            } else {
+                DanfeGeneration::NormalPortrait
+            })),
+            emission_type: self.emission_type,
+            verifier_digit: access_key.verifier_digit(),
+            environment: self.environment,
+            finality: self.finality,
+            consumer: self.consumer.unwrap_or(nfce),
+            intermediator_indicator: self.intermediator_indicator.or_else(|| {
+                presence
+                    .as_ref()
+                    .is_some_and(Presence::is_remote)
+                    .then_some(IntermediatorIndicator::Own)
+            }),
+            presence,
+            emission_version: self.emission_version,
+            contingency: None,
+        })
+    }
+}
+
+impl Identification {
+    /// Random numeric code (cNF) of 8 digits for the document `number` (nNF)
+    ///
+    /// Follows the restrictions of the NT 2019.001, so the access key can not
+    /// be guessed from the number of the document.
+    pub fn generate_numeric_code(number: u32) -> u32 {
+        let mut rng = rand::thread_rng();
+        loop {
+            let code = rng.gen_range(0..100_000_000);
+            if is_valid_numeric_code(code, number) {
+                return code;
+            }
+        }
+    }
+}
+
+/// Whether the numeric code (cNF) is accepted for the document `number` (nNF)
+///
+/// The code must differ from the number and must not be a run of a single
+/// digit (00000000, 11111111...) nor an ascending sequence (12345678,
+/// 90123456...).
+pub fn is_valid_numeric_code(code: u32, number: u32) -> bool {
+    if code == number || code >= 100_000_000 {
+        return false;
+    }
+    let digits = format!("{:08}", code).into_bytes();
+    let repeated = digits.windows(2).all(|pair| pair[0] == pair[1]);
+    let sequence = digits
+        .windows(2)
+        .all(|pair| (pair[0] - b'0' + 1) % 10 == pair[1] - b'0');
+    !repeated && !sequence
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FixedClock;
+    use crate::models::tests::{setup_config, setup_identification, setup_local_date};
+
+    #[test]
+    fn test_defaults() {
+        let identification = IdentificationBuilder::new(&setup_config(), 12345, "Venda")
+            .build()
+            .unwrap();
+
+        assert_eq!(identification.model, Model::NFe);
+        assert_eq!(identification.series, 1);
+        assert_eq!(identification.environment, Environment::Homologation);
+        assert_eq!(identification.location.city.code, 3106200);
+        assert_eq!(
+            identification.printing_type,
+            Some(DanfeGeneration::NormalPortrait)
+        );
+        assert!(!identification.consumer);
+        assert_ne!(identification.numeric_code, 12345);
+        assert!(identification.numeric_code < 100_000_000);
+    }
+
+    #[test]
+    fn test_numeric_code() {
+        assert!(is_valid_numeric_code(48213907, 12345));
+        assert!(is_valid_numeric_code(12345, 1));
+        assert!(!is_valid_numeric_code(12345, 12345));
+        assert!(!is_valid_numeric_code(0, 1));
+        assert!(!is_valid_numeric_code(77777777, 1));
+        assert!(!is_valid_numeric_code(12345678, 1));
+        assert!(!is_valid_numeric_code(1234567, 1));
+        assert!(!is_valid_numeric_code(90123456, 1));
+        assert!(!is_valid_numeric_code(100_000_000, 1));
+
+        for _ in 0..100 {
+            let code = Identification::generate_numeric_code(12345);
+            assert!(is_valid_numeric_code(code, 12345));
+        }
+    }
+
+    #[test]
+    fn test_nfce() {
+        let config = setup_config()
+            .with_environment(Environment::Production)
+            .with_model(Model::NFCe);
+        let identification = IdentificationBuilder::new(&config, 12345, "Venda de mercadoria")
+            .set_numeric_code(48213907)
+            .set_emission_date(setup_local_date(14, 30))
+            .build()
+            .unwrap();
+
+        // Digit of the access key 31231012345678000195650010000123451482139077
+        assert_eq!(
+            identification,
+            Identification {
+                numeric_code: 48213907,
+                verifier_digit: 7,
+                ..setup_identification()
+            }
+        );
+    }
+
+    #[test]
+    fn test_invalid_codes() {
+        let builder = |number| IdentificationBuilder::new(&setup_config(), number, "Venda");
+        assert_eq!(
+            builder(0).build(),
+            Err(IdentificationError::InvalidNumber(0))
+        );
+        assert_eq!(
+            builder(MAX_NUMBER + 1).build(),
+            Err(IdentificationError::InvalidNumber(MAX_NUMBER + 1))
+        );
+        assert!(builder(MAX_NUMBER).build().is_ok());
+        assert_eq!(
+            builder(12345).set_series(MAX_SERIES + 1).build(),
+            Err(IdentificationError::InvalidSeries(MAX_SERIES + 1))
+        );
+        assert_eq!(
+            builder(12345)
+                .set_series(MAX_SERIES)
+                .build()
+                .unwrap()
+                .series,
+            MAX_SERIES
+        );
+        assert_eq!(
+            builder(12345).set_numeric_code(12345678).build(),
+            Err(IdentificationError::InvalidNumericCode(12345678))
+        );
+        assert_eq!(
+            builder(12345).set_numeric_code(12345).build(),
+            Err(IdentificationError::InvalidNumericCode(12345))
+        );
+        assert_eq!(
+            builder(12345).set_numeric_code(100_000_000).build(),
+            Err(IdentificationError::InvalidNumericCode(100_000_000))
+        );
+    }
+
+    #[test]
+    fn test_access_key_month_in_state_offset() {
+        let config = setup_config();
+        // 22:30 of the last day of October in Belo Horizonte
+        let emission_date = chrono::DateTime::parse_from_rfc3339("2023-11-01T01:30:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Local);
+        let identification = IdentificationBuilder::new(&config, 12345, "Venda")
+            .set_numeric_code(48213907)
+            .set_emission_date(emission_date)
+            .build()
+            .unwrap();
+        let access_key = AccessKey::new(&Components {
+            state: identification.location.state.clone(),
+            year: 23,
+            month: 10,
+            issuer: &config.issuer().document,
+            model: identification.model.clone(),
+            series: identification.series,
+            number: identification.number,
+            emission_type: identification.emission_type.clone(),
+            numeric_code: identification.numeric_code,
+        });
+        assert_eq!(identification.verifier_digit, access_key.verifier_digit());
+    }
+
+    #[test]
+    fn test_clock() {
+        let moment = chrono::DateTime::parse_from_rfc3339("2023-10-05T14:30:00-03:00").unwrap();
+        let config = setup_config()
+            .with_clock(FixedClock::new(moment))
+            .with_emission_version("PDV 5.2.1");
+        let build = || {
+            IdentificationBuilder::new(&config, 12345, "Venda de mercadoria")
+                .set_numeric_code(48213907)
+                .build()
+                .unwrap()
+        };
+        let identification = build();
+        assert_eq!(identification.emission_date, moment);
+        assert_eq!(identification.emission_version, "PDV 5.2.1");
+        assert_eq!(build(), identification);
+    }
+}
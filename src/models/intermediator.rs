@@ -0,0 +1,38 @@
+//! Intermediator of the operation (infIntermed)
+
+use crate::enums::CNPJ;
+use serde::{Deserialize, Serialize};
+
+/// Site or platform of a third party (marketplace) where the operation was
+/// made, informed when the intermediation of the identification is
+/// `IntermediatorIndicator::ThirdParty` (indIntermed = 1)
+///
+/// cnpj: CNPJ of the intermediator (CNPJ)
+/// id: Identifier of the issuer in the platform of the intermediator, 2 to 60 characters (idCadIntTran)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename = "infIntermed")]
+pub struct Intermediator {
+    #[serde(rename = "CNPJ")]
+    pub cnpj: CNPJ,
+    #[serde(rename = "idCadIntTran")]
+    pub id: String,
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::xml::{indent_xml, normalize_xml as canonicalize};
+    use nf_e_macros::serialization_test;
+    use quick_xml::{de::from_str as deserialize, se::to_string as serialize};
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/intermediator.xml",
+        indent = "indent_xml"
+    )]
+    pub fn setup_intermediator() -> Intermediator {
+        Intermediator {
+            cnpj: CNPJ("98765432000198".to_string()),
+            id: "LOJA-12345".to_string(),
+        }
+    }
+}
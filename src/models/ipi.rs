@@ -0,0 +1,243 @@
+//! IPI group of the industrialized products and II group of the imported ones
+//!
+//! The legal framing class (clEnq) was removed in the layout 4.00, so only the
+//! framing code (cEnq) is kept.
+
+use crate::decimal::{Money, Quantity};
+use crate::enums::{CNPJ, IPICST};
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize};
+
+/// IPI structure of an item
+///
+/// producer: CNPJ of the producer, when different from the issuer (CNPJProd) - Optional
+/// seal_code: Code of the IPI control seal (cSelo) - Optional
+/// seal_quantity: Quantity of control seals (qSelo) - Optional
+/// framing_code: Legal framing code of the IPI (cEnq)
+/// tax: Taxation of the item (IPITrib or IPINT)
+#[derive(Debug, PartialEq)]
+pub struct IPI {
+    pub producer: Option<CNPJ>,
+    pub seal_code: Option<String>,
+    pub seal_quantity: Option<u64>,
+    pub framing_code: String,
+    pub tax: IPITax,
+}
+
+/// Taxation of the IPI, either taxed or not taxed
+#[derive(Debug, PartialEq)]
+pub enum IPITax {
+    IPITrib(IPITrib),
+    IPINT(IPINT),
+}
+
+impl IPI {
+    /// IPI value of the item (vIPI), zero when not taxed
+    pub fn value(&self) -> Money {
+        match &self.tax {
+            IPITax::IPITrib(data) => data.value,
+            IPITax::IPINT(_) => Money::ZERO,
+        }
+    }
+}
+
+impl Serialize for IPI {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let len = 2
+            + self.producer.is_some() as usize
+            + self.seal_code.is_some() as usize
+            + self.seal_quantity.is_some() as usize;
+
+        let mut state = serializer.serialize_struct("IPI", len)?;
+        if let Some(producer) = &self.producer {
+            state.serialize_field("CNPJProd", producer)?;
+        }
+        if let Some(seal_code) = &self.seal_code {
+            state.serialize_field("cSelo", seal_code)?;
+        }
+        if let Some(seal_quantity) = &self.seal_quantity {
+            state.serialize_field("qSelo", seal_quantity)?;
+        }
+        state.serialize_field("cEnq", &self.framing_code)?;
+        match &self.tax {
+            IPITax::IPITrib(data) => state.serialize_field("IPITrib", data)?,
+            IPITax::IPINT(data) => state.serialize_field("IPINT", data)?,
+        }
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for IPI {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[allow(non_snake_case)]
+        struct IPIHelper {
+            #[serde(rename = "CNPJProd")]
+            producer: Option<CNPJ>,
+            #[serde(rename = "cSelo")]
+            seal_code: Option<String>,
+            #[serde(rename = "qSelo")]
+            seal_quantity: Option<u64>,
+            #[serde(rename = "cEnq")]
+            framing_code: String,
+            IPITrib: Option<IPITrib>,
+            IPINT: Option<IPINT>,
+        }
+
+        let helper = IPIHelper::deserialize(deserializer)?;
+        let tax = match (helper.IPITrib, helper.IPINT) {
+            (Some(data), None) => IPITax::IPITrib(data),
+            (None, Some(data)) => IPITax::IPINT(data),
+            _ => {
+                return Err(serde::de::Error::custom(
+                    "IPI must have exactly one of IPITrib or IPINT",
+                ));
+            }
+        };
+
+        Ok(IPI {
+            producer: helper.producer,
+            seal_code: helper.seal_code,
+            seal_quantity: helper.seal_quantity,
+            framing_code: helper.framing_code,
+            tax,
+        })
+    }
+}
+
+/// IPI structure for CST 00, 49, 50 and 99 (taxed), either by percentage or by quantity
+///
+/// cst: Tax situation code (CST)
+/// base: Calculation base (vBC) - Optional
+/// rate: IPI rate (pIPI) - Optional
+/// quantity: Quantity in the standard unit (qUnid) - Optional
+/// unit_value: Value per standard unit (vUnid) - Optional
+/// value: IPI value (vIPI)
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct IPITrib {
+    #[serde(rename = "CST")]
+    pub cst: IPICST,
+    #[serde(rename = "vBC", skip_serializing_if = "Option::is_none")]
+    pub base: Option<Money>,
+    #[serde(rename = "pIPI", skip_serializing_if = "Option::is_none")]
+    pub rate: Option<Money>,
+    #[serde(rename = "qUnid", skip_serializing_if = "Option::is_none")]
+    pub quantity: Option<Quantity>,
+    #[serde(rename = "vUnid", skip_serializing_if = "Option::is_none")]
+    pub unit_value: Option<Quantity>,
+    #[serde(rename = "vIPI")]
+    pub value: Money,
+}
+
+/// IPI structure for CST 01 to 05 and 51 to 55 (not taxed)
+///
+/// cst: Tax situation code (CST)
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct IPINT {
+    #[serde(rename = "CST")]
+    pub cst: IPICST,
+}
+
+/// II structure of an imported item
+///
+/// base: Calculation base (vBC)
+/// customs_expenses: Customs expenses (vDespAdu)
+/// value: II value (vII)
+/// iof_value: IOF value (vIOF)
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct II {
+    #[serde(rename = "vBC")]
+    pub base: Money,
+    #[serde(rename = "vDespAdu")]
+    pub customs_expenses: Money,
+    #[serde(rename = "vII")]
+    pub value: Money,
+    #[serde(rename = "vIOF")]
+    pub iof_value: Money,
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::xml::{indent_xml, normalize_xml as canonicalize};
+    use nf_e_macros::serialization_test;
+    use quick_xml::{de::from_str as deserialize, se::to_string as serialize};
+    use rust_decimal_macros::dec;
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/ipi/ipi_trib.xml",
+        indent = "indent_xml"
+    )]
+    pub fn setup_ipi_trib() -> IPI {
+        IPI {
+            producer: None,
+            seal_code: None,
+            seal_quantity: None,
+            framing_code: "999".to_string(),
+            tax: IPITax::IPITrib(IPITrib {
+                cst: IPICST::TaxedExit,
+                base: Some(Money::from(dec!(100.00))),
+                rate: Some(Money::from(dec!(10.00))),
+                quantity: None,
+                unit_value: None,
+                value: Money::from(dec!(10.00)),
+            }),
+        }
+    }
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/ipi/ipi_unit.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_ipi_unit() -> IPI {
+        IPI {
+            producer: Some(CNPJ("12345678000195".to_string())),
+            seal_code: Some("9710-01".to_string()),
+            seal_quantity: Some(12),
+            framing_code: "999".to_string(),
+            tax: IPITax::IPITrib(IPITrib {
+                cst: IPICST::TaxedExit,
+                base: None,
+                rate: None,
+                quantity: Some(Quantity::from(dec!(12))),
+                unit_value: Some(Quantity::from(dec!(0.45))),
+                value: Money::from(dec!(5.40)),
+            }),
+        }
+    }
+
+    #[serialization_test(fixture = "../../tests/fixtures/ipi/ipi_nt.xml", indent = "indent_xml")]
+    fn setup_ipi_nt() -> IPI {
+        IPI {
+            producer: None,
+            seal_code: None,
+            seal_quantity: None,
+            framing_code: "301".to_string(),
+            tax: IPITax::IPINT(IPINT {
+                cst: IPICST::ImmuneExit,
+            }),
+        }
+    }
+
+    #[serialization_test(fixture = "../../tests/fixtures/ipi/ii.xml", indent = "indent_xml")]
+    pub fn setup_ii() -> II {
+        II {
+            base: Money::from(dec!(1000.00)),
+            customs_expenses: Money::from(dec!(150.00)),
+            value: Money::from(dec!(140.00)),
+            iof_value: Money::from(dec!(3.80)),
+        }
+    }
+
+    #[test]
+    fn test_ipi_value() {
+        assert_eq!(setup_ipi_trib().value(), Money::from(dec!(10.00)));
+        assert_eq!(setup_ipi_nt().value(), Money::ZERO);
+    }
+}
@@ -0,0 +1,159 @@
+//! ISSQN group of the service items and its totals, for the documents mixing
+//! goods and services (NF-e conjugada)
+
+use crate::decimal::Money;
+use crate::enums::{ISSIndicator, SpecialTaxRegime, TaxIncentive};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// ISSQN structure of a service item
+///
+/// base: Calculation base (vBC)
+/// rate: ISSQN rate (vAliq)
+/// value: ISSQN value (vISSQN)
+/// generator_city: IBGE code of the city of the taxable event (cMunFG)
+/// service_item: Item of the service list of the LC 116/2003, as "NN.NN" (cListServ)
+/// deduction: Deduction of the calculation base (vDeducao) - Optional
+/// other: Other retentions (vOutro) - Optional
+/// unconditional_discount: Unconditional discount (vDescIncond) - Optional
+/// conditional_discount: Conditional discount (vDescCond) - Optional
+/// retained_value: Retained ISS value (vISSRet) - Optional
+/// indicator: Enforceability of the ISS (indISS)
+/// service_code: Municipal code of the service (cServico) - Optional
+/// incidence_city: IBGE code of the city of incidence (cMun) - Optional
+/// country: Code of the country where the service is provided (cPais) - Optional
+/// process_number: Number of the process suspending the enforceability (nProcesso) - Optional
+/// incentive: Tax incentive (indIncentivo)
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ISSQN {
+    #[serde(rename = "vBC")]
+    pub base: Money,
+    #[serde(rename = "vAliq")]
+    pub rate: Money,
+    #[serde(rename = "vISSQN")]
+    pub value: Money,
+    #[serde(rename = "cMunFG")]
+    pub generator_city: u32,
+    #[serde(rename = "cListServ")]
+    pub service_item: String,
+    #[serde(rename = "vDeducao", skip_serializing_if = "Option::is_none")]
+    pub deduction: Option<Money>,
+    #[serde(rename = "vOutro", skip_serializing_if = "Option::is_none")]
+    pub other: Option<Money>,
+    #[serde(rename = "vDescIncond", skip_serializing_if = "Option::is_none")]
+    pub unconditional_discount: Option<Money>,
+    #[serde(rename = "vDescCond", skip_serializing_if = "Option::is_none")]
+    pub conditional_discount: Option<Money>,
+    #[serde(rename = "vISSRet", skip_serializing_if = "Option::is_none")]
+    pub retained_value: Option<Money>,
+    #[serde(rename = "indISS")]
+    pub indicator: ISSIndicator,
+    #[serde(rename = "cServico", skip_serializing_if = "Option::is_none")]
+    pub service_code: Option<String>,
+    #[serde(rename = "cMun", skip_serializing_if = "Option::is_none")]
+    pub incidence_city: Option<u32>,
+    #[serde(rename = "cPais", skip_serializing_if = "Option::is_none")]
+    pub country: Option<u32>,
+    #[serde(rename = "nProcesso", skip_serializing_if = "Option::is_none")]
+    pub process_number: Option<String>,
+    #[serde(rename = "indIncentivo")]
+    pub incentive: TaxIncentive,
+}
+
+/// Totals of the service items (ISSQNtot)
+///
+/// services: Total value of the services (vServ) - Optional
+/// base: ISSQN calculation base (vBC) - Optional
+/// value: ISSQN value (vISS) - Optional
+/// pis_value: PIS value of the services (vPIS) - Optional
+/// cofins_value: COFINS value of the services (vCOFINS) - Optional
+/// competence: Date of the provision of the services (dCompet)
+/// deduction: Deduction of the calculation base (vDeducao) - Optional
+/// other: Other retentions (vOutro) - Optional
+/// unconditional_discount: Unconditional discount (vDescIncond) - Optional
+/// conditional_discount: Conditional discount (vDescCond) - Optional
+/// retained_value: Retained ISS value (vISSRet) - Optional
+/// special_regime: Special tax regime (cRegTrib) - Optional
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename = "ISSQNtot")]
+pub struct ISSQNTotal {
+    #[serde(rename = "vServ", skip_serializing_if = "Option::is_none")]
+    pub services: Option<Money>,
+    #[serde(rename = "vBC", skip_serializing_if = "Option::is_none")]
+    pub base: Option<Money>,
+    #[serde(rename = "vISS", skip_serializing_if = "Option::is_none")]
+    pub value: Option<Money>,
+    #[serde(rename = "vPIS", skip_serializing_if = "Option::is_none")]
+    pub pis_value: Option<Money>,
+    #[serde(rename = "vCOFINS", skip_serializing_if = "Option::is_none")]
+    pub cofins_value: Option<Money>,
+    #[serde(rename = "dCompet")]
+    pub competence: NaiveDate,
+    #[serde(rename = "vDeducao", skip_serializing_if = "Option::is_none")]
+    pub deduction: Option<Money>,
+    #[serde(rename = "vOutro", skip_serializing_if = "Option::is_none")]
+    pub other: Option<Money>,
+    #[serde(rename = "vDescIncond", skip_serializing_if = "Option::is_none")]
+    pub unconditional_discount: Option<Money>,
+    #[serde(rename = "vDescCond", skip_serializing_if = "Option::is_none")]
+    pub conditional_discount: Option<Money>,
+    #[serde(rename = "vISSRet", skip_serializing_if = "Option::is_none")]
+    pub retained_value: Option<Money>,
+    #[serde(rename = "cRegTrib", skip_serializing_if = "Option::is_none")]
+    pub special_regime: Option<SpecialTaxRegime>,
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::xml::{indent_xml, normalize_xml as canonicalize};
+    use nf_e_macros::serialization_test;
+    use quick_xml::{de::from_str as deserialize, se::to_string as serialize};
+    use rust_decimal_macros::dec;
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/issqn/issqn.xml",
+        indent = "indent_xml"
+    )]
+    pub fn setup_issqn() -> ISSQN {
+        ISSQN {
+            base: Money::from(dec!(100.00)),
+            rate: Money::from(dec!(5.00)),
+            value: Money::from(dec!(5.00)),
+            generator_city: 3106200,
+            service_item: "14.01".to_string(),
+            deduction: None,
+            other: None,
+            unconditional_discount: None,
+            conditional_discount: None,
+            retained_value: Some(Money::from(dec!(2.00))),
+            indicator: ISSIndicator::Enforceable,
+            service_code: None,
+            incidence_city: Some(3106200),
+            country: None,
+            process_number: None,
+            incentive: TaxIncentive::No,
+        }
+    }
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/issqn/issqn_total.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_issqn_total() -> ISSQNTotal {
+        ISSQNTotal {
+            services: Some(Money::from(dec!(100.00))),
+            base: Some(Money::from(dec!(100.00))),
+            value: Some(Money::from(dec!(5.00))),
+            pis_value: None,
+            cofins_value: None,
+            competence: NaiveDate::from_ymd_opt(2023, 10, 5).unwrap(),
+            deduction: None,
+            other: None,
+            unconditional_discount: None,
+            conditional_discount: None,
+            retained_value: Some(Money::from(dec!(2.00))),
+            special_regime: Some(SpecialTaxRegime::MicroenterpriseOrSmallBusiness),
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! Withdrawal (retirada) and delivery (entrega) locations, when the goods are
+//! picked up or delivered at an address other than the issuer's or the
+//! recipient's
+
+use super::Address;
+use crate::enums::{IE, PersonDocument};
+use crate::states::{City, State};
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize};
+
+/// Location of withdrawal or delivery of the goods (retirada, entrega)
+///
+/// document: Document of the holder of the location (CNPJ or CPF)
+/// name: Legal name of the holder (xNome) - Optional
+/// address: Address of the location (xLgr, nro, xCpl, xBairro, cMun, xMun, UF, CEP, fone) -
+/// the ZIP code and the telephone are omitted when empty
+/// email: E-mail address of the holder (email) - Optional
+/// ie: State registration of the holder (IE) - Optional
+#[derive(Debug, PartialEq, Clone)]
+pub struct ThirdPartyLocation {
+    pub document: PersonDocument,
+    pub name: Option<String>,
+    pub address: Address,
+    pub email: Option<String>,
+    pub ie: Option<IE>,
+}
+
+impl Serialize for ThirdPartyLocation {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let address = &self.address;
+        let len = 10
+            + !address.zip_code.is_empty() as usize
+            + !address.telephone.is_empty() as usize
+            + self.name.is_some() as usize
+            + address.line_2.is_some() as usize
+            + self.email.is_some() as usize
+            + self.ie.is_some() as usize;
+
+        let mut state = serializer.serialize_struct("local", len)?;
+        state.serialize_field("$value", &self.document)?;
+        if let Some(name) = &self.name {
+            state.serialize_field("xNome", name)?;
+        }
+        state.serialize_field("xLgr", &address.line_1)?;
+        state.serialize_field("nro", &address.number)?;
+        if let Some(line_2) = &address.line_2 {
+            state.serialize_field("xCpl", line_2)?;
+        }
+        state.serialize_field("xBairro", &address.neighborhood)?;
+        state.serialize_field("cMun", &address.city.code)?;
+        state.serialize_field("xMun", &address.city.name)?;
+        state.serialize_field("UF", address.state.acronym())?;
+        if !address.zip_code.is_empty() {
+            state.serialize_field("CEP", &address.zip_code)?;
+        }
+        state.serialize_field("cPais", &1058)?;
+        state.serialize_field("xPais", &"Brasil".to_string())?;
+        if !address.telephone.is_empty() {
+            state.serialize_field("fone", &address.telephone)?;
+        }
+        if let Some(email) = &self.email {
+            state.serialize_field("email", email)?;
+        }
+        if let Some(ie) = &self.ie {
+            state.serialize_field("IE", &ie.0)?;
+        }
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for ThirdPartyLocation {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct ThirdPartyLocationHelper {
+            #[serde(rename = "$value")]
+            document: PersonDocument,
+            #[serde(rename = "xNome")]
+            x_nome: Option<String>,
+            #[serde(rename = "xLgr")]
+            x_lgr: String,
+            #[serde(rename = "nro")]
+            nro: String,
+            #[serde(rename = "xCpl")]
+            x_cpl: Option<String>,
+            #[serde(rename = "xBairro")]
+            x_bairro: String,
+            #[serde(rename = "cMun")]
+            c_mun: u32,
+            #[serde(rename = "xMun")]
+            x_mun: String,
+            #[serde(rename = "UF")]
+            uf: String,
+            #[serde(rename = "CEP")]
+            cep: Option<String>,
+            // Only Brazil is supported, but the fields must be consumed so
+            // they are not taken as the document
+            #[serde(rename = "cPais")]
+            _c_pais: Option<u32>,
+            #[serde(rename = "xPais")]
+            _x_pais: Option<String>,
+            #[serde(rename = "fone")]
+            fone: Option<String>,
+            #[serde(rename = "email")]
+            email: Option<String>,
+            #[serde(rename = "IE")]
+            ie: Option<String>,
+        }
+
+        let helper = ThirdPartyLocationHelper::deserialize(deserializer)?;
+        let state = State::from_acronym(&helper.uf).ok_or_else(|| {
+            serde::de::Error::custom(format!("Invalid state acronym: {}", helper.uf))
+        })?;
+
+        Ok(ThirdPartyLocation {
+            document: helper.document,
+            name: helper.x_nome,
+            address: Address {
+                line_1: helper.x_lgr,
+                line_2: helper.x_cpl,
+                number: helper.nro,
+                neighborhood: helper.x_bairro,
+                city: City {
+                    code: helper.c_mun,
+                    name: helper.x_mun,
+                },
+                state,
+                zip_code: helper.cep.unwrap_or_default(),
+                telephone: helper.fone.unwrap_or_default(),
+            },
+            email: helper.email,
+            ie: helper.ie.map(IE),
+        })
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::enums::CNPJ;
+    use crate::models::tests::setup_address;
+    use crate::xml::{indent_xml, normalize_xml as canonicalize};
+    use nf_e_macros::serialization_test;
+    use quick_xml::{de::from_str as deserialize, se::to_string as serialize};
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/location/entrega.xml",
+        indent = "indent_xml"
+    )]
+    pub fn setup_delivery() -> ThirdPartyLocation {
+        ThirdPartyLocation {
+            document: PersonDocument::CNPJ(CNPJ("11222333000181".to_string())),
+            name: Some("Deposito Exemplo LTDA".to_string()),
+            address: setup_address(),
+            email: Some("deposito@exemplo.com.br".to_string()),
+            ie: Some(IE("0623079040081".to_string())),
+        }
+    }
+}
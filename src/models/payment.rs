@@ -0,0 +1,133 @@
+//! Payment group of the document (pag)
+
+use crate::decimal::Money;
+use crate::enums::{CNPJ, CardBrand, CardIntegration, PaymentIndicator, PaymentType};
+use serde::{Deserialize, Serialize};
+
+/// Payment information based on the XML structure of the NFe
+///
+/// payments: Payments of the document, up to 100 (detPag)
+/// change: Change given back to the consumer (vTroco) - Optional, only with cash
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(rename = "pag")]
+pub struct Payments {
+    #[serde(rename = "detPag")]
+    pub payments: Vec<Payment>,
+    #[serde(rename = "vTroco", skip_serializing_if = "Option::is_none")]
+    pub change: Option<Money>,
+}
+
+impl Payments {
+    /// Sum of the values of the payments
+    pub fn paid(&self) -> Money {
+        self.payments.iter().map(|payment| payment.value).sum()
+    }
+
+    /// Value kept by the issuer: the payments without the change
+    pub fn net(&self) -> Money {
+        self.paid() - self.change.unwrap_or_default()
+    }
+}
+
+/// Payment detail (detPag)
+///
+/// indicator: Form of the payment, cash or installments (indPag) - Optional
+/// type: Means of payment (tPag)
+/// description: Description of the means of payment, 2 to 60 characters (xPag) - Optional, required
+/// for `PaymentType::Other`
+/// value: Value paid (vPag)
+/// card: Card of the payment (card) - Optional
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(rename = "detPag")]
+pub struct Payment {
+    #[serde(rename = "indPag", skip_serializing_if = "Option::is_none")]
+    pub indicator: Option<PaymentIndicator>,
+    #[serde(rename = "tPag")]
+    pub r#type: PaymentType,
+    #[serde(rename = "xPag", skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(rename = "vPag")]
+    pub value: Money,
+    #[serde(rename = "card", skip_serializing_if = "Option::is_none")]
+    pub card: Option<Card>,
+}
+
+/// Card of the payment (card)
+///
+/// integration: Integration with the automation system (tpIntegra)
+/// acquirer: CNPJ of the card acquirer (CNPJ) - Optional
+/// brand: Brand of the card (tBand) - Optional
+/// authorization: Authorization code of the transaction (cAut) - Optional
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(rename = "card")]
+pub struct Card {
+    #[serde(rename = "tpIntegra")]
+    pub integration: CardIntegration,
+    #[serde(rename = "CNPJ", skip_serializing_if = "Option::is_none")]
+    pub acquirer: Option<CNPJ>,
+    #[serde(rename = "tBand", skip_serializing_if = "Option::is_none")]
+    pub brand: Option<CardBrand>,
+    #[serde(rename = "cAut", skip_serializing_if = "Option::is_none")]
+    pub authorization: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xml::{indent_xml, normalize_xml as canonicalize};
+    use nf_e_macros::serialization_test;
+    use quick_xml::{de::from_str as deserialize, se::to_string as serialize};
+    use rust_decimal_macros::dec;
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/payment/payments.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_payments_with_card() -> Payments {
+        Payments {
+            payments: vec![
+                Payment {
+                    indicator: Some(PaymentIndicator::Cash),
+                    r#type: PaymentType::Cash,
+                    description: None,
+                    value: Money::from(dec!(50.00)),
+                    card: None,
+                },
+                Payment {
+                    indicator: None,
+                    r#type: PaymentType::CreditCard,
+                    description: None,
+                    value: Money::from(dec!(73.94)),
+                    card: Some(Card {
+                        integration: CardIntegration::Integrated,
+                        acquirer: Some(CNPJ("11222333000181".to_string())),
+                        brand: Some(CardBrand::Mastercard),
+                        authorization: Some("A1B2C3".to_string()),
+                    }),
+                },
+            ],
+            change: Some(Money::from(dec!(10.00))),
+        }
+    }
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/payment/payment_other.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_payment_other() -> Payment {
+        Payment {
+            indicator: Some(PaymentIndicator::Installments),
+            r#type: PaymentType::Other,
+            description: Some("Permuta de mercadorias".to_string()),
+            value: Money::from(dec!(123.94)),
+            card: None,
+        }
+    }
+
+    #[test]
+    fn test_net() {
+        let payments = setup_payments_with_card();
+        assert_eq!(payments.paid(), Money::from(dec!(123.94)));
+        assert_eq!(payments.net(), Money::from(dec!(113.94)));
+    }
+}
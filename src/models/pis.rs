@@ -0,0 +1,172 @@
+//! PIS groups, one per kind of taxation
+
+use crate::decimal::{Money, Quantity};
+use crate::enums::ContributionCST;
+use serde::{Deserialize, Serialize};
+
+/// PIS structure for CST 01 and 02 (taxed by percentage)
+///
+/// cst: Tax situation code (CST)
+/// base: Calculation base (vBC)
+/// rate: PIS rate (pPIS)
+/// value: PIS value (vPIS)
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct PISAliq {
+    #[serde(rename = "CST")]
+    pub cst: ContributionCST,
+    #[serde(rename = "vBC")]
+    pub base: Money,
+    #[serde(rename = "pPIS")]
+    pub rate: Money,
+    #[serde(rename = "vPIS")]
+    pub value: Money,
+}
+
+/// PIS structure for CST 03 (taxed by quantity)
+///
+/// cst: Tax situation code (CST)
+/// quantity: Quantity sold, base of the unit rate (qBCProd)
+/// unit_rate: Rate per unit (vAliqProd)
+/// value: PIS value (vPIS)
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct PISQtde {
+    #[serde(rename = "CST")]
+    pub cst: ContributionCST,
+    #[serde(rename = "qBCProd")]
+    pub quantity: Quantity,
+    #[serde(rename = "vAliqProd")]
+    pub unit_rate: Quantity,
+    #[serde(rename = "vPIS")]
+    pub value: Money,
+}
+
+/// PIS structure for CST 04 to 09 (not taxed)
+///
+/// cst: Tax situation code (CST)
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct PISNT {
+    #[serde(rename = "CST")]
+    pub cst: ContributionCST,
+}
+
+/// PIS structure for CST 49 to 99 (others), taxed either by percentage or by quantity
+///
+/// cst: Tax situation code (CST)
+/// base: Calculation base (vBC) - Optional
+/// rate: PIS rate (pPIS) - Optional
+/// quantity: Quantity sold, base of the unit rate (qBCProd) - Optional
+/// unit_rate: Rate per unit (vAliqProd) - Optional
+/// value: PIS value (vPIS)
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct PISOutr {
+    #[serde(rename = "CST")]
+    pub cst: ContributionCST,
+    #[serde(rename = "vBC", skip_serializing_if = "Option::is_none")]
+    pub base: Option<Money>,
+    #[serde(rename = "pPIS", skip_serializing_if = "Option::is_none")]
+    pub rate: Option<Money>,
+    #[serde(rename = "qBCProd", skip_serializing_if = "Option::is_none")]
+    pub quantity: Option<Quantity>,
+    #[serde(rename = "vAliqProd", skip_serializing_if = "Option::is_none")]
+    pub unit_rate: Option<Quantity>,
+    #[serde(rename = "vPIS")]
+    pub value: Money,
+}
+
+/// PIS ST structure, taxed either by percentage or by quantity
+///
+/// base: Calculation base (vBC) - Optional
+/// rate: PIS rate (pPIS) - Optional
+/// quantity: Quantity sold, base of the unit rate (qBCProd) - Optional
+/// unit_rate: Rate per unit (vAliqProd) - Optional
+/// value: PIS value (vPIS)
+/// included: Whether the value is included in the total of the NFe (indSomaPISST) - Optional
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct PISST {
+    #[serde(rename = "vBC", skip_serializing_if = "Option::is_none")]
+    pub base: Option<Money>,
+    #[serde(rename = "pPIS", skip_serializing_if = "Option::is_none")]
+    pub rate: Option<Money>,
+    #[serde(rename = "qBCProd", skip_serializing_if = "Option::is_none")]
+    pub quantity: Option<Quantity>,
+    #[serde(rename = "vAliqProd", skip_serializing_if = "Option::is_none")]
+    pub unit_rate: Option<Quantity>,
+    #[serde(rename = "vPIS")]
+    pub value: Money,
+    #[serde(
+        rename = "indSomaPISST",
+        default,
+        with = "crate::utils::optional_flag",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub included: Option<bool>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xml::{indent_xml, normalize_xml as canonicalize};
+    use nf_e_macros::serialization_test;
+    use quick_xml::{de::from_str as deserialize, se::to_string as serialize};
+    use rust_decimal_macros::dec;
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/pis/pis_aliq.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_pis_aliq() -> PISAliq {
+        PISAliq {
+            cst: ContributionCST::TaxableBasicRate,
+            base: Money::from(dec!(100.0)),
+            rate: Money::from(dec!(1.65)),
+            value: Money::from(dec!(1.65)),
+        }
+    }
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/pis/pis_qtde.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_pis_qtde() -> PISQtde {
+        PISQtde {
+            cst: ContributionCST::TaxableUnitRate,
+            quantity: Quantity::from(dec!(10.0)),
+            unit_rate: Quantity::from(dec!(0.5)),
+            value: Money::from(dec!(5.0)),
+        }
+    }
+
+    #[serialization_test(fixture = "../../tests/fixtures/pis/pis_nt.xml", indent = "indent_xml")]
+    fn setup_pis_nt() -> PISNT {
+        PISNT {
+            cst: ContributionCST::Exempt,
+        }
+    }
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/pis/pis_outr.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_pis_outr() -> PISOutr {
+        PISOutr {
+            cst: ContributionCST::Others,
+            base: Some(Money::from(dec!(100.0))),
+            rate: Some(Money::from(dec!(0.65))),
+            quantity: None,
+            unit_rate: None,
+            value: Money::from(dec!(0.65)),
+        }
+    }
+
+    #[serialization_test(fixture = "../../tests/fixtures/pis/pis_st.xml", indent = "indent_xml")]
+    fn setup_pis_st() -> PISST {
+        PISST {
+            base: None,
+            rate: None,
+            quantity: Some(Quantity::from(dec!(10.0))),
+            unit_rate: Some(Quantity::from(dec!(0.5))),
+            value: Money::from(dec!(5.0)),
+            included: Some(true),
+        }
+    }
+}
@@ -0,0 +1,333 @@
+//! Product-specific groups of the items (rastro, veicProd, med, comb)
+
+use crate::decimal::{Money, Quantity};
+use crate::enums::{
+    DenatranColor, VINCondition, VehicleCondition, VehicleFuel, VehicleOperation,
+    VehicleRestriction, VehicleSpecies, VehicleType,
+};
+use crate::states::State;
+use chrono::NaiveDate;
+use nf_e_macros::nfe_decimal;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// Traceability of a batch of the product, up to 500 per item (rastro)
+///
+/// batch: Number of the batch (nLote)
+/// quantity: Quantity of the product in the batch (qLote)
+/// manufacture_date: Date of manufacture or production (dFab)
+/// expiration_date: Expiration date (dVal)
+/// aggregation_code: Code of aggregation (cAgreg) - Optional
+#[nfe_decimal]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename = "rastro")]
+pub struct Traceability {
+    #[serde(rename = "nLote")]
+    pub batch: String,
+    #[nfe_decimal(places = 3)]
+    #[serde(rename = "qLote")]
+    pub quantity: Decimal,
+    #[serde(rename = "dFab")]
+    pub manufacture_date: NaiveDate,
+    #[serde(rename = "dVal")]
+    pub expiration_date: NaiveDate,
+    #[serde(rename = "cAgreg", skip_serializing_if = "Option::is_none")]
+    pub aggregation_code: Option<String>,
+}
+
+/// New vehicle details (veicProd)
+///
+/// operation: Kind of the sale (tpOp)
+/// chassis: Chassis number (chassi)
+/// color_code: Color code of the manufacturer (cCor)
+/// color: Color description (xCor)
+/// power: Power of the engine, in hp (pot)
+/// displacement: Displacement of the engine, in cc (cilin)
+/// net_weight: Net weight (pesoL)
+/// gross_weight: Gross weight (pesoB)
+/// serial: Serial number (nSerie)
+/// fuel: Fuel (tpComb)
+/// engine: Engine number (nMotor)
+/// max_traction: Maximum traction capacity, in tons (CMT)
+/// wheelbase: Distance between the axles (dist)
+/// model_year: Model year (anoMod)
+/// manufacture_year: Manufacture year (anoFab)
+/// paint: Type of the paint (tpPint)
+/// type: Type of the vehicle (tpVeic)
+/// species: Species of the vehicle (espVeic)
+/// vin: Condition of the VIN (VIN)
+/// condition: Condition of the vehicle (condVeic)
+/// model_code: Brand and model code of the RENAVAM (cMod)
+/// denatran_color: Color of the DENATRAN table (cCorDENATRAN)
+/// capacity: Maximum capacity of passengers, seated (lota)
+/// restriction: Restriction on the vehicle (tpRest)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename = "veicProd")]
+pub struct NewVehicle {
+    #[serde(rename = "tpOp")]
+    pub operation: VehicleOperation,
+    #[serde(rename = "chassi")]
+    pub chassis: String,
+    #[serde(rename = "cCor")]
+    pub color_code: String,
+    #[serde(rename = "xCor")]
+    pub color: String,
+    #[serde(rename = "pot")]
+    pub power: String,
+    #[serde(rename = "cilin")]
+    pub displacement: String,
+    #[serde(rename = "pesoL")]
+    pub net_weight: String,
+    #[serde(rename = "pesoB")]
+    pub gross_weight: String,
+    #[serde(rename = "nSerie")]
+    pub serial: String,
+    #[serde(rename = "tpComb")]
+    pub fuel: VehicleFuel,
+    #[serde(rename = "nMotor")]
+    pub engine: String,
+    #[serde(rename = "CMT")]
+    pub max_traction: String,
+    #[serde(rename = "dist")]
+    pub wheelbase: String,
+    #[serde(rename = "anoMod")]
+    pub model_year: u16,
+    #[serde(rename = "anoFab")]
+    pub manufacture_year: u16,
+    #[serde(rename = "tpPint")]
+    pub paint: String,
+    #[serde(rename = "tpVeic")]
+    pub r#type: VehicleType,
+    #[serde(rename = "espVeic")]
+    pub species: VehicleSpecies,
+    #[serde(rename = "VIN")]
+    pub vin: VINCondition,
+    #[serde(rename = "condVeic")]
+    pub condition: VehicleCondition,
+    #[serde(rename = "cMod")]
+    pub model_code: String,
+    #[serde(rename = "cCorDENATRAN")]
+    pub denatran_color: DenatranColor,
+    #[serde(rename = "lota")]
+    pub capacity: u16,
+    #[serde(rename = "tpRest")]
+    pub restriction: VehicleRestriction,
+}
+
+/// Medicine and pharmaceutical raw material details (med)
+///
+/// anvisa_code: ANVISA registration code, or "ISENTO" when exempt (cProdANVISA)
+/// exemption_reason: Reason of the exemption of the registration (xMotivoIsencao) - Optional
+/// max_consumer_price: Maximum consumer price (vPMC)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename = "med")]
+pub struct Medicine {
+    #[serde(rename = "cProdANVISA")]
+    pub anvisa_code: String,
+    #[serde(rename = "xMotivoIsencao", skip_serializing_if = "Option::is_none")]
+    pub exemption_reason: Option<String>,
+    #[serde(rename = "vPMC")]
+    pub max_consumer_price: Money,
+}
+
+/// Fuel details, required for the products with an ANP code (comb)
+///
+/// anp_code: Product code of the ANP (cProdANP)
+/// anp_description: Product description of the ANP (descANP)
+/// lpg_rate: Percentage of LPG derived from petroleum (pGLP) - Optional
+/// national_gas_rate: Percentage of national natural gas (pGNn) - Optional
+/// imported_gas_rate: Percentage of imported natural gas (pGNi) - Optional
+/// starting_value: Starting value per unit (vPart) - Optional
+/// codif: Authorization code of the CODIF system (CODIF) - Optional
+/// temperature_quantity: Quantity billed at the ambient temperature (qTemp) - Optional
+/// consumption_state: State of consumption (UFCons)
+/// cide: CIDE of the fuel (CIDE) - Optional
+/// totalizer: Readings of the pump totalizer (encerrante) - Optional
+#[nfe_decimal]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename = "comb")]
+pub struct Fuel {
+    #[serde(rename = "cProdANP")]
+    pub anp_code: u32,
+    #[serde(rename = "descANP")]
+    pub anp_description: String,
+    #[nfe_decimal(places = 4)]
+    #[serde(rename = "pGLP")]
+    pub lpg_rate: Option<Decimal>,
+    #[nfe_decimal(places = 4)]
+    #[serde(rename = "pGNn")]
+    pub national_gas_rate: Option<Decimal>,
+    #[nfe_decimal(places = 4)]
+    #[serde(rename = "pGNi")]
+    pub imported_gas_rate: Option<Decimal>,
+    #[serde(rename = "vPart", skip_serializing_if = "Option::is_none")]
+    pub starting_value: Option<Money>,
+    #[serde(rename = "CODIF", skip_serializing_if = "Option::is_none")]
+    pub codif: Option<String>,
+    #[nfe_decimal(places = 4)]
+    #[serde(rename = "qTemp")]
+    pub temperature_quantity: Option<Decimal>,
+    #[serde(rename = "UFCons", with = "crate::states::acronym")]
+    pub consumption_state: State,
+    #[serde(rename = "CIDE", skip_serializing_if = "Option::is_none")]
+    pub cide: Option<CIDE>,
+    #[serde(rename = "encerrante", skip_serializing_if = "Option::is_none")]
+    pub totalizer: Option<PumpTotalizer>,
+}
+
+/// CIDE of the fuel (CIDE)
+///
+/// quantity: Quantity, base of the unit rate (qBCProd)
+/// unit_rate: Rate per unit (vAliqProd)
+/// value: CIDE value (vCIDE)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct CIDE {
+    #[serde(rename = "qBCProd")]
+    pub quantity: Quantity,
+    #[serde(rename = "vAliqProd")]
+    pub unit_rate: Quantity,
+    #[serde(rename = "vCIDE")]
+    pub value: Money,
+}
+
+/// Readings of the pump totalizer at the supply (encerrante)
+///
+/// nozzle: Number of the nozzle (nBico)
+/// pump: Number of the pump (nBomba) - Optional
+/// tank: Number of the tank (nTanque)
+/// initial_reading: Reading at the start of the supply (vEncIni)
+/// final_reading: Reading at the end of the supply (vEncFin)
+#[nfe_decimal]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct PumpTotalizer {
+    #[serde(rename = "nBico")]
+    pub nozzle: u16,
+    #[serde(rename = "nBomba", skip_serializing_if = "Option::is_none")]
+    pub pump: Option<u16>,
+    #[serde(rename = "nTanque")]
+    pub tank: u16,
+    #[nfe_decimal(places = 3)]
+    #[serde(rename = "vEncIni")]
+    pub initial_reading: Decimal,
+    #[nfe_decimal(places = 3)]
+    #[serde(rename = "vEncFin")]
+    pub final_reading: Decimal,
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::xml::{indent_xml, normalize_xml as canonicalize};
+    use nf_e_macros::serialization_test;
+    use quick_xml::{de::from_str as deserialize, se::to_string as serialize};
+    use rust_decimal_macros::dec;
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/product/rastro.xml",
+        indent = "indent_xml"
+    )]
+    pub fn setup_traceability() -> Traceability {
+        Traceability {
+            batch: "L2309A".to_string(),
+            quantity: dec!(1),
+            manufacture_date: NaiveDate::from_ymd_opt(2023, 9, 1).unwrap(),
+            expiration_date: NaiveDate::from_ymd_opt(2025, 9, 1).unwrap(),
+            aggregation_code: None,
+        }
+    }
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/product/med.xml",
+        indent = "indent_xml"
+    )]
+    pub fn setup_medicine() -> Medicine {
+        Medicine {
+            anvisa_code: "1234567890123".to_string(),
+            exemption_reason: None,
+            max_consumer_price: Money::from(dec!(24.90)),
+        }
+    }
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/product/comb.xml",
+        indent = "indent_xml"
+    )]
+    pub fn setup_fuel() -> Fuel {
+        Fuel {
+            anp_code: 320102001,
+            anp_description: "GASOLINA C COMUM".to_string(),
+            lpg_rate: None,
+            national_gas_rate: None,
+            imported_gas_rate: None,
+            starting_value: None,
+            codif: None,
+            temperature_quantity: None,
+            consumption_state: State::MinasGerais,
+            cide: None,
+            totalizer: Some(PumpTotalizer {
+                nozzle: 3,
+                pump: Some(2),
+                tank: 1,
+                initial_reading: dec!(152340.125),
+                final_reading: dec!(152380.125),
+            }),
+        }
+    }
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/product/comb_cide.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_fuel_cide() -> Fuel {
+        Fuel {
+            anp_code: 210203001,
+            anp_description: "GLP".to_string(),
+            lpg_rate: Some(dec!(60.5)),
+            national_gas_rate: Some(dec!(25.25)),
+            imported_gas_rate: Some(dec!(14.25)),
+            starting_value: Some(Money::from(dec!(4.50))),
+            codif: None,
+            temperature_quantity: None,
+            consumption_state: State::SaoPaulo,
+            cide: Some(CIDE {
+                quantity: Quantity::from(dec!(13)),
+                unit_rate: Quantity::from(dec!(0.1)),
+                value: Money::from(dec!(1.30)),
+            }),
+            totalizer: None,
+        }
+    }
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/product/veic_prod.xml",
+        indent = "indent_xml"
+    )]
+    pub fn setup_vehicle() -> NewVehicle {
+        NewVehicle {
+            operation: VehicleOperation::DealershipSale,
+            chassis: "9BWZZZ377VT004251".to_string(),
+            color_code: "B4B4".to_string(),
+            color: "BRANCO CRISTAL".to_string(),
+            power: "116".to_string(),
+            displacement: "1598".to_string(),
+            net_weight: "1150".to_string(),
+            gross_weight: "1580".to_string(),
+            serial: "004251".to_string(),
+            fuel: VehicleFuel::AlcoholGasoline,
+            engine: "CFZ123456".to_string(),
+            max_traction: "1.5800".to_string(),
+            wheelbase: "2470".to_string(),
+            model_year: 2024,
+            manufacture_year: 2023,
+            paint: "S".to_string(),
+            r#type: VehicleType::Car,
+            species: VehicleSpecies::Passenger,
+            vin: VINCondition::Normal,
+            condition: VehicleCondition::Finished,
+            model_code: "115024".to_string(),
+            denatran_color: DenatranColor::White,
+            capacity: 5,
+            restriction: VehicleRestriction::None,
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! Layout profiles of the models
+//!
+//! The NF-e and the NFC-e share the layout, but each model allows a different
+//! set of groups: the NFC-e is a sale to the final consumer, without billing
+//! (cobr), export (detExport) or withholdings (retTrib) and with the QR Code
+//! (infNFeSupl), while the NF-e always identifies its recipient (dest). The
+//! profiles check those groups, for the builder and for the signed document.
+
+use super::{InfoBuilder, NFe, ValidationError, validation};
+use crate::enums::Model;
+
+/// Groups allowed and required by a model
+pub trait Profile {
+    const MODEL: Model;
+
+    /// Checks the groups of the document being built
+    fn check(builder: &InfoBuilder, errors: &mut Vec<ValidationError>);
+
+    /// Checks the groups added by the signature, as the QR Code
+    fn check_signed(nfe: &NFe, errors: &mut Vec<ValidationError>);
+}
+
+/// Profile of the NF-e (model 55)
+pub struct NFeProfile;
+
+/// Profile of the NFC-e (model 65)
+pub struct NFCeProfile;
+
+impl Profile for NFeProfile {
+    const MODEL: Model = Model::NFe;
+
+    fn check(builder: &InfoBuilder, errors: &mut Vec<ValidationError>) {
+        if builder.recipient.is_none() {
+            errors.push(ValidationError::NFeMissingRecipient);
+        }
+    }
+
+    fn check_signed(_: &NFe, _: &mut Vec<ValidationError>) {}
+}
+
+impl Profile for NFCeProfile {
+    const MODEL: Model = Model::NFCe;
+
+    fn check(builder: &InfoBuilder, errors: &mut Vec<ValidationError>) {
+        validation::check_nfce(builder, errors);
+        if builder.billing.is_some() {
+            errors.push(ValidationError::NFCeBilling);
+        }
+        if !builder.withholdings.is_empty() {
+            errors.push(ValidationError::NFCeWithholding);
+        }
+        for (index, detail) in builder.details.iter().enumerate() {
+            if !detail.item.export_details.is_empty() {
+                errors.push(ValidationError::NFCeExport(index + 1));
+            }
+        }
+    }
+
+    fn check_signed(nfe: &NFe, errors: &mut Vec<ValidationError>) {
+        if nfe.supplement.is_none() {
+            errors.push(ValidationError::NFCeMissingQRCode);
+        }
+    }
+}
+
+/// Checks the builder against the profile of its model
+pub(crate) fn check(builder: &InfoBuilder, errors: &mut Vec<ValidationError>) {
+    match builder.identification.model {
+        Model::NFe => NFeProfile::check(builder, errors),
+        Model::NFCe => NFCeProfile::check(builder, errors),
+    }
+}
+
+/// Checks the signed document against the profile of its model
+pub(crate) fn check_signed(nfe: &NFe) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    match nfe.info.identification.model {
+        Model::NFe => NFeProfile::check_signed(nfe, &mut errors),
+        Model::NFCe => NFCeProfile::check_signed(nfe, &mut errors),
+    }
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::billing::tests::setup_billing;
+    use crate::models::foreign_trade::tests::setup_export_detail;
+    use crate::models::tests::{setup_info_builder, setup_nfe, setup_recipient};
+    use crate::models::withholding::tests::setup_withholding;
+    use crate::models::{InfoBuilderError, Total};
+    use crate::qr_code::CSC;
+
+    fn check_builder(builder: &InfoBuilder) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        check(builder, &mut errors);
+        errors
+    }
+
+    #[test]
+    fn test_nfce_groups() {
+        let mut builder = setup_info_builder()
+            .set_billing(setup_billing())
+            .add_withholding(setup_withholding());
+        builder.details[1]
+            .item
+            .export_details
+            .push(setup_export_detail());
+        assert_eq!(
+            check_builder(&builder),
+            vec![
+                ValidationError::NFCeBilling,
+                ValidationError::NFCeWithholding,
+                ValidationError::NFCeExport(2)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nfe_recipient() {
+        let mut builder = setup_info_builder();
+        builder.identification.model = Model::NFe;
+        assert_eq!(
+            check_builder(&builder),
+            vec![ValidationError::NFeMissingRecipient]
+        );
+
+        let builder = builder.set_recipient(setup_recipient());
+        assert_eq!(check_builder(&builder), vec![]);
+        assert!(
+            validation::validate(&builder, &Total::calculate(&builder))
+                .iter()
+                .all(|error| *error != ValidationError::NFeMissingRecipient)
+        );
+    }
+
+    #[test]
+    fn test_build_profile() {
+        assert!(setup_info_builder().build_profile::<NFCeProfile>().is_ok());
+        assert_eq!(
+            setup_info_builder().build_profile::<NFeProfile>(),
+            Err(InfoBuilderError::Validation(vec![
+                ValidationError::ProfileModel(Model::NFe)
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_signed_nfce() {
+        let nfe = setup_nfe();
+        assert_eq!(
+            nfe.check_profile(),
+            Err(vec![ValidationError::NFCeMissingQRCode])
+        );
+        let mut nfe = nfe
+            .with_qr_code(&CSC::new(1, "0123456789ABCDEF".to_string()))
+            .unwrap();
+        assert_eq!(nfe.check_profile(), Ok(()));
+
+        nfe.supplement = None;
+        nfe.info.identification.model = Model::NFe;
+        assert_eq!(nfe.check_profile(), Ok(()));
+    }
+}
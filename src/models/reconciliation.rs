@@ -0,0 +1,155 @@
+//! Reconciliation of the totals (total) of a document with its details
+//!
+//! Documents built by the `InfoBuilder` have their totals calculated, while the
+//! ones received from third parties, as through the distribution, carry the
+//! totals of their issuer, which may not add up.
+
+use super::{Info, Total};
+use crate::decimal::Money;
+
+/// Value of the totals that differs from the one calculated from the details
+///
+/// field: Path of the value in the totals (ICMSTot/vProd, ISSQNtot/vServ...)
+/// informed: Value of the document - None when absent
+/// calculated: Value calculated from the details - None when absent
+#[derive(Debug, Clone, PartialEq)]
+pub struct TotalDivergence {
+    pub field: &'static str,
+    pub informed: Option<Money>,
+    pub calculated: Option<Money>,
+}
+
+/// Values of the totals compared with the calculated ones
+fn values(total: &Total) -> Vec<(&'static str, Option<Money>)> {
+    let icms = &total.icms;
+    let mut values = vec![
+        ("ICMSTot/vBC", Some(icms.base)),
+        ("ICMSTot/vICMS", Some(icms.value)),
+        ("ICMSTot/vICMSDeson", Some(icms.unburdened)),
+        ("ICMSTot/vFCP", Some(icms.fcp_value)),
+        ("ICMSTot/vBCST", Some(icms.base_tributary_substitution)),
+        ("ICMSTot/vST", Some(icms.total_tributary_substitution)),
+        (
+            "ICMSTot/vFCPST",
+            Some(icms.fcp_value_tributary_substitution),
+        ),
+        (
+            "ICMSTot/vFCPSTRet",
+            Some(icms.retained_fcp_value_tributary_substitution),
+        ),
+        ("ICMSTot/vProd", Some(icms.total_products)),
+        ("ICMSTot/vFrete", Some(icms.freight)),
+        ("ICMSTot/vSeg", Some(icms.insurance)),
+        ("ICMSTot/vDesc", Some(icms.discount)),
+        ("ICMSTot/vII", Some(icms.import_tax)),
+        ("ICMSTot/vIPI", Some(icms.industrial_tax)),
+        ("ICMSTot/vIPIDevol", Some(icms.refunded_industrial_tax)),
+        ("ICMSTot/vPIS", Some(icms.pis_value)),
+        ("ICMSTot/vCOFINS", Some(icms.cofins_value)),
+        ("ICMSTot/vOutro", Some(icms.other)),
+        ("ICMSTot/vNF", Some(icms.total)),
+        ("ICMSTot/vTotTrib", icms.approximate_burden),
+    ];
+    let issqn = total.issqn.as_ref();
+    let value = |value: fn(&super::ISSQNTotal) -> Option<Money>| issqn.and_then(value);
+    values.extend([
+        ("ISSQNtot/vServ", value(|issqn| issqn.services)),
+        ("ISSQNtot/vBC", value(|issqn| issqn.base)),
+        ("ISSQNtot/vISS", value(|issqn| issqn.value)),
+        ("ISSQNtot/vPIS", value(|issqn| issqn.pis_value)),
+        ("ISSQNtot/vCOFINS", value(|issqn| issqn.cofins_value)),
+        ("ISSQNtot/vDeducao", value(|issqn| issqn.deduction)),
+        ("ISSQNtot/vOutro", value(|issqn| issqn.other)),
+        (
+            "ISSQNtot/vDescIncond",
+            value(|issqn| issqn.unconditional_discount),
+        ),
+        (
+            "ISSQNtot/vDescCond",
+            value(|issqn| issqn.conditional_discount),
+        ),
+        ("ISSQNtot/vISSRet", value(|issqn| issqn.retained_value)),
+    ]);
+    values
+}
+
+impl Info {
+    /// Totals recalculated from the details of the document
+    ///
+    /// The withholdings (retTrib), the competence and the special regime of the
+    /// ISSQN are kept as informed, since they are not derived from the details.
+    pub fn recalculate_totals(&self) -> Total {
+        let issqn = self.total.issqn.as_ref();
+        Total::from_details(
+            &self.details,
+            issqn.map_or_else(
+                || self.identification.emission_date.date_naive(),
+                |issqn| issqn.competence,
+            ),
+            issqn.and_then(|issqn| issqn.special_regime),
+            self.total.withholding.clone(),
+        )
+    }
+
+    /// Values of the totals that differ from the ones recalculated from the details
+    ///
+    /// Empty when the totals add up. The values are compared regardless of their
+    /// scale, so 10.5 and 10.50 are the same value.
+    pub fn verify_totals(&self) -> Vec<TotalDivergence> {
+        let calculated = self.recalculate_totals();
+        values(&self.total)
+            .into_iter()
+            .zip(values(&calculated))
+            .filter(|((_, informed), (_, calculated))| informed != calculated)
+            .map(|((field, informed), (_, calculated))| TotalDivergence {
+                field,
+                informed,
+                calculated,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::tests::setup_info_builder;
+    use crate::models::withholding::tests::setup_withholding;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_verify_totals() {
+        let mut info = setup_info_builder().build().unwrap();
+        assert_eq!(info.recalculate_totals(), info.total);
+        assert_eq!(info.verify_totals(), vec![]);
+
+        let products = info.total.icms.total_products;
+        info.total.icms.total_products = products + Money::from(dec!(1.00));
+        info.total.icms.approximate_burden = Some(Money::from(dec!(2.50)));
+        assert_eq!(
+            info.verify_totals(),
+            vec![
+                TotalDivergence {
+                    field: "ICMSTot/vProd",
+                    informed: Some(products + Money::from(dec!(1.00))),
+                    calculated: Some(products),
+                },
+                TotalDivergence {
+                    field: "ICMSTot/vTotTrib",
+                    informed: Some(Money::from(dec!(2.50))),
+                    calculated: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_informed_values_kept() {
+        let mut info = setup_info_builder().build().unwrap();
+        info.total.withholding = Some(setup_withholding());
+
+        let recalculated = info.recalculate_totals();
+        assert_eq!(recalculated.withholding, Some(setup_withholding()));
+        assert_eq!(info.verify_totals(), vec![]);
+    }
+}
@@ -0,0 +1,332 @@
+//! Transport group of the document (transp)
+
+use crate::decimal::{Money, Weight};
+use crate::enums::{IE, PersonDocument, TransportType};
+use crate::states::State;
+use serde::{Deserialize, Serialize, Serializer, ser::SerializeStruct};
+
+/// Transport information based on the XML structure of the NFe
+///
+/// type: Freight modality (modFrete)
+/// transporter: Carrier of the goods (transporta) - Optional
+/// withholding: ICMS withheld over the transport service (retTransp) - Optional
+/// vehicle: Vehicle carrying the goods (veicTransp) - Optional
+/// trailers: Trailers of the vehicle, up to 5 (reboque)
+/// volumes: Volumes carried (vol)
+#[derive(Default, PartialEq, Debug)]
+pub struct Transport {
+    pub r#type: TransportType,
+    pub transporter: Option<Transporter>,
+    pub withholding: Option<TransportWithholding>,
+    pub vehicle: Option<Vehicle>,
+    pub trailers: Vec<Vehicle>,
+    pub volumes: Vec<Volume>,
+}
+
+impl Serialize for Transport {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("transp", 6)?;
+        state.serialize_field("modFrete", &self.r#type.code())?;
+        if let Some(transporter) = &self.transporter {
+            state.serialize_field("transporta", transporter)?;
+        }
+        if let Some(withholding) = &self.withholding {
+            state.serialize_field("retTransp", withholding)?;
+        }
+        if let Some(vehicle) = &self.vehicle {
+            state.serialize_field("veicTransp", vehicle)?;
+        }
+        if !self.trailers.is_empty() {
+            state.serialize_field("reboque", &self.trailers)?;
+        }
+        if !self.volumes.is_empty() {
+            state.serialize_field("vol", &self.volumes)?;
+        }
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Transport {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct TransportHelper {
+            #[serde(rename = "modFrete")]
+            mod_frete: u8,
+            #[serde(rename = "transporta")]
+            transporta: Option<Transporter>,
+            #[serde(rename = "retTransp")]
+            ret_transp: Option<TransportWithholding>,
+            #[serde(rename = "veicTransp")]
+            veic_transp: Option<Vehicle>,
+            #[serde(rename = "reboque", default)]
+            reboque: Vec<Vehicle>,
+            #[serde(rename = "vol", default)]
+            vol: Vec<Volume>,
+        }
+
+        let helper = TransportHelper::deserialize(deserializer)?;
+        let r#type = TransportType::try_from(helper.mod_frete).map_err(serde::de::Error::custom)?;
+
+        Ok(Transport {
+            r#type,
+            transporter: helper.transporta,
+            withholding: helper.ret_transp,
+            vehicle: helper.veic_transp,
+            trailers: helper.reboque,
+            volumes: helper.vol,
+        })
+    }
+}
+
+/// Carrier of the goods (transporta)
+///
+/// document: Document of the carrier (CNPJ or CPF) - Optional
+/// name: Legal name of the carrier (xNome) - Optional
+/// ie: State registration of the carrier (IE) - Optional
+/// address: Full address of the carrier (xEnder) - Optional
+/// city: Name of the city of the carrier (xMun) - Optional
+/// state: State of the carrier (UF) - Optional
+#[derive(Default, Debug, PartialEq, Clone)]
+pub struct Transporter {
+    pub document: Option<PersonDocument>,
+    pub name: Option<String>,
+    pub ie: Option<IE>,
+    pub address: Option<String>,
+    pub city: Option<String>,
+    pub state: Option<State>,
+}
+
+impl Serialize for Transporter {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("transporta", 6)?;
+        if let Some(document) = &self.document {
+            state.serialize_field("$value", document)?;
+        }
+        if let Some(name) = &self.name {
+            state.serialize_field("xNome", name)?;
+        }
+        if let Some(ie) = &self.ie {
+            state.serialize_field("IE", &ie.0)?;
+        }
+        if let Some(address) = &self.address {
+            state.serialize_field("xEnder", address)?;
+        }
+        if let Some(city) = &self.city {
+            state.serialize_field("xMun", city)?;
+        }
+        if let Some(uf) = &self.state {
+            state.serialize_field("UF", uf.acronym())?;
+        }
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Transporter {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct TransporterHelper {
+            #[serde(rename = "$value")]
+            document: Option<PersonDocument>,
+            #[serde(rename = "xNome")]
+            x_nome: Option<String>,
+            #[serde(rename = "IE")]
+            ie: Option<String>,
+            #[serde(rename = "xEnder")]
+            x_ender: Option<String>,
+            #[serde(rename = "xMun")]
+            x_mun: Option<String>,
+            #[serde(rename = "UF")]
+            uf: Option<String>,
+        }
+
+        let helper = TransporterHelper::deserialize(deserializer)?;
+        let state = helper
+            .uf
+            .map(|uf| {
+                State::from_acronym(&uf).ok_or_else(|| {
+                    serde::de::Error::custom(format!("Invalid state acronym: {}", uf))
+                })
+            })
+            .transpose()?;
+
+        Ok(Transporter {
+            document: helper.document,
+            name: helper.x_nome,
+            ie: helper.ie.map(IE),
+            address: helper.x_ender,
+            city: helper.x_mun,
+            state,
+        })
+    }
+}
+
+/// ICMS withheld over the transport service (retTransp)
+///
+/// service_value: Value of the transport service (vServ)
+/// base: Calculation base of the withholding (vBCRet)
+/// rate: ICMS rate of the withholding (pICMSRet)
+/// value: ICMS withheld (vICMSRet)
+/// cfop: CFOP of the transport service (CFOP)
+/// city_code: IBGE code of the city where the service happened (cMunFG)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct TransportWithholding {
+    #[serde(rename = "vServ")]
+    pub service_value: Money,
+    #[serde(rename = "vBCRet")]
+    pub base: Money,
+    #[serde(rename = "pICMSRet")]
+    pub rate: Money,
+    #[serde(rename = "vICMSRet")]
+    pub value: Money,
+    #[serde(rename = "CFOP")]
+    pub cfop: u32,
+    #[serde(rename = "cMunFG")]
+    pub city_code: u32,
+}
+
+/// Vehicle or trailer carrying the goods (veicTransp, reboque)
+///
+/// plate: License plate (placa)
+/// state: State of the license plate (UF)
+/// rntc: National registry of road cargo carriers (RNTC) - Optional
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Vehicle {
+    #[serde(rename = "placa")]
+    pub plate: String,
+    #[serde(rename = "UF", with = "crate::states::acronym")]
+    pub state: State,
+    #[serde(rename = "RNTC", skip_serializing_if = "Option::is_none")]
+    pub rntc: Option<String>,
+}
+
+/// Volumes carried (vol)
+///
+/// quantity: Number of volumes (qVol) - Optional
+/// species: Species of the volumes, e.g. boxes (esp) - Optional
+/// brand: Brand of the volumes (marca) - Optional
+/// numbering: Numbering of the volumes (nVol) - Optional
+/// net_weight: Net weight in kg (pesoL) - Optional
+/// gross_weight: Gross weight in kg (pesoB) - Optional
+/// seals: Seals of the volumes (lacres)
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Clone)]
+#[serde(rename = "vol")]
+pub struct Volume {
+    #[serde(rename = "qVol", skip_serializing_if = "Option::is_none")]
+    pub quantity: Option<u64>,
+    #[serde(rename = "esp", skip_serializing_if = "Option::is_none")]
+    pub species: Option<String>,
+    #[serde(rename = "marca", skip_serializing_if = "Option::is_none")]
+    pub brand: Option<String>,
+    #[serde(rename = "nVol", skip_serializing_if = "Option::is_none")]
+    pub numbering: Option<String>,
+    #[serde(rename = "pesoL", skip_serializing_if = "Option::is_none")]
+    pub net_weight: Option<Weight>,
+    #[serde(rename = "pesoB", skip_serializing_if = "Option::is_none")]
+    pub gross_weight: Option<Weight>,
+    #[serde(rename = "lacres", default, skip_serializing_if = "Vec::is_empty")]
+    pub seals: Vec<Seal>,
+}
+
+/// Seal of a volume (lacres)
+///
+/// number: Number of the seal (nLacre)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Seal {
+    #[serde(rename = "nLacre")]
+    pub number: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::CNPJ;
+    use crate::xml::{indent_xml, normalize_xml as canonicalize};
+    use nf_e_macros::serialization_test;
+    use quick_xml::{de::from_str as deserialize, se::to_string as serialize};
+    use rust_decimal_macros::dec;
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/transport/transporter.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_transporter() -> Transporter {
+        Transporter {
+            document: Some(PersonDocument::CNPJ(CNPJ("98765432000198".to_string()))),
+            name: Some("Transportadora Exemplo LTDA".to_string()),
+            ie: Some(IE("110042490114".to_string())),
+            address: Some("Rodovia Anhanguera, km 10".to_string()),
+            city: Some("Sao Paulo".to_string()),
+            state: Some(State::SaoPaulo),
+        }
+    }
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/transport/volume.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_volume() -> Volume {
+        Volume {
+            quantity: Some(2),
+            species: Some("CAIXA".to_string()),
+            brand: Some("Exemplo".to_string()),
+            numbering: Some("1-2".to_string()),
+            net_weight: Some(Weight::from(dec!(10.5))),
+            gross_weight: Some(Weight::from(dec!(11.25))),
+            seals: vec![
+                Seal {
+                    number: "L001".to_string(),
+                },
+                Seal {
+                    number: "L002".to_string(),
+                },
+            ],
+        }
+    }
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/transport/transport.xml",
+        indent = "indent_xml"
+    )]
+    fn setup_transport() -> Transport {
+        Transport {
+            r#type: TransportType::CIF,
+            transporter: Some(setup_transporter()),
+            withholding: Some(TransportWithholding {
+                service_value: Money::from(dec!(150.0)),
+                base: Money::from(dec!(150.0)),
+                rate: Money::from(dec!(12.0)),
+                value: Money::from(dec!(18.0)),
+                cfop: 5353,
+                city_code: 3550308,
+            }),
+            vehicle: Some(Vehicle {
+                plate: "ABC1D23".to_string(),
+                state: State::SaoPaulo,
+                rntc: Some("12345678".to_string()),
+            }),
+            trailers: vec![Vehicle {
+                plate: "XYZ9F87".to_string(),
+                state: State::MinasGerais,
+                rntc: None,
+            }],
+            volumes: vec![setup_volume(), Volume::default()],
+        }
+    }
+
+    #[serialization_test(expected = "<transporta/>")]
+    fn setup_empty_transporter() -> Transporter {
+        Transporter::default()
+    }
+}
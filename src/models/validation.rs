@@ -0,0 +1,587 @@
+use super::{DoNotMatchTotal, InfoBuilder, Total, profile};
+use crate::enums::{
+    CFOPError, DestinationTarget, EmissionType, Finality, GTINError, IEError,
+    IntermediatorIndicator, Model, Operation, PaymentType, Presence, TaxRegime, validate_cfop,
+    validate_gtin,
+};
+
+/// Maximum number of items (det) of a document
+pub const MAX_ITEMS: usize = 990;
+
+/// Rule violated by the document being built
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationError {
+    /// Payments without the change (vTroco) differ from the total of the document
+    PaymentsDoNotMatchTotal(DoNotMatchTotal),
+    /// Change (vTroco) is only given back on cash payments
+    ChangeWithoutCash,
+    /// Payment (detPag) by other means (tPag = 99) must describe them (xPag)
+    MissingPaymentDescription(usize),
+    /// Number of items outside of 1..=990
+    ItemCount(usize),
+    /// NFC-e only allows normal emissions (finNFe = 1)
+    NFCeFinality(Finality),
+    /// NFC-e only allows internal operations (idDest = 1)
+    NFCeDestination(DestinationTarget),
+    /// NFC-e must not inform the exit or entry date (dhSaiEnt)
+    NFCeExitDate,
+    /// NFC-e only allows outgoing operations (tpNF = 1)
+    NFCeOperation(Operation),
+    /// NFC-e is always issued to a final consumer (indFinal = 1)
+    NFCeNotFinalConsumer,
+    /// NFC-e must inform the presence indicator (indPres)
+    NFCeMissingPresence,
+    /// NFC-e only allows in-person or delivery operations (indPres = 1, 4 or 5)
+    NFCePresence(Presence),
+    /// Contingency emissions must inform dhCont and xJust
+    MissingContingency,
+    /// Length of the contingency justification outside of 15..=256 characters
+    ContingencyJustification(usize),
+    /// Offline emissions (tpEmis = 9) are exclusive to NFC-e
+    OfflineNotNFCe,
+    /// Operations without the buyer at the establishment (indPres = 2, 3, 4 or 9) must
+    /// inform their intermediation (indIntermed)
+    MissingIntermediatorIndicator(Presence),
+    /// Operations in the platform of a third party (indIntermed = 1) must identify the
+    /// intermediator (infIntermed)
+    MissingIntermediator,
+    /// State registration of the issuer rejected by the state of its address
+    IssuerIE(IEError),
+    /// State registration of the recipient rejected by the state of its address
+    RecipientIE(IEError),
+    /// Sum of the installments (dup) differs from the net value of the invoice (vLiq)
+    InstallmentsDoNotMatchInvoice(DoNotMatchTotal),
+    /// ICMS group of the item (nItem) does not match the tax regime of the issuer, CSOSN for
+    /// CRT 1 and 4 and CST for CRT 2 and 3
+    TaxRegimeMismatch(usize, TaxRegime),
+    /// GTIN (cEAN) of the item (nItem) with an invalid format or check digit, rejected by
+    /// SEFAZ with cStat 611
+    GTIN(usize, GTINError),
+    /// NCM of the item (nItem) with more than 8 digits or out of the chapters 01 to 97,
+    /// "00000000" being only accepted for services
+    NCM(usize, u32),
+    /// CFOP of the item (nItem) unknown or not matching the operation and destination
+    CFOP(usize, CFOPError),
+    /// Model of the identification other than the one of the profile being built
+    ProfileModel(Model),
+    /// NF-e must identify the recipient (dest)
+    NFeMissingRecipient,
+    /// NFC-e must not inform the billing (cobr)
+    NFCeBilling,
+    /// NFC-e must not inform the export details (detExport) of the item (nItem)
+    NFCeExport(usize),
+    /// NFC-e must be signed with the QR Code (infNFeSupl)
+    NFCeMissingQRCode,
+    /// NFC-e must not inform the taxes withheld by the recipient (retTrib)
+    NFCeWithholding,
+    /// Exempted ICMS (vICMSDeson) of the item (nItem) informed without its reason
+    /// (motDesICMS), or the reason without the value
+    UnburdenReason(usize),
+}
+
+fn check_paid(builder: &InfoBuilder, total: &Total, errors: &mut Vec<ValidationError>) {
+    let payments = &builder.payments;
+    if payments.change.is_some()
+        && !payments
+            .payments
+            .iter()
+            .any(|payment| payment.r#type == PaymentType::Cash)
+    {
+        errors.push(ValidationError::ChangeWithoutCash);
+    }
+    for (index, payment) in payments.payments.iter().enumerate() {
+        if payment.r#type == PaymentType::Other && payment.description.is_none() {
+            errors.push(ValidationError::MissingPaymentDescription(index + 1));
+        }
+    }
+    // Complementary and adjustment notes may be issued without payment (tPag = 90)
+    let unpaid = matches!(
+        builder.identification.finality,
+        Finality::Complementary | Finality::Adjustment
+    ) && payments
+        .payments
+        .iter()
+        .all(|payment| payment.r#type == PaymentType::NoPayment);
+    if unpaid {
+        return;
+    }
+    let paid = payments.net();
+    let expected = total.icms.total;
+    if paid.rounded() != expected.rounded() {
+        errors.push(ValidationError::PaymentsDoNotMatchTotal(DoNotMatchTotal {
+            expected,
+            total: paid,
+        }));
+    }
+}
+
+fn check_billing(builder: &InfoBuilder, errors: &mut Vec<ValidationError>) {
+    let Some(billing) = &builder.billing else {
+        return;
+    };
+    if let Some(expected) = billing
+        .invoice
+        .as_ref()
+        .and_then(|invoice| invoice.net_value)
+        && !billing.installments.is_empty()
+    {
+        let total = billing.installments_total();
+        if total.rounded() != expected.rounded() {
+            errors.push(ValidationError::InstallmentsDoNotMatchInvoice(
+                DoNotMatchTotal { expected, total },
+            ));
+        }
+    }
+}
+
+fn check_items(builder: &InfoBuilder, errors: &mut Vec<ValidationError>) {
+    let count = builder.details.len();
+    if !(1..=MAX_ITEMS).contains(&count) {
+        errors.push(ValidationError::ItemCount(count));
+    }
+}
+
+fn check_gtin(builder: &InfoBuilder, errors: &mut Vec<ValidationError>) {
+    for (index, detail) in builder.details.iter().enumerate() {
+        if let Some(gtin) = &detail.item.gtin
+            && let Err(error) = validate_gtin(gtin)
+        {
+            errors.push(ValidationError::GTIN(index + 1, error));
+        }
+    }
+}
+
+fn check_codes(builder: &InfoBuilder, errors: &mut Vec<ValidationError>) {
+    let identification = &builder.identification;
+    for (index, detail) in builder.details.iter().enumerate() {
+        let item = &detail.item;
+        let chapter = item.ncm / 1_000_000;
+        let valid_ncm = match item.ncm {
+            0 => item.service,
+            ncm => ncm <= 99_999_999 && (1..=97).contains(&chapter) && chapter != 77,
+        };
+        if !valid_ncm {
+            errors.push(ValidationError::NCM(index + 1, item.ncm));
+        }
+        if let Err(error) = validate_cfop(
+            item.cfop,
+            &identification.r#type,
+            &identification.destination,
+        ) {
+            errors.push(ValidationError::CFOP(index + 1, error));
+        }
+    }
+}
+
+fn check_ie(builder: &InfoBuilder, errors: &mut Vec<ValidationError>) {
+    let address = &builder.issuer.address;
+    if let Err(error) = address.ie.validate(&address.address.state) {
+        errors.push(ValidationError::IssuerIE(error));
+    }
+    if let Some(recipient) = &builder.recipient
+        && let (Some(ie), Some(address)) = (&recipient.ie, &recipient.address)
+        && let Err(error) = ie.validate(&address.state)
+    {
+        errors.push(ValidationError::RecipientIE(error));
+    }
+}
+
+fn check_tax_regime(builder: &InfoBuilder, errors: &mut Vec<ValidationError>) {
+    let regime = builder.issuer.tax_regime;
+    for (index, detail) in builder.details.iter().enumerate() {
+        if let Some(icms) = &detail.tax.icms
+            && icms.is_simples_nacional() != regime.uses_csosn()
+        {
+            errors.push(ValidationError::TaxRegimeMismatch(index + 1, regime));
+        }
+    }
+}
+
+fn check_unburden(builder: &InfoBuilder, errors: &mut Vec<ValidationError>) {
+    for (index, detail) in builder.details.iter().enumerate() {
+        let (value, reason) = detail
+            .tax
+            .icms
+            .as_ref()
+            .map_or((None, None), |icms| icms.unburden());
+        if value.is_some() != reason.is_some() {
+            errors.push(ValidationError::UnburdenReason(index + 1));
+        }
+    }
+}
+
+fn check_contingency(builder: &InfoBuilder, errors: &mut Vec<ValidationError>) {
+    let identification = &builder.identification;
+    if identification.model != Model::NFCe && identification.emission_type == EmissionType::Offline
+    {
+        errors.push(ValidationError::OfflineNotNFCe);
+    }
+    match &identification.contingency {
+        None if identification.emission_type != EmissionType::Normal => {
+            errors.push(ValidationError::MissingContingency)
+        }
+        None => {}
+        Some(contingency) => {
+            let length = contingency.justification.chars().count();
+            if !(15..=256).contains(&length) {
+                errors.push(ValidationError::ContingencyJustification(length));
+            }
+        }
+    }
+}
+
+fn check_intermediator(builder: &InfoBuilder, errors: &mut Vec<ValidationError>) {
+    let identification = &builder.identification;
+    match (
+        &identification.intermediator_indicator,
+        &identification.presence,
+    ) {
+        (None, Some(presence)) if presence.is_remote() => errors.push(
+            ValidationError::MissingIntermediatorIndicator(presence.clone()),
+        ),
+        (Some(IntermediatorIndicator::ThirdParty), _) if builder.intermediator.is_none() => {
+            errors.push(ValidationError::MissingIntermediator)
+        }
+        _ => {}
+    }
+}
+
+pub(super) fn check_nfce(builder: &InfoBuilder, errors: &mut Vec<ValidationError>) {
+    let identification = &builder.identification;
+    if identification.finality != Finality::Normal {
+        errors.push(ValidationError::NFCeFinality(
+            identification.finality.clone(),
+        ));
+    }
+    if identification.destination != DestinationTarget::Internal {
+        errors.push(ValidationError::NFCeDestination(
+            identification.destination.clone(),
+        ));
+    }
+    if identification.date.is_some() {
+        errors.push(ValidationError::NFCeExitDate);
+    }
+    if identification.r#type != Operation::Outgoing {
+        errors.push(ValidationError::NFCeOperation(
+            identification.r#type.clone(),
+        ));
+    }
+    if !identification.consumer {
+        errors.push(ValidationError::NFCeNotFinalConsumer);
+    }
+    match &identification.presence {
+        None => errors.push(ValidationError::NFCeMissingPresence),
+        Some(Presence::InplaceIndoor | Presence::InplaceOutdoor | Presence::Delivery) => {}
+        Some(presence) => errors.push(ValidationError::NFCePresence(presence.clone())),
+    }
+}
+
+/// Checks every rule against the builder, collecting all the violations
+pub(crate) fn validate(builder: &InfoBuilder, total: &Total) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    check_paid(builder, total, &mut errors);
+    check_billing(builder, &mut errors);
+    check_items(builder, &mut errors);
+    check_gtin(builder, &mut errors);
+    check_codes(builder, &mut errors);
+    check_ie(builder, &mut errors);
+    check_tax_regime(builder, &mut errors);
+    check_unburden(builder, &mut errors);
+    check_contingency(builder, &mut errors);
+    check_intermediator(builder, &mut errors);
+    profile::check(builder, &mut errors);
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decimal::Money;
+    use crate::enums::{CST, ICMS, IE, IEIndicator, NO_GTIN, Origin, UnburdenReason};
+    use crate::models::ICMS40;
+    use crate::models::billing::tests::setup_billing;
+    use crate::models::intermediator::tests::setup_intermediator;
+    use crate::models::tests::{setup_info_builder, setup_local_date, setup_recipient};
+    use crate::models::{Contingency, Installment, Recipient};
+    use crate::states::State;
+    use rust_decimal_macros::dec;
+
+    fn validate_builder(builder: &InfoBuilder) -> Vec<ValidationError> {
+        validate(builder, &Total::calculate(builder))
+    }
+
+    fn setup_nfe_builder() -> InfoBuilder {
+        let mut builder = setup_info_builder().set_recipient(setup_recipient());
+        builder.identification.model = Model::NFe;
+        builder
+    }
+
+    #[test]
+    fn test_valid_nfce() {
+        assert_eq!(validate_builder(&setup_info_builder()), vec![]);
+    }
+
+    #[test]
+    fn test_invalid_nfce_collects_every_error() {
+        let mut builder = setup_info_builder();
+        builder.identification.finality = Finality::Complementary;
+        builder.identification.destination = DestinationTarget::Interstate;
+        builder.identification.date = Some(setup_local_date(15, 0));
+        builder.identification.r#type = Operation::Incoming;
+        builder.identification.consumer = false;
+        builder.identification.presence = Some(Presence::Teleservice);
+
+        let cfop = CFOPError::Operation(5403, Operation::Incoming);
+        assert_eq!(
+            validate_builder(&builder),
+            vec![
+                ValidationError::CFOP(1, cfop.clone()),
+                ValidationError::CFOP(2, cfop),
+                ValidationError::MissingIntermediatorIndicator(Presence::Teleservice),
+                ValidationError::NFCeFinality(Finality::Complementary),
+                ValidationError::NFCeDestination(DestinationTarget::Interstate),
+                ValidationError::NFCeExitDate,
+                ValidationError::NFCeOperation(Operation::Incoming),
+                ValidationError::NFCeNotFinalConsumer,
+                ValidationError::NFCePresence(Presence::Teleservice),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nfce_missing_presence() {
+        let mut builder = setup_info_builder();
+        builder.identification.presence = None;
+        assert_eq!(
+            validate_builder(&builder),
+            vec![ValidationError::NFCeMissingPresence]
+        );
+    }
+
+    #[test]
+    fn test_nfe_rules_not_applied() {
+        let mut builder = setup_nfe_builder();
+        builder.identification.destination = DestinationTarget::Interstate;
+        builder.identification.presence = None;
+        for detail in &mut builder.details {
+            detail.item.cfop = 6403;
+        }
+        assert_eq!(validate_builder(&builder), vec![]);
+    }
+
+    #[test]
+    fn test_codes() {
+        let mut builder = setup_info_builder();
+        builder.details[0].item.ncm = 0;
+        builder.details[1].item.cfop = 5199;
+        let mut expected = vec![ValidationError::NCM(1, 0)];
+        if cfg!(feature = "tables") {
+            expected.push(ValidationError::CFOP(2, CFOPError::Unknown(5199)));
+        }
+        assert_eq!(validate_builder(&builder), expected);
+
+        builder.details[0].item.service = true;
+        builder.details[1].item.ncm = 77_000_000;
+        builder.details[1].item.cfop = 5102;
+        assert_eq!(
+            validate_builder(&builder),
+            vec![ValidationError::NCM(2, 77_000_000)]
+        );
+    }
+
+    #[test]
+    fn test_contingency() {
+        let mut builder = setup_info_builder();
+        builder.identification.emission_type = EmissionType::Offline;
+        assert_eq!(
+            validate_builder(&builder),
+            vec![ValidationError::MissingContingency]
+        );
+
+        let date = setup_local_date(14, 0);
+        let builder = setup_info_builder().set_contingency(Contingency::new(date, "Sem rede"));
+        assert_eq!(
+            validate_builder(&builder),
+            vec![ValidationError::ContingencyJustification(8)]
+        );
+
+        let contingency = Contingency::new(date, "Falha de comunicacao com a SEFAZ");
+        let builder = setup_info_builder().set_contingency(contingency.clone());
+        assert_eq!(validate_builder(&builder), vec![]);
+        let mut builder = setup_nfe_builder().set_contingency(contingency);
+        builder.identification.emission_type = EmissionType::Offline;
+        assert_eq!(
+            validate_builder(&builder),
+            vec![ValidationError::OfflineNotNFCe]
+        );
+    }
+
+    #[test]
+    fn test_intermediator() {
+        let mut builder = setup_info_builder();
+        builder.identification.presence = Some(Presence::Delivery);
+        assert_eq!(
+            validate_builder(&builder),
+            vec![ValidationError::MissingIntermediatorIndicator(
+                Presence::Delivery
+            )]
+        );
+
+        builder.identification.intermediator_indicator = Some(IntermediatorIndicator::ThirdParty);
+        assert_eq!(
+            validate_builder(&builder),
+            vec![ValidationError::MissingIntermediator]
+        );
+
+        let builder = builder.set_intermediator(setup_intermediator());
+        assert_eq!(validate_builder(&builder), vec![]);
+    }
+
+    #[test]
+    fn test_ie() {
+        let mut builder = setup_info_builder();
+        builder.issuer.address.ie = IE("0623079040082".to_string());
+        builder.recipient = Some(Recipient {
+            ie_indicator: IEIndicator::Contributor,
+            ie: Some(IE("110042490114".to_string())),
+            ..setup_recipient()
+        });
+        assert_eq!(
+            validate_builder(&builder),
+            vec![
+                ValidationError::IssuerIE(IEError::VerifierDigits(State::MinasGerais)),
+                ValidationError::RecipientIE(IEError::Length(State::MinasGerais, 12)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_gtin() {
+        let mut builder = setup_info_builder();
+        builder.details[1].item.gtin = Some(NO_GTIN.to_string());
+        assert_eq!(validate_builder(&builder), vec![]);
+
+        builder.details[0].item.gtin = Some("7896235354490".to_string());
+        builder.details[1].item.gtin = Some("789623535449".to_string());
+        assert_eq!(
+            validate_builder(&builder),
+            vec![
+                ValidationError::GTIN(1, GTINError::CheckDigit),
+                ValidationError::GTIN(2, GTINError::CheckDigit),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tax_regime() {
+        let mut builder = setup_info_builder();
+        builder.issuer.tax_regime = TaxRegime::MEI;
+        assert_eq!(validate_builder(&builder), vec![]);
+
+        builder.issuer.tax_regime = TaxRegime::SimplesExcesso;
+        assert_eq!(
+            validate_builder(&builder),
+            vec![
+                ValidationError::TaxRegimeMismatch(1, TaxRegime::SimplesExcesso),
+                ValidationError::TaxRegimeMismatch(2, TaxRegime::SimplesExcesso),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unburden() {
+        let mut builder = setup_info_builder();
+        builder.issuer.tax_regime = TaxRegime::Normal;
+        let exempt = |unburdened, unburden_reason| {
+            Some(ICMS::ICMS40(ICMS40 {
+                origin: Origin::National,
+                cst: CST::Exempt,
+                unburdened,
+                unburden_reason,
+            }))
+        };
+        builder.details[0].tax.icms = exempt(Some(Money::ZERO), Some(UnburdenReason::Other));
+        builder.details[1].tax.icms = exempt(None, None);
+        assert_eq!(validate_builder(&builder), vec![]);
+
+        builder.details[0].tax.icms = exempt(Some(Money::ZERO), None);
+        builder.details[1].tax.icms = exempt(None, Some(UnburdenReason::Taxi));
+        assert_eq!(
+            validate_builder(&builder),
+            vec![
+                ValidationError::UnburdenReason(1),
+                ValidationError::UnburdenReason(2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_billing() {
+        let builder = setup_nfe_builder().set_billing(setup_billing());
+        assert_eq!(validate_builder(&builder), vec![]);
+
+        let builder = builder.add_installment(Installment {
+            number: Some("003".to_string()),
+            due_date: None,
+            value: Money::from(dec!(10.0)),
+        });
+        assert_eq!(
+            validate_builder(&builder),
+            vec![ValidationError::InstallmentsDoNotMatchInvoice(
+                DoNotMatchTotal {
+                    expected: Money::from(dec!(113.94)),
+                    total: Money::from(dec!(123.94)),
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn test_change() {
+        let mut builder = setup_info_builder();
+        builder.payments.payments[0].value = Money::from(dec!(50.0));
+        builder.payments.change = Some(Money::from(dec!(10.0)));
+        assert_eq!(validate_builder(&builder), vec![]);
+
+        builder.payments.payments[0].r#type = PaymentType::PIX;
+        assert_eq!(
+            validate_builder(&builder),
+            vec![ValidationError::ChangeWithoutCash]
+        );
+    }
+
+    #[test]
+    fn test_payment_type() {
+        let mut builder = setup_nfe_builder();
+        builder.payments.payments[1].r#type = PaymentType::Other;
+        assert_eq!(
+            validate_builder(&builder),
+            vec![ValidationError::MissingPaymentDescription(2)]
+        );
+        builder.payments.payments[1].description = Some("Permuta".to_string());
+        assert_eq!(validate_builder(&builder), vec![]);
+
+        let mut builder = setup_nfe_builder();
+        builder.payments.payments.truncate(1);
+        builder.payments.payments[0].r#type = PaymentType::NoPayment;
+        builder.payments.payments[0].value = Money::ZERO;
+        let expected = Total::calculate(&builder).icms.total;
+        assert_eq!(
+            validate_builder(&builder),
+            vec![ValidationError::PaymentsDoNotMatchTotal(DoNotMatchTotal {
+                expected,
+                total: Money::ZERO,
+            })]
+        );
+        builder.identification.finality = Finality::Adjustment;
+        assert_eq!(validate_builder(&builder), vec![]);
+    }
+
+    #[test]
+    fn test_item_count() {
+        let mut builder = setup_info_builder();
+        builder.details.clear();
+        let errors = validate_builder(&builder);
+        assert!(errors.contains(&ValidationError::ItemCount(0)));
+    }
+}
@@ -0,0 +1,142 @@
+//! Taxes withheld by the recipient (retTrib)
+
+use crate::decimal::Money;
+use serde::{Deserialize, Serialize};
+
+/// Totals of the taxes withheld by the recipient, as the public bodies and
+/// the companies hiring services
+///
+/// Each payment may withhold its own values, added with
+/// `InfoBuilder::add_withholding` and summed in the total of the document.
+/// Only the values other than zero are serialized.
+///
+/// pis: Withheld PIS (vRetPIS) - Optional
+/// cofins: Withheld COFINS (vRetCOFINS) - Optional
+/// csll: Withheld CSLL (vRetCSLL) - Optional
+/// irrf_base: Base of the withheld income tax (vBCIRRF) - Optional
+/// irrf: Withheld income tax (vIRRF) - Optional
+/// social_security_base: Base of the withheld social security (vBCRetPrev) - Optional
+/// social_security: Withheld social security (vRetPrev) - Optional
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Clone)]
+#[serde(rename = "retTrib")]
+pub struct Withholding {
+    #[serde(rename = "vRetPIS", skip_serializing_if = "Option::is_none")]
+    pub pis: Option<Money>,
+    #[serde(rename = "vRetCOFINS", skip_serializing_if = "Option::is_none")]
+    pub cofins: Option<Money>,
+    #[serde(rename = "vRetCSLL", skip_serializing_if = "Option::is_none")]
+    pub csll: Option<Money>,
+    #[serde(rename = "vBCIRRF", skip_serializing_if = "Option::is_none")]
+    pub irrf_base: Option<Money>,
+    #[serde(rename = "vIRRF", skip_serializing_if = "Option::is_none")]
+    pub irrf: Option<Money>,
+    #[serde(rename = "vBCRetPrev", skip_serializing_if = "Option::is_none")]
+    pub social_security_base: Option<Money>,
+    #[serde(rename = "vRetPrev", skip_serializing_if = "Option::is_none")]
+    pub social_security: Option<Money>,
+}
+
+impl Withholding {
+    /// Sum of the withholdings, without the values that add up to zero
+    ///
+    /// None when every value is zero.
+    pub fn sum<'a>(withholdings: impl IntoIterator<Item = &'a Withholding>) -> Option<Self> {
+        let mut total = Withholding::default();
+        for withholding in withholdings {
+            let values = [
+                (&mut total.pis, withholding.pis),
+                (&mut total.cofins, withholding.cofins),
+                (&mut total.csll, withholding.csll),
+                (&mut total.irrf_base, withholding.irrf_base),
+                (&mut total.irrf, withholding.irrf),
+                (
+                    &mut total.social_security_base,
+                    withholding.social_security_base,
+                ),
+                (&mut total.social_security, withholding.social_security),
+            ];
+            for (total, value) in values {
+                *total = Some(total.unwrap_or(Money::ZERO) + value.unwrap_or(Money::ZERO));
+            }
+        }
+
+        let values = [
+            &mut total.pis,
+            &mut total.cofins,
+            &mut total.csll,
+            &mut total.irrf_base,
+            &mut total.irrf,
+            &mut total.social_security_base,
+            &mut total.social_security,
+        ];
+        let mut empty = true;
+        for value in values {
+            *value = value.filter(|value| *value != Money::ZERO);
+            empty &= value.is_none();
+        }
+        (!empty).then_some(total)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::models::Total;
+    use crate::models::tests::setup_info_builder;
+    use crate::xml::{indent_xml, normalize_xml as canonicalize};
+    use nf_e_macros::serialization_test;
+    use quick_xml::{de::from_str as deserialize, se::to_string as serialize};
+    use rust_decimal_macros::dec;
+
+    #[serialization_test(
+        fixture = "../../tests/fixtures/withholding.xml",
+        indent = "indent_xml"
+    )]
+    pub fn setup_withholding() -> Withholding {
+        Withholding {
+            pis: Some(Money::from(dec!(0.65))),
+            cofins: Some(Money::from(dec!(3.00))),
+            csll: Some(Money::from(dec!(1.00))),
+            irrf_base: Some(Money::from(dec!(100.00))),
+            irrf: Some(Money::from(dec!(1.50))),
+            social_security_base: None,
+            social_security: None,
+        }
+    }
+
+    #[test]
+    fn test_sum() {
+        let other = Withholding {
+            pis: Some(Money::from(dec!(0.35))),
+            social_security: Some(Money::ZERO),
+            ..Default::default()
+        };
+        let total = Withholding::sum([&setup_withholding(), &other]).unwrap();
+        assert_eq!(total.pis, Some(Money::from(dec!(1.00))));
+        assert_eq!(total.cofins, Some(Money::from(dec!(3.00))));
+        assert_eq!(total.social_security, None);
+
+        let total = Withholding::sum([&other, &Withholding::default()]).unwrap();
+        assert_eq!(
+            total,
+            Withholding {
+                pis: Some(Money::from(dec!(0.35))),
+                ..Default::default()
+            }
+        );
+        assert_eq!(Withholding::sum([&Withholding::default()]), None);
+        assert_eq!(Withholding::sum([]), None);
+    }
+
+    #[test]
+    fn test_total() {
+        let builder = setup_info_builder();
+        assert_eq!(Total::calculate(&builder).withholding, None);
+
+        let builder = builder
+            .add_withholding(setup_withholding())
+            .add_withholding(setup_withholding());
+        let total = Total::calculate(&builder).withholding.unwrap();
+        assert_eq!(total.irrf, Some(Money::from(dec!(3.00))));
+    }
+}
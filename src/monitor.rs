@@ -0,0 +1,277 @@
+//! Availability of the webservices of the states (disponibilidade)
+//!
+//! The `Monitor` queries the NfeStatusServico4 webservice of each of its
+//! states at the same time, so a check takes as long as the slowest state,
+//! and reports the status of each one, as the dashboards of the retailers
+//! showing the authorizers available for emission.
+
+use crate::enums::Environment;
+use crate::soap::{Client, ClientError, ServiceStatus, StatusReport};
+use crate::states::State;
+use std::pin::Pin;
+use std::task::Poll;
+use std::time::Duration;
+
+/// State and environment whose webservices are checked
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorTarget {
+    pub state: State,
+    pub environment: Environment,
+}
+
+/// Set of states and environments checked together
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Monitor {
+    targets: Vec<MonitorTarget>,
+}
+
+impl Monitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Monitor of every state in the environment
+    pub fn all_states(environment: Environment) -> Self {
+        Self::new().with_states(
+            (11..=53).filter_map(|code| State::try_from(code).ok()),
+            environment,
+        )
+    }
+
+    pub fn with_target(mut self, state: State, environment: Environment) -> Self {
+        let target = MonitorTarget { state, environment };
+        if !self.targets.contains(&target) {
+            self.targets.push(target);
+        }
+        self
+    }
+
+    pub fn with_states(
+        self,
+        states: impl IntoIterator<Item = State>,
+        environment: Environment,
+    ) -> Self {
+        states.into_iter().fold(self, |monitor, state| {
+            monitor.with_target(state, environment.clone())
+        })
+    }
+
+    pub fn targets(&self) -> &[MonitorTarget] {
+        &self.targets
+    }
+
+    /// Queries the status of the webservices of every target at the same time
+    pub async fn check(&self, client: &Client) -> AvailabilityReport {
+        self.check_with(|state, environment| client.service_status(state, environment))
+            .await
+    }
+
+    async fn check_with<F, Fut>(&self, query: F) -> AvailabilityReport
+    where
+        F: Fn(State, Environment) -> Fut,
+        Fut: Future<Output = Result<StatusReport, ClientError>>,
+    {
+        let results = join_all(
+            self.targets
+                .iter()
+                .map(|target| query(target.state.clone(), target.environment.clone()))
+                .collect(),
+        )
+        .await;
+        AvailabilityReport {
+            entries: self
+                .targets
+                .iter()
+                .cloned()
+                .zip(results)
+                .map(|(target, result)| Availability { target, result })
+                .collect(),
+        }
+    }
+}
+
+/// Polls the futures together, returning their outputs in the same order
+async fn join_all<F: Future>(futures: Vec<F>) -> Vec<F::Output> {
+    let mut futures: Vec<Pin<Box<F>>> = futures.into_iter().map(Box::pin).collect();
+    let mut outputs: Vec<Option<F::Output>> = futures.iter().map(|_| None).collect();
+    std::future::poll_fn(|context| {
+        let mut pending = false;
+        for (future, output) in futures.iter_mut().zip(outputs.iter_mut()) {
+            if output.is_none() {
+                match future.as_mut().poll(context) {
+                    Poll::Ready(value) => *output = Some(value),
+                    Poll::Pending => pending = true,
+                }
+            }
+        }
+        if pending {
+            Poll::Pending
+        } else {
+            Poll::Ready(())
+        }
+    })
+    .await;
+    outputs
+        .into_iter()
+        .map(|output| output.expect("Every future is ready"))
+        .collect()
+}
+
+/// Availability of the webservices of a target
+///
+/// target: State and environment checked
+/// result: Status of the webservice, or the error of the query
+#[derive(Debug, Clone, PartialEq)]
+pub struct Availability {
+    pub target: MonitorTarget,
+    pub result: Result<StatusReport, ClientError>,
+}
+
+impl Availability {
+    /// Status of the service, unavailable when the query failed
+    pub fn status(&self) -> ServiceStatus {
+        self.result
+            .as_ref()
+            .map_or(ServiceStatus::Unavailable, |report| report.status)
+    }
+
+    /// Average processing time informed by the authorizer (tMed)
+    pub fn average_time(&self) -> Option<Duration> {
+        let report = self.result.as_ref().ok()?;
+        report
+            .response
+            .average_time
+            .map(|seconds| Duration::from_secs(seconds.into()))
+    }
+
+    /// Version of the application of the authorizer (verAplic)
+    pub fn application_version(&self) -> Option<&str> {
+        self.result
+            .as_ref()
+            .ok()
+            .map(|report| report.response.application_version.as_str())
+    }
+
+    /// Time taken by the query, when it was answered
+    pub fn response_time(&self) -> Option<Duration> {
+        self.result.as_ref().ok().map(|report| report.response_time)
+    }
+}
+
+/// Availability of every target of the monitor, in the order they were added
+#[derive(Debug, Clone, PartialEq)]
+pub struct AvailabilityReport {
+    pub entries: Vec<Availability>,
+}
+
+impl AvailabilityReport {
+    /// Availability of the state in the environment, if monitored
+    pub fn get(&self, state: &State, environment: &Environment) -> Option<&Availability> {
+        self.entries
+            .iter()
+            .find(|entry| entry.target.state == *state && entry.target.environment == *environment)
+    }
+
+    /// Whether every target is operational
+    pub fn all_operational(&self) -> bool {
+        self.entries
+            .iter()
+            .all(|entry| entry.status() == ServiceStatus::Operational)
+    }
+
+    /// Targets that are not operational, paused or unavailable
+    pub fn unavailable(&self) -> impl Iterator<Item = &Availability> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.status() != ServiceStatus::Operational)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::soap::{StatusResponse, parse_status_response};
+
+    fn setup_report(status: u16) -> StatusReport {
+        let response = parse_status_response(include_str!(
+            "../tests/fixtures/soap/ret_cons_stat_serv.xml"
+        ))
+        .unwrap();
+        let response = StatusResponse { status, ..response };
+        StatusReport {
+            status: ServiceStatus::from(&response),
+            response_time: Duration::from_millis(300),
+            response,
+        }
+    }
+
+    #[test]
+    fn test_targets() {
+        let monitor = Monitor::all_states(Environment::Production);
+        assert_eq!(monitor.targets().len(), 27);
+
+        let monitor = Monitor::new()
+            .with_states(
+                [State::SaoPaulo, State::MinasGerais, State::SaoPaulo],
+                Environment::Production,
+            )
+            .with_target(State::SaoPaulo, Environment::Homologation);
+        assert_eq!(monitor.targets().len(), 3);
+    }
+
+    #[test]
+    fn test_check() {
+        let monitor = Monitor::new().with_states(
+            [State::SaoPaulo, State::MinasGerais, State::Bahia],
+            Environment::Production,
+        );
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .start_paused(true)
+            .build()
+            .unwrap();
+
+        let (report, elapsed) = runtime.block_on(async {
+            let start = tokio::time::Instant::now();
+            let report = monitor
+                .check_with(|state, _| async move {
+                    tokio::time::sleep(Duration::from_secs(10)).await;
+                    match state {
+                        State::SaoPaulo => Ok(setup_report(107)),
+                        State::MinasGerais => Ok(setup_report(108)),
+                        _ => Err(ClientError::Timeout),
+                    }
+                })
+                .await;
+            (report, start.elapsed())
+        });
+        // The states are queried at the same time
+        assert!(elapsed < Duration::from_secs(20));
+
+        let sao_paulo = report
+            .get(&State::SaoPaulo, &Environment::Production)
+            .unwrap();
+        assert_eq!(sao_paulo.status(), ServiceStatus::Operational);
+        assert_eq!(sao_paulo.average_time(), Some(Duration::from_secs(1)));
+        assert_eq!(sao_paulo.application_version(), Some("MG-NFE-PL009_V4"));
+
+        assert!(!report.all_operational());
+        assert_eq!(
+            report
+                .unavailable()
+                .map(|entry| (entry.target.state.clone(), entry.status()))
+                .collect::<Vec<_>>(),
+            vec![
+                (State::MinasGerais, ServiceStatus::Paused),
+                (State::Bahia, ServiceStatus::Unavailable),
+            ]
+        );
+        assert_eq!(
+            report
+                .get(&State::Bahia, &Environment::Production)
+                .unwrap()
+                .response_time(),
+            None
+        );
+    }
+}
@@ -0,0 +1,361 @@
+//! QR Code (version 2) of the NFC-e, carried in the `infNFeSupl` group
+//!
+//! Online emissions only reference the access key, while offline emissions
+//! (tpEmis = 9) also carry the day of emission, the total value and the digest
+//! of the document, since SEFAZ does not know the document yet. In both cases
+//! the parameters are hashed (SHA-1) together with the CSC token.
+
+use crate::access_key::AccessKey;
+use crate::enums::{EmissionType, Environment, Model};
+use crate::models::{Info, Supplement};
+use crate::states::State;
+use chrono::Datelike;
+use sha1::{Digest, Sha1};
+
+/// Version of the QR Code layout
+pub const QR_CODE_VERSION: u8 = 2;
+
+/// Taxpayer security code (CSC) registered at SEFAZ
+///
+/// id: Identifier of the CSC (idCSC)
+/// token: Secret token of the CSC
+#[derive(Debug, Clone, PartialEq)]
+pub struct CSC {
+    pub id: u32,
+    pub token: String,
+}
+
+/// Largest identifier of a CSC, as idCSC has up to 6 digits
+pub const MAX_CSC_ID: u32 = 999_999;
+
+/// Largest length of the token of a CSC
+pub const MAX_CSC_TOKEN_LENGTH: usize = 36;
+
+impl CSC {
+    pub fn new(id: u32, token: String) -> Self {
+        CSC { id, token }
+    }
+
+    /// Checks the identifier and the token against the limits of SEFAZ
+    pub fn validate(&self) -> Result<(), QRCodeError> {
+        if self.id == 0 || self.id > MAX_CSC_ID {
+            return Err(QRCodeError::InvalidCSCId(self.id));
+        }
+        if self.token.is_empty() {
+            return Err(QRCodeError::EmptyToken);
+        }
+        if self.token.len() > MAX_CSC_TOKEN_LENGTH
+            || !self.token.chars().all(|c| c.is_ascii_alphanumeric())
+        {
+            return Err(QRCodeError::InvalidCSCToken);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QRCodeError {
+    NotNFCe,
+    EmptyToken,
+    /// Identifier out of 1 to `MAX_CSC_ID`
+    InvalidCSCId(u32),
+    /// Token longer than `MAX_CSC_TOKEN_LENGTH` or with other than letters and digits
+    InvalidCSCToken,
+    /// No CSC configured for the environment of the document
+    MissingCSC(Environment),
+    /// Key of an offline emission, whose QR Code needs the document
+    OfflineKey,
+}
+
+/// Base URL of the QR Code page of each state
+pub fn qr_code_url(state: &State, environment: &Environment) -> &'static str {
+    let production = *environment == Environment::Production;
+    match state {
+        State::Acre if production => "http://www.sefaznet.ac.gov.br/nfce/qrcode",
+        State::Acre => "http://www.hml.sefaznet.ac.gov.br/nfce/qrcode",
+        State::Alagoas => "http://nfce.sefaz.al.gov.br/QRCode/consultarNFCe.jsp",
+        State::Amapa if production => "https://www.sefaz.ap.gov.br/nfce/nfcep.php",
+        State::Amapa => "https://www.sefaz.ap.gov.br/nfcehml/nfce.php",
+        State::Amazonas if production => {
+            "https://sistemas.sefaz.am.gov.br/nfceweb/consultarNFCe.jsp"
+        }
+        State::Amazonas => "https://sistemas.sefaz.am.gov.br/nfceweb-hom/consultarNFCe.jsp",
+        State::Bahia if production => "http://nfe.sefaz.ba.gov.br/servicos/nfce/qrcode.aspx",
+        State::Bahia => "http://hnfe.sefaz.ba.gov.br/servicos/nfce/qrcode.aspx",
+        State::Ceara if production => "http://nfce.sefaz.ce.gov.br/pages/ShowNFCe.html",
+        State::Ceara => "http://nfceh.sefaz.ce.gov.br/pages/ShowNFCe.html",
+        State::DistritoFederal => "http://www.fazenda.df.gov.br/nfce/qrcode",
+        State::EspiritoSanto if production => "http://app.sefaz.es.gov.br/ConsultaNFCe/qrcode.aspx",
+        State::EspiritoSanto => "http://homologacao.sefaz.es.gov.br/ConsultaNFCe/qrcode.aspx",
+        State::Goias if production => "https://nfeweb.sefaz.go.gov.br/nfeweb/sites/nfce/danfeNFCe",
+        State::Goias => "https://nfewebhomolog.sefaz.go.gov.br/nfeweb/sites/nfce/danfeNFCe",
+        State::Maranhao if production => "http://www.nfce.sefaz.ma.gov.br/portal/consultarNFCe.jsp",
+        State::Maranhao => "http://www.hom.nfce.sefaz.ma.gov.br/portal/consultarNFCe.jsp",
+        State::MatoGrosso if production => "http://www.sefaz.mt.gov.br/nfce/consultanfce",
+        State::MatoGrosso => "http://homologacao.sefaz.mt.gov.br/nfce/consultanfce",
+        State::MatoGrossoDoSul => "http://www.dfe.ms.gov.br/nfce/qrcode",
+        State::MinasGerais if production => {
+            "https://portalsped.fazenda.mg.gov.br/portalnfce/sistema/qrcode.xhtml"
+        }
+        State::MinasGerais => {
+            "https://hportalsped.fazenda.mg.gov.br/portalnfce/sistema/qrcode.xhtml"
+        }
+        State::Para if production => {
+            "https://appnfc.sefa.pa.gov.br/portal/view/consultas/nfce/nfceForm.seam"
+        }
+        State::Para => {
+            "https://appnfc.sefa.pa.gov.br/portal-homologacao/view/consultas/nfce/nfceForm.seam"
+        }
+        State::Paraiba if production => "http://www.sefaz.pb.gov.br/nfce",
+        State::Paraiba => "http://www.sefaz.pb.gov.br/nfcehom",
+        State::Parana => "http://www.fazenda.pr.gov.br/nfce/qrcode",
+        State::Pernambuco if production => "http://nfce.sefaz.pe.gov.br/nfce/consulta",
+        State::Pernambuco => "http://nfcehomolog.sefaz.pe.gov.br/nfce/consulta",
+        State::Piaui => "http://www.sefaz.pi.gov.br/nfce/qrcode",
+        State::RioDeJaneiro => "https://consultadfe.fazenda.rj.gov.br/consultaNFCe/QRCode",
+        State::RioGrandeDoNorte if production => "http://nfce.set.rn.gov.br/consultarNFCe.aspx",
+        State::RioGrandeDoNorte => "http://hom.nfce.set.rn.gov.br/consultarNFCe.aspx",
+        State::RioGrandeDoSul => "https://www.sefaz.rs.gov.br/NFCE/NFCE-COM.aspx",
+        State::Rondonia => "http://www.nfce.sefin.ro.gov.br/consultanfce/consulta.jsp",
+        State::Roraima if production => "https://www.sefaz.rr.gov.br/servlet/qrcode",
+        State::Roraima => "http://200.174.88.103:8080/nfce/servlet/qrcode",
+        State::SantaCatarina if production => "https://sat.sef.sc.gov.br/nfce/consulta",
+        State::SantaCatarina => "https://hom.sat.sef.sc.gov.br/nfce/consulta",
+        State::SaoPaulo if production => {
+            "https://www.nfce.fazenda.sp.gov.br/NFCeConsultaPublica/Paginas/ConsultaQRCode.aspx"
+        }
+        State::SaoPaulo => {
+            "https://www.homologacao.nfce.fazenda.sp.gov.br/NFCeConsultaPublica/Paginas/ConsultaQRCode.aspx"
+        }
+        State::Sergipe if production => "http://www.nfce.se.gov.br/nfce/qrcode",
+        State::Sergipe => "http://www.hom.nfe.se.gov.br/nfce/qrcode",
+        State::Tocantins => "http://www.sefaz.to.gov.br/nfce/qrcode",
+    }
+}
+
+/// URL of the access key consultation page of each state (urlChave)
+pub fn consultation_url(state: &State, environment: &Environment) -> &'static str {
+    let production = *environment == Environment::Production;
+    match state {
+        State::Acre => "www.sefaznet.ac.gov.br/nfce/consulta",
+        State::Alagoas => "www.sefaz.al.gov.br/nfce/consulta",
+        State::Amapa => "www.sefaz.ap.gov.br/nfce/consulta",
+        State::Amazonas => "www.sefaz.am.gov.br/nfce/consulta",
+        State::Bahia if production => "http://www.sefaz.ba.gov.br/nfce/consulta",
+        State::Bahia => "http://hinternet.sefaz.ba.gov.br/nfce/consulta",
+        State::Ceara => "www.sefaz.ce.gov.br/nfce/consulta",
+        State::DistritoFederal => "www.fazenda.df.gov.br/nfce/consulta",
+        State::EspiritoSanto => "www.sefaz.es.gov.br/nfce/consulta",
+        State::Goias => "www.sefaz.go.gov.br/nfce/consulta",
+        State::Maranhao => "www.sefaz.ma.gov.br/nfce/consulta",
+        State::MatoGrosso => "www.sefaz.mt.gov.br/nfce/consulta",
+        State::MatoGrossoDoSul => "www.dfe.ms.gov.br/nfce/consulta",
+        State::MinasGerais if production => "https://portalsped.fazenda.mg.gov.br/portalnfce",
+        State::MinasGerais => "https://hportalsped.fazenda.mg.gov.br/portalnfce",
+        State::Para => "www.sefa.pa.gov.br/nfce/consulta",
+        State::Paraiba if production => "www.sefaz.pb.gov.br/nfce/consulta",
+        State::Paraiba => "www.sefaz.pb.gov.br/nfcehom",
+        State::Parana => "www.fazenda.pr.gov.br/nfce/consulta",
+        State::Pernambuco => "nfce.sefaz.pe.gov.br/nfce/consulta",
+        State::Piaui => "www.sefaz.pi.gov.br/nfce/consulta",
+        State::RioDeJaneiro => "www.fazenda.rj.gov.br/nfce/consulta",
+        State::RioGrandeDoNorte => "www.set.rn.gov.br/nfce/consulta",
+        State::RioGrandeDoSul => "www.sefaz.rs.gov.br/nfce/consulta",
+        State::Rondonia => "www.sefin.ro.gov.br/nfce/consulta",
+        State::Roraima => "www.sefaz.rr.gov.br/nfce/consulta",
+        State::SantaCatarina if production => "https://sat.sef.sc.gov.br/nfce/consulta",
+        State::SantaCatarina => "https://hom.sat.sef.sc.gov.br/nfce/consulta",
+        State::SaoPaulo if production => "https://www.nfce.fazenda.sp.gov.br/NFCeConsultaPublica",
+        State::SaoPaulo => "https://www.homologacao.nfce.fazenda.sp.gov.br/NFCeConsultaPublica",
+        State::Sergipe => "www.sefaz.se.gov.br/nfce/consulta",
+        State::Tocantins => "www.sefaz.to.gov.br/nfce/consulta",
+    }
+}
+
+fn to_hex(input: &[u8]) -> String {
+    input.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+/// Builds the `p` parameter of the QR Code, including its hash
+///
+/// `digest` is the DigestValue of the signature, only used by offline emissions.
+pub fn parameters(info: &Info, digest: &str, csc: &CSC) -> Result<String, QRCodeError> {
+    let identification = &info.identification;
+    if identification.model != Model::NFCe {
+        return Err(QRCodeError::NotNFCe);
+    }
+    if csc.token.is_empty() {
+        return Err(QRCodeError::EmptyToken);
+    }
+
+    let mut parameters = vec![
+        info.access_key().to_string(),
+        QR_CODE_VERSION.to_string(),
+        identification.environment.code().to_string(),
+    ];
+    if identification.emission_type == EmissionType::Offline {
+        parameters.push(format!("{:02}", identification.local_emission_date().day()));
+        parameters.push(info.total.icms.total.to_string());
+        parameters.push(to_hex(digest.as_bytes()));
+    }
+    parameters.push(csc.id.to_string());
+    Ok(hashed(parameters, csc))
+}
+
+/// Joins the parameters followed by their hash with the CSC token
+fn hashed(parameters: Vec<String>, csc: &CSC) -> String {
+    let joined = parameters.join("|");
+    let hash = Sha1::digest(format!("{}{}", joined, csc.token).as_bytes());
+    format!("{}|{}", joined, to_hex(&hash))
+}
+
+/// Builds the QR Code and the consultation URL (urlChave) of an online NFC-e
+/// from its key alone, as printed by the receipts next to the QR Code
+pub fn online_supplement(
+    key: &AccessKey,
+    environment: &Environment,
+    csc: &CSC,
+) -> Result<Supplement, QRCodeError> {
+    if key.model() != Model::NFCe {
+        return Err(QRCodeError::NotNFCe);
+    }
+    if key.emission_type() == EmissionType::Offline {
+        return Err(QRCodeError::OfflineKey);
+    }
+    if csc.token.is_empty() {
+        return Err(QRCodeError::EmptyToken);
+    }
+
+    let parameters = vec![
+        key.to_string(),
+        QR_CODE_VERSION.to_string(),
+        environment.code().to_string(),
+        csc.id.to_string(),
+    ];
+    let state = key.state();
+    Ok(Supplement {
+        qr_code: format!(
+            "{}?p={}",
+            qr_code_url(&state, environment),
+            hashed(parameters, csc)
+        ),
+        url: key.consultation_url(environment).to_string(),
+    })
+}
+
+/// Generates the supplementary information (infNFeSupl) of a NFC-e
+pub fn generate(info: &Info, digest: &str, csc: &CSC) -> Result<Supplement, QRCodeError> {
+    let state = &info.identification.location.state;
+    let environment = &info.identification.environment;
+
+    Ok(Supplement {
+        qr_code: format!(
+            "{}?p={}",
+            qr_code_url(state, environment),
+            parameters(info, digest, csc)?
+        ),
+        url: consultation_url(state, environment).to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::tests::setup_info_builder;
+
+    const DIGEST: &str = "oUXFqAZS2BejMb22mU0r/DBzolo=";
+
+    fn setup_csc() -> CSC {
+        CSC::new(1, "0123456789ABCDEF".to_string())
+    }
+
+    #[test]
+    fn test_online_qr_code() {
+        let info = setup_info_builder().build().unwrap();
+        let supplement = generate(&info, DIGEST, &setup_csc()).unwrap();
+
+        assert_eq!(
+            supplement.qr_code,
+            concat!(
+                "https://portalsped.fazenda.mg.gov.br/portalnfce/sistema/qrcode.xhtml?p=",
+                "31231012345678000195650010000123451123456783|2|1|1|",
+                "77B7D967C978705F6C13E167A044FFB542E53EC6"
+            )
+        );
+        assert_eq!(
+            supplement.url,
+            "https://portalsped.fazenda.mg.gov.br/portalnfce"
+        );
+    }
+
+    #[test]
+    fn test_online_supplement() {
+        let mut info = setup_info_builder().build().unwrap();
+        let environment = info.identification.environment.clone();
+        assert_eq!(
+            online_supplement(&info.access_key(), &environment, &setup_csc()),
+            generate(&info, DIGEST, &setup_csc())
+        );
+
+        info.identification.emission_type = EmissionType::Offline;
+        assert_eq!(
+            online_supplement(&info.access_key(), &environment, &setup_csc()),
+            Err(QRCodeError::OfflineKey)
+        );
+    }
+
+    #[test]
+    fn test_offline_qr_code() {
+        let mut info = setup_info_builder().build().unwrap();
+        info.identification.emission_type = EmissionType::Offline;
+
+        assert_eq!(
+            parameters(&info, DIGEST, &setup_csc()).unwrap(),
+            concat!(
+                "31231012345678000195650010000123459123456789|2|1|05|113.94|",
+                "6F55584671415A533242656A4D6232326D5530722F44427A6F6C6F3D|1|",
+                "BC411F812738DDD3EDDB72D3ED7BA794DEF19346"
+            )
+        );
+    }
+
+    #[test]
+    fn test_qr_code_errors() {
+        let mut info = setup_info_builder().build().unwrap();
+        assert_eq!(
+            generate(&info, DIGEST, &CSC::new(1, String::new())),
+            Err(QRCodeError::EmptyToken)
+        );
+
+        info.identification.model = Model::NFe;
+        assert_eq!(
+            generate(&info, DIGEST, &setup_csc()),
+            Err(QRCodeError::NotNFCe)
+        );
+    }
+
+    #[test]
+    fn test_validate_csc() {
+        assert_eq!(setup_csc().validate(), Ok(()));
+        assert_eq!(
+            CSC::new(0, "0123456789ABCDEF".to_string()).validate(),
+            Err(QRCodeError::InvalidCSCId(0))
+        );
+        assert_eq!(
+            CSC::new(1_000_000, "0123456789ABCDEF".to_string()).validate(),
+            Err(QRCodeError::InvalidCSCId(1_000_000))
+        );
+        assert_eq!(
+            CSC::new(1, String::new()).validate(),
+            Err(QRCodeError::EmptyToken)
+        );
+        assert_eq!(
+            CSC::new(1, "0123-4567".to_string()).validate(),
+            Err(QRCodeError::InvalidCSCToken)
+        );
+        assert_eq!(
+            CSC::new(1, "A".repeat(37)).validate(),
+            Err(QRCodeError::InvalidCSCToken)
+        );
+    }
+}
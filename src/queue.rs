@@ -0,0 +1,586 @@
+//! Persistent queue of the documents issued offline
+//!
+//! NFC-e issued in contingency (tpEmis 9) are handed to the consumer before
+//! being authorized, so they must survive until the connectivity returns. The
+//! queue persists them in a `QueueStorage` and `Queue::flush` transmits them
+//! in the order they were issued, marking each one with its protocol, of
+//! authorization or of denial, or with its final rejection.
+
+use crate::access_key::AccessKey;
+use crate::emitter::rejection_status;
+use crate::models::{NFe, NFeProc, Protocol, ProtocolError};
+use crate::registry::DocumentStatus;
+use crate::soap::rejection::RejectionCode;
+use crate::soap::{AuthorizationBatch, Client, ClientError};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueueError {
+    /// The storage could not be read or written
+    Io(String),
+    Serialization(String),
+    Deserialization(String),
+    /// The protocol returned for a document is not the one of the document
+    Protocol(ProtocolError),
+}
+
+/// Final rejection of a queued document
+///
+/// status: Status code of the rejection (cStat)
+/// reason: Description of the rejection (xMotivo)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Rejection {
+    #[serde(rename = "cStat")]
+    pub status: u16,
+    #[serde(rename = "xMotivo")]
+    pub reason: String,
+}
+
+impl Rejection {
+    pub fn code(&self) -> RejectionCode {
+        self.status.into()
+    }
+}
+
+/// Situation of a document of the queue
+#[derive(Debug, PartialEq)]
+pub enum QueueStatus {
+    /// Waiting for the transmission
+    Pending,
+    Authorized(Protocol),
+    /// Use denied, the key can not be used again
+    Denied(Protocol),
+    Rejected(Rejection),
+}
+
+/// Storage of the documents of the queue
+///
+/// Documents are identified by their access key. `pending` must return them
+/// in the order of `push`, so they are transmitted in the order of issue.
+pub trait QueueStorage {
+    /// Persists the signed document at the end of the queue
+    fn push(&mut self, nfe: &NFe) -> Result<(), QueueError>;
+
+    /// Documents waiting for transmission, in the order they were pushed
+    fn pending(&self) -> Result<Vec<NFe>, QueueError>;
+
+    /// Removes the document from the pending ones, keeping it with its protocol
+    fn mark_authorized(&mut self, nfe_proc: &NFeProc) -> Result<(), QueueError>;
+
+    /// Removes the document from the pending ones, keeping it with its
+    /// protocol of denial
+    ///
+    /// Defaults to `mark_authorized`, the status telling the denials apart by
+    /// their protocol.
+    fn mark_denied(&mut self, nfe_proc: &NFeProc) -> Result<(), QueueError> {
+        self.mark_authorized(nfe_proc)
+    }
+
+    /// Removes the document from the pending ones, keeping it with its rejection
+    fn mark_rejected(&mut self, nfe: &NFe, rejection: &Rejection) -> Result<(), QueueError>;
+
+    /// Situation of the document, or None when it was never pushed
+    fn status(&self, access_key: &AccessKey) -> Result<Option<QueueStatus>, QueueError>;
+}
+
+impl<S: QueueStorage + ?Sized> QueueStorage for Box<S> {
+    fn push(&mut self, nfe: &NFe) -> Result<(), QueueError> {
+        (**self).push(nfe)
+    }
+
+    fn pending(&self) -> Result<Vec<NFe>, QueueError> {
+        (**self).pending()
+    }
+
+    fn mark_authorized(&mut self, nfe_proc: &NFeProc) -> Result<(), QueueError> {
+        (**self).mark_authorized(nfe_proc)
+    }
+
+    fn mark_denied(&mut self, nfe_proc: &NFeProc) -> Result<(), QueueError> {
+        (**self).mark_denied(nfe_proc)
+    }
+
+    fn mark_rejected(&mut self, nfe: &NFe, rejection: &Rejection) -> Result<(), QueueError> {
+        (**self).mark_rejected(nfe, rejection)
+    }
+
+    fn status(&self, access_key: &AccessKey) -> Result<Option<QueueStatus>, QueueError> {
+        (**self).status(access_key)
+    }
+}
+
+/// Storage of the queue in a directory, as XML files
+///
+/// The pending documents are kept in `pending/`, prefixed with their position
+/// in the queue. Authorized documents are moved to `authorized/` as nfeProc
+/// (`<key>-procNFe.xml`), denied ones to `denied/` as well, and rejected ones
+/// to `rejected/`, along with their rejection (`<key>-rej.xml`).
+pub struct FileStorage {
+    directory: PathBuf,
+}
+
+impl FileStorage {
+    /// Storage in `directory`, created with its subdirectories when missing
+    pub fn new(directory: impl Into<PathBuf>) -> Result<Self, QueueError> {
+        let directory = directory.into();
+        for subdirectory in ["pending", "authorized", "denied", "rejected"] {
+            std::fs::create_dir_all(directory.join(subdirectory))
+                .map_err(|e| QueueError::Io(e.to_string()))?;
+        }
+        Ok(FileStorage { directory })
+    }
+
+    /// Files of the pending documents, sorted by their position in the queue
+    fn pending_files(&self) -> Result<Vec<PathBuf>, QueueError> {
+        let mut files = std::fs::read_dir(self.directory.join("pending"))
+            .map_err(|e| QueueError::Io(e.to_string()))?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| QueueError::Io(e.to_string()))?;
+        files.retain(|file| file.extension().is_some_and(|extension| extension == "xml"));
+        files.sort();
+        Ok(files)
+    }
+
+    fn pending_file(&self, access_key: &str) -> Result<Option<PathBuf>, QueueError> {
+        Ok(self.pending_files()?.into_iter().find(|file| {
+            file.file_stem()
+                .and_then(|stem| stem.to_str())
+                .is_some_and(|stem| stem.ends_with(access_key))
+        }))
+    }
+
+    fn remove_pending(&self, access_key: &str) -> Result<(), QueueError> {
+        if let Some(file) = self.pending_file(access_key)? {
+            std::fs::remove_file(file).map_err(|e| QueueError::Io(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Moves the document to `subdirectory` as nfeProc
+    fn write_proc(&self, subdirectory: &str, nfe_proc: &NFeProc) -> Result<(), QueueError> {
+        let access_key = nfe_proc.nfe.info.access_key().to_string();
+        let file = format!("{}-procNFe.xml", access_key);
+        self.write(
+            self.directory.join(subdirectory).join(file),
+            "nfeProc",
+            nfe_proc,
+        )?;
+        self.remove_pending(&access_key)
+    }
+
+    fn write<T: Serialize>(&self, path: PathBuf, root: &str, value: &T) -> Result<(), QueueError> {
+        let xml = quick_xml::se::to_string_with_root(root, value)
+            .map_err(|e| QueueError::Serialization(e.to_string()))?;
+        // Written aside and renamed, so a crash never leaves a partial document
+        let temporary = path.with_extension("tmp");
+        std::fs::write(&temporary, xml).map_err(|e| QueueError::Io(e.to_string()))?;
+        std::fs::rename(&temporary, path).map_err(|e| QueueError::Io(e.to_string()))
+    }
+}
+
+fn read<T: for<'de> Deserialize<'de>>(path: &PathBuf) -> Result<T, QueueError> {
+    let xml = std::fs::read_to_string(path).map_err(|e| QueueError::Io(e.to_string()))?;
+    quick_xml::de::from_str(&xml).map_err(|e| QueueError::Deserialization(e.to_string()))
+}
+
+impl QueueStorage for FileStorage {
+    fn push(&mut self, nfe: &NFe) -> Result<(), QueueError> {
+        let position = self
+            .pending_files()?
+            .last()
+            .and_then(|file| file.file_stem()?.to_str()?.split('-').next()?.parse().ok())
+            .map_or(1, |position: u64| position + 1);
+        let file = format!("{:010}-{}.xml", position, nfe.info.access_key());
+        self.write(self.directory.join("pending").join(file), "NFe", nfe)
+    }
+
+    fn pending(&self) -> Result<Vec<NFe>, QueueError> {
+        self.pending_files()?.iter().map(read).collect()
+    }
+
+    fn mark_authorized(&mut self, nfe_proc: &NFeProc) -> Result<(), QueueError> {
+        self.write_proc("authorized", nfe_proc)
+    }
+
+    fn mark_denied(&mut self, nfe_proc: &NFeProc) -> Result<(), QueueError> {
+        self.write_proc("denied", nfe_proc)
+    }
+
+    fn mark_rejected(&mut self, nfe: &NFe, rejection: &Rejection) -> Result<(), QueueError> {
+        let access_key = nfe.info.access_key().to_string();
+        let directory = self.directory.join("rejected");
+        self.write(directory.join(format!("{}.xml", access_key)), "NFe", nfe)?;
+        self.write(
+            directory.join(format!("{}-rej.xml", access_key)),
+            "rejeicao",
+            rejection,
+        )?;
+        self.remove_pending(&access_key)
+    }
+
+    fn status(&self, access_key: &AccessKey) -> Result<Option<QueueStatus>, QueueError> {
+        let authorized = self
+            .directory
+            .join("authorized")
+            .join(format!("{}-procNFe.xml", access_key));
+        if authorized.exists() {
+            let nfe_proc: NFeProc = read(&authorized)?;
+            return Ok(Some(QueueStatus::Authorized(nfe_proc.protocol)));
+        }
+        let denied = self
+            .directory
+            .join("denied")
+            .join(format!("{}-procNFe.xml", access_key));
+        if denied.exists() {
+            let nfe_proc: NFeProc = read(&denied)?;
+            return Ok(Some(QueueStatus::Denied(nfe_proc.protocol)));
+        }
+        let rejected = self
+            .directory
+            .join("rejected")
+            .join(format!("{}-rej.xml", access_key));
+        if rejected.exists() {
+            return Ok(Some(QueueStatus::Rejected(read(&rejected)?)));
+        }
+        Ok(self
+            .pending_file(access_key.as_str())?
+            .map(|_| QueueStatus::Pending))
+    }
+}
+
+/// Result of a flush of the queue
+///
+/// authorized: Documents authorized, in the order they were sent
+/// denied: Documents of which the use was denied, in the order they were sent
+/// rejected: Documents rejected, in the order they were sent
+/// interrupted: Failure that stopped the flush, as the connectivity still
+/// missing - None when every document was sent
+/// pending: Documents still waiting for transmission
+#[derive(Debug, PartialEq, Default)]
+pub struct FlushReport {
+    pub authorized: Vec<AccessKey>,
+    pub denied: Vec<AccessKey>,
+    pub rejected: Vec<AccessKey>,
+    pub interrupted: Option<ClientError>,
+    pub pending: usize,
+}
+
+/// Queue of the documents issued offline
+pub struct Queue<S: QueueStorage> {
+    storage: S,
+}
+
+impl<S: QueueStorage> Queue<S> {
+    pub fn new(storage: S) -> Self {
+        Queue { storage }
+    }
+
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+
+    /// Persists the signed document until it is transmitted
+    pub fn push(&mut self, nfe: &NFe) -> Result<(), QueueError> {
+        self.storage.push(nfe)
+    }
+
+    /// Situation of the document, or None when it was never queued
+    pub fn status(&self, access_key: &AccessKey) -> Result<Option<QueueStatus>, QueueError> {
+        self.storage.status(access_key)
+    }
+
+    /// Transmits the pending documents in order, each one in a synchronous batch
+    ///
+    /// The batches are numbered from `first_batch_id`. The documents rejected
+    /// as already authorized (204) or denied (205), sent before a failure of
+    /// the connection, get the protocol of their key from the protocol query.
+    pub async fn flush(
+        &mut self,
+        client: &Client,
+        first_batch_id: u64,
+    ) -> Result<FlushReport, QueueError> {
+        let mut ids = first_batch_id..;
+        self.flush_with(|nfe| {
+            let id = ids.next().unwrap_or(first_batch_id);
+            async move {
+                let mut batch = AuthorizationBatch::single(id, nfe);
+                let result =
+                    client
+                        .authorize(&batch)
+                        .await
+                        .and_then(|response| match response.protocol {
+                            Some(protocol) if protocol.info.is_denied() => Ok(protocol),
+                            _ => response.into_protocol(),
+                        });
+                let nfe = batch.documents.remove(0);
+                let result = match result {
+                    Err(ClientError::Rejected(code, reason))
+                        if rejection_status(&code) != DocumentStatus::Draft =>
+                    {
+                        let environment = nfe.info.identification.environment.clone();
+                        match client
+                            .query_status(&nfe.info.access_key(), environment)
+                            .await
+                        {
+                            Ok(response) => {
+                                response.protocol.ok_or(ClientError::Rejected(code, reason))
+                            }
+                            Err(error) => Err(error),
+                        }
+                    }
+                    result => result,
+                };
+                (nfe, result)
+            }
+        })
+        .await
+    }
+
+    /// Transmits the pending documents in order with `send`
+    ///
+    /// `send` returns the document along with its protocol, of authorization
+    /// or of denial, or its failure. The definitive rejections mark the
+    /// document, while the other failures, as timeouts or the service paused,
+    /// stop the flush, so the remaining documents keep their order for the
+    /// next one. So do the rejections of the keys already used (204, 205),
+    /// which are not final without the protocol of the key.
+    pub async fn flush_with<F, Fut>(&mut self, mut send: F) -> Result<FlushReport, QueueError>
+    where
+        F: FnMut(NFe) -> Fut,
+        Fut: Future<Output = (NFe, Result<Protocol, ClientError>)>,
+    {
+        let mut report = FlushReport::default();
+        let mut pending = self.storage.pending()?.into_iter();
+
+        for nfe in pending.by_ref() {
+            let (nfe, result) = send(nfe).await;
+            let access_key = nfe.info.access_key();
+            match result {
+                Ok(protocol) => {
+                    let nfe_proc = nfe.into_proc(protocol).map_err(QueueError::Protocol)?;
+                    if nfe_proc.protocol.info.is_denied() {
+                        self.storage.mark_denied(&nfe_proc)?;
+                        report.denied.push(access_key);
+                    } else {
+                        self.storage.mark_authorized(&nfe_proc)?;
+                        report.authorized.push(access_key);
+                    }
+                }
+                Err(ClientError::Rejected(status, reason))
+                    if !status.is_retryable()
+                        && rejection_status(&status) == DocumentStatus::Draft =>
+                {
+                    let rejection = Rejection {
+                        status: status.code(),
+                        reason,
+                    };
+                    self.storage.mark_rejected(&nfe, &rejection)?;
+                    report.rejected.push(access_key);
+                }
+                Err(error) => {
+                    report.interrupted = Some(error);
+                    report.pending = 1;
+                    break;
+                }
+            }
+        }
+
+        report.pending += pending.count();
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::enums::Environment;
+    use crate::models::ProtocolInfo;
+    use crate::models::tests::setup_nfe;
+    use chrono::DateTime;
+
+    pub fn setup_directory(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("nf-e-queue-{}-{}", name, rand::random::<u32>()));
+        let _ = std::fs::remove_dir_all(&directory);
+        directory
+    }
+
+    fn setup_numbered_nfe(number: u32) -> NFe {
+        let mut nfe = setup_nfe();
+        nfe.info.identification.number = number;
+        nfe
+    }
+
+    fn protocol_of(nfe: &NFe, status: u16) -> Protocol {
+        Protocol {
+            version: "4.00".to_string(),
+            info: ProtocolInfo {
+                id: None,
+                environment: Environment::Homologation,
+                application_version: "SVRS202401011200".to_string(),
+                access_key: nfe.info.access_key().to_string(),
+                received_at: DateTime::parse_from_rfc3339("2025-08-01T10:00:01-03:00").unwrap(),
+                number: Some("131250000000001".to_string()),
+                digest: None,
+                status,
+                reason: "Autorizado o uso da NF-e".to_string(),
+            },
+        }
+    }
+
+    fn block_on<T>(future: impl Future<Output = T>) -> T {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn test_file_storage() {
+        let directory = setup_directory("storage");
+        let mut storage = FileStorage::new(&directory).unwrap();
+        let first = setup_numbered_nfe(1);
+        let second = setup_numbered_nfe(2);
+        storage.push(&first).unwrap();
+        storage.push(&second).unwrap();
+
+        let pending = storage.pending().unwrap();
+        assert_eq!(pending, vec![setup_numbered_nfe(1), setup_numbered_nfe(2)]);
+        assert_eq!(
+            storage.status(&first.info.access_key()).unwrap(),
+            Some(QueueStatus::Pending)
+        );
+
+        let rejection = Rejection {
+            status: 539,
+            reason: "Duplicidade de NF-e com diferenca na Chave de Acesso".to_string(),
+        };
+        storage.mark_rejected(&first, &rejection).unwrap();
+        assert_eq!(
+            storage.status(&first.info.access_key()).unwrap(),
+            Some(QueueStatus::Rejected(rejection))
+        );
+        assert_eq!(storage.pending().unwrap(), vec![setup_numbered_nfe(2)]);
+
+        let third = setup_numbered_nfe(3);
+        storage.push(&third).unwrap();
+        assert_eq!(
+            storage.pending().unwrap(),
+            vec![setup_numbered_nfe(2), setup_numbered_nfe(3)]
+        );
+        assert_eq!(
+            storage
+                .status(&setup_numbered_nfe(4).info.access_key())
+                .unwrap(),
+            None
+        );
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_flush() {
+        let directory = setup_directory("flush");
+        let mut queue = Queue::new(FileStorage::new(&directory).unwrap());
+        for number in 1..=4 {
+            queue.push(&setup_numbered_nfe(number)).unwrap();
+        }
+
+        let mut sent = Vec::new();
+        let report = block_on(queue.flush_with(|nfe| {
+            let number = nfe.info.identification.number;
+            sent.push(number);
+            let result = match number {
+                1 => Ok(protocol_of(&nfe, 100)),
+                2 => Err(ClientError::Rejected(
+                    RejectionCode::from(225),
+                    "Falha no Schema XML".to_string(),
+                )),
+                _ => Err(ClientError::Timeout),
+            };
+            async move { (nfe, result) }
+        }))
+        .unwrap();
+
+        assert_eq!(sent, vec![1, 2, 3]);
+        assert_eq!(
+            report,
+            FlushReport {
+                authorized: vec![setup_numbered_nfe(1).info.access_key()],
+                denied: vec![],
+                rejected: vec![setup_numbered_nfe(2).info.access_key()],
+                interrupted: Some(ClientError::Timeout),
+                pending: 2,
+            }
+        );
+
+        let first = setup_numbered_nfe(1);
+        assert_eq!(
+            queue.status(&first.info.access_key()).unwrap(),
+            Some(QueueStatus::Authorized(protocol_of(&first, 100)))
+        );
+        assert_eq!(
+            queue.storage().pending().unwrap(),
+            vec![setup_numbered_nfe(3), setup_numbered_nfe(4)]
+        );
+
+        let report = block_on(queue.flush_with(|nfe| {
+            let result = Ok(protocol_of(&nfe, 100));
+            async move { (nfe, result) }
+        }))
+        .unwrap();
+        assert_eq!(report.authorized.len(), 2);
+        assert_eq!(report.pending, 0);
+        assert!(queue.storage().pending().unwrap().is_empty());
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_flush_used_keys() {
+        let directory = setup_directory("used");
+        let mut queue = Queue::new(FileStorage::new(&directory).unwrap());
+        for number in 1..=3 {
+            queue.push(&setup_numbered_nfe(number)).unwrap();
+        }
+
+        let report = block_on(queue.flush_with(|nfe| {
+            let result = match nfe.info.identification.number {
+                1 => Ok(protocol_of(&nfe, 302)),
+                _ => Err(ClientError::Rejected(
+                    RejectionCode::DuplicateNFe,
+                    "Duplicidade de NF-e".to_string(),
+                )),
+            };
+            async move { (nfe, result) }
+        }))
+        .unwrap();
+
+        // The duplicity is not final without the protocol of the key
+        let first = setup_numbered_nfe(1);
+        assert_eq!(report.denied, vec![first.info.access_key()]);
+        assert!(report.rejected.is_empty());
+        assert_eq!(report.pending, 2);
+        assert_eq!(
+            queue.status(&first.info.access_key()).unwrap(),
+            Some(QueueStatus::Denied(protocol_of(&first, 302)))
+        );
+        assert_eq!(
+            queue
+                .status(&setup_numbered_nfe(2).info.access_key())
+                .unwrap(),
+            Some(QueueStatus::Pending)
+        );
+
+        let result = block_on(queue.flush_with(|nfe| {
+            let result = Ok(protocol_of(&setup_numbered_nfe(4), 100));
+            async move { (nfe, result) }
+        }));
+        assert!(matches!(result, Err(QueueError::Protocol(_))));
+        assert_eq!(queue.storage().pending().unwrap().len(), 2);
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
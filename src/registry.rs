@@ -0,0 +1,234 @@
+//! Registry of the keys of the documents issued
+//!
+//! SEFAZ rejects a document whose access key was already used (cStat 204,
+//! duplicidade), so a document authorized while its answer was lost must not
+//! be transmitted again. A `DocumentRegistry` keeps the situation of each key
+//! and the `Emitter` refuses to transmit the ones already issued.
+
+use crate::access_key::AccessKey;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RegistryError {
+    /// The registry could not be read or written
+    Io(String),
+    /// Invalid content of the file of the registry
+    Corrupted(String),
+    /// A thread panicked while holding the registry
+    Locked,
+}
+
+/// Situation of a document in the registry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentStatus {
+    /// Built and signed, not sent yet
+    Draft,
+    /// Sent to SEFAZ, without a final answer
+    Transmitted,
+    Authorized,
+    /// Use denied (110, 301, 302...), the key can not be used again
+    Denied,
+    /// Authorized and then cancelled
+    Cancelled,
+}
+
+impl DocumentStatus {
+    /// Whether the key was used by SEFAZ and sending it again is a duplicity
+    pub fn is_issued(&self) -> bool {
+        matches!(
+            self,
+            DocumentStatus::Authorized | DocumentStatus::Denied | DocumentStatus::Cancelled
+        )
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            DocumentStatus::Draft => "draft",
+            DocumentStatus::Transmitted => "transmitted",
+            DocumentStatus::Authorized => "authorized",
+            DocumentStatus::Denied => "denied",
+            DocumentStatus::Cancelled => "cancelled",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        [
+            DocumentStatus::Draft,
+            DocumentStatus::Transmitted,
+            DocumentStatus::Authorized,
+            DocumentStatus::Denied,
+            DocumentStatus::Cancelled,
+        ]
+        .into_iter()
+        .find(|status| status.name() == name)
+    }
+}
+
+/// Registry of the situation of the documents, by access key
+pub trait DocumentRegistry {
+    /// Records the situation of the document, replacing the previous one
+    fn record(&self, access_key: &AccessKey, status: DocumentStatus) -> Result<(), RegistryError>;
+
+    /// Situation of the document, or None when it was never recorded
+    fn status(&self, access_key: &AccessKey) -> Result<Option<DocumentStatus>, RegistryError>;
+}
+
+/// Registry kept in memory, lost when the process ends
+#[derive(Debug, Default)]
+pub struct MemoryRegistry {
+    documents: Mutex<BTreeMap<AccessKey, DocumentStatus>>,
+}
+
+impl MemoryRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl DocumentRegistry for MemoryRegistry {
+    fn record(&self, access_key: &AccessKey, status: DocumentStatus) -> Result<(), RegistryError> {
+        let mut documents = self.documents.lock().map_err(|_| RegistryError::Locked)?;
+        documents.insert(access_key.clone(), status);
+        Ok(())
+    }
+
+    fn status(&self, access_key: &AccessKey) -> Result<Option<DocumentStatus>, RegistryError> {
+        let documents = self.documents.lock().map_err(|_| RegistryError::Locked)?;
+        Ok(documents.get(access_key).copied())
+    }
+}
+
+/// Registry persisted in a text file, one line per change
+///
+/// Each line has the access key and the name of the status, separated by a
+/// space: `35250812345678000195650010000000011123456780 authorized`. Lines are
+/// only appended, the last one of a key is its situation. Only one process
+/// may use the file at a time.
+#[derive(Debug)]
+pub struct FileRegistry {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileRegistry {
+    /// Registry stored at `path`, created on the first record when missing
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        FileRegistry {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+}
+
+impl DocumentRegistry for FileRegistry {
+    fn record(&self, access_key: &AccessKey, status: DocumentStatus) -> Result<(), RegistryError> {
+        let _lock = self.lock.lock().map_err(|_| RegistryError::Locked)?;
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| RegistryError::Io(e.to_string()))?;
+        writeln!(file, "{} {}", access_key, status.name())
+            .and_then(|_| file.sync_data())
+            .map_err(|e| RegistryError::Io(e.to_string()))
+    }
+
+    fn status(&self, access_key: &AccessKey) -> Result<Option<DocumentStatus>, RegistryError> {
+        let _lock = self.lock.lock().map_err(|_| RegistryError::Locked)?;
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(RegistryError::Io(e.to_string())),
+        };
+
+        let mut status = None;
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            let corrupted = || RegistryError::Corrupted(line.to_string());
+            let (key, name) = line.split_once(' ').ok_or_else(corrupted)?;
+            let name = DocumentStatus::from_name(name.trim()).ok_or_else(corrupted)?;
+            if key == access_key.as_str() {
+                status = Some(name);
+            }
+        }
+        Ok(status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access_key::Components;
+    use crate::enums::{CNPJ, EmissionType, Model, PersonDocument};
+    use crate::states::State;
+
+    fn setup_key(number: u32) -> AccessKey {
+        let issuer = PersonDocument::CNPJ(CNPJ("12345678000195".to_string()));
+        AccessKey::new(&Components {
+            state: State::SaoPaulo,
+            year: 25,
+            month: 8,
+            issuer: &issuer,
+            model: Model::NFCe,
+            series: 1,
+            number,
+            emission_type: EmissionType::Normal,
+            numeric_code: 1,
+        })
+    }
+
+    fn check_registry(registry: &dyn DocumentRegistry) {
+        let first = setup_key(1);
+        let second = setup_key(2);
+        assert_eq!(registry.status(&first), Ok(None));
+
+        registry
+            .record(&first, DocumentStatus::Transmitted)
+            .unwrap();
+        registry.record(&second, DocumentStatus::Draft).unwrap();
+        registry.record(&first, DocumentStatus::Authorized).unwrap();
+        assert_eq!(
+            registry.status(&first),
+            Ok(Some(DocumentStatus::Authorized))
+        );
+        assert_eq!(registry.status(&second), Ok(Some(DocumentStatus::Draft)));
+        assert_eq!(registry.status(&setup_key(3)), Ok(None));
+    }
+
+    #[test]
+    fn test_memory_registry() {
+        check_registry(&MemoryRegistry::new());
+    }
+
+    #[test]
+    fn test_file_registry() {
+        let path = std::env::temp_dir().join(format!("nf-e-registry-{}", rand::random::<u32>()));
+        check_registry(&FileRegistry::new(&path));
+
+        // The situations survive the registry
+        let registry = FileRegistry::new(&path);
+        assert_eq!(
+            registry.status(&setup_key(1)),
+            Ok(Some(DocumentStatus::Authorized))
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 3);
+
+        std::fs::write(&path, format!("{} sent\n", setup_key(1))).unwrap();
+        assert!(matches!(
+            registry.status(&setup_key(1)),
+            Err(RegistryError::Corrupted(_))
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_is_issued() {
+        assert!(DocumentStatus::Authorized.is_issued());
+        assert!(DocumentStatus::Denied.is_issued());
+        assert!(DocumentStatus::Cancelled.is_issued());
+        assert!(!DocumentStatus::Draft.is_issued());
+        assert!(!DocumentStatus::Transmitted.is_issued());
+    }
+}
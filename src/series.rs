@@ -0,0 +1,341 @@
+//! Numbering of the documents of each model and series
+//!
+//! A `SeriesCounter` hands out the next number (nNF) of the series and keeps
+//! the numbers reserved for documents that were never issued, as the ones
+//! rejected by SEFAZ. These gaps must be disabled (inutilização) before the
+//! deadline, see `crate::disablement`.
+
+use crate::disablement::MAX_NUMBER;
+use crate::enums::Model;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SeriesError {
+    /// The state of the counter could not be read or written
+    Io(String),
+    /// Invalid content of the file of the counter
+    Corrupted(String),
+    /// A thread panicked while holding the counter
+    Locked,
+    /// Every number of the series was handed out
+    Exhausted(Model, u16),
+}
+
+/// Counter of the numbers of the documents, per model and series
+///
+/// `next` is atomic: concurrent callers never get the same number. The
+/// numbers handed out stay reserved until they are confirmed, for documents
+/// that were issued, or disabled.
+pub trait SeriesCounter {
+    /// Reserves the next number of the series
+    fn next(&self, model: &Model, series: u16) -> Result<u32, SeriesError>;
+
+    /// Marks the number as used by a document issued (authorized, denied or
+    /// in contingency), so it is not a gap
+    fn confirm(&self, model: &Model, series: u16, number: u32) -> Result<(), SeriesError>;
+
+    /// Marks the numbers as disabled, so they are not gaps anymore
+    fn disable(
+        &self,
+        model: &Model,
+        series: u16,
+        numbers: RangeInclusive<u32>,
+    ) -> Result<(), SeriesError>;
+
+    /// Ranges of the numbers reserved and neither confirmed nor disabled
+    ///
+    /// Includes the numbers of the documents still being issued.
+    fn gaps(&self, model: &Model, series: u16) -> Result<Vec<RangeInclusive<u32>>, SeriesError>;
+}
+
+/// Numbering of a single series
+///
+/// last: Last number handed out, 0 before the first
+/// reserved: Numbers handed out and not confirmed nor disabled
+#[derive(Debug, Clone, PartialEq, Default)]
+struct Series {
+    last: u32,
+    reserved: BTreeSet<u32>,
+}
+
+type Key = (u8, u16);
+
+impl Series {
+    fn next(&mut self, model: &Model, series: u16) -> Result<u32, SeriesError> {
+        if self.last >= MAX_NUMBER {
+            return Err(SeriesError::Exhausted(model.clone(), series));
+        }
+        self.last += 1;
+        self.reserved.insert(self.last);
+        Ok(self.last)
+    }
+
+    fn release(&mut self, numbers: RangeInclusive<u32>) {
+        self.last = self.last.max(*numbers.end());
+        self.reserved.retain(|number| !numbers.contains(number));
+    }
+
+    fn gaps(&self) -> Vec<RangeInclusive<u32>> {
+        let mut gaps: Vec<RangeInclusive<u32>> = Vec::new();
+        for &number in &self.reserved {
+            match gaps.last_mut() {
+                Some(gap) if *gap.end() + 1 == number => *gap = *gap.start()..=number,
+                _ => gaps.push(number..=number),
+            }
+        }
+        gaps
+    }
+}
+
+/// Operations of the trait over the series of a counter
+fn apply<T>(
+    series: &mut BTreeMap<Key, Series>,
+    model: &Model,
+    number: u16,
+    operation: impl FnOnce(&mut Series) -> T,
+) -> T {
+    operation(series.entry((model.code(), number)).or_default())
+}
+
+/// Counter kept in memory, lost when the process ends
+#[derive(Debug, Default)]
+pub struct MemoryCounter {
+    series: Mutex<BTreeMap<Key, Series>>,
+}
+
+impl MemoryCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Continues the series after `last`, the last number already issued
+    pub fn starting_after(self, model: &Model, series: u16, last: u32) -> Self {
+        if let Ok(mut counters) = self.series.lock() {
+            apply(&mut counters, model, series, |series| series.last = last);
+        }
+        self
+    }
+
+    fn update<T>(
+        &self,
+        model: &Model,
+        series: u16,
+        operation: impl FnOnce(&mut Series) -> T,
+    ) -> Result<T, SeriesError> {
+        let mut counters = self.series.lock().map_err(|_| SeriesError::Locked)?;
+        Ok(apply(&mut counters, model, series, operation))
+    }
+}
+
+impl SeriesCounter for MemoryCounter {
+    fn next(&self, model: &Model, series: u16) -> Result<u32, SeriesError> {
+        self.update(model, series, |counter| counter.next(model, series))?
+    }
+
+    fn confirm(&self, model: &Model, series: u16, number: u32) -> Result<(), SeriesError> {
+        self.update(model, series, |counter| counter.release(number..=number))
+    }
+
+    fn disable(
+        &self,
+        model: &Model,
+        series: u16,
+        numbers: RangeInclusive<u32>,
+    ) -> Result<(), SeriesError> {
+        self.update(model, series, |counter| counter.release(numbers))
+    }
+
+    fn gaps(&self, model: &Model, series: u16) -> Result<Vec<RangeInclusive<u32>>, SeriesError> {
+        self.update(model, series, |counter| counter.gaps())
+    }
+}
+
+/// Counter persisted in a text file, one line per series
+///
+/// Each line has the model, the series, the last number and the reserved
+/// numbers, separated by spaces: `65 1 120 115 118`. The file is rewritten
+/// aside and renamed on every change, so a crash never loses the numbering.
+/// Only one process may use the file at a time.
+#[derive(Debug)]
+pub struct FileCounter {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileCounter {
+    /// Counter stored at `path`, created on the first change when missing
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        FileCounter {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn load(&self) -> Result<BTreeMap<Key, Series>, SeriesError> {
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+            Err(e) => return Err(SeriesError::Io(e.to_string())),
+        };
+
+        let mut counters = BTreeMap::new();
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            let corrupted = || SeriesError::Corrupted(line.to_string());
+            let mut numbers = line.split_whitespace().map(str::parse::<u32>);
+            let mut field = || {
+                numbers
+                    .next()
+                    .ok_or_else(corrupted)?
+                    .map_err(|_| corrupted())
+            };
+            let model = u8::try_from(field()?).map_err(|_| corrupted())?;
+            let series = u16::try_from(field()?).map_err(|_| corrupted())?;
+            let last = field()?;
+            let reserved = numbers
+                .collect::<Result<BTreeSet<_>, _>>()
+                .map_err(|_| corrupted())?;
+            counters.insert((model, series), Series { last, reserved });
+        }
+        Ok(counters)
+    }
+
+    fn save(&self, counters: &BTreeMap<Key, Series>) -> Result<(), SeriesError> {
+        let mut content = String::new();
+        for ((model, number), series) in counters {
+            content.push_str(&format!("{} {} {}", model, number, series.last));
+            for reserved in &series.reserved {
+                content.push_str(&format!(" {}", reserved));
+            }
+            content.push('\n');
+        }
+        let temporary = self.path.with_extension("tmp");
+        std::fs::write(&temporary, content).map_err(|e| SeriesError::Io(e.to_string()))?;
+        std::fs::rename(&temporary, &self.path).map_err(|e| SeriesError::Io(e.to_string()))
+    }
+
+    fn update<T>(
+        &self,
+        model: &Model,
+        series: u16,
+        operation: impl FnOnce(&mut Series) -> T,
+    ) -> Result<T, SeriesError> {
+        let _lock = self.lock.lock().map_err(|_| SeriesError::Locked)?;
+        let mut counters = self.load()?;
+        let result = apply(&mut counters, model, series, operation);
+        self.save(&counters)?;
+        Ok(result)
+    }
+}
+
+impl SeriesCounter for FileCounter {
+    fn next(&self, model: &Model, series: u16) -> Result<u32, SeriesError> {
+        self.update(model, series, |counter| counter.next(model, series))?
+    }
+
+    fn confirm(&self, model: &Model, series: u16, number: u32) -> Result<(), SeriesError> {
+        self.update(model, series, |counter| counter.release(number..=number))
+    }
+
+    fn disable(
+        &self,
+        model: &Model,
+        series: u16,
+        numbers: RangeInclusive<u32>,
+    ) -> Result<(), SeriesError> {
+        self.update(model, series, |counter| counter.release(numbers))
+    }
+
+    fn gaps(&self, model: &Model, series: u16) -> Result<Vec<RangeInclusive<u32>>, SeriesError> {
+        let _lock = self.lock.lock().map_err(|_| SeriesError::Locked)?;
+        let mut counters = self.load()?;
+        Ok(apply(&mut counters, model, series, |counter| {
+            counter.gaps()
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn check_counter(counter: &dyn SeriesCounter) {
+        let model = Model::NFCe;
+        for expected in 1..=6 {
+            assert_eq!(counter.next(&model, 1), Ok(expected));
+        }
+        assert_eq!(counter.next(&Model::NFe, 1), Ok(1));
+        assert_eq!(counter.next(&model, 2), Ok(1));
+
+        for number in [1, 3, 6] {
+            counter.confirm(&model, 1, number).unwrap();
+        }
+        assert_eq!(counter.gaps(&model, 1), Ok(vec![2..=2, 4..=5]));
+
+        counter.disable(&model, 1, 4..=5).unwrap();
+        assert_eq!(counter.gaps(&model, 1), Ok(vec![2..=2]));
+        assert_eq!(counter.next(&model, 1), Ok(7));
+    }
+
+    #[test]
+    fn test_memory_counter() {
+        check_counter(&MemoryCounter::new());
+
+        let counter = MemoryCounter::new().starting_after(&Model::NFe, 3, 1500);
+        assert_eq!(counter.next(&Model::NFe, 3), Ok(1501));
+        assert_eq!(counter.gaps(&Model::NFe, 3), Ok(vec![1501..=1501]));
+
+        let counter = MemoryCounter::new().starting_after(&Model::NFe, 1, MAX_NUMBER);
+        assert_eq!(
+            counter.next(&Model::NFe, 1),
+            Err(SeriesError::Exhausted(Model::NFe, 1))
+        );
+    }
+
+    #[test]
+    fn test_file_counter() {
+        let path = std::env::temp_dir().join(format!("nf-e-series-{}", rand::random::<u32>()));
+        check_counter(&FileCounter::new(&path));
+
+        // The numbering survives the counter
+        let counter = FileCounter::new(&path);
+        assert_eq!(counter.next(&Model::NFCe, 1), Ok(8));
+        assert_eq!(counter.gaps(&Model::NFCe, 1), Ok(vec![2..=2, 7..=8]));
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "55 1 1 1\n65 1 8 2 7 8\n65 2 1 1\n"
+        );
+
+        std::fs::write(&path, "65 1 abc\n").unwrap();
+        assert!(matches!(
+            counter.next(&Model::NFCe, 1),
+            Err(SeriesError::Corrupted(_))
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_numbers() {
+        let counter = Arc::new(MemoryCounter::new());
+        let handles = (0..4)
+            .map(|_| {
+                let counter = counter.clone();
+                std::thread::spawn(move || {
+                    (0..50)
+                        .map(|_| counter.next(&Model::NFCe, 1).unwrap())
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut numbers = handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>();
+        numbers.sort();
+        assert_eq!(numbers, (1..=200).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,637 @@
+//! XMLDSig enveloped signatures for the NF-e documents
+//!
+//! The referenced element is canonicalized (C14N), hashed with SHA-1 and the
+//! resulting `SignedInfo` is signed with RSA-SHA1 using the A1 certificate
+//! loaded from the configured PKCS#12 file.
+
+use crate::config::{ConfigError, PKCS12Config};
+use crate::enums::{CNPJ, Environment};
+use crate::models::{
+    CanonicalizationMethod, DigestMethod, KeyInfo, Signature, SignatureInfo, SignatureMethod,
+    SignatureReference, SignatureTransforms, X509Data,
+};
+use crate::xml::{canonicalize_xml, find_element, to_namespaced_xml};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use chrono::{DateTime, Utc};
+use p12_keystore::KeyStore;
+use rsa::pkcs1v15::{SigningKey, VerifyingKey};
+use rsa::pkcs8::{DecodePrivateKey, DecodePublicKey, EncodePrivateKey, LineEnding};
+use rsa::signature::{SignatureEncoding, Signer as _, Verifier};
+use rsa::{RsaPrivateKey, RsaPublicKey};
+use sha1::{Digest, Sha1};
+use std::sync::Arc;
+use x509_parser::certificate::X509Certificate;
+use x509_parser::extensions::GeneralName;
+
+pub use crate::models::XMLDSIG_NAMESPACE;
+pub use crate::xml::{Canonicalization, canonicalize_xml_with};
+
+#[cfg(all(feature = "pkcs11", unix))]
+pub mod pkcs11;
+
+/// OID of the CNPJ of the holder in the alternative name of ICP-Brasil certificates
+const CNPJ_OID: &str = "2.16.76.1.3.3";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SignError {
+    ConfigError(ConfigError),
+    Io(String),
+    InvalidPKCS12(String),
+    MissingPrivateKey,
+    MissingCertificate,
+    InvalidCertificate(String),
+    InvalidPrivateKey(String),
+    Serialization(String),
+    Canonicalization(String),
+    /// Failure of the device holding the key, as an A3 token
+    Token(String),
+    /// Environment (tpAmb) of the document differs from the one of the
+    /// configuration: document, configuration
+    Environment(Environment, Environment),
+}
+
+/// Holder of the private key that signs the documents
+///
+/// Implemented by the A1 `Certificate`, signing in software, and by the
+/// `pkcs11::PKCS11Signer` of A3 tokens and smartcards (feature "pkcs11").
+pub trait Signer {
+    /// DER encoded certificate of the key, embedded in the signatures
+    fn certificate_der(&self) -> &[u8];
+
+    /// RSA-SHA1 (PKCS#1 v1.5) signature of `data`
+    fn sign_sha1(&self, data: &[u8]) -> Result<Vec<u8>, SignError>;
+}
+
+/// Reason why a signature does not hold
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerifyError {
+    /// Name of the element missing from the document
+    MissingElement(String),
+    Serialization(String),
+    Canonicalization(String),
+    /// Id of the element and URI of the reference
+    ReferenceMismatch(String, String),
+    /// The element changed after it was signed
+    DigestMismatch,
+    InvalidCertificate(String),
+    /// The SignatureValue was not produced by the key of the certificate
+    InvalidSignature,
+    /// The certificate is not issued by the trusted authorities
+    UntrustedCertificate,
+}
+
+/// A1 certificate used to sign the documents
+///
+/// key: RSA private key of the certificate
+/// der: DER encoded leaf certificate
+/// chain: DER encoded certificates of the issuing authorities
+/// subject: Distinguished name of the holder
+/// expiration: End of the validity of the leaf certificate
+/// cnpj: CNPJ of the holder, for e-CNPJ certificates
+#[derive(Clone)]
+pub struct Certificate {
+    key: RsaPrivateKey,
+    der: Vec<u8>,
+    chain: Vec<Vec<u8>>,
+    subject: String,
+    expiration: DateTime<Utc>,
+    cnpj: Option<CNPJ>,
+}
+
+/// CNPJ of the holder, from the alternative name or the "NAME:CNPJ" common name
+fn holder_cnpj(certificate: &X509Certificate) -> Option<CNPJ> {
+    let alternative = certificate
+        .subject_alternative_name()
+        .ok()
+        .flatten()
+        .and_then(|extension| {
+            extension
+                .value
+                .general_names
+                .iter()
+                .find_map(|name| match name {
+                    // The encoded value ends with the 14 digits of the CNPJ
+                    GeneralName::OtherName(oid, value) if oid.to_id_string() == CNPJ_OID => {
+                        let digits = value.get(value.len().checked_sub(14)?..)?;
+                        std::str::from_utf8(digits).ok().map(str::to_string)
+                    }
+                    _ => None,
+                })
+        });
+    let common_name = certificate
+        .subject()
+        .iter_common_name()
+        .find_map(|name| Some(name.as_str().ok()?.rsplit_once(':')?.1.to_string()));
+
+    alternative
+        .into_iter()
+        .chain(common_name)
+        .find_map(|digits| CNPJ::parse(&digits).ok())
+}
+
+fn pem_certificate(der: &[u8]) -> String {
+    let mut pem = String::from("-----BEGIN CERTIFICATE-----\n");
+    for line in BASE64.encode(der).as_bytes().chunks(64) {
+        pem.push_str(std::str::from_utf8(line).unwrap_or_default());
+        pem.push('\n');
+    }
+    pem.push_str("-----END CERTIFICATE-----\n");
+    pem
+}
+
+impl Certificate {
+    /// Certificate of the global configuration, cached after the first load
+    pub fn from_config() -> Result<Arc<Self>, SignError> {
+        crate::config::get_pkcs12_certificate()
+    }
+
+    pub fn from_pkcs12(config: &PKCS12Config) -> Result<Self, SignError> {
+        let data = std::fs::read(&config.path).map_err(|e| SignError::Io(e.to_string()))?;
+        Self::from_pkcs12_der(&data, &config.password)
+    }
+
+    pub fn from_pkcs12_der(data: &[u8], password: &str) -> Result<Self, SignError> {
+        let keystore = KeyStore::from_pkcs12(data, password)
+            .map_err(|e| SignError::InvalidPKCS12(e.to_string()))?;
+        let (_, chain) = keystore
+            .private_key_chain()
+            .ok_or(SignError::MissingPrivateKey)?;
+        let key = RsaPrivateKey::from_pkcs8_der(chain.key())
+            .map_err(|e| SignError::InvalidPrivateKey(e.to_string()))?;
+        let (leaf, authorities) = chain
+            .chain()
+            .split_first()
+            .ok_or(SignError::MissingCertificate)?;
+        let (_, parsed) = x509_parser::parse_x509_certificate(leaf.as_der())
+            .map_err(|e| SignError::InvalidCertificate(e.to_string()))?;
+        let expiration = DateTime::from_timestamp(parsed.validity().not_after.timestamp(), 0)
+            .ok_or_else(|| SignError::InvalidCertificate("Invalid expiration".to_string()))?;
+
+        Ok(Certificate {
+            key,
+            der: leaf.as_der().to_vec(),
+            chain: authorities
+                .iter()
+                .map(|authority| authority.as_der().to_vec())
+                .collect(),
+            subject: parsed.subject().to_string(),
+            expiration,
+            cnpj: holder_cnpj(&parsed),
+        })
+    }
+
+    pub fn der(&self) -> &[u8] {
+        &self.der
+    }
+
+    /// Base64 encoded DER certificate, as embedded in `X509Certificate`
+    pub fn encoded(&self) -> String {
+        BASE64.encode(&self.der)
+    }
+
+    /// DER encoded certificates of the issuing authorities, from the closest
+    pub fn chain(&self) -> &[Vec<u8>] {
+        &self.chain
+    }
+
+    /// Distinguished name of the holder
+    pub fn subject(&self) -> &str {
+        &self.subject
+    }
+
+    /// End of the validity of the certificate
+    pub fn expiration(&self) -> DateTime<Utc> {
+        self.expiration
+    }
+
+    /// CNPJ of the holder, when it is an e-CNPJ certificate
+    pub fn cnpj(&self) -> Option<&CNPJ> {
+        self.cnpj.as_ref()
+    }
+
+    /// DER encoded PKCS#8 private key
+    pub fn private_key_der(&self) -> Result<Vec<u8>, SignError> {
+        Ok(self
+            .key
+            .to_pkcs8_der()
+            .map_err(|e| SignError::InvalidPrivateKey(e.to_string()))?
+            .as_bytes()
+            .to_vec())
+    }
+
+    /// PKCS#8 private key followed by the certificate and its chain, all PEM encoded
+    pub fn to_pem(&self) -> Result<String, SignError> {
+        let mut pem = self
+            .key
+            .to_pkcs8_pem(LineEnding::LF)
+            .map_err(|e| SignError::InvalidPrivateKey(e.to_string()))?
+            .to_string();
+        pem.push_str(&pem_certificate(&self.der));
+        for authority in &self.chain {
+            pem.push_str(&pem_certificate(authority));
+        }
+        Ok(pem)
+    }
+}
+
+impl Signer for Certificate {
+    fn certificate_der(&self) -> &[u8] {
+        &self.der
+    }
+
+    fn sign_sha1(&self, data: &[u8]) -> Result<Vec<u8>, SignError> {
+        let signing_key = SigningKey::<Sha1>::new(self.key.clone());
+        Ok(signing_key.sign(data).to_vec())
+    }
+}
+
+fn canonicalize(xml: &str) -> Result<String, SignError> {
+    canonicalize_xml(xml).map_err(|e| SignError::Canonicalization(e.to_string()))
+}
+
+/// Generates the enveloped signature of an element
+///
+/// `element` must be the serialized element referenced by `id`, including
+/// the namespace declarations it inherits from the document root.
+pub fn sign(element: &str, id: &str, signer: &dyn Signer) -> Result<Signature, SignError> {
+    let digest = Sha1::digest(canonicalize(element)?.as_bytes());
+
+    let info = SignatureInfo {
+        canonicalization_method: CanonicalizationMethod,
+        signature_method: SignatureMethod,
+        reference: SignatureReference {
+            uri: format!("#{}", id),
+            transforms: SignatureTransforms,
+            digest_method: DigestMethod,
+            digest_value: BASE64.encode(digest),
+        },
+    };
+
+    let signed_info = to_namespaced_xml("SignedInfo", XMLDSIG_NAMESPACE, &info)
+        .map_err(|e| SignError::Serialization(e.to_string()))?;
+    let value = signer.sign_sha1(canonicalize(&signed_info)?.as_bytes())?;
+
+    Ok(Signature {
+        info,
+        value: BASE64.encode(value),
+        key_info: KeyInfo {
+            data: X509Data {
+                certificate: BASE64.encode(signer.certificate_der()),
+            },
+        },
+    })
+}
+
+/// Checks the enveloped signature of an element
+///
+/// `element` must be the serialized element referenced by `id`, as given to
+/// `sign`, and the SignedInfo is serialized back from `signature`, so it only
+/// holds for the signatures of the library, see `verify_document` for the
+/// received documents. The digest of the element is checked against the
+/// DigestValue and the SignatureValue against the key of the embedded
+/// certificate, which is not checked against its authorities, see
+/// `verify_chain`.
+pub fn verify(element: &str, id: &str, signature: &Signature) -> Result<(), VerifyError> {
+    let signed_info = to_namespaced_xml("SignedInfo", XMLDSIG_NAMESPACE, &signature.info)
+        .map_err(|e| VerifyError::Serialization(e.to_string()))?;
+    verify_signed_info(element, id, &signed_info, signature)
+}
+
+/// Checks the enveloped signature of the first element `local_name` of the
+/// `namespace` in a received document, as `verify`
+///
+/// The element and the SignedInfo are canonicalized as they were received,
+/// with the namespaces they inherit, whatever the prefixes and the formatting
+/// used by the system that signed them.
+pub fn verify_document(xml: &str, namespace: &str, local_name: &str) -> Result<(), VerifyError> {
+    let missing = |name: &str| VerifyError::MissingElement(name.to_string());
+    let find = |xml: &str, namespace: &str, name: &str| {
+        find_element(xml, namespace, name)
+            .map_err(|e| VerifyError::Serialization(format!("{:?}", e)))?
+            .ok_or_else(|| missing(name))
+    };
+    let element = find(xml, namespace, local_name)?;
+    let id = element
+        .id
+        .ok_or_else(|| missing(&format!("{}/@Id", local_name)))?;
+    let signature = find(xml, XMLDSIG_NAMESPACE, "Signature")?.xml;
+    let signed_info = find(&signature, XMLDSIG_NAMESPACE, "SignedInfo")?.xml;
+    let signature: Signature = quick_xml::de::from_str(&signature)
+        .map_err(|e| VerifyError::Serialization(e.to_string()))?;
+    verify_signed_info(&element.xml, &id, &signed_info, &signature)
+}
+
+fn verify_signed_info(
+    element: &str,
+    id: &str,
+    signed_info: &str,
+    signature: &Signature,
+) -> Result<(), VerifyError> {
+    let canonicalize =
+        |xml: &str| canonicalize_xml(xml).map_err(|e| VerifyError::Canonicalization(e.to_string()));
+
+    let reference = &signature.info.reference;
+    if reference.uri != format!("#{}", id) {
+        return Err(VerifyError::ReferenceMismatch(
+            id.to_string(),
+            reference.uri.clone(),
+        ));
+    }
+    let digest = Sha1::digest(canonicalize(element)?.as_bytes());
+    if BASE64.encode(digest) != reference.digest_value.trim() {
+        return Err(VerifyError::DigestMismatch);
+    }
+
+    let der = signature_certificate(signature)?;
+    let (_, certificate) = x509_parser::parse_x509_certificate(&der)
+        .map_err(|e| VerifyError::InvalidCertificate(e.to_string()))?;
+    let key = RsaPublicKey::from_public_key_der(certificate.public_key().raw)
+        .map_err(|e| VerifyError::InvalidCertificate(e.to_string()))?;
+    let value = BASE64
+        .decode(signature.value.split_whitespace().collect::<String>())
+        .map_err(|_| VerifyError::InvalidSignature)?;
+    let value = rsa::pkcs1v15::Signature::try_from(value.as_slice())
+        .map_err(|_| VerifyError::InvalidSignature)?;
+
+    VerifyingKey::<Sha1>::new(key)
+        .verify(canonicalize(signed_info)?.as_bytes(), &value)
+        .map_err(|_| VerifyError::InvalidSignature)
+}
+
+/// DER encoded certificate embedded in the signature
+fn signature_certificate(signature: &Signature) -> Result<Vec<u8>, VerifyError> {
+    let encoded: String = signature
+        .key_info
+        .data
+        .certificate
+        .split_whitespace()
+        .collect();
+    BASE64
+        .decode(encoded)
+        .map_err(|e| VerifyError::InvalidCertificate(e.to_string()))
+}
+
+/// Checks that the certificate of the signature is issued by the `trusted`
+/// DER encoded certificates, as the ICP-Brasil roots and their intermediate
+/// authorities
+///
+/// The chain must end at a self-signed certificate among the trusted ones. The
+/// validity periods are not checked, as the documents stay valid after the
+/// expiration of the certificate that signed them.
+pub fn verify_chain(signature: &Signature, trusted: &[Vec<u8>]) -> Result<(), VerifyError> {
+    let authorities = trusted
+        .iter()
+        .map(|der| {
+            x509_parser::parse_x509_certificate(der)
+                .map(|(_, certificate)| certificate)
+                .map_err(|e| VerifyError::InvalidCertificate(e.to_string()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let der = signature_certificate(signature)?;
+    let (_, mut certificate) = x509_parser::parse_x509_certificate(&der)
+        .map_err(|e| VerifyError::InvalidCertificate(e.to_string()))?;
+
+    // Each authority signs at most once, so the walk ends on loops
+    for _ in 0..=authorities.len() {
+        let issuer = authorities.iter().find(|authority| {
+            authority.subject() == certificate.issuer()
+                && certificate
+                    .verify_signature(Some(authority.public_key()))
+                    .is_ok()
+        });
+        let Some(issuer) = issuer else {
+            break;
+        };
+        if issuer.subject() == issuer.issuer() {
+            return Ok(());
+        }
+        certificate = issuer.clone();
+    }
+    Err(VerifyError::UntrustedCertificate)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    pub fn setup_certificate() -> Certificate {
+        Certificate::from_pkcs12(&PKCS12Config::new(
+            "tests/credentials/cert.p12".to_string(),
+            "12345678".to_string(),
+        ))
+        .expect("Failed to load certificate")
+    }
+
+    #[test]
+    fn test_load_pkcs12() {
+        let certificate = setup_certificate();
+        let pem = include_str!("../tests/credentials/cert.pem");
+        let body: String = pem.lines().filter(|l| !l.starts_with("-----")).collect();
+        assert_eq!(certificate.encoded(), body);
+    }
+
+    #[test]
+    fn test_load_pkcs12_wrong_password() {
+        let data = include_bytes!("../tests/credentials/cert.p12");
+        let result = Certificate::from_pkcs12_der(data, "wrong");
+        assert!(matches!(result, Err(SignError::InvalidPKCS12(_))));
+    }
+
+    #[test]
+    fn test_certificate_details() {
+        let certificate = setup_certificate();
+        assert!(certificate.chain().is_empty());
+        assert_eq!(certificate.cnpj(), None);
+        assert_eq!(
+            certificate.expiration().to_rfc3339(),
+            "2126-09-23T21:23:28+00:00"
+        );
+    }
+
+    #[test]
+    fn test_load_e_cnpj() {
+        let certificate = Certificate::from_pkcs12(&PKCS12Config::new(
+            "tests/credentials/ecnpj.p12".to_string(),
+            "12345678".to_string(),
+        ))
+        .expect("Failed to load certificate");
+        assert_eq!(
+            certificate.cnpj(),
+            Some(&CNPJ("12345678000195".to_string()))
+        );
+        assert!(
+            certificate
+                .subject()
+                .contains("CN=EMPRESA EXEMPLO LTDA:12345678000195")
+        );
+        assert_eq!(certificate.chain().len(), 1);
+        assert_eq!(
+            certificate
+                .to_pem()
+                .unwrap()
+                .matches("BEGIN CERTIFICATE")
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_sign_digest() {
+        let certificate = setup_certificate();
+        let signature = sign(
+            r#"<root xmlns="urn:test" Id="ID1"><child>value</child></root>"#,
+            "ID1",
+            &certificate,
+        )
+        .expect("Failed to sign");
+
+        let expected = BASE64.encode(Sha1::digest(
+            r#"<root xmlns="urn:test" Id="ID1"><child>value</child></root>"#.as_bytes(),
+        ));
+        assert_eq!(signature.info.reference.uri, "#ID1");
+        assert_eq!(signature.info.reference.digest_value, expected);
+    }
+
+    #[test]
+    fn test_verify() {
+        let element = r#"<root xmlns="urn:test" Id="ID1"><child>value</child></root>"#;
+        let signature = sign(element, "ID1", &setup_certificate()).unwrap();
+        assert_eq!(verify(element, "ID1", &signature), Ok(()));
+        assert_eq!(
+            verify(element, "ID2", &signature),
+            Err(VerifyError::ReferenceMismatch(
+                "ID2".to_string(),
+                "#ID1".to_string()
+            ))
+        );
+
+        let changed = element.replace("value", "changed");
+        assert_eq!(
+            verify(&changed, "ID1", &signature),
+            Err(VerifyError::DigestMismatch)
+        );
+
+        let other = sign(&changed, "ID1", &setup_certificate()).unwrap();
+        let forged = Signature {
+            value: other.value,
+            ..signature
+        };
+        assert_eq!(
+            verify(element, "ID1", &forged),
+            Err(VerifyError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn test_verify_document() {
+        // Signed by another system: prefixed, indented and with the
+        // SignatureValue split in lines
+        let element = r#"<item xmlns="urn:test" Id="ID1">value</item>"#;
+        let digest = BASE64.encode(Sha1::digest(element.as_bytes()));
+        let signed_info = format!(
+            r##"
+    <ds:CanonicalizationMethod Algorithm="http://www.w3.org/TR/2001/REC-xml-c14n-20010315"></ds:CanonicalizationMethod>
+    <ds:SignatureMethod Algorithm="http://www.w3.org/2000/09/xmldsig#rsa-sha1"></ds:SignatureMethod>
+    <ds:Reference URI="#ID1">
+      <ds:Transforms>
+        <ds:Transform Algorithm="http://www.w3.org/2000/09/xmldsig#enveloped-signature"></ds:Transform>
+        <ds:Transform Algorithm="http://www.w3.org/TR/2001/REC-xml-c14n-20010315"></ds:Transform>
+      </ds:Transforms>
+      <ds:DigestMethod Algorithm="http://www.w3.org/2000/09/xmldsig#sha1"></ds:DigestMethod>
+      <ds:DigestValue>{}</ds:DigestValue>
+    </ds:Reference>
+  "##,
+            digest
+        );
+        // The SignedInfo inherits the default namespace of the root
+        let canonical = format!(
+            r#"<ds:SignedInfo xmlns="urn:test" xmlns:ds="{}">{}</ds:SignedInfo>"#,
+            XMLDSIG_NAMESPACE, signed_info
+        );
+        let certificate = setup_certificate();
+        let value = BASE64.encode(certificate.sign_sha1(canonical.as_bytes()).unwrap());
+        let (first, second) = value.split_at(value.len() / 2);
+        let document = format!(
+            r#"<root xmlns="urn:test">
+  <item Id="ID1">value</item>
+  <ds:Signature xmlns:ds="{}">
+  <ds:SignedInfo>{}</ds:SignedInfo>
+  <ds:SignatureValue>{}
+{}</ds:SignatureValue>
+  <ds:KeyInfo><ds:X509Data><ds:X509Certificate>{}</ds:X509Certificate></ds:X509Data></ds:KeyInfo>
+  </ds:Signature>
+</root>"#,
+            XMLDSIG_NAMESPACE,
+            signed_info,
+            first,
+            second,
+            certificate.encoded()
+        );
+        assert_eq!(verify_document(&document, "urn:test", "item"), Ok(()));
+
+        // Serialized back, the SignedInfo is not the one signed
+        let signature: Signature = quick_xml::de::from_str(
+            &document[document.find("<ds:Signature").unwrap()..document.find("</root>").unwrap()],
+        )
+        .unwrap();
+        assert_eq!(
+            verify(element, "ID1", &signature),
+            Err(VerifyError::InvalidSignature)
+        );
+
+        assert_eq!(
+            verify_document(
+                &document.replace(">value<", ">changed<"),
+                "urn:test",
+                "item"
+            ),
+            Err(VerifyError::DigestMismatch)
+        );
+        assert_eq!(
+            verify_document(
+                &document.replace("<ds:Reference URI", "<ds:Reference  URI"),
+                "urn:test",
+                "item"
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            verify_document(
+                &document.replace("\n    <ds:Sign", "<ds:Sign"),
+                "urn:test",
+                "item"
+            ),
+            Err(VerifyError::InvalidSignature)
+        );
+        assert_eq!(
+            verify_document(&document, "urn:test", "other"),
+            Err(VerifyError::MissingElement("other".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_verify_chain() {
+        let certificate = Certificate::from_pkcs12(&PKCS12Config::new(
+            "tests/credentials/ecnpj.p12".to_string(),
+            "12345678".to_string(),
+        ))
+        .unwrap();
+        let signature = sign(r#"<root Id="ID1"/>"#, "ID1", &certificate).unwrap();
+        assert_eq!(verify_chain(&signature, certificate.chain()), Ok(()));
+        assert_eq!(
+            verify_chain(&signature, &[]),
+            Err(VerifyError::UntrustedCertificate)
+        );
+
+        // Self-signed, so only trusted when it is one of the authorities
+        let signature = sign(r#"<root Id="ID1"/>"#, "ID1", &setup_certificate()).unwrap();
+        assert_eq!(
+            verify_chain(&signature, certificate.chain()),
+            Err(VerifyError::UntrustedCertificate)
+        );
+        assert_eq!(
+            verify_chain(&signature, &[setup_certificate().der().to_vec()]),
+            Ok(())
+        );
+    }
+}
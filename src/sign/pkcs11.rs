@@ -0,0 +1,436 @@
+//! Signing with the keys of A3 tokens and smartcards through PKCS#11
+//!
+//! The private key never leaves the device: the module of the manufacturer
+//! (as libaetpkss.so or libeToken.so) is loaded at runtime and performs the
+//! RSA-SHA1 operation, so only the certificate is read from the token.
+
+use super::{SignError, Signer};
+use std::ffi::{CStr, CString, c_char, c_int, c_ulong, c_void};
+use std::sync::Mutex;
+
+type Ulong = c_ulong;
+type ReturnValue = Ulong;
+type SessionHandle = Ulong;
+type ObjectHandle = Ulong;
+
+const CKR_OK: ReturnValue = 0;
+const CKR_USER_ALREADY_LOGGED_IN: ReturnValue = 0x100;
+const CKR_CRYPTOKI_ALREADY_INITIALIZED: ReturnValue = 0x191;
+const CKF_SERIAL_SESSION: Ulong = 0x4;
+const CKU_USER: Ulong = 1;
+const CKA_CLASS: Ulong = 0x0;
+const CKA_LABEL: Ulong = 0x3;
+const CKA_VALUE: Ulong = 0x11;
+const CKA_ID: Ulong = 0x102;
+const CKO_CERTIFICATE: Ulong = 0x1;
+const CKO_PRIVATE_KEY: Ulong = 0x3;
+const CKM_SHA1_RSA_PKCS: Ulong = 0x6;
+const RTLD_NOW: c_int = 2;
+
+#[repr(C)]
+struct Attribute {
+    kind: Ulong,
+    value: *mut c_void,
+    length: Ulong,
+}
+
+#[repr(C)]
+struct Mechanism {
+    kind: Ulong,
+    parameter: *mut c_void,
+    length: Ulong,
+}
+
+type Unused = Option<unsafe extern "C" fn()>;
+
+/// Prefix of CK_FUNCTION_LIST, up to C_Sign
+#[repr(C)]
+struct FunctionList {
+    version: [u8; 2],
+    initialize: unsafe extern "C" fn(*mut c_void) -> ReturnValue,
+    finalize: unsafe extern "C" fn(*mut c_void) -> ReturnValue,
+    get_info: Unused,
+    get_function_list: Unused,
+    get_slot_list: unsafe extern "C" fn(u8, *mut Ulong, *mut Ulong) -> ReturnValue,
+    get_slot_info: Unused,
+    get_token_info: Unused,
+    get_mechanism_list: Unused,
+    get_mechanism_info: Unused,
+    init_token: Unused,
+    init_pin: Unused,
+    set_pin: Unused,
+    open_session: unsafe extern "C" fn(
+        Ulong,
+        Ulong,
+        *mut c_void,
+        *mut c_void,
+        *mut SessionHandle,
+    ) -> ReturnValue,
+    close_session: unsafe extern "C" fn(SessionHandle) -> ReturnValue,
+    close_all_sessions: Unused,
+    get_session_info: Unused,
+    get_operation_state: Unused,
+    set_operation_state: Unused,
+    login: unsafe extern "C" fn(SessionHandle, Ulong, *const u8, Ulong) -> ReturnValue,
+    logout: unsafe extern "C" fn(SessionHandle) -> ReturnValue,
+    create_object: Unused,
+    copy_object: Unused,
+    destroy_object: Unused,
+    get_object_size: Unused,
+    get_attribute_value:
+        unsafe extern "C" fn(SessionHandle, ObjectHandle, *mut Attribute, Ulong) -> ReturnValue,
+    set_attribute_value: Unused,
+    find_objects_init: unsafe extern "C" fn(SessionHandle, *const Attribute, Ulong) -> ReturnValue,
+    find_objects:
+        unsafe extern "C" fn(SessionHandle, *mut ObjectHandle, Ulong, *mut Ulong) -> ReturnValue,
+    find_objects_final: unsafe extern "C" fn(SessionHandle) -> ReturnValue,
+    encrypt_init: Unused,
+    encrypt: Unused,
+    encrypt_update: Unused,
+    encrypt_final: Unused,
+    decrypt_init: Unused,
+    decrypt: Unused,
+    decrypt_update: Unused,
+    decrypt_final: Unused,
+    digest_init: Unused,
+    digest: Unused,
+    digest_update: Unused,
+    digest_key: Unused,
+    digest_final: Unused,
+    sign_init: unsafe extern "C" fn(SessionHandle, *const Mechanism, ObjectHandle) -> ReturnValue,
+    sign: unsafe extern "C" fn(SessionHandle, *const u8, Ulong, *mut u8, *mut Ulong) -> ReturnValue,
+}
+
+type GetFunctionList = unsafe extern "C" fn(*mut *const FunctionList) -> ReturnValue;
+
+#[cfg_attr(target_os = "linux", link(name = "dl"))]
+unsafe extern "C" {
+    fn dlopen(filename: *const c_char, flag: c_int) -> *mut c_void;
+    fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+    fn dlerror() -> *const c_char;
+    fn dlclose(handle: *mut c_void) -> c_int;
+}
+
+fn token_error(message: impl ToString) -> SignError {
+    SignError::Token(message.to_string())
+}
+
+fn check(function: &str, value: ReturnValue) -> Result<(), SignError> {
+    match value {
+        CKR_OK => Ok(()),
+        value => Err(token_error(format!("{} failed: 0x{:X}", function, value))),
+    }
+}
+
+/// A3 token holding the key, as read by `PKCS11Signer::open`
+///
+/// module: Path of the PKCS#11 library of the token manufacturer
+/// pin: PIN of the user of the token
+/// slot: Slot of the token - Optional, the first one with a token when missing
+/// label: Label of the private key - Optional, the first key when missing
+#[derive(Debug, Clone, PartialEq)]
+pub struct PKCS11Config {
+    pub module: String,
+    pub pin: String,
+    pub slot: Option<u64>,
+    pub label: Option<String>,
+}
+
+impl PKCS11Config {
+    pub fn new(module: String, pin: String) -> Self {
+        PKCS11Config {
+            module,
+            pin,
+            slot: None,
+            label: None,
+        }
+    }
+
+    pub fn with_slot(mut self, slot: u64) -> Self {
+        self.slot = Some(slot);
+        self
+    }
+
+    pub fn with_label(mut self, label: String) -> Self {
+        self.label = Some(label);
+        self
+    }
+}
+
+/// Signer of a logged in session of an A3 token
+///
+/// The session is closed, and the module unloaded, when dropped. The signatures
+/// are serialized, as the sessions of PKCS#11 do not sign concurrently.
+pub struct PKCS11Signer {
+    library: *mut c_void,
+    functions: *const FunctionList,
+    session: Mutex<SessionHandle>,
+    key: ObjectHandle,
+    certificate: Vec<u8>,
+}
+
+// The handles are only used through the functions of the module, which are
+// called by one thread at a time under the lock of the session
+unsafe impl Send for PKCS11Signer {}
+unsafe impl Sync for PKCS11Signer {}
+
+impl PKCS11Signer {
+    /// Loads the module, logs in the token and finds the key and its certificate
+    pub fn open(config: &PKCS11Config) -> Result<Self, SignError> {
+        let path = CString::new(config.module.as_str()).map_err(token_error)?;
+        let library = unsafe { dlopen(path.as_ptr(), RTLD_NOW) };
+        if library.is_null() {
+            let reason = unsafe { dlerror() };
+            let reason = match reason.is_null() {
+                true => "unknown error".to_string(),
+                false => unsafe { CStr::from_ptr(reason) }
+                    .to_string_lossy()
+                    .into_owned(),
+            };
+            return Err(token_error(format!(
+                "Failed to load {}: {}",
+                config.module, reason
+            )));
+        }
+
+        let mut signer = PKCS11Signer {
+            library,
+            functions: std::ptr::null(),
+            session: Mutex::new(0),
+            key: 0,
+            certificate: Vec::new(),
+        };
+        signer.start(config)?;
+        Ok(signer)
+    }
+
+    fn functions(&self) -> &FunctionList {
+        unsafe { &*self.functions }
+    }
+
+    /// Initializes the module and logs in, filling the handles of `self`
+    fn start(&mut self, config: &PKCS11Config) -> Result<(), SignError> {
+        let symbol = CString::new("C_GetFunctionList").map_err(token_error)?;
+        let get_function_list = unsafe { dlsym(self.library, symbol.as_ptr()) };
+        if get_function_list.is_null() {
+            return Err(token_error("C_GetFunctionList not found in the module"));
+        }
+        let get_function_list: GetFunctionList = unsafe { std::mem::transmute(get_function_list) };
+        check("C_GetFunctionList", unsafe {
+            get_function_list(&mut self.functions)
+        })?;
+        if self.functions.is_null() {
+            return Err(token_error("C_GetFunctionList returned no functions"));
+        }
+
+        // Owned by the module, which outlives the borrows of `self`
+        let functions = unsafe { &*self.functions };
+        match unsafe { (functions.initialize)(std::ptr::null_mut()) } {
+            CKR_CRYPTOKI_ALREADY_INITIALIZED => {}
+            value => check("C_Initialize", value)?,
+        }
+
+        let slot = match config.slot {
+            Some(slot) => slot as Ulong,
+            None => self.first_slot()?,
+        };
+        let mut session = 0;
+        check("C_OpenSession", unsafe {
+            (functions.open_session)(
+                slot,
+                CKF_SERIAL_SESSION,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                &mut session,
+            )
+        })?;
+        *self.session.get_mut().map_err(token_error)? = session;
+
+        let pin = config.pin.as_bytes();
+        match unsafe { (functions.login)(session, CKU_USER, pin.as_ptr(), pin.len() as Ulong) } {
+            CKR_USER_ALREADY_LOGGED_IN => {}
+            value => check("C_Login", value)?,
+        }
+
+        let mut class = CKO_PRIVATE_KEY;
+        let mut template = vec![Attribute {
+            kind: CKA_CLASS,
+            value: &mut class as *mut Ulong as *mut c_void,
+            length: size_of::<Ulong>() as Ulong,
+        }];
+        let mut label = config.label.clone().unwrap_or_default().into_bytes();
+        if config.label.is_some() {
+            template.push(Attribute {
+                kind: CKA_LABEL,
+                value: label.as_mut_ptr() as *mut c_void,
+                length: label.len() as Ulong,
+            });
+        }
+        self.key = self
+            .find(session, &template)?
+            .ok_or(SignError::MissingPrivateKey)?;
+
+        // The certificate of the key shares its CKA_ID
+        let mut id = self.attribute(session, self.key, CKA_ID)?;
+        let mut class = CKO_CERTIFICATE;
+        let mut template = vec![Attribute {
+            kind: CKA_CLASS,
+            value: &mut class as *mut Ulong as *mut c_void,
+            length: size_of::<Ulong>() as Ulong,
+        }];
+        if !id.is_empty() {
+            template.push(Attribute {
+                kind: CKA_ID,
+                value: id.as_mut_ptr() as *mut c_void,
+                length: id.len() as Ulong,
+            });
+        }
+        let certificate = self
+            .find(session, &template)?
+            .ok_or(SignError::MissingCertificate)?;
+        self.certificate = self.attribute(session, certificate, CKA_VALUE)?;
+        Ok(())
+    }
+
+    /// First slot with a token present
+    fn first_slot(&self) -> Result<Ulong, SignError> {
+        let functions = self.functions();
+        let mut count = 0;
+        check("C_GetSlotList", unsafe {
+            (functions.get_slot_list)(1, std::ptr::null_mut(), &mut count)
+        })?;
+        let mut slots = vec![0; count as usize];
+        check("C_GetSlotList", unsafe {
+            (functions.get_slot_list)(1, slots.as_mut_ptr(), &mut count)
+        })?;
+        slots
+            .first()
+            .copied()
+            .ok_or_else(|| token_error("No token present"))
+    }
+
+    /// First object matching `template`
+    fn find(
+        &self,
+        session: SessionHandle,
+        template: &[Attribute],
+    ) -> Result<Option<ObjectHandle>, SignError> {
+        let functions = self.functions();
+        check("C_FindObjectsInit", unsafe {
+            (functions.find_objects_init)(session, template.as_ptr(), template.len() as Ulong)
+        })?;
+        let mut object = 0;
+        let mut count = 0;
+        let found = check("C_FindObjects", unsafe {
+            (functions.find_objects)(session, &mut object, 1, &mut count)
+        });
+        check("C_FindObjectsFinal", unsafe {
+            (functions.find_objects_final)(session)
+        })?;
+        found.map(|_| (count > 0).then_some(object))
+    }
+
+    /// Value of the attribute `kind` of `object`
+    fn attribute(
+        &self,
+        session: SessionHandle,
+        object: ObjectHandle,
+        kind: Ulong,
+    ) -> Result<Vec<u8>, SignError> {
+        let functions = self.functions();
+        let mut attribute = Attribute {
+            kind,
+            value: std::ptr::null_mut(),
+            length: 0,
+        };
+        check("C_GetAttributeValue", unsafe {
+            (functions.get_attribute_value)(session, object, &mut attribute, 1)
+        })?;
+        let mut value = vec![0u8; attribute.length as usize];
+        attribute.value = value.as_mut_ptr() as *mut c_void;
+        check("C_GetAttributeValue", unsafe {
+            (functions.get_attribute_value)(session, object, &mut attribute, 1)
+        })?;
+        value.truncate(attribute.length as usize);
+        Ok(value)
+    }
+}
+
+impl Signer for PKCS11Signer {
+    fn certificate_der(&self) -> &[u8] {
+        &self.certificate
+    }
+
+    fn sign_sha1(&self, data: &[u8]) -> Result<Vec<u8>, SignError> {
+        let session = self.session.lock().map_err(token_error)?;
+        let functions = self.functions();
+        let mechanism = Mechanism {
+            kind: CKM_SHA1_RSA_PKCS,
+            parameter: std::ptr::null_mut(),
+            length: 0,
+        };
+        check("C_SignInit", unsafe {
+            (functions.sign_init)(*session, &mechanism, self.key)
+        })?;
+
+        // The length of a RSA signature is the length of the modulus, so the
+        // buffer is sized by the module before signing
+        let mut length = 0;
+        check("C_Sign", unsafe {
+            (functions.sign)(
+                *session,
+                data.as_ptr(),
+                data.len() as Ulong,
+                std::ptr::null_mut(),
+                &mut length,
+            )
+        })?;
+        let mut signature = vec![0u8; length as usize];
+        check("C_Sign", unsafe {
+            (functions.sign)(
+                *session,
+                data.as_ptr(),
+                data.len() as Ulong,
+                signature.as_mut_ptr(),
+                &mut length,
+            )
+        })?;
+        signature.truncate(length as usize);
+        Ok(signature)
+    }
+}
+
+impl Drop for PKCS11Signer {
+    fn drop(&mut self) {
+        if !self.functions.is_null() {
+            let functions = unsafe { &*self.functions };
+            let session = self.session.get_mut().map(|session| *session).unwrap_or(0);
+            unsafe {
+                if session != 0 {
+                    (functions.logout)(session);
+                    (functions.close_session)(session);
+                }
+                (functions.finalize)(std::ptr::null_mut());
+            }
+        }
+        unsafe {
+            dlclose(self.library);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_module() {
+        let config = PKCS11Config::new("/nonexistent/libtoken.so".to_string(), "1234".to_string())
+            .with_slot(0)
+            .with_label("NFe".to_string());
+        let result = PKCS11Signer::open(&config);
+        assert!(
+            matches!(result, Err(SignError::Token(message)) if message.contains("libtoken.so"))
+        );
+    }
+}
@@ -0,0 +1,1476 @@
+//! SOAP client for the SEFAZ webservices
+//!
+//! The requests are sent over mutual TLS, authenticating with the same A1
+//! certificate used to sign the documents.
+//!
+//! The client is instrumented with `tracing`: each request has a span with the
+//! service and the ids of its documents (chave, lote), and the XML sent and
+//! received is logged at the debug level, without the certificates.
+
+use crate::access_key::AccessKey;
+use crate::certificate::CertificateError;
+use crate::config::{Config, ProxyConfig};
+use crate::disablement::{Disablement, DisablementResponse};
+use crate::distribution::{DistributionQuery, DistributionResponse};
+use crate::enums::{EmissionType, Environment, Model};
+use crate::events::{
+    EventBatch, EventInfo, EventPayload, EventProc, EventResponse, EventType, NATIONAL_ORGAN,
+};
+use crate::models::{Contingency, NFE_NAMESPACE, NFe, Protocol};
+use crate::sign::{Certificate, SignError};
+use crate::states::State;
+use crate::taxpayer::{RegistrationQuery, RegistrationResponse, TaxpayerDocument};
+use endpoints::{Authorizer, Service};
+use envelope::{SoapEnvelope, SoapFault, element_text, unwrap_response};
+use rejection::RejectionCode;
+use retry::{AttemptHook, RetryPolicy};
+use serde::{Deserialize, Serialize, ser::SerializeStruct};
+use std::time::Duration;
+use tls::Tls;
+use tracing::Instrument;
+
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod endpoints;
+pub mod envelope;
+pub mod rejection;
+pub mod retry;
+pub mod tls;
+
+pub use envelope::SOAP_NAMESPACE;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClientError {
+    Sign(SignError),
+    Certificate(CertificateError),
+    Tls(String),
+    Http(String),
+    /// The webservice did not answer in time
+    Timeout,
+    /// Receipt number (nRec) of a batch still being processed when the wait
+    /// for its result ended
+    Unprocessed(String),
+    /// HTTP status of a server error (5xx)
+    ServerError(u16),
+    Serialization(String),
+    Deserialization(String),
+    /// The authorizer of the document does not offer the service
+    UnavailableService(Service),
+    /// Status code (cStat) and reason (xMotivo) of a rejected request
+    Rejected(RejectionCode, String),
+    /// Batch that can not be sent, as an empty one
+    InvalidBatch(String),
+    /// Fault answered by the webservice instead of a response (soap:Fault)
+    Fault(SoapFault),
+}
+
+/// Maximum number of documents of an authorization batch
+pub const MAX_BATCH_DOCUMENTS: usize = 50;
+
+/// Batch of documents sent for authorization (enviNFe)
+///
+/// Synchronous batches (indSinc = 1) carry a single document and the response
+/// already has its protocol. Asynchronous batches return a receipt, queried
+/// with `Client::query_receipt` for the protocols of the documents.
+///
+/// id: Batch identifier (idLote)
+/// synchronous: Processing mode (indSinc)
+/// documents: Signed documents, up to 50 (NFe)
+#[derive(Debug, PartialEq)]
+pub struct AuthorizationBatch {
+    pub id: u64,
+    pub synchronous: bool,
+    pub documents: Vec<NFe>,
+}
+
+impl AuthorizationBatch {
+    /// Synchronous batch of a single document
+    pub fn single(id: u64, nfe: NFe) -> Self {
+        AuthorizationBatch {
+            id,
+            synchronous: true,
+            documents: vec![nfe],
+        }
+    }
+
+    /// Asynchronous batch of up to 50 documents
+    pub fn asynchronous(id: u64, documents: Vec<NFe>) -> Self {
+        AuthorizationBatch {
+            id,
+            synchronous: false,
+            documents,
+        }
+    }
+
+    /// Authorizer of the documents, which must all be sent to the same one
+    fn authorizer(&self) -> Result<Authorizer, ClientError> {
+        let invalid = |reason: &str| Err(ClientError::InvalidBatch(reason.to_string()));
+        if self.documents.len() > MAX_BATCH_DOCUMENTS {
+            return invalid("More than 50 documents in the batch");
+        }
+        if self.synchronous && self.documents.len() > 1 {
+            return invalid("Synchronous batches carry a single document");
+        }
+        let mut identifications = self.documents.iter().map(|nfe| &nfe.info.identification);
+        let Some(first) = identifications.next() else {
+            return invalid("Empty batch");
+        };
+        let authorizer = endpoints::authorizer(first);
+        if identifications.any(|identification| {
+            endpoints::authorizer(identification) != authorizer
+                || identification.environment != first.environment
+        }) {
+            return invalid("Documents of different authorizers or environments");
+        }
+        Ok(authorizer)
+    }
+}
+
+impl Serialize for AuthorizationBatch {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("enviNFe", 5)?;
+        state.serialize_field("@xmlns", NFE_NAMESPACE)?;
+        state.serialize_field("@versao", "4.00")?;
+        state.serialize_field("idLote", &self.id)?;
+        state.serialize_field("indSinc", &(self.synchronous as u8))?;
+        state.serialize_field("NFe", &self.documents)?;
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for AuthorizationBatch {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct AuthorizationBatchHelper {
+            #[serde(rename = "idLote")]
+            id: u64,
+            #[serde(rename = "indSinc")]
+            synchronous: u8,
+            #[serde(rename = "NFe")]
+            documents: Vec<NFe>,
+        }
+
+        let helper = AuthorizationBatchHelper::deserialize(deserializer)?;
+        Ok(AuthorizationBatch {
+            id: helper.id,
+            synchronous: helper.synchronous == 1,
+            documents: helper.documents,
+        })
+    }
+}
+
+/// Receipt of a batch processed asynchronously (infRec)
+///
+/// number: Receipt number (nRec)
+/// average_time: Average processing time in seconds (tMed)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Receipt {
+    #[serde(rename = "nRec")]
+    pub number: String,
+    #[serde(rename = "tMed")]
+    pub average_time: u32,
+}
+
+/// Response of the authorization webservice (retEnviNFe)
+///
+/// version: Layout version (@versao)
+/// environment: Environment type (tpAmb)
+/// application_version: Version of the SEFAZ application (verAplic)
+/// status: Status code of the batch (cStat)
+/// reason: Status description (xMotivo)
+/// state_code: IBGE code of the state that answered (cUF)
+/// received_at: Date and time of the reception (dhRecbto)
+/// receipt: Receipt of an asynchronous batch (infRec) - Optional
+/// protocol: Protocol of the document (protNFe) - Only for synchronous batches
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct AuthorizationResponse {
+    #[serde(rename = "@versao")]
+    pub version: String,
+    #[serde(rename = "tpAmb")]
+    pub environment: Environment,
+    #[serde(rename = "verAplic")]
+    pub application_version: String,
+    #[serde(rename = "cStat")]
+    pub status: u16,
+    #[serde(rename = "xMotivo")]
+    pub reason: String,
+    #[serde(rename = "cUF")]
+    pub state_code: u8,
+    #[serde(rename = "dhRecbto")]
+    pub received_at: chrono::DateTime<chrono::FixedOffset>,
+    #[serde(rename = "infRec", skip_serializing_if = "Option::is_none")]
+    pub receipt: Option<Receipt>,
+    #[serde(rename = "protNFe", skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<Protocol>,
+}
+
+impl AuthorizationResponse {
+    /// Protocol of an authorized document
+    ///
+    /// Returns `ClientError::Rejected` with the status of the document when it
+    /// was not authorized, or with the status of the batch when it was not
+    /// processed at all.
+    pub fn into_protocol(self) -> Result<Protocol, ClientError> {
+        match self.protocol {
+            Some(protocol) if protocol.info.is_authorized() => Ok(protocol),
+            Some(protocol) => Err(ClientError::Rejected(
+                protocol.info.status.into(),
+                protocol.info.reason,
+            )),
+            None => Err(ClientError::Rejected(self.status.into(), self.reason)),
+        }
+    }
+}
+
+/// Asynchronous batch received by the authorizer, waiting to be processed
+///
+/// authorizer: Authorizer that received the batch
+/// environment: Environment type of the documents
+/// receipt: Receipt of the batch (infRec)
+#[derive(Debug, PartialEq, Clone)]
+pub struct PendingBatch {
+    pub authorizer: Authorizer,
+    pub environment: Environment,
+    pub receipt: Receipt,
+}
+
+/// Query of the result of an asynchronous batch (consReciNFe)
+///
+/// environment: Environment type (tpAmb)
+/// receipt_number: Receipt number of the batch (nRec)
+#[derive(Debug, PartialEq)]
+pub struct ReceiptQuery {
+    pub environment: Environment,
+    pub receipt_number: String,
+}
+
+impl Serialize for ReceiptQuery {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("consReciNFe", 4)?;
+        state.serialize_field("@xmlns", NFE_NAMESPACE)?;
+        state.serialize_field("@versao", "4.00")?;
+        state.serialize_field("tpAmb", &self.environment)?;
+        state.serialize_field("nRec", &self.receipt_number)?;
+        state.end()
+    }
+}
+
+/// Response of the authorization result webservice (retConsReciNFe)
+///
+/// version: Layout version (@versao)
+/// environment: Environment type (tpAmb)
+/// application_version: Version of the SEFAZ application (verAplic)
+/// receipt_number: Receipt number of the batch (nRec)
+/// status: Status code of the batch (cStat)
+/// reason: Status description (xMotivo)
+/// state_code: IBGE code of the state that answered (cUF)
+/// received_at: Date and time of the processing (dhRecbto)
+/// protocols: Protocols of the documents of the batch (protNFe)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct ReceiptResponse {
+    #[serde(rename = "@versao")]
+    pub version: String,
+    #[serde(rename = "tpAmb")]
+    pub environment: Environment,
+    #[serde(rename = "verAplic")]
+    pub application_version: String,
+    #[serde(rename = "nRec")]
+    pub receipt_number: String,
+    #[serde(rename = "cStat")]
+    pub status: u16,
+    #[serde(rename = "xMotivo")]
+    pub reason: String,
+    #[serde(rename = "cUF")]
+    pub state_code: u8,
+    #[serde(rename = "dhRecbto")]
+    pub received_at: chrono::DateTime<chrono::FixedOffset>,
+    #[serde(rename = "protNFe", default, skip_serializing_if = "Vec::is_empty")]
+    pub protocols: Vec<Protocol>,
+}
+
+impl ReceiptResponse {
+    /// Batch still being processed (105), to be queried again later
+    pub fn is_processing(&self) -> bool {
+        self.status == 105
+    }
+
+    /// Protocol of an authorized document of the batch
+    ///
+    /// Returns `ClientError::Rejected` with the status of the document when it
+    /// was not authorized, or with the status of the batch when the document
+    /// has no protocol.
+    pub fn protocol_of(&self, access_key: &AccessKey) -> Result<&Protocol, ClientError> {
+        match self
+            .protocols
+            .iter()
+            .find(|protocol| protocol.info.access_key == access_key.as_str())
+        {
+            Some(protocol) if protocol.info.is_authorized() => Ok(protocol),
+            Some(protocol) => Err(ClientError::Rejected(
+                protocol.info.status.into(),
+                protocol.info.reason.clone(),
+            )),
+            None => Err(ClientError::Rejected(
+                self.status.into(),
+                self.reason.clone(),
+            )),
+        }
+    }
+}
+
+/// Authorization of a document that may have been moved to contingency
+///
+/// nfe: Signed document, as sent to the authorizer that answered
+/// response: Response of that authorizer (retEnviNFe)
+#[derive(Debug, PartialEq)]
+pub struct Authorization {
+    pub nfe: NFe,
+    pub response: AuthorizationResponse,
+}
+
+/// Query of the situation of a document (consSitNFe)
+///
+/// environment: Environment type (tpAmb)
+/// access_key: Access key of the NF-e (chNFe)
+#[derive(Debug, PartialEq)]
+pub struct ProtocolQuery {
+    pub environment: Environment,
+    pub access_key: AccessKey,
+}
+
+impl Serialize for ProtocolQuery {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("consSitNFe", 5)?;
+        state.serialize_field("@xmlns", NFE_NAMESPACE)?;
+        state.serialize_field("@versao", "4.00")?;
+        state.serialize_field("tpAmb", &self.environment)?;
+        state.serialize_field("xServ", "CONSULTAR")?;
+        state.serialize_field("chNFe", &self.access_key)?;
+        state.end()
+    }
+}
+
+/// Response of the protocol query webservice (retConsSitNFe)
+///
+/// version: Layout version (@versao)
+/// environment: Environment type (tpAmb)
+/// application_version: Version of the SEFAZ application (verAplic)
+/// status: Status code of the document (cStat)
+/// reason: Status description (xMotivo)
+/// state_code: IBGE code of the state that answered (cUF)
+/// received_at: Date and time of the processing (dhRecbto)
+/// access_key: Access key of the NF-e (chNFe) - Optional
+/// protocol: Authorization protocol (protNFe) - Only for known documents
+/// events: Events registered for the document (procEventoNFe)
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ProtocolQueryResponse {
+    #[serde(rename = "@versao")]
+    pub version: String,
+    #[serde(rename = "tpAmb")]
+    pub environment: Environment,
+    #[serde(rename = "verAplic")]
+    pub application_version: String,
+    #[serde(rename = "cStat")]
+    pub status: u16,
+    #[serde(rename = "xMotivo")]
+    pub reason: String,
+    #[serde(rename = "cUF")]
+    pub state_code: u8,
+    #[serde(rename = "dhRecbto")]
+    pub received_at: chrono::DateTime<chrono::FixedOffset>,
+    #[serde(rename = "chNFe", skip_serializing_if = "Option::is_none")]
+    pub access_key: Option<String>,
+    #[serde(rename = "protNFe", skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<Protocol>,
+    #[serde(rename = "procEventoNFe", default)]
+    pub events: Vec<EventProc>,
+}
+
+impl ProtocolQueryResponse {
+    /// Registered events of the given type, in the order returned by SEFAZ
+    pub fn events_of(&self, event_type: EventType) -> impl Iterator<Item = &EventProc> {
+        self.events.iter().filter(move |proc| {
+            proc.event.info.event_type() == event_type && proc.result.info.is_registered()
+        })
+    }
+
+    /// Registered cancellation of the document
+    pub fn cancellation(&self) -> Option<&EventProc> {
+        self.events_of(EventType::Cancellation).next()
+    }
+
+    /// Registered correction letter with the highest sequence, which replaces the others
+    pub fn last_correction(&self) -> Option<&EventProc> {
+        self.events_of(EventType::Correction)
+            .max_by_key(|proc| proc.event.info.sequence)
+    }
+}
+
+/// Query of the status of the webservices of a state (consStatServ)
+///
+/// environment: Environment type (tpAmb)
+/// state: State of the authorizer (cUF)
+#[derive(Debug, PartialEq)]
+pub struct StatusQuery {
+    pub environment: Environment,
+    pub state: State,
+}
+
+impl Serialize for StatusQuery {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("consStatServ", 5)?;
+        state.serialize_field("@xmlns", NFE_NAMESPACE)?;
+        state.serialize_field("@versao", "4.00")?;
+        state.serialize_field("tpAmb", &self.environment)?;
+        state.serialize_field("cUF", &self.state.code())?;
+        state.serialize_field("xServ", "STATUS")?;
+        state.end()
+    }
+}
+
+/// Response of the status webservice (retConsStatServ)
+///
+/// version: Layout version (@versao)
+/// environment: Environment type (tpAmb)
+/// application_version: Version of the SEFAZ application (verAplic)
+/// status: Status code of the service (cStat)
+/// reason: Status description (xMotivo)
+/// state_code: IBGE code of the state that answered (cUF)
+/// received_at: Date and time of the processing (dhRecbto)
+/// average_time: Average processing time in seconds (tMed) - Optional
+/// return_at: Expected return of a paused service (dhRetorno) - Optional
+/// observation: Notes of the authorizer (xObs) - Optional
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct StatusResponse {
+    #[serde(rename = "@versao")]
+    pub version: String,
+    #[serde(rename = "tpAmb")]
+    pub environment: Environment,
+    #[serde(rename = "verAplic")]
+    pub application_version: String,
+    #[serde(rename = "cStat")]
+    pub status: u16,
+    #[serde(rename = "xMotivo")]
+    pub reason: String,
+    #[serde(rename = "cUF")]
+    pub state_code: u8,
+    #[serde(rename = "dhRecbto")]
+    pub received_at: chrono::DateTime<chrono::FixedOffset>,
+    #[serde(rename = "tMed", skip_serializing_if = "Option::is_none")]
+    pub average_time: Option<u32>,
+    #[serde(rename = "dhRetorno", skip_serializing_if = "Option::is_none")]
+    pub return_at: Option<chrono::DateTime<chrono::FixedOffset>>,
+    #[serde(rename = "xObs", skip_serializing_if = "Option::is_none")]
+    pub observation: Option<String>,
+}
+
+/// Availability of the webservices of an authorizer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceStatus {
+    /// Service in operation (107)
+    Operational,
+    /// Service paused for a moment (108)
+    Paused,
+    /// Service paused without a forecast (109) or any other status
+    Unavailable,
+}
+
+impl From<&StatusResponse> for ServiceStatus {
+    fn from(response: &StatusResponse) -> Self {
+        match response.status {
+            107 => ServiceStatus::Operational,
+            108 => ServiceStatus::Paused,
+            _ => ServiceStatus::Unavailable,
+        }
+    }
+}
+
+/// Status of the service with the time taken by the request
+///
+/// status: Availability of the service
+/// response_time: Time between sending the request and reading the response
+/// response: Response of the webservice (retConsStatServ)
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusReport {
+    pub status: ServiceStatus,
+    pub response_time: std::time::Duration,
+    pub response: StatusResponse,
+}
+
+/// Async client of the SEFAZ webservices
+///
+/// Clients created from a configuration check its certificate before each call.
+/// Transient failures are retried following the `RetryPolicy` of the client.
+pub struct Client {
+    http: reqwest::Client,
+    config: Option<Config>,
+    retry: RetryPolicy,
+    hook: Option<AttemptHook>,
+}
+
+impl Client {
+    /// Creates a client authenticating with the certificate of the global configuration
+    pub fn from_config() -> Result<Self, ClientError> {
+        let config =
+            crate::config::global().map_err(|e| ClientError::Sign(SignError::ConfigError(e)))?;
+        Self::with_config(&config)
+    }
+
+    /// Creates a client authenticating with the certificate of `config`
+    ///
+    /// The requests go through the proxy of the configuration, trusting its
+    /// root certificates besides the default ones.
+    pub fn with_config(config: &Config) -> Result<Self, ClientError> {
+        let certificate = config.certificate().map_err(ClientError::Sign)?;
+        let mut roots = Vec::new();
+        for path in config.root_certificates() {
+            roots.extend(tls::read_root_certificates(path)?);
+        }
+        let tls = Tls::with_roots(&certificate, &roots)?;
+        let mut client = Self::build(&tls, config.proxy())?;
+        client.config = Some(config.clone());
+        Ok(client)
+    }
+
+    pub fn new(certificate: &Certificate) -> Result<Self, ClientError> {
+        Self::with_tls(&Tls::new(certificate)?)
+    }
+
+    /// Creates a client with a TLS configuration, that may be shared by other clients
+    pub fn with_tls(tls: &Tls) -> Result<Self, ClientError> {
+        Self::build(tls, None)
+    }
+
+    /// Creates a client reaching the webservices through `proxy`
+    pub fn with_proxy(tls: &Tls, proxy: &ProxyConfig) -> Result<Self, ClientError> {
+        Self::build(tls, Some(proxy))
+    }
+
+    fn build(tls: &Tls, proxy: Option<&ProxyConfig>) -> Result<Self, ClientError> {
+        let mut builder = tls.apply(reqwest::Client::builder());
+        if let Some(config) = proxy {
+            let mut proxy =
+                reqwest::Proxy::all(&config.url).map_err(|e| ClientError::Http(e.to_string()))?;
+            if let Some((username, password)) = &config.credentials {
+                proxy = proxy.basic_auth(username, password);
+            }
+            builder = builder.proxy(proxy);
+        }
+        let http = builder
+            .build()
+            .map_err(|e| ClientError::Tls(e.to_string()))?;
+
+        Ok(Self {
+            http,
+            config: None,
+            retry: RetryPolicy::default(),
+            hook: None,
+        })
+    }
+
+    /// Replaces the default retry policy of the requests
+    pub fn with_retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Calls `hook` after every attempt of the requests
+    pub fn on_attempt(mut self, hook: impl Fn(&retry::Attempt) + Send + Sync + 'static) -> Self {
+        self.hook = Some(std::sync::Arc::new(hook));
+        self
+    }
+
+    async fn post(
+        &self,
+        url: &str,
+        service: Service,
+        request: &str,
+    ) -> Result<String, ClientError> {
+        if let Some(config) = &self.config {
+            config
+                .validate_certificate(config.clock().now())
+                .map_err(ClientError::Certificate)?;
+        }
+        let span = tracing::debug_span!("request", service = service.name(), url);
+        retry::retry(&self.retry, self.hook.as_ref(), service, || {
+            self.send(url, service, request)
+        })
+        .instrument(span)
+        .await
+    }
+
+    /// Sends the request once, failing on the statuses worth retrying
+    ///
+    /// The paused statuses (cStat 108 and 109) are the answer of the status
+    /// service, so they only fail the requests to the other services. Faults
+    /// caused by the request are not retried, even when answered as 5xx.
+    async fn send(
+        &self,
+        url: &str,
+        service: Service,
+        request: &str,
+    ) -> Result<String, ClientError> {
+        if tracing::enabled!(tracing::Level::DEBUG) {
+            tracing::debug!(request = %envelope::redact(request), "Sending the request");
+        }
+        let response = self
+            .http
+            .post(url)
+            .header("Content-Type", "application/soap+xml; charset=utf-8")
+            .body(request.to_string())
+            .send()
+            .await
+            .map_err(|e| {
+                if e.is_timeout() {
+                    ClientError::Timeout
+                } else {
+                    ClientError::Http(e.to_string())
+                }
+            })?;
+        let status = response.status();
+        let text = response
+            .text()
+            .await
+            .map_err(|e| ClientError::Http(e.to_string()))?;
+        if tracing::enabled!(tracing::Level::DEBUG) {
+            tracing::debug!(
+                status = status.as_u16(),
+                response = %envelope::redact(&text),
+                "Received the response"
+            );
+        }
+        if status.is_server_error() {
+            return Err(match envelope::fault(&text) {
+                Some(fault) if fault.is_sender() => ClientError::Fault(fault),
+                _ => ClientError::ServerError(status.as_u16()),
+            });
+        }
+        if service != Service::Status {
+            let status = element_text(&text, "cStat")
+                .and_then(|status| status.parse::<u16>().ok())
+                .map(RejectionCode::from);
+            if let Some(status) = status
+                && status.is_retryable()
+            {
+                let reason = element_text(&text, "xMotivo").unwrap_or_default();
+                return Err(ClientError::Rejected(status, reason));
+            }
+        }
+        Ok(text)
+    }
+
+    /// Sends the batch to the NFeAutorizacao4 webservice of the authorizer of its documents
+    pub async fn authorize(
+        &self,
+        batch: &AuthorizationBatch,
+    ) -> Result<AuthorizationResponse, ClientError> {
+        let service = Service::Authorization;
+        // Checks the batch first, the environment is the one of all its documents
+        let authorizer = batch.authorizer()?;
+        let environment = &batch.documents[0].info.identification.environment;
+        let url = authorizer
+            .url(environment, service)
+            .ok_or(ClientError::UnavailableService(service))?;
+        let request = SoapEnvelope::new(service, "enviNFe", batch).to_xml()?;
+        let keys = batch
+            .documents
+            .iter()
+            .map(|nfe| nfe.info.access_key().to_string())
+            .collect::<Vec<_>>();
+        let span = tracing::info_span!("authorize", lote = batch.id, chave = keys.join(","));
+        let response = self.post(&url, service, &request).instrument(span).await?;
+        parse_authorization_response(&response)
+    }
+
+    /// Sends the NF-e for authorization, falling back to the virtual contingency
+    ///
+    /// When the authorizer of the state keeps failing (timeouts, server errors
+    /// or paused service, after the retries of the client), the document is
+    /// moved to SVC-AN or SVC-RS with `contingency::svc`, signed again with
+    /// `certificate` and sent there. NFC-e and documents already in
+    /// contingency are only sent to their authorizer.
+    pub async fn authorize_with_failover(
+        &self,
+        id: u64,
+        nfe: NFe,
+        certificate: &Certificate,
+        justification: &str,
+    ) -> Result<Authorization, ClientError> {
+        let mut batch = AuthorizationBatch::single(id, nfe);
+        let result = self.authorize(&batch).await;
+        let nfe = batch.documents.remove(0);
+        let identification = &nfe.info.identification;
+        match result {
+            Ok(response) => return Ok(Authorization { nfe, response }),
+            Err(error)
+                if retry::is_transient(&error)
+                    && identification.model == Model::NFe
+                    && identification.emission_type == EmissionType::Normal => {}
+            Err(error) => return Err(error),
+        }
+
+        let now = self
+            .config
+            .as_ref()
+            .map_or_else(chrono::Local::now, |config| config.clock().local());
+        let contingency = Contingency::new(now, justification);
+        let info = crate::contingency::svc(nfe.info, contingency);
+        let nfe = NFe::with_certificate(info, certificate).map_err(ClientError::Sign)?;
+        let mut batch = AuthorizationBatch::single(id, nfe);
+        let response = self.authorize(&batch).await?;
+        Ok(Authorization {
+            nfe: batch.documents.remove(0),
+            response,
+        })
+    }
+
+    /// Sends up to 50 documents in an asynchronous batch
+    ///
+    /// The protocols of the documents are queried with `query_receipt` once
+    /// the batch is processed, after around its `average_time`.
+    pub async fn send_batch(
+        &self,
+        id: u64,
+        documents: Vec<NFe>,
+    ) -> Result<PendingBatch, ClientError> {
+        let batch = AuthorizationBatch::asynchronous(id, documents);
+        let authorizer = batch.authorizer()?;
+        let environment = batch.documents[0].info.identification.environment.clone();
+        let response = self.authorize(&batch).await?;
+        match response.receipt {
+            Some(receipt) if response.status == 103 => Ok(PendingBatch {
+                authorizer,
+                environment,
+                receipt,
+            }),
+            _ => Err(ClientError::Rejected(
+                response.status.into(),
+                response.reason,
+            )),
+        }
+    }
+
+    /// Queries the result of the batch in the NFeRetAutorizacao4 webservice of its authorizer
+    pub async fn query_receipt(
+        &self,
+        batch: &PendingBatch,
+    ) -> Result<ReceiptResponse, ClientError> {
+        let service = Service::AuthorizationResult;
+        let url = batch
+            .authorizer
+            .url(&batch.environment, service)
+            .ok_or(ClientError::UnavailableService(service))?;
+        let query = ReceiptQuery {
+            environment: batch.environment.clone(),
+            receipt_number: batch.receipt.number.clone(),
+        };
+        let request = SoapEnvelope::new(service, "consReciNFe", &query).to_xml()?;
+        let span = tracing::info_span!("query_receipt", recibo = query.receipt_number);
+        let response = self.post(&url, service, &request).instrument(span).await?;
+        parse_receipt_response(&response)
+    }
+
+    /// Waits for the result of the batch, querying its receipt until it is processed
+    ///
+    /// The first query is sent after the average time (tMed) of the receipt,
+    /// the next ones every `poll_interval`. Fails with
+    /// `ClientError::Unprocessed` when the batch is still processing (105)
+    /// and the next query would go past `timeout`. Querying too often may be
+    /// blocked as excessive consumption (656).
+    pub async fn wait_for_result(
+        &self,
+        batch: &PendingBatch,
+        poll_interval: Duration,
+        timeout: Duration,
+    ) -> Result<ReceiptResponse, ClientError> {
+        poll_receipt(batch, poll_interval, timeout, || self.query_receipt(batch)).await
+    }
+
+    /// Queries the documents of interest in the NFeDistribuicaoDFe webservice of the Ambiente Nacional
+    ///
+    /// The `docZip` of the response are decompressed on demand with `DocZip::document`.
+    pub async fn distribution(
+        &self,
+        query: &DistributionQuery,
+    ) -> Result<DistributionResponse, ClientError> {
+        let service = Service::Distribution;
+        let url = Authorizer::AN
+            .url(&query.environment, service)
+            .ok_or(ClientError::UnavailableService(service))?;
+        let request = SoapEnvelope::new(service, "distDFeInt", query).to_xml()?;
+        let response = self.post(&url, service, &request).await?;
+        parse_distribution_response(&response)
+    }
+
+    /// Queries the status of the NfeStatusServico4 webservice of the state
+    pub async fn service_status(
+        &self,
+        state: State,
+        environment: Environment,
+    ) -> Result<StatusReport, ClientError> {
+        let service = Service::Status;
+        let url = endpoints::url(&state, &environment, service)
+            .ok_or(ClientError::UnavailableService(service))?;
+        let query = StatusQuery { environment, state };
+        let request = SoapEnvelope::new(service, "consStatServ", &query).to_xml()?;
+
+        let start = std::time::Instant::now();
+        let response = self.post(&url, service, &request).await?;
+        let response_time = start.elapsed();
+
+        let response = parse_status_response(&response)?;
+        Ok(StatusReport {
+            status: ServiceStatus::from(&response),
+            response_time,
+            response,
+        })
+    }
+
+    /// Queries the protocol and events of the document in its authorizer (NFeConsultaProtocolo4)
+    pub async fn query_status(
+        &self,
+        access_key: &AccessKey,
+        environment: Environment,
+    ) -> Result<ProtocolQueryResponse, ClientError> {
+        let service = Service::ProtocolQuery;
+        let url = endpoints::url(&access_key.state(), &environment, service)
+            .ok_or(ClientError::UnavailableService(service))?;
+        let query = ProtocolQuery {
+            environment,
+            access_key: access_key.clone(),
+        };
+        let request = SoapEnvelope::new(service, "consSitNFe", &query).to_xml()?;
+        let span = tracing::info_span!("query_status", chave = access_key.as_str());
+        let response = self.post(&url, service, &request).instrument(span).await?;
+        parse_protocol_query_response(&response)
+    }
+
+    /// Queries the registration of the taxpayer in the CadConsultaCadastro4 webservice of the state
+    ///
+    /// The situation, state registrations and address of the taxpayer are in
+    /// `RegistrationResponse::taxpayers`, empty when it is not registered.
+    pub async fn query_registration(
+        &self,
+        state: State,
+        document: TaxpayerDocument,
+        environment: Environment,
+    ) -> Result<RegistrationResponse, ClientError> {
+        let service = Service::Registration;
+        let url = endpoints::url(&state, &environment, service)
+            .ok_or(ClientError::UnavailableService(service))?;
+        let query = RegistrationQuery { state, document };
+        let request = SoapEnvelope::new(service, "ConsCad", &query).to_xml()?;
+        let span = tracing::info_span!("query_registration", uf = query.state.acronym());
+        let response = self.post(&url, service, &request).instrument(span).await?;
+        parse_registration_response(&response)
+    }
+
+    /// Sends the batch to the NFeRecepcaoEvento4 webservice of the state of its events
+    pub async fn send_events<TDet: EventPayload>(
+        &self,
+        batch: &EventBatch<TDet>,
+    ) -> Result<EventResponse, ClientError> {
+        let service = Service::Event;
+        let info = &batch
+            .events
+            .first()
+            .ok_or_else(|| ClientError::Serialization("Empty event batch".to_string()))?
+            .info;
+        let url = event_url(info)?;
+        let request = SoapEnvelope::new(service, "envEvento", batch).to_xml()?;
+        let span = tracing::info_span!(
+            "send_events",
+            lote = batch.id,
+            chave = info.access_key.as_str()
+        );
+        let response = self.post(&url, service, &request).instrument(span).await?;
+        parse_event_response(&response)
+    }
+
+    /// Sends the request to the NFeInutilizacao4 webservice of the state of the issuer
+    pub async fn disable(
+        &self,
+        disablement: &Disablement,
+    ) -> Result<DisablementResponse, ClientError> {
+        let service = Service::Disablement;
+        let info = &disablement.info;
+        let url = endpoints::url(&info.state, &info.environment, service)
+            .ok_or(ClientError::UnavailableService(service))?;
+        let request = SoapEnvelope::new(service, "inutNFe", disablement).to_xml()?;
+        let span = tracing::info_span!("disable", id = info.id());
+        let response = self.post(&url, service, &request).instrument(span).await?;
+        parse_disablement_response(&response)
+    }
+}
+
+/// Queries the receipt with `query` until the batch is processed, see
+/// `Client::wait_for_result`
+async fn poll_receipt<F, Fut>(
+    batch: &PendingBatch,
+    poll_interval: Duration,
+    timeout: Duration,
+    mut query: F,
+) -> Result<ReceiptResponse, ClientError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<ReceiptResponse, ClientError>>,
+{
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut delay = Duration::from_secs(batch.receipt.average_time.into());
+    loop {
+        if tokio::time::Instant::now() + delay > deadline {
+            return Err(ClientError::Unprocessed(batch.receipt.number.clone()));
+        }
+        tokio::time::sleep(delay).await;
+        let response = query().await?;
+        if !response.is_processing() {
+            return Ok(response);
+        }
+        delay = poll_interval;
+    }
+}
+
+/// Parses the SOAP response of the NFeAutorizacao4 webservice
+pub fn parse_authorization_response(xml: &str) -> Result<AuthorizationResponse, ClientError> {
+    unwrap_response(xml, "retEnviNFe")
+}
+
+/// Parses the SOAP response of the NFeRetAutorizacao4 webservice
+pub fn parse_receipt_response(xml: &str) -> Result<ReceiptResponse, ClientError> {
+    unwrap_response(xml, "retConsReciNFe")
+}
+
+/// URL of the NFeRecepcaoEvento4 webservice of the organ of the event
+///
+/// Events of the Ambiente Nacional (cOrgao 91), as the manifestations of the
+/// recipient, are sent to it instead of the authorizer of a state.
+fn event_url<TDet>(info: &EventInfo<TDet>) -> Result<String, ClientError> {
+    let service = Service::Event;
+    let url = if info.organ == NATIONAL_ORGAN {
+        Authorizer::AN.url(&info.environment, service)
+    } else {
+        let state = State::try_from(info.organ).map_err(ClientError::Serialization)?;
+        endpoints::url(&state, &info.environment, service)
+    };
+    url.ok_or(ClientError::UnavailableService(service))
+}
+
+/// Parses the SOAP response of the NFeDistribuicaoDFe webservice
+pub fn parse_distribution_response(xml: &str) -> Result<DistributionResponse, ClientError> {
+    unwrap_response(xml, "retDistDFeInt")
+}
+
+/// Parses the SOAP response of the NfeStatusServico4 webservice
+pub fn parse_status_response(xml: &str) -> Result<StatusResponse, ClientError> {
+    unwrap_response(xml, "retConsStatServ")
+}
+
+/// Parses the SOAP response of the NFeConsultaProtocolo4 webservice
+pub fn parse_protocol_query_response(xml: &str) -> Result<ProtocolQueryResponse, ClientError> {
+    unwrap_response(xml, "retConsSitNFe")
+}
+
+/// Parses the SOAP response of the CadConsultaCadastro4 webservice
+pub fn parse_registration_response(xml: &str) -> Result<RegistrationResponse, ClientError> {
+    unwrap_response(xml, "retConsCad")
+}
+
+/// Parses the SOAP response of the NFeRecepcaoEvento4 webservice
+pub fn parse_event_response(xml: &str) -> Result<EventResponse, ClientError> {
+    unwrap_response(xml, "retEnvEvento")
+}
+
+/// Parses the SOAP response of the NFeInutilizacao4 webservice
+pub fn parse_disablement_response(xml: &str) -> Result<DisablementResponse, ClientError> {
+    unwrap_response(xml, "retInutNFe")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access_key::tests::setup_access_key;
+    use crate::enums::{CNPJ, PersonDocument};
+    use crate::events::{CancellationDetail, CorrectionEvent, EventDetail, OtherDetail};
+    use crate::models::ProtocolInfo;
+    use crate::models::tests::setup_nfe;
+    use crate::taxpayer::{Accreditation, RegistrationResult};
+    use chrono::DateTime;
+
+    fn setup_protocol() -> Protocol {
+        Protocol {
+            version: "4.00".to_string(),
+            info: ProtocolInfo {
+                id: Some("ID135250000000001".to_string()),
+                environment: Environment::Homologation,
+                application_version: "SP_NFE_PL009_V4".to_string(),
+                access_key: "35250812345678000195650010000000011000000019".to_string(),
+                received_at: DateTime::parse_from_rfc3339("2025-08-01T10:00:01-03:00").unwrap(),
+                number: Some("135250000000001".to_string()),
+                digest: Some("oUXFqAZS2BejMb22mU0r/DBzolo=".to_string()),
+                status: 100,
+                reason: "Autorizado o uso da NF-e".to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_parse_authorized_response() {
+        let response =
+            parse_authorization_response(include_str!("../tests/fixtures/soap/ret_envi_nfe.xml"))
+                .expect("Failed to parse response");
+
+        assert_eq!(response.status, 104);
+        assert_eq!(response.state_code, 35);
+        assert_eq!(response.receipt, None);
+        assert_eq!(response.protocol, Some(setup_protocol()));
+        assert_eq!(response.into_protocol(), Ok(setup_protocol()));
+    }
+
+    #[test]
+    fn test_parse_rejected_response() {
+        let response = parse_authorization_response(include_str!(
+            "../tests/fixtures/soap/ret_envi_nfe_rejected.xml"
+        ))
+        .expect("Failed to parse response");
+
+        assert_eq!(
+            response.into_protocol(),
+            Err(ClientError::Rejected(
+                RejectionCode::DuplicateWithDifferentKey,
+                "Rejeicao: Duplicidade de NF-e com diferenca na Chave de Acesso".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_event_url() {
+        use crate::events::ManifestationEvent;
+
+        let author = PersonDocument::CNPJ(CNPJ("98765432000198".to_string()));
+        let date = DateTime::parse_from_rfc3339("2025-08-02T09:00:00-03:00").unwrap();
+        let manifestation = ManifestationEvent::confirmation(&setup_access_key())
+            .unwrap()
+            .info(Environment::Production, author.clone(), date);
+        assert_eq!(
+            event_url(&manifestation).unwrap(),
+            "https://www.nfe.fazenda.gov.br/NFeRecepcaoEvento4/NFeRecepcaoEvento4.asmx"
+        );
+        let correction =
+            CorrectionEvent::new(&setup_access_key(), 1, "Onde se le Rua A, leia-se Rua B")
+                .unwrap()
+                .info(Environment::Production, author, date);
+        assert_eq!(
+            event_url(&correction).unwrap(),
+            endpoints::url(&State::SaoPaulo, &Environment::Production, Service::Event).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_event_response() {
+        let response =
+            parse_event_response(include_str!("../tests/fixtures/soap/ret_env_evento.xml"))
+                .expect("Failed to parse response");
+
+        assert_eq!(response.status, 128);
+        assert_eq!(response.results.len(), 1);
+        let result = &response.results[0].info;
+        assert!(result.is_registered());
+        assert_eq!(result.event_type, Some(EventType::Cancellation));
+        assert_eq!(result.protocol, Some("135250000000002".to_string()));
+    }
+
+    #[test]
+    fn test_parse_correction_response() {
+        let response = parse_event_response(include_str!(
+            "../tests/fixtures/soap/ret_env_evento_correction.xml"
+        ))
+        .expect("Failed to parse response");
+
+        let info = CorrectionEvent::new(&setup_access_key(), 2, "Onde se le Rua A, leia-se Rua B")
+            .unwrap()
+            .info(
+                Environment::Homologation,
+                PersonDocument::CNPJ(CNPJ("12345678000195".to_string())),
+                chrono::Local::now().fixed_offset(),
+            );
+        let result = response.result_of(&info).expect("Missing result");
+        assert!(result.is_registered());
+        assert_eq!(result.event_type, Some(EventType::Correction));
+
+        let mut previous = info.clone();
+        previous.sequence = 1;
+        assert_eq!(response.result_of(&previous), None);
+    }
+
+    #[test]
+    fn test_parse_disablement_response() {
+        let response =
+            parse_disablement_response(include_str!("../tests/fixtures/soap/ret_inut_nfe.xml"))
+                .expect("Failed to parse response");
+
+        assert!(response.info.is_homologated());
+        assert_eq!(response.info.start, Some(10));
+        assert_eq!(response.info.end, Some(15));
+        assert_eq!(response.info.protocol, Some("135250000000003".to_string()));
+    }
+
+    #[test]
+    fn test_parse_distribution_response() {
+        let response = parse_distribution_response(include_str!(
+            "../tests/fixtures/soap/ret_dist_dfe_int.xml"
+        ))
+        .expect("Failed to parse response");
+
+        assert_eq!(response.last_nsu, 43);
+        assert!(response.has_more());
+        assert_eq!(response.documents().len(), 1);
+        let document = response.documents()[0]
+            .document()
+            .expect("Failed to read document");
+        assert!(matches!(
+            document,
+            crate::distribution::DistributedDocument::Summary(_)
+        ));
+    }
+
+    #[test]
+    fn test_batch_serialization() {
+        let batch = AuthorizationBatch::asynchronous(7, vec![setup_nfe(), setup_nfe()]);
+        let xml = quick_xml::se::to_string_with_root("enviNFe", &batch).unwrap();
+        assert!(xml.contains("<idLote>7</idLote><indSinc>0</indSinc><NFe"));
+        assert_eq!(xml.matches("<NFe ").count(), 2);
+        assert_eq!(
+            quick_xml::de::from_str::<AuthorizationBatch>(&xml).unwrap(),
+            batch
+        );
+    }
+
+    #[test]
+    fn test_invalid_batch() {
+        let invalid = |batch: AuthorizationBatch| {
+            matches!(batch.authorizer(), Err(ClientError::InvalidBatch(_)))
+        };
+        assert_eq!(
+            AuthorizationBatch::single(1, setup_nfe()).authorizer(),
+            Ok(Authorizer::MG)
+        );
+        assert!(invalid(AuthorizationBatch::asynchronous(1, Vec::new())));
+        assert!(invalid(AuthorizationBatch {
+            id: 1,
+            synchronous: true,
+            documents: vec![setup_nfe(), setup_nfe()],
+        }));
+        let xml = quick_xml::se::to_string(&setup_nfe()).unwrap();
+        let documents = (0..=MAX_BATCH_DOCUMENTS)
+            .map(|_| quick_xml::de::from_str(&xml).unwrap())
+            .collect();
+        assert!(invalid(AuthorizationBatch::asynchronous(1, documents)));
+
+        let mut other = setup_nfe();
+        other.info.identification.location.state = State::SaoPaulo;
+        assert!(invalid(AuthorizationBatch::asynchronous(
+            1,
+            vec![setup_nfe(), other]
+        )));
+    }
+
+    #[test]
+    fn test_authorize_empty_batch() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let client = Client::new(&crate::sign::tests::setup_certificate()).unwrap();
+        let batch = AuthorizationBatch::asynchronous(1, Vec::new());
+        assert_eq!(
+            runtime.block_on(client.authorize(&batch)).unwrap_err(),
+            ClientError::InvalidBatch("Empty batch".to_string())
+        );
+    }
+
+    #[test]
+    fn test_receipt_query() {
+        let query = ReceiptQuery {
+            environment: Environment::Homologation,
+            receipt_number: "351000000000001".to_string(),
+        };
+        let expected = concat!(
+            r#"<consReciNFe xmlns="http://www.portalfiscal.inf.br/nfe" versao="4.00">"#,
+            "<tpAmb>2</tpAmb><nRec>351000000000001</nRec></consReciNFe>"
+        );
+        assert_eq!(
+            quick_xml::se::to_string_with_root("consReciNFe", &query).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_parse_receipt_response() {
+        let response =
+            parse_receipt_response(include_str!("../tests/fixtures/soap/ret_cons_reci_nfe.xml"))
+                .expect("Failed to parse response");
+
+        assert!(!response.is_processing());
+        assert_eq!(response.protocols.len(), 2);
+        assert_eq!(
+            response.protocol_of(&setup_access_key()),
+            Ok(&setup_protocol())
+        );
+        let rejected = AccessKey::parse("35250812345678000195650010000000021000000024").unwrap();
+        assert_eq!(
+            response.protocol_of(&rejected),
+            Err(ClientError::Rejected(
+                RejectionCode::DuplicateNFe,
+                "Rejeicao: Duplicidade de NF-e".to_string()
+            ))
+        );
+    }
+
+    fn setup_pending_batch(average_time: u32) -> PendingBatch {
+        PendingBatch {
+            authorizer: Authorizer::SP,
+            environment: Environment::Homologation,
+            receipt: Receipt {
+                number: "351000000000001".to_string(),
+                average_time,
+            },
+        }
+    }
+
+    #[test]
+    fn test_poll_receipt() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        let processed =
+            parse_receipt_response(include_str!("../tests/fixtures/soap/ret_cons_reci_nfe.xml"))
+                .unwrap();
+        let processing = ReceiptResponse {
+            status: 105,
+            reason: "Lote em processamento".to_string(),
+            protocols: vec![],
+            ..processed.clone()
+        };
+        let interval = Duration::from_millis(1);
+
+        let mut queries = 0;
+        let result = runtime.block_on(poll_receipt(
+            &setup_pending_batch(0),
+            interval,
+            Duration::from_secs(1),
+            || {
+                queries += 1;
+                let response = if queries < 3 {
+                    processing.clone()
+                } else {
+                    processed.clone()
+                };
+                async move { Ok(response) }
+            },
+        ));
+        assert_eq!(result, Ok(processed.clone()));
+        assert_eq!(queries, 3);
+
+        let result = runtime.block_on(poll_receipt(
+            &setup_pending_batch(0),
+            interval,
+            Duration::from_millis(20),
+            || {
+                let response = processing.clone();
+                async move { Ok(response) }
+            },
+        ));
+        assert_eq!(
+            result,
+            Err(ClientError::Unprocessed("351000000000001".to_string()))
+        );
+
+        // The average time of the receipt is waited before the first query
+        let result = runtime.block_on(poll_receipt(
+            &setup_pending_batch(5),
+            interval,
+            Duration::from_secs(1),
+            || async { panic!("Queried before the average time") },
+        ));
+        assert_eq!(
+            result,
+            Err(ClientError::Unprocessed("351000000000001".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_status_query() {
+        let query = StatusQuery {
+            environment: Environment::Production,
+            state: State::MinasGerais,
+        };
+        let expected = concat!(
+            r#"<consStatServ xmlns="http://www.portalfiscal.inf.br/nfe" versao="4.00">"#,
+            "<tpAmb>1</tpAmb><cUF>31</cUF><xServ>STATUS</xServ></consStatServ>"
+        );
+        assert_eq!(
+            quick_xml::se::to_string_with_root("consStatServ", &query).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_parse_status_response() {
+        let response = parse_status_response(include_str!(
+            "../tests/fixtures/soap/ret_cons_stat_serv.xml"
+        ))
+        .expect("Failed to parse response");
+
+        assert_eq!(ServiceStatus::from(&response), ServiceStatus::Operational);
+        assert_eq!(response.average_time, Some(1));
+
+        let paused = StatusResponse {
+            status: 108,
+            ..response.clone()
+        };
+        assert_eq!(ServiceStatus::from(&paused), ServiceStatus::Paused);
+        let unavailable = StatusResponse {
+            status: 109,
+            ..response
+        };
+        assert_eq!(
+            ServiceStatus::from(&unavailable),
+            ServiceStatus::Unavailable
+        );
+    }
+
+    #[test]
+    fn test_protocol_query() {
+        let query = ProtocolQuery {
+            environment: Environment::Homologation,
+            access_key: setup_access_key(),
+        };
+        let expected = concat!(
+            r#"<consSitNFe xmlns="http://www.portalfiscal.inf.br/nfe" versao="4.00">"#,
+            "<tpAmb>2</tpAmb><xServ>CONSULTAR</xServ>",
+            "<chNFe>35250812345678000195650010000000011000000019</chNFe></consSitNFe>"
+        );
+        assert_eq!(
+            quick_xml::se::to_string_with_root("consSitNFe", &query).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_parse_protocol_query_response() {
+        let response = parse_protocol_query_response(include_str!(
+            "../tests/fixtures/soap/ret_cons_sit_nfe.xml"
+        ))
+        .expect("Failed to parse response");
+
+        assert_eq!(response.status, 101);
+        assert_eq!(response.protocol, Some(setup_protocol()));
+        assert_eq!(response.last_correction(), None);
+        let cancellation = response.cancellation().expect("Missing cancellation");
+        assert_eq!(
+            cancellation.event.info.detail,
+            EventDetail::Cancellation(CancellationDetail {
+                protocol: "135250000000001".to_string(),
+                justification: "Cancelamento por erro na digitacao".to_string(),
+            })
+        );
+        assert_eq!(
+            cancellation.result.info.protocol,
+            Some("135250000000002".to_string())
+        );
+
+        // Events registered by SEFAZ of types not known by the crate
+        let xml =
+            include_str!("../tests/fixtures/soap/ret_cons_sit_nfe.xml").replace("110111", "610600");
+        let response = parse_protocol_query_response(&xml).expect("Failed to parse response");
+        assert_eq!(response.cancellation(), None);
+        let event = &response.events[0].event.info;
+        assert_eq!(event.event_type(), EventType::Other(610600));
+        assert_eq!(
+            event.detail,
+            EventDetail::Other(OtherDetail {
+                event_type: 610600,
+                description: "Cancelamento".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_registration_response() {
+        let response =
+            parse_registration_response(include_str!("../tests/fixtures/soap/ret_cons_cad.xml"))
+                .expect("Failed to parse response");
+
+        assert_eq!(response.info.status, 111);
+        assert_eq!(response.info.state, State::SaoPaulo);
+        let taxpayer = response.enabled().expect("Missing enabled taxpayer");
+        assert_eq!(taxpayer.ie.0, "110042490114");
+        assert_eq!(taxpayer.nfe_accreditation, Accreditation::Required);
+        assert_eq!(
+            taxpayer
+                .address
+                .as_ref()
+                .and_then(|address| address.city_code),
+            Some(3550308)
+        );
+
+        let not_found = RegistrationResponse {
+            info: RegistrationResult {
+                status: 259,
+                ..response.info.clone()
+            },
+            ..response
+        };
+        assert!(not_found.taxpayers().is_empty());
+    }
+
+    #[test]
+    fn test_parse_missing_element() {
+        let result = parse_authorization_response("<soap:Envelope></soap:Envelope>");
+        assert!(matches!(result, Err(ClientError::Deserialization(_))));
+    }
+
+    #[test]
+    fn test_client_identity() {
+        assert!(Client::new(&crate::sign::tests::setup_certificate()).is_ok());
+    }
+
+    #[test]
+    fn test_client_proxy() {
+        let tls = Tls::new(&crate::sign::tests::setup_certificate()).unwrap();
+        let proxy = ProxyConfig::new("http://proxy.example:3128".to_string())
+            .with_credentials("user".to_string(), "secret".to_string());
+        assert!(Client::with_proxy(&tls, &proxy).is_ok());
+
+        let invalid = ProxyConfig::new("not a url".to_string());
+        assert!(matches!(
+            Client::with_proxy(&tls, &invalid),
+            Err(ClientError::Http(_))
+        ));
+
+        let config = crate::models::tests::setup_config()
+            .with_proxy(proxy)
+            .with_root_certificate("tests/credentials/cert.pem".to_string());
+        assert!(Client::with_config(&config).is_ok());
+        let config = config.with_root_certificate("tests/credentials/missing.pem".to_string());
+        assert!(matches!(
+            Client::with_config(&config),
+            Err(ClientError::Tls(_))
+        ));
+    }
+}
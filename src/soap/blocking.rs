@@ -0,0 +1,206 @@
+//! Blocking client of the SEFAZ webservices
+//!
+//! Mirrors the async `Client` for synchronous callers, as POS and desktop
+//! systems, running its requests on a runtime of its own. As the blocking
+//! client of reqwest, it must not be used from inside of an async runtime.
+
+use super::retry::{Attempt, RetryPolicy};
+use super::tls::Tls;
+use super::{
+    Authorization, AuthorizationBatch, AuthorizationResponse, ClientError, PendingBatch,
+    ProtocolQueryResponse, ReceiptResponse, StatusReport,
+};
+use crate::access_key::AccessKey;
+use crate::config::{Config, ProxyConfig};
+use crate::disablement::{Disablement, DisablementResponse};
+use crate::distribution::{DistributionQuery, DistributionResponse};
+use crate::enums::Environment;
+use crate::events::{EventBatch, EventPayload, EventResponse};
+use crate::models::NFe;
+use crate::monitor::{AvailabilityReport, Monitor};
+use crate::sign::Certificate;
+use crate::states::State;
+use crate::taxpayer::{RegistrationResponse, TaxpayerDocument};
+use std::time::Duration;
+use tokio::runtime::Runtime;
+
+/// Blocking client of the SEFAZ webservices
+///
+/// Has the same methods and the same behavior of `soap::Client`, returning
+/// once the request and its retries are finished.
+pub struct Client {
+    inner: super::Client,
+    runtime: Runtime,
+}
+
+impl Client {
+    fn wrap(inner: super::Client) -> Result<Self, ClientError> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| ClientError::Http(e.to_string()))?;
+        Ok(Self { inner, runtime })
+    }
+
+    /// Creates a client authenticating with the certificate of the global configuration
+    pub fn from_config() -> Result<Self, ClientError> {
+        Self::wrap(super::Client::from_config()?)
+    }
+
+    /// Creates a client authenticating with the certificate of `config`
+    pub fn with_config(config: &Config) -> Result<Self, ClientError> {
+        Self::wrap(super::Client::with_config(config)?)
+    }
+
+    pub fn new(certificate: &Certificate) -> Result<Self, ClientError> {
+        Self::wrap(super::Client::new(certificate)?)
+    }
+
+    /// Creates a client with a TLS configuration, that may be shared by other clients
+    pub fn with_tls(tls: &Tls) -> Result<Self, ClientError> {
+        Self::wrap(super::Client::with_tls(tls)?)
+    }
+
+    /// Creates a client reaching the webservices through `proxy`
+    pub fn with_proxy(tls: &Tls, proxy: &ProxyConfig) -> Result<Self, ClientError> {
+        Self::wrap(super::Client::with_proxy(tls, proxy)?)
+    }
+
+    /// Replaces the default retry policy of the requests
+    pub fn with_retry(mut self, policy: RetryPolicy) -> Self {
+        self.inner = self.inner.with_retry(policy);
+        self
+    }
+
+    /// Calls `hook` after every attempt of the requests
+    pub fn on_attempt(mut self, hook: impl Fn(&Attempt) + Send + Sync + 'static) -> Self {
+        self.inner = self.inner.on_attempt(hook);
+        self
+    }
+
+    /// Sends the batch to the NFeAutorizacao4 webservice of the authorizer of its documents
+    pub fn authorize(
+        &self,
+        batch: &AuthorizationBatch,
+    ) -> Result<AuthorizationResponse, ClientError> {
+        self.runtime.block_on(self.inner.authorize(batch))
+    }
+
+    /// Sends the NF-e for authorization, falling back to the virtual contingency
+    ///
+    /// See `soap::Client::authorize_with_failover`.
+    pub fn authorize_with_failover(
+        &self,
+        id: u64,
+        nfe: NFe,
+        certificate: &Certificate,
+        justification: &str,
+    ) -> Result<Authorization, ClientError> {
+        self.runtime.block_on(self.inner.authorize_with_failover(
+            id,
+            nfe,
+            certificate,
+            justification,
+        ))
+    }
+
+    /// Sends up to 50 documents in an asynchronous batch
+    pub fn send_batch(&self, id: u64, documents: Vec<NFe>) -> Result<PendingBatch, ClientError> {
+        self.runtime.block_on(self.inner.send_batch(id, documents))
+    }
+
+    /// Queries the result of the batch in the NFeRetAutorizacao4 webservice of its authorizer
+    pub fn query_receipt(&self, batch: &PendingBatch) -> Result<ReceiptResponse, ClientError> {
+        self.runtime.block_on(self.inner.query_receipt(batch))
+    }
+
+    /// Waits for the result of the batch, see `super::Client::wait_for_result`
+    pub fn wait_for_result(
+        &self,
+        batch: &PendingBatch,
+        poll_interval: Duration,
+        timeout: Duration,
+    ) -> Result<ReceiptResponse, ClientError> {
+        self.runtime
+            .block_on(self.inner.wait_for_result(batch, poll_interval, timeout))
+    }
+
+    /// Queries the documents of interest in the NFeDistribuicaoDFe webservice of the Ambiente Nacional
+    pub fn distribution(
+        &self,
+        query: &DistributionQuery,
+    ) -> Result<DistributionResponse, ClientError> {
+        self.runtime.block_on(self.inner.distribution(query))
+    }
+
+    /// Queries the status of the NfeStatusServico4 webservice of the state
+    pub fn service_status(
+        &self,
+        state: State,
+        environment: Environment,
+    ) -> Result<StatusReport, ClientError> {
+        self.runtime
+            .block_on(self.inner.service_status(state, environment))
+    }
+
+    /// Queries the status of the webservices of every target of the monitor at the same time
+    pub fn check_availability(&self, monitor: &Monitor) -> AvailabilityReport {
+        self.runtime.block_on(monitor.check(&self.inner))
+    }
+
+    /// Queries the protocol and events of the document in its authorizer (NFeConsultaProtocolo4)
+    pub fn query_status(
+        &self,
+        access_key: &AccessKey,
+        environment: Environment,
+    ) -> Result<ProtocolQueryResponse, ClientError> {
+        self.runtime
+            .block_on(self.inner.query_status(access_key, environment))
+    }
+
+    /// Queries the registration of the taxpayer in the CadConsultaCadastro4 webservice of the state
+    pub fn query_registration(
+        &self,
+        state: State,
+        document: TaxpayerDocument,
+        environment: Environment,
+    ) -> Result<RegistrationResponse, ClientError> {
+        self.runtime
+            .block_on(self.inner.query_registration(state, document, environment))
+    }
+
+    /// Sends the batch to the NFeRecepcaoEvento4 webservice of the state of its events
+    pub fn send_events<TDet: EventPayload>(
+        &self,
+        batch: &EventBatch<TDet>,
+    ) -> Result<EventResponse, ClientError> {
+        self.runtime.block_on(self.inner.send_events(batch))
+    }
+
+    /// Sends the request to the NFeInutilizacao4 webservice of the state of the issuer
+    pub fn disable(&self, disablement: &Disablement) -> Result<DisablementResponse, ClientError> {
+        self.runtime.block_on(self.inner.disable(disablement))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sign::tests::setup_certificate;
+
+    #[test]
+    fn test_blocking_request() {
+        let client = Client::new(&setup_certificate())
+            .expect("Failed to create the client")
+            .with_retry(RetryPolicy::none());
+        let batch: EventBatch = EventBatch {
+            id: 1,
+            events: Vec::new(),
+        };
+
+        assert_eq!(
+            client.send_events(&batch),
+            Err(ClientError::Serialization("Empty event batch".to_string()))
+        );
+    }
+}
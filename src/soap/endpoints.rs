@@ -0,0 +1,477 @@
+//! SEFAZ webservice URLs of the NF-e (model 55) layout 4.00
+//!
+//! Each state is served by its own authorizer or by a virtual one (SVRS or
+//! SVAN), and falls back to a contingency authorizer (SVC-AN or SVC-RS) when
+//! the document is issued with `EmissionType::SVCAN` or `EmissionType::SVCRS`.
+
+use crate::enums::{EmissionType, Environment};
+use crate::models::Identification;
+use crate::states::State;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Service {
+    /// NFeAutorizacao4
+    Authorization,
+    /// NFeRetAutorizacao4
+    AuthorizationResult,
+    /// NFeConsultaProtocolo4
+    ProtocolQuery,
+    /// NFeRecepcaoEvento4
+    Event,
+    /// NFeInutilizacao4
+    Disablement,
+    /// NFeStatusServico4
+    Status,
+    /// NFeDistribuicaoDFe, only offered by the Ambiente Nacional
+    Distribution,
+    /// CadConsultaCadastro4, offered by the states and not by the contingency authorizers
+    Registration,
+}
+
+impl Service {
+    /// WSDL name of the service
+    pub fn name(&self) -> &'static str {
+        match self {
+            Service::Authorization => "NFeAutorizacao4",
+            Service::AuthorizationResult => "NFeRetAutorizacao4",
+            Service::ProtocolQuery => "NFeConsultaProtocolo4",
+            Service::Event => "NFeRecepcaoEvento4",
+            Service::Disablement => "NFeInutilizacao4",
+            Service::Status => "NFeStatusServico4",
+            Service::Distribution => "NFeDistribuicaoDFe",
+            Service::Registration => "CadConsultaCadastro4",
+        }
+    }
+
+    /// Namespace of the `nfeDadosMsg` element of the service
+    pub fn namespace(&self) -> String {
+        format!("http://www.portalfiscal.inf.br/nfe/wsdl/{}", self.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Authorizer {
+    AM,
+    BA,
+    CE,
+    GO,
+    MG,
+    MS,
+    MT,
+    PE,
+    PR,
+    RS,
+    SP,
+    /// Sefaz Virtual do Ambiente Nacional
+    SVAN,
+    /// Sefaz Virtual do Rio Grande do Sul
+    SVRS,
+    /// Sefaz Virtual de Contingência do Ambiente Nacional
+    SVCAN,
+    /// Sefaz Virtual de Contingência do Rio Grande do Sul
+    SVCRS,
+    /// Ambiente Nacional, for the distribution and the national events
+    AN,
+}
+
+impl Authorizer {
+    /// Authorizer used in normal emission
+    pub fn from_state(state: &State) -> Self {
+        match state {
+            State::Amazonas => Authorizer::AM,
+            State::Bahia => Authorizer::BA,
+            State::Ceara => Authorizer::CE,
+            State::Goias => Authorizer::GO,
+            State::MinasGerais => Authorizer::MG,
+            State::MatoGrossoDoSul => Authorizer::MS,
+            State::MatoGrosso => Authorizer::MT,
+            State::Pernambuco => Authorizer::PE,
+            State::Parana => Authorizer::PR,
+            State::RioGrandeDoSul => Authorizer::RS,
+            State::SaoPaulo => Authorizer::SP,
+            State::Maranhao => Authorizer::SVAN,
+            State::Acre
+            | State::Alagoas
+            | State::Amapa
+            | State::DistritoFederal
+            | State::EspiritoSanto
+            | State::Para
+            | State::Paraiba
+            | State::Piaui
+            | State::RioDeJaneiro
+            | State::RioGrandeDoNorte
+            | State::Rondonia
+            | State::Roraima
+            | State::SantaCatarina
+            | State::Sergipe
+            | State::Tocantins => Authorizer::SVRS,
+        }
+    }
+
+    /// Contingency authorizer (SVC) of the state
+    pub fn contingency(state: &State) -> Self {
+        match state {
+            State::Amazonas
+            | State::Bahia
+            | State::Goias
+            | State::Maranhao
+            | State::MatoGrossoDoSul
+            | State::MatoGrosso
+            | State::Pernambuco
+            | State::Parana => Authorizer::SVCRS,
+            _ => Authorizer::SVCAN,
+        }
+    }
+
+    /// Production and homologation hosts of the Ambiente Nacional services
+    fn national_endpoint(service: Service) -> Option<(&'static str, &'static str, &'static str)> {
+        match service {
+            Service::Event => Some((
+                "https://www.nfe.fazenda.gov.br/",
+                "https://hom1.nfe.fazenda.gov.br/",
+                "NFeRecepcaoEvento4/NFeRecepcaoEvento4.asmx",
+            )),
+            Service::Distribution => Some((
+                "https://www1.nfe.fazenda.gov.br/",
+                "https://hom1.nfe.fazenda.gov.br/",
+                "NFeDistribuicaoDFe/NFeDistribuicaoDFe.asmx",
+            )),
+            _ => None,
+        }
+    }
+
+    /// Production and homologation hosts of the registration query
+    ///
+    /// RS and SVRS serve it from their own hosts, apart from the NF-e ones.
+    fn registration_endpoint(&self) -> Option<(&'static str, &'static str, &'static str)> {
+        match self {
+            Authorizer::RS => Some((
+                "https://cad.sefazrs.rs.gov.br/ws/",
+                "https://cad.sefazrs.rs.gov.br/ws/",
+                "cadconsultacadastro/cadconsultacadastro4.asmx",
+            )),
+            Authorizer::SVRS => Some((
+                "https://cad.svrs.rs.gov.br/ws/",
+                "https://cad-homologacao.svrs.rs.gov.br/ws/",
+                "cadconsultacadastro/cadconsultacadastro4.asmx",
+            )),
+            // Amazonas and the virtual authorizers of the Ambiente Nacional do not offer it
+            Authorizer::AM
+            | Authorizer::SVAN
+            | Authorizer::SVCAN
+            | Authorizer::SVCRS
+            | Authorizer::AN => None,
+            _ => {
+                let (production, homologation, _) = self.endpoint(Service::Status)?;
+                let path = match self {
+                    Authorizer::SP => "cadconsultacadastro4.asmx",
+                    Authorizer::BA => "CadConsultaCadastro4/CadConsultaCadastro4.asmx",
+                    _ => Service::Registration.name(),
+                };
+                Some((production, homologation, path))
+            }
+        }
+    }
+
+    /// Production and homologation hosts, followed by the path of the service
+    fn endpoint(&self, service: Service) -> Option<(&'static str, &'static str, &'static str)> {
+        if service == Service::Registration {
+            return self.registration_endpoint();
+        }
+        let (production, homologation) = match self {
+            Authorizer::AN => return Self::national_endpoint(service),
+            Authorizer::AM => (
+                "https://nfe.sefaz.am.gov.br/services2/services/",
+                "https://homnfe.sefaz.am.gov.br/services2/services/",
+            ),
+            Authorizer::BA => (
+                "https://nfe.sefaz.ba.gov.br/webservices/",
+                "https://hnfe.sefaz.ba.gov.br/webservices/",
+            ),
+            Authorizer::CE => (
+                "https://nfe.sefaz.ce.gov.br/nfe4/services/",
+                "https://nfeh.sefaz.ce.gov.br/nfe4/services/",
+            ),
+            Authorizer::GO => (
+                "https://nfe.sefaz.go.gov.br/nfe/services/",
+                "https://homolog.sefaz.go.gov.br/nfe/services/",
+            ),
+            Authorizer::MG => (
+                "https://nfe.fazenda.mg.gov.br/nfe2/services/",
+                "https://hnfe.fazenda.mg.gov.br/nfe2/services/",
+            ),
+            Authorizer::MS => (
+                "https://nfe.sefaz.ms.gov.br/ws/",
+                "https://hom.nfe.sefaz.ms.gov.br/ws/",
+            ),
+            Authorizer::MT => (
+                "https://nfe.sefaz.mt.gov.br/nfews/v2/services/",
+                "https://homologacao.sefaz.mt.gov.br/nfews/v2/services/",
+            ),
+            Authorizer::PE => (
+                "https://nfe.sefaz.pe.gov.br/nfe-service/services/",
+                "https://nfehomolog.sefaz.pe.gov.br/nfe-service/services/",
+            ),
+            Authorizer::PR => (
+                "https://nfe.sefa.pr.gov.br/nfe/",
+                "https://homologacao.nfe.sefa.pr.gov.br/nfe/",
+            ),
+            Authorizer::RS => (
+                "https://nfe.sefazrs.rs.gov.br/ws/",
+                "https://nfe-homologacao.sefazrs.rs.gov.br/ws/",
+            ),
+            Authorizer::SP => (
+                "https://nfe.fazenda.sp.gov.br/ws/",
+                "https://homologacao.nfe.fazenda.sp.gov.br/ws/",
+            ),
+            Authorizer::SVAN => (
+                "https://www.sefazvirtual.fazenda.gov.br/",
+                "https://hom.sefazvirtual.fazenda.gov.br/",
+            ),
+            Authorizer::SVRS | Authorizer::SVCRS => (
+                "https://nfe.svrs.rs.gov.br/ws/",
+                "https://nfe-homologacao.svrs.rs.gov.br/ws/",
+            ),
+            Authorizer::SVCAN => (
+                "https://www.svc.fazenda.gov.br/",
+                "https://hom.svc.fazenda.gov.br/",
+            ),
+        };
+
+        let path = match (self, service) {
+            (_, Service::Distribution | Service::Registration) => return None,
+            // The contingency authorizers do not disable numbers
+            (Authorizer::SVCAN | Authorizer::SVCRS, Service::Disablement) => return None,
+            (Authorizer::AM | Authorizer::MT, Service::Authorization) => "NfeAutorizacao4",
+            (Authorizer::AM | Authorizer::MT, Service::AuthorizationResult) => "NfeRetAutorizacao4",
+            (Authorizer::AM | Authorizer::MT, Service::ProtocolQuery) => "NfeConsulta4",
+            (Authorizer::AM | Authorizer::MT, Service::Event) => "RecepcaoEvento4",
+            (Authorizer::AM | Authorizer::MT, Service::Disablement) => "NfeInutilizacao4",
+            (Authorizer::AM | Authorizer::MT, Service::Status) => "NfeStatusServico4",
+            (Authorizer::RS | Authorizer::SVRS | Authorizer::SVCRS, service) => match service {
+                Service::Authorization => "NfeAutorizacao/NFeAutorizacao4.asmx",
+                Service::AuthorizationResult => "NfeRetAutorizacao/NFeRetAutorizacao4.asmx",
+                Service::ProtocolQuery => "NfeConsulta/NfeConsulta4.asmx",
+                Service::Event => "recepcaoevento/recepcaoevento4.asmx",
+                Service::Disablement => "nfeinutilizacao/nfeinutilizacao4.asmx",
+                Service::Status => "NfeStatusServico/NfeStatusServico4.asmx",
+                Service::Distribution | Service::Registration => return None,
+            },
+            (Authorizer::SP, service) => match service {
+                Service::Authorization => "nfeautorizacao4.asmx",
+                Service::AuthorizationResult => "nferetautorizacao4.asmx",
+                Service::ProtocolQuery => "nfeconsultaprotocolo4.asmx",
+                Service::Event => "nferecepcaoevento4.asmx",
+                Service::Disablement => "nfeinutilizacao4.asmx",
+                Service::Status => "nfestatusservico4.asmx",
+                Service::Distribution | Service::Registration => return None,
+            },
+            (Authorizer::BA | Authorizer::SVAN | Authorizer::SVCAN, service) => match service {
+                Service::Authorization => "NFeAutorizacao4/NFeAutorizacao4.asmx",
+                Service::AuthorizationResult => "NFeRetAutorizacao4/NFeRetAutorizacao4.asmx",
+                Service::ProtocolQuery => "NFeConsultaProtocolo4/NFeConsultaProtocolo4.asmx",
+                Service::Event => "NFeRecepcaoEvento4/NFeRecepcaoEvento4.asmx",
+                Service::Disablement => "NFeInutilizacao4/NFeInutilizacao4.asmx",
+                Service::Status => "NFeStatusServico4/NFeStatusServico4.asmx",
+                Service::Distribution | Service::Registration => return None,
+            },
+            (_, service) => service.name(),
+        };
+
+        Some((production, homologation, path))
+    }
+
+    /// URL of the service, if offered by the authorizer
+    pub fn url(&self, environment: &Environment, service: Service) -> Option<String> {
+        let (production, homologation, path) = self.endpoint(service)?;
+        let host = match environment {
+            Environment::Production => production,
+            Environment::Homologation => homologation,
+        };
+        Some(format!("{}{}", host, path))
+    }
+}
+
+/// URL of the service for the state, in normal emission
+pub fn url(state: &State, environment: &Environment, service: Service) -> Option<String> {
+    Authorizer::from_state(state).url(environment, service)
+}
+
+/// Authorizer of the document, taking the contingency emission into account
+pub fn authorizer(identification: &Identification) -> Authorizer {
+    let state = &identification.location.state;
+    match identification.emission_type {
+        EmissionType::SVCAN | EmissionType::SVCRS => Authorizer::contingency(state),
+        _ => Authorizer::from_state(state),
+    }
+}
+
+/// URL of the service for the document, taking the contingency emission into account
+pub fn resolve(identification: &Identification, service: Service) -> Option<String> {
+    authorizer(identification).url(&identification.environment, service)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::tests::setup_info_builder;
+
+    #[test]
+    fn test_own_authorizer() {
+        assert_eq!(
+            url(
+                &State::SaoPaulo,
+                &Environment::Production,
+                Service::Authorization
+            )
+            .unwrap(),
+            "https://nfe.fazenda.sp.gov.br/ws/nfeautorizacao4.asmx"
+        );
+        assert_eq!(
+            url(
+                &State::MinasGerais,
+                &Environment::Homologation,
+                Service::Status
+            )
+            .unwrap(),
+            "https://hnfe.fazenda.mg.gov.br/nfe2/services/NFeStatusServico4"
+        );
+        assert_eq!(
+            url(
+                &State::MatoGrosso,
+                &Environment::Production,
+                Service::ProtocolQuery
+            )
+            .unwrap(),
+            "https://nfe.sefaz.mt.gov.br/nfews/v2/services/NfeConsulta4"
+        );
+    }
+
+    #[test]
+    fn test_virtual_authorizer() {
+        assert_eq!(
+            url(
+                &State::SantaCatarina,
+                &Environment::Production,
+                Service::Event
+            )
+            .unwrap(),
+            "https://nfe.svrs.rs.gov.br/ws/recepcaoevento/recepcaoevento4.asmx"
+        );
+        assert_eq!(
+            url(
+                &State::Maranhao,
+                &Environment::Homologation,
+                Service::Disablement
+            )
+            .unwrap(),
+            "https://hom.sefazvirtual.fazenda.gov.br/NFeInutilizacao4/NFeInutilizacao4.asmx"
+        );
+    }
+
+    #[test]
+    fn test_contingency_authorizer() {
+        assert_eq!(Authorizer::contingency(&State::SaoPaulo), Authorizer::SVCAN);
+        assert_eq!(Authorizer::contingency(&State::Parana), Authorizer::SVCRS);
+        assert_eq!(
+            Authorizer::SVCAN
+                .url(&Environment::Production, Service::Authorization)
+                .unwrap(),
+            "https://www.svc.fazenda.gov.br/NFeAutorizacao4/NFeAutorizacao4.asmx"
+        );
+        assert_eq!(
+            Authorizer::SVCAN.url(&Environment::Production, Service::Disablement),
+            None
+        );
+    }
+
+    #[test]
+    fn test_national_environment() {
+        assert_eq!(
+            Authorizer::AN
+                .url(&Environment::Production, Service::Distribution)
+                .unwrap(),
+            "https://www1.nfe.fazenda.gov.br/NFeDistribuicaoDFe/NFeDistribuicaoDFe.asmx"
+        );
+        assert_eq!(
+            Authorizer::AN
+                .url(&Environment::Homologation, Service::Event)
+                .unwrap(),
+            "https://hom1.nfe.fazenda.gov.br/NFeRecepcaoEvento4/NFeRecepcaoEvento4.asmx"
+        );
+        assert_eq!(
+            Authorizer::AN.url(&Environment::Production, Service::Authorization),
+            None
+        );
+        assert_eq!(
+            url(
+                &State::SaoPaulo,
+                &Environment::Production,
+                Service::Distribution
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_registration() {
+        assert_eq!(
+            url(
+                &State::SaoPaulo,
+                &Environment::Production,
+                Service::Registration
+            )
+            .unwrap(),
+            "https://nfe.fazenda.sp.gov.br/ws/cadconsultacadastro4.asmx"
+        );
+        assert_eq!(
+            url(
+                &State::SantaCatarina,
+                &Environment::Homologation,
+                Service::Registration
+            )
+            .unwrap(),
+            "https://cad-homologacao.svrs.rs.gov.br/ws/cadconsultacadastro/cadconsultacadastro4.asmx"
+        );
+        assert_eq!(
+            url(
+                &State::MinasGerais,
+                &Environment::Production,
+                Service::Registration
+            )
+            .unwrap(),
+            "https://nfe.fazenda.mg.gov.br/nfe2/services/CadConsultaCadastro4"
+        );
+        assert_eq!(
+            url(
+                &State::Maranhao,
+                &Environment::Production,
+                Service::Registration
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_resolve_from_identification() {
+        let mut identification = setup_info_builder().build().unwrap().identification;
+        assert_eq!(
+            resolve(&identification, Service::Authorization).unwrap(),
+            "https://nfe.fazenda.mg.gov.br/nfe2/services/NFeAutorizacao4"
+        );
+
+        identification.emission_type = EmissionType::SVCAN;
+        assert_eq!(
+            resolve(&identification, Service::Authorization).unwrap(),
+            "https://www.svc.fazenda.gov.br/NFeAutorizacao4/NFeAutorizacao4.asmx"
+        );
+    }
+
+    #[test]
+    fn test_service_namespace() {
+        assert_eq!(
+            Service::Authorization.namespace(),
+            "http://www.portalfiscal.inf.br/nfe/wsdl/NFeAutorizacao4"
+        );
+    }
+}
@@ -0,0 +1,276 @@
+//! SOAP 1.2 envelopes of the SEFAZ webservices
+//!
+//! Every webservice takes the message in a `nfeDadosMsg` element with the
+//! namespace of its WSDL and answers it in the body of the envelope, or with a
+//! `soap:Fault` when the request could not be processed.
+
+use super::ClientError;
+use super::endpoints::Service;
+use lazy_static::lazy_static;
+use quick_xml::{Reader, events::Event};
+use regex_lite::Regex;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+pub const SOAP_NAMESPACE: &str = "http://www.w3.org/2003/05/soap-envelope";
+
+lazy_static! {
+    /// Elements with the certificate of the signatures
+    static ref CERTIFICATE: Regex = Regex::new(
+        r"(<(?:[\w.-]+:)?X509Certificate(?:\s[^>]*)?>)[^<]*(</(?:[\w.-]+:)?X509Certificate>)"
+    )
+    .expect("Invalid certificate pattern");
+}
+
+/// Request to a webservice, serialized as a SOAP 1.2 envelope
+///
+/// service: Webservice requested, defining the namespace of `nfeDadosMsg`
+/// root: Name of the root element of the message (enviNFe, consSitNFe...)
+/// message: Content of `nfeDadosMsg`
+#[derive(Debug, PartialEq)]
+pub struct SoapEnvelope<'a, T> {
+    pub service: Service,
+    pub root: &'a str,
+    pub message: &'a T,
+}
+
+impl<'a, T: Serialize> SoapEnvelope<'a, T> {
+    pub fn new(service: Service, root: &'a str, message: &'a T) -> Self {
+        SoapEnvelope {
+            service,
+            root,
+            message,
+        }
+    }
+
+    /// XML of the envelope, as posted to the webservice
+    pub fn to_xml(&self) -> Result<String, ClientError> {
+        let message = quick_xml::se::to_string_with_root(self.root, self.message)
+            .map_err(|e| ClientError::Serialization(e.to_string()))?;
+        Ok(wrap(self.service, &message))
+    }
+}
+
+/// Fault answered instead of the response of the webservice (soap:Fault)
+///
+/// code: Value of the code, as soap:Sender or soap:Receiver (Code)
+/// subcode: Value of the subcode (Subcode) - Optional
+/// reason: Description of the fault (Reason)
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoapFault {
+    pub code: String,
+    pub subcode: Option<String>,
+    pub reason: String,
+}
+
+impl SoapFault {
+    /// Whether the fault was caused by the request, so sending it again fails too
+    pub fn is_sender(&self) -> bool {
+        self.code.rsplit(':').next() == Some("Sender")
+    }
+}
+
+/// Wraps the message in a SOAP 1.2 envelope for the webservice `service`
+///
+/// The distribution webservice expects `nfeDadosMsg` inside of the
+/// `nfeDistDFeInteresse` operation element, while the others take it directly.
+pub(super) fn wrap(service: Service, message: &str) -> String {
+    let body = match service {
+        Service::Distribution => format!(
+            r#"<nfeDistDFeInteresse xmlns="{}"><nfeDadosMsg>{}</nfeDadosMsg></nfeDistDFeInteresse>"#,
+            service.namespace(),
+            message
+        ),
+        _ => format!(
+            r#"<nfeDadosMsg xmlns="{}">{}</nfeDadosMsg>"#,
+            service.namespace(),
+            message
+        ),
+    };
+    format!(
+        r#"<soap12:Envelope xmlns:soap12="{}"><soap12:Body>{}</soap12:Body></soap12:Envelope>"#,
+        SOAP_NAMESPACE, body
+    )
+}
+
+/// Fault of the response, if the webservice answered one
+pub fn fault(xml: &str) -> Option<SoapFault> {
+    let fault = extract_element(xml, "Fault").ok()?;
+    let code = extract_element(fault, "Code").ok();
+    Some(SoapFault {
+        code: code
+            .and_then(|code| element_text(code, "Value"))
+            .unwrap_or_default(),
+        subcode: code
+            .and_then(|code| extract_element(code, "Subcode").ok())
+            .and_then(|subcode| element_text(subcode, "Value")),
+        reason: element_text(fault, "Text").unwrap_or_default(),
+    })
+}
+
+/// Deserializes the element `root` of the body of the response
+///
+/// Faults are returned as `ClientError::Fault`, whatever the content of the body.
+pub fn unwrap_response<T: DeserializeOwned>(xml: &str, root: &str) -> Result<T, ClientError> {
+    if let Some(fault) = fault(xml) {
+        return Err(ClientError::Fault(fault));
+    }
+    quick_xml::de::from_str(extract_element(xml, root)?)
+        .map_err(|e| ClientError::Deserialization(e.to_string()))
+}
+
+/// Copy of the message for the logs, without the certificates of its signatures
+///
+/// The private key and the password of the PKCS#12 never take part of the
+/// messages, so the signed content is kept as sent.
+pub fn redact(xml: &str) -> String {
+    CERTIFICATE
+        .replace_all(xml, "${1}[REDACTED]${2}")
+        .into_owned()
+}
+
+/// Finds the first element with the local name `name` inside the document
+pub(super) fn extract_element<'a>(xml: &'a str, name: &str) -> Result<&'a str, ClientError> {
+    let mut reader = Reader::from_str(xml);
+    loop {
+        let start = reader.buffer_position() as usize;
+        match reader.read_event() {
+            Ok(Event::Start(e)) if e.local_name().as_ref() == name.as_bytes() => {
+                reader
+                    .read_to_end(e.name())
+                    .map_err(|e| ClientError::Deserialization(e.to_string()))?;
+                return Ok(&xml[start..reader.buffer_position() as usize]);
+            }
+            Ok(Event::Eof) => {
+                return Err(ClientError::Deserialization(format!(
+                    "Element {} not found in the response",
+                    name
+                )));
+            }
+            Ok(_) => {}
+            Err(e) => return Err(ClientError::Deserialization(e.to_string())),
+        }
+    }
+}
+
+/// Text of the first element with the local name `name` inside the document
+pub(super) fn element_text(xml: &str, name: &str) -> Option<String> {
+    let element = extract_element(xml, name).ok()?;
+    let start = element.find('>')? + 1;
+    let end = element.rfind("</")?;
+    Some(element.get(start..end)?.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::Environment;
+    use crate::soap::{StatusQuery, StatusResponse};
+    use crate::states::State;
+
+    const FAULT: &str = concat!(
+        r#"<soap:Envelope xmlns:soap="http://www.w3.org/2003/05/soap-envelope"><soap:Body>"#,
+        r#"<soap:Fault><soap:Code><soap:Value>soap:Sender</soap:Value>"#,
+        r#"<soap:Subcode><soap:Value>ns:InvalidMessage</soap:Value></soap:Subcode></soap:Code>"#,
+        r#"<soap:Reason><soap:Text xml:lang="pt-BR">Mensagem fora do padrao</soap:Text></soap:Reason>"#,
+        r#"</soap:Fault></soap:Body></soap:Envelope>"#
+    );
+
+    #[test]
+    fn test_envelope() {
+        let expected = concat!(
+            r#"<soap12:Envelope xmlns:soap12="http://www.w3.org/2003/05/soap-envelope">"#,
+            r#"<soap12:Body><nfeDadosMsg xmlns="http://www.portalfiscal.inf.br/nfe/wsdl/NFeAutorizacao4">"#,
+            r#"<enviNFe/></nfeDadosMsg></soap12:Body></soap12:Envelope>"#
+        );
+        assert_eq!(wrap(Service::Authorization, "<enviNFe/>"), expected);
+    }
+
+    #[test]
+    fn test_distribution_envelope() {
+        let expected = concat!(
+            r#"<soap12:Envelope xmlns:soap12="http://www.w3.org/2003/05/soap-envelope"><soap12:Body>"#,
+            r#"<nfeDistDFeInteresse xmlns="http://www.portalfiscal.inf.br/nfe/wsdl/NFeDistribuicaoDFe">"#,
+            r#"<nfeDadosMsg><distDFeInt/></nfeDadosMsg></nfeDistDFeInteresse>"#,
+            r#"</soap12:Body></soap12:Envelope>"#
+        );
+        assert_eq!(wrap(Service::Distribution, "<distDFeInt/>"), expected);
+    }
+
+    #[test]
+    fn test_soap_envelope() {
+        let query = StatusQuery {
+            environment: Environment::Homologation,
+            state: State::SaoPaulo,
+        };
+        let xml = SoapEnvelope::new(Service::Status, "consStatServ", &query)
+            .to_xml()
+            .expect("Failed to serialize the envelope");
+
+        assert!(xml.starts_with(concat!(
+            r#"<soap12:Envelope xmlns:soap12="http://www.w3.org/2003/05/soap-envelope"><soap12:Body>"#,
+            r#"<nfeDadosMsg xmlns="http://www.portalfiscal.inf.br/nfe/wsdl/NFeStatusServico4">"#,
+            r#"<consStatServ"#
+        )));
+        assert!(xml.ends_with("</consStatServ></nfeDadosMsg></soap12:Body></soap12:Envelope>"));
+    }
+
+    #[test]
+    fn test_fault() {
+        let fault = fault(FAULT).expect("Fault not found");
+        assert_eq!(
+            fault,
+            SoapFault {
+                code: "soap:Sender".to_string(),
+                subcode: Some("ns:InvalidMessage".to_string()),
+                reason: "Mensagem fora do padrao".to_string(),
+            }
+        );
+        assert!(fault.is_sender());
+
+        let result = unwrap_response::<StatusResponse>(FAULT, "retConsStatServ");
+        assert_eq!(result, Err(ClientError::Fault(fault)));
+    }
+
+    #[test]
+    fn test_unwrap_response() {
+        let xml = include_str!("../../tests/fixtures/soap/ret_envi_nfe.xml");
+        assert_eq!(fault(xml), None);
+        assert!(matches!(
+            unwrap_response::<StatusResponse>(xml, "retConsStatServ"),
+            Err(ClientError::Deserialization(_))
+        ));
+    }
+
+    #[test]
+    fn test_redact() {
+        let xml = concat!(
+            r#"<Signature xmlns="http://www.w3.org/2000/09/xmldsig#">"#,
+            r#"<SignatureValue>c2lnbmF0dXJl</SignatureValue><KeyInfo><X509Data>"#,
+            r#"<X509Certificate>MIIDdzCCAl+gAwIBAgIE</X509Certificate>"#,
+            r#"<ds:X509Certificate xmlns:ds="http://www.w3.org/2000/09/xmldsig#">MIIC</ds:X509Certificate>"#,
+            r#"</X509Data></KeyInfo></Signature>"#
+        );
+        assert_eq!(
+            redact(xml),
+            concat!(
+                r#"<Signature xmlns="http://www.w3.org/2000/09/xmldsig#">"#,
+                r#"<SignatureValue>c2lnbmF0dXJl</SignatureValue><KeyInfo><X509Data>"#,
+                r#"<X509Certificate>[REDACTED]</X509Certificate>"#,
+                r#"<ds:X509Certificate xmlns:ds="http://www.w3.org/2000/09/xmldsig#">[REDACTED]</ds:X509Certificate>"#,
+                r#"</X509Data></KeyInfo></Signature>"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_element_text() {
+        let xml = include_str!("../../tests/fixtures/soap/ret_envi_nfe.xml");
+        assert_eq!(element_text(xml, "cStat"), Some("104".to_string()));
+        assert_eq!(
+            element_text(xml, "xMotivo"),
+            Some("Lote processado".to_string())
+        );
+        assert_eq!(element_text(xml, "infRec"), None);
+    }
+}
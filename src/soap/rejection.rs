@@ -0,0 +1,235 @@
+//! Catalog of the status codes (cStat) returned by the SEFAZ webservices
+//!
+//! Only the usual rejections are catalogued, with the messages of the
+//! Manual de Orientação do Contribuinte. Any other status is kept as
+//! `RejectionCode::Other`, so no status of the authorizer is lost.
+
+use std::fmt;
+
+/// Group of the rejection, telling what must be fixed before sending again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectionCategory {
+    /// The XML does not follow the schema of the layout
+    Schema,
+    /// The signature of the document does not match its content
+    Signature,
+    /// Certificate of the transmitter or of the signature refused
+    Certificate,
+    /// Document, number or event already registered by the authorizer
+    Duplicity,
+    /// Issuer not allowed to issue documents
+    AuthorizationDenied,
+    /// Use denied by fiscal irregularity of the issuer or of the recipient
+    UseDenied,
+    /// Content of the document refused by the rules of the layout
+    Validation,
+    /// Webservice paused or refusing the request for now
+    Service,
+    /// Status not catalogued
+    Other,
+}
+
+macro_rules! rejection_codes {
+    ($($(#[$doc:meta])* $name:ident = $code:literal, $category:ident, $message:literal;)*) => {
+        /// Status code (cStat) of a request refused by the authorizer
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum RejectionCode {
+            $($(#[$doc])* $name,)*
+            /// Status not catalogued
+            Other(u16),
+        }
+
+        impl RejectionCode {
+            /// Status code (cStat)
+            pub fn code(&self) -> u16 {
+                match self {
+                    $(RejectionCode::$name => $code,)*
+                    RejectionCode::Other(code) => *code,
+                }
+            }
+
+            /// Group of the rejection
+            pub fn category(&self) -> RejectionCategory {
+                match self {
+                    $(RejectionCode::$name => RejectionCategory::$category,)*
+                    RejectionCode::Other(_) => RejectionCategory::Other,
+                }
+            }
+
+            /// Message of the status in the Manual de Orientação do Contribuinte
+            pub fn message(&self) -> &'static str {
+                match self {
+                    $(RejectionCode::$name => $message,)*
+                    RejectionCode::Other(_) => "Rejeição: Código não catalogado",
+                }
+            }
+        }
+
+        impl From<u16> for RejectionCode {
+            fn from(code: u16) -> Self {
+                match code {
+                    $($code => RejectionCode::$name,)*
+                    _ => RejectionCode::Other(code),
+                }
+            }
+        }
+    };
+}
+
+rejection_codes! {
+    ServicePaused = 108, Service, "Serviço Paralisado Momentaneamente (curto prazo)";
+    ServiceUnavailable = 109, Service, "Serviço Paralisado sem Previsão";
+    UseDenied = 110, UseDenied, "Uso Denegado";
+    IssuerNotEnabled = 203, AuthorizationDenied, "Rejeição: Emissor não habilitado para emissão da NF-e";
+    DuplicateNFe = 204, Duplicity, "Rejeição: Duplicidade de NF-e";
+    DeniedNFe = 205, UseDenied, "Rejeição: NF-e está denegada na base de dados da SEFAZ";
+    AlreadyDisabled = 206, Duplicity, "Rejeição: NF-e já está inutilizada na Base de dados da SEFAZ";
+    InvalidIssuerCNPJ = 207, Validation, "Rejeição: CNPJ do emitente inválido";
+    InvalidIssuerIE = 209, Validation, "Rejeição: IE do emitente inválida";
+    InvalidRecipientIE = 210, Validation, "Rejeição: IE do destinatário inválida";
+    InvalidSubstituteIE = 211, Validation, "Rejeição: IE do substituto inválida";
+    CertificateCNPJMismatch = 213, Certificate, "Rejeição: CNPJ-Base do Emitente difere do CNPJ-Base do Certificado Digital";
+    SchemaFailure = 215, Schema, "Rejeição: Falha no schema XML";
+    NotFound = 217, Validation, "Rejeição: NF-e não consta na base de dados da SEFAZ";
+    AlreadyCancelled = 218, Duplicity, "Rejeição: NF-e já está cancelada na base de dados da SEFAZ";
+    BatchSchemaFailure = 225, Schema, "Rejeição: Falha no Schema XML do lote de NFe";
+    IssuerStateMismatch = 226, Validation, "Rejeição: Código da UF do Emitente diverge da UF autorizadora";
+    MissingIdPrefix = 227, Validation, "Rejeição: Erro na Chave de Acesso - Campo Id – falta a literal NFe";
+    LateEmission = 228, Validation, "Rejeição: Data de Emissão muito atrasada";
+    MissingIssuerIE = 229, Validation, "Rejeição: IE do emitente não informada";
+    IssuerIENotRegistered = 230, AuthorizationDenied, "Rejeição: IE do emitente não cadastrada";
+    IssuerIENotLinked = 231, AuthorizationDenied, "Rejeição: IE do emitente não vinculada ao CNPJ";
+    RecipientIENotRegistered = 233, Validation, "Rejeição: IE do destinatário não cadastrada";
+    InvalidAccessKeyDigit = 236, Validation, "Rejeição: Chave de Acesso com dígito verificador inválido";
+    VersionAboveCurrent = 238, Schema, "Rejeição: Cabeçalho - Versão do arquivo XML superior a Versão vigente";
+    UnsupportedVersion = 239, Schema, "Rejeição: Cabeçalho - Versão do arquivo XML não suportada";
+    HeaderSchemaFailure = 242, Schema, "Rejeição: Cabeçalho - Falha no Schema XML";
+    MalformedXML = 243, Schema, "Rejeição: XML Mal Formado";
+    IssuerCNPJNotRegistered = 245, AuthorizationDenied, "Rejeição: CNPJ Emitente não cadastrado";
+    EnvironmentMismatch = 252, Validation, "Rejeição: Ambiente informado diverge do Ambiente de recebimento";
+    RangeAlreadyDisabled = 256, Duplicity, "Rejeição: Uma NF-e da faixa já está inutilizada na Base de dados da SEFAZ";
+    SeriesOutOfRange = 266, Validation, "Rejeição: Série utilizada fora da faixa permitida no Web Service (0-889)";
+    TransmitterCertificateInvalid = 280, Certificate, "Rejeição: Certificado Transmissor inválido";
+    TransmitterCertificateExpired = 281, Certificate, "Rejeição: Certificado Transmissor Data Validade";
+    TransmitterCertificateWithoutCNPJ = 282, Certificate, "Rejeição: Certificado Transmissor sem CNPJ";
+    TransmitterCertificateChain = 283, Certificate, "Rejeição: Certificado Transmissor - erro Cadeia de Certificação";
+    TransmitterCertificateRevoked = 284, Certificate, "Rejeição: Certificado Transmissor revogado";
+    TransmitterCertificateNotICPBrasil = 285, Certificate, "Rejeição: Certificado Transmissor difere ICP-Brasil";
+    TransmitterCertificateCRL = 286, Certificate, "Rejeição: Certificado Transmissor erro no acesso a LCR";
+    SignatureCertificateInvalid = 290, Certificate, "Rejeição: Certificado Assinatura inválido";
+    SignatureCertificateExpired = 291, Certificate, "Rejeição: Certificado Assinatura Data Validade";
+    SignatureCertificateWithoutCNPJ = 292, Certificate, "Rejeição: Certificado Assinatura sem CNPJ";
+    SignatureCertificateChain = 293, Certificate, "Rejeição: Certificado Assinatura - erro Cadeia de Certificação";
+    SignatureCertificateRevoked = 294, Certificate, "Rejeição: Certificado Assinatura revogado";
+    SignatureCertificateNotICPBrasil = 295, Certificate, "Rejeição: Certificado Assinatura difere ICP-Brasil";
+    SignatureCertificateCRL = 296, Certificate, "Rejeição: Certificado Assinatura erro no acesso a LCR";
+    SignatureMismatch = 297, Signature, "Rejeição: Assinatura difere do calculado";
+    SignatureStandard = 298, Signature, "Rejeição: Assinatura difere do padrão do Projeto";
+    IssuerIrregular = 301, UseDenied, "Uso Denegado: Irregularidade fiscal do emitente";
+    RecipientIrregular = 302, UseDenied, "Uso Denegado: Irregularidade fiscal do destinatário";
+    RecipientNotEnabled = 303, UseDenied, "Uso Denegado: Destinatário não habilitado a operar na UF";
+    ReturnCFOP = 327, Validation, "Rejeição: CFOP inválido para NF-e com finalidade de devolução";
+    NonUTF8 = 402, Schema, "Rejeição: XML da área de dados com codificação diferente de UTF-8";
+    NamespacePrefix = 404, Schema, "Rejeição: Uso de prefixo de namespace não permitido";
+    UnknownCSC = 462, Validation, "Rejeição: Código Identificador do CSC no QR-Code não cadastrado na SEFAZ";
+    QRCodeHash = 464, Validation, "Rejeição: Código de Hash no QR-Code difere do calculado";
+    LateCancellation = 501, Validation, "Rejeição: Pedido de Cancelamento intempestivo (NF-e autorizada a mais de 7 dias)";
+    IdMismatch = 502, Validation, "Rejeição: Erro na Chave de Acesso - Campo Id não corresponde à concatenação dos campos correspondentes";
+    ICMSBaseTotal = 531, Validation, "Rejeição: Total da BC ICMS difere do somatório dos itens";
+    ICMSTotal = 532, Validation, "Rejeição: Total do ICMS difere do somatório dos itens";
+    STBaseTotal = 533, Validation, "Rejeição: Total da BC ICMS-ST difere do somatório dos itens";
+    STTotal = 534, Validation, "Rejeição: Total do ICMS-ST difere do somatório dos itens";
+    DuplicateWithDifferentKey = 539, Duplicity, "Rejeição: Duplicidade de NF-e com diferença na Chave de Acesso";
+    DuplicateDisablement = 563, Duplicity, "Rejeição: Já existe pedido de Inutilização com a mesma faixa de inutilização";
+    ProductsTotal = 564, Validation, "Rejeição: Total do Produto / Serviço difere do somatório dos itens";
+    DuplicateEvent = 573, Duplicity, "Rejeição: Duplicidade de Evento";
+    EventRequiresAuthorized = 580, Validation, "Rejeição: O evento exige uma NF-e autorizada";
+    EditingCharacters = 588, Schema, "Rejeição: Não é permitida a presença de caracteres de edição no início/fim da mensagem ou entre as tags da mensagem";
+    EventSequence = 594, Validation, "Rejeição: O número de sequencia do evento informado é maior que o permitido";
+    InvoiceTotal = 610, Validation, "Rejeição: Total da NF difere do somatório dos Valores compõe o valor Total da NF.";
+    ProductValue = 629, Validation, "Rejeição: Valor do Produto difere do produto Valor Unitário de Comercialização e Quantidade Comercial";
+    /// The issuer must wait an hour before sending the same request again
+    ExcessiveConsumption = 656, Service, "Rejeição: Consumo Indevido";
+    FutureEmission = 703, Validation, "Rejeição: Data-Hora de Emissão posterior ao horário de recebimento";
+    LateNFCe = 704, Validation, "Rejeição: NFC-e com Data-Hora de emissão atrasada";
+    UnknownNCM = 778, Validation, "Rejeição: Informado NCM inexistente";
+    Uncatalogued = 999, Other, "Rejeição: Erro não catalogado";
+}
+
+impl RejectionCode {
+    /// Whether the same request may succeed when sent again right away
+    ///
+    /// Only the paused services (108 and 109) are retryable, any other
+    /// rejection answers the same until the request is fixed. Excessive
+    /// consumption (656) blocks the issuer for an hour, so it is not retried.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            RejectionCode::ServicePaused | RejectionCode::ServiceUnavailable
+        )
+    }
+}
+
+impl fmt::Display for RejectionCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} - {}", self.code(), self.message())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_code() {
+        assert_eq!(
+            RejectionCode::from(539),
+            RejectionCode::DuplicateWithDifferentKey
+        );
+        assert_eq!(RejectionCode::from(301), RejectionCode::IssuerIrregular);
+        assert_eq!(RejectionCode::from(100), RejectionCode::Other(100));
+        for code in [108, 204, 297, 539, 656, 999, 100] {
+            assert_eq!(RejectionCode::from(code).code(), code);
+        }
+    }
+
+    #[test]
+    fn test_category() {
+        assert_eq!(
+            RejectionCode::SchemaFailure.category(),
+            RejectionCategory::Schema
+        );
+        assert_eq!(
+            RejectionCode::SignatureMismatch.category(),
+            RejectionCategory::Signature
+        );
+        assert_eq!(
+            RejectionCode::DuplicateNFe.category(),
+            RejectionCategory::Duplicity
+        );
+        assert_eq!(
+            RejectionCode::RecipientIrregular.category(),
+            RejectionCategory::UseDenied
+        );
+        assert_eq!(
+            RejectionCode::Other(100).category(),
+            RejectionCategory::Other
+        );
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        assert!(RejectionCode::ServicePaused.is_retryable());
+        assert!(RejectionCode::ServiceUnavailable.is_retryable());
+        assert!(!RejectionCode::ExcessiveConsumption.is_retryable());
+        assert!(!RejectionCode::DuplicateNFe.is_retryable());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            RejectionCode::DuplicateNFe.to_string(),
+            "204 - Rejeição: Duplicidade de NF-e"
+        );
+    }
+}
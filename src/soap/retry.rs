@@ -0,0 +1,229 @@
+//! Retry policy of the requests to the SEFAZ webservices
+//!
+//! Only transient failures are retried: timeouts, server errors (5xx) and the
+//! service paused statuses (cStat 108 and 109). Rejections of the documents
+//! and invalid requests fail at the first attempt.
+
+use super::ClientError;
+use super::endpoints::Service;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Retry policy with exponential backoff
+///
+/// max_attempts: Attempts of each request, including the first one
+/// initial_delay: Delay before the second attempt
+/// multiplier: Factor applied to the delay after each attempt
+/// max_delay: Upper bound of the delay, before the jitter
+/// jitter: Fraction of the delay randomly added or removed, from 0 to 1
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_delay: Duration,
+    pub multiplier: f64,
+    pub max_delay: Duration,
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_delay: Duration::from_secs(1),
+            multiplier: 2.0,
+            max_delay: Duration::from_secs(30),
+            jitter: 0.2,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy sending each request once
+    pub fn none() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Delay after the failed attempt `attempt` (from 1), with `random` in [0, 1)
+    /// picking the jitter
+    pub fn delay(&self, attempt: u32, random: f64) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let backoff = self.initial_delay.as_secs_f64() * self.multiplier.powi(exponent);
+        let backoff = backoff.min(self.max_delay.as_secs_f64());
+        let jitter = self.jitter.clamp(0.0, 1.0) * (2.0 * random - 1.0);
+        Duration::from_secs_f64((backoff * (1.0 + jitter)).max(0.0))
+    }
+}
+
+/// Whether the request may succeed when sent again
+pub fn is_transient(error: &ClientError) -> bool {
+    match error {
+        ClientError::Timeout | ClientError::ServerError(_) => true,
+        ClientError::Rejected(status, _) => status.is_retryable(),
+        _ => false,
+    }
+}
+
+/// Attempt of a request, reported to the hook of the client
+///
+/// service: Webservice requested
+/// number: Number of the attempt, from 1
+/// error: Failure of the attempt - None when it succeeded
+/// retry_in: Delay before the next attempt - None when there is no other
+#[derive(Debug, PartialEq)]
+pub struct Attempt<'a> {
+    pub service: Service,
+    pub number: u32,
+    pub error: Option<&'a ClientError>,
+    pub retry_in: Option<Duration>,
+}
+
+/// Observer of the attempts of the requests
+pub type AttemptHook = Arc<dyn Fn(&Attempt) + Send + Sync>;
+
+/// Runs `request` until it succeeds, fails permanently or runs out of attempts
+pub(crate) async fn retry<T, F, Fut>(
+    policy: &RetryPolicy,
+    hook: Option<&AttemptHook>,
+    service: Service,
+    mut request: F,
+) -> Result<T, ClientError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, ClientError>>,
+{
+    let mut number = 1;
+    loop {
+        let result = request().await;
+        let error = result.as_ref().err();
+        let retry_in = error
+            .filter(|error| is_transient(error) && number < policy.max_attempts)
+            .map(|_| policy.delay(number, rand::random()));
+        if let Some(hook) = hook {
+            hook(&Attempt {
+                service,
+                number,
+                error,
+                retry_in,
+            });
+        }
+        match retry_in {
+            Some(delay) => tokio::time::sleep(delay).await,
+            None => return result,
+        }
+        number += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::soap::rejection::RejectionCode;
+    use std::sync::Mutex;
+
+    fn block_on<T>(future: impl Future<Output = T>) -> T {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .start_paused(true)
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn test_delay() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.delay(1, 0.5), Duration::from_secs(1));
+        assert_eq!(policy.delay(3, 0.5), Duration::from_secs(4));
+        assert_eq!(policy.delay(10, 0.5), Duration::from_secs(30));
+        assert_eq!(policy.delay(1, 0.0), Duration::from_millis(800));
+        assert_eq!(policy.delay(2, 1.0), Duration::from_millis(2400));
+    }
+
+    #[test]
+    fn test_is_transient() {
+        assert!(is_transient(&ClientError::Timeout));
+        assert!(is_transient(&ClientError::ServerError(503)));
+        assert!(is_transient(&ClientError::Rejected(
+            RejectionCode::ServicePaused,
+            String::new()
+        )));
+        assert!(is_transient(&ClientError::Rejected(
+            RejectionCode::ServiceUnavailable,
+            String::new()
+        )));
+        assert!(!is_transient(&ClientError::Rejected(
+            RejectionCode::DuplicateWithDifferentKey,
+            String::new()
+        )));
+        assert!(!is_transient(&ClientError::Http(String::new())));
+    }
+
+    #[test]
+    fn test_retry() {
+        let attempts = Arc::new(Mutex::new(Vec::new()));
+        let recorded = attempts.clone();
+        let hook: AttemptHook = Arc::new(move |attempt: &Attempt| {
+            recorded.lock().unwrap().push((
+                attempt.number,
+                attempt.error.cloned(),
+                attempt.retry_in,
+            ));
+        });
+        let policy = RetryPolicy {
+            jitter: 0.0,
+            ..Default::default()
+        };
+
+        let mut responses = vec![Ok("retEnviNFe"), Err(ClientError::Timeout)];
+        let result = block_on(retry(&policy, Some(&hook), Service::Authorization, || {
+            let response = responses.pop().unwrap();
+            async move { response }
+        }));
+
+        assert_eq!(result, Ok("retEnviNFe"));
+        assert_eq!(
+            *attempts.lock().unwrap(),
+            vec![
+                (1, Some(ClientError::Timeout), Some(Duration::from_secs(1))),
+                (2, None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_retry_gives_up() {
+        let mut count = 0;
+        let result: Result<(), _> = block_on(retry(
+            &RetryPolicy::default(),
+            None,
+            Service::Status,
+            || {
+                count += 1;
+                async { Err(ClientError::ServerError(500)) }
+            },
+        ));
+        assert_eq!(result, Err(ClientError::ServerError(500)));
+        assert_eq!(count, 3);
+
+        count = 0;
+        let result: Result<(), _> = block_on(retry(
+            &RetryPolicy::default(),
+            None,
+            Service::Authorization,
+            || {
+                count += 1;
+                async {
+                    Err(ClientError::Rejected(
+                        RejectionCode::DuplicateWithDifferentKey,
+                        String::new(),
+                    ))
+                }
+            },
+        ));
+        assert!(result.is_err());
+        assert_eq!(count, 1);
+    }
+}
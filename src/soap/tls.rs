@@ -0,0 +1,163 @@
+//! Mutual TLS of the requests to the SEFAZ webservices
+//!
+//! The client authenticates with the A1 certificate loaded from the PKCS#12
+//! file, converted to the key and certificate chain of the TLS library. By
+//! default it is rustls, with no system dependency. The `native-tls` feature
+//! replaces it by the TLS library of the system.
+//!
+//! Root certificates missing from the default stores, as the ICP-Brasil chains
+//! of some authorizers, are added to the configuration.
+
+use super::ClientError;
+use crate::sign::Certificate;
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+
+#[cfg(not(any(feature = "rustls", feature = "native-tls")))]
+compile_error!("The SOAP client requires one of the features rustls or native-tls");
+
+/// TLS configuration of the client, shared by all its requests
+#[derive(Clone)]
+pub struct Tls {
+    #[cfg(not(feature = "native-tls"))]
+    config: rustls::ClientConfig,
+    #[cfg(feature = "native-tls")]
+    identity: reqwest::Identity,
+    #[cfg(feature = "native-tls")]
+    roots: Vec<reqwest::Certificate>,
+}
+
+impl Tls {
+    /// Configuration authenticating with `certificate`
+    pub fn new(certificate: &Certificate) -> Result<Self, ClientError> {
+        Self::with_roots(certificate, &[])
+    }
+
+    /// Configuration authenticating with `certificate`, trusting the DER
+    /// encoded `roots` besides the default ones
+    ///
+    /// The servers are verified with the Mozilla root certificates.
+    #[cfg(not(feature = "native-tls"))]
+    pub fn with_roots(certificate: &Certificate, roots: &[Vec<u8>]) -> Result<Self, ClientError> {
+        use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+
+        let key = certificate.private_key_der().map_err(ClientError::Sign)?;
+        let chain = std::iter::once(certificate.der())
+            .chain(certificate.chain().iter().map(Vec::as_slice))
+            .map(|der| CertificateDer::from(der.to_vec()))
+            .collect();
+        let mut store = rustls::RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        };
+        for root in roots {
+            store
+                .add(CertificateDer::from(root.clone()))
+                .map_err(|e| ClientError::Tls(e.to_string()))?;
+        }
+        let provider = rustls::crypto::ring::default_provider();
+        let config = rustls::ClientConfig::builder_with_provider(provider.into())
+            .with_safe_default_protocol_versions()
+            .map_err(|e| ClientError::Tls(e.to_string()))?
+            .with_root_certificates(store)
+            .with_client_auth_cert(chain, PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key)))
+            .map_err(|e| ClientError::Tls(e.to_string()))?;
+
+        Ok(Tls { config })
+    }
+
+    /// Configuration authenticating with `certificate`, trusting the DER
+    /// encoded `roots` besides the default ones
+    ///
+    /// The servers are verified with the root certificates of the system.
+    #[cfg(feature = "native-tls")]
+    pub fn with_roots(certificate: &Certificate, roots: &[Vec<u8>]) -> Result<Self, ClientError> {
+        let pem = certificate.to_pem().map_err(ClientError::Sign)?;
+        let start = pem
+            .find("-----BEGIN CERTIFICATE-----")
+            .ok_or_else(|| ClientError::Tls("Missing certificate".to_string()))?;
+        let (key, chain) = pem.split_at(start);
+        let identity = reqwest::Identity::from_pkcs8_pem(chain.as_bytes(), key.as_bytes())
+            .map_err(|e| ClientError::Tls(e.to_string()))?;
+
+        let roots = roots
+            .iter()
+            .map(|root| {
+                reqwest::Certificate::from_der(root).map_err(|e| ClientError::Tls(e.to_string()))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Tls { identity, roots })
+    }
+
+    /// Applies the configuration to the builder of the HTTP client
+    pub(super) fn apply(&self, builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+        #[cfg(not(feature = "native-tls"))]
+        let builder = builder.use_preconfigured_tls(self.config.clone());
+        #[cfg(feature = "native-tls")]
+        let builder = self.roots.iter().fold(
+            builder.use_native_tls().identity(self.identity.clone()),
+            |builder, root| builder.add_root_certificate(root.clone()),
+        );
+        builder
+    }
+}
+
+/// Reads the root certificates of a PEM bundle or of a DER encoded file
+pub fn read_root_certificates(path: &str) -> Result<Vec<Vec<u8>>, ClientError> {
+    let data = std::fs::read(path).map_err(|e| ClientError::Tls(format!("{}: {}", path, e)))?;
+    let Ok(pem) = std::str::from_utf8(&data) else {
+        return Ok(vec![data]);
+    };
+
+    let mut certificates = Vec::new();
+    for block in pem.split("-----BEGIN CERTIFICATE-----").skip(1) {
+        let (encoded, _) = block
+            .split_once("-----END CERTIFICATE-----")
+            .ok_or_else(|| ClientError::Tls(format!("{}: Unclosed certificate", path)))?;
+        let encoded = encoded
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>();
+        certificates.push(
+            BASE64
+                .decode(encoded)
+                .map_err(|e| ClientError::Tls(format!("{}: {}", path, e)))?,
+        );
+    }
+    if certificates.is_empty() {
+        return Err(ClientError::Tls(format!("{}: No certificate found", path)));
+    }
+    Ok(certificates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sign::tests::setup_certificate;
+
+    #[test]
+    fn test_client_auth() {
+        let tls = Tls::new(&setup_certificate()).expect("Failed to convert the certificate");
+        #[cfg(not(feature = "native-tls"))]
+        assert!(tls.config.client_auth_cert_resolver.has_certs());
+        assert!(tls.apply(reqwest::Client::builder()).build().is_ok());
+    }
+
+    #[test]
+    fn test_root_certificates() {
+        let roots = read_root_certificates("tests/credentials/cert.pem")
+            .expect("Failed to read the root certificates");
+        assert_eq!(roots.len(), 1);
+
+        let tls = Tls::with_roots(&setup_certificate(), &roots).expect("Invalid root certificate");
+        assert!(tls.apply(reqwest::Client::builder()).build().is_ok());
+
+        assert!(matches!(
+            read_root_certificates("tests/credentials/missing.pem"),
+            Err(ClientError::Tls(_))
+        ));
+        assert!(matches!(
+            Tls::with_roots(&setup_certificate(), &[b"invalid".to_vec()]),
+            Err(ClientError::Tls(_))
+        ));
+    }
+}
@@ -1,3 +1,4 @@
+use chrono::{DateTime, FixedOffset, NaiveDate, TimeDelta, Utc};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
@@ -183,4 +184,198 @@ impl State {
     pub fn code(&self) -> u8 {
         self.clone() as u8
     }
+
+    /// Legal offset from UTC of the capital of the state at the instant `at`
+    ///
+    /// Follows the daylight saving time of the South, Southeast and
+    /// Center-West until its end in 2019. Instants before 2008 use the current
+    /// standard offsets.
+    pub fn utc_offset(&self, at: DateTime<Utc>) -> FixedOffset {
+        let hours = self.standard_offset(at);
+        let daylight_saving = self.observes_daylight_saving(at)
+            && DAYLIGHT_SAVING.iter().any(|&(start, end)| {
+                let start = midnight(start) - TimeDelta::hours(hours.into());
+                let end = midnight(end) - TimeDelta::hours((hours + 1).into());
+                start <= at && at < end
+            });
+        let hours = if daylight_saving { hours + 1 } else { hours };
+        FixedOffset::east_opt(i32::from(hours) * 3600).expect("Offset of a state out of range")
+    }
+
+    fn standard_offset(&self, at: DateTime<Utc>) -> i8 {
+        // Acre was moved to UTC-4 between 2008 and 2013
+        let acre_change = midnight((2008, 6, 24)) + TimeDelta::hours(5)
+            ..midnight((2013, 11, 10)) + TimeDelta::hours(4);
+        match self {
+            State::Acre if acre_change.contains(&at) => -4,
+            State::Acre => -5,
+            State::Amazonas
+            | State::Rondonia
+            | State::Roraima
+            | State::MatoGrosso
+            | State::MatoGrossoDoSul => -4,
+            _ => -3,
+        }
+    }
+
+    fn observes_daylight_saving(&self, at: DateTime<Utc>) -> bool {
+        match self {
+            State::RioGrandeDoSul
+            | State::SantaCatarina
+            | State::Parana
+            | State::SaoPaulo
+            | State::RioDeJaneiro
+            | State::EspiritoSanto
+            | State::MinasGerais
+            | State::Goias
+            | State::DistritoFederal
+            | State::MatoGrosso
+            | State::MatoGrossoDoSul => true,
+            // Only in the summer of 2011-2012
+            State::Bahia => (midnight((2011, 10, 1))..midnight((2012, 3, 1))).contains(&at),
+            // Only in the summer of 2012-2013
+            State::Tocantins => (midnight((2012, 10, 1))..midnight((2013, 3, 1))).contains(&at),
+            _ => false,
+        }
+    }
+}
+
+/// Year, month and day
+type Date = (i32, u32, u32);
+
+/// Start and end dates of the daylight saving time, changed at midnight
+const DAYLIGHT_SAVING: [(Date, Date); 11] = [
+    ((2008, 10, 19), (2009, 2, 15)),
+    ((2009, 10, 18), (2010, 2, 21)),
+    ((2010, 10, 17), (2011, 2, 20)),
+    ((2011, 10, 16), (2012, 2, 26)),
+    ((2012, 10, 21), (2013, 2, 17)),
+    ((2013, 10, 20), (2014, 2, 16)),
+    ((2014, 10, 19), (2015, 2, 22)),
+    ((2015, 10, 18), (2016, 2, 21)),
+    ((2016, 10, 16), (2017, 2, 19)),
+    ((2017, 10, 15), (2018, 2, 18)),
+    ((2018, 11, 4), (2019, 2, 17)),
+];
+
+fn midnight((year, month, day): Date) -> DateTime<Utc> {
+    NaiveDate::from_ymd_opt(year, month, day)
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .expect("Invalid date")
+        .and_utc()
+}
+
+/// Code of the municipality of Fernando de Noronha (PE), two hours behind UTC
+pub const FERNANDO_DE_NORONHA: u32 = 2605459;
+
+impl Location {
+    /// Legal offset from UTC of the location at the instant `at`
+    ///
+    /// Same as the state, except for Fernando de Noronha.
+    pub fn utc_offset(&self, at: DateTime<Utc>) -> FixedOffset {
+        if self.city.code == FERNANDO_DE_NORONHA {
+            return FixedOffset::west_opt(2 * 3600).expect("Offset out of range");
+        }
+        self.state.utc_offset(at)
+    }
+}
+
+/// Serde helpers representing a state by its acronym (UF)
+pub mod acronym {
+    use super::State;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(state: &State, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(state.acronym())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<State, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let acronym = String::deserialize(deserializer)?;
+        State::from_acronym(&acronym).ok_or_else(|| {
+            serde::de::Error::custom(format!("Invalid state acronym: {}", acronym))
+        })
+    }
+}
+
+pub mod optional_acronym {
+    use super::State;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(state: &Option<State>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match state {
+            Some(state) => serializer.serialize_str(state.acronym()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<State>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<String>::deserialize(deserializer)? {
+            None => Ok(None),
+            Some(acronym) => State::from_acronym(&acronym).map(Some).ok_or_else(|| {
+                serde::de::Error::custom(format!("Invalid state acronym: {}", acronym))
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn offset(state: State, year: i32, month: u32, day: u32, hour: u32) -> i32 {
+        let at = Utc.with_ymd_and_hms(year, month, day, hour, 0, 0).unwrap();
+        state.utc_offset(at).local_minus_utc() / 3600
+    }
+
+    #[test]
+    fn test_standard_offsets() {
+        assert_eq!(offset(State::MinasGerais, 2023, 10, 5, 12), -3);
+        assert_eq!(offset(State::Pernambuco, 2023, 10, 5, 12), -3);
+        assert_eq!(offset(State::Amazonas, 2023, 10, 5, 12), -4);
+        assert_eq!(offset(State::MatoGrosso, 2023, 10, 5, 12), -4);
+        assert_eq!(offset(State::Acre, 2023, 10, 5, 12), -5);
+        assert_eq!(offset(State::Acre, 2010, 10, 5, 12), -4);
+    }
+
+    #[test]
+    fn test_daylight_saving() {
+        assert_eq!(offset(State::SaoPaulo, 2018, 12, 1, 12), -2);
+        assert_eq!(offset(State::MatoGrosso, 2018, 12, 1, 12), -3);
+        assert_eq!(offset(State::Pernambuco, 2018, 12, 1, 12), -3);
+        assert_eq!(offset(State::Bahia, 2011, 12, 1, 12), -2);
+        assert_eq!(offset(State::Bahia, 2012, 12, 1, 12), -3);
+        assert_eq!(offset(State::SaoPaulo, 2019, 12, 1, 12), -3);
+
+        // Starts at 2018-11-04 00:00 -03:00 and ends at 2019-02-17 00:00 -02:00
+        assert_eq!(offset(State::SaoPaulo, 2018, 11, 4, 2), -3);
+        assert_eq!(offset(State::SaoPaulo, 2018, 11, 4, 3), -2);
+        assert_eq!(offset(State::SaoPaulo, 2019, 2, 17, 1), -2);
+        assert_eq!(offset(State::SaoPaulo, 2019, 2, 17, 2), -3);
+    }
+
+    #[test]
+    fn test_fernando_de_noronha() {
+        let location = Location {
+            state: State::Pernambuco,
+            city: City {
+                code: FERNANDO_DE_NORONHA,
+                name: "Fernando de Noronha".to_string(),
+            },
+        };
+        let at = Utc.with_ymd_and_hms(2023, 10, 5, 12, 0, 0).unwrap();
+        assert_eq!(location.utc_offset(at).local_minus_utc(), -2 * 3600);
+    }
 }
@@ -0,0 +1,197 @@
+//! Streaming reader of large XML files, as the month-end batches of nfeProc
+//!
+//! Only the element being read is kept in memory: the source is scanned with
+//! the pull parser of quick-xml and each matching element is deserialized on
+//! its own, whatever wraps it.
+
+use crate::models::{Detail, NFeProc};
+use quick_xml::Reader;
+use quick_xml::Writer;
+use quick_xml::events::Event;
+use serde::de::DeserializeOwned;
+use std::io::{BufReader, Read};
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamError {
+    /// The source could not be read or is not well-formed XML
+    Xml(String),
+    /// Element that does not match the structure of the item
+    Deserialization(String),
+}
+
+/// Iterator over the elements named `tag` of an XML source
+///
+/// Elements nested in a matching element are part of it, so they are not
+/// yielded on their own. The iterator ends at the first XML error, as the
+/// position of the parser is lost.
+pub struct XmlStream<R: Read, T> {
+    reader: Reader<BufReader<R>>,
+    tag: String,
+    buffer: Vec<u8>,
+    failed: bool,
+    item: PhantomData<T>,
+}
+
+impl<R: Read, T: DeserializeOwned> XmlStream<R, T> {
+    /// Streams the elements with the local name `tag` of `source`
+    pub fn new(source: R, tag: &str) -> Self {
+        XmlStream {
+            reader: Reader::from_reader(BufReader::new(source)),
+            tag: tag.to_string(),
+            buffer: Vec::new(),
+            failed: false,
+            item: PhantomData,
+        }
+    }
+
+    /// Copies the element opened by `start` until its end, returning its XML
+    fn read_element(&mut self, start: Event<'static>) -> Result<String, StreamError> {
+        let mut writer = Writer::new(Vec::new());
+        let empty = matches!(start, Event::Empty(_));
+        writer
+            .write_event(start)
+            .map_err(|e| StreamError::Xml(e.to_string()))?;
+        if !empty {
+            self.copy_content(&mut writer)?;
+        }
+
+        String::from_utf8(writer.into_inner()).map_err(|e| StreamError::Xml(e.to_string()))
+    }
+
+    /// Copies the content of the open element to `writer`, up to its end tag
+    fn copy_content(&mut self, writer: &mut Writer<Vec<u8>>) -> Result<(), StreamError> {
+        let mut depth = 0usize;
+        loop {
+            self.buffer.clear();
+            let event = self
+                .reader
+                .read_event_into(&mut self.buffer)
+                .map_err(|e| StreamError::Xml(e.to_string()))?;
+            let end = match &event {
+                Event::Start(_) => {
+                    depth += 1;
+                    false
+                }
+                Event::End(_) if depth == 0 => true,
+                Event::End(_) => {
+                    depth -= 1;
+                    false
+                }
+                Event::Eof => {
+                    return Err(StreamError::Xml(format!("Unclosed element {}", self.tag)));
+                }
+                _ => false,
+            };
+            writer
+                .write_event(event)
+                .map_err(|e| StreamError::Xml(e.to_string()))?;
+            if end {
+                return Ok(());
+            }
+        }
+    }
+
+    fn next_element(&mut self) -> Result<Option<String>, StreamError> {
+        loop {
+            self.buffer.clear();
+            let event = self
+                .reader
+                .read_event_into(&mut self.buffer)
+                .map_err(|e| StreamError::Xml(e.to_string()))?;
+            match &event {
+                Event::Start(start) | Event::Empty(start)
+                    if start.local_name().as_ref() == self.tag.as_bytes() =>
+                {
+                    let start = event.into_owned();
+                    return self.read_element(start).map(Some);
+                }
+                Event::Eof => return Ok(None),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl<R: Read, T: DeserializeOwned> Iterator for XmlStream<R, T> {
+    type Item = Result<T, StreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        match self.next_element() {
+            Ok(Some(xml)) => Some(
+                quick_xml::de::from_str(&xml)
+                    .map_err(|e| StreamError::Deserialization(e.to_string())),
+            ),
+            Ok(None) => None,
+            Err(error) => {
+                self.failed = true;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+/// Streams the authorized documents (nfeProc) of `source`
+pub fn nfe_procs<R: Read>(source: R) -> XmlStream<R, NFeProc> {
+    XmlStream::new(source, "nfeProc")
+}
+
+/// Streams the items (det) of the documents of `source`
+pub fn details<R: Read>(source: R) -> XmlStream<R, Detail> {
+    XmlStream::new(source, "det")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::tests::setup_nfe_proc;
+
+    fn batch(count: usize) -> String {
+        let nfe_proc = include_str!("../tests/fixtures/nfe_proc.xml");
+        format!("<lote>{}</lote>", nfe_proc.repeat(count))
+    }
+
+    #[test]
+    fn test_nfe_procs() {
+        let xml = batch(3);
+        let documents = nfe_procs(xml.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .expect("Failed to stream the documents");
+
+        assert_eq!(documents.len(), 3);
+        assert!(
+            documents
+                .into_iter()
+                .all(|document| document == setup_nfe_proc())
+        );
+    }
+
+    #[test]
+    fn test_details() {
+        let xml = batch(2);
+        let details = details(xml.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .expect("Failed to stream the details");
+
+        assert_eq!(details.len(), 4);
+        assert_eq!(details, {
+            let mut details = setup_nfe_proc().nfe.info.details;
+            details.extend(setup_nfe_proc().nfe.info.details);
+            details
+        });
+    }
+
+    #[test]
+    fn test_unclosed_element() {
+        let xml = batch(2);
+        let truncated = &xml[..xml.len() - 100];
+        let results = nfe_procs(truncated.as_bytes()).collect::<Vec<_>>();
+
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        assert!(matches!(results[1], Err(StreamError::Xml(_))));
+    }
+}
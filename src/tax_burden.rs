@@ -0,0 +1,199 @@
+//! Approximate tax burden of the items (Lei da Transparência, Lei 12.741/2012)
+//!
+//! Sales to final consumers must inform the approximate value of the taxes
+//! paid by the consumer (vTotTrib). The values come from a table of rates by
+//! product, usually the one of IBPT ("De olho no imposto"), published for each
+//! state. The `InfoBuilder` fills the items with a `TaxBurdenProvider`.
+
+use crate::decimal::Money;
+use crate::enums::{ICMS, Origin};
+use crate::models::Detail;
+use crate::states::State;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaxBurdenError {
+    /// Line of the table with missing or invalid columns
+    InvalidLine(usize, String),
+}
+
+/// Approximate rates of the taxes over the value of the item, in percent
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TaxBurdenRates {
+    pub federal: Decimal,
+    pub state: Decimal,
+    pub municipal: Decimal,
+}
+
+impl TaxBurdenRates {
+    pub fn total(&self) -> Decimal {
+        self.federal + self.state + self.municipal
+    }
+
+    /// Approximate value of the taxes of the item: the rates over its value
+    /// less the discount
+    pub fn burden(&self, detail: &Detail) -> Money {
+        let item = &detail.item;
+        let value = item.total_value - item.discount_value.unwrap_or(Money::ZERO);
+        Money::from(value.value() * self.total() / Decimal::ONE_HUNDRED)
+    }
+}
+
+/// Source of the rates of the approximate tax burden
+pub trait TaxBurdenProvider {
+    /// Rates of the item sold by an issuer of `state`, `None` when unknown
+    fn rates(&self, detail: &Detail, state: &State) -> Option<TaxBurdenRates>;
+}
+
+impl<F> TaxBurdenProvider for F
+where
+    F: Fn(&Detail, &State) -> Option<TaxBurdenRates>,
+{
+    fn rates(&self, detail: &Detail, state: &State) -> Option<TaxBurdenRates> {
+        self(detail, state)
+    }
+}
+
+/// Rates of a product in the IBPT table
+///
+/// national_federal: Federal rate of the national products (nacionalfederal)
+/// imported_federal: Federal rate of the imported products (importadosfederal)
+/// state: State rate (estadual)
+/// municipal: Municipal rate (municipal)
+#[derive(Debug, Clone, PartialEq)]
+pub struct IBPTEntry {
+    pub national_federal: Decimal,
+    pub imported_federal: Decimal,
+    pub state: Decimal,
+    pub municipal: Decimal,
+}
+
+/// IBPT table of a state, indexed by the NCM of the products
+///
+/// Services (NBS and LC 116 codes) are not read, as the items carry no code
+/// of them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IBPTTable {
+    pub state: State,
+    /// Source of the rates, as printed on the DANFE (fonte)
+    pub source: Option<String>,
+    entries: HashMap<u32, IBPTEntry>,
+}
+
+impl IBPTTable {
+    /// Reads the CSV file of the state, with columns separated by ";":
+    /// codigo;ex;tipo;descricao;nacionalfederal;importadosfederal;estadual;municipal;...
+    pub fn parse(state: State, csv: &str) -> Result<Self, TaxBurdenError> {
+        let mut table = IBPTTable {
+            state,
+            source: None,
+            entries: HashMap::new(),
+        };
+        for (index, line) in csv.lines().enumerate().skip(1) {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let invalid = || TaxBurdenError::InvalidLine(index + 1, line.to_string());
+            let columns = line.split(';').map(str::trim).collect::<Vec<_>>();
+            if columns.len() < 8 {
+                return Err(invalid());
+            }
+            // Type 0 is the NCM, the exceptions (ex) of the TIPI are skipped
+            if columns[2] != "0" || !columns[1].is_empty() {
+                continue;
+            }
+            let rate = |column: usize| Decimal::from_str(columns[column]).map_err(|_| invalid());
+            let ncm = columns[0].parse().map_err(|_| invalid())?;
+            table.entries.insert(
+                ncm,
+                IBPTEntry {
+                    national_federal: rate(4)?,
+                    imported_federal: rate(5)?,
+                    state: rate(6)?,
+                    municipal: rate(7)?,
+                },
+            );
+            if table.source.is_none() {
+                table.source = columns
+                    .get(12)
+                    .filter(|s| !s.is_empty())
+                    .map(|s| s.to_string());
+            }
+        }
+        Ok(table)
+    }
+
+    pub fn entry(&self, ncm: u32) -> Option<&IBPTEntry> {
+        self.entries.get(&ncm)
+    }
+}
+
+/// Whether the origin of the item (orig) takes the federal rate of the imported products
+fn imported(icms: Option<&ICMS>) -> bool {
+    matches!(
+        icms.map(ICMS::origin),
+        Some(
+            Origin::Foreign
+                | Origin::ForeignInternalMarket
+                | Origin::ForeignNoSimilar
+                | Origin::ForeignInternalMarketNoSimilar
+        )
+    )
+}
+
+impl TaxBurdenProvider for IBPTTable {
+    fn rates(&self, detail: &Detail, state: &State) -> Option<TaxBurdenRates> {
+        if *state != self.state {
+            return None;
+        }
+        let entry = self.entry(detail.item.ncm)?;
+        Some(TaxBurdenRates {
+            federal: match imported(detail.tax.icms.as_ref()) {
+                true => entry.imported_federal,
+                false => entry.national_federal,
+            },
+            state: entry.state,
+            municipal: entry.municipal,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::tests::setup_detail;
+    use rust_decimal_macros::dec;
+
+    const TABLE: &str = "codigo;ex;tipo;descricao;nacionalfederal;importadosfederal;estadual;municipal;vigenciainicio;vigenciafim;chave;versao;fonte
+33072010;;0;Desodorantes;13.45;19.21;18.00;0.00;01/07/2025;31/12/2025;A1B2C3;25.2.A;IBPT/empresometro.com.br
+33072010;01;0;Desodorantes ex 01;5.00;5.00;5.00;0.00;01/07/2025;31/12/2025;A1B2C3;25.2.A;IBPT/empresometro.com.br
+1.01;;1;Servicos de construcao;13.45;15.45;0.00;2.00;01/07/2025;31/12/2025;A1B2C3;25.2.A;IBPT/empresometro.com.br
+";
+
+    #[test]
+    fn test_ibpt_table() {
+        let table = IBPTTable::parse(State::MinasGerais, TABLE).expect("Failed to read the table");
+        assert_eq!(table.source.as_deref(), Some("IBPT/empresometro.com.br"));
+        assert_eq!(table.entry(33072010).unwrap().national_federal, dec!(13.45));
+
+        let detail = setup_detail();
+        let rates = table.rates(&detail, &State::MinasGerais).unwrap();
+        assert_eq!(rates.total(), dec!(31.45));
+        // 31.45% of 56.97
+        assert_eq!(rates.burden(&detail).to_string(), "17.92");
+        assert_eq!(table.rates(&detail, &State::SaoPaulo), None);
+
+        assert_eq!(
+            IBPTTable::parse(
+                State::MinasGerais,
+                "header\n33072010;;0;Desodorantes;abc;1;1;1"
+            ),
+            Err(TaxBurdenError::InvalidLine(
+                2,
+                "33072010;;0;Desodorantes;abc;1;1;1".to_string()
+            ))
+        );
+    }
+}
@@ -0,0 +1,272 @@
+//! Query of the registration of the taxpayers (ConsCad)
+//!
+//! The CadConsultaCadastro4 webservice of a state returns the situation of the
+//! taxpayers registered in it, with their state registrations and addresses.
+//! It is useful to validate the recipient before issuing an interstate NF-e.
+
+use crate::enums::{CNPJ, CPF, IE};
+use crate::models::NFE_NAMESPACE;
+use crate::states::State;
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use nf_e_macros::CodedEnum;
+use serde::{Deserialize, Serialize, ser::SerializeStruct};
+
+pub const REGISTRATION_VERSION: &str = "2.00";
+
+/// Document of the queried taxpayer
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub enum TaxpayerDocument {
+    CNPJ(CNPJ),
+    CPF(CPF),
+    IE(IE),
+}
+
+/// Query of the registration of a taxpayer (ConsCad)
+///
+/// state: State of the registration (UF)
+/// document: Document of the taxpayer (CNPJ, CPF or IE)
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegistrationQuery {
+    pub state: State,
+    pub document: TaxpayerDocument,
+}
+
+impl Serialize for RegistrationQuery {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        #[derive(Serialize)]
+        struct InfCons<'a> {
+            #[serde(rename = "xServ")]
+            service: &'a str,
+            #[serde(rename = "UF")]
+            state: &'a str,
+            #[serde(rename = "$value")]
+            document: &'a TaxpayerDocument,
+        }
+
+        let mut state = serializer.serialize_struct("ConsCad", 3)?;
+        state.serialize_field("@xmlns", NFE_NAMESPACE)?;
+        state.serialize_field("@versao", REGISTRATION_VERSION)?;
+        state.serialize_field(
+            "infCons",
+            &InfCons {
+                service: "CONS-CAD",
+                state: self.state.acronym(),
+                document: &self.document,
+            },
+        )?;
+        state.end()
+    }
+}
+
+/// Situation of the taxpayer (cSit)
+#[derive(CodedEnum, PartialEq, Debug, Clone)]
+pub enum TaxpayerSituation {
+    Disabled = 0,
+    Enabled = 1,
+}
+
+/// Accreditation of the taxpayer to issue a kind of document (indCredNFe, indCredCTe)
+#[derive(CodedEnum, PartialEq, Debug, Clone)]
+pub enum Accreditation {
+    NotAccredited = 0,
+    Accredited = 1,
+    /// Accredited, with the emission required for every operation
+    Required = 2,
+    /// Accredited, with the emission required for some operations
+    PartiallyRequired = 3,
+    /// The state does not inform the accreditation
+    NotInformed = 4,
+}
+
+/// Address of the taxpayer (ender)
+///
+/// line_1: Street (xLgr) - Optional
+/// number: Number (nro) - Optional
+/// line_2: Complement (xCpl) - Optional
+/// neighborhood: Neighborhood (xBairro) - Optional
+/// city_code: IBGE code of the city (cMun) - Optional
+/// city_name: Name of the city (xMun) - Optional
+/// zip_code: Postal code (CEP) - Optional
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct TaxpayerAddress {
+    #[serde(rename = "xLgr", skip_serializing_if = "Option::is_none")]
+    pub line_1: Option<String>,
+    #[serde(rename = "nro", skip_serializing_if = "Option::is_none")]
+    pub number: Option<String>,
+    #[serde(rename = "xCpl", skip_serializing_if = "Option::is_none")]
+    pub line_2: Option<String>,
+    #[serde(rename = "xBairro", skip_serializing_if = "Option::is_none")]
+    pub neighborhood: Option<String>,
+    #[serde(rename = "cMun", skip_serializing_if = "Option::is_none")]
+    pub city_code: Option<u32>,
+    #[serde(rename = "xMun", skip_serializing_if = "Option::is_none")]
+    pub city_name: Option<String>,
+    #[serde(rename = "CEP", skip_serializing_if = "Option::is_none")]
+    pub zip_code: Option<String>,
+}
+
+/// Registration of a taxpayer (infCad)
+///
+/// ie: State registration (IE)
+/// cnpj: CNPJ of the taxpayer (CNPJ) - Optional
+/// cpf: CPF of the taxpayer (CPF) - Optional
+/// state: State of the registration (UF)
+/// situation: Situation of the taxpayer (cSit)
+/// nfe_accreditation: Accreditation to issue NF-e (indCredNFe)
+/// cte_accreditation: Accreditation to issue CT-e (indCredCTe)
+/// name: Legal name (xNome)
+/// trade_name: Trade name (xFant) - Optional
+/// tax_regime: Tax regime of the ICMS (xRegApur) - Optional
+/// cnae: Main economic activity (CNAE) - Optional
+/// started_at: Start of the activities (dIniAtiv) - Optional
+/// situation_date: Date of the last change of situation (dUltSit) - Optional
+/// closed_at: Date of the closing of the registration (dBaixa) - Optional
+/// single_ie: Single state registration of the taxpayer (IEUnica) - Optional
+/// current_ie: Current state registration, when it was replaced (IEAtual) - Optional
+/// address: Address of the taxpayer (ender) - Optional
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Taxpayer {
+    #[serde(rename = "IE")]
+    pub ie: IE,
+    #[serde(rename = "CNPJ", skip_serializing_if = "Option::is_none")]
+    pub cnpj: Option<CNPJ>,
+    #[serde(rename = "CPF", skip_serializing_if = "Option::is_none")]
+    pub cpf: Option<CPF>,
+    #[serde(rename = "UF", with = "crate::states::acronym")]
+    pub state: State,
+    #[serde(rename = "cSit")]
+    pub situation: TaxpayerSituation,
+    #[serde(rename = "indCredNFe")]
+    pub nfe_accreditation: Accreditation,
+    #[serde(rename = "indCredCTe")]
+    pub cte_accreditation: Accreditation,
+    #[serde(rename = "xNome")]
+    pub name: String,
+    #[serde(rename = "xFant", skip_serializing_if = "Option::is_none")]
+    pub trade_name: Option<String>,
+    #[serde(rename = "xRegApur", skip_serializing_if = "Option::is_none")]
+    pub tax_regime: Option<String>,
+    #[serde(rename = "CNAE", skip_serializing_if = "Option::is_none")]
+    pub cnae: Option<String>,
+    #[serde(rename = "dIniAtiv", skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<NaiveDate>,
+    #[serde(rename = "dUltSit", skip_serializing_if = "Option::is_none")]
+    pub situation_date: Option<NaiveDate>,
+    #[serde(rename = "dBaixa", skip_serializing_if = "Option::is_none")]
+    pub closed_at: Option<NaiveDate>,
+    #[serde(rename = "IEUnica", skip_serializing_if = "Option::is_none")]
+    pub single_ie: Option<IE>,
+    #[serde(rename = "IEAtual", skip_serializing_if = "Option::is_none")]
+    pub current_ie: Option<IE>,
+    #[serde(rename = "ender", skip_serializing_if = "Option::is_none")]
+    pub address: Option<TaxpayerAddress>,
+}
+
+impl Taxpayer {
+    /// Whether the taxpayer is enabled and can receive an NF-e as a contributor
+    pub fn is_enabled(&self) -> bool {
+        self.situation == TaxpayerSituation::Enabled
+    }
+}
+
+/// Result of the query (infCons)
+///
+/// application_version: Version of the SEFAZ application (verAplic)
+/// status: Status code of the query (cStat)
+/// reason: Status description (xMotivo)
+/// state: State of the registration (UF)
+/// ie: State registration queried (IE) - Optional
+/// cnpj: CNPJ queried (CNPJ) - Optional
+/// cpf: CPF queried (CPF) - Optional
+/// queried_at: Date and time of the query (dhCons)
+/// state_code: IBGE code of the state that answered (cUF)
+/// taxpayers: Registrations found (infCad)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct RegistrationResult {
+    #[serde(rename = "verAplic")]
+    pub application_version: String,
+    #[serde(rename = "cStat")]
+    pub status: u16,
+    #[serde(rename = "xMotivo")]
+    pub reason: String,
+    #[serde(rename = "UF", with = "crate::states::acronym")]
+    pub state: State,
+    #[serde(rename = "IE", skip_serializing_if = "Option::is_none")]
+    pub ie: Option<String>,
+    #[serde(rename = "CNPJ", skip_serializing_if = "Option::is_none")]
+    pub cnpj: Option<String>,
+    #[serde(rename = "CPF", skip_serializing_if = "Option::is_none")]
+    pub cpf: Option<String>,
+    #[serde(rename = "dhCons")]
+    pub queried_at: DateTime<FixedOffset>,
+    #[serde(rename = "cUF")]
+    pub state_code: u8,
+    #[serde(rename = "infCad", default, skip_serializing_if = "Vec::is_empty")]
+    pub taxpayers: Vec<Taxpayer>,
+}
+
+/// Response of the registration query (retConsCad)
+///
+/// version: Layout version (@versao)
+/// info: Result of the query (infCons)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct RegistrationResponse {
+    #[serde(rename = "@versao")]
+    pub version: String,
+    #[serde(rename = "infCons")]
+    pub info: RegistrationResult,
+}
+
+impl RegistrationResponse {
+    /// Registrations found, empty when the taxpayer is not registered in the state
+    ///
+    /// SEFAZ answers 111 with a single registration and 112 with several ones.
+    pub fn taxpayers(&self) -> &[Taxpayer] {
+        match self.info.status {
+            111 | 112 => &self.info.taxpayers,
+            _ => &[],
+        }
+    }
+
+    /// Enabled registration of the taxpayer, if any
+    pub fn enabled(&self) -> Option<&Taxpayer> {
+        self.taxpayers()
+            .iter()
+            .find(|taxpayer| taxpayer.is_enabled())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registration_query() {
+        let query = RegistrationQuery {
+            state: State::SaoPaulo,
+            document: TaxpayerDocument::CNPJ(CNPJ("12345678000195".to_string())),
+        };
+        let expected = concat!(
+            r#"<ConsCad xmlns="http://www.portalfiscal.inf.br/nfe" versao="2.00">"#,
+            "<infCons><xServ>CONS-CAD</xServ><UF>SP</UF><CNPJ>12345678000195</CNPJ></infCons>",
+            "</ConsCad>"
+        );
+        assert_eq!(
+            quick_xml::se::to_string_with_root("ConsCad", &query).unwrap(),
+            expected
+        );
+
+        let query = RegistrationQuery {
+            state: State::SaoPaulo,
+            document: TaxpayerDocument::IE(IE("110042490114".to_string())),
+        };
+        assert!(
+            quick_xml::se::to_string_with_root("ConsCad", &query)
+                .unwrap()
+                .contains("<IE>110042490114</IE>")
+        );
+    }
+}
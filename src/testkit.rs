@@ -0,0 +1,332 @@
+//! Sample documents for the tests of the integrators
+//!
+//! Realistic values accepted by the rules of the builder, so the crates using
+//! this one can test their own code without copying its test setup. The
+//! samples are issued in homologation by an issuer of Minas Gerais under the
+//! Simples Nacional. With the `sign` feature their configuration carries the
+//! self-signed certificate of the test suite, so the documents and events can
+//! be signed, but never accepted by SEFAZ.
+//!
+//! Enabled by the `testkit` feature.
+
+use crate::access_key::{AccessKey, Components};
+use crate::config::{Config, PKCS12Config};
+use crate::decimal::{Money, Quantity};
+use crate::enums::{
+    CNPJ, CPF, CSOSN, DestinationTarget, EmissionType, ICMS, IE, IEIndicator, Model, Origin,
+    PaymentType, PersonDocument, Presence, RecipientDocument, TaxRegime, with_verifier_digits,
+};
+use crate::events::CancelEvent;
+use crate::models::{
+    Address, Detail, ICMSSN102, IdentificationBuilder, Info, InfoBuilder, Issuer, Item, Payment,
+    Payments, Recipient, Tax, TaxableAddress,
+};
+#[cfg(feature = "sign")]
+use crate::sign::Certificate;
+use crate::states::{City, State};
+use chrono::Timelike;
+use rand::Rng;
+use rust_decimal::Decimal;
+
+/// Password of the certificate of the samples
+pub const CERTIFICATE_PASSWORD: &str = "12345678";
+
+/// Random CNPJ of a head office (0001), with valid verifier digits
+pub fn random_cnpj() -> CNPJ {
+    let mut rng = rand::thread_rng();
+    let mut digits: Vec<u32> = (0..8).map(|_| rng.gen_range(0..10)).collect();
+    // The root 00000000 with the branch 0001 is still a valid document
+    digits.extend([0, 0, 0, 1]);
+    CNPJ(with_verifier_digits(digits, 9))
+}
+
+/// Random CPF with valid verifier digits
+pub fn random_cpf() -> CPF {
+    let mut rng = rand::thread_rng();
+    loop {
+        let digits: Vec<u32> = (0..9).map(|_| rng.gen_range(0..10)).collect();
+        // Sequences of a single digit are rejected as documents
+        if digits.iter().any(|&digit| digit != digits[0]) {
+            return CPF(with_verifier_digits(digits, 11));
+        }
+    }
+}
+
+/// Random access key of a document of `model` issued in São Paulo by a
+/// random CNPJ
+pub fn random_access_key(model: Model) -> AccessKey {
+    let mut rng = rand::thread_rng();
+    let issuer = PersonDocument::CNPJ(random_cnpj());
+    AccessKey::new(&Components {
+        state: State::SaoPaulo,
+        year: rng.gen_range(20..30),
+        month: rng.gen_range(1..=12),
+        issuer: &issuer,
+        model,
+        series: rng.gen_range(1..900),
+        number: rng.gen_range(1..=999_999_999),
+        emission_type: EmissionType::Normal,
+        numeric_code: rng.gen_range(0..100_000_000),
+    })
+}
+
+/// Self-signed certificate of the issuer of the samples
+#[cfg(feature = "sign")]
+pub fn sample_certificate() -> Certificate {
+    Certificate::from_pkcs12_der(
+        include_bytes!("../tests/credentials/cert.p12"),
+        CERTIFICATE_PASSWORD,
+    )
+    .expect("Invalid certificate of the samples")
+}
+
+/// Issuer of the samples, from Belo Horizonte under the Simples Nacional
+pub fn sample_issuer() -> Issuer {
+    Issuer {
+        document: PersonDocument::CNPJ(CNPJ("12345678000195".to_string())),
+        name: "Empresa Exemplo LTDA".to_string(),
+        trade_name: Some("Empresa Exemplo".to_string()),
+        address: TaxableAddress {
+            address: Address {
+                line_1: "Rua Exemplo".to_string(),
+                line_2: Some("Loja 1".to_string()),
+                number: "123".to_string(),
+                neighborhood: "Centro".to_string(),
+                city: City {
+                    code: 3106200,
+                    name: "Belo Horizonte".to_string(),
+                },
+                state: State::MinasGerais,
+                zip_code: "30110000".to_string(),
+                telephone: "3132123456".to_string(),
+            },
+            ie: IE("0623079040081".to_string()),
+        },
+        ie_st: None,
+        im: None,
+        cnae: None,
+        tax_regime: TaxRegime::Simples,
+    }
+}
+
+/// Configuration of the issuer of the samples, for NF-e in homologation
+///
+/// Without the `sign` feature, or to sign with another certificate, the
+/// PKCS#12 file must be set with a configuration of its own.
+pub fn sample_config() -> Config {
+    let config = Config::new(
+        sample_issuer(),
+        PKCS12Config::new(String::new(), CERTIFICATE_PASSWORD.to_string()),
+    );
+    #[cfg(feature = "sign")]
+    let config = config.with_certificate(sample_certificate());
+    config
+}
+
+/// Company of São Paulo contributor of the ICMS
+pub fn sample_recipient() -> Recipient {
+    Recipient {
+        document: RecipientDocument::CNPJ(CNPJ("11222333000181".to_string())),
+        name: Some("Comercio Destino LTDA".to_string()),
+        address: Some(Address {
+            line_1: "Avenida Paulista".to_string(),
+            line_2: None,
+            number: "1000".to_string(),
+            neighborhood: "Bela Vista".to_string(),
+            city: City {
+                code: 3550308,
+                name: "Sao Paulo".to_string(),
+            },
+            state: State::SaoPaulo,
+            zip_code: "01310100".to_string(),
+            telephone: "1132123456".to_string(),
+        }),
+        ie_indicator: IEIndicator::Contributor,
+        ie: Some(IE("110042490114".to_string())),
+        suframa: None,
+        im: None,
+        email: Some("compras@destino.com.br".to_string()),
+    }
+}
+
+/// Sale of 3 units of a product, taxed under the Simples Nacional (CSOSN 102)
+pub fn sample_detail(cfop: u32) -> Detail {
+    let unit_value = Decimal::new(1899, 2);
+    let quantity = Decimal::from(3);
+    Detail {
+        item: Item {
+            cfop,
+            code: "7896235354499".to_string(),
+            description: "DESODORANTE AEROSOL 200ML".to_string(),
+            ncm: 33072010,
+            nve_codes: Vec::new(),
+            cest: None,
+            scale: None,
+            manufacturer: None,
+            benefit_code: None,
+            ex_tipi: None,
+            gtin: Some("7896235354499".to_string()),
+            included: true,
+            quantity: Quantity::from(quantity),
+            total_value: Money::from(unit_value * quantity),
+            unit: "UN".to_string(),
+            tribute_unit: "UN".to_string(),
+            tribute_quantity: Quantity::from(quantity),
+            tribute_unit_value: Money::from(unit_value),
+            freight: None,
+            insurance: None,
+            discount_value: None,
+            other_value: None,
+            import_declarations: Vec::new(),
+            export_details: Vec::new(),
+            traceability: Vec::new(),
+            vehicle: None,
+            medicine: None,
+            fuel: None,
+            service: false,
+        },
+        tax: Tax {
+            approximate_burden: None,
+            icms: Some(ICMS::ICMSSN102(ICMSSN102 {
+                origin: Origin::National,
+                csosn: CSOSN::FinalConsumer,
+            })),
+            ipi: None,
+            ii: None,
+            issqn: None,
+            pis: None,
+            pis_st: None,
+            cofins: None,
+            cofins_st: None,
+        },
+        devolution: None,
+        additional_info: None,
+    }
+}
+
+/// Numeric code (cNF) of the samples, so their access keys only change with
+/// the month of the emission
+const NUMERIC_CODE: u32 = 10203040;
+
+/// Current moment of the clock of `config` without the fraction of the second, which the layout drops
+fn emission_date(config: &Config) -> chrono::DateTime<chrono::Local> {
+    config.clock().local().with_nanosecond(0).unwrap()
+}
+
+fn payments(r#type: PaymentType, details: &[Detail]) -> Payments {
+    Payments {
+        payments: vec![Payment {
+            indicator: None,
+            r#type,
+            description: None,
+            value: details.iter().map(|detail| detail.item.total_value).sum(),
+            card: None,
+        }],
+        change: None,
+    }
+}
+
+/// NFC-e of a sale to a consumer at the establishment, paid by PIX
+pub fn sample_nfce() -> Info {
+    let config = sample_config().with_model(Model::NFCe);
+    let details = vec![sample_detail(5102), sample_detail(5102)];
+    let identification = IdentificationBuilder::new(&config, 1, "Venda de mercadoria")
+        .set_presence(Presence::InplaceIndoor)
+        .set_numeric_code(NUMERIC_CODE)
+        .set_emission_date(emission_date(&config))
+        .build()
+        .expect("Invalid sample identification");
+    let payments = payments(PaymentType::PIX, &details);
+    details
+        .into_iter()
+        .fold(
+            InfoBuilder::new(&config, identification, payments),
+            InfoBuilder::add_detail,
+        )
+        .build()
+        .expect("Invalid sample NFC-e")
+}
+
+/// NF-e of a sale to a company of another state (São Paulo), paid by boleto
+pub fn sample_nfe_interstate() -> Info {
+    let config = sample_config();
+    let detail = sample_detail(6102);
+    let identification = IdentificationBuilder::new(&config, 1, "Venda de mercadoria")
+        .set_destination(DestinationTarget::Interstate)
+        .set_numeric_code(NUMERIC_CODE)
+        .set_emission_date(emission_date(&config))
+        .build()
+        .expect("Invalid sample identification");
+    let payments = payments(PaymentType::Boleto, std::slice::from_ref(&detail));
+    InfoBuilder::new(&config, identification, payments)
+        .set_recipient(sample_recipient())
+        .add_detail(detail)
+        .build()
+        .expect("Invalid sample NF-e")
+}
+
+/// Cancellation of `sample_nfe_interstate`
+pub fn sample_cancel_event() -> CancelEvent {
+    CancelEvent::new(
+        &sample_nfe_interstate().access_key(),
+        "131250000000001",
+        "Cancelamento por erro na emissao",
+    )
+    .expect("Invalid sample cancellation")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::Environment;
+    use quick_xml::{de::from_str as deserialize, se::to_string as serialize};
+
+    #[test]
+    fn test_random_documents() {
+        for _ in 0..100 {
+            let cnpj = random_cnpj();
+            assert_eq!(CNPJ::parse(&cnpj.0), Ok(cnpj));
+            let cpf = random_cpf();
+            assert_eq!(CPF::parse(&cpf.0), Ok(cpf));
+            let key = random_access_key(Model::NFe);
+            assert_eq!(AccessKey::parse(key.as_str()), Ok(key));
+        }
+    }
+
+    #[test]
+    fn test_samples() {
+        let nfce = sample_nfce();
+        assert_eq!(nfce.identification.model, Model::NFCe);
+        assert_eq!(nfce.identification.environment, Environment::Homologation);
+        let xml = serialize(&nfce).unwrap();
+        assert_eq!(deserialize::<Info>(&xml).unwrap(), nfce);
+
+        let nfe = sample_nfe_interstate();
+        assert_eq!(
+            nfe.identification.destination,
+            DestinationTarget::Interstate
+        );
+        assert_eq!(nfe.total.icms.total, Money::from(Decimal::new(5697, 2)));
+
+        let event = sample_cancel_event();
+        assert!(
+            event
+                .info(
+                    Environment::Homologation,
+                    nfe.issuer.document.clone(),
+                    chrono::Local::now().fixed_offset(),
+                )
+                .id()
+                .contains(nfe.access_key().as_str())
+        );
+    }
+
+    #[cfg(feature = "sign")]
+    #[test]
+    fn test_sign_samples() {
+        let nfe = crate::models::NFe::with_certificate(sample_nfce(), &sample_certificate());
+        assert!(nfe.is_ok());
+        let config = sample_config();
+        assert!(config.validate_certificate(chrono::Utc::now()).is_ok());
+    }
+}
@@ -1,81 +1,63 @@
-use quick_xml::{events::Event, Reader, Writer};
-use std::{error::Error, io::Cursor};
-use xml_canonicalization::Canonicalizer;
-
-fn remove_whitespaces_from_xml(input: &str) -> Result<String, Box<dyn Error>> {
-    let mut reader = Reader::from_str(input);
-    reader.config_mut().trim_text(true);
-
-    let mut writer = Writer::new(Cursor::new(Vec::new()));
-
-    loop {
-        match reader.read_event() {
-            Ok(Event::GeneralRef(e)) => writer.write_event(Event::GeneralRef(e))?,
-            Ok(Event::Start(e)) => writer.write_event(Event::Start(e))?,
-            Ok(Event::End(e)) => writer.write_event(Event::End(e))?,
-            Ok(Event::Empty(e)) => writer.write_event(Event::Empty(e))?,
-            Ok(Event::Text(e)) => writer.write_event(Event::Text(e))?,
-            Ok(Event::Comment(e)) => writer.write_event(Event::Comment(e))?,
-            Ok(Event::CData(e)) => writer.write_event(Event::CData(e))?,
-            Ok(Event::Decl(e)) => writer.write_event(Event::Decl(e))?,
-            Ok(Event::PI(e)) => writer.write_event(Event::PI(e))?,
-            Ok(Event::DocType(e)) => writer.write_event(Event::DocType(e))?,
-
-            Ok(Event::Eof) => break,
-
-            Err(e) => return Err(Box::new(e)),
+/// Serde helpers for optional "0"/"1" flags
+pub mod optional_flag {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(value: &Option<bool>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match value {
+            Some(flag) => serializer.serialize_u8(*flag as u8),
+            None => serializer.serialize_none(),
         }
     }
 
-    let result = writer.into_inner().into_inner();
-    Ok(String::from_utf8(result).unwrap())
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<bool>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<u8>::deserialize(deserializer)? {
+            None => Ok(None),
+            Some(0) => Ok(Some(false)),
+            Some(1) => Ok(Some(true)),
+            Some(value) => Err(serde::de::Error::custom(format!(
+                "Invalid flag value: {}",
+                value
+            ))),
+        }
+    }
 }
 
-pub fn canonicalize_xml(input: &str) -> Result<String, Box<dyn Error>> {
-    let mut result = Vec::new();
-    let cleaned = remove_whitespaces_from_xml(input)?;
-    Canonicalizer::read_from_str(&cleaned)
-        .write_to_writer(Cursor::new(&mut result))
-        .canonicalize(false)?;
-    String::from_utf8(result).map_err(|e| e.into())
+thread_local! {
+    static LENIENT: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
 }
 
-pub fn left_pad(input: &str, total_length: usize, pad_char: char) -> String {
-    if input.len() >= total_length {
-        input.to_string()
-    } else {
-        let padding = pad_char.to_string().repeat(total_length - input.len());
-        format!("{}{}", padding, input)
-    }
+/// Runs `parse` with the deserializers in lenient mode
+///
+/// Documents issued by other systems differ from the ones of the library: the
+/// deserializers check `is_lenient` to accept their variations, which the
+/// strict mode rejects so the round trip of the library stays exact.
+pub(crate) fn lenient<T>(parse: impl FnOnce() -> T) -> T {
+    let previous = LENIENT.with(|lenient| lenient.replace(true));
+    let result = parse();
+    LENIENT.with(|lenient| lenient.set(previous));
+    result
 }
 
-#[cfg(test)]
-mod test {
-    use super::*;
-
-    #[test]
-    fn test_canonicalize_str() {
-        let input = r#"<root><child attribute="value">Text</child></root>"#;
-        let expected = r#"<root><child attribute="value">Text</child></root>"#;
-
-        match canonicalize_xml(input) {
-            Ok(output) => assert_eq!(output, expected),
-            Err(e) => panic!("Error during canonicalization: {}", e),
-        }
-    }
-    
-    #[test]
-    fn test_left_pad() {
-        let input = "123";
-        let padded = left_pad(input, 5, '0');
-        assert_eq!(padded, "00123");
-
-        let input2 = "12345";
-        let padded2 = left_pad(input2, 5, '0');
-        assert_eq!(padded2, "12345");
+/// Whether the deserialization runs in lenient mode, see `lenient`
+pub(crate) fn is_lenient() -> bool {
+    LENIENT.with(|lenient| lenient.get())
+}
 
-        let input3 = "123456";
-        let padded3 = left_pad(input3, 5, '0');
-        assert_eq!(padded3, "123456");
+/// Value of a field required by the library but optional in the layout,
+/// empty when missing in lenient mode
+pub(crate) fn lenient_field<E: serde::de::Error>(
+    value: Option<String>,
+    field: &'static str,
+) -> Result<String, E> {
+    match value {
+        Some(value) => Ok(value),
+        None if is_lenient() => Ok(String::new()),
+        None => Err(E::missing_field(field)),
     }
 }
@@ -0,0 +1,5 @@
+//! Local validation of the documents, before their transmission
+//!
+//! The rules of the builder live in `models`, this module checks the final XML.
+
+pub mod xsd;
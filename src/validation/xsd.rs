@@ -0,0 +1,1375 @@
+//! Validation of the serialized XML against the schemas of the layout (PL_009)
+//!
+//! The XSDs under `schemas/` are embedded in the library and compiled on the
+//! first validation. Only the subset of XML Schema used by the layout is
+//! supported: sequences, choices, element references, wildcards, attributes,
+//! simple content and simple types restricted by pattern, enumeration, length
+//! and white space. Identity constraints (`unique`) are not checked.
+
+use crate::models::NFE_NAMESPACE;
+use crate::xml::{resolve_reference, with_namespace};
+use lazy_static::lazy_static;
+use quick_xml::{
+    Reader,
+    events::{BytesStart, Event},
+};
+use regex_lite::Regex;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+const XS_NAMESPACE: &str = "http://www.w3.org/2001/XMLSchema";
+
+/// Schema files shipped with the library, by their schemaLocation
+const FILES: [(&str, &str); 9] = [
+    ("nfe_v4.00.xsd", include_str!("../../schemas/nfe_v4.00.xsd")),
+    (
+        "leiauteNFe_v4.00.xsd",
+        include_str!("../../schemas/leiauteNFe_v4.00.xsd"),
+    ),
+    (
+        "tiposBasico_v4.00.xsd",
+        include_str!("../../schemas/tiposBasico_v4.00.xsd"),
+    ),
+    (
+        "DFeTiposBasicos_v1.00.xsd",
+        include_str!("../../schemas/DFeTiposBasicos_v1.00.xsd"),
+    ),
+    (
+        "xmldsig-core-schema_v1.01.xsd",
+        include_str!("../../schemas/xmldsig-core-schema_v1.01.xsd"),
+    ),
+    (
+        "envEvento_v1.00.xsd",
+        include_str!("../../schemas/envEvento_v1.00.xsd"),
+    ),
+    (
+        "leiauteEvento_v1.00.xsd",
+        include_str!("../../schemas/leiauteEvento_v1.00.xsd"),
+    ),
+    (
+        "inutNFe_v4.00.xsd",
+        include_str!("../../schemas/inutNFe_v4.00.xsd"),
+    ),
+    (
+        "leiauteInutNFe_v4.00.xsd",
+        include_str!("../../schemas/leiauteInutNFe_v4.00.xsd"),
+    ),
+];
+
+/// Schema of the validated document, by its root element
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Schema {
+    /// Signed NF-e or NFC-e (NFe)
+    NFe,
+    /// Batch of events (envEvento)
+    Event,
+    /// Disablement of a range of numbers (inutNFe)
+    Disablement,
+}
+
+impl Schema {
+    /// File of the schema, under `schemas/`
+    pub fn file(&self) -> &'static str {
+        match self {
+            Schema::NFe => "nfe_v4.00.xsd",
+            Schema::Event => "envEvento_v1.00.xsd",
+            Schema::Disablement => "inutNFe_v4.00.xsd",
+        }
+    }
+
+    fn root(&self) -> &'static str {
+        match self {
+            Schema::NFe => "NFe",
+            Schema::Event => "envEvento",
+            Schema::Disablement => "inutNFe",
+        }
+    }
+}
+
+/// Violation of the schema
+///
+/// line: Line of the violated element, starting at 1
+/// column: Column of the violated element, starting at 1
+/// element: Name of the violated element
+/// message: Description of the violation
+#[derive(Debug, Clone, PartialEq)]
+pub struct XsdError {
+    pub line: usize,
+    pub column: usize,
+    pub element: String,
+    pub message: String,
+}
+
+/// Namespace and local name
+type QName = (String, String);
+
+/// Element of a schema file, without its annotations
+struct Node {
+    name: String,
+    attributes: HashMap<String, String>,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn parse(xml: &str) -> Node {
+        let mut reader = Reader::from_str(xml);
+        reader.config_mut().expand_empty_elements = true;
+        let mut stack: Vec<Node> = Vec::new();
+
+        loop {
+            match reader.read_event().expect("Invalid embedded schema") {
+                Event::Start(e) => {
+                    let attributes = e
+                        .attributes()
+                        .map(|attribute| {
+                            let attribute = attribute.expect("Invalid embedded schema");
+                            (
+                                String::from_utf8_lossy(attribute.key.as_ref()).into_owned(),
+                                attribute
+                                    .unescape_value()
+                                    .expect("Invalid embedded schema")
+                                    .into_owned(),
+                            )
+                        })
+                        .collect();
+                    stack.push(Node {
+                        name: String::from_utf8_lossy(e.local_name().as_ref()).into_owned(),
+                        attributes,
+                        children: Vec::new(),
+                    });
+                }
+                Event::End(_) => {
+                    let node = stack.pop().expect("Invalid embedded schema");
+                    match stack.last_mut() {
+                        Some(_) if node.name == "annotation" => {}
+                        Some(parent) => parent.children.push(node),
+                        None => return node,
+                    }
+                }
+                Event::Eof => panic!("Invalid embedded schema"),
+                _ => {}
+            }
+        }
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(String::as_str)
+    }
+
+    fn child(&self, name: &str) -> Option<&Node> {
+        self.children.iter().find(|child| child.name == name)
+    }
+}
+
+#[derive(Clone)]
+enum TypeRef {
+    Named(QName),
+    Simple(Arc<SimpleType>),
+    Complex(Arc<ComplexType>),
+    /// Element without a type, accepting any content
+    Any,
+}
+
+struct SimpleType {
+    base: TypeRef,
+    facets: Facets,
+}
+
+#[derive(Default)]
+struct Facets {
+    /// Source and compiled alternatives of the patterns of the restriction
+    pattern: Option<(String, Regex)>,
+    enumeration: Vec<String>,
+    length: Option<usize>,
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    collapse: bool,
+}
+
+struct ComplexType {
+    attributes: Vec<AttributeDecl>,
+    any_attribute: bool,
+    content: Content,
+}
+
+enum Content {
+    Empty,
+    Simple(TypeRef),
+    Elements(Particle),
+}
+
+struct AttributeDecl {
+    name: String,
+    r#type: TypeRef,
+    required: bool,
+    fixed: Option<String>,
+}
+
+struct ElementDecl {
+    name: QName,
+    r#type: TypeRef,
+}
+
+struct Particle {
+    term: Term,
+    min: usize,
+    /// `None` when unbounded
+    max: Option<usize>,
+}
+
+enum Term {
+    Element(Arc<ElementDecl>),
+    Reference(QName),
+    Sequence(Vec<Particle>),
+    Choice(Vec<Particle>),
+    Any,
+}
+
+/// Namespaces in scope of a schema file
+struct File {
+    target: String,
+    prefixes: HashMap<String, String>,
+}
+
+impl File {
+    fn new(root: &Node, namespace: Option<&str>) -> Self {
+        // Schemas without a target namespace take the one of the includer
+        let target = root
+            .attribute("targetNamespace")
+            .or(namespace)
+            .unwrap_or_default()
+            .to_string();
+        let prefixes = root
+            .attributes
+            .iter()
+            .filter_map(|(key, value)| {
+                let prefix = match key.as_str() {
+                    "xmlns" => "",
+                    key => key.strip_prefix("xmlns:")?,
+                };
+                Some((prefix.to_string(), value.clone()))
+            })
+            .collect();
+        Self { target, prefixes }
+    }
+
+    fn resolve(&self, name: &str) -> QName {
+        let (prefix, local) = name.split_once(':').unwrap_or(("", name));
+        let namespace = match self.prefixes.get(prefix) {
+            Some(namespace) => namespace.clone(),
+            None if prefix.is_empty() => self.target.clone(),
+            None => String::new(),
+        };
+        (namespace, local.to_string())
+    }
+
+    fn element(&self, node: &Node) -> ElementDecl {
+        let name = (
+            self.target.clone(),
+            node.attribute("name").unwrap_or_default().to_string(),
+        );
+        let r#type = if let Some(r#type) = node.attribute("type") {
+            TypeRef::Named(self.resolve(r#type))
+        } else if let Some(complex) = node.child("complexType") {
+            TypeRef::Complex(Arc::new(self.complex_type(complex)))
+        } else if let Some(simple) = node.child("simpleType") {
+            TypeRef::Simple(Arc::new(self.simple_type(simple)))
+        } else {
+            TypeRef::Any
+        };
+        ElementDecl { name, r#type }
+    }
+
+    fn particle(&self, node: &Node) -> Option<Particle> {
+        let term = match node.name.as_str() {
+            "element" => match node.attribute("ref") {
+                Some(reference) => Term::Reference(self.resolve(reference)),
+                None => Term::Element(Arc::new(self.element(node))),
+            },
+            "sequence" => Term::Sequence(self.particles(node)),
+            "choice" => Term::Choice(self.particles(node)),
+            "any" => Term::Any,
+            _ => return None,
+        };
+        let min = node
+            .attribute("minOccurs")
+            .map_or(1, |min| min.parse().expect("Invalid minOccurs"));
+        let max = match node.attribute("maxOccurs") {
+            Some("unbounded") => None,
+            Some(max) => Some(max.parse().expect("Invalid maxOccurs")),
+            None => Some(1),
+        };
+        Some(Particle { term, min, max })
+    }
+
+    fn particles(&self, node: &Node) -> Vec<Particle> {
+        node.children
+            .iter()
+            .filter_map(|child| self.particle(child))
+            .collect()
+    }
+
+    fn complex_type(&self, node: &Node) -> ComplexType {
+        let mut complex = ComplexType {
+            attributes: Vec::new(),
+            any_attribute: false,
+            content: Content::Empty,
+        };
+        for child in &node.children {
+            match child.name.as_str() {
+                "sequence" | "choice" => {
+                    if let Some(particle) = self.particle(child) {
+                        complex.content = Content::Elements(particle);
+                    }
+                }
+                "attribute" => complex.attributes.push(self.attribute(child)),
+                "anyAttribute" => complex.any_attribute = true,
+                "simpleContent" => {
+                    let Some(derivation) = child
+                        .children
+                        .iter()
+                        .find(|c| c.name == "extension" || c.name == "restriction")
+                    else {
+                        continue;
+                    };
+                    let base = derivation.attribute("base").unwrap_or("xs:string");
+                    complex.content = Content::Simple(TypeRef::Named(self.resolve(base)));
+                    for attribute in &derivation.children {
+                        if attribute.name == "attribute" {
+                            complex.attributes.push(self.attribute(attribute));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        complex
+    }
+
+    fn attribute(&self, node: &Node) -> AttributeDecl {
+        let r#type = if let Some(r#type) = node.attribute("type") {
+            TypeRef::Named(self.resolve(r#type))
+        } else if let Some(simple) = node.child("simpleType") {
+            TypeRef::Simple(Arc::new(self.simple_type(simple)))
+        } else {
+            TypeRef::Named((XS_NAMESPACE.to_string(), "string".to_string()))
+        };
+        AttributeDecl {
+            name: node.attribute("name").unwrap_or_default().to_string(),
+            r#type,
+            required: node.attribute("use") == Some("required"),
+            fixed: node.attribute("fixed").map(str::to_string),
+        }
+    }
+
+    fn simple_type(&self, node: &Node) -> SimpleType {
+        let Some(restriction) = node.child("restriction") else {
+            return SimpleType {
+                base: TypeRef::Named((XS_NAMESPACE.to_string(), "string".to_string())),
+                facets: Facets::default(),
+            };
+        };
+        let base = match restriction.attribute("base") {
+            Some(base) => TypeRef::Named(self.resolve(base)),
+            None => restriction
+                .child("simpleType")
+                .map_or(TypeRef::Any, |simple| {
+                    TypeRef::Simple(Arc::new(self.simple_type(simple)))
+                }),
+        };
+
+        let mut facets = Facets::default();
+        let mut patterns = Vec::new();
+        for facet in &restriction.children {
+            let value = facet.attribute("value").unwrap_or_default();
+            let size = || value.parse().ok();
+            match facet.name.as_str() {
+                "pattern" => patterns.push(value.to_string()),
+                "enumeration" => facets.enumeration.push(value.to_string()),
+                "length" => facets.length = size(),
+                "minLength" => facets.min_length = size(),
+                "maxLength" => facets.max_length = size(),
+                "whiteSpace" => facets.collapse = value == "collapse",
+                _ => {}
+            }
+        }
+        if !patterns.is_empty() {
+            // Patterns of the same restriction are alternatives, anchored at both ends
+            let alternatives = patterns
+                .iter()
+                .map(|pattern| format!("(?:{})", pattern))
+                .collect::<Vec<_>>()
+                .join("|");
+            let regex = Regex::new(&format!("^(?:{})$", alternatives))
+                .unwrap_or_else(|e| panic!("Invalid pattern {}: {}", alternatives, e));
+            facets.pattern = Some((patterns.join(" | "), regex));
+        }
+        SimpleType { base, facets }
+    }
+}
+
+/// Global declarations of every schema shipped with the library
+#[derive(Default)]
+struct Schemas {
+    elements: HashMap<QName, Arc<ElementDecl>>,
+    types: HashMap<QName, TypeRef>,
+}
+
+lazy_static! {
+    static ref SCHEMAS: Schemas = Schemas::load();
+}
+
+impl Schemas {
+    fn load() -> Self {
+        let mut schemas = Schemas::default();
+        let mut loaded = HashSet::new();
+        for schema in [Schema::NFe, Schema::Event, Schema::Disablement] {
+            schemas.include(schema.file(), None, &mut loaded);
+        }
+        schemas
+    }
+
+    fn include(&mut self, location: &str, namespace: Option<&str>, loaded: &mut HashSet<String>) {
+        if !loaded.insert(location.to_string()) {
+            return;
+        }
+        let (_, content) = FILES
+            .iter()
+            .find(|(file, _)| *file == location)
+            .unwrap_or_else(|| panic!("Missing schema {}", location));
+        let root = Node::parse(content);
+        let file = File::new(&root, namespace);
+
+        for child in &root.children {
+            let name = || {
+                (
+                    file.target.clone(),
+                    child.attribute("name").unwrap_or_default().to_string(),
+                )
+            };
+            match child.name.as_str() {
+                "include" | "import" => {
+                    let namespace = (child.name == "include").then_some(file.target.as_str());
+                    if let Some(location) = child.attribute("schemaLocation") {
+                        self.include(location, namespace, loaded);
+                    }
+                }
+                "element" => {
+                    let element = file.element(child);
+                    self.elements
+                        .insert(element.name.clone(), Arc::new(element));
+                }
+                "complexType" => {
+                    let complex = TypeRef::Complex(Arc::new(file.complex_type(child)));
+                    self.types.insert(name(), complex);
+                }
+                "simpleType" => {
+                    let simple = TypeRef::Simple(Arc::new(file.simple_type(child)));
+                    self.types.insert(name(), simple);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Definition of a named type, builtin types are kept as names
+    fn definition(&self, r#type: &TypeRef) -> Result<TypeRef, String> {
+        match r#type {
+            TypeRef::Named((namespace, _)) if namespace == XS_NAMESPACE => Ok(r#type.clone()),
+            TypeRef::Named(name) => self
+                .types
+                .get(name)
+                .cloned()
+                .ok_or_else(|| format!("Unknown type `{}`", name.1)),
+            _ => Ok(r#type.clone()),
+        }
+    }
+
+    fn check_element(&self, node: &Instance, decl: &ElementDecl) -> Result<(), XsdError> {
+        match self.definition(&decl.r#type).map_err(|e| node.error(e))? {
+            TypeRef::Any => Ok(()),
+            TypeRef::Complex(complex) => self.check_complex(node, &complex),
+            simple => {
+                if let Some((name, _)) = node.attributes.first() {
+                    return Err(node.error(format!("Unexpected attribute `{}`", name)));
+                }
+                if let Some(child) = node.children.first() {
+                    return Err(child.error(format!("Unexpected element `{}`", child.name.1)));
+                }
+                self.check_value(&simple, &node.text)
+                    .map_err(|e| node.error(e))
+            }
+        }
+    }
+
+    fn check_complex(&self, node: &Instance, complex: &ComplexType) -> Result<(), XsdError> {
+        for attribute in &complex.attributes {
+            match node.attribute(&attribute.name) {
+                None if attribute.required => {
+                    return Err(node.error(format!("Missing attribute `{}`", attribute.name)));
+                }
+                None => {}
+                Some(value) => {
+                    if let Some(fixed) = &attribute.fixed
+                        && value != fixed
+                    {
+                        return Err(node.error(format!(
+                            "Attribute `{}` must be `{}`",
+                            attribute.name, fixed
+                        )));
+                    }
+                    self.check_value(&attribute.r#type, value).map_err(|e| {
+                        node.error(format!("Attribute `{}`: {}", attribute.name, e))
+                    })?;
+                }
+            }
+        }
+        if !complex.any_attribute
+            && let Some((name, _)) = node
+                .attributes
+                .iter()
+                .find(|(name, _)| !complex.attributes.iter().any(|a| &a.name == name))
+        {
+            return Err(node.error(format!("Unexpected attribute `{}`", name)));
+        }
+
+        match &complex.content {
+            Content::Empty | Content::Simple(_) if !node.children.is_empty() => {
+                let child = &node.children[0];
+                Err(child.error(format!("Unexpected element `{}`", child.name.1)))
+            }
+            Content::Empty if !node.text.trim().is_empty() => {
+                Err(node.error("Unexpected text".to_string()))
+            }
+            Content::Empty => Ok(()),
+            Content::Simple(r#type) => self
+                .check_value(r#type, &node.text)
+                .map_err(|e| node.error(e)),
+            Content::Elements(_) if !node.text.trim().is_empty() => {
+                Err(node.error(format!("Unexpected text `{}`", node.text.trim())))
+            }
+            Content::Elements(particle) => self.check_children(node, particle),
+        }
+    }
+
+    fn check_children(&self, node: &Instance, particle: &Particle) -> Result<(), XsdError> {
+        let mut progress = Progress::default();
+        let start = State {
+            position: 0,
+            targets: Vec::new(),
+        };
+        let states = self.apply(particle, &node.children, vec![start], &mut progress);
+        let Some(state) = states
+            .into_iter()
+            .find(|state| state.position == node.children.len())
+        else {
+            let expected = progress
+                .expected
+                .iter()
+                .map(|name| format!("`{}`", name))
+                .collect::<Vec<_>>()
+                .join(", ");
+            return Err(match node.children.get(progress.furthest) {
+                Some(child) if expected.is_empty() => {
+                    child.error(format!("Unexpected element `{}`", child.name.1))
+                }
+                Some(child) => child.error(format!(
+                    "Unexpected element `{}`, expected {}",
+                    child.name.1, expected
+                )),
+                None => node.error(format!("Missing element, expected {}", expected)),
+            });
+        };
+
+        for (child, target) in node.children.iter().zip(state.targets) {
+            if let Some(decl) = target {
+                self.check_element(child, &decl)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// States reached after matching the particle from each of the states
+    fn apply(
+        &self,
+        particle: &Particle,
+        children: &[Instance],
+        states: Vec<State>,
+        progress: &mut Progress,
+    ) -> Vec<State> {
+        // Every useful repetition consumes a child, so unbounded particles stop there
+        let limit = particle
+            .max
+            .unwrap_or(usize::MAX)
+            .min(particle.min + children.len() + 1);
+        let mut current = states;
+        let mut reached = Vec::new();
+        for count in 0..=limit {
+            if count >= particle.min {
+                reached.extend(current.iter().cloned());
+            }
+            if count == limit || current.is_empty() {
+                break;
+            }
+            current = deduplicate(self.term(&particle.term, children, &current, progress));
+        }
+        deduplicate(reached)
+    }
+
+    fn term(
+        &self,
+        term: &Term,
+        children: &[Instance],
+        states: &[State],
+        progress: &mut Progress,
+    ) -> Vec<State> {
+        match term {
+            Term::Element(decl) => states
+                .iter()
+                .filter_map(|state| state.step(decl, children, progress))
+                .collect(),
+            Term::Reference(name) => match self.elements.get(name) {
+                Some(decl) => states
+                    .iter()
+                    .filter_map(|state| state.step(decl, children, progress))
+                    .collect(),
+                None => Vec::new(),
+            },
+            Term::Any => states
+                .iter()
+                .filter(|state| state.position < children.len())
+                .map(|state| state.advance(None, progress))
+                .collect(),
+            Term::Sequence(particles) => {
+                let mut current = states.to_vec();
+                for particle in particles {
+                    current = self.apply(particle, children, current, progress);
+                }
+                current
+            }
+            Term::Choice(particles) => particles
+                .iter()
+                .flat_map(|particle| self.apply(particle, children, states.to_vec(), progress))
+                .collect(),
+        }
+    }
+
+    fn check_value(&self, r#type: &TypeRef, value: &str) -> Result<(), String> {
+        match self.definition(r#type)? {
+            TypeRef::Simple(simple) => {
+                self.check_value(&simple.base, value)?;
+                simple.facets.check(value)
+            }
+            // Builtin types are only constrained by the facets of the layout
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Facets {
+    fn check(&self, value: &str) -> Result<(), String> {
+        let value = if self.collapse {
+            Cow::Owned(value.split_whitespace().collect::<Vec<_>>().join(" "))
+        } else {
+            Cow::Borrowed(value)
+        };
+        let length = value.chars().count();
+        if let Some(expected) = self.length
+            && length != expected
+        {
+            return Err(format!("`{}` must have {} characters", value, expected));
+        }
+        if let Some(min) = self.min_length
+            && length < min
+        {
+            return Err(format!("`{}` is shorter than {} characters", value, min));
+        }
+        if let Some(max) = self.max_length
+            && length > max
+        {
+            return Err(format!("`{}` is longer than {} characters", value, max));
+        }
+        if !self.enumeration.is_empty() && !self.enumeration.iter().any(|e| *e == value) {
+            return Err(format!("`{}` is not an allowed value", value));
+        }
+        if let Some((source, regex)) = &self.pattern
+            && !regex.is_match(&value)
+        {
+            return Err(format!(
+                "`{}` does not match the pattern `{}`",
+                value, source
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Partial match of the children of an element
+#[derive(Clone)]
+struct State {
+    position: usize,
+    /// Declaration matched by each child, `None` for wildcards
+    targets: Vec<Option<Arc<ElementDecl>>>,
+}
+
+impl State {
+    fn step(
+        &self,
+        decl: &Arc<ElementDecl>,
+        children: &[Instance],
+        progress: &mut Progress,
+    ) -> Option<State> {
+        match children.get(self.position) {
+            Some(child) if child.name == decl.name => {
+                Some(self.advance(Some(decl.clone()), progress))
+            }
+            _ => {
+                progress.expect(self.position, &decl.name.1);
+                None
+            }
+        }
+    }
+
+    fn advance(&self, target: Option<Arc<ElementDecl>>, progress: &mut Progress) -> State {
+        let mut targets = self.targets.clone();
+        targets.push(target);
+        progress.reach(self.position + 1);
+        State {
+            position: self.position + 1,
+            targets,
+        }
+    }
+}
+
+fn deduplicate(states: Vec<State>) -> Vec<State> {
+    let mut positions = HashSet::new();
+    states
+        .into_iter()
+        .filter(|state| positions.insert(state.position))
+        .collect()
+}
+
+/// Furthest child reached while matching, with the elements expected there
+#[derive(Default)]
+struct Progress {
+    furthest: usize,
+    expected: Vec<String>,
+}
+
+impl Progress {
+    fn reach(&mut self, position: usize) {
+        if position > self.furthest {
+            self.furthest = position;
+            self.expected.clear();
+        }
+    }
+
+    fn expect(&mut self, position: usize, name: &str) {
+        self.reach(position);
+        if position == self.furthest && !self.expected.iter().any(|e| e == name) {
+            self.expected.push(name.to_string());
+        }
+    }
+}
+
+/// Element of the validated document
+struct Instance {
+    name: QName,
+    line: usize,
+    column: usize,
+    attributes: Vec<(String, String)>,
+    children: Vec<Instance>,
+    text: String,
+}
+
+impl Instance {
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn error(&self, message: String) -> XsdError {
+        XsdError {
+            line: self.line,
+            column: self.column,
+            element: self.name.1.clone(),
+            message,
+        }
+    }
+}
+
+/// Line and column of the offsets of the document, computed incrementally
+struct Cursor<'a> {
+    xml: &'a str,
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Cursor<'_> {
+    fn advance(&mut self, offset: usize) -> (usize, usize) {
+        for c in self.xml[self.offset..offset].chars() {
+            if c == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+        }
+        self.offset = offset;
+        (self.line, self.column)
+    }
+}
+
+fn instance(
+    e: &BytesStart,
+    scopes: &mut Vec<HashMap<String, String>>,
+    (line, column): (usize, usize),
+) -> Result<Instance, String> {
+    let mut scope = scopes.last().cloned().unwrap_or_default();
+    let mut attributes = Vec::new();
+    for attribute in e.attributes() {
+        let attribute = attribute.map_err(|e| e.to_string())?;
+        let key = String::from_utf8_lossy(attribute.key.as_ref()).into_owned();
+        let value = attribute
+            .unescape_value()
+            .map_err(|e| e.to_string())?
+            .into_owned();
+        match key.as_str() {
+            "xmlns" => {
+                scope.insert(String::new(), value);
+            }
+            _ => match key.strip_prefix("xmlns:") {
+                Some(prefix) => {
+                    scope.insert(prefix.to_string(), value);
+                }
+                None => attributes.push((key, value)),
+            },
+        }
+    }
+
+    let name = String::from_utf8_lossy(e.name().as_ref()).into_owned();
+    let (prefix, local) = name.split_once(':').unwrap_or(("", &name));
+    let namespace = scope.get(prefix).cloned().unwrap_or_default();
+    let instance = Instance {
+        name: (namespace, local.to_string()),
+        line,
+        column,
+        attributes,
+        children: Vec::new(),
+        text: String::new(),
+    };
+    scopes.push(scope);
+    Ok(instance)
+}
+
+fn parse(xml: &str) -> Result<Instance, XsdError> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().expand_empty_elements = true;
+    let mut cursor = Cursor {
+        xml,
+        offset: 0,
+        line: 1,
+        column: 1,
+    };
+    let mut scopes = Vec::new();
+    let mut stack: Vec<Instance> = Vec::new();
+
+    loop {
+        let position = reader.buffer_position() as usize;
+        let malformed = |message: String, cursor: &mut Cursor| {
+            let (line, column) = cursor.advance(position);
+            XsdError {
+                line,
+                column,
+                element: String::new(),
+                message,
+            }
+        };
+        let event = match reader.read_event() {
+            Ok(event) => event,
+            Err(e) => return Err(malformed(e.to_string(), &mut cursor)),
+        };
+        match event {
+            Event::Start(e) => {
+                let start = position + xml[position..].find('<').unwrap_or(0);
+                let node = instance(&e, &mut scopes, cursor.advance(start))
+                    .map_err(|message| malformed(message, &mut cursor))?;
+                stack.push(node);
+            }
+            Event::End(_) => {
+                scopes.pop();
+                let node = stack.pop().expect("Unbalanced document");
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(node),
+                    None => return Ok(node),
+                }
+            }
+            Event::Text(e) => {
+                if let Some(node) = stack.last_mut() {
+                    let text = e
+                        .decode()
+                        .map_err(|e| malformed(e.to_string(), &mut cursor))?;
+                    node.text.push_str(&text);
+                }
+            }
+            Event::CData(e) => {
+                if let Some(node) = stack.last_mut() {
+                    let text = e
+                        .decode()
+                        .map_err(|e| malformed(e.to_string(), &mut cursor))?;
+                    node.text.push_str(&text);
+                }
+            }
+            Event::GeneralRef(e) => {
+                let name = e
+                    .decode()
+                    .map_err(|e| malformed(e.to_string(), &mut cursor))?;
+                let resolved = resolve_reference(&name).ok_or_else(|| {
+                    malformed(format!("Unknown entity reference: &{};", name), &mut cursor)
+                })?;
+                if let Some(node) = stack.last_mut() {
+                    node.text.push(resolved);
+                }
+            }
+            Event::Eof => return Err(malformed("Missing root element".to_string(), &mut cursor)),
+            _ => {}
+        }
+    }
+}
+
+/// Validates the document against its schema, stopping at the first violation
+pub fn validate(schema: Schema, xml: &str) -> Result<(), XsdError> {
+    let root = parse(xml)?;
+    let name = (NFE_NAMESPACE.to_string(), schema.root().to_string());
+    match SCHEMAS.elements.get(&name) {
+        Some(decl) if root.name == name => SCHEMAS.check_element(&root, decl),
+        _ => Err(root.error(format!(
+            "Expected the root element `{}` of {}",
+            schema.root(),
+            schema.file()
+        ))),
+    }
+}
+
+/// Checks the order of the elements of the document against the sequences
+/// of its schema, stopping at the first element out of place
+///
+/// Unlike `validate`, the values, the attributes and the occurrences are not
+/// checked: only the relative order of the elements of each group. Elements
+/// not declared in their group are reported as unexpected, unless the group
+/// accepts any element (xs:any), where they are skipped along with their
+/// content.
+pub fn check_order(schema: Schema, xml: &str) -> Result<(), XsdError> {
+    let root = parse(xml)?;
+    let name = (NFE_NAMESPACE.to_string(), schema.root().to_string());
+    match SCHEMAS.elements.get(&name) {
+        Some(decl) if root.name == name => SCHEMAS.check_element_order(&root, decl),
+        _ => Err(root.error(format!(
+            "Expected the root element `{}` of {}",
+            schema.root(),
+            schema.file()
+        ))),
+    }
+}
+
+/// Checks the order of the elements of a fragment of the document, as a
+/// group serialized alone, see `check_order`
+///
+/// `path` locates the root of the fragment from the root of the schema, as
+/// "NFe/infNFe/det/prod", whatever the name of the root of the fragment.
+/// Fragments without a namespace are read in the namespace of the NF-e.
+pub fn check_fragment_order(schema: Schema, path: &str, xml: &str) -> Result<(), XsdError> {
+    let root = parse(&with_namespace(xml, NFE_NAMESPACE))?;
+    let unknown = || root.error(format!("Unknown path `{}` in {}", path, schema.file()));
+    let mut names = path.split('/');
+    if names.next() != Some(schema.root()) {
+        return Err(unknown());
+    }
+    let name = (NFE_NAMESPACE.to_string(), schema.root().to_string());
+    let mut decl = SCHEMAS.elements.get(&name).cloned().ok_or_else(unknown)?;
+    for name in names {
+        decl = SCHEMAS.child(&decl, name).ok_or_else(unknown)?;
+    }
+    SCHEMAS.check_element_order(&root, &decl)
+}
+
+impl Schemas {
+    fn check_element_order(&self, node: &Instance, decl: &ElementDecl) -> Result<(), XsdError> {
+        let TypeRef::Complex(complex) = self.definition(&decl.r#type).map_err(|e| node.error(e))?
+        else {
+            return Ok(());
+        };
+        let Content::Elements(particle) = &complex.content else {
+            return Ok(());
+        };
+
+        let mut ranks = Vec::new();
+        self.rank(particle, &mut 0, &mut ranks);
+        let mut previous: Option<(usize, &Instance)> = None;
+        for child in &node.children {
+            let Some((_, rank, decl)) = ranks.iter().find(|(name, _, _)| *name == child.name)
+            else {
+                if accepts_any(particle) {
+                    continue;
+                }
+                return Err(child.error(format!("Unexpected element `{}`", child.name.1)));
+            };
+            if let Some((last, before)) = previous
+                && *rank < last
+            {
+                return Err(child.error(format!(
+                    "Element `{}` must come before `{}`",
+                    child.name.1, before.name.1
+                )));
+            }
+            previous = Some((*rank, child));
+            self.check_element_order(child, decl)?;
+        }
+        Ok(())
+    }
+
+    /// Declaration of the child element `name` of the element `decl`
+    fn child(&self, decl: &ElementDecl, name: &str) -> Option<Arc<ElementDecl>> {
+        let TypeRef::Complex(complex) = self.definition(&decl.r#type).ok()? else {
+            return None;
+        };
+        let Content::Elements(particle) = &complex.content else {
+            return None;
+        };
+        let mut ranks = Vec::new();
+        self.rank(particle, &mut 0, &mut ranks);
+        ranks
+            .into_iter()
+            .find(|(child, _, _)| child.1 == name)
+            .map(|(_, _, decl)| decl)
+    }
+
+    /// Position of each element of the particle in its sequence, shared by
+    /// the alternatives of a choice
+    fn rank(
+        &self,
+        particle: &Particle,
+        rank: &mut usize,
+        ranks: &mut Vec<(QName, usize, Arc<ElementDecl>)>,
+    ) {
+        match &particle.term {
+            Term::Element(decl) => {
+                ranks.push((decl.name.clone(), *rank, decl.clone()));
+                *rank += 1;
+            }
+            Term::Reference(name) => {
+                if let Some(decl) = self.elements.get(name) {
+                    ranks.push((decl.name.clone(), *rank, decl.clone()));
+                }
+                *rank += 1;
+            }
+            Term::Sequence(particles) => {
+                for particle in particles {
+                    self.rank(particle, rank, ranks);
+                }
+            }
+            Term::Choice(particles) => {
+                let start = *rank;
+                for particle in particles {
+                    let mut alternative = start;
+                    self.rank(particle, &mut alternative, ranks);
+                    *rank = (*rank).max(alternative);
+                }
+            }
+            Term::Any => {}
+        }
+    }
+}
+
+/// Whether the particle accepts elements of any name (xs:any)
+fn accepts_any(particle: &Particle) -> bool {
+    match &particle.term {
+        Term::Any => true,
+        Term::Sequence(particles) | Term::Choice(particles) => particles.iter().any(accepts_any),
+        Term::Element(_) | Term::Reference(_) => false,
+    }
+}
+
+#[cfg(all(test, feature = "sign"))]
+mod tests {
+    use super::*;
+    use crate::access_key::tests::{ACCESS_KEY, setup_access_key};
+    use crate::disablement::{Disablement, DisablementInfo};
+    use crate::enums::{CNPJ, Environment, Model, PersonDocument};
+    use crate::events::tests::setup_date;
+    use crate::events::{CancelEvent, Event, EventBatch};
+    use crate::sign::tests::setup_certificate;
+    use crate::states::State;
+
+    fn setup_event_batch_xml() -> String {
+        let info = CancelEvent::new(
+            &setup_access_key(),
+            "135250000000001",
+            "Cancelamento por erro na digitacao",
+        )
+        .expect("Failed to create cancel event")
+        .info(
+            Environment::Homologation,
+            PersonDocument::CNPJ(CNPJ("12345678000195".to_string())),
+            setup_date(),
+        );
+        let batch = EventBatch {
+            id: 1,
+            events: vec![Event::with_certificate(info, &setup_certificate()).unwrap()],
+        };
+        quick_xml::se::to_string_with_root("envEvento", &batch).expect("Failed to serialize")
+    }
+
+    #[test]
+    fn test_valid_event_batch() {
+        assert_eq!(validate(Schema::Event, &setup_event_batch_xml()), Ok(()));
+    }
+
+    #[test]
+    fn test_invalid_value() {
+        let xml = setup_event_batch_xml()
+            .replace("><", ">\n<")
+            .replace(&format!(">{}<", ACCESS_KEY), ">3525<");
+        let line = xml[..xml.find("<chNFe>").unwrap()].lines().count() + 1;
+        assert_eq!(
+            validate(Schema::Event, &xml),
+            Err(XsdError {
+                line,
+                column: 1,
+                element: "chNFe".to_string(),
+                message: "`3525` does not match the pattern `[0-9]{44}`".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_unexpected_element() {
+        let xml = setup_event_batch_xml().replace("<tpAmb>2</tpAmb>", "");
+        let error = validate(Schema::Event, &xml).unwrap_err();
+        assert_eq!(error.element, "CNPJ");
+        assert_eq!(error.column, xml.find("<CNPJ>").unwrap() + 1);
+        assert_eq!(error.message, "Unexpected element `CNPJ`, expected `tpAmb`");
+    }
+
+    #[test]
+    fn test_valid_nfe() {
+        use crate::models::NFe;
+        use crate::testkit::{sample_certificate, sample_nfce, sample_nfe_interstate};
+
+        let xml = quick_xml::se::to_string(&crate::models::tests::setup_nfe()).unwrap();
+        assert_eq!(validate(Schema::NFe, &xml), Ok(()));
+
+        for info in [sample_nfce(), sample_nfe_interstate()] {
+            let nfe = NFe::with_certificate(info, &sample_certificate()).unwrap();
+            let xml = quick_xml::se::to_string(&nfe).unwrap();
+            assert_eq!(validate(Schema::NFe, &xml), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_invalid_root() {
+        let error = validate(Schema::NFe, &setup_event_batch_xml()).unwrap_err();
+        assert_eq!(error.element, "envEvento");
+        assert_eq!((error.line, error.column), (1, 1));
+    }
+
+    #[test]
+    fn test_valid_disablement() {
+        let info = DisablementInfo::new(
+            Environment::Homologation,
+            State::SaoPaulo,
+            25,
+            CNPJ("12345678000195".to_string()),
+            Model::NFe,
+            1,
+            10,
+            15,
+            "Numeracao pulada por falha no sistema",
+        )
+        .expect("Failed to create disablement");
+        let disablement = Disablement::with_certificate(info, &setup_certificate()).unwrap();
+        let xml = quick_xml::se::to_string_with_root("inutNFe", &disablement).unwrap();
+        assert_eq!(validate(Schema::Disablement, &xml), Ok(()));
+    }
+
+    #[test]
+    fn test_fixtures_order() {
+        let fixtures = [
+            ("NFe", "nfe.xml"),
+            ("NFe/infNFe", "info.xml"),
+            ("NFe/infNFe", "info_authorized.xml"),
+            ("NFe/infNFe/ide", "identification.xml"),
+            ("NFe/infNFe/ide", "identification_contingency.xml"),
+            ("NFe/infNFe/emit", "issuer.xml"),
+            ("NFe/infNFe/emit", "issuer_services.xml"),
+            ("NFe/infNFe/emit/enderEmit", "address.xml"),
+            ("NFe/infNFe/dest", "recipient.xml"),
+            ("NFe/infNFe/dest", "recipient_consumer.xml"),
+            ("NFe/infNFe/entrega", "location/entrega.xml"),
+            ("NFe/infNFe/autXML", "authorized.xml"),
+            ("NFe/infNFe/det", "detail.xml"),
+            ("NFe/infNFe/det", "detail_additional_info.xml"),
+            ("NFe/infNFe/det/prod", "item.xml"),
+            ("NFe/infNFe/det/prod", "item_fiscal.xml"),
+            ("NFe/infNFe/det/prod", "item_foreign_trade.xml"),
+            ("NFe/infNFe/det/prod", "item_fuel.xml"),
+            ("NFe/infNFe/det/prod", "item_medicine.xml"),
+            ("NFe/infNFe/det/prod/DI", "foreign_trade/di.xml"),
+            (
+                "NFe/infNFe/det/prod/detExport",
+                "foreign_trade/det_export.xml",
+            ),
+            ("NFe/infNFe/det/prod/comb", "product/comb.xml"),
+            ("NFe/infNFe/det/prod/comb", "product/comb_cide.xml"),
+            ("NFe/infNFe/det/prod/med", "product/med.xml"),
+            ("NFe/infNFe/det/prod/rastro", "product/rastro.xml"),
+            ("NFe/infNFe/det/prod/veicProd", "product/veic_prod.xml"),
+            ("NFe/infNFe/det/imposto", "tax.xml"),
+            ("NFe/infNFe/det/imposto", "tax_contributions.xml"),
+            ("NFe/infNFe/det/imposto/ICMS", "enums/icms.xml"),
+            ("NFe/infNFe/det/imposto/ICMS", "enums/icms00.xml"),
+            ("NFe/infNFe/det/imposto/ICMS/ICMS00", "icms/icms00.xml"),
+            ("NFe/infNFe/det/imposto/ICMS/ICMS10", "icms/icms10.xml"),
+            ("NFe/infNFe/det/imposto/ICMS/ICMS20", "icms/icms20.xml"),
+            ("NFe/infNFe/det/imposto/ICMS/ICMS30", "icms/icms30.xml"),
+            ("NFe/infNFe/det/imposto/ICMS/ICMS40", "icms/icms40.xml"),
+            ("NFe/infNFe/det/imposto/ICMS/ICMS51", "icms/icms51.xml"),
+            ("NFe/infNFe/det/imposto/ICMS/ICMS60", "icms/icms60.xml"),
+            ("NFe/infNFe/det/imposto/ICMS/ICMS70", "icms/icms70.xml"),
+            ("NFe/infNFe/det/imposto/ICMS/ICMS90", "icms/icms90.xml"),
+            ("NFe/infNFe/det/imposto/ICMS/ICMSPart", "icms/icms_part.xml"),
+            ("NFe/infNFe/det/imposto/ICMS/ICMSST", "icms/icms_st.xml"),
+            (
+                "NFe/infNFe/det/imposto/ICMS/ICMSSN101",
+                "icms/icmssn101.xml",
+            ),
+            (
+                "NFe/infNFe/det/imposto/ICMS/ICMSSN201",
+                "icms/icmssn201.xml",
+            ),
+            (
+                "NFe/infNFe/det/imposto/ICMS/ICMSSN202",
+                "icms/icmssn202.xml",
+            ),
+            (
+                "NFe/infNFe/det/imposto/ICMS/ICMSSN500",
+                "icms/icmssn500.xml",
+            ),
+            (
+                "NFe/infNFe/det/imposto/ICMS/ICMSSN900",
+                "icms/icmssn900.xml",
+            ),
+            ("NFe/infNFe/det/imposto/IPI", "ipi/ipi_nt.xml"),
+            ("NFe/infNFe/det/imposto/IPI", "ipi/ipi_trib.xml"),
+            ("NFe/infNFe/det/imposto/IPI", "ipi/ipi_unit.xml"),
+            ("NFe/infNFe/det/imposto/II", "ipi/ii.xml"),
+            ("NFe/infNFe/det/imposto/ISSQN", "issqn/issqn.xml"),
+            ("NFe/infNFe/det/imposto/PIS/PISAliq", "pis/pis_aliq.xml"),
+            ("NFe/infNFe/det/imposto/PIS/PISNT", "pis/pis_nt.xml"),
+            ("NFe/infNFe/det/imposto/PIS/PISOutr", "pis/pis_outr.xml"),
+            ("NFe/infNFe/det/imposto/PIS/PISQtde", "pis/pis_qtde.xml"),
+            ("NFe/infNFe/det/imposto/PISST", "pis/pis_st.xml"),
+            (
+                "NFe/infNFe/det/imposto/COFINS/COFINSAliq",
+                "cofins/cofins_aliq.xml",
+            ),
+            (
+                "NFe/infNFe/det/imposto/COFINS/COFINSNT",
+                "cofins/cofins_nt.xml",
+            ),
+            (
+                "NFe/infNFe/det/imposto/COFINS/COFINSOutr",
+                "cofins/cofins_outr.xml",
+            ),
+            (
+                "NFe/infNFe/det/imposto/COFINS/COFINSQtde",
+                "cofins/cofins_qtde.xml",
+            ),
+            ("NFe/infNFe/det/imposto/COFINSST", "cofins/cofins_st.xml"),
+            ("NFe/infNFe/det/impostoDevol", "devolution.xml"),
+            ("NFe/infNFe/total", "total.xml"),
+            ("NFe/infNFe/total/ISSQNtot", "issqn/issqn_total.xml"),
+            ("NFe/infNFe/total/retTrib", "withholding.xml"),
+            ("NFe/infNFe/transp", "transport.xml"),
+            ("NFe/infNFe/transp", "transport/transport.xml"),
+            ("NFe/infNFe/transp/transporta", "transport/transporter.xml"),
+            ("NFe/infNFe/transp/vol", "transport/volume.xml"),
+            ("NFe/infNFe/cobr", "billing/billing.xml"),
+            ("NFe/infNFe/pag", "payments.xml"),
+            ("NFe/infNFe/pag", "payment/payments.xml"),
+            ("NFe/infNFe/pag/detPag", "payment/payment_other.xml"),
+            ("NFe/infNFe/infIntermed", "intermediator.xml"),
+            ("envEvento/evento/infEvento", "events/cancel_info.xml"),
+            ("envEvento/evento/infEvento", "events/correction_info.xml"),
+            (
+                "envEvento/evento/infEvento",
+                "events/interested_actor_info.xml",
+            ),
+            (
+                "envEvento/evento/infEvento",
+                "events/not_performed_info.xml",
+            ),
+            ("inutNFe/infInut", "disablement/info.xml"),
+        ];
+
+        for (path, fixture) in fixtures {
+            let schema = match path.split('/').next() {
+                Some("envEvento") => Schema::Event,
+                Some("inutNFe") => Schema::Disablement,
+                _ => Schema::NFe,
+            };
+            let xml = std::fs::read_to_string(format!("tests/fixtures/{}", fixture)).unwrap();
+            assert_eq!(
+                check_fragment_order(schema, path, &xml),
+                Ok(()),
+                "Order of {}",
+                fixture
+            );
+        }
+    }
+
+    #[test]
+    fn test_fragment_order() {
+        let xml = "<prod><cProd>1</cProd><cEAN>SEM GTIN</cEAN><xProd>Item</xProd></prod>";
+        assert_eq!(
+            check_fragment_order(Schema::NFe, "NFe/infNFe/det/prod", xml),
+            Ok(())
+        );
+
+        let xml = "<prod><xProd>Item</xProd><cProd>1</cProd></prod>";
+        let error = check_fragment_order(Schema::NFe, "NFe/infNFe/det/prod", xml).unwrap_err();
+        assert_eq!(error.message, "Element `cProd` must come before `xProd`");
+
+        let error = check_fragment_order(Schema::NFe, "NFe/infNFe/produto", xml).unwrap_err();
+        assert_eq!(
+            error.message,
+            "Unknown path `NFe/infNFe/produto` in nfe_v4.00.xsd"
+        );
+    }
+
+    #[test]
+    fn test_nfe_order() {
+        let nfe = crate::models::tests::setup_nfe();
+        let xml = quick_xml::se::to_string(&nfe).expect("Failed to serialize");
+        assert_eq!(check_order(Schema::NFe, &xml), Ok(()));
+
+        let misplaced = xml.replacen("<det ", "<total/><det ", 1);
+        let error = check_order(Schema::NFe, &misplaced).unwrap_err();
+        assert_eq!(error.element, "det");
+        assert_eq!(error.message, "Element `det` must come before `total`");
+
+        let undeclared = xml.replace("<tpImp>", "<xMun>Belo Horizonte</xMun><tpImp>");
+        let error = check_order(Schema::NFe, &undeclared).unwrap_err();
+        assert_eq!(error.element, "xMun");
+        assert_eq!(error.message, "Unexpected element `xMun`");
+
+        let undeclared = xml.replace("</enderEmit>", "<IE>0623079040081</IE></enderEmit>");
+        let error = check_order(Schema::NFe, &undeclared).unwrap_err();
+        assert_eq!(error.element, "IE");
+        assert_eq!(error.message, "Unexpected element `IE`");
+    }
+}
@@ -0,0 +1,829 @@
+//! XML helpers of the documents
+//!
+//! Canonicalization, as signed by the documents, and the serialization glue of
+//! the layout: the namespace of the NF-e, the XML declaration and the
+//! indentation of the output.
+
+use crate::models::NFE_NAMESPACE;
+use quick_xml::{
+    NsReader, Reader, Writer,
+    escape::escape,
+    events::{BytesText, Event},
+    name::{Namespace, PrefixDeclaration, ResolveResult},
+};
+use serde::Serialize;
+use std::{collections::BTreeMap, error::Error};
+
+/// Declaration prepended to the documents by `XmlOptions::declaration`
+pub const XML_DECLARATION: &str = r#"<?xml version="1.0" encoding="UTF-8"?>"#;
+
+/// Error of the XML helpers
+#[derive(Debug, Clone, PartialEq)]
+pub enum XmlError {
+    Serialization(String),
+    Syntax(String),
+}
+
+fn syntax(error: impl ToString) -> XmlError {
+    XmlError::Syntax(error.to_string())
+}
+
+/// Layout of the output of `to_xml_with`
+///
+/// declaration: Prepends `XML_DECLARATION`
+/// indent: Number of spaces indenting each level - Optional, compact when missing
+/// namespace: Default namespace declared on the root, unless it declares one - Optional
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct XmlOptions {
+    pub declaration: bool,
+    pub indent: Option<usize>,
+    pub namespace: Option<String>,
+}
+
+impl XmlOptions {
+    /// Options of the documents of the NF-e: compact, with its namespace
+    pub fn nfe() -> Self {
+        XmlOptions {
+            namespace: Some(NFE_NAMESPACE.to_string()),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_declaration(mut self) -> Self {
+        self.declaration = true;
+        self
+    }
+
+    pub fn with_indent(mut self, indent: usize) -> Self {
+        self.indent = Some(indent);
+        self
+    }
+}
+
+/// Serializes `value` in the namespace of the NF-e
+pub fn to_nfe_xml<T: Serialize>(value: &T) -> Result<String, XmlError> {
+    to_xml_with(value, &XmlOptions::nfe())
+}
+
+/// Serializes `value` with the layout of `options`
+pub fn to_xml_with<T: Serialize>(value: &T, options: &XmlOptions) -> Result<String, XmlError> {
+    let mut xml =
+        quick_xml::se::to_string(value).map_err(|e| XmlError::Serialization(e.to_string()))?;
+    if let Some(namespace) = &options.namespace {
+        xml = with_namespace(&xml, namespace);
+    }
+    if let Some(indent) = options.indent {
+        xml = pretty_print(&xml, indent)?;
+    }
+    if options.declaration {
+        xml = with_declaration(&xml);
+    }
+    Ok(xml)
+}
+
+/// Declares the default `namespace` on the root of the input, unless it
+/// already declares one
+pub fn with_namespace(input: &str, namespace: &str) -> String {
+    let root = input
+        .match_indices('<')
+        .map(|(i, _)| i)
+        .find(|&i| input[i + 1..].starts_with(|c: char| c.is_alphabetic() || c == '_'));
+    let Some(start) = root else {
+        return input.to_string();
+    };
+    let tag_end = input[start..].find('>').map_or(input.len(), |i| start + i);
+    if input[start..tag_end].contains(" xmlns=") {
+        return input.to_string();
+    }
+    let name_end = input[start..tag_end]
+        .find(|c: char| c.is_whitespace() || c == '/')
+        .map_or(tag_end, |i| start + i);
+    format!(
+        r#"{} xmlns="{}"{}"#,
+        &input[..name_end],
+        namespace,
+        &input[name_end..]
+    )
+}
+
+/// Prepends `XML_DECLARATION` to the input, unless it has a declaration
+pub fn with_declaration(input: &str) -> String {
+    match input.trim_start().starts_with("<?xml") {
+        true => input.to_string(),
+        false => format!("{}{}", XML_DECLARATION, input),
+    }
+}
+
+/// Removes the declaration of the input, as required inside of the SOAP
+/// messages
+pub fn without_declaration(input: &str) -> &str {
+    let trimmed = input.trim_start();
+    match trimmed.starts_with("<?xml") {
+        true => trimmed
+            .find("?>")
+            .map_or(trimmed, |end| trimmed[end + 2..].trim_start()),
+        false => input,
+    }
+}
+
+/// Removes the whitespace between the elements, as the indentation
+pub fn remove_whitespaces_from_xml(input: &str) -> Result<String, XmlError> {
+    rewrite(input, Writer::new(Vec::new()))
+}
+
+/// Indents the elements of the input with `indent` spaces each level
+pub fn pretty_print(input: &str, indent: usize) -> Result<String, XmlError> {
+    rewrite(input, Writer::new_with_indent(Vec::new(), b' ', indent))
+}
+
+/// Writes the events of the input without the whitespace between elements
+///
+/// The text and the entity references between two tags are written as a single
+/// text, so the whitespace next to a reference is kept.
+fn rewrite(input: &str, mut writer: Writer<Vec<u8>>) -> Result<String, XmlError> {
+    let mut reader = Reader::from_str(input);
+    let mut text = String::new();
+
+    loop {
+        let event = reader.read_event().map_err(syntax)?;
+        match event {
+            Event::Text(e) => text.push_str(std::str::from_utf8(&e).map_err(syntax)?),
+            Event::GeneralRef(e) => {
+                text.push('&');
+                text.push_str(std::str::from_utf8(&e).map_err(syntax)?);
+                text.push(';');
+            }
+            event => {
+                if !text.trim().is_empty() {
+                    let escaped = BytesText::from_escaped(std::mem::take(&mut text));
+                    writer.write_event(Event::Text(escaped)).map_err(syntax)?;
+                }
+                text.clear();
+                match event {
+                    Event::Eof => break,
+                    event => writer.write_event(event).map_err(syntax)?,
+                }
+            }
+        }
+    }
+
+    String::from_utf8(writer.into_inner()).map_err(syntax)
+}
+
+pub(crate) fn escape_text(input: &str, output: &mut String) {
+    for c in input.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '\r' => output.push_str("&#xD;"),
+            _ => output.push(c),
+        }
+    }
+}
+
+pub(crate) fn escape_attribute(input: &str, output: &mut String) {
+    for c in input.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '"' => output.push_str("&quot;"),
+            '\t' => output.push_str("&#x9;"),
+            '\n' => output.push_str("&#xA;"),
+            '\r' => output.push_str("&#xD;"),
+            _ => output.push(c),
+        }
+    }
+}
+
+pub(crate) fn resolve_reference(name: &str) -> Option<char> {
+    match name {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        _ => {
+            let code = name.strip_prefix("#x").map_or_else(
+                || name.strip_prefix('#').and_then(|n| n.parse().ok()),
+                |hex| u32::from_str_radix(hex, 16).ok(),
+            )?;
+            char::from_u32(code)
+        }
+    }
+}
+
+/// Canonicalization algorithm of `canonicalize_xml_with`
+///
+/// Both drop the comments. They differ on the namespace declarations: the
+/// inclusive one renders every namespace in scope where it changes, while the
+/// exclusive one only renders the namespaces visibly used by the element or
+/// its attributes, besides the prefixes of `inclusive_prefixes` ("#default"
+/// standing for the default namespace), which follow the inclusive rules.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Canonicalization {
+    /// Canonical XML 1.0
+    #[default]
+    Inclusive,
+    /// Exclusive XML Canonicalization 1.0 with the InclusiveNamespaces PrefixList
+    Exclusive { inclusive_prefixes: Vec<String> },
+}
+
+impl Canonicalization {
+    /// URI of the algorithm, as referenced by the signatures
+    pub fn algorithm(&self) -> &'static str {
+        match self {
+            Canonicalization::Inclusive => "http://www.w3.org/TR/2001/REC-xml-c14n-20010315",
+            Canonicalization::Exclusive { .. } => "http://www.w3.org/2001/10/xml-exc-c14n#",
+        }
+    }
+
+    /// Whether the namespace `prefix` is rendered where it is in scope, even
+    /// when the element does not use it
+    fn renders_unused(&self, prefix: &str) -> bool {
+        match self {
+            Canonicalization::Inclusive => true,
+            Canonicalization::Exclusive { inclusive_prefixes } => {
+                inclusive_prefixes.iter().any(|inclusive| {
+                    inclusive == prefix || (inclusive == "#default" && prefix.is_empty())
+                })
+            }
+        }
+    }
+}
+
+/// Canonical XML 1.0 (inclusive, without comments) of the input
+pub fn canonicalize_xml(input: &str) -> Result<String, Box<dyn Error>> {
+    canonicalize_xml_with(input, &Canonicalization::Inclusive)
+}
+
+/// Canonical form of the input with the `method` algorithm
+///
+/// Text is kept as it is, whitespace included. Empty elements are expanded,
+/// namespace declarations are rendered before the attributes and only where
+/// they change the namespace rendered by the ancestors, and attributes are
+/// sorted by namespace URI and local name.
+pub fn canonicalize_xml_with(
+    input: &str,
+    method: &Canonicalization,
+) -> Result<String, Box<dyn Error>> {
+    canonicalize(input, method, false)
+}
+
+/// Canonical form of the input without its indentation, the text made only of
+/// whitespace, to compare documents whatever their formatting
+///
+/// Not the form signed, which keeps every text, see `canonicalize_xml`.
+pub fn normalize_xml(input: &str) -> Result<String, Box<dyn Error>> {
+    canonicalize(input, &Canonicalization::Inclusive, true)
+}
+
+fn canonicalize(
+    input: &str,
+    method: &Canonicalization,
+    skip_indentation: bool,
+) -> Result<String, Box<dyn Error>> {
+    let mut reader = Reader::from_str(input);
+    reader.config_mut().expand_empty_elements = true;
+
+    let mut output = String::new();
+    let mut text = String::new();
+    // Namespaces in scope and namespaces rendered by the output ancestors
+    let mut scopes: Vec<BTreeMap<String, String>> = vec![BTreeMap::new()];
+    let mut rendered: Vec<BTreeMap<String, String>> = vec![BTreeMap::new()];
+
+    loop {
+        let event = reader.read_event()?;
+        if !matches!(
+            event,
+            Event::Text(_) | Event::CData(_) | Event::GeneralRef(_)
+        ) {
+            if !(skip_indentation && text.trim().is_empty()) {
+                escape_text(&text, &mut output);
+            }
+            text.clear();
+        }
+
+        match event {
+            Event::Start(e) => {
+                let mut scope = scopes.last().cloned().unwrap_or_default();
+                let mut visible = rendered.last().cloned().unwrap_or_default();
+                let mut attributes = Vec::new();
+
+                for attribute in e.attributes() {
+                    let attribute = attribute?;
+                    let key = String::from_utf8(attribute.key.as_ref().to_vec())?;
+                    let value = attribute.unescape_value()?.into_owned();
+                    let prefix = if key == "xmlns" {
+                        Some(String::new())
+                    } else {
+                        key.strip_prefix("xmlns:").map(str::to_string)
+                    };
+
+                    match prefix {
+                        Some(prefix) => {
+                            scope.insert(prefix, value);
+                        }
+                        None => attributes.push((key, value)),
+                    }
+                }
+
+                let name = std::str::from_utf8(e.name().as_ref())?.to_string();
+                let mut used = vec![
+                    name.split_once(':')
+                        .map_or(String::new(), |(prefix, _)| prefix.to_string()),
+                ];
+                used.extend(
+                    attributes
+                        .iter()
+                        .filter_map(|(key, _)| key.split_once(':'))
+                        .map(|(prefix, _)| prefix.to_string()),
+                );
+
+                let mut namespaces = Vec::new();
+                for (prefix, value) in &scope {
+                    if prefix == "xml" || !(used.contains(prefix) || method.renders_unused(prefix))
+                    {
+                        continue;
+                    }
+                    let inherited = visible.get(prefix).map_or("", String::as_str);
+                    if inherited != value {
+                        let key = if prefix.is_empty() {
+                            "xmlns".to_string()
+                        } else {
+                            format!("xmlns:{}", prefix)
+                        };
+                        namespaces.push((key, value.clone()));
+                        visible.insert(prefix.clone(), value.clone());
+                    }
+                }
+
+                let mut attributes = attributes
+                    .into_iter()
+                    .map(|(key, value)| {
+                        let (uri, local) = match key.split_once(':') {
+                            Some((prefix, local)) => (
+                                scope.get(prefix).cloned().unwrap_or_default(),
+                                local.to_string(),
+                            ),
+                            None => (String::new(), key.clone()),
+                        };
+                        (uri, local, key, value)
+                    })
+                    .collect::<Vec<_>>();
+                attributes.sort_by(|a, b| (&a.0, &a.1).cmp(&(&b.0, &b.1)));
+
+                output.push('<');
+                output.push_str(&name);
+                for (key, value) in namespaces {
+                    output.push_str(&format!(" {}=\"", key));
+                    escape_attribute(&value, &mut output);
+                    output.push('"');
+                }
+                for (_, _, key, value) in attributes {
+                    output.push_str(&format!(" {}=\"", key));
+                    escape_attribute(&value, &mut output);
+                    output.push('"');
+                }
+                output.push('>');
+                scopes.push(scope);
+                rendered.push(visible);
+            }
+            Event::End(e) => {
+                scopes.pop();
+                rendered.pop();
+                output.push_str("</");
+                output.push_str(std::str::from_utf8(e.name().as_ref())?);
+                output.push('>');
+            }
+            Event::Text(e) => text.push_str(&e.decode()?),
+            Event::CData(e) => text.push_str(&e.decode()?),
+            Event::GeneralRef(e) => {
+                let name = e.decode()?;
+                let resolved = resolve_reference(&name)
+                    .ok_or_else(|| format!("Unknown entity reference: &{};", name))?;
+                text.push(resolved);
+            }
+            Event::PI(e) => {
+                output.push_str("<?");
+                output.push_str(std::str::from_utf8(&e)?);
+                output.push_str("?>");
+            }
+            Event::Eof => break,
+            Event::Decl(_) | Event::DocType(_) | Event::Comment(_) | Event::Empty(_) => {}
+        }
+    }
+
+    Ok(output)
+}
+
+/// Input indented by 4 spaces per level, the form of the fixtures, with the
+/// elements without content self-closed and the indentation dropped
+pub fn indent_xml(input: &str) -> Result<String, Box<dyn Error>> {
+    let mut reader = Reader::from_str(input);
+    let mut events = Vec::new();
+    // Entity references split the text nodes, join them back so the writer
+    // keeps the whole content on the element line
+    let mut text = String::new();
+    loop {
+        let event = reader.read_event()?;
+        match &event {
+            Event::Text(content) => text.push_str(std::str::from_utf8(content)?),
+            Event::GeneralRef(name) => {
+                text.push('&');
+                text.push_str(std::str::from_utf8(name)?);
+                text.push(';');
+            }
+            _ => {
+                if !text.trim().is_empty() {
+                    events.push(Event::Text(BytesText::from_escaped(text.clone())));
+                }
+                text.clear();
+                match event {
+                    Event::Eof => break,
+                    event => events.push(event),
+                }
+            }
+        }
+    }
+
+    let mut writer = Writer::new_with_indent(Vec::new(), b' ', 4);
+    let mut events = events.into_iter().peekable();
+    while let Some(event) = events.next() {
+        match event {
+            Event::Start(start) if matches!(events.peek(), Some(Event::End(_))) => {
+                events.next();
+                writer.write_event(Event::Empty(start))?;
+            }
+            event => writer.write_event(event)?,
+        }
+    }
+    Ok(String::from_utf8(writer.into_inner())?)
+}
+
+/// Serializes `value` as the `root` element declaring the default `namespace`
+pub fn to_namespaced_xml<T: Serialize>(
+    root: &str,
+    namespace: &str,
+    value: &T,
+) -> Result<String, quick_xml::SeError> {
+    #[derive(Serialize)]
+    struct Namespaced<'a, T> {
+        #[serde(rename = "@xmlns")]
+        xmlns: &'a str,
+        #[serde(flatten)]
+        value: &'a T,
+    }
+
+    quick_xml::se::to_string_with_root(
+        root,
+        &Namespaced {
+            xmlns: namespace,
+            value,
+        },
+    )
+}
+
+/// Element taken from a document by `find_element`
+///
+/// xml: Text of the element as received, declaring the namespaces it inherits
+/// id: Value of its Id attribute - Optional
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScopedElement {
+    pub xml: String,
+    pub id: Option<String>,
+}
+
+/// First element `local_name` of the `namespace` in the input, whatever its
+/// prefix
+///
+/// The namespaces in scope declared by its ancestors are added to its start
+/// tag, so the element alone has the canonical form it has in the document,
+/// as signed by the references to it.
+pub fn find_element(
+    input: &str,
+    namespace: &str,
+    local_name: &str,
+) -> Result<Option<ScopedElement>, XmlError> {
+    let mut reader = NsReader::from_str(input);
+    loop {
+        let start = reader.buffer_position() as usize;
+        let (resolved, event) = reader.read_resolved_event().map_err(syntax)?;
+        let found =
+            matches!(resolved, ResolveResult::Bound(Namespace(uri)) if uri == namespace.as_bytes());
+        let (element, empty) = match event {
+            Event::Start(element) => (element, false),
+            Event::Empty(element) => (element, true),
+            Event::Eof => return Ok(None),
+            _ => continue,
+        };
+        if !found || element.local_name().as_ref() != local_name.as_bytes() {
+            continue;
+        }
+
+        let mut declared = Vec::new();
+        let mut id = None;
+        for attribute in element.attributes() {
+            let attribute = attribute.map_err(syntax)?;
+            match attribute.key.as_namespace_binding() {
+                Some(prefix) => declared.push(prefix),
+                None if attribute.key.as_ref() == b"Id" => {
+                    id = Some(attribute.unescape_value().map_err(syntax)?.into_owned());
+                }
+                None => {}
+            }
+        }
+        let mut inherited = String::new();
+        for (prefix, uri) in reader.prefixes() {
+            if declared.contains(&prefix) || uri.as_ref().is_empty() {
+                continue;
+            }
+            let uri = std::str::from_utf8(uri.as_ref()).map_err(syntax)?;
+            match prefix {
+                PrefixDeclaration::Default => inherited.push_str(" xmlns=\""),
+                PrefixDeclaration::Named(prefix) => {
+                    let prefix = std::str::from_utf8(prefix).map_err(syntax)?;
+                    inherited.push_str(&format!(" xmlns:{}=\"", prefix));
+                }
+            }
+            inherited.push_str(&escape(uri));
+            inherited.push('"');
+        }
+
+        if !empty {
+            reader.read_to_end(element.name()).map_err(syntax)?;
+        }
+        let text = &input[start..reader.buffer_position() as usize];
+        let name_end = 1 + element.name().as_ref().len();
+        return Ok(Some(ScopedElement {
+            xml: format!("{}{}{}", &text[..name_end], inherited, &text[name_end..]),
+            id,
+        }));
+    }
+}
+
+/// Pads the input on the left with `pad_char` up to `total_length` characters
+pub fn left_pad(input: &str, total_length: usize, pad_char: char) -> String {
+    if input.len() >= total_length {
+        input.to_string()
+    } else {
+        let padding = pad_char.to_string().repeat(total_length - input.len());
+        format!("{}{}", padding, input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonicalize_str() {
+        let input = r#"<root><child attribute="value">Text</child></root>"#;
+        let expected = r#"<root><child attribute="value">Text</child></root>"#;
+
+        match canonicalize_xml(input) {
+            Ok(output) => assert_eq!(output, expected),
+            Err(e) => panic!("Error during canonicalization: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_canonicalize_namespaces_and_attributes() {
+        let input = r#"<?xml version="1.0"?>
+            <root b="2" a="1" xmlns="urn:test">
+                <child xmlns="urn:test" attr="x &amp; y"/>
+                <text>1 &lt; 2</text>
+            </root>"#;
+        let expected = concat!(
+            r#"<root xmlns="urn:test" a="1" b="2">"#,
+            r#"<child attr="x &amp; y"></child>"#,
+            r#"<text>1 &lt; 2</text>"#,
+            r#"</root>"#
+        );
+
+        assert_eq!(normalize_xml(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_indent() {
+        let input = r#"<root a="1"><child>Text</child><empty></empty></root>"#;
+        let expected = concat!(
+            "<root a=\"1\">\n",
+            "    <child>Text</child>\n",
+            "    <empty/>\n",
+            "</root>"
+        );
+        assert_eq!(indent_xml(input).unwrap(), expected);
+        assert_eq!(indent_xml(expected).unwrap(), expected);
+        assert_eq!(
+            indent_xml("<root><text>1 &lt; 2 &amp; </text></root>").unwrap(),
+            "<root>\n    <text>1 &lt; 2 &amp; </text>\n</root>"
+        );
+        assert_eq!(
+            normalize_xml(expected).unwrap(),
+            normalize_xml(input).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_canonicalize_keeps_text() {
+        let input = "<root>\n    <text> 1 &lt; 2 </text>\n</root>";
+        assert_eq!(canonicalize_xml(input).unwrap(), input);
+        assert_eq!(
+            normalize_xml(input).unwrap(),
+            "<root><text> 1 &lt; 2 </text></root>"
+        );
+    }
+
+    #[test]
+    fn test_exclusive_canonicalization() {
+        let input = concat!(
+            r#"<root xmlns="urn:root" xmlns:a="urn:a" xmlns:b="urn:b">"#,
+            r#"<child a:attr="1"><a:inner>Text</a:inner></child>"#,
+            r#"</root>"#
+        );
+
+        assert_eq!(
+            canonicalize_xml_with(input, &Canonicalization::Inclusive).unwrap(),
+            concat!(
+                r#"<root xmlns="urn:root" xmlns:a="urn:a" xmlns:b="urn:b">"#,
+                r#"<child a:attr="1"><a:inner>Text</a:inner></child>"#,
+                r#"</root>"#
+            )
+        );
+        let exclusive = Canonicalization::Exclusive {
+            inclusive_prefixes: Vec::new(),
+        };
+        assert_eq!(
+            canonicalize_xml_with(input, &exclusive).unwrap(),
+            concat!(
+                r#"<root xmlns="urn:root">"#,
+                r#"<child xmlns:a="urn:a" a:attr="1"><a:inner>Text</a:inner></child>"#,
+                r#"</root>"#
+            )
+        );
+        let exclusive = Canonicalization::Exclusive {
+            inclusive_prefixes: vec!["b".to_string()],
+        };
+        assert_eq!(
+            canonicalize_xml_with(input, &exclusive).unwrap(),
+            concat!(
+                r#"<root xmlns="urn:root" xmlns:b="urn:b">"#,
+                r#"<child xmlns:a="urn:a" a:attr="1"><a:inner>Text</a:inner></child>"#,
+                r#"</root>"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_exclusive_subset() {
+        // Element signed out of its document, inheriting the default namespace
+        let input = r#"<infNFe xmlns="urn:nfe" xmlns:x="urn:x" Id="NFe1"><ide>1</ide></infNFe>"#;
+        let exclusive = Canonicalization::Exclusive {
+            inclusive_prefixes: Vec::new(),
+        };
+        assert_eq!(
+            canonicalize_xml_with(input, &exclusive).unwrap(),
+            r#"<infNFe xmlns="urn:nfe" Id="NFe1"><ide>1</ide></infNFe>"#
+        );
+        assert_eq!(
+            Canonicalization::default().algorithm(),
+            "http://www.w3.org/TR/2001/REC-xml-c14n-20010315"
+        );
+    }
+
+    #[test]
+    fn test_to_namespaced_xml() {
+        #[derive(Serialize)]
+        struct Child {
+            #[serde(rename = "@Id")]
+            id: String,
+            value: String,
+        }
+
+        let child = Child {
+            id: "ID1".to_string(),
+            value: "Text".to_string(),
+        };
+        let expected = r#"<root xmlns="urn:test" Id="ID1"><value>Text</value></root>"#;
+        assert_eq!(
+            to_namespaced_xml("root", "urn:test", &child).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_left_pad() {
+        let input = "123";
+        let padded = left_pad(input, 5, '0');
+        assert_eq!(padded, "00123");
+
+        let input2 = "12345";
+        let padded2 = left_pad(input2, 5, '0');
+        assert_eq!(padded2, "12345");
+
+        let input3 = "123456";
+        let padded3 = left_pad(input3, 5, '0');
+        assert_eq!(padded3, "123456");
+    }
+
+    #[test]
+    fn test_to_nfe_xml() {
+        #[derive(Serialize)]
+        #[serde(rename = "consStatServ")]
+        struct Status {
+            #[serde(rename = "@versao")]
+            version: String,
+            #[serde(rename = "tpAmb")]
+            environment: u8,
+        }
+
+        let status = Status {
+            version: "4.00".to_string(),
+            environment: 2,
+        };
+        assert_eq!(
+            to_nfe_xml(&status).unwrap(),
+            concat!(
+                r#"<consStatServ xmlns="http://www.portalfiscal.inf.br/nfe" versao="4.00">"#,
+                r#"<tpAmb>2</tpAmb></consStatServ>"#
+            )
+        );
+
+        let options = XmlOptions::nfe().with_declaration().with_indent(2);
+        assert_eq!(
+            to_xml_with(&status, &options).unwrap(),
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+                r#"<consStatServ xmlns="http://www.portalfiscal.inf.br/nfe" versao="4.00">"#,
+                "\n  <tpAmb>2</tpAmb>\n</consStatServ>"
+            )
+        );
+    }
+
+    #[test]
+    fn test_namespace_declared_once() {
+        let input = r#"<NFe xmlns="urn:nfe"><infNFe/></NFe>"#;
+        assert_eq!(with_namespace(input, NFE_NAMESPACE), input);
+        assert_eq!(
+            with_namespace(r#"<?xml version="1.0"?><a/>"#, "urn:a"),
+            r#"<?xml version="1.0"?><a xmlns="urn:a"/>"#
+        );
+    }
+
+    #[test]
+    fn test_declaration() {
+        let declared = with_declaration("<a/>");
+        assert_eq!(declared, format!("{}<a/>", XML_DECLARATION));
+        assert_eq!(with_declaration(&declared), declared);
+        assert_eq!(without_declaration(&declared), "<a/>");
+        assert_eq!(without_declaration("<a/>"), "<a/>");
+    }
+
+    #[test]
+    fn test_whitespaces() {
+        let input = "<a>\n  <b>x &amp; y</b>\n  <c>&lt; &gt;</c>\n</a>";
+        let compact = remove_whitespaces_from_xml(input).unwrap();
+        assert_eq!(compact, "<a><b>x &amp; y</b><c>&lt; &gt;</c></a>");
+        assert_eq!(
+            pretty_print(&compact, 2).unwrap(),
+            "<a>\n  <b>x &amp; y</b>\n  <c>&lt; &gt;</c>\n</a>"
+        );
+        assert!(pretty_print("<a></b>", 2).is_err());
+    }
+
+    #[test]
+    fn test_find_element() {
+        let input = concat!(
+            r#"<nfe:NFe xmlns:nfe="http://www.portalfiscal.inf.br/nfe" xmlns:x="urn:x">"#,
+            r#"<nfe:infNFe Id="NFe1" xmlns:x="urn:y"><nfe:ide>1</nfe:ide></nfe:infNFe>"#,
+            r#"<Signature xmlns="http://www.w3.org/2000/09/xmldsig#"><SignedInfo/></Signature>"#,
+            "</nfe:NFe>"
+        );
+        assert_eq!(
+            find_element(input, NFE_NAMESPACE, "infNFe").unwrap(),
+            Some(ScopedElement {
+                xml: concat!(
+                    r#"<nfe:infNFe xmlns:nfe="http://www.portalfiscal.inf.br/nfe" Id="NFe1" "#,
+                    r#"xmlns:x="urn:y"><nfe:ide>1</nfe:ide></nfe:infNFe>"#
+                )
+                .to_string(),
+                id: Some("NFe1".to_string()),
+            })
+        );
+        let signed_info = find_element(input, "http://www.w3.org/2000/09/xmldsig#", "SignedInfo")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            signed_info.xml,
+            concat!(
+                r#"<SignedInfo xmlns:nfe="http://www.portalfiscal.inf.br/nfe" xmlns:x="urn:x" "#,
+                r#"xmlns="http://www.w3.org/2000/09/xmldsig#"/>"#
+            )
+        );
+        assert_eq!(signed_info.id, None);
+        // Same local name, other namespace
+        assert_eq!(find_element(input, "urn:x", "infNFe").unwrap(), None);
+        assert!(find_element("<a><b></a>", "urn:x", "c").is_err());
+    }
+}
@@ -0,0 +1,34 @@
+use nf_e::enums::{CNPJ, IE};
+use nf_e_macros::serialization_test;
+
+mod helpers {
+    /// Canonical form of the simple elements of these tests
+    pub fn trim(xml: &str) -> Result<String, std::convert::Infallible> {
+        Ok(xml.trim().to_string())
+    }
+}
+
+#[serialization_test(
+    expected = "<CNPJ>12345678000195</CNPJ>",
+    ser = "quick_xml::se::to_string",
+    de = "quick_xml::de::from_str",
+    canon = "helpers::trim",
+    roundtrip
+)]
+fn setup_cnpj() -> CNPJ {
+    CNPJ("12345678000195".to_string())
+}
+
+#[serialization_test(
+    case(name = "digits", expected = "<IE>123456789</IE>"),
+    case(name = "exempt", expected = " <IE>ISENTO</IE>\n"),
+    ser = "quick_xml::se::to_string",
+    de = "quick_xml::de::from_str",
+    canon = "helpers::trim"
+)]
+fn setup_ie(case: &str) -> IE {
+    match case {
+        "digits" => IE("123456789".to_string()),
+        _ => IE("ISENTO".to_string()),
+    }
+}